@@ -23,6 +23,8 @@ def rust_spec_test(name, spec_file, deps = [], **kwargs):
 
     This macro creates a rust_test target that passes the spec file path
     via an environment variable, following the pattern from java_spec_test.
+    The test has its own libtest-mimic harness, which runs each spec case
+    as a separate test and supports `shard_count`.
 
     Args:
         name: Name of the test target
@@ -37,10 +39,12 @@ def rust_spec_test(name, spec_file, deps = [], **kwargs):
     rust_test(
         name = name,
         srcs = ["tests/spec_test.rs"],
+        use_libtest_harness = False,
         deps = deps + [
+            "@crates//:libtest-mimic",
             "@crates//:serde",
             "@crates//:serde_json",
-            "@crates//:serde_yaml",
+            "@crates//:serde_yaml_ng",
         ],
         data = [spec_file],
         env = {
@@ -0,0 +1,63 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fuzzes splitting rendered output into messages.
+//!
+//! Inputs are seeded with the markers helpers emit so the fuzzer reaches
+//! role, history, media, and section handling quickly.
+
+#![no_main]
+
+use dotprompt::parse::to_messages;
+use dotprompt::{DataArgument, Message, Part, Role, TextPart};
+use libfuzzer_sys::fuzz_target;
+
+/// Marker fragments substituted for control bytes in the input.
+const MARKERS: [&str; 6] = [
+    "<<<dotprompt:role:user>>>",
+    "<<<dotprompt:role:model>>>",
+    "<<<dotprompt:history>>>",
+    "<<<dotprompt:media:url ",
+    "<<<dotprompt:section ",
+    ">>>",
+];
+
+fuzz_target!(|source: &str| {
+    let rendered: String = source
+        .chars()
+        .map(|c| {
+            MARKERS
+                .get(c as usize)
+                .map_or_else(|| c.to_string(), |marker| (*marker).to_string())
+        })
+        .collect();
+
+    let history = vec![Message {
+        role: Role::User,
+        content: vec![Part::Text(TextPart {
+            text: "earlier".to_string(),
+            metadata: None,
+        })],
+        metadata: None,
+    }];
+    let data: DataArgument = DataArgument {
+        messages: Some(history),
+        ..Default::default()
+    };
+
+    let _ = to_messages::<serde_json::Value>(&rendered, None);
+    let _ = to_messages(&rendered, Some(&data));
+});
@@ -0,0 +1,670 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversion of rendered prompts into model provider request bodies.
+//!
+//! Each adapter maps the rendered messages, model, config, tools, and output
+//! schema onto the request format of one API:
+//!
+//! - [`to_openai_request`] - `OpenAI` Chat Completions
+//! - [`to_anthropic_request`] - Anthropic Messages
+//! - [`to_gemini_request`] - Gemini `generateContent`
+//! - [`to_genkit_request`] - Genkit `GenerateRequest`
+//!
+//! ```
+//! use dotprompt::adapters::to_openai_request;
+//! use dotprompt::{DataArgument, Dotprompt, RenderedPrompt};
+//!
+//! let data = DataArgument::<serde_json::Value>::default();
+//! let rendered: RenderedPrompt = Dotprompt::new(None)
+//!     .render("---\nmodel: openai/gpt-4o\n---\nHello!", &data, None)
+//!     .unwrap();
+//! let request = to_openai_request(&rendered);
+//! assert_eq!(request["model"], "gpt-4o");
+//! assert_eq!(request["messages"][0]["content"], "Hello!");
+//! ```
+//!
+//! Model names drop their provider prefix (`openai/gpt-4o` becomes
+//! `gpt-4o`). Config keys with a standard Genkit name (`temperature`,
+//! `maxOutputTokens`, `topP`, `topK`, `stopSequences`) are renamed for the
+//! target API; other keys are passed through unchanged. Pending parts are
+//! placeholders and are left out of every request except Genkit's.
+//!
+//! Request metadata passed in [`DataArgument::request_metadata`] is sent
+//! where the API accepts it: as `OpenAI`'s `metadata`, with values as
+//! strings, and as Anthropic's `metadata.user_id` when it has a `user_id`.
+//! Gemini and Genkit requests have no such field, so it is left out.
+//!
+//! [`DataArgument::request_metadata`]: crate::types::DataArgument::request_metadata
+
+use serde_json::{Map, Value, json};
+
+use crate::types::{Message, Part, PromptOutputConfig, RenderedPrompt, Role};
+
+/// Converts a rendered prompt into an `OpenAI` Chat Completions request.
+#[must_use]
+pub fn to_openai_request(rendered: &RenderedPrompt) -> Value {
+    let mut request = Map::new();
+    if let Some(model) = model_name(rendered) {
+        request.insert("model".to_string(), model.into());
+    }
+
+    let mut messages = Vec::new();
+    for message in &rendered.messages {
+        messages.extend(openai_messages(message));
+    }
+    request.insert("messages".to_string(), messages.into());
+
+    let tools: Vec<Value> = tool_declarations(rendered)
+        .map(|(name, description, parameters)| {
+            json!({
+                "type": "function",
+                "function": function_declaration(name, description, parameters),
+            })
+        })
+        .collect();
+    if !tools.is_empty() {
+        request.insert("tools".to_string(), tools.into());
+    }
+
+    if let Some(output) = json_output(rendered) {
+        let format = output.schema.as_ref().map_or_else(
+            || json!({ "type": "json_object" }),
+            |schema| json!({ "type": "json_schema", "json_schema": { "name": "output", "schema": schema } }),
+        );
+        request.insert("response_format".to_string(), format);
+    }
+
+    merge_config(
+        &mut request,
+        rendered,
+        &[
+            ("maxOutputTokens", "max_tokens"),
+            ("topP", "top_p"),
+            ("stopSequences", "stop"),
+        ],
+    );
+
+    if let Some(metadata) = rendered.request_metadata() {
+        let metadata: Map<String, Value> = metadata
+            .iter()
+            .map(|(key, value)| {
+                let value = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), str::to_string);
+                (key.clone(), value.into())
+            })
+            .collect();
+        request.insert("metadata".to_string(), metadata.into());
+    }
+    Value::Object(request)
+}
+
+/// Converts a message into `OpenAI` messages. Tool responses become one
+/// `tool` message each.
+fn openai_messages(message: &Message) -> Vec<Value> {
+    if message.role == Role::Tool {
+        return message
+            .content
+            .iter()
+            .filter_map(|part| match part {
+                Part::ToolResponse(response) => {
+                    let response = &response.tool_response;
+                    Some(json!({
+                        "role": "tool",
+                        "tool_call_id": response.ref_.as_deref().unwrap_or(&response.name),
+                        "content": json_text(response.output.as_ref()),
+                    }))
+                }
+                _ => None,
+            })
+            .collect();
+    }
+
+    let role = match message.role {
+        Role::System => "system",
+        Role::Model => "assistant",
+        Role::User | Role::Tool => "user",
+    };
+    let mut content = Vec::new();
+    let mut tool_calls = Vec::new();
+    for part in &message.content {
+        match part {
+            Part::Text(text) => content.push(json!({ "type": "text", "text": text.text })),
+            Part::Data(data) => {
+                content.push(json!({ "type": "text", "text": json_text(Some(&json!(data.data))) }));
+            }
+            Part::Media(media) => {
+                content
+                    .push(json!({ "type": "image_url", "image_url": { "url": media.media.url } }));
+            }
+            Part::ToolRequest(request) => {
+                let request = &request.tool_request;
+                tool_calls.push(json!({
+                    "id": request.ref_.as_deref().unwrap_or(&request.name),
+                    "type": "function",
+                    "function": {
+                        "name": request.name,
+                        "arguments": json_text(request.input.as_ref()),
+                    },
+                }));
+            }
+            Part::ToolResponse(_) | Part::Pending(_) => {}
+        }
+    }
+
+    let mut result = Map::new();
+    result.insert("role".to_string(), role.into());
+    // Plain text messages use the string form, which every compatible API
+    // accepts.
+    let content = if content.iter().all(|part| part["type"] == "text") {
+        let texts: Vec<&str> = content
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect();
+        Value::String(texts.concat())
+    } else {
+        Value::Array(content)
+    };
+    result.insert("content".to_string(), content);
+    if !tool_calls.is_empty() {
+        result.insert("tool_calls".to_string(), tool_calls.into());
+    }
+    vec![Value::Object(result)]
+}
+
+/// Converts a rendered prompt into an Anthropic Messages request.
+///
+/// System messages become the top-level `system` prompt, and `max_tokens`,
+/// which the API requires, defaults to 1024.
+#[must_use]
+pub fn to_anthropic_request(rendered: &RenderedPrompt) -> Value {
+    let mut request = Map::new();
+    if let Some(model) = model_name(rendered) {
+        request.insert("model".to_string(), model.into());
+    }
+
+    let (system, rest) = rendered.split_system();
+    if let Some(system) = system {
+        request.insert("system".to_string(), system.into());
+    }
+
+    let messages: Vec<Value> = rest
+        .into_iter()
+        .map(|message| {
+            let role = if message.role == Role::Model {
+                "assistant"
+            } else {
+                "user"
+            };
+            let content: Vec<Value> = message.content.iter().filter_map(anthropic_block).collect();
+            json!({ "role": role, "content": content })
+        })
+        .collect();
+    request.insert("messages".to_string(), messages.into());
+
+    let tools: Vec<Value> = tool_declarations(rendered)
+        .map(|(name, description, parameters)| {
+            let mut tool = Map::new();
+            tool.insert("name".to_string(), name.into());
+            if let Some(description) = description {
+                tool.insert("description".to_string(), description.into());
+            }
+            tool.insert("input_schema".to_string(), parameters);
+            Value::Object(tool)
+        })
+        .collect();
+    if !tools.is_empty() {
+        request.insert("tools".to_string(), tools.into());
+    }
+
+    request.insert("max_tokens".to_string(), 1024.into());
+    merge_config(
+        &mut request,
+        rendered,
+        &[
+            ("maxOutputTokens", "max_tokens"),
+            ("topP", "top_p"),
+            ("topK", "top_k"),
+            ("stopSequences", "stop_sequences"),
+        ],
+    );
+
+    // Anthropic only accepts an end-user id as request metadata.
+    if let Some(user_id) = rendered
+        .request_metadata()
+        .and_then(|metadata| metadata.get("user_id"))
+    {
+        request.insert("metadata".to_string(), json!({ "user_id": user_id }));
+    }
+    Value::Object(request)
+}
+
+/// Converts a part into an Anthropic content block.
+fn anthropic_block(part: &Part) -> Option<Value> {
+    Some(match part {
+        Part::Text(text) => json!({ "type": "text", "text": text.text }),
+        Part::Data(data) => json!({ "type": "text", "text": json_text(Some(&json!(data.data))) }),
+        Part::Media(media) => {
+            let source = split_data_url(&media.media.url).map_or_else(
+                || json!({ "type": "url", "url": media.media.url }),
+                |(media_type, data)| json!({ "type": "base64", "media_type": media_type, "data": data }),
+            );
+            json!({ "type": "image", "source": source })
+        }
+        Part::ToolRequest(request) => {
+            let request = &request.tool_request;
+            json!({
+                "type": "tool_use",
+                "id": request.ref_.as_deref().unwrap_or(&request.name),
+                "name": request.name,
+                "input": request.input.clone().unwrap_or_else(|| json!({})),
+            })
+        }
+        Part::ToolResponse(response) => {
+            let response = &response.tool_response;
+            json!({
+                "type": "tool_result",
+                "tool_use_id": response.ref_.as_deref().unwrap_or(&response.name),
+                "content": json_text(response.output.as_ref()),
+            })
+        }
+        Part::Pending(_) => return None,
+    })
+}
+
+/// Converts a rendered prompt into a Gemini `generateContent` request body.
+///
+/// The model is part of the endpoint URL rather than the body, so it is
+/// not included.
+#[must_use]
+pub fn to_gemini_request(rendered: &RenderedPrompt) -> Value {
+    let mut request = Map::new();
+
+    let system: Vec<Value> = rendered
+        .messages
+        .iter()
+        .filter(|message| message.role == Role::System)
+        .flat_map(|message| message.content.iter().filter_map(gemini_part))
+        .collect();
+    if !system.is_empty() {
+        request.insert("systemInstruction".to_string(), json!({ "parts": system }));
+    }
+
+    let contents: Vec<Value> = rendered
+        .messages
+        .iter()
+        .filter(|message| message.role != Role::System)
+        .map(|message| {
+            let role = if message.role == Role::Model {
+                "model"
+            } else {
+                "user"
+            };
+            let parts: Vec<Value> = message.content.iter().filter_map(gemini_part).collect();
+            json!({ "role": role, "parts": parts })
+        })
+        .collect();
+    request.insert("contents".to_string(), contents.into());
+
+    let declarations: Vec<Value> = tool_declarations(rendered)
+        .map(|(name, description, parameters)| function_declaration(name, description, parameters))
+        .collect();
+    if !declarations.is_empty() {
+        request.insert(
+            "tools".to_string(),
+            json!([{ "functionDeclarations": declarations }]),
+        );
+    }
+
+    // Gemini's generation config uses the same names as Genkit's.
+    let mut generation_config = Map::new();
+    merge_config(&mut generation_config, rendered, &[]);
+    if let Some(output) = json_output(rendered) {
+        generation_config.insert("responseMimeType".to_string(), "application/json".into());
+        if let Some(schema) = &output.schema {
+            generation_config.insert("responseSchema".to_string(), schema.clone());
+        }
+    }
+    if !generation_config.is_empty() {
+        request.insert("generationConfig".to_string(), generation_config.into());
+    }
+    Value::Object(request)
+}
+
+/// Converts a part into a Gemini part.
+fn gemini_part(part: &Part) -> Option<Value> {
+    Some(match part {
+        Part::Text(text) => json!({ "text": text.text }),
+        Part::Data(data) => json!({ "text": json_text(Some(&json!(data.data))) }),
+        Part::Media(media) => {
+            let content_type = media.media.content_type.as_deref();
+            split_data_url(&media.media.url).map_or_else(
+                || {
+                    let mut file = Map::new();
+                    if let Some(content_type) = content_type {
+                        file.insert("mimeType".to_string(), content_type.into());
+                    }
+                    file.insert("fileUri".to_string(), media.media.url.clone().into());
+                    json!({ "fileData": file })
+                },
+                |(mime_type, data)| json!({ "inlineData": { "mimeType": mime_type, "data": data } }),
+            )
+        }
+        Part::ToolRequest(request) => json!({
+            "functionCall": {
+                "name": request.tool_request.name,
+                "args": request.tool_request.input.clone().unwrap_or_else(|| json!({})),
+            }
+        }),
+        Part::ToolResponse(response) => {
+            // The response must be an object.
+            let output = match &response.tool_response.output {
+                Some(Value::Object(fields)) => Value::Object(fields.clone()),
+                Some(output) => json!({ "output": output }),
+                None => json!({}),
+            };
+            json!({
+                "functionResponse": {
+                    "name": response.tool_response.name,
+                    "response": output,
+                }
+            })
+        }
+        Part::Pending(_) => return None,
+    })
+}
+
+/// Converts a rendered prompt into a Genkit `GenerateRequest`.
+///
+/// Genkit uses Dotprompt's message format, so messages are copied as-is.
+#[must_use]
+pub fn to_genkit_request(rendered: &RenderedPrompt) -> Value {
+    let mut request = Map::new();
+    if let Some(model) = &rendered.metadata.model {
+        request.insert("model".to_string(), model.clone().into());
+    }
+    request.insert("messages".to_string(), json!(rendered.messages));
+    if let Some(config) = &rendered.metadata.config {
+        request.insert("config".to_string(), config.clone());
+    }
+    if let Some(tools) = &rendered.metadata.tool_defs {
+        request.insert("tools".to_string(), json!(tools));
+    }
+    if let Some(output) = &rendered.metadata.output {
+        request.insert("output".to_string(), json!(output));
+    }
+    Value::Object(request)
+}
+
+/// Returns the model name without its provider prefix.
+fn model_name(rendered: &RenderedPrompt) -> Option<&str> {
+    rendered
+        .metadata
+        .model
+        .as_deref()
+        .map(|model| model.split_once('/').map_or(model, |(_, name)| name))
+}
+
+/// Returns the name, description, and input schema of each tool.
+fn tool_declarations(
+    rendered: &RenderedPrompt,
+) -> impl Iterator<Item = (&str, Option<&str>, Value)> {
+    rendered.metadata.tool_defs.iter().flatten().map(|tool| {
+        (
+            tool.name.as_str(),
+            tool.description.as_deref(),
+            json!(tool.input_schema),
+        )
+    })
+}
+
+/// Builds a function declaration shared by the `OpenAI` and Gemini formats.
+fn function_declaration(name: &str, description: Option<&str>, parameters: Value) -> Value {
+    let mut function = Map::new();
+    function.insert("name".to_string(), name.into());
+    if let Some(description) = description {
+        function.insert("description".to_string(), description.into());
+    }
+    function.insert("parameters".to_string(), parameters);
+    Value::Object(function)
+}
+
+/// Returns the output config when JSON output is requested.
+fn json_output(rendered: &RenderedPrompt) -> Option<&PromptOutputConfig> {
+    rendered
+        .metadata
+        .output
+        .as_ref()
+        .filter(|output| output.format.as_deref() == Some("json"))
+}
+
+/// Copies the prompt config into a request, renaming keys as given.
+fn merge_config(
+    request: &mut Map<String, Value>,
+    rendered: &RenderedPrompt,
+    renames: &[(&str, &str)],
+) {
+    let Some(Value::Object(config)) = &rendered.metadata.config else {
+        return;
+    };
+    for (key, value) in config {
+        let key = renames
+            .iter()
+            .find(|(from, _)| from == key)
+            .map_or(key.as_str(), |(_, to)| to);
+        request.insert(key.to_string(), value.clone());
+    }
+}
+
+/// Serializes an optional JSON value as text, with `null` for `None`.
+fn json_text(value: Option<&Value>) -> String {
+    value.unwrap_or(&Value::Null).to_string()
+}
+
+/// Splits a base64 `data:` URL into its media type and data.
+fn split_data_url(url: &str) -> Option<(&str, &str)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let media_type = header.strip_suffix(";base64")?;
+    Some((media_type, data))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        MediaContent, MediaPart, PromptMetadata, REQUEST_METADATA_KEY, TextPart, ToolDefinition,
+        ToolRequestContent, ToolRequestPart, ToolResponseContent, ToolResponsePart,
+    };
+    use std::collections::HashMap;
+
+    fn text(text: &str) -> Part {
+        Part::Text(TextPart {
+            text: text.to_string(),
+            metadata: None,
+        })
+    }
+
+    fn message(role: Role, content: Vec<Part>) -> Message {
+        Message {
+            role,
+            content,
+            metadata: None,
+        }
+    }
+
+    fn sample() -> RenderedPrompt {
+        RenderedPrompt {
+            metadata: PromptMetadata {
+                model: Some("openai/gpt-4o".to_string()),
+                config: Some(json!({ "temperature": 0.5, "maxOutputTokens": 200 })),
+                tool_defs: Some(vec![ToolDefinition {
+                    name: "lookup".to_string(),
+                    description: Some("Looks things up.".to_string()),
+                    input_schema: serde_json::from_value(json!({ "type": "object" })).unwrap(),
+                    output_schema: None,
+                }]),
+                output: Some(PromptOutputConfig {
+                    format: Some("json".to_string()),
+                    schema: Some(json!({ "type": "object" })),
+                }),
+                ..Default::default()
+            },
+            messages: vec![
+                message(Role::System, vec![text("Be brief.")]),
+                message(
+                    Role::User,
+                    vec![
+                        text("Describe "),
+                        Part::Media(MediaPart {
+                            media: MediaContent {
+                                url: "data:image/png;base64,AAAA".to_string(),
+                                content_type: None,
+                            },
+                            metadata: None,
+                        }),
+                    ],
+                ),
+                message(
+                    Role::Model,
+                    vec![Part::ToolRequest(ToolRequestPart {
+                        tool_request: ToolRequestContent {
+                            name: "lookup".to_string(),
+                            input: Some(json!({ "q": "cat" })),
+                            ref_: Some("1".to_string()),
+                        },
+                        metadata: None,
+                    })],
+                ),
+                message(
+                    Role::Tool,
+                    vec![Part::ToolResponse(ToolResponsePart {
+                        tool_response: ToolResponseContent {
+                            name: "lookup".to_string(),
+                            output: Some(json!("a cat")),
+                            ref_: Some("1".to_string()),
+                        },
+                        metadata: None,
+                    })],
+                ),
+            ],
+            render_info: None,
+        }
+    }
+
+    #[test]
+    fn test_openai_request() {
+        let request = to_openai_request(&sample());
+        assert_eq!(request["model"], "gpt-4o");
+        assert_eq!(request["max_tokens"], 200);
+        assert_eq!(request["temperature"], 0.5);
+        assert_eq!(
+            request["messages"][0],
+            json!({ "role": "system", "content": "Be brief." })
+        );
+        assert_eq!(request["messages"][1]["content"][1]["type"], "image_url");
+        assert_eq!(
+            request["messages"][2]["tool_calls"][0]["function"]["arguments"],
+            r#"{"q":"cat"}"#
+        );
+        assert_eq!(
+            request["messages"][3],
+            json!({ "role": "tool", "tool_call_id": "1", "content": "\"a cat\"" })
+        );
+        assert_eq!(request["tools"][0]["function"]["name"], "lookup");
+        assert_eq!(request["response_format"]["type"], "json_schema");
+    }
+
+    #[test]
+    fn test_anthropic_request() {
+        let request = to_anthropic_request(&sample());
+        assert_eq!(request["system"], "Be brief.");
+        assert_eq!(request["max_tokens"], 200);
+        assert_eq!(request["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            request["messages"][0]["content"][1]["source"],
+            json!({ "type": "base64", "media_type": "image/png", "data": "AAAA" })
+        );
+        assert_eq!(request["messages"][1]["content"][0]["type"], "tool_use");
+        assert_eq!(request["messages"][2]["role"], "user");
+        assert_eq!(request["messages"][2]["content"][0]["tool_use_id"], "1");
+        assert_eq!(
+            request["tools"][0]["input_schema"],
+            json!({ "type": "object" })
+        );
+    }
+
+    #[test]
+    fn test_gemini_request() {
+        let request = to_gemini_request(&sample());
+        assert!(request.get("model").is_none());
+        assert_eq!(
+            request["systemInstruction"],
+            json!({ "parts": [{ "text": "Be brief." }] })
+        );
+        assert_eq!(
+            request["contents"][0]["parts"][1]["inlineData"]["mimeType"],
+            "image/png"
+        );
+        assert_eq!(
+            request["contents"][1]["parts"][0]["functionCall"]["name"],
+            "lookup"
+        );
+        assert_eq!(
+            request["contents"][2]["parts"][0]["functionResponse"]["response"],
+            json!({ "output": "a cat" })
+        );
+        assert_eq!(request["generationConfig"]["maxOutputTokens"], 200);
+        assert_eq!(
+            request["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        assert_eq!(
+            request["tools"][0]["functionDeclarations"][0]["name"],
+            "lookup"
+        );
+    }
+
+    #[test]
+    fn test_genkit_request() {
+        let rendered = sample();
+        let request = to_genkit_request(&rendered);
+        assert_eq!(request["model"], "openai/gpt-4o");
+        assert_eq!(request["messages"], json!(rendered.messages));
+        assert_eq!(request["output"]["format"], "json");
+    }
+
+    #[test]
+    fn test_request_metadata() {
+        let mut rendered = sample();
+        assert!(to_openai_request(&rendered).get("metadata").is_none());
+
+        rendered.metadata.metadata = Some(HashMap::from([(
+            REQUEST_METADATA_KEY.to_string(),
+            json!({ "traceId": "abc", "attempt": 2, "user_id": "u1" }),
+        )]));
+        assert_eq!(
+            to_openai_request(&rendered)["metadata"],
+            json!({ "traceId": "abc", "attempt": "2", "user_id": "u1" })
+        );
+        assert_eq!(
+            to_anthropic_request(&rendered)["metadata"],
+            json!({ "user_id": "u1" })
+        );
+        assert!(to_gemini_request(&rendered).get("metadata").is_none());
+    }
+}
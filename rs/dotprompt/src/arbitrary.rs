@@ -0,0 +1,324 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Property-testing strategies for the message and metadata types.
+//!
+//! Enabled by the `proptest` feature. [`Role`], [`Part`], [`Message`], and
+//! [`PromptMetadata`] implement [`Arbitrary`], so tests in downstream crates
+//! can write `any::<Message>()`:
+//!
+//! ```ignore
+//! use dotprompt::Message;
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn messages_survive_my_transport(message in any::<Message>()) {
+//!         prop_assert_eq!(send_and_receive(&message), message);
+//!     }
+//! }
+//! ```
+//!
+//! Generated values only use shapes that survive a JSON round trip: JSON
+//! numbers are integers, and optional JSON fields are never `Some(null)`,
+//! which serializes the same as `None`.
+
+use std::collections::HashMap;
+
+use proptest::collection::{btree_map, hash_map, vec};
+use proptest::option;
+use proptest::prelude::*;
+use serde_json::Value;
+
+use crate::types::{
+    DataPart, MediaContent, MediaPart, Message, MissingPolicy, Part, PendingPart,
+    PromptInputConfig, PromptMetadata, PromptOutputConfig, RenderConfig, Role, TextPart,
+    ToolDefinition, ToolRequestContent, ToolRequestPart, ToolResponseContent, ToolResponsePart,
+};
+
+/// Strategy for identifiers used as names and object keys.
+pub fn identifier() -> impl Strategy<Value = String> {
+    "[a-zA-Z_][a-zA-Z0-9_]{0,8}"
+}
+
+/// Strategy for short free-form text.
+pub fn text() -> impl Strategy<Value = String> {
+    "\\PC{0,16}"
+}
+
+/// Strategy for JSON values up to three levels deep.
+pub fn json_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        text().prop_map(Value::String),
+    ];
+    leaf.prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..4).prop_map(Value::Array),
+            btree_map(identifier(), inner, 0..4)
+                .prop_map(|fields| Value::Object(fields.into_iter().collect())),
+        ]
+    })
+}
+
+/// Strategy for JSON values other than `null`.
+pub fn non_null_json_value() -> impl Strategy<Value = Value> {
+    json_value().prop_filter("null", |value| !value.is_null())
+}
+
+/// Strategy for metadata maps.
+pub fn metadata_map() -> impl Strategy<Value = HashMap<String, Value>> {
+    hash_map(identifier(), json_value(), 0..3)
+}
+
+/// Strategy for optional metadata maps.
+fn metadata() -> impl Strategy<Value = Option<HashMap<String, Value>>> {
+    option::of(metadata_map())
+}
+
+impl Arbitrary for Role {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(Self::User),
+            Just(Self::Model),
+            Just(Self::Tool),
+            Just(Self::System),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Part {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let tool_ref = || option::of(identifier());
+        prop_oneof![
+            (text(), metadata())
+                .prop_map(|(text, metadata)| Self::Text(TextPart { text, metadata })),
+            (metadata_map(), metadata())
+                .prop_map(|(data, metadata)| Self::Data(DataPart { data, metadata })),
+            (text(), option::of("[a-z]+/[a-z]+"), metadata()).prop_map(
+                |(url, content_type, metadata)| Self::Media(MediaPart {
+                    media: MediaContent { url, content_type },
+                    metadata,
+                })
+            ),
+            (
+                identifier(),
+                option::of(non_null_json_value()),
+                tool_ref(),
+                metadata()
+            )
+                .prop_map(|(name, input, ref_, metadata)| {
+                    Self::ToolRequest(ToolRequestPart {
+                        tool_request: ToolRequestContent { name, input, ref_ },
+                        metadata,
+                    })
+                }),
+            (
+                identifier(),
+                option::of(non_null_json_value()),
+                tool_ref(),
+                metadata()
+            )
+                .prop_map(|(name, output, ref_, metadata)| {
+                    Self::ToolResponse(ToolResponsePart {
+                        tool_response: ToolResponseContent { name, output, ref_ },
+                        metadata,
+                    })
+                }),
+            metadata_map().prop_map(|metadata| Self::Pending(PendingPart { metadata })),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Message {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (any::<Role>(), vec(any::<Part>(), 0..4), metadata())
+            .prop_map(|(role, content, metadata)| Self {
+                role,
+                content,
+                metadata,
+            })
+            .boxed()
+    }
+}
+
+/// Strategy for tool definitions.
+fn tool_definition() -> impl Strategy<Value = ToolDefinition> {
+    (
+        identifier(),
+        option::of(text()),
+        metadata_map(),
+        option::of(metadata_map()),
+    )
+        .prop_map(
+            |(name, description, input_schema, output_schema)| ToolDefinition {
+                name,
+                description,
+                input_schema,
+                output_schema,
+            },
+        )
+}
+
+impl Arbitrary for PromptMetadata {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let names = || option::of(vec(identifier(), 0..3));
+        let identity = (
+            option::of(identifier()),
+            option::of(identifier()),
+            option::of(identifier()),
+            option::of(text()),
+            names(),
+            names(),
+            names(),
+        );
+        let model = (
+            option::of(identifier()),
+            names(),
+            option::of(vec(tool_definition(), 0..2)),
+            option::of(non_null_json_value()),
+        );
+        let io = (
+            option::of(
+                (
+                    option::of(metadata_map()),
+                    option::of(non_null_json_value()),
+                )
+                    .prop_map(|(default, schema)| PromptInputConfig { default, schema }),
+            ),
+            option::of(
+                (option::of(identifier()), option::of(non_null_json_value()))
+                    .prop_map(|(format, schema)| PromptOutputConfig { format, schema }),
+            ),
+            option::of(
+                option::of(prop_oneof![
+                    Just(MissingPolicy::Empty),
+                    Just(MissingPolicy::Null),
+                    Just(MissingPolicy::Error),
+                ])
+                .prop_map(|on_missing| RenderConfig { on_missing }),
+            ),
+        );
+        let extra = (
+            metadata(),
+            option::of(hash_map(identifier(), metadata_map(), 0..2)),
+            metadata(),
+        );
+
+        (identity, model, io, extra)
+            .prop_map(
+                |(
+                    (name, variant, version, description, tags, owners, reviewers),
+                    (model, tools, tool_defs, config),
+                    (input, output, render),
+                    (raw, ext, metadata),
+                )| Self {
+                    name,
+                    variant,
+                    version,
+                    description,
+                    tags,
+                    owners,
+                    reviewers,
+                    model,
+                    tools,
+                    tool_defs,
+                    config,
+                    input,
+                    output,
+                    render,
+                    examples: None,
+                    raw,
+                    ext,
+                    metadata,
+                },
+            )
+            .boxed()
+    }
+}
+
+/// Strategy for picoschema definitions: type names with array and union
+/// suffixes, and objects of those.
+pub fn picoschema() -> impl Strategy<Value = Value> {
+    let primitive = prop_oneof![
+        Just("string"),
+        Just("number"),
+        Just("integer"),
+        Just("boolean"),
+        Just("null"),
+    ];
+    let type_name =
+        (primitive, 0..3usize, option::of(Just("null"))).prop_map(|(name, arrays, nullable)| {
+            let name = format!("{name}{}", "[]".repeat(arrays));
+            Value::String(nullable.map_or_else(|| name.clone(), |n| format!("{name} | {n}")))
+        });
+    type_name.prop_recursive(3, 16, 4, |inner| {
+        btree_map(identifier(), inner, 1..4)
+            .prop_map(|fields| Value::Object(fields.into_iter().collect()))
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_document;
+    use crate::picoschema::picoschema_to_json_schema;
+
+    proptest! {
+        #[test]
+        fn test_message_json_round_trip(message in any::<Message>()) {
+            let json = serde_json::to_string(&message).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Message>(&json).unwrap(), message);
+        }
+
+        #[test]
+        fn test_metadata_json_round_trip(metadata in any::<PromptMetadata>()) {
+            let json = serde_json::to_string(&metadata).unwrap();
+            prop_assert_eq!(serde_json::from_str::<PromptMetadata>(&json).unwrap(), metadata);
+        }
+
+        #[test]
+        fn test_metadata_frontmatter_round_trip(metadata in any::<PromptMetadata>()) {
+            let source = format!("---\n{}---\nHello", serde_yaml_ng::to_string(&metadata).unwrap());
+            let parsed = parse_document::<Value>(&source).unwrap();
+            prop_assert_eq!(parsed.metadata, metadata);
+            prop_assert_eq!(parsed.template, "Hello");
+        }
+
+        #[test]
+        fn test_picoschema_expansion_is_idempotent(schema in picoschema()) {
+            let once = picoschema_to_json_schema(&schema).unwrap();
+            prop_assert_eq!(picoschema_to_json_schema(&once).unwrap(), once);
+        }
+    }
+}
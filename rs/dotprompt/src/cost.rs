@@ -0,0 +1,372 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cost estimation for rendered prompts.
+//!
+//! [`estimate_cost`] prices a rendered prompt with the model's per-token
+//! rates from a [`PricingTable`]. The built-in table covers common `OpenAI`,
+//! Anthropic, and Gemini models; provider prices change, so a project can
+//! override or extend it with a `pricing.toml` file:
+//!
+//! ```toml
+//! # USD per million tokens.
+//! [models."gpt-4o"]
+//! input = 2.5
+//! output = 10.0
+//! ```
+//!
+//! Models are matched by the longest table entry that their name, with or
+//! without its provider prefix, starts with, so `gpt-4o` also prices
+//! `openai/gpt-4o-2024-08-06`.
+//!
+//! Token counts come from [`estimate_tokens`], a character-based heuristic
+//! rather than a provider tokenizer, so estimates are approximate. Output
+//! tokens are the prompt's `maxOutputTokens`, an upper bound, or zero when it
+//! is not set.
+//!
+//! ```
+//! use dotprompt::cost::estimate_cost;
+//! use dotprompt::{DataArgument, Dotprompt, RenderedPrompt};
+//!
+//! let source = "---\nmodel: openai/gpt-4o\nconfig:\n  maxOutputTokens: 100\n---\nHello!";
+//! let rendered: RenderedPrompt = Dotprompt::new(None)
+//!     .render(source, &DataArgument::<serde_json::Value>::default(), None)
+//!     .unwrap();
+//! let estimate = estimate_cost(&rendered, "openai/gpt-4o").unwrap();
+//! assert_eq!(estimate.output_tokens, 100);
+//! assert!(estimate.total_cost > 0.0);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DotpromptError, Result};
+use crate::types::{Part, RenderedPrompt};
+
+/// Average number of characters per token assumed by [`estimate_tokens`].
+pub(crate) const CHARS_PER_TOKEN: usize = 4;
+
+/// Tokens per million, the unit prices are quoted in.
+const PER_MILLION: f64 = 1_000_000.0;
+
+/// Built-in prices in USD per million input and output tokens.
+const BUILTIN_PRICES: [(&str, f64, f64); 14] = [
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("o3-mini", 1.1, 4.4),
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("gemini-2.5-flash", 0.3, 2.5),
+    ("gemini-2.0-flash", 0.1, 0.4),
+    ("gemini-1.5-pro", 1.25, 5.0),
+    ("gemini-1.5-flash", 0.075, 0.3),
+];
+
+/// Prices for one model, in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Price per million input (prompt) tokens.
+    pub input: f64,
+
+    /// Price per million output (completion) tokens.
+    pub output: f64,
+}
+
+/// Per-model prices, usually the built-in table with overrides from
+/// `pricing.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PricingTable {
+    /// Prices keyed by model name or name prefix.
+    #[serde(default)]
+    pub models: BTreeMap<String, ModelPricing>,
+}
+
+impl PricingTable {
+    /// Conventional file name for a pricing table.
+    pub const FILE_NAME: &'static str = "pricing.toml";
+
+    /// Returns the built-in prices.
+    #[must_use]
+    pub fn builtin() -> Self {
+        let models = BUILTIN_PRICES
+            .iter()
+            .map(|&(model, input, output)| (model.to_string(), ModelPricing { input, output }))
+            .collect();
+        Self { models }
+    }
+
+    /// Parses a pricing table from TOML source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is not a valid pricing table or has a
+    /// negative price.
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let table: Self =
+            toml::from_str(source).map_err(|e| DotpromptError::PricingError(e.to_string()))?;
+        for (model, pricing) in &table.models {
+            if pricing.input < 0.0 || pricing.output < 0.0 {
+                return Err(DotpromptError::PricingError(format!(
+                    "model '{model}' has a negative price"
+                )));
+            }
+        }
+        Ok(table)
+    }
+
+    /// Loads a pricing table from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid pricing
+    /// table.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        Self::from_toml(&source).map_err(|e| match e {
+            DotpromptError::PricingError(msg) => {
+                DotpromptError::PricingError(format!("{}: {msg}", path.display()))
+            }
+            other => other,
+        })
+    }
+
+    /// Adds the prices in `overrides`, replacing existing entries for the
+    /// same models.
+    #[must_use]
+    pub fn with_overrides(mut self, overrides: Self) -> Self {
+        self.models.extend(overrides.models);
+        self
+    }
+
+    /// Looks up the prices for a model.
+    ///
+    /// The longest entry that the model name, with or without its provider
+    /// prefix, starts with wins.
+    #[must_use]
+    pub fn get(&self, model: &str) -> Option<&ModelPricing> {
+        let bare = model.split_once('/').map_or(model, |(_, name)| name);
+        self.models
+            .iter()
+            .filter(|(key, _)| model.starts_with(key.as_str()) || bare.starts_with(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, pricing)| pricing)
+    }
+
+    /// Estimates the cost of sending a rendered prompt to a model, or `None`
+    /// if the table has no price for the model.
+    #[must_use]
+    pub fn estimate_cost(&self, rendered: &RenderedPrompt, model: &str) -> Option<CostEstimate> {
+        let pricing = self.get(model)?;
+        let input_tokens = prompt_tokens(rendered);
+        let output_tokens = max_output_tokens(rendered);
+        #[allow(clippy::cast_precision_loss)]
+        let (input_cost, output_cost) = (
+            input_tokens as f64 * pricing.input / PER_MILLION,
+            output_tokens as f64 * pricing.output / PER_MILLION,
+        );
+        Some(CostEstimate {
+            model: model.to_string(),
+            input_tokens,
+            output_tokens,
+            input_cost,
+            output_cost,
+            total_cost: input_cost + output_cost,
+        })
+    }
+}
+
+/// The estimated cost of one model call, in USD.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEstimate {
+    /// The model the estimate is for.
+    pub model: String,
+
+    /// Estimated prompt tokens.
+    pub input_tokens: usize,
+
+    /// Output tokens priced: the prompt's `maxOutputTokens`, or zero.
+    pub output_tokens: usize,
+
+    /// Cost of the input tokens.
+    pub input_cost: f64,
+
+    /// Cost of the output tokens.
+    pub output_cost: f64,
+
+    /// Sum of the input and output costs.
+    pub total_cost: f64,
+}
+
+/// Estimates the cost of sending a rendered prompt to a model using the
+/// built-in prices, or `None` if the model is not in the table.
+///
+/// Use [`PricingTable::estimate_cost`] to apply overridden prices.
+#[must_use]
+pub fn estimate_cost(rendered: &RenderedPrompt, model: &str) -> Option<CostEstimate> {
+    PricingTable::builtin().estimate_cost(rendered, model)
+}
+
+/// Estimates the number of tokens in a text, assuming four characters per
+/// token.
+#[must_use]
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Estimates the tokens sent for a rendered prompt: its messages, tool
+/// definitions, and output schema. Media is not counted.
+fn prompt_tokens(rendered: &RenderedPrompt) -> usize {
+    let parts: usize = rendered
+        .messages
+        .iter()
+        .flat_map(|message| &message.content)
+        .map(|part| match part {
+            Part::Text(text) => estimate_tokens(&text.text),
+            Part::Data(data) => estimate_tokens(&serde_json::json!(data.data).to_string()),
+            Part::ToolRequest(request) => {
+                estimate_tokens(&serde_json::json!(request.tool_request).to_string())
+            }
+            Part::ToolResponse(response) => {
+                estimate_tokens(&serde_json::json!(response.tool_response).to_string())
+            }
+            Part::Media(_) | Part::Pending(_) => 0,
+        })
+        .sum();
+    let tools = rendered.metadata.tool_defs.as_ref().map_or(0, |tools| {
+        estimate_tokens(&serde_json::json!(tools).to_string())
+    });
+    let schema = rendered
+        .metadata
+        .output
+        .as_ref()
+        .and_then(|output| output.schema.as_ref())
+        .map_or(0, |schema| estimate_tokens(&schema.to_string()));
+    parts + tools + schema
+}
+
+/// Returns the prompt's `maxOutputTokens` config, or zero.
+fn max_output_tokens(rendered: &RenderedPrompt) -> usize {
+    rendered
+        .metadata
+        .config
+        .as_ref()
+        .and_then(|config| config.get("maxOutputTokens"))
+        .and_then(serde_json::Value::as_u64)
+        .and_then(|tokens| usize::try_from(tokens).ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::types::{Message, PromptMetadata, Role, TextPart};
+    use serde_json::json;
+
+    fn rendered(text: &str, config: Option<serde_json::Value>) -> RenderedPrompt {
+        RenderedPrompt {
+            metadata: PromptMetadata {
+                config,
+                ..Default::default()
+            },
+            messages: vec![Message {
+                role: Role::User,
+                content: vec![Part::Text(TextPart {
+                    text: text.to_string(),
+                    metadata: None,
+                })],
+                metadata: None,
+            }],
+            render_info: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("éééé"), 1);
+    }
+
+    #[test]
+    fn test_lookup_uses_longest_prefix() {
+        let table = PricingTable::builtin();
+        let price = |input, output| Some(ModelPricing { input, output });
+        assert_eq!(table.get("openai/gpt-4o-mini").copied(), price(0.15, 0.6));
+        assert_eq!(table.get("gpt-4o-2024-08-06").copied(), price(2.5, 10.0));
+        assert_eq!(
+            table.get("anthropic/claude-sonnet-4-20250514").copied(),
+            price(3.0, 15.0)
+        );
+        assert!(table.get("unknown/model").is_none());
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let prompt = rendered(&"x".repeat(4000), Some(json!({ "maxOutputTokens": 500 })));
+        let estimate = estimate_cost(&prompt, "openai/gpt-4o").unwrap();
+        assert_eq!(estimate.input_tokens, 1000);
+        assert_eq!(estimate.output_tokens, 500);
+        assert!((estimate.input_cost - 0.0025).abs() < 1e-12);
+        assert!((estimate.output_cost - 0.005).abs() < 1e-12);
+        assert!((estimate.total_cost - 0.0075).abs() < 1e-12);
+
+        assert_eq!(
+            estimate_cost(&rendered("hi", None), "gpt-4o")
+                .unwrap()
+                .output_tokens,
+            0
+        );
+        assert!(estimate_cost(&prompt, "mystery").is_none());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides = PricingTable::from_toml(
+            r#"
+[models."gpt-4o"]
+input = 1.0
+output = 2.0
+
+[models."acme/house-model"]
+input = 0.5
+output = 0.5
+"#,
+        )
+        .unwrap();
+        let table = PricingTable::builtin().with_overrides(overrides);
+        let price = |input, output| Some(ModelPricing { input, output });
+        assert_eq!(table.get("openai/gpt-4o").copied(), price(1.0, 2.0));
+        assert_eq!(table.get("acme/house-model").copied(), price(0.5, 0.5));
+        assert_eq!(table.get("gpt-4o-mini").copied(), price(0.15, 0.6));
+
+        let err =
+            PricingTable::from_toml("[models.bad]\ninput = -1.0\noutput = 1.0\n").unwrap_err();
+        assert!(err.to_string().contains("negative price"));
+        assert!(PricingTable::from_toml("[models.bad]\ninput = 1.0\n").is_err());
+    }
+}
@@ -20,14 +20,102 @@
 //! compilation, rendering, and metadata resolution.
 
 use crate::error::{DotpromptError, Result};
-use crate::helpers::register_builtin_helpers;
-use crate::parse::{parse_document, to_messages};
+use crate::examples;
+use crate::formats::{self, Format, append_instructions};
+use crate::helpers::{MISSING_POLICY_KEY, register_builtin_helpers};
+use crate::limits::{EACH_GUARD_HELPER, EachGuard, LimitedOutput, RenderLimits};
+use crate::manifest::HelperManifest;
+use crate::number::{self, NumberFormat};
+use crate::observer::{ParseEnd, PartialResolved, RenderEnd, RenderObserver};
+use crate::parse::{
+    apply_sections, extract_frontmatter_and_body, identify_inline_partials, normalize_messages,
+    normalize_tracked, parse_document, parse_frontmatter_value, to_messages,
+};
+use crate::picoschema::{picoschema_to_json_schema_with, resolve_schema_refs};
+use crate::response::{self, ParsedResponse, ResponseError};
+use crate::store::PromptStore;
+use crate::trace::{Instrumented, TracedPrompt, flag_history, follow_moves};
 use crate::types::{
-    DataArgument, JsonSchema, ParsedPrompt, PartialResolver, PromptFunction, PromptMetadata,
-    RenderedPrompt, SchemaResolver, ToolDefinition, ToolResolver,
+    DataArgument, ExampleResolver, ExampleSelector, JsonSchema, ListPartialsOptions,
+    ListPromptsOptions, LoadPartialOptions, LoadPromptOptions, Message, MissingPolicy,
+    ParsedPrompt, PartialResolver, PrecompileFailure, PrecompileReport, PromptFunction,
+    PromptMetadata, REQUEST_METADATA_KEY, RenderInfo, RenderedPrompt, SchemaResolver,
+    ToolDefinition, ToolResolver,
 };
+use crate::util::{calculate_version, format_timestamp};
 use handlebars::{Handlebars, HelperDef};
-use std::collections::HashMap;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, LazyLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Instant, SystemTime};
+
+/// A namespaced partial name, e.g. `lib:header` or `lib:shared/page-header`.
+const NAMESPACED_PARTIAL_NAME: &str = r"[A-Za-z_][\w.-]*:[\w./-]+";
+
+/// Matches namespaced names in partial tags, e.g. `{{> lib:header}}`,
+/// `{{#> lib:layout}}` or `{{/lib:layout}}`.
+#[allow(clippy::expect_used)]
+static NAMESPACED_PARTIAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(\{{\{{~?)(#?>|/)(\s*)({NAMESPACED_PARTIAL_NAME})"
+    ))
+    .expect("internal regex pattern should compile")
+});
+
+/// Matches the partials a template includes, e.g. `{{> header}}` or
+/// `{{#> lib:layout}}`.
+#[allow(clippy::expect_used)]
+static PARTIAL_INCLUDE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"\{{\{{~?#?>\s*({NAMESPACED_PARTIAL_NAME}|[a-zA-Z_][a-zA-Z0-9_]*)\s*~?\}}\}}"
+    ))
+    .expect("internal regex pattern should compile")
+});
+
+/// Matches a Handlebars expression, capturing a leading escape and the
+/// comment marker, e.g. `{{@auth.uid}}`, `\\{{x}}` or `{{! note }}`.
+#[allow(clippy::expect_used)]
+static EXPRESSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\\)?\{\{(~?!)?[^}]*\}\}").expect("internal regex pattern should compile")
+});
+
+/// Matches a data variable such as `@auth` within an expression, or a string
+/// literal, which is left alone.
+#[allow(clippy::expect_used)]
+static DATA_VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#""[^"]*"|'[^']*'|([^\w.@/-])@([A-Za-z_][\w-]*)"#)
+        .expect("internal regex pattern should compile")
+});
+
+/// Data variables Handlebars provides itself.
+const BUILTIN_DATA_VARIABLES: &[&str] = &[
+    "root",
+    "index",
+    "key",
+    "first",
+    "last",
+    "partial-block",
+    "level",
+];
+
+/// Prefix of the render data keys that hold context variables.
+const CONTEXT_PREFIX: &str = "__ctx_";
+
+/// The order `{{#each}}` visits the keys of an object in.
+///
+/// The order is applied to the whole render context, so it also decides the
+/// key order of objects printed by helpers such as `json`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Sorted by key, whatever order the data was built in.
+    #[default]
+    Sorted,
+    /// The order of the input data. Without the `preserve_order` feature,
+    /// `serde_json` objects keep their keys sorted, so this is the same as
+    /// [`Sorted`](Self::Sorted).
+    Insertion,
+}
 
 /// Options for configuring a Dotprompt instance.
 #[derive(Default)]
@@ -41,6 +129,9 @@ pub struct DotpromptOptions {
     /// Pre-registered helpers.
     pub helpers: Option<HashMap<String, Box<dyn HelperDef + Send + Sync>>>,
 
+    /// Descriptions of custom helpers, for tooling (see [`HelperManifest`]).
+    pub helper_manifest: Option<HelperManifest>,
+
     /// Pre-registered partials.
     pub partials: Option<HashMap<String, String>>,
 
@@ -50,6 +141,14 @@ pub struct DotpromptOptions {
     /// Pre-registered schemas.
     pub schemas: Option<HashMap<String, JsonSchema>>,
 
+    /// Custom output formats, by the name prompts give in `output.format`.
+    /// They take precedence over the built-in formats.
+    pub formats: Option<HashMap<String, Arc<dyn Format>>>,
+
+    /// Pre-registered few-shot examples, by the `examples.source` prompts
+    /// give.
+    pub examples: Option<HashMap<String, Vec<serde_json::Value>>>,
+
     /// Tool resolver for dynamic tool lookup.
     pub tool_resolver: Option<Box<dyn ToolResolver>>,
 
@@ -58,6 +157,48 @@ pub struct DotpromptOptions {
 
     /// Partial resolver for dynamic partial lookup.
     pub partial_resolver: Option<Box<dyn PartialResolver>>,
+
+    /// Example resolver for loading the few-shot examples prompts name.
+    pub example_resolver: Option<Box<dyn ExampleResolver>>,
+
+    /// Chooses which loaded examples a render uses. Defaults to
+    /// [`StaticExampleSelector`](crate::StaticExampleSelector).
+    pub example_selector: Option<Box<dyn ExampleSelector>>,
+
+    /// Resolvers for namespaced partials, keyed by namespace: `{{> lib:header}}`
+    /// asks the `lib` resolver for `header`.
+    pub partial_namespaces: Option<HashMap<String, Box<dyn PartialResolver>>>,
+
+    /// Store consulted for partials that no resolver provides.
+    pub partial_store: Option<Box<dyn PromptStore>>,
+
+    /// Attach a [`RenderInfo`] block to every rendered prompt.
+    pub render_info: bool,
+
+    /// Tag the parts after each `{{section}}` marker with its `purpose`
+    /// instead of emitting a pending part; see [`apply_sections`].
+    pub section_metadata: bool,
+
+    /// Drop empty messages, merge consecutive messages with the same role,
+    /// and trim whitespace around message text; see [`normalize_messages`].
+    pub normalize_messages: bool,
+
+    /// Limits on the work done by each render.
+    pub limits: RenderLimits,
+
+    /// The order `{{#each}}` visits object keys in.
+    pub key_order: KeyOrder,
+
+    /// How numbers in the render data print.
+    pub number_format: NumberFormat,
+
+    /// How `null` and missing values render, unless a prompt's
+    /// `render.onMissing` frontmatter or the render's options say otherwise.
+    pub on_missing: MissingPolicy,
+
+    /// Observer told about parses, renders, and resolved partials; see
+    /// [`crate::observer`].
+    pub observer: Option<Box<dyn RenderObserver>>,
 }
 
 /// The main Dotprompt class for template management.
@@ -89,9 +230,24 @@ impl std::fmt::Debug for DotpromptOptions {
             .field("default_model", &self.default_model)
             .field("model_configs", &self.model_configs)
             .field("helpers", &"<helpers>")
+            .field("helper_manifest", &self.helper_manifest)
             .field("partials", &self.partials)
             .field("tools", &self.tools)
             .field("schemas", &self.schemas)
+            .field(
+                "formats",
+                &self
+                    .formats
+                    .as_ref()
+                    .map(|formats| formats.keys().collect::<Vec<_>>()),
+            )
+            .field(
+                "examples",
+                &self
+                    .examples
+                    .as_ref()
+                    .map(|examples| examples.keys().collect::<Vec<_>>()),
+            )
             .field(
                 "tool_resolver",
                 &self.tool_resolver.as_ref().map(|_| "<resolver>"),
@@ -104,6 +260,33 @@ impl std::fmt::Debug for DotpromptOptions {
                 "partial_resolver",
                 &self.partial_resolver.as_ref().map(|_| "<resolver>"),
             )
+            .field(
+                "example_resolver",
+                &self.example_resolver.as_ref().map(|_| "<resolver>"),
+            )
+            .field(
+                "example_selector",
+                &self.example_selector.as_ref().map(|_| "<selector>"),
+            )
+            .field(
+                "partial_namespaces",
+                &self
+                    .partial_namespaces
+                    .as_ref()
+                    .map(|namespaces| namespaces.keys().collect::<Vec<_>>()),
+            )
+            .field(
+                "partial_store",
+                &self.partial_store.as_ref().map(|_| "<store>"),
+            )
+            .field("render_info", &self.render_info)
+            .field("section_metadata", &self.section_metadata)
+            .field("normalize_messages", &self.normalize_messages)
+            .field("limits", &self.limits)
+            .field("key_order", &self.key_order)
+            .field("number_format", &self.number_format)
+            .field("on_missing", &self.on_missing)
+            .field("observer", &self.observer.as_ref().map(|_| "<observer>"))
             .finish()
     }
 }
@@ -112,26 +295,77 @@ impl std::fmt::Debug for DotpromptOptions {
 ///
 /// This struct provides methods for parsing, compiling, and rendering
 /// prompt templates with Handlebars and YAML frontmatter.
+///
+/// # Partial resolution
+///
+/// When a partial name could come from several places, the first of these
+/// wins, regardless of the order they were configured in:
+///
+/// 1. partials registered explicitly, through
+///    [`DotpromptOptions::partials`] or [`define_partial`](Self::define_partial);
+/// 2. the [`partial_resolver`](DotpromptOptions::partial_resolver);
+/// 3. the [`partial_store`](DotpromptOptions::partial_store).
+///
+/// A namespaced name such as `lib:header` is looked up as `header` by the
+/// resolver registered for `lib`, in place of steps 2 and 3. Names whose
+/// namespace has no resolver are looked up whole.
+///
+/// # Sharing
+///
+/// Helpers, partials, tools, and schemas can be registered through `&self`,
+/// and partials are resolved through `&self`, so one instance can be shared
+/// as an `Arc<Dotprompt>` by threads that render concurrently. A render sees
+/// the registrations made before it started.
 #[allow(dead_code)] // Fields will be used in future functionality
 pub struct Dotprompt {
-    handlebars: Handlebars<'static>,
+    registry: RwLock<Registry>,
+    helper_manifest: HelperManifest,
     default_model: Option<String>,
     model_configs: HashMap<String, serde_json::Value>,
-    tools: HashMap<String, ToolDefinition>,
-    schemas: HashMap<String, JsonSchema>,
     tool_resolver: Option<Box<dyn ToolResolver>>,
     schema_resolver: Option<Box<dyn SchemaResolver>>,
     partial_resolver: Option<Box<dyn PartialResolver>>,
+    example_resolver: Option<Box<dyn ExampleResolver>>,
+    example_selector: Box<dyn ExampleSelector>,
+    partial_store: Option<Box<dyn PromptStore>>,
+    render_info: bool,
+    section_metadata: bool,
+    normalize_messages: bool,
+    limits: RenderLimits,
+    key_order: KeyOrder,
+    number_format: NumberFormat,
+    on_missing: MissingPolicy,
+    observer: Option<Box<dyn RenderObserver>>,
+}
+
+/// What can be registered after a [`Dotprompt`] is created.
+///
+/// Kept behind one lock so that a render sees a consistent set.
+struct Registry {
+    handlebars: Handlebars<'static>,
+    tools: HashMap<String, ToolDefinition>,
+    schemas: HashMap<String, JsonSchema>,
+    formats: HashMap<String, Arc<dyn Format>>,
+    examples: HashMap<String, Vec<serde_json::Value>>,
+    partial_namespaces: HashMap<String, Arc<dyn PartialResolver>>,
+    /// Sources of registered partials, for [`RenderInfo`] versions.
+    partial_sources: HashMap<String, String>,
 }
 
+// The registry's fields are listed individually.
+#[allow(clippy::missing_fields_in_debug)]
 impl std::fmt::Debug for Dotprompt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let registry = self.registry();
         f.debug_struct("Dotprompt")
             .field("handlebars", &"<handlebars>")
+            .field("helper_manifest", &self.helper_manifest)
             .field("default_model", &self.default_model)
             .field("model_configs", &self.model_configs)
-            .field("tools", &self.tools)
-            .field("schemas", &self.schemas)
+            .field("tools", &registry.tools)
+            .field("schemas", &registry.schemas)
+            .field("formats", &registry.formats.keys().collect::<Vec<_>>())
+            .field("examples", &registry.examples.keys().collect::<Vec<_>>())
             .field(
                 "tool_resolver",
                 &self.tool_resolver.as_ref().map(|_| "<resolver>"),
@@ -144,6 +378,28 @@ impl std::fmt::Debug for Dotprompt {
                 "partial_resolver",
                 &self.partial_resolver.as_ref().map(|_| "<resolver>"),
             )
+            .field(
+                "example_resolver",
+                &self.example_resolver.as_ref().map(|_| "<resolver>"),
+            )
+            .field("example_selector", &"<selector>")
+            .field(
+                "partial_namespaces",
+                &registry.partial_namespaces.keys().collect::<Vec<_>>(),
+            )
+            .field(
+                "partial_store",
+                &self.partial_store.as_ref().map(|_| "<store>"),
+            )
+            .field("partial_sources", &registry.partial_sources)
+            .field("render_info", &self.render_info)
+            .field("section_metadata", &self.section_metadata)
+            .field("normalize_messages", &self.normalize_messages)
+            .field("limits", &self.limits)
+            .field("key_order", &self.key_order)
+            .field("number_format", &self.number_format)
+            .field("on_missing", &self.on_missing)
+            .field("observer", &self.observer.as_ref().map(|_| "<observer>"))
             .finish()
     }
 }
@@ -176,22 +432,50 @@ impl Dotprompt {
             }
         }
 
+        if let Some(max) = opts.limits.max_each_iterations {
+            handlebars.register_helper(EACH_GUARD_HELPER, Box::new(EachGuard { max }));
+        }
+
         // Register partials
-        if let Some(partials) = opts.partials {
-            for (name, source) in partials {
-                let _ = handlebars.register_template_string(&name, source);
-            }
+        let partial_sources = opts.partials.unwrap_or_default();
+        for (name, source) in &partial_sources {
+            let _ = register_partial(&mut handlebars, &opts.limits, name, source);
         }
 
         Self {
-            handlebars,
+            registry: RwLock::new(Registry {
+                handlebars,
+                tools: opts.tools.unwrap_or_default(),
+                schemas: opts.schemas.unwrap_or_default(),
+                formats: opts.formats.unwrap_or_default(),
+                examples: opts.examples.unwrap_or_default(),
+                partial_namespaces: opts
+                    .partial_namespaces
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(namespace, resolver)| (namespace, Arc::from(resolver)))
+                    .collect(),
+                partial_sources,
+            }),
+            helper_manifest: opts.helper_manifest.unwrap_or_default(),
             default_model: opts.default_model,
             model_configs: opts.model_configs.unwrap_or_default(),
-            tools: opts.tools.unwrap_or_default(),
-            schemas: opts.schemas.unwrap_or_default(),
             tool_resolver: opts.tool_resolver,
             schema_resolver: opts.schema_resolver,
             partial_resolver: opts.partial_resolver,
+            example_resolver: opts.example_resolver,
+            example_selector: opts
+                .example_selector
+                .unwrap_or_else(|| Box::new(examples::StaticExampleSelector)),
+            partial_store: opts.partial_store,
+            render_info: opts.render_info,
+            section_metadata: opts.section_metadata,
+            normalize_messages: opts.normalize_messages,
+            limits: opts.limits,
+            key_order: opts.key_order,
+            number_format: opts.number_format,
+            on_missing: opts.on_missing,
+            observer: opts.observer,
         }
     }
 
@@ -204,16 +488,39 @@ impl Dotprompt {
     ///
     /// # Returns
     ///
-    /// Returns a mutable reference to self for chaining.
+    /// Returns a reference to self for chaining.
     pub fn define_helper(
-        &mut self,
+        &self,
         name: impl Into<String>,
         helper: Box<dyn HelperDef + Send + Sync>,
-    ) -> &mut Self {
-        self.handlebars.register_helper(&name.into(), helper);
+    ) -> &Self {
+        self.registry_mut()
+            .handlebars
+            .register_helper(&name.into(), helper);
         self
     }
 
+    /// Locks the registry for reading.
+    ///
+    /// Registrations cannot leave the registry half-updated, so a lock
+    /// poisoned by a panic elsewhere is still safe to use.
+    fn registry(&self) -> RwLockReadGuard<'_, Registry> {
+        self.registry.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the registry for writing.
+    fn registry_mut(&self) -> RwLockWriteGuard<'_, Registry> {
+        self.registry
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the descriptions of custom helpers supplied in the options.
+    #[must_use]
+    pub const fn helper_manifest(&self) -> &HelperManifest {
+        &self.helper_manifest
+    }
+
     /// Registers a partial template.
     ///
     /// # Arguments
@@ -223,22 +530,44 @@ impl Dotprompt {
     ///
     /// # Returns
     ///
-    /// Returns a mutable reference to self for chaining.
+    /// Returns a reference to self for chaining.
     ///
     /// # Errors
     ///
     /// Returns error if template compilation fails.
     pub fn define_partial(
-        &mut self,
+        &self,
         name: impl Into<String>,
         source: impl Into<String>,
-    ) -> Result<&mut Self> {
-        self.handlebars
-            .register_template_string(&name.into(), source.into())
-            .map_err(|e| DotpromptError::CompilationError(e.to_string()))?;
+    ) -> Result<&Self> {
+        let name = name.into();
+        let source = source.into();
+        let mut registry = self.registry_mut();
+        register_partial(&mut registry.handlebars, &self.limits, &name, &source)?;
+        registry.partial_sources.insert(name, source);
+        drop(registry);
         Ok(self)
     }
 
+    /// Registers the resolver for partials in a namespace.
+    ///
+    /// `{{> lib:header}}` asks the resolver registered for `lib` for
+    /// `header`. Replaces any resolver already registered for the namespace.
+    ///
+    /// # Returns
+    ///
+    /// Returns a reference to self for chaining.
+    pub fn define_partial_namespace(
+        &self,
+        namespace: impl Into<String>,
+        resolver: Box<dyn PartialResolver>,
+    ) -> &Self {
+        self.registry_mut()
+            .partial_namespaces
+            .insert(namespace.into(), Arc::from(resolver));
+        self
+    }
+
     /// Registers a tool definition.
     ///
     /// # Arguments
@@ -247,12 +576,59 @@ impl Dotprompt {
     ///
     /// # Returns
     ///
-    /// Returns a mutable reference to self for chaining.
-    pub fn define_tool(&mut self, def: ToolDefinition) -> &mut Self {
-        self.tools.insert(def.name.clone(), def);
+    /// Returns a reference to self for chaining.
+    pub fn define_tool(&self, def: ToolDefinition) -> &Self {
+        self.registry_mut().tools.insert(def.name.clone(), def);
+        self
+    }
+
+    /// Registers a custom output format, which prompts use by giving its
+    /// name in `output.format`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the format
+    /// * `format` - The format implementation
+    ///
+    /// # Returns
+    ///
+    /// Returns a reference to self for chaining.
+    pub fn define_format(&self, name: impl Into<String>, format: Arc<dyn Format>) -> &Self {
+        self.registry_mut().formats.insert(name.into(), format);
+        self
+    }
+
+    /// Registers few-shot examples for prompts whose `examples.source` is
+    /// `source`.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source prompts name
+    /// * `examples` - The examples
+    ///
+    /// # Returns
+    ///
+    /// Returns a reference to self for chaining.
+    pub fn define_examples(
+        &self,
+        source: impl Into<String>,
+        examples: Vec<serde_json::Value>,
+    ) -> &Self {
+        self.registry_mut().examples.insert(source.into(), examples);
         self
     }
 
+    /// Returns the output format with this name: a registered one, or else a
+    /// built-in one.
+    #[must_use]
+    pub fn format(&self, name: &str) -> Option<Arc<dyn Format>> {
+        self.registry()
+            .formats
+            .get(name)
+            .cloned()
+            .or_else(|| formats::builtin(name))
+    }
+
     /// Parses a prompt template.
     ///
     /// # Arguments
@@ -270,7 +646,19 @@ impl Dotprompt {
     where
         M: serde::de::DeserializeOwned + Default,
     {
-        parse_document(source.as_ref())
+        let source = source.as_ref();
+        let Some(observer) = &self.observer else {
+            return parse_document(source);
+        };
+        observer.on_parse_start(source.len());
+        let started = Instant::now();
+        let result = parse_document(source);
+        observer.on_parse_end(&ParseEnd {
+            source_bytes: source.len(),
+            duration: started.elapsed(),
+            error: result.as_ref().err(),
+        });
+        result
     }
 
     /// Renders a prompt template.
@@ -324,14 +712,340 @@ impl Dotprompt {
         &self,
         source: impl AsRef<str>,
         data: &DataArgument<V>,
-        _options: Option<PromptMetadata<M>>,
+        options: Option<PromptMetadata<M>>,
+    ) -> Result<RenderedPrompt<M>>
+    where
+        V: serde::Serialize + Default + Clone,
+        M: serde::de::DeserializeOwned + Default + Clone,
+    {
+        let source = source.as_ref();
+        self.observe_render(
+            source,
+            |rendered: &RenderedPrompt<M>| &rendered.messages,
+            || self.render_untraced(source, data, options),
+        )
+    }
+
+    /// Renders a prompt template without notifying the observer.
+    fn render_untraced<V, M>(
+        &self,
+        source: &str,
+        data: &DataArgument<V>,
+        options: Option<PromptMetadata<M>>,
     ) -> Result<RenderedPrompt<M>>
     where
         V: serde::Serialize + Default + Clone,
         M: serde::de::DeserializeOwned + Default + Clone,
     {
-        let parsed: ParsedPrompt<M> = self.parse(source.as_ref())?;
+        let parsed: ParsedPrompt<M> = self.parse(source)?;
+        let mut metadata =
+            self.resolve_parsed_metadata(source, parsed.metadata.clone(), options)?;
+        let rendered_string = self.render_template(&parsed.template, data, &metadata)?;
+
+        // Convert to messages (passing data for history)
+        let mut messages = to_messages(&rendered_string, Some(data));
+        if self.section_metadata {
+            apply_sections(&mut messages);
+        }
+        if self.normalize_messages {
+            messages = normalize_messages(messages);
+        }
+        if let Some(instructions) = self.output_instructions(&metadata)? {
+            append_instructions(&mut messages, instructions);
+        }
+
+        attach_request_metadata(&mut metadata, data);
+        Ok(RenderedPrompt {
+            render_info: self.render_info(source, &parsed),
+            metadata,
+            messages,
+        })
+    }
+
+    /// Renders a prompt template and records which template regions produced
+    /// each part of the output.
+    ///
+    /// The rendered prompt is identical to the one returned by
+    /// [`render`](Self::render); see [`crate::trace`] for what is recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if rendering fails.
+    pub fn render_traced<V, M>(
+        &self,
+        source: impl AsRef<str>,
+        data: &DataArgument<V>,
+        options: Option<PromptMetadata<M>>,
+    ) -> Result<TracedPrompt<M>>
+    where
+        V: serde::Serialize + Default + Clone,
+        M: serde::de::DeserializeOwned + Default + Clone,
+    {
+        let source = source.as_ref();
+        self.observe_render(
+            source,
+            |traced: &TracedPrompt<M>| &traced.rendered.messages,
+            || self.render_instrumented(source, data, options),
+        )
+    }
+
+    /// Renders a prompt template with tracing, without notifying the
+    /// observer.
+    fn render_instrumented<V, M>(
+        &self,
+        source: &str,
+        data: &DataArgument<V>,
+        options: Option<PromptMetadata<M>>,
+    ) -> Result<TracedPrompt<M>>
+    where
+        V: serde::Serialize + Default + Clone,
+        M: serde::de::DeserializeOwned + Default + Clone,
+    {
+        let parsed: ParsedPrompt<M> = self.parse(source)?;
+        // The body is the untrimmed tail of the source, minus surrounding
+        // whitespace when there is frontmatter.
+        let offset = source
+            .trim_end()
+            .len()
+            .saturating_sub(parsed.template.trim_end().len());
+        let mut metadata =
+            self.resolve_parsed_metadata(source, parsed.metadata.clone(), options)?;
+        let instrumented = Instrumented::new(&parsed.template, offset);
+        let rendered_string = self.render_template(&instrumented.template, data, &metadata)?;
+
+        let mut data = data.clone();
+        if let Some(history) = &mut data.messages {
+            flag_history(history);
+        }
+        let mut messages = to_messages(&rendered_string, Some(&data));
+        if self.section_metadata {
+            apply_sections(&mut messages);
+        }
+        let mut parts = instrumented.extract(&mut messages);
+        if self.normalize_messages {
+            let moves;
+            (messages, moves) = normalize_tracked(messages);
+            parts = follow_moves(parts, &moves);
+        }
+        if let Some(instructions) = self.output_instructions(&metadata)? {
+            append_instructions(&mut messages, instructions);
+        }
+
+        attach_request_metadata(&mut metadata, &data);
+        Ok(TracedPrompt {
+            rendered: RenderedPrompt {
+                render_info: self.render_info(source, &parsed),
+                metadata,
+                messages,
+            },
+            parts,
+        })
+    }
+
+    /// Parses a model response to a prompt with its output config.
+    ///
+    /// The response is parsed with `output.format`, or as `json` if the
+    /// prompt has an output schema and `text` if not. Strings that differ
+    /// from an `enum` value of the schema only in case or surrounding
+    /// whitespace are replaced by that value, and the output is then checked
+    /// against the schema; see [`crate::response`].
+    ///
+    /// `metadata` should be resolved, as the metadata of a
+    /// [`RenderedPrompt`] or from [`render_metadata`](Self::render_metadata)
+    /// is, so that its output schema is JSON Schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::InvalidResponse`] if the response is not in
+    /// the output format or does not match the schema, and
+    /// [`DotpromptError::OutputFormatError`] if the format is unknown.
+    pub fn parse_response<M>(
+        &self,
+        metadata: &PromptMetadata<M>,
+        text: &str,
+    ) -> Result<ParsedResponse> {
+        let output = metadata.output.as_ref();
+        let schema = output.and_then(|output| output.schema.as_ref());
+        let name = output
+            .and_then(|output| output.format.clone())
+            .unwrap_or_else(|| if schema.is_some() { "json" } else { "text" }.to_string());
+        let format = self
+            .format(&name)
+            .ok_or_else(|| DotpromptError::OutputFormatError {
+                format: name.clone(),
+                reason: "unknown format".to_string(),
+            })?;
+
+        let mut value = format.parse(text, schema).map_err(|err| match err {
+            DotpromptError::OutputFormatError { format, reason } => {
+                ResponseError::Unparsable { format, reason }.into()
+            }
+            other => other,
+        })?;
+        // Text is not structured, whatever the schema says.
+        if let (Some(schema), false) = (schema, name == "text") {
+            response::coerce_enums(schema, &mut value);
+            let violations = response::validate(schema, &value);
+            if !violations.is_empty() {
+                return Err(ResponseError::SchemaMismatch {
+                    output: value,
+                    violations,
+                }
+                .into());
+            }
+        }
+        Ok(ParsedResponse {
+            format: name,
+            output: value,
+        })
+    }
+
+    /// Runs a render, telling the observer when it starts and ends.
+    fn observe_render<T>(
+        &self,
+        source: &str,
+        messages: impl FnOnce(&T) -> &[Message],
+        render: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let Some(observer) = &self.observer else {
+            return render();
+        };
+        observer.on_render_start(source.len());
+        let started = Instant::now();
+        let result = render();
+        let duration = started.elapsed();
+        observer.on_render_end(&RenderEnd::new(
+            source.len(),
+            duration,
+            result.as_ref().map(messages),
+        ));
+        result
+    }
+
+    /// Describes the revision of a prompt being rendered, if enabled.
+    fn render_info<M>(&self, source: &str, parsed: &ParsedPrompt<M>) -> Option<RenderInfo> {
+        if !self.render_info {
+            return None;
+        }
+
+        // Version every partial reachable from the template.
+        let registry = self.registry();
+        let mut partial_versions = BTreeMap::new();
+        let mut pending: Vec<String> = self
+            .identify_partials(&parsed.template)
+            .into_iter()
+            .collect();
+        while let Some(name) = pending.pop() {
+            if partial_versions.contains_key(&name) {
+                continue;
+            }
+            if let Some(partial) = registry.partial_sources.get(&name) {
+                pending.extend(self.identify_partials(partial));
+                partial_versions.insert(name, calculate_version(partial));
+            }
+        }
+
+        Some(RenderInfo {
+            template_version: calculate_version(source),
+            partial_versions,
+            library_version: env!("CARGO_PKG_VERSION").to_string(),
+            model: parsed
+                .metadata
+                .model
+                .clone()
+                .or_else(|| self.default_model.clone()),
+            rendered_at: format_timestamp(SystemTime::now()),
+        })
+    }
+
+    /// Returns how a render of a prompt with `metadata` treats `null` and
+    /// missing values: as the prompt (or the render's options) say, or as
+    /// configured for the instance.
+    fn missing_policy<M>(&self, metadata: &PromptMetadata<M>) -> MissingPolicy {
+        metadata
+            .render
+            .as_ref()
+            .and_then(|render| render.on_missing)
+            .unwrap_or(self.on_missing)
+    }
+
+    /// Returns the instructions the prompt's output format adds to it, if any.
+    fn output_instructions<M>(&self, metadata: &PromptMetadata<M>) -> Result<Option<String>> {
+        let Some(output) = &metadata.output else {
+            return Ok(None);
+        };
+        output
+            .format
+            .as_deref()
+            .and_then(|name| self.format(name))
+            .map_or(Ok(None), |format| {
+                format.instructions(output.schema.as_ref())
+            })
+    }
+
+    /// Renders the few-shot examples a prompt names, if any.
+    fn render_examples<M>(
+        &self,
+        metadata: &PromptMetadata<M>,
+        input: &serde_json::Value,
+    ) -> Result<Option<String>> {
+        let Some(config) = &metadata.examples else {
+            return Ok(None);
+        };
+        let registered = self.registry().examples.get(&config.source).cloned();
+        let examples = match (registered, &self.example_resolver) {
+            (Some(examples), _) => examples,
+            (None, Some(resolver)) => resolver.resolve(&config.source)?.ok_or_else(|| {
+                DotpromptError::ExampleResolutionError(format!("{}: not found", config.source))
+            })?,
+            (None, None) => {
+                return Err(DotpromptError::ExampleResolutionError(format!(
+                    "{}: not found",
+                    config.source
+                )));
+            }
+        };
+
+        let template = config
+            .template
+            .as_deref()
+            .unwrap_or(examples::DEFAULT_TEMPLATE);
+        // Selectors may call back into this instance, so the registry is
+        // only locked once they are done.
+        let selected = self.example_selector.select(input, examples, config)?;
+        let registry = self.registry();
+        let rendered = selected
+            .iter()
+            .map(|example| {
+                registry
+                    .handlebars
+                    .render_template(template, example)
+                    .map_err(|e| DotpromptError::from_render_error(&e, template))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(
+            rendered.join(
+                config
+                    .separator
+                    .as_deref()
+                    .unwrap_or(examples::DEFAULT_SEPARATOR),
+            ),
+        ))
+    }
 
+    /// Renders a template body to a string, filling in input fields missing
+    /// from `data` from the prompt's defaults and its few-shot examples.
+    fn render_template<V, M>(
+        &self,
+        template: &str,
+        data: &DataArgument<V>,
+        metadata: &PromptMetadata<M>,
+    ) -> Result<String>
+    where
+        V: serde::Serialize,
+    {
+        let defaults = metadata.input.as_ref().and_then(|i| i.default.as_ref());
+        let on_missing = self.missing_policy(metadata);
         // Build render context from input
         let mut render_context = data.input.as_ref().map_or_else(
             || serde_json::Value::Object(serde_json::Map::new()),
@@ -340,47 +1054,64 @@ impl Dotprompt {
                     .unwrap_or_else(|_| serde_json::Value::Object(serde_json::Map::new()))
             },
         );
+        if let (serde_json::Value::Object(map), Some(defaults)) = (&mut render_context, defaults) {
+            let mut defaults: Vec<_> = defaults.iter().collect();
+            defaults.sort_unstable_by_key(|&(key, _)| key);
+            for (key, value) in defaults {
+                map.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+        let examples = self.render_examples(metadata, &render_context)?;
+        if let (serde_json::Value::Object(map), Some(examples)) = (&mut render_context, examples) {
+            map.insert("examples".to_string(), serde_json::Value::String(examples));
+        }
 
-        // Add all context variables as @-prefixed variables
-        // Each key in context becomes accessible as @key in templates
-        // e.g., context: {state: {...}, auth: {...}} creates @state and @auth
-        let mut template_to_render = parsed.template.clone();
+        // Each context entry is exposed as an @-prefixed variable, e.g.
+        // context: {state: {...}, auth: {...}} creates @state and @auth
         if let (serde_json::Value::Object(map), Some(context)) =
             (&mut render_context, &data.context)
         {
             for (key, value) in context {
-                // Use __ctx_{key} as workaround since Handlebars treats @ as private data prefix
-                let internal_key = format!("__ctx_{key}");
-                map.insert(internal_key.clone(), value.clone());
-
-                // Replace all occurrences of @{key} with __ctx_{key} in template
-                template_to_render = template_to_render
-                    .replace(&format!("{{{{@{key}."), &format!("{{{{__{key}."))
-                    .replace(&format!("{{{{ @{key}."), &format!("{{{{ __{key}."));
-
-                // Also handle shortcut for just the key (e.g., {{@isAdmin}})
-                template_to_render = template_to_render
-                    .replace(&format!("{{{{@{key}}}}}"), &format!("{{{{__{key}}}}}"))
-                    .replace(&format!("{{{{ @{key} }}}}"), &format!("{{{{ __{key} }}}}"));
-
-                // Insert with __ prefix for template access
-                map.insert(format!("__{key}"), value.clone());
+                map.insert(format!("{CONTEXT_PREFIX}{key}"), value.clone());
             }
         }
 
-        // Render template
-        let rendered_string = self
-            .handlebars
-            .render_template(&template_to_render, &render_context)
-            .map_err(|e| DotpromptError::RenderError(e.to_string()))?;
+        if on_missing != MissingPolicy::Empty {
+            // Null fields are treated as missing, so that they reach the
+            // policy instead of rendering as nothing.
+            remove_null_fields(&mut render_context);
+            if let serde_json::Value::Object(map) = &mut render_context {
+                map.insert(
+                    MISSING_POLICY_KEY.to_string(),
+                    serde_json::to_value(on_missing)?,
+                );
+            }
+        }
+        if self.key_order == KeyOrder::Sorted {
+            render_context.sort_all_objects();
+        }
+        number::apply(&self.number_format, &mut render_context);
 
-        // Convert to messages (passing data for history)
-        let messages = to_messages(&rendered_string, Some(data));
+        let registry = self.registry();
+        self.limits
+            .check_partials(template, &registry.partial_sources)?;
+        let template_to_render = self
+            .limits
+            .prepare(expose_context_variables(&escape_namespaced_partials(template)).into_owned());
 
-        Ok(RenderedPrompt {
-            metadata: parsed.metadata,
-            messages,
-        })
+        // Render template
+        let mut output = LimitedOutput::new(self.limits.max_output_bytes);
+        match registry.handlebars.render_template_to_write(
+            &template_to_render,
+            &render_context,
+            &mut output,
+        ) {
+            Ok(()) => Ok(output.into_string()),
+            Err(e) => Err(self
+                .limits
+                .exceeded(&output)
+                .unwrap_or_else(|| DotpromptError::from_render_error(&e, template))),
+        }
     }
 
     /// Registers a schema definition.
@@ -393,8 +1124,8 @@ impl Dotprompt {
     /// # Returns
     ///
     /// Returns a mutable reference to self for chaining.
-    pub fn define_schema(&mut self, name: impl Into<String>, schema: JsonSchema) -> &mut Self {
-        self.schemas.insert(name.into(), schema);
+    pub fn define_schema(&self, name: impl Into<String>, schema: JsonSchema) -> &Self {
+        self.registry_mut().schemas.insert(name.into(), schema);
         self
     }
 
@@ -424,8 +1155,127 @@ impl Dotprompt {
         Ok(PromptFunction { prompt })
     }
 
+    /// Parses and compiles every prompt and partial in a store.
+    ///
+    /// Use this at startup to fail fast on broken prompts instead of finding
+    /// them when a request renders one. Each prompt's frontmatter is parsed,
+    /// its tools and schemas are resolved, its partials are resolved, and its
+    /// template is compiled. The store's partials (other than variants) are
+    /// registered along the way, so later renders do not load them again.
+    ///
+    /// Problems with individual prompts and partials are collected in the
+    /// report rather than returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be listed.
+    pub fn precompile_store<S: PromptStore + ?Sized>(&self, store: &S) -> Result<PrecompileReport> {
+        let mut report = PrecompileReport::default();
+
+        // Partials first, so the prompts below find them registered.
+        let mut cursor = None;
+        loop {
+            let page = store.list_partials(Some(ListPartialsOptions {
+                cursor,
+                ..Default::default()
+            }))?;
+            for partial_ref in page.partials {
+                let options = LoadPartialOptions {
+                    variant: partial_ref.variant.clone(),
+                    version: None,
+                };
+                let compiled = store
+                    .load_partial(&partial_ref.name, Some(options))
+                    .and_then(|data| {
+                        if partial_ref.variant.is_some() {
+                            compile_template(&data.source).map_err(|e| DotpromptError::BadPartial {
+                                name: partial_ref.name.clone(),
+                                reason: e.to_string(),
+                            })
+                        } else {
+                            self.define_partial(partial_ref.name.as_str(), &data.source)
+                                .map(|_| ())
+                        }
+                    });
+                record(
+                    &mut report,
+                    partial_ref.name,
+                    partial_ref.variant,
+                    true,
+                    compiled,
+                );
+            }
+            match page.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let mut cursor = None;
+        loop {
+            let page = store.list(Some(ListPromptsOptions {
+                cursor,
+                ..Default::default()
+            }))?;
+            for prompt_ref in page.prompts {
+                let options = LoadPromptOptions {
+                    variant: prompt_ref.variant.clone(),
+                    version: None,
+                };
+                let compiled = store
+                    .load(&prompt_ref.name, Some(options))
+                    .and_then(|data| self.precompile(&data.source));
+                record(
+                    &mut report,
+                    prompt_ref.name,
+                    prompt_ref.variant,
+                    false,
+                    compiled,
+                );
+            }
+            match page.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        Ok(report)
+    }
+
+    /// Does everything rendering `source` does short of running the
+    /// template.
+    fn precompile(&self, source: &str) -> Result<()> {
+        let parsed: ParsedPrompt = self.parse(source)?;
+        self.resolve_parsed_metadata(source, parsed.metadata, None)?;
+        self.resolve_partials(&parsed.template)?;
+        compile_template(&parsed.template)?;
+
+        let registry = self.registry();
+        let missing = self
+            .identify_partials(&parsed.template)
+            .into_iter()
+            .find(|name| {
+                registry
+                    .handlebars
+                    .get_template(&partial_key(name))
+                    .is_none()
+            });
+        drop(registry);
+        missing.map_or(Ok(()), |name| {
+            Err(DotpromptError::BadPartial {
+                name,
+                reason: "not found".to_string(),
+            })
+        })
+    }
+
     /// Processes and resolves all metadata for a prompt template.
     ///
+    /// This is the metadata [`render`](Self::render) returns: the model
+    /// defaults to [`DotpromptOptions::default_model`], `config` starts from
+    /// the model's entry in [`DotpromptOptions::model_configs`], tool names
+    /// are resolved to definitions, and picoschema is expanded to JSON
+    /// Schema.
+    ///
     /// # Arguments
     ///
     /// * `source` - The template source
@@ -437,7 +1287,8 @@ impl Dotprompt {
     ///
     /// # Errors
     ///
-    /// Returns error if parsing fails.
+    /// Returns error if parsing fails, a tool or schema cannot be resolved,
+    /// or a schema is not valid picoschema.
     pub fn render_metadata<M>(
         &self,
         source: impl AsRef<str>,
@@ -446,8 +1297,44 @@ impl Dotprompt {
     where
         M: serde::de::DeserializeOwned + Default + Clone,
     {
-        let parsed: ParsedPrompt<M> = self.parse(source.as_ref())?;
-        self.resolve_metadata(parsed.metadata, additional_metadata)
+        let source = source.as_ref();
+        let parsed: ParsedPrompt<M> = self.parse(source)?;
+        self.resolve_parsed_metadata(source, parsed.metadata, additional_metadata)
+    }
+
+    /// Resolves a prompt's parsed metadata, starting from the configuration
+    /// registered for its model in [`DotpromptOptions::model_configs`].
+    ///
+    /// The prompt's own `config` fields override the model's.
+    fn resolve_parsed_metadata<M>(
+        &self,
+        source: &str,
+        mut metadata: PromptMetadata<M>,
+        additional: Option<PromptMetadata<M>>,
+    ) -> Result<PromptMetadata<M>>
+    where
+        M: serde::de::DeserializeOwned + Default + Clone,
+    {
+        let model = additional
+            .as_ref()
+            .and_then(|extra| extra.model.as_ref())
+            .or(metadata.model.as_ref())
+            .or(self.default_model.as_ref());
+        if let Some(serde_json::Value::Object(defaults)) =
+            model.and_then(|model| self.model_configs.get(model))
+        {
+            let mut config = defaults.clone();
+            // `M` need not be serializable, so take the prompt's own config
+            // from its frontmatter.
+            let (yaml, _) = extract_frontmatter_and_body(source)?;
+            if let serde_json::Value::Object(own) =
+                serde_json::to_value(parse_frontmatter_value(&yaml)?)?["config"].take()
+            {
+                config.extend(own);
+            }
+            metadata.config = Some(serde_json::from_value(serde_json::Value::Object(config))?);
+        }
+        self.resolve_metadata(metadata, additional)
     }
 
     /// Merges multiple metadata objects together, resolving tools and schemas.
@@ -489,6 +1376,12 @@ impl Dotprompt {
             if extra.output.is_some() {
                 base.output = extra.output;
             }
+            if extra.render.is_some() {
+                base.render = extra.render;
+            }
+            if extra.examples.is_some() {
+                base.examples = extra.examples;
+            }
         }
 
         // Apply default model if none specified
@@ -496,10 +1389,9 @@ impl Dotprompt {
             base.model.clone_from(&self.default_model);
         }
 
-        // Resolve tool references
-        base = self.resolve_tools(base);
-
-        Ok(base)
+        // Resolve tool references and expand schemas
+        base = self.resolve_tools(base)?;
+        self.render_picoschema(base)
     }
 
     /// Resolves tool names to their definitions.
@@ -511,19 +1403,26 @@ impl Dotprompt {
     /// # Returns
     ///
     /// Returns metadata with resolved tool definitions.
-    pub fn resolve_tools<M>(&self, mut meta: PromptMetadata<M>) -> PromptMetadata<M> {
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::ToolResolutionError`] if the tool resolver
+    /// fails. Tools it does not know are left unresolved.
+    pub fn resolve_tools<M>(&self, mut meta: PromptMetadata<M>) -> Result<PromptMetadata<M>> {
         if let Some(tool_names) = &meta.tools {
             let mut resolved_defs = meta.tool_defs.take().unwrap_or_default();
 
             for name in tool_names {
                 // Check registered tools first
-                if let Some(def) = self.tools.get(name) {
-                    resolved_defs.push(def.clone());
+                let registered = self.registry().tools.get(name).cloned();
+                if let Some(def) = registered {
+                    resolved_defs.push(def);
                 } else if let Some(resolver) = &self.tool_resolver {
                     // Try resolver
-                    if let Some(def) = resolver.resolve(name) {
-                        resolved_defs.push(def);
-                    }
+                    let def = resolver
+                        .resolve(name)
+                        .map_err(|e| DotpromptError::ToolResolutionError(format!("{name}: {e}")))?;
+                    resolved_defs.extend(def);
                 }
             }
 
@@ -531,7 +1430,7 @@ impl Dotprompt {
                 meta.tool_defs = Some(resolved_defs);
             }
         }
-        meta
+        Ok(meta)
     }
 
     /// Identifies all partial references in a template.
@@ -542,23 +1441,19 @@ impl Dotprompt {
     ///
     /// # Returns
     ///
-    /// Returns a set of partial names referenced in the template.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal regex pattern fails to compile (should never happen).
+    /// Returns a set of partial names referenced in the template, excluding
+    /// partials the template defines inline.
     #[must_use]
-    #[allow(clippy::expect_used)]
     pub fn identify_partials(&self, template: &str) -> std::collections::HashSet<String> {
         let mut partials = std::collections::HashSet::new();
-        // Simple regex-based partial detection: {{> partialName}}
-        let re = regex::Regex::new(r"\{\{>\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\}\}")
-            .expect("internal regex pattern should compile");
-        for cap in re.captures_iter(template) {
+        for cap in PARTIAL_INCLUDE.captures_iter(template) {
             if let Some(name) = cap.get(1) {
                 partials.insert(name.as_str().to_string());
             }
         }
+        for name in identify_inline_partials(template) {
+            partials.remove(&name);
+        }
         partials
     }
 
@@ -575,7 +1470,7 @@ impl Dotprompt {
     /// # Errors
     ///
     /// Returns error if a partial cannot be resolved.
-    pub fn resolve_partials(&mut self, template: &str) -> Result<()> {
+    pub fn resolve_partials(&self, template: &str) -> Result<()> {
         let mut visited = std::collections::HashSet::new();
         self.resolve_partials_recursive(template, &mut visited)
     }
@@ -591,7 +1486,7 @@ impl Dotprompt {
     ///
     /// Returns error if a partial cannot be resolved or compiled.
     fn resolve_partials_recursive(
-        &mut self,
+        &self,
         template: &str,
         visited: &mut std::collections::HashSet<String>,
     ) -> Result<()> {
@@ -599,7 +1494,12 @@ impl Dotprompt {
 
         for name in partial_names {
             // Skip if already registered
-            if self.handlebars.get_template(&name).is_some() {
+            let registered = self
+                .registry()
+                .handlebars
+                .get_template(&partial_key(&name))
+                .is_some();
+            if registered {
                 continue;
             }
 
@@ -611,26 +1511,64 @@ impl Dotprompt {
             // Mark as being processed
             visited.insert(name.clone());
 
-            // Try resolver
-            #[allow(clippy::collapsible_if)]
-            if let Some(resolver) = &self.partial_resolver {
-                if let Some(source) = resolver.resolve(&name) {
-                    self.handlebars
-                        .register_template_string(&name, source.clone())
-                        .map_err(|e| DotpromptError::CompilationError(e.to_string()))?;
-
-                    // Recursively resolve partials in the resolved content
-                    self.resolve_partials_recursive(&source, visited)?;
+            let started = Instant::now();
+            if let Some(source) = self.lookup_partial(&name)? {
+                if let Some(observer) = &self.observer {
+                    observer.on_partial_resolved(&PartialResolved {
+                        name: &name,
+                        source_bytes: source.len(),
+                        duration: started.elapsed(),
+                    });
                 }
+                register_partial(
+                    &mut self.registry_mut().handlebars,
+                    &self.limits,
+                    &name,
+                    &source,
+                )?;
+
+                // Recursively resolve partials in the resolved content
+                self.resolve_partials_recursive(&source, visited)?;
+                self.registry_mut().partial_sources.insert(name, source);
             }
         }
         Ok(())
     }
 
+    /// Looks up the source of a partial that is not registered, following
+    /// the precedence described on [`Dotprompt`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::PartialResolutionError`] if a resolver
+    /// fails. Store errors are treated as the partial being absent.
+    fn lookup_partial(&self, name: &str) -> Result<Option<String>> {
+        let failed =
+            |e: DotpromptError| DotpromptError::PartialResolutionError(format!("{name}: {e}"));
+        if let Some((namespace, local)) = name.split_once(':') {
+            // Resolvers may call back into this instance, so the registry is
+            // not locked while they run.
+            let resolver = self.registry().partial_namespaces.get(namespace).cloned();
+            if let Some(resolver) = resolver {
+                return resolver.resolve(local).map_err(failed);
+            }
+        }
+        let resolved = match &self.partial_resolver {
+            Some(resolver) => resolver.resolve(name).map_err(failed)?,
+            None => None,
+        };
+        Ok(resolved.or_else(|| {
+            let store = self.partial_store.as_ref()?;
+            store.load_partial(name, None).ok().map(|data| data.source)
+        }))
+    }
+
     /// Processes schema definitions in picoschema format into standard JSON Schema.
     ///
     /// This resolves any compact picoschema syntax in the input/output schemas
-    /// to their full JSON Schema equivalents.
+    /// to their full JSON Schema equivalents. Schema names used as picoschema
+    /// types and `$ref: "#/schemas/Name"` references are looked up in the
+    /// registered schemas, then with the schema resolver.
     ///
     /// # Arguments
     ///
@@ -642,45 +1580,198 @@ impl Dotprompt {
     ///
     /// # Errors
     ///
-    /// Returns error if picoschema conversion fails.
+    /// Returns error if picoschema conversion fails or a referenced schema
+    /// cannot be resolved.
     pub fn render_picoschema<M>(&self, mut meta: PromptMetadata<M>) -> Result<PromptMetadata<M>>
     where
         M: Default + Clone,
     {
-        use crate::picoschema::picoschema_to_json_schema;
+        let lookup = |name: &str| self.lookup_schema(name);
+        let expand = |schema: &serde_json::Value| {
+            let mut converted = picoschema_to_json_schema_with(schema, &lookup)?;
+            resolve_schema_refs(&mut converted, &lookup)?;
+            Ok::<_, DotpromptError>(converted)
+        };
 
         // Process input schema if present
-        #[allow(clippy::collapsible_if)]
-        if let Some(ref mut input) = meta.input {
-            if let Some(ref schema) = input.schema {
-                let converted = picoschema_to_json_schema(schema)?;
-                input.schema = Some(converted);
-            }
+        if let Some(input) = meta.input.as_mut() {
+            input.schema = input.schema.as_ref().map(expand).transpose()?;
         }
 
         // Process output schema if present
-        #[allow(clippy::collapsible_if)]
-        if let Some(ref mut output) = meta.output {
-            if let Some(ref schema) = output.schema {
-                let converted = picoschema_to_json_schema(schema)?;
-                output.schema = Some(converted);
-            }
+        if let Some(output) = meta.output.as_mut() {
+            output.schema = output.schema.as_ref().map(expand).transpose()?;
         }
 
         Ok(meta)
     }
+
+    /// Looks up a named schema: registered schemas first, then the schema
+    /// resolver.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::SchemaResolutionError`] if the resolver fails.
+    fn lookup_schema(&self, name: &str) -> Result<Option<JsonSchema>> {
+        if let Some(schema) = self.registry().schemas.get(name) {
+            return Ok(Some(schema.clone()));
+        }
+        self.schema_resolver.as_ref().map_or(Ok(None), |resolver| {
+            resolver
+                .resolve(name)
+                .map_err(|e| DotpromptError::SchemaResolutionError(format!("{name}: {e}")))
+        })
+    }
+}
+
+/// Copies the caller's request metadata into the rendered prompt's
+/// `metadata` map under [`REQUEST_METADATA_KEY`].
+fn attach_request_metadata<V, M>(metadata: &mut PromptMetadata<M>, data: &DataArgument<V>) {
+    if let Some(request_metadata) = &data.request_metadata {
+        metadata.metadata.get_or_insert_default().insert(
+            REQUEST_METADATA_KEY.to_string(),
+            serde_json::Value::Object(request_metadata.clone()),
+        );
+    }
+}
+
+/// Returns the name a partial is registered under with Handlebars.
+///
+/// Handlebars does not accept `:` in partial names, so namespaced partials
+/// are registered, and referenced, in bracketed form.
+fn partial_key(name: &str) -> Cow<'_, str> {
+    if name.contains(':') {
+        Cow::Owned(format!("[{name}]"))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Rewrites namespaced partial references in a template to their
+/// [`partial_key`].
+///
+/// Handlebars does not accept a bracketed name where a partial block ends,
+/// so `{{/lib:layout}}` becomes `{{/}}`, which closes the innermost block.
+fn escape_namespaced_partials(template: &str) -> Cow<'_, str> {
+    NAMESPACED_PARTIAL.replace_all(template, |caps: &regex::Captures<'_>| {
+        if &caps[2] == "/" {
+            format!("{}/", &caps[1])
+        } else {
+            format!("{}{}{}[{}]", &caps[1], &caps[2], &caps[3], &caps[4])
+        }
+    })
+}
+
+/// Rewrites context variables such as `@auth.uid` to lookups of the render
+/// data's root, where [`Dotprompt::render`] stores the context.
+///
+/// Handlebars only resolves the first segment of a data variable, and only
+/// in the block that set it, so `@auth.uid` is rewritten to
+/// `@root.__ctx_auth.uid`. Variables Handlebars provides itself, such as
+/// `@index`, are left alone, as are string literals, comments, and escaped
+/// expressions.
+fn expose_context_variables(template: &str) -> Cow<'_, str> {
+    EXPRESSION.replace_all(template, |caps: &regex::Captures<'_>| {
+        let expression = &caps[0];
+        if caps.get(1).is_some() || caps.get(2).is_some() {
+            return expression.to_string();
+        }
+        DATA_VARIABLE
+            .replace_all(expression, |caps: &regex::Captures<'_>| {
+                match (caps.get(1), caps.get(2)) {
+                    (Some(before), Some(name))
+                        if !BUILTIN_DATA_VARIABLES.contains(&name.as_str()) =>
+                    {
+                        format!("{}@root.{CONTEXT_PREFIX}{}", before.as_str(), name.as_str())
+                    }
+                    _ => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    })
+}
+
+/// Removes the fields whose value is `null` from the objects in `value`.
+fn remove_null_fields(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, field| !field.is_null());
+            map.values_mut().for_each(remove_null_fields);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(remove_null_fields),
+        _ => {}
+    }
+}
+
+/// Compiles a template body the way rendering does, without registering it.
+fn compile_template(template: &str) -> Result<()> {
+    let escaped = escape_namespaced_partials(template);
+    handlebars::Template::compile(&expose_context_variables(&escaped))
+        .map(|_| ())
+        .map_err(|e| DotpromptError::from_template_error(&e, template))
+}
+
+/// Adds the outcome of compiling a prompt or partial to a report.
+fn record(
+    report: &mut PrecompileReport,
+    name: String,
+    variant: Option<String>,
+    partial: bool,
+    compiled: Result<()>,
+) {
+    match compiled {
+        Ok(()) => {
+            let label = match &variant {
+                Some(variant) => format!("{name}.{variant}"),
+                None => name,
+            };
+            if partial {
+                report.partials.push(label);
+            } else {
+                report.prompts.push(label);
+            }
+        }
+        Err(error) => report.failures.push(PrecompileFailure {
+            name,
+            variant,
+            partial,
+            error,
+        }),
+    }
+}
+
+/// Compiles a partial and registers it with Handlebars.
+fn register_partial(
+    handlebars: &mut Handlebars<'static>,
+    limits: &RenderLimits,
+    name: &str,
+    source: &str,
+) -> Result<()> {
+    let source = escape_namespaced_partials(source);
+    let compiled = limits.prepare_partial(expose_context_variables(&source).into_owned());
+    handlebars
+        .register_template_string(&partial_key(name), compiled)
+        .map_err(|e| DotpromptError::BadPartial {
+            name: name.to_string(),
+            reason: DotpromptError::from_template_error(&e, &source).to_string(),
+        })
 }
 
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
     use super::*;
+    use crate::response::SchemaViolation;
+    use crate::trace::SegmentKind;
+    use crate::types::{
+        ExamplesConfig, Message, Part, PromptInputConfig, PromptOutputConfig, Role, TextPart,
+    };
     use serde_json::json;
 
     #[test]
     fn test_new_dotprompt() {
         let dp = Dotprompt::new(None);
-        assert!(dp.tools.is_empty());
+        assert!(dp.registry().tools.is_empty());
     }
 
     #[test]
@@ -706,9 +1797,410 @@ mod tests {
         assert_eq!(rendered.messages.len(), 1);
     }
 
+    #[test]
+    fn test_render_traced() {
+        let dp = Dotprompt::new(None);
+        let source = "---\nmodel: m\n---\n{{role \"system\"}}Be brief.\n{{#if formal}}\nDear {{name}},\n{{/if}}";
+        let data = DataArgument {
+            input: Some(json!({"name": "Ada", "formal": true})),
+            messages: Some(vec![Message {
+                role: Role::Model,
+                content: vec![Part::Text(TextPart {
+                    text: "Earlier".to_string(),
+                    metadata: None,
+                })],
+                metadata: None,
+            }]),
+            ..Default::default()
+        };
+
+        let traced: TracedPrompt = dp
+            .render_traced(source, &data, None)
+            .expect("traced render should succeed");
+        let rendered: RenderedPrompt = dp
+            .render(source, &data, None)
+            .expect("render should succeed");
+        assert_eq!(
+            serde_json::to_value(&traced.rendered.messages).expect("serialize"),
+            serde_json::to_value(&rendered.messages).expect("serialize")
+        );
+
+        // The history message is not traced.
+        assert_eq!(traced.parts.len(), 1);
+        let segments = &traced.parts[0].segments;
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["Be brief.", "\n", "Dear ", "Ada", ",", "\n"]);
+
+        let name = &segments[3];
+        assert_eq!(name.kind, SegmentKind::Expression);
+        assert_eq!(&source[name.span.clone()], "{{name}}");
+        assert_eq!(name.blocks, ["{{#if formal}}"]);
+        assert_eq!(&source[segments[0].span.clone()], "Be brief.");
+    }
+
+    #[test]
+    fn test_render_info() {
+        let source = "---\nmodel: m\n---\n{{> header}}Hi";
+        let data: DataArgument = DataArgument::default();
+
+        let plain = Dotprompt::new(None);
+        let rendered: RenderedPrompt = plain.render("Hi", &data, None).expect("render");
+        assert!(rendered.render_info.is_none());
+
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            render_info: true,
+            ..Default::default()
+        }));
+        dp.define_partial("header", "{{> logo}}: ")
+            .expect("partial");
+        dp.define_partial("logo", "ACME").expect("partial");
+        dp.define_partial("unused", "x").expect("partial");
+        let rendered: RenderedPrompt = dp.render(source, &data, None).expect("render");
+        let info = rendered.render_info.expect("render info");
+
+        assert_eq!(info.template_version, calculate_version(source));
+        assert_eq!(
+            info.partial_versions.keys().collect::<Vec<_>>(),
+            ["header", "logo"]
+        );
+        assert_eq!(info.partial_versions["logo"], calculate_version("ACME"));
+        assert_eq!(info.library_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.model.as_deref(), Some("m"));
+        assert!(info.rendered_at.ends_with('Z'));
+    }
+
+    #[test]
+    fn test_section_metadata() {
+        let source = "{{section \"rules\"}}Be brief.{{section \"examples\"}}Q: hi";
+        let data: DataArgument = DataArgument::default();
+
+        let rendered: RenderedPrompt = Dotprompt::new(None)
+            .render(source, &data, None)
+            .expect("render");
+        assert!(matches!(rendered.messages[0].content[0], Part::Pending(_)));
+
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            section_metadata: true,
+            ..Default::default()
+        }));
+        let rendered: RenderedPrompt = dp.render(source, &data, None).expect("render");
+        let sections: Vec<(&str, &serde_json::Value)> = rendered.messages[0]
+            .content
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some((
+                    text.text.as_str(),
+                    &text.metadata.as_ref().expect("metadata")["purpose"],
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                ("Be brief.", &serde_json::json!("rules")),
+                ("Q: hi", &serde_json::json!("examples")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_messages() {
+        let source = "{{role \"system\"}}\nBe brief.\n{{role \"user\"}}\n{{role \"model\"}}\n\n{{role \"user\"}}\nHi {{name}}\n{{role \"user\"}}Bye\n";
+        let data: DataArgument = DataArgument {
+            input: Some(json!({"name": "Ada"})),
+            ..Default::default()
+        };
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            normalize_messages: true,
+            ..Default::default()
+        }));
+
+        let rendered: RenderedPrompt = dp.render(source, &data, None).expect("render");
+        let traced: TracedPrompt = dp.render_traced(source, &data, None).expect("trace");
+        assert_eq!(
+            serde_json::to_value(&rendered.messages).expect("json"),
+            json!([
+                {"role": "system", "content": [{"text": "Be brief."}]},
+                {"role": "user", "content": [{"text": "Hi Ada"}, {"text": "Bye"}]},
+            ])
+        );
+        assert_eq!(
+            serde_json::to_value(&traced.rendered.messages).expect("json"),
+            serde_json::to_value(&rendered.messages).expect("json")
+        );
+
+        let segments: Vec<((usize, usize), Vec<&str>)> = traced
+            .parts
+            .iter()
+            .map(|trace| {
+                (
+                    (trace.message, trace.part),
+                    trace.segments.iter().map(|s| s.text.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                ((0, 0), vec!["Be brief."]),
+                ((1, 0), vec!["Hi ", "Ada"]),
+                ((1, 1), vec!["Bye"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_limits() {
+        use crate::limits::Limit;
+
+        struct Rows;
+
+        impl PartialResolver for Rows {
+            fn resolve(&self, _name: &str) -> Result<Option<String>> {
+                Ok(Some("{{#each rows}}{{this}}{{/each}}".to_string()))
+            }
+        }
+
+        let limited = |limits| {
+            let dp = Dotprompt::new(Some(DotpromptOptions {
+                limits,
+                ..Default::default()
+            }));
+            dp.define_partial("outer", "[{{> inner}}]")
+                .expect("partial");
+            dp.define_partial("inner", "{{name}}").expect("partial");
+            dp
+        };
+        let render = |dp: &Dotprompt, source: &str| {
+            let data: DataArgument = DataArgument {
+                input: Some(json!({"name": "Ada", "rows": [[1, 2], [3, 4]]})),
+                ..Default::default()
+            };
+            dp.render::<_, serde_json::Value>(source, &data, None)
+                .map(|rendered| rendered.messages)
+        };
+        let exceeded = |result: Result<_>| match result {
+            Err(DotpromptError::LimitExceeded { limit, max }) => Some((limit, max)),
+            _ => None,
+        };
+
+        let nested = "{{#each rows as |row|}}{{#each row}}{{this}}{{/each}};{{/each}}";
+        let dp = limited(RenderLimits {
+            max_each_iterations: Some(6),
+            ..Default::default()
+        });
+        assert!(render(&dp, nested).is_ok());
+        let dp = limited(RenderLimits {
+            max_each_iterations: Some(5),
+            ..Default::default()
+        });
+        assert_eq!(
+            exceeded(render(&dp, nested)),
+            Some((Limit::EachIterations, 5))
+        );
+
+        let dp = limited(RenderLimits {
+            max_output_bytes: Some(4),
+            ..Default::default()
+        });
+        assert!(render(&dp, "{{name}}!").is_ok());
+        assert_eq!(
+            exceeded(render(&dp, "Hi {{name}}")),
+            Some((Limit::OutputBytes, 4))
+        );
+
+        let dp = limited(RenderLimits {
+            max_partial_depth: Some(1),
+            ..Default::default()
+        });
+        assert!(render(&dp, "{{> inner}}").is_ok());
+        assert_eq!(
+            exceeded(render(&dp, "{{> outer}}")),
+            Some((Limit::PartialDepth, 1))
+        );
+
+        let dp = limited(RenderLimits {
+            max_each_iterations: Some(5),
+            ..Default::default()
+        });
+        dp.define_partial("table", nested).expect("partial");
+        assert_eq!(
+            exceeded(render(&dp, "{{> table}}")),
+            Some((Limit::EachIterations, 5))
+        );
+        assert!(render(&dp, "{{#each rows}}{{/each}}").is_ok());
+
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            limits: RenderLimits {
+                max_each_iterations: Some(1),
+                ..Default::default()
+            },
+            partial_resolver: Some(Box::new(Rows)),
+            ..Default::default()
+        }));
+        dp.resolve_partials("{{> rows}}").expect("resolve");
+        assert_eq!(
+            exceeded(render(&dp, "{{> rows}}")),
+            Some((Limit::EachIterations, 1))
+        );
+
+        let unlimited = limited(RenderLimits::default());
+        assert!(render(&unlimited, nested).is_ok());
+    }
+
+    #[test]
+    fn test_observer() {
+        use crate::observer::RenderObserver;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Debug, Default)]
+        struct Recorder(Mutex<Vec<String>>);
+        impl Recorder {
+            fn record(&self, event: String) {
+                self.0.lock().expect("lock").push(event);
+            }
+        }
+        impl RenderObserver for Recorder {
+            fn on_parse_start(&self, source_bytes: usize) {
+                self.record(format!("parse start {source_bytes}"));
+            }
+            fn on_parse_end(&self, event: &ParseEnd<'_>) {
+                self.record(format!("parse end {}", event.error.is_some()));
+            }
+            fn on_render_start(&self, source_bytes: usize) {
+                self.record(format!("render start {source_bytes}"));
+            }
+            fn on_render_end(&self, event: &RenderEnd<'_>) {
+                self.record(format!(
+                    "render end {} {} {}",
+                    event.messages,
+                    event.output_bytes,
+                    event.error.is_some()
+                ));
+            }
+            fn on_partial_resolved(&self, event: &PartialResolved<'_>) {
+                self.record(format!("partial {} {}", event.name, event.source_bytes));
+            }
+        }
+        struct Partials;
+        impl PartialResolver for Partials {
+            fn resolve(&self, _name: &str) -> Result<Option<String>> {
+                Ok(Some("Hi".to_string()))
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            observer: Some(Box::new(Arc::clone(&recorder))),
+            partial_resolver: Some(Box::new(Partials)),
+            ..Default::default()
+        }));
+        let source = "{{> greeting}} {{name}}";
+        dp.resolve_partials(source).expect("resolve");
+        let data = DataArgument {
+            input: Some(json!({"name": "Ada"})),
+            ..Default::default()
+        };
+        let _: RenderedPrompt = dp.render(source, &data, None).expect("render");
+        let _: TracedPrompt = dp.render_traced(source, &data, None).expect("render");
+        assert!(
+            dp.render::<_, serde_json::Value>("{{#if}}", &data, None)
+                .is_err()
+        );
+
+        let events = recorder.0.lock().expect("lock").clone();
+        let rendered = [
+            "render start 23",
+            "parse start 23",
+            "parse end false",
+            "render end 1 6 false",
+        ];
+        let mut expected = vec!["partial greeting 2"];
+        expected.extend(rendered);
+        expected.extend(rendered);
+        expected.extend([
+            "render start 7",
+            "parse start 7",
+            "parse end false",
+            "render end 0 0 true",
+        ]);
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn test_inline_partials() {
+        struct External;
+        impl crate::types::PartialResolver for External {
+            fn resolve(&self, _name: &str) -> Result<Option<String>> {
+                Ok(Some("external".to_string()))
+            }
+        }
+
+        let template = "{{#*inline \"greet\"}}Hi {{name}}{{/inline}}{{> greet}}!";
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            partial_resolver: Some(Box::new(External)),
+            ..Default::default()
+        }));
+        assert!(dp.identify_partials(template).is_empty());
+        dp.resolve_partials(template)
+            .expect("inline partials need no resolver");
+
+        let data = DataArgument {
+            input: Some(json!({"name": "Ada"})),
+            ..Default::default()
+        };
+        let rendered: RenderedPrompt = dp
+            .render(template, &data, None)
+            .expect("template should render");
+        assert_eq!(
+            rendered.messages[0].content,
+            vec![Part::Text(TextPart {
+                text: "Hi Ada!".to_string(),
+                metadata: None,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_request_metadata_flows_into_rendered_metadata() {
+        let dp = Dotprompt::new(None);
+        let source = "---\nmetadata:\n  owner: search\n---\nHi {{@traceId}}!";
+        let data: DataArgument = DataArgument {
+            request_metadata: Some(
+                json!({"traceId": "abc"})
+                    .as_object()
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+            ..Default::default()
+        };
+        let rendered: RenderedPrompt = dp.render(source, &data, None).expect("should render");
+        let metadata = rendered.metadata.metadata.as_ref().expect("metadata");
+        assert_eq!(metadata["owner"], json!("search"));
+        assert_eq!(metadata[REQUEST_METADATA_KEY], json!({"traceId": "abc"}));
+        assert_eq!(rendered.request_metadata(), data.request_metadata.as_ref());
+        // Request metadata is not exposed to the template.
+        assert_eq!(
+            rendered.messages[0].content,
+            vec![Part::Text(TextPart {
+                text: "Hi !".to_string(),
+                metadata: None,
+            })]
+        );
+
+        let traced: TracedPrompt = dp
+            .render_traced(source, &data, None)
+            .expect("should render");
+        assert_eq!(
+            traced.rendered.request_metadata(),
+            data.request_metadata.as_ref()
+        );
+    }
+
     #[test]
     fn test_define_tool() {
-        let mut dp = Dotprompt::new(None);
+        let dp = Dotprompt::new(None);
         let tool = ToolDefinition {
             name: "test".to_string(),
             description: Some("Test tool".to_string()),
@@ -716,7 +2208,7 @@ mod tests {
             output_schema: None,
         };
         dp.define_tool(tool);
-        assert!(dp.tools.contains_key("test"));
+        assert!(dp.registry().tools.contains_key("test"));
     }
 
     #[test]
@@ -729,18 +2221,18 @@ mod tests {
         }
 
         impl crate::types::PartialResolver for CyclicResolver {
-            fn resolve(&self, name: &str) -> Option<String> {
+            fn resolve(&self, name: &str) -> Result<Option<String>> {
                 *self
                     .counts
                     .lock()
                     .expect("lock should not be poisoned")
                     .get_mut(name)
                     .expect("partial name should exist in counts") += 1;
-                match name {
+                Ok(match name {
                     "partialA" => Some("Content A {{> partialB}}".to_string()),
                     "partialB" => Some("Content B {{> partialA}}".to_string()),
                     _ => None,
-                }
+                })
             }
         }
 
@@ -766,7 +2258,7 @@ mod tests {
             ..Default::default()
         };
 
-        let mut dp = Dotprompt::new(Some(options));
+        let dp = Dotprompt::new(Some(options));
 
         // Template that starts the cycle
         let template = "Start {{> partialA}} End";
@@ -793,4 +2285,844 @@ mod tests {
         );
         drop(counts);
     }
+
+    #[test]
+    fn test_shared_across_threads() {
+        use std::sync::Arc;
+
+        struct GreetingResolver;
+
+        impl PartialResolver for GreetingResolver {
+            fn resolve(&self, name: &str) -> Result<Option<String>> {
+                Ok(Some(format!("{name}, {{{{name}}}}")))
+            }
+        }
+
+        let dp = Arc::new(Dotprompt::new(Some(DotpromptOptions {
+            partial_resolver: Some(Box::new(GreetingResolver)),
+            ..Default::default()
+        })));
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let dp = Arc::clone(&dp);
+                std::thread::spawn(move || {
+                    let source = format!("{{{{> hello{i}}}}}!");
+                    dp.resolve_partials(&source).expect("resolve");
+                    dp.define_tool(ToolDefinition {
+                        name: format!("tool{i}"),
+                        description: None,
+                        input_schema: HashMap::new(),
+                        output_schema: None,
+                    });
+                    let data = DataArgument::<serde_json::Value> {
+                        input: Some(json!({"name": format!("thread {i}")})),
+                        ..Default::default()
+                    };
+                    let rendered: RenderedPrompt = dp.render(&source, &data, None).expect("render");
+                    rendered.messages[0].content[0].clone()
+                })
+            })
+            .collect();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            let part = handle.join().expect("thread");
+            assert_eq!(
+                part,
+                Part::Text(TextPart {
+                    text: format!("hello{i}, thread {i}!"),
+                    metadata: None,
+                })
+            );
+        }
+        assert_eq!(dp.registry().tools.len(), 8);
+    }
+
+    #[test]
+    fn test_each_over_object_key_order() {
+        #[derive(serde::Serialize, Clone, Default)]
+        struct Scores {
+            carol: u8,
+            alice: u8,
+            bob: u8,
+        }
+
+        #[derive(serde::Serialize, Clone, Default)]
+        struct Input {
+            scores: Scores,
+        }
+
+        let render = |key_order| {
+            let dp = Dotprompt::new(Some(DotpromptOptions {
+                key_order,
+                ..Default::default()
+            }));
+            let data = DataArgument {
+                input: Some(Input {
+                    scores: Scores {
+                        carol: 3,
+                        alice: 1,
+                        bob: 2,
+                    },
+                }),
+                ..Default::default()
+            };
+            let rendered: RenderedPrompt = dp
+                .render("{{#each scores}}{{@key}}={{this}};{{/each}}", &data, None)
+                .expect("render");
+            rendered.messages[0].content.clone()
+        };
+        let text = |text: &str| {
+            vec![Part::Text(TextPart {
+                text: text.to_string(),
+                metadata: None,
+            })]
+        };
+
+        assert_eq!(render(KeyOrder::Sorted), text("alice=1;bob=2;carol=3;"));
+        let insertion = if cfg!(feature = "preserve_order") {
+            "carol=3;alice=1;bob=2;"
+        } else {
+            "alice=1;bob=2;carol=3;"
+        };
+        assert_eq!(render(KeyOrder::Insertion), text(insertion));
+    }
+
+    #[test]
+    fn test_missing_value_policies() {
+        use crate::types::RenderConfig;
+
+        let data = DataArgument {
+            input: Some(json!({"user": {"name": "Ada", "nickname": null}})),
+            ..Default::default()
+        };
+        let template = "{{user.name}}/{{user.nickname}}/{{user.email}}";
+        let render = |dp: &Dotprompt, source: &str, options| {
+            dp.render(source, &data, options)
+                .map(
+                    |rendered: RenderedPrompt| match &rendered.messages[0].content[0] {
+                        Part::Text(part) => part.text.clone(),
+                        other => format!("{other:?}"),
+                    },
+                )
+        };
+        let with_policy = |on_missing| {
+            Dotprompt::new(Some(DotpromptOptions {
+                on_missing,
+                ..Default::default()
+            }))
+        };
+
+        let dp = with_policy(MissingPolicy::Empty);
+        assert_eq!(render(&dp, template, None).expect("render"), "Ada//");
+        let dp = with_policy(MissingPolicy::Null);
+        assert_eq!(
+            render(&dp, template, None).expect("render"),
+            "Ada/null/null"
+        );
+        let dp = with_policy(MissingPolicy::Error);
+        assert!(matches!(
+            render(&dp, template, None),
+            Err(DotpromptError::MissingValue { path }) if path == "user.nickname"
+        ));
+        // Falsy checks still see a missing value as false.
+        assert_eq!(
+            render(&dp, "{{#if user.nickname}}a{{else}}b{{/if}}", None).expect("render"),
+            "b"
+        );
+
+        // The prompt's frontmatter overrides the instance, and the render's
+        // options override both.
+        let dp = with_policy(MissingPolicy::Empty);
+        let strict = format!("---\nrender:\n  onMissing: error\n---\n{template}");
+        assert!(matches!(
+            render(&dp, &strict, None),
+            Err(DotpromptError::MissingValue { .. })
+        ));
+        let lenient = PromptMetadata {
+            render: Some(RenderConfig {
+                on_missing: Some(MissingPolicy::Null),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&dp, &strict, Some(lenient)).expect("render"),
+            "Ada/null/null"
+        );
+
+        // Unknown helpers are still reported as such.
+        let dp = with_policy(MissingPolicy::Error);
+        assert!(matches!(
+            render(&dp, "{{shout user.name}}", None),
+            Err(DotpromptError::UnknownHelper { name }) if name == "shout"
+        ));
+    }
+
+    #[test]
+    fn test_output_format_instructions() {
+        let dp = Dotprompt::new(None);
+        let data = DataArgument::<serde_json::Value>::default();
+        let source = "---\noutput:\n  format: json\n  schema:\n    name: string\n---\n{{role \"system\"}}Be brief.{{role \"user\"}}Who are you?";
+        let rendered: RenderedPrompt = dp.render(source, &data, None).expect("render");
+
+        let part = match rendered.messages[1].content.last() {
+            Some(Part::Text(part)) => part.clone(),
+            other => TextPart {
+                text: format!("{other:?}"),
+                metadata: None,
+            },
+        };
+        assert!(part.text.starts_with("Output should be in JSON format"));
+        assert!(part.text.contains("\"name\""));
+        assert_eq!(
+            part.metadata.as_ref().map(|metadata| &metadata["purpose"]),
+            Some(&json!("output"))
+        );
+        assert_eq!(rendered.messages[0].content.len(), 1);
+
+        // Text output and unknown formats add nothing.
+        for format in ["text", "my-xml"] {
+            let source = format!("---\noutput:\n  format: {format}\n---\nHi");
+            let rendered: RenderedPrompt = dp.render(&source, &data, None).expect("render");
+            assert_eq!(rendered.messages[0].content.len(), 1);
+        }
+
+        let source = "---\noutput:\n  format: enum\n---\nPick a color.";
+        assert!(matches!(
+            dp.render::<_, serde_json::Value>(source, &data, None),
+            Err(DotpromptError::OutputFormatError { format, .. }) if format == "enum"
+        ));
+    }
+
+    #[test]
+    fn test_custom_output_format() {
+        struct Xml;
+
+        impl Format for Xml {
+            fn instructions(&self, _schema: Option<&JsonSchema>) -> Result<Option<String>> {
+                Ok(Some("Wrap your answer in <answer> tags.".to_string()))
+            }
+
+            fn parse(&self, text: &str, _schema: Option<&JsonSchema>) -> Result<serde_json::Value> {
+                let answer = text
+                    .split_once("<answer>")
+                    .and_then(|(_, rest)| rest.split_once("</answer>"))
+                    .map(|(answer, _)| answer.trim());
+                Ok(json!(answer))
+            }
+        }
+
+        let xml: Arc<dyn Format> = Arc::new(Xml);
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            formats: Some(HashMap::from([("my-xml".to_string(), xml)])),
+            ..Default::default()
+        }));
+        let data = DataArgument::<serde_json::Value>::default();
+        let last_text = |source: &str| {
+            let rendered: RenderedPrompt = dp.render(source, &data, None).expect("render");
+            match rendered.messages[0].content.last() {
+                Some(Part::Text(part)) => part.text.clone(),
+                other => format!("{other:?}"),
+            }
+        };
+
+        let source = "---\noutput:\n  format: my-xml\n---\nWhat is 2+2?";
+        assert_eq!(last_text(source), "Wrap your answer in <answer> tags.");
+        let format = dp.format("my-xml").expect("registered format");
+        assert_eq!(
+            format
+                .parse("Sure. <answer> 4 </answer>", None)
+                .expect("parse"),
+            json!("4")
+        );
+
+        // Registered formats take precedence over built-in ones.
+        let source = "---\noutput:\n  format: text\n---\nWhat is 2+2?";
+        assert_eq!(last_text(source), "What is 2+2?");
+        dp.define_format("text", Arc::new(Xml));
+        assert_eq!(last_text(source), "Wrap your answer in <answer> tags.");
+    }
+
+    #[test]
+    fn test_parse_response() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Review {
+            stars: u8,
+            mood: String,
+        }
+
+        let dp = Dotprompt::new(None);
+        let source = "---\noutput:\n  schema:\n    stars: integer\n    mood(enum): [happy, sad]\n---\nReview {{product}}.";
+        let metadata = dp
+            .render_metadata::<serde_json::Value>(source, None)
+            .expect("metadata");
+
+        let response = "Here is the review:\n```json\n{\"stars\": 4, \"mood\": \"Happy\"}\n```";
+        let parsed = dp.parse_response(&metadata, response).expect("parse");
+        assert_eq!(parsed.format, "json");
+        assert_eq!(
+            parsed.into_typed::<Review>().expect("typed"),
+            Review {
+                stars: 4,
+                mood: "happy".to_string(),
+            }
+        );
+
+        let err = dp
+            .parse_response(&metadata, r#"{"stars": "four", "mood": "meh"}"#)
+            .expect_err("mismatch");
+        let violations = match err {
+            DotpromptError::InvalidResponse(ResponseError::SchemaMismatch {
+                violations, ..
+            }) => violations,
+            other => vec![SchemaViolation {
+                path: String::new(),
+                message: other.to_string(),
+            }],
+        };
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(paths.len(), 2, "{violations:?}");
+        assert!(paths.contains(&"$.stars") && paths.contains(&"$.mood"));
+
+        assert!(matches!(
+            dp.parse_response(&metadata, "I'd rather not."),
+            Err(DotpromptError::InvalidResponse(ResponseError::Unparsable { format, .. }))
+                if format == "json"
+        ));
+
+        let plain = PromptMetadata::<serde_json::Value>::default();
+        let parsed = dp.parse_response(&plain, "Hello!").expect("text");
+        assert_eq!(
+            (parsed.format.as_str(), parsed.output),
+            ("text", json!("Hello!"))
+        );
+    }
+
+    #[test]
+    fn test_examples_from_source() {
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            examples: Some(HashMap::from([(
+                "./examples/sentiment.jsonl".to_string(),
+                vec![
+                    json!({"text": "Loved it", "label": "positive"}),
+                    json!({"text": "Meh", "label": "neutral"}),
+                    json!({"text": "Awful", "label": "negative"}),
+                ],
+            )])),
+            ..Default::default()
+        }));
+        let data = DataArgument {
+            input: Some(json!({"text": "Great value"})),
+            ..Default::default()
+        };
+        let render = |source: &str| {
+            dp.render::<_, serde_json::Value>(source, &data, None)
+                .map(|rendered| match &rendered.messages[0].content[0] {
+                    Part::Text(part) => part.text.clone(),
+                    other => format!("{other:?}"),
+                })
+        };
+
+        let source = "---\nexamples:\n  source: ./examples/sentiment.jsonl\n  count: 2\n  template: \"{{text}} => {{label}}\"\n  separator: \"\\n\"\n---\n{{examples}}\n{{text}} =>";
+        assert_eq!(
+            render(source).expect("render"),
+            "Loved it => positive\nMeh => neutral\nGreat value =>"
+        );
+
+        let source =
+            "---\nexamples:\n  source: ./examples/sentiment.jsonl\n  count: 1\n---\n{{examples}}";
+        let rendered: serde_json::Value =
+            serde_json::from_str(&render(source).expect("render")).expect("json");
+        assert_eq!(rendered, json!({"text": "Loved it", "label": "positive"}));
+
+        let source = "---\nexamples:\n  source: ./examples/other.jsonl\n---\n{{examples}}";
+        assert!(matches!(
+            render(source),
+            Err(DotpromptError::ExampleResolutionError(message)) if message.contains("other.jsonl")
+        ));
+    }
+
+    #[test]
+    fn test_example_selector_can_call_back() {
+        use std::sync::{Arc, Weak};
+
+        /// Registers a partial on the instance it selects examples for.
+        struct Reentrant(Weak<Dotprompt>);
+
+        impl ExampleSelector for Reentrant {
+            fn select(
+                &self,
+                _input: &serde_json::Value,
+                examples: Vec<serde_json::Value>,
+                _config: &ExamplesConfig,
+            ) -> Result<Vec<serde_json::Value>> {
+                if let Some(dp) = self.0.upgrade() {
+                    dp.define_partial("selected", "yes")?;
+                }
+                Ok(examples)
+            }
+        }
+
+        let dp = Arc::new_cyclic(|weak| {
+            Dotprompt::new(Some(DotpromptOptions {
+                examples: Some(HashMap::from([(
+                    "qa.yaml".to_string(),
+                    vec![json!({"text": "Hi"})],
+                )])),
+                example_selector: Some(Box::new(Reentrant(weak.clone()))),
+                ..Default::default()
+            }))
+        });
+        let source =
+            "---\nexamples:\n  source: qa.yaml\n  template: \"{{text}}\"\n---\n{{examples}}";
+        let rendered = dp
+            .render::<serde_json::Value, serde_json::Value>(source, &DataArgument::default(), None)
+            .expect("render");
+        assert_eq!(
+            rendered.messages[0].content,
+            [Part::Text(TextPart {
+                text: "Hi".to_string(),
+                metadata: None,
+            })]
+        );
+        assert!(dp.registry().partial_sources.contains_key("selected"));
+    }
+
+    #[test]
+    fn test_example_selector_sees_input() {
+        /// Picks the examples that share the input's first word.
+        struct SameFirstWord;
+
+        impl ExampleSelector for SameFirstWord {
+            fn select(
+                &self,
+                input: &serde_json::Value,
+                examples: Vec<serde_json::Value>,
+                config: &ExamplesConfig,
+            ) -> Result<Vec<serde_json::Value>> {
+                let first_word = |value: &serde_json::Value| {
+                    value["text"]
+                        .as_str()
+                        .and_then(|text| text.split_whitespace().next())
+                        .map(str::to_lowercase)
+                };
+                let word = first_word(input);
+                Ok(examples
+                    .into_iter()
+                    .filter(|example| first_word(example) == word)
+                    .take(config.count.unwrap_or(usize::MAX))
+                    .collect())
+            }
+        }
+
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            examples: Some(HashMap::from([(
+                "qa.yaml".to_string(),
+                vec![
+                    json!({"text": "Why is the sky blue?"}),
+                    json!({"text": "How do plants grow?"}),
+                    json!({"text": "Why do cats purr?"}),
+                ],
+            )])),
+            example_selector: Some(Box::new(SameFirstWord)),
+            ..Default::default()
+        }));
+        let source = "---\nexamples:\n  source: qa.yaml\n  template: \"{{text}}\"\n  separator: \" \"\n---\n{{examples}}";
+        let rendered = dp
+            .render::<_, serde_json::Value>(
+                source,
+                &DataArgument {
+                    input: Some(json!({"text": "why not?"})),
+                    ..Default::default()
+                },
+                None,
+            )
+            .expect("render");
+        assert_eq!(
+            rendered.messages[0].content,
+            vec![Part::Text(TextPart {
+                text: "Why is the sky blue? Why do cats purr?".to_string(),
+                metadata: None,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_precompile_store_reports_each_failure() {
+        use crate::stores::dir::{DirStore, DirStoreOptions};
+
+        let directory =
+            std::env::temp_dir().join(format!("dotprompt-precompile-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).expect("create store");
+        let files = [
+            ("_header.prompt", "Hi {{name}}. "),
+            ("_broken.prompt", "{{#if x}}open"),
+            ("ok.prompt", "{{> header}}Welcome."),
+            ("ok.short.prompt", "Hi."),
+            ("bad_yaml.prompt", "---\nmodel: [\n---\nHi"),
+            ("unclosed.prompt", "line one\n{{#each items}}{{this}}"),
+            ("missing_partial.prompt", "{{> nowhere}}"),
+            (
+                "unknown_schema.prompt",
+                "---\noutput:\n  schema: Nope\n---\nHi",
+            ),
+        ];
+        for (path, source) in files {
+            std::fs::write(directory.join(path), source).expect("write prompt");
+        }
+        let store = DirStore::new(DirStoreOptions {
+            directory: directory.clone(),
+            ..Default::default()
+        });
+
+        let dp = Dotprompt::new(None);
+        let report = dp.precompile_store(&store).expect("list store");
+        std::fs::remove_dir_all(&directory).expect("remove store");
+
+        assert!(!report.is_ok());
+        assert_eq!(report.partials, ["header"]);
+        let mut prompts = report.prompts.clone();
+        prompts.sort();
+        assert_eq!(prompts, ["ok", "ok.short"]);
+
+        let mut failures: Vec<String> = report.failures.iter().map(ToString::to_string).collect();
+        failures.sort();
+        assert_eq!(failures.len(), 5, "{failures:#?}");
+        assert!(
+            failures[0].starts_with("partial 'broken':"),
+            "{failures:#?}"
+        );
+        assert!(
+            failures[1].starts_with("prompt 'bad_yaml':"),
+            "{failures:#?}"
+        );
+        assert!(
+            failures[2].starts_with("prompt 'missing_partial':") && failures[2].contains("nowhere"),
+            "{failures:#?}"
+        );
+        assert!(
+            matches!(
+                report.failures.iter().find(|f| f.name == "unclosed").map(|f| &f.error),
+                Some(DotpromptError::UnclosedBlock { name, line: 2 }) if name == "each"
+            ),
+            "{failures:#?}"
+        );
+        assert!(
+            failures[4].starts_with("prompt 'unknown_schema':"),
+            "{failures:#?}"
+        );
+
+        // The store's partials are registered for later renders.
+        let rendered: RenderedPrompt = dp
+            .render(
+                "{{> header}}",
+                &DataArgument {
+                    input: Some(json!({"name": "Ada"})),
+                    ..Default::default()
+                },
+                None,
+            )
+            .expect("render");
+        assert_eq!(
+            rendered.messages[0].content,
+            vec![Part::Text(TextPart {
+                text: "Hi Ada. ".to_string(),
+                metadata: None,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_partial_precedence_and_namespaces() {
+        use crate::stores::dir::{DirStore, DirStoreOptions};
+
+        struct MapResolver(HashMap<&'static str, &'static str>);
+
+        impl PartialResolver for MapResolver {
+            fn resolve(&self, name: &str) -> Result<Option<String>> {
+                Ok(self.0.get(name).map(ToString::to_string))
+            }
+        }
+
+        let directory = std::env::temp_dir().join(format!(
+            "dotprompt-partial-precedence-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).expect("create store");
+        for name in ["a", "b", "c"] {
+            std::fs::write(directory.join(format!("_{name}.prompt")), "store")
+                .expect("write partial");
+        }
+
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            partials: Some(HashMap::from([("a".to_string(), "static".to_string())])),
+            partial_resolver: Some(Box::new(MapResolver(HashMap::from([
+                ("a", "resolver"),
+                ("b", "resolver"),
+            ])))),
+            partial_store: Some(Box::new(DirStore::new(DirStoreOptions {
+                directory: directory.clone(),
+                ..Default::default()
+            }))),
+            ..Default::default()
+        }));
+        dp.define_partial_namespace(
+            "lib",
+            Box::new(MapResolver(HashMap::from([
+                ("card", "[{{> lib:title}}]"),
+                ("title", "T"),
+                ("a", "lib"),
+            ]))),
+        );
+
+        let template = "{{> a}} {{> b}} {{> c}} {{> lib:a}} {{> lib:card}}";
+        dp.resolve_partials(template).expect("resolve");
+        std::fs::remove_dir_all(&directory).expect("remove store");
+
+        let rendered: RenderedPrompt = dp
+            .render(
+                template,
+                &DataArgument::<serde_json::Value>::default(),
+                None,
+            )
+            .expect("render");
+        assert_eq!(
+            rendered.messages[0].content,
+            [Part::Text(TextPart {
+                text: "static resolver store lib [T]".to_string(),
+                metadata: None,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_namespaced_partial_names() {
+        struct Lib;
+
+        impl PartialResolver for Lib {
+            fn resolve(&self, name: &str) -> Result<Option<String>> {
+                Ok(Some(match name {
+                    "layout" => "<{{> @partial-block}}>".to_string(),
+                    other => format!("[{other}]"),
+                }))
+            }
+        }
+
+        let dp = Dotprompt::new(None);
+        dp.define_partial_namespace("lib", Box::new(Lib));
+        let render = |template: &str| {
+            dp.resolve_partials(template)?;
+            let rendered: RenderedPrompt = dp.render(
+                template,
+                &DataArgument::<serde_json::Value>::default(),
+                None,
+            )?;
+            Ok::<_, DotpromptError>(match &rendered.messages[0].content[0] {
+                Part::Text(part) => part.text.clone(),
+                other => format!("{other:?}"),
+            })
+        };
+
+        assert_eq!(
+            dp.identify_partials("{{> lib:shared-header}} {{~> lib:shared/header~}} {{#> lib:layout}}x{{/lib:layout}}"),
+            ["lib:shared-header", "lib:shared/header", "lib:layout"]
+                .into_iter()
+                .map(String::from)
+                .collect()
+        );
+        assert_eq!(
+            render("{{> lib:shared-header}}").expect("render"),
+            "[shared-header]"
+        );
+        assert_eq!(
+            render("{{> lib:shared/header}}").expect("render"),
+            "[shared/header]"
+        );
+        assert_eq!(
+            render("{{#> lib:layout}}x{{/lib:layout}}").expect("render"),
+            "<x>"
+        );
+        assert_eq!(
+            render("{{#> lib:layout}}{{#> lib:layout}}y{{/lib:layout}}{{/lib:layout}}")
+                .expect("render"),
+            "<<y>>"
+        );
+    }
+
+    #[test]
+    fn test_namespace_resolver_can_call_back() {
+        use std::sync::{Arc, Weak};
+
+        /// Registers a schema on the instance it resolves partials for.
+        struct Reentrant(Weak<Dotprompt>);
+
+        impl PartialResolver for Reentrant {
+            fn resolve(&self, name: &str) -> Result<Option<String>> {
+                if let Some(dp) = self.0.upgrade() {
+                    dp.define_schema(name, json!({"type": "object"}));
+                }
+                Ok(Some(format!("<{name}>")))
+            }
+        }
+
+        let dp = Arc::new(Dotprompt::new(None));
+        dp.define_partial_namespace("lib", Box::new(Reentrant(Arc::downgrade(&dp))));
+        dp.resolve_partials("{{> lib:card}}").expect("resolve");
+        assert!(dp.registry().schemas.contains_key("card"));
+    }
+
+    #[test]
+    fn test_resolver_failures_are_errors() {
+        struct Offline;
+
+        impl PartialResolver for Offline {
+            fn resolve(&self, _name: &str) -> Result<Option<String>> {
+                Err(DotpromptError::IoError(std::io::Error::other("offline")))
+            }
+        }
+
+        impl ToolResolver for Offline {
+            fn resolve(&self, _name: &str) -> Result<Option<ToolDefinition>> {
+                Err(DotpromptError::IoError(std::io::Error::other("offline")))
+            }
+        }
+
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            partial_resolver: Some(Box::new(Offline)),
+            tool_resolver: Some(Box::new(Offline)),
+            ..Default::default()
+        }));
+        let err = dp.resolve_partials("{{> header}}").expect_err("offline");
+        assert!(
+            matches!(&err, DotpromptError::PartialResolutionError(message) if message.starts_with("header: ")),
+            "{err}"
+        );
+
+        let meta: PromptMetadata = PromptMetadata {
+            tools: Some(vec!["search".to_string()]),
+            ..Default::default()
+        };
+        let err = dp.resolve_tools(meta).expect_err("offline");
+        assert!(
+            matches!(err, DotpromptError::ToolResolutionError(_)),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_named_schemas() {
+        struct Registry;
+
+        impl SchemaResolver for Registry {
+            fn resolve(&self, name: &str) -> Result<Option<JsonSchema>> {
+                Ok((name == "Address").then(|| json!({"type": "string"})))
+            }
+        }
+
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            schema_resolver: Some(Box::new(Registry)),
+            ..Default::default()
+        }));
+        dp.define_schema(
+            "Person",
+            json!({"type": "object", "properties": {"home": {"$ref": "#/schemas/Address"}}}),
+        );
+
+        let meta: PromptMetadata = PromptMetadata {
+            input: Some(PromptInputConfig {
+                schema: Some(json!({"author": "Person", "home": "Address"})),
+                ..Default::default()
+            }),
+            output: Some(PromptOutputConfig {
+                schema: Some(json!({"$ref": "#/schemas/Person"})),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let meta = dp.render_picoschema(meta).expect("schemas should resolve");
+        let input = meta.input.and_then(|i| i.schema).expect("input schema");
+        assert_eq!(input["properties"]["home"], json!({"type": "string"}));
+        assert_eq!(
+            input["properties"]["author"]["properties"]["home"]["$ref"],
+            "#/$defs/Address"
+        );
+        let output = meta.output.and_then(|o| o.schema).expect("output schema");
+        assert_eq!(output["$ref"], "#/$defs/Person");
+        assert_eq!(output["$defs"]["Address"], json!({"type": "string"}));
+
+        let meta: PromptMetadata = PromptMetadata {
+            output: Some(PromptOutputConfig {
+                schema: Some(json!({"$ref": "#/schemas/Robot"})),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            dp.render_picoschema(meta),
+            Err(DotpromptError::SchemaResolutionError(_))
+        ));
+    }
+
+    #[test]
+    fn test_context_variables() {
+        assert_eq!(
+            expose_context_variables(
+                r#"{{@auth.uid}} {{#each x}}{{@index}}{{/each}} {{eq "@a" @b}} \{{@c}} {{! @d }}"#
+            ),
+            r#"{{@root.__ctx_auth.uid}} {{#each x}}{{@index}}{{/each}} {{eq "@a" @root.__ctx_b}} \{{@c}} {{! @d }}"#
+        );
+
+        let dp = Dotprompt::new(None);
+        dp.define_partial("whoami", "{{@auth.uid}}")
+            .expect("partial should compile");
+        let data = DataArgument::<serde_json::Value> {
+            context: Some(HashMap::from([("auth".to_string(), json!({"uid": "u1"}))])),
+            ..Default::default()
+        };
+        let rendered: RenderedPrompt = dp.render("I am {{> whoami}}", &data, None).expect("render");
+        assert!(matches!(
+            &rendered.messages[0].content[0],
+            Part::Text(t) if t.text == "I am u1"
+        ));
+    }
+
+    #[test]
+    fn test_render_metadata_resolves_everything() {
+        let dp = Dotprompt::new(Some(DotpromptOptions {
+            default_model: Some("base-model".to_string()),
+            model_configs: Some(HashMap::from([(
+                "base-model".to_string(),
+                json!({"temperature": 0.5, "topK": 3}),
+            )])),
+            ..Default::default()
+        }));
+        dp.define_tool(ToolDefinition {
+            name: "search".to_string(),
+            description: None,
+            input_schema: HashMap::new(),
+            output_schema: None,
+        });
+        let source = "---\nconfig:\n  temperature: 1\ntools: [search]\ninput:\n  default: {name: World}\n  schema:\n    name: string, who to greet\n---\nHello, {{name}}!";
+
+        let meta: PromptMetadata = dp.render_metadata(source, None).expect("metadata");
+        assert_eq!(meta.model.as_deref(), Some("base-model"));
+        assert_eq!(meta.config, Some(json!({"temperature": 1, "topK": 3})));
+        assert_eq!(
+            meta.tool_defs.map(|defs| defs[0].name.clone()),
+            Some("search".to_string())
+        );
+        let schema = meta.input.and_then(|i| i.schema).expect("input schema");
+        assert_eq!(schema["properties"]["name"]["description"], "who to greet");
+
+        let rendered: RenderedPrompt = dp
+            .render(source, &DataArgument::<serde_json::Value>::default(), None)
+            .expect("render");
+        assert_eq!(
+            rendered.metadata.config,
+            Some(json!({"temperature": 1, "topK": 3}))
+        );
+        assert!(matches!(
+            &rendered.messages[0].content[0],
+            Part::Text(t) if t.text == "Hello, World!"
+        ));
+    }
 }
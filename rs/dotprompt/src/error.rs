@@ -16,8 +16,24 @@
 
 //! Error types for the dotprompt library.
 
+use std::sync::LazyLock;
+
+use handlebars::{RenderError, RenderErrorReason, TemplateError, TemplateErrorReason};
+use regex::Regex;
 use thiserror::Error;
 
+use crate::limits::Limit;
+use crate::response::ResponseError;
+
+/// Matches block open and close tags, capturing the `#` or `/` and the
+/// block name. Partial blocks (`{{#> name}}`) and inline partials
+/// (`{{#*inline "name"}}`) count as blocks too.
+#[allow(clippy::expect_used)]
+static BLOCK_TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\{\{~?\s*([#/])(?:>|\*inline)?\s*"?([^\s}~"]+)"#)
+        .expect("internal regex pattern should compile")
+});
+
 /// Result type alias for dotprompt operations.
 pub type Result<T> = std::result::Result<T, DotpromptError>;
 
@@ -26,7 +42,7 @@ pub type Result<T> = std::result::Result<T, DotpromptError>;
 pub enum DotpromptError {
     /// Failed to parse YAML frontmatter.
     #[error("failed to parse frontmatter: {0}")]
-    FrontmatterParseError(#[from] serde_yaml::Error),
+    FrontmatterParseError(#[from] serde_yaml_ng::Error),
 
     /// I/O error.
     #[error("I/O error: {0}")]
@@ -36,10 +52,36 @@ pub enum DotpromptError {
     #[error("failed to parse JSON: {0}")]
     JsonParseError(#[from] serde_json::Error),
 
-    /// Template compilation failed.
+    /// Template compilation failed for a reason without its own variant.
     #[error("template compilation failed: {0}")]
     CompilationError(String),
 
+    /// A template calls a helper that is not registered.
+    #[error("unknown helper '{name}'")]
+    UnknownHelper {
+        /// The helper name.
+        name: String,
+    },
+
+    /// A block is never closed, or is closed by a different block's tag.
+    #[error("block '{name}' opened on line {line} is not closed")]
+    UnclosedBlock {
+        /// The block name, such as `if` or `each`.
+        name: String,
+        /// The line of the opening tag, counting from 1 at the start of the
+        /// template body.
+        line: usize,
+    },
+
+    /// A partial is missing or does not compile.
+    #[error("bad partial '{name}': {reason}")]
+    BadPartial {
+        /// The partial name.
+        name: String,
+        /// Why the partial could not be used.
+        reason: String,
+    },
+
     /// Template rendering failed.
     #[error("template rendering failed: {0}")]
     RenderError(String),
@@ -64,6 +106,14 @@ pub enum DotpromptError {
     #[error("schema resolution failed: {0}")]
     SchemaResolutionError(String),
 
+    /// Partial resolution failed.
+    #[error("partial resolution failed: {0}")]
+    PartialResolutionError(String),
+
+    /// Few-shot examples could not be loaded.
+    #[error("example resolution failed: {0}")]
+    ExampleResolutionError(String),
+
     /// Regex pattern error.
     #[error("regex pattern error: {0}")]
     RegexError(#[from] regex::Error),
@@ -79,4 +129,188 @@ pub enum DotpromptError {
     /// Store error.
     #[error("store error: {0}")]
     StoreError(String),
+
+    /// A prompt or partial does not exist in a store.
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// A value the template uses is `null` or missing from the data, and the
+    /// render's [`MissingPolicy`](crate::MissingPolicy) is `error`.
+    #[error("missing value: {path}")]
+    MissingValue {
+        /// The expression that has no value, such as `user.name`.
+        path: String,
+    },
+
+    /// An output format cannot be used with a prompt's output schema, or a
+    /// response is not in the format.
+    #[error("output format '{format}': {reason}")]
+    OutputFormatError {
+        /// The format name, such as `json`.
+        format: String,
+        /// What is wrong.
+        reason: String,
+    },
+
+    /// A model response does not fit the prompt's output config.
+    #[error("invalid response: {0}")]
+    InvalidResponse(#[from] ResponseError),
+
+    /// A [`PromptRegistry`](crate::PromptRegistry) has no prompt with this
+    /// name and variant.
+    #[error("unknown prompt '{name}'{}", variant.as_ref().map(|v| format!(" (variant '{v}')")).unwrap_or_default())]
+    UnknownPrompt {
+        /// The prompt name.
+        name: String,
+        /// The variant, if one was asked for.
+        variant: Option<String>,
+    },
+
+    /// A prompt in a [`PromptRegistry`](crate::PromptRegistry) could not be
+    /// parsed or its partials could not be registered.
+    #[error("prompt '{name}' is invalid: {source}")]
+    InvalidPrompt {
+        /// The prompt name.
+        name: String,
+        /// Why the prompt is invalid.
+        source: Box<Self>,
+    },
+
+    /// Helper manifest is invalid.
+    #[error("invalid helper manifest: {0}")]
+    HelperManifestError(String),
+
+    /// Pricing table is invalid.
+    #[error("invalid pricing table: {0}")]
+    PricingError(String),
+
+    /// Tool catalog is invalid.
+    #[error("invalid tool catalog: {0}")]
+    ToolCatalogError(String),
+
+    /// A render exceeded one of its [`RenderLimits`](crate::RenderLimits).
+    #[error("render limit exceeded: {limit} is limited to {max}")]
+    LimitExceeded {
+        /// The limit that was exceeded.
+        limit: Limit,
+        /// The configured maximum.
+        max: usize,
+    },
+}
+
+impl DotpromptError {
+    /// Interprets a failed Handlebars render of `template`, turning compile
+    /// problems into their own variants.
+    pub(crate) fn from_render_error(err: &RenderError, template: &str) -> Self {
+        match err.reason() {
+            RenderErrorReason::TemplateError(err) => Self::from_template_error(err, template),
+            RenderErrorReason::HelperNotFound(name) => Self::UnknownHelper { name: name.clone() },
+            RenderErrorReason::MissingVariable(path) => Self::MissingValue {
+                path: path.clone().unwrap_or_default(),
+            },
+            RenderErrorReason::PartialNotFound(name) => Self::BadPartial {
+                name: unescape_partial_name(name).to_string(),
+                reason: "not found".to_string(),
+            },
+            _ => Self::RenderError(err.to_string()),
+        }
+    }
+
+    /// Interprets a Handlebars compile error for `template`.
+    pub(crate) fn from_template_error(err: &TemplateError, template: &str) -> Self {
+        match err.reason() {
+            TemplateErrorReason::MismatchingClosedHelper(..)
+            | TemplateErrorReason::MismatchingClosedDecorator(..)
+            | TemplateErrorReason::InvalidSyntax(_) => unclosed_block(template).map_or_else(
+                || Self::CompilationError(err.to_string()),
+                |(name, line)| Self::UnclosedBlock { name, line },
+            ),
+            _ => Self::CompilationError(err.to_string()),
+        }
+    }
+}
+
+/// Returns the name and line of the innermost block in `template` that is
+/// not closed by its own tag.
+fn unclosed_block(template: &str) -> Option<(String, usize)> {
+    let mut open: Vec<(&str, usize)> = Vec::new();
+    for caps in BLOCK_TAG.captures_iter(template) {
+        let (Some(kind), Some(name)) = (caps.get(1), caps.get(2)) else {
+            continue;
+        };
+        if kind.as_str() == "#" {
+            let line = template[..kind.start()].matches('\n').count() + 1;
+            open.push((name.as_str(), line));
+            continue;
+        }
+        match open.last() {
+            Some((top, _)) if *top == name.as_str() => {
+                open.pop();
+            }
+            Some(_) => break,
+            None => return None,
+        }
+    }
+    open.pop()
+        .map(|(name, line)| (unescape_partial_name(name).to_string(), line))
+}
+
+/// Returns a partial name without the brackets that namespaced partial
+/// names get when registered.
+fn unescape_partial_name(name: &str) -> &str {
+    name.strip_prefix('[')
+        .and_then(|name| name.strip_suffix(']'))
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use handlebars::Handlebars;
+
+    use super::*;
+
+    fn compile(template: &str) -> DotpromptError {
+        let err = Handlebars::new()
+            .render_template(template, &serde_json::json!({}))
+            .expect_err("template should fail");
+        DotpromptError::from_render_error(&err, template)
+    }
+
+    #[test]
+    fn test_unclosed_block_at_end() {
+        assert!(matches!(
+            compile("Hi\n{{#if a}}\n{{#each b}}{{/each}}\nbye"),
+            DotpromptError::UnclosedBlock { name, line: 2 } if name == "if"
+        ));
+    }
+
+    #[test]
+    fn test_block_closed_by_another_tag() {
+        assert!(matches!(
+            compile("{{#each a}}\n{{#if b}}\n{{/each}}"),
+            DotpromptError::UnclosedBlock { name, line: 2 } if name == "if"
+        ));
+    }
+
+    #[test]
+    fn test_unknown_helper_and_missing_partial() {
+        assert!(matches!(
+            compile("{{shout name}}"),
+            DotpromptError::UnknownHelper { name } if name == "shout"
+        ));
+        assert!(matches!(
+            compile("{{> footer}}"),
+            DotpromptError::BadPartial { name, .. } if name == "footer"
+        ));
+    }
+
+    #[test]
+    fn test_other_syntax_errors_keep_the_message() {
+        let err = compile("{{ name }");
+        assert!(
+            matches!(err, DotpromptError::CompilationError(_)),
+            "{err:?}"
+        );
+    }
 }
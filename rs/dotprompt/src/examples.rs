@@ -0,0 +1,198 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Few-shot examples loaded from data files.
+//!
+//! A prompt names a file of examples in its `examples` frontmatter (see
+//! [`ExamplesConfig`]). When it renders, the examples are loaded through
+//! [`DotpromptOptions::examples`](crate::DotpromptOptions::examples) or the
+//! [`ExampleResolver`](crate::ExampleResolver), chosen, rendered one by one
+//! with the example template, and joined into the `examples` variable:
+//!
+//! ```text
+//! ---
+//! examples:
+//!   source: ./examples/sentiment.jsonl
+//!   count: 3
+//!   template: "Text: {{text}}\nSentiment: {{label}}"
+//! ---
+//! Classify the sentiment of the text.
+//!
+//! {{examples}}
+//!
+//! Text: {{input}}
+//! Sentiment:
+//! ```
+//!
+//! `examples` takes the place of an input field of the same name.
+//!
+//! Which examples are used is up to the
+//! [`ExampleSelector`](crate::ExampleSelector); the default,
+//! [`StaticExampleSelector`], ignores the input.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::error::{DotpromptError, Result};
+use crate::types::{ExampleSampling, ExampleSelector, ExamplesConfig};
+
+/// Template an example is rendered with when the prompt gives none.
+pub(crate) const DEFAULT_TEMPLATE: &str = "{{json this}}";
+
+/// Text between examples when the prompt gives none.
+pub(crate) const DEFAULT_SEPARATOR: &str = "\n\n";
+
+/// Parses the text of an examples file: one JSON value per line for
+/// `.jsonl`, a YAML (or JSON) list otherwise.
+///
+/// # Errors
+///
+/// Returns [`DotpromptError::ExampleResolutionError`] naming the source and
+/// line if the text does not parse.
+pub fn parse_examples(source: &str, text: &str) -> Result<Vec<Value>> {
+    let invalid =
+        |reason: String| DotpromptError::ExampleResolutionError(format!("{source}: {reason}"));
+    let extension = Path::new(source)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    if extension == Some("jsonl") {
+        return text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| invalid(format!("line {}: {e}", i + 1)))
+            })
+            .collect();
+    }
+    match serde_yaml_ng::from_str(text).map_err(|e| invalid(e.to_string()))? {
+        Value::Array(examples) => Ok(examples),
+        _ => Err(invalid("expected a list of examples".to_string())),
+    }
+}
+
+/// Chooses the examples a prompt uses, in the order they are rendered.
+#[must_use]
+pub fn select(mut examples: Vec<Value>, config: &ExamplesConfig) -> Vec<Value> {
+    let count = config.count.unwrap_or(examples.len()).min(examples.len());
+    if config.sample == ExampleSampling::Random {
+        let seed = config.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| {
+                    elapsed.as_secs() ^ u64::from(elapsed.subsec_nanos())
+                })
+        });
+        shuffle(&mut examples, seed);
+    }
+    examples.truncate(count);
+    examples
+}
+
+/// Selects examples with [`select`], without looking at the input.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StaticExampleSelector;
+
+impl ExampleSelector for StaticExampleSelector {
+    fn select(
+        &self,
+        _input: &Value,
+        examples: Vec<Value>,
+        config: &ExamplesConfig,
+    ) -> Result<Vec<Value>> {
+        Ok(select(examples, config))
+    }
+}
+
+/// Shuffles `items` with a Fisher-Yates shuffle driven by `SplitMix64`, so
+/// a seed picks the same order on every platform and release.
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    for i in (1..items.len()).rev() {
+        // The modulo bias is negligible for lists of examples.
+        let j = usize::try_from(next() % (i as u64 + 1)).unwrap_or(i);
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_examples() {
+        let jsonl = "{\"q\": 1}\n\n{\"q\": 2}\n";
+        assert_eq!(
+            parse_examples("a.jsonl", jsonl).expect("jsonl"),
+            vec![json!({"q": 1}), json!({"q": 2})]
+        );
+        assert_eq!(
+            parse_examples("a.yaml", "- q: 1\n- q: 2\n").expect("yaml"),
+            vec![json!({"q": 1}), json!({"q": 2})]
+        );
+        assert_eq!(
+            parse_examples("a.json", "[1, 2]").expect("json"),
+            vec![json!(1), json!(2)]
+        );
+
+        let err = parse_examples("a.jsonl", "{\"q\": 1}\n{oops}").expect_err("bad line");
+        assert!(err.to_string().contains("a.jsonl: line 2"), "{err}");
+        assert!(parse_examples("a.yaml", "q: 1").is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let examples: Vec<Value> = (0..10).map(|i| json!(i)).collect();
+        let first = ExamplesConfig {
+            count: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            select(examples.clone(), &first),
+            vec![json!(0), json!(1), json!(2)]
+        );
+
+        let random = ExamplesConfig {
+            count: Some(4),
+            sample: ExampleSampling::Random,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let chosen = select(examples.clone(), &random);
+        assert_eq!(chosen.len(), 4);
+        assert_eq!(
+            chosen,
+            select(examples.clone(), &random),
+            "same seed, same examples"
+        );
+        assert_ne!(chosen, examples[..4]);
+
+        let all = ExamplesConfig::default();
+        assert_eq!(select(examples.clone(), &all), examples);
+    }
+}
@@ -0,0 +1,335 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Output formats.
+//!
+//! A prompt's `output.format` names a [`Format`], which adds instructions
+//! for that format to the rendered prompt and parses model responses back
+//! into values. The built-in formats are:
+//!
+//! | Format  | Instructions                              | Parses to            |
+//! |---------|-------------------------------------------|----------------------|
+//! | `json`  | the output schema, if there is one        | any JSON value       |
+//! | `array` | the output schema, which must be an array | a JSON array         |
+//! | `enum`  | the schema's `enum` values                | one of those values  |
+//! | `text`  | none                                      | the text as a string |
+//!
+//! Instructions are appended as a text part with `purpose: output` to the
+//! last user message.
+//!
+//! Other formats can be registered with
+//! [`DotpromptOptions::formats`](crate::DotpromptOptions::formats) or
+//! [`Dotprompt::define_format`](crate::Dotprompt::define_format). Prompts
+//! with an unknown format render as if they had none.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::error::{DotpromptError, Result};
+use crate::types::{JsonSchema, Message, Part, Role, TextPart};
+
+/// A structured output format.
+pub trait Format: Send + Sync {
+    /// Returns the instructions that ask a model for output in this format,
+    /// or `None` if the prompt needs none.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::OutputFormatError`] if the format cannot be
+    /// used with `schema`.
+    fn instructions(&self, schema: Option<&JsonSchema>) -> Result<Option<String>>;
+
+    /// Parses a model response in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::OutputFormatError`] if `text` is not in this
+    /// format.
+    fn parse(&self, text: &str, schema: Option<&JsonSchema>) -> Result<Value>;
+}
+
+/// JSON output, described by the output schema if there is one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+/// A JSON array, described by an output schema of type `array`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArrayFormat;
+
+/// One of the values of the output schema's `enum`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnumFormat;
+
+/// Plain text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextFormat;
+
+/// The names of the built-in formats.
+pub const BUILTIN_FORMATS: &[&str] = &["json", "array", "enum", "text"];
+
+/// Returns the built-in format with this name.
+#[must_use]
+pub fn builtin(name: &str) -> Option<Arc<dyn Format>> {
+    match name {
+        "json" => Some(Arc::new(JsonFormat)),
+        "array" => Some(Arc::new(ArrayFormat)),
+        "enum" => Some(Arc::new(EnumFormat)),
+        "text" => Some(Arc::new(TextFormat)),
+        _ => None,
+    }
+}
+
+impl Format for JsonFormat {
+    fn instructions(&self, schema: Option<&JsonSchema>) -> Result<Option<String>> {
+        schema
+            .map(|schema| {
+                Ok(format!(
+                    "Output should be in JSON format and conform to the following schema:\n\n```\n{}\n```\n",
+                    serde_json::to_string_pretty(schema)?
+                ))
+            })
+            .transpose()
+    }
+
+    fn parse(&self, text: &str, _schema: Option<&JsonSchema>) -> Result<Value> {
+        parse_json(text).map_err(|reason| error("json", reason))
+    }
+}
+
+impl Format for ArrayFormat {
+    fn instructions(&self, schema: Option<&JsonSchema>) -> Result<Option<String>> {
+        let schema = schema
+            .filter(|schema| schema.get("type").and_then(Value::as_str) == Some("array"))
+            .ok_or_else(|| error("array", "the output schema must have type 'array'"))?;
+        Ok(Some(format!(
+            "Output should be a JSON array conforming to the following schema:\n\n```\n{}\n```\n",
+            serde_json::to_string_pretty(schema)?
+        )))
+    }
+
+    fn parse(&self, text: &str, _schema: Option<&JsonSchema>) -> Result<Value> {
+        match parse_json(text).map_err(|reason| error("array", reason))? {
+            array @ Value::Array(_) => Ok(array),
+            other => Err(error(
+                "array",
+                format!("expected a JSON array, got {}", json_type(&other)),
+            )),
+        }
+    }
+}
+
+impl Format for EnumFormat {
+    fn instructions(&self, schema: Option<&JsonSchema>) -> Result<Option<String>> {
+        let values = enum_values(schema)?
+            .iter()
+            .map(enum_text)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(Some(format!(
+            "Output should be ONE of the following enum values. Do not output any additional information or add quotes.\n\n```\n{values}\n```\n"
+        )))
+    }
+
+    fn parse(&self, text: &str, schema: Option<&JsonSchema>) -> Result<Value> {
+        let text = text.trim();
+        let text = text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .unwrap_or(text);
+        find_enum_value(enum_values(schema)?, text)
+            .cloned()
+            .ok_or_else(|| error("enum", format!("'{text}' is not one of the enum values")))
+    }
+}
+
+impl Format for TextFormat {
+    fn instructions(&self, _schema: Option<&JsonSchema>) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    fn parse(&self, text: &str, _schema: Option<&JsonSchema>) -> Result<Value> {
+        Ok(Value::String(text.to_string()))
+    }
+}
+
+/// Returns the JSON in a response: the first fenced code block if there is
+/// one, otherwise the response itself.
+#[must_use]
+pub fn extract_json(text: &str) -> &str {
+    let Some((_, fenced)) = text.split_once("```") else {
+        return text.trim();
+    };
+    // Skip the language tag, such as `json`, on the opening fence.
+    let body = fenced.split_once('\n').map_or(fenced, |(_, body)| body);
+    body.split_once("```").map_or(body, |(body, _)| body).trim()
+}
+
+/// Parses the JSON in a response, falling back to the text between its
+/// first `{` or `[` and its last `}` or `]` when there is prose around it.
+fn parse_json(text: &str) -> std::result::Result<Value, String> {
+    let json = extract_json(text);
+    serde_json::from_str(json).or_else(|err| {
+        let start = json.find(['{', '[']);
+        let end = json.rfind(['}', ']']);
+        match (start, end) {
+            (Some(start), Some(end)) if start < end => {
+                serde_json::from_str(&json[start..=end]).map_err(|err| err.to_string())
+            }
+            _ => Err(err.to_string()),
+        }
+    })
+}
+
+/// Returns the `enum` values of an output schema.
+fn enum_values(schema: Option<&JsonSchema>) -> Result<&Vec<Value>> {
+    schema
+        .and_then(|schema| schema.get("enum"))
+        .and_then(Value::as_array)
+        .ok_or_else(|| error("enum", "the output schema must list 'enum' values"))
+}
+
+/// Returns the enum value a model meant by `text`: the one it equals, or
+/// else one it equals ignoring case and surrounding whitespace.
+pub(crate) fn find_enum_value<'a>(values: &'a [Value], text: &str) -> Option<&'a Value> {
+    values
+        .iter()
+        .find(|value| enum_text(value) == text)
+        .or_else(|| {
+            values
+                .iter()
+                .find(|value| enum_text(value).eq_ignore_ascii_case(text.trim()))
+        })
+}
+
+/// Returns an enum value as a model would write it.
+fn enum_text(value: &Value) -> String {
+    value
+        .as_str()
+        .map_or_else(|| value.to_string(), str::to_string)
+}
+
+/// Returns the JSON type name of a value.
+pub(crate) const fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Builds an error for a format.
+fn error(format: &str, reason: impl Into<String>) -> DotpromptError {
+    DotpromptError::OutputFormatError {
+        format: format.to_string(),
+        reason: reason.into(),
+    }
+}
+
+/// Appends output instructions to the last user message, or to a new user
+/// message if there is none.
+pub(crate) fn append_instructions(messages: &mut Vec<Message>, instructions: String) {
+    let part = Part::Text(TextPart {
+        text: instructions,
+        metadata: Some(HashMap::from([(
+            "purpose".to_string(),
+            Value::String("output".to_string()),
+        )])),
+    });
+    match messages
+        .iter_mut()
+        .rev()
+        .find(|message| message.role == Role::User)
+    {
+        Some(message) => message.content.push(part),
+        None => messages.push(Message {
+            role: Role::User,
+            content: vec![part],
+            metadata: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_instructions() {
+        let schema = json!({"type": "object", "properties": {"name": {"type": "string"}}});
+        let json = JsonFormat.instructions(Some(&schema)).expect("json");
+        assert!(json.is_some_and(|text| text.contains("\"name\"")));
+        assert_eq!(JsonFormat.instructions(None).expect("json"), None);
+        assert_eq!(TextFormat.instructions(Some(&schema)).expect("text"), None);
+
+        assert!(matches!(
+            ArrayFormat.instructions(Some(&schema)),
+            Err(DotpromptError::OutputFormatError { format, .. }) if format == "array"
+        ));
+        let colors = json!({"enum": ["red", "green"]});
+        assert!(
+            EnumFormat
+                .instructions(Some(&colors))
+                .expect("enum")
+                .is_some_and(|text| text.contains("```\nred\ngreen\n```"))
+        );
+        assert!(EnumFormat.instructions(None).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let fenced = "Here you go:\n```json\n{\"a\": 1}\n```\nAnything else?";
+        assert_eq!(
+            JsonFormat.parse(fenced, None).expect("json"),
+            json!({"a": 1})
+        );
+        assert_eq!(
+            JsonFormat
+                .parse("Sure! {\"a\": [1, 2]} Hope that helps.", None)
+                .expect("json"),
+            json!({"a": [1, 2]})
+        );
+        assert!(JsonFormat.parse("no json here", None).is_err());
+
+        assert_eq!(
+            ArrayFormat.parse("[1, 2]", None).expect("array"),
+            json!([1, 2])
+        );
+        assert!(ArrayFormat.parse("{\"a\": 1}", None).is_err());
+
+        let colors = json!({"enum": ["red", "green"]});
+        assert_eq!(
+            EnumFormat
+                .parse(" \"green\"\n", Some(&colors))
+                .expect("enum"),
+            json!("green")
+        );
+        assert_eq!(
+            EnumFormat.parse("Red", Some(&colors)).expect("enum"),
+            json!("red")
+        );
+        assert!(EnumFormat.parse("blue", Some(&colors)).is_err());
+
+        assert_eq!(TextFormat.parse(" hi ", None).expect("text"), json!(" hi "));
+    }
+}
@@ -18,8 +18,94 @@
 //!
 //! This module provides custom Handlebars helpers that enable dotprompt-specific
 //! functionality like role markers, media references, and JSON serialization.
+//!
+//! The array helpers `first`, `last`, `slice`, `join`, and `length` compute
+//! values, so they can also be used in subexpressions:
+//!
+//! ```handlebars
+//! {{#each (slice items 0 3)}}- {{this}}
+//! {{/each}}
+//! ```
+//!
+//! So can the math helpers `add`, `subtract`, `multiply`, `divide`, `round`,
+//! and `percent`. Their arguments may be numbers or strings that parse as
+//! numbers; anything else, including a missing value, is an error. Integer
+//! arithmetic stays integral unless it overflows, and whole-number results
+//! render without a fraction, so `{{divide 6 3}}` renders `2`.
+//!
+//! `default` and `coalesce` return their first argument that is not empty,
+//! for optional fields: `{{default nickname name}}`.
+//!
+//! `pluralize` and `formatNumber` format counts and numbers for prose:
+//! `{{count}} {{pluralize count "item"}}` and
+//! `{{formatNumber total decimals=2 locale="de"}}`.
+//!
+//! `table` renders an array of objects as a Markdown table:
+//! `{{table rows columns="name,score"}}`.
+//!
+//! `truncateTokens` shortens long text, such as retrieved documents, to a
+//! token budget: `{{truncateTokens document 500 strategy="middle"}}`.
+//!
+//! Used without arguments, the helpers above read the data field of the
+//! same name instead, so a `first` or `round` field still renders with
+//! `{{first}}` or `{{round}}`.
+//!
+//! A value missing from the data renders as its render's [`MissingPolicy`]
+//! says: as nothing, as `null`, or as an error.
+//!
+//! Text inside `{{#verbatim}}...{{/verbatim}}` and raw blocks
+//! (`{{{{raw}}}}...{{{{/raw}}}}`) is never read as role, media, history, or
+//! section markers, so code examples that show them are kept as written.
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, JsonRender, Output, PathAndJson,
+    RenderContext, RenderError, RenderErrorReason, Renderable, ScopedJson,
+};
+use serde_json::Value;
 
-use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext, Renderable};
+use crate::cost::{CHARS_PER_TOKEN, estimate_tokens};
+use crate::parse::escape_markers;
+use crate::types::MissingPolicy;
+
+/// Marks where [`truncate_tokens_helper`] removed text.
+const TRUNCATION_MARKER: &str = "…";
+
+/// Key of the render data entry holding the render's [`MissingPolicy`],
+/// when it is not [`MissingPolicy::Empty`].
+pub(crate) const MISSING_POLICY_KEY: &str = "__on_missing";
+
+/// Names of the helpers [`register_builtin_helpers`] registers, for tools
+/// such as editors and language servers that need to recognize them.
+///
+/// Handlebars's own helpers, like `if` and `each`, are not included.
+pub const BUILTIN_HELPERS: [&str; 26] = [
+    "json",
+    "role",
+    "history",
+    "section",
+    "media",
+    "ifEquals",
+    "unlessEquals",
+    "first",
+    "last",
+    "slice",
+    "join",
+    "length",
+    "add",
+    "subtract",
+    "multiply",
+    "divide",
+    "round",
+    "percent",
+    "default",
+    "coalesce",
+    "pluralize",
+    "formatNumber",
+    "table",
+    "truncateTokens",
+    "verbatim",
+    "raw",
+];
 
 /// Registers all built-in helpers with a Handlebars instance.
 ///
@@ -34,9 +120,233 @@ pub fn register_builtin_helpers(handlebars: &mut Handlebars) {
     handlebars.register_helper("media", Box::new(media_helper));
     handlebars.register_helper("ifEquals", Box::new(if_equals_helper));
     handlebars.register_helper("unlessEquals", Box::new(unless_equals_helper));
+    handlebars.register_helper("first", Box::new(ValueHelper(first_helper)));
+    handlebars.register_helper("last", Box::new(ValueHelper(last_helper)));
+    handlebars.register_helper("slice", Box::new(ValueHelper(slice_helper)));
+    handlebars.register_helper("join", Box::new(ValueHelper(join_helper)));
+    handlebars.register_helper("length", Box::new(ValueHelper(length_helper)));
+    handlebars.register_helper("add", Box::new(ValueHelper(add_helper)));
+    handlebars.register_helper("subtract", Box::new(ValueHelper(subtract_helper)));
+    handlebars.register_helper("multiply", Box::new(ValueHelper(multiply_helper)));
+    handlebars.register_helper("divide", Box::new(ValueHelper(divide_helper)));
+    handlebars.register_helper("round", Box::new(ValueHelper(round_helper)));
+    handlebars.register_helper("percent", Box::new(ValueHelper(percent_helper)));
+    handlebars.register_helper("default", Box::new(ValueHelper(default_helper)));
+    handlebars.register_helper("coalesce", Box::new(ValueHelper(coalesce_helper)));
+    handlebars.register_helper("pluralize", Box::new(ValueHelper(pluralize_helper)));
+    handlebars.register_helper("formatNumber", Box::new(ValueHelper(format_number_helper)));
+    handlebars.register_helper("table", Box::new(ValueHelper(table_helper)));
+    handlebars.register_helper(
+        "truncateTokens",
+        Box::new(ValueHelper(truncate_tokens_helper)),
+    );
+    handlebars.register_helper("verbatim", Box::new(VerbatimHelper));
+    handlebars.register_helper("raw", Box::new(VerbatimHelper));
+    handlebars.register_helper("helperMissing", Box::new(MissingValueHelper));
+}
+
+/// Renders `{{name}}` when `name` is neither a helper nor in the data.
+///
+/// Handlebars also calls this hook for unknown helpers given arguments, and
+/// for unknown helpers in subexpressions; those stay errors.
+struct MissingValueHelper;
+
+impl HelperDef for MissingValueHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if !h.params().is_empty() || !h.hash().is_empty() {
+            return Err(RenderErrorReason::HelperNotFound(h.name().to_string()).into());
+        }
+        if let Some(text) = missing_value(h, ctx)? {
+            out.write(text)?;
+        }
+        Ok(())
+    }
+
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        Err(RenderErrorReason::HelperNotFound(h.name().to_string()).into())
+    }
+}
+
+/// Returns what a missing `{{name}}` renders as under the render's
+/// [`MissingPolicy`]: nothing, `null`, or an error.
+fn missing_value(h: &Helper<'_>, ctx: &Context) -> Result<Option<&'static str>, RenderError> {
+    let policy = ctx
+        .data()
+        .get(MISSING_POLICY_KEY)
+        .and_then(|policy| serde_json::from_value(policy.clone()).ok())
+        .unwrap_or_default();
+    match policy {
+        MissingPolicy::Empty => Ok(None),
+        MissingPolicy::Null => Ok(Some("null")),
+        MissingPolicy::Error => {
+            Err(RenderErrorReason::MissingVariable(Some(h.name().to_string())).into())
+        }
+    }
+}
+
+/// A helper that computes a value rather than writing output, so it can be
+/// used in a subexpression as well as on its own.
+///
+/// Without arguments, the helper's name is read as a variable instead, so
+/// data fields named `first` or `round` still render with `{{first}}`.
+struct ValueHelper(fn(&Helper<'_>) -> Result<Value, RenderError>);
+
+impl HelperDef for ValueHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        if h.params().is_empty() && h.hash().is_empty() {
+            let value = rc.evaluate(ctx, h.name())?;
+            if !value.is_missing() {
+                return Ok(value);
+            }
+            return Ok(missing_value(h, ctx)?.map_or(ScopedJson::Missing, |text| {
+                ScopedJson::Derived(Value::String(text.to_string()))
+            }));
+        }
+        (self.0)(h).map(ScopedJson::Derived)
+    }
+}
+
+/// Returns a helper's first parameter as an array. A missing value is an
+/// empty array.
+fn array_param<'a>(h: &'a Helper<'_>) -> Result<&'a [Value], RenderError> {
+    match h.param(0).map(PathAndJson::value) {
+        Some(Value::Array(items)) => Ok(items),
+        Some(Value::Null) => Ok(&[]),
+        _ => Err(RenderErrorReason::Other(format!("{} requires an array", h.name())).into()),
+    }
+}
+
+/// First element helper.
+///
+/// Returns the first element of an array, or nothing if it is empty.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{first items}}
+/// ```
+fn first_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    Ok(array_param(h)?.first().cloned().unwrap_or(Value::Null))
+}
+
+/// Last element helper.
+///
+/// Returns the last element of an array, or nothing if it is empty.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{last items}}
+/// ```
+fn last_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    Ok(array_param(h)?.last().cloned().unwrap_or(Value::Null))
+}
+
+/// Array slice helper.
+///
+/// Returns the elements from `start` up to, but not including, `end`, like
+/// JavaScript's `Array.prototype.slice`. Negative indices count from the end
+/// of the array, and `end` defaults to its length.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{#each (slice items 0 3)}}{{this}}{{/each}}
+/// {{json (slice items -2)}}
+/// ```
+fn slice_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let items = array_param(h)?;
+    let index = |position: usize, default: usize| -> Result<usize, RenderError> {
+        let Some(param) = h.param(position) else {
+            return Ok(default);
+        };
+        let index = param.value().as_i64().ok_or_else(|| {
+            RenderErrorReason::Other("slice indices must be integers".to_string())
+        })?;
+        let len = i64::try_from(items.len()).unwrap_or(i64::MAX);
+        let index = if index < 0 { len + index } else { index };
+        Ok(usize::try_from(index.clamp(0, len)).unwrap_or(0))
+    };
+    let start = index(1, 0)?;
+    let end = index(2, items.len())?;
+    Ok(Value::Array(
+        items
+            .get(start..end.max(start))
+            .unwrap_or_default()
+            .to_vec(),
+    ))
+}
+
+/// Array join helper.
+///
+/// Joins the elements of an array, rendered as `{{this}}` would render them,
+/// with a separator that defaults to `", "`.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{join tags}}
+/// {{join tags " | "}}
+/// ```
+fn join_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let items = array_param(h)?;
+    let separator = match h.param(1).map(PathAndJson::value) {
+        None => ", ",
+        Some(Value::String(separator)) => separator,
+        Some(_) => {
+            return Err(
+                RenderErrorReason::Other("join separator must be a string".to_string()).into(),
+            );
+        }
+    };
+    let parts: Vec<String> = items.iter().map(JsonRender::render).collect();
+    Ok(Value::String(parts.join(separator)))
+}
 
-    // Register @ prefix variable helpers
-    // Note: Handlebars treats @var as private data, but we expose @state via local path
+/// Length helper.
+///
+/// Returns the number of elements in an array, characters in a string, or
+/// keys in an object. A missing value has length 0.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{length items}} items
+/// {{#if (length items)}}...{{/if}}
+/// ```
+fn length_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let length = match h.param(0).map(PathAndJson::value) {
+        Some(Value::Array(items)) => items.len(),
+        Some(Value::String(text)) => text.chars().count(),
+        Some(Value::Object(fields)) => fields.len(),
+        Some(Value::Null) => 0,
+        _ => {
+            return Err(RenderErrorReason::Other(
+                "length requires an array, string, or object".to_string(),
+            )
+            .into());
+        }
+    };
+    Ok(Value::from(length))
 }
 
 /// JSON serialization helper.
@@ -248,6 +558,42 @@ fn if_equals_helper<'reg, 'rc>(
     Ok(())
 }
 
+/// Verbatim block helper.
+///
+/// Renders its content with any markers escaped, so helpers like `role` and
+/// `media` inside it produce text instead of message structure. Raw blocks
+/// use this helper too, since their content is never a template.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{#verbatim}}
+/// Role markers look like {{marker}}.
+/// {{/verbatim}}
+/// ```
+///
+/// Used without a block, `{{verbatim}}` and `{{raw}}` read the data field of
+/// the same name, like the helpers wrapped in [`ValueHelper`].
+struct VerbatimHelper;
+
+impl HelperDef for VerbatimHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        hbs: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let Some(template) = h.template() else {
+            return ValueHelper(|_| Ok(Value::Null)).call(h, hbs, ctx, rc, out);
+        };
+        let rendered = template.renders(hbs, ctx, rc)?;
+        out.write(&escape_markers(&rendered))?;
+        Ok(())
+    }
+}
+
 /// Conditional inequality block helper.
 ///
 /// Renders content if two values are not equal.
@@ -287,6 +633,488 @@ fn unless_equals_helper<'reg, 'rc>(
     Ok(())
 }
 
+/// Returns a helper parameter as a number, parsing strings.
+fn number_param(h: &Helper<'_>, index: usize) -> Result<serde_json::Number, RenderError> {
+    let number = match h.param(index).map(PathAndJson::value) {
+        Some(Value::Number(number)) => Some(number.clone()),
+        Some(Value::String(text)) => serde_json::from_str(text.trim()).ok(),
+        _ => None,
+    };
+    number.ok_or_else(|| RenderErrorReason::Other(format!("{} requires numbers", h.name())).into())
+}
+
+/// Returns a helper parameter as a float, parsing strings.
+fn float_param(h: &Helper<'_>, index: usize) -> Result<f64, RenderError> {
+    Ok(number_param(h, index)?.as_f64().unwrap_or_default())
+}
+
+/// Returns the optional number of decimal places a helper rounds to.
+fn places_param(h: &Helper<'_>, index: usize) -> Result<i32, RenderError> {
+    h.param(index).map_or(Ok(0), |param| {
+        param
+            .value()
+            .as_u64()
+            .filter(|places| *places <= 15)
+            .and_then(|places| i32::try_from(places).ok())
+            .ok_or_else(|| {
+                RenderErrorReason::Other(format!(
+                    "{} places must be an integer from 0 to 15",
+                    h.name()
+                ))
+                .into()
+            })
+    })
+}
+
+/// Converts a computed float to a value, as an integer if it is whole.
+fn float_value(h: &Helper<'_>, value: f64) -> Result<Value, RenderError> {
+    /// Largest float below which every whole number is exact.
+    const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+
+    if !value.is_finite() {
+        return Err(RenderErrorReason::Other(format!("{} overflowed", h.name())).into());
+    }
+    if value.fract() == 0.0 && value.abs() < MAX_EXACT {
+        // Whole and within the exact range, so the conversion is lossless.
+        #[allow(clippy::cast_possible_truncation)]
+        return Ok(Value::from(value as i64));
+    }
+    Ok(serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number))
+}
+
+/// Rounds to a number of decimal places, half away from zero.
+fn round_to(value: f64, places: i32) -> f64 {
+    let factor = 10_f64.powi(places);
+    (value * factor).round() / factor
+}
+
+/// Applies a binary operation, on integers when both operands are integers
+/// and the result fits.
+fn arithmetic(
+    h: &Helper<'_>,
+    integer: fn(i64, i64) -> Option<i64>,
+    float: fn(f64, f64) -> f64,
+) -> Result<Value, RenderError> {
+    let (a, b) = (number_param(h, 0)?, number_param(h, 1)?);
+    if let Some(result) = a.as_i64().zip(b.as_i64()).and_then(|(a, b)| integer(a, b)) {
+        return Ok(Value::from(result));
+    }
+    let (a, b) = (
+        a.as_f64().unwrap_or_default(),
+        b.as_f64().unwrap_or_default(),
+    );
+    float_value(h, float(a, b))
+}
+
+/// Addition helper.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{add count 1}}
+/// ```
+fn add_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    arithmetic(h, i64::checked_add, |a, b| a + b)
+}
+
+/// Subtraction helper.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{subtract budget spent}}
+/// ```
+fn subtract_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    arithmetic(h, i64::checked_sub, |a, b| a - b)
+}
+
+/// Multiplication helper.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{multiply price quantity}}
+/// ```
+fn multiply_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    arithmetic(h, i64::checked_mul, |a, b| a * b)
+}
+
+/// Division helper.
+///
+/// Always divides as floats, so `{{divide 7 2}}` renders `3.5`. Dividing by
+/// zero is an error.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{divide total count}}
+/// ```
+fn divide_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    if float_param(h, 1)? == 0.0 {
+        return Err(RenderErrorReason::Other("divide by zero".to_string()).into());
+    }
+    arithmetic(h, |_, _| None, |a, b| a / b)
+}
+
+/// Rounding helper.
+///
+/// Rounds half away from zero, to a whole number or to the given number of
+/// decimal places.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{round score}}
+/// {{round (divide total count) 2}}
+/// ```
+fn round_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let value = float_param(h, 0)?;
+    float_value(h, round_to(value, places_param(h, 1)?))
+}
+
+/// Percentage helper.
+///
+/// Returns `value` as a percentage of `total`, without a `%` sign, rounded
+/// to a whole number or to the given number of decimal places. A zero total
+/// is an error.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{percent done total}}% complete
+/// {{percent done total 1}}%
+/// ```
+fn percent_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let (value, total) = (float_param(h, 0)?, float_param(h, 1)?);
+    if total == 0.0 {
+        return Err(RenderErrorReason::Other("percent of a zero total".to_string()).into());
+    }
+    float_value(h, round_to(value / total * 100.0, places_param(h, 2)?))
+}
+
+/// Returns the first parameter that is not missing, null, an empty string, or
+/// an empty array, or nothing if they all are.
+///
+/// `0` and `false` are not empty.
+fn first_present(h: &Helper<'_>) -> Value {
+    h.params()
+        .iter()
+        .map(PathAndJson::value)
+        .find(|value| !matches!(value, Value::Null) && !is_empty(value))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+/// Returns whether a value is an empty string or array.
+const fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::String(text) => text.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// Default value helper.
+///
+/// Returns `value` unless it is empty, and `fallback` otherwise. See
+/// [`first_present`] for what counts as empty.
+///
+/// # Example
+///
+/// ```handlebars
+/// Hello, {{default nickname "friend"}}!
+/// ```
+fn default_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    if h.params().len() != 2 {
+        return Err(RenderErrorReason::Other(
+            "default requires a value and a fallback".to_string(),
+        )
+        .into());
+    }
+    Ok(first_present(h))
+}
+
+/// Coalescing helper.
+///
+/// Returns the first of its arguments that is not empty. See
+/// [`first_present`] for what counts as empty.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{coalesce displayName username "anonymous"}}
+/// ```
+fn coalesce_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    if h.params().is_empty() {
+        return Err(RenderErrorReason::Other(
+            "coalesce requires at least one argument".to_string(),
+        )
+        .into());
+    }
+    Ok(first_present(h))
+}
+
+/// Pluralization helper.
+///
+/// Returns the singular form when the count is 1 or -1, and the plural form
+/// otherwise. The plural defaults to the singular with an `s` appended.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{count}} {{pluralize count "item"}}
+/// {{count}} {{pluralize count "person" "people"}}
+/// ```
+fn pluralize_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let count = float_param(h, 0)?;
+    let word = |index: usize| h.param(index).and_then(|param| param.value().as_str());
+    let singular = word(1).ok_or_else(|| {
+        RenderErrorReason::Other("pluralize requires a count and a singular form".to_string())
+    })?;
+    let word = if (count.abs() - 1.0).abs() < f64::EPSILON {
+        singular.to_string()
+    } else {
+        word(2).map_or_else(|| format!("{singular}s"), str::to_string)
+    };
+    Ok(Value::String(word))
+}
+
+/// Returns the digit group and decimal separators of a locale, by its
+/// language.
+fn number_separators(locale: &str) -> Option<(&'static str, &'static str)> {
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    match language.to_ascii_lowercase().as_str() {
+        "en" | "ja" | "ko" | "zh" | "th" | "he" => Some((",", ".")),
+        "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" => Some((".", ",")),
+        "fr" | "nb" | "no" | "sv" | "fi" | "pl" | "cs" | "ru" | "uk" => Some(("\u{a0}", ",")),
+        _ => None,
+    }
+}
+
+/// Number formatting helper.
+///
+/// Groups the digits of a number by thousands with the separators of a
+/// locale, which defaults to `en`. With `decimals`, the number is rounded to
+/// exactly that many decimal places; otherwise it keeps up to three.
+///
+/// Locales are matched by language: `en`, `ja`, `ko`, `zh`, `th`, and `he`
+/// group with `,`; `de`, `es`, `it`, `nl`, `pt`, `id`, `tr`, and `da` with
+/// `.`; and `fr`, `nb`, `no`, `sv`, `fi`, `pl`, `cs`, `ru`, and `uk` with a
+/// no-break space. Other locales are an error.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{formatNumber 1234567.891}}                      {{!-- 1,234,567.891 --}}
+/// {{formatNumber price decimals=2 locale="de-DE"}}  {{!-- 1.234,50 --}}
+/// ```
+fn format_number_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let number = number_param(h, 0)?;
+    let locale = match h.hash_get("locale").map(PathAndJson::value) {
+        None => "en",
+        Some(Value::String(locale)) => locale,
+        Some(_) => {
+            return Err(RenderErrorReason::Other(
+                "formatNumber locale must be a string".to_string(),
+            )
+            .into());
+        }
+    };
+    let (group, decimal) = number_separators(locale).ok_or_else(|| {
+        RenderErrorReason::Other(format!("formatNumber does not support locale '{locale}'"))
+    })?;
+    let decimals = match h.hash_get("decimals").map(PathAndJson::value) {
+        None => None,
+        Some(value) => Some(
+            value
+                .as_u64()
+                .filter(|decimals| *decimals <= 15)
+                .and_then(|decimals| usize::try_from(decimals).ok())
+                .ok_or_else(|| {
+                    RenderErrorReason::Other(
+                        "formatNumber decimals must be an integer from 0 to 15".to_string(),
+                    )
+                })?,
+        ),
+    };
+
+    let value = number.as_f64().unwrap_or_default();
+    let text = match (decimals, number.as_i64()) {
+        (None, Some(integer)) => integer.to_string(),
+        (None, None) => {
+            let text = format!("{:.3}", round_to(value, 3));
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+        (Some(decimals), _) => {
+            let places = i32::try_from(decimals).unwrap_or_default();
+            format!("{:.decimals$}", round_to(value, places))
+        }
+    };
+
+    let (sign, digits) = text
+        .strip_prefix('-')
+        .map_or(("", text.as_str()), |digits| ("-", digits));
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push_str(group);
+        }
+        grouped.push(digit);
+    }
+    if !fraction.is_empty() {
+        grouped.push_str(decimal);
+        grouped.push_str(fraction);
+    }
+    Ok(Value::String(format!("{sign}{grouped}")))
+}
+
+/// Markdown table helper.
+///
+/// Renders an array of objects as a Markdown table, one row per object. The
+/// `columns` hash lists the keys to show, separated by commas; by default
+/// every key is shown, in the order keys first appear. Cells render as
+/// `{{this}}` would, except that arrays and objects render as JSON, and `|`
+/// and line breaks are escaped so each row stays on one line.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{table results columns="name,score"}}
+/// ```
+///
+/// renders
+///
+/// ```markdown
+/// | name | score |
+/// | --- | --- |
+/// | Ada | 10 |
+/// ```
+fn table_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let rows = array_param(h)?
+        .iter()
+        .map(|row| {
+            row.as_object().ok_or_else(|| {
+                RenderError::from(RenderErrorReason::Other(
+                    "table rows must be objects".to_string(),
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let columns: Vec<String> = match h.hash_get("columns").map(PathAndJson::value) {
+        Some(Value::String(columns)) => columns
+            .split(',')
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Some(_) => {
+            return Err(
+                RenderErrorReason::Other("table columns must be a string".to_string()).into(),
+            );
+        }
+        None => {
+            let mut columns: Vec<String> = Vec::new();
+            for key in rows.iter().flat_map(|row| row.keys()) {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+            columns
+        }
+    };
+    if columns.is_empty() {
+        return Ok(Value::String(String::new()));
+    }
+
+    let cell = |value: Option<&Value>| {
+        let text = match value {
+            None | Some(Value::Null) => String::new(),
+            Some(value @ (Value::Array(_) | Value::Object(_))) => value.to_string(),
+            Some(value) => value.render(),
+        };
+        text.replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>")
+    };
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+    let mut lines = vec![
+        line(
+            columns
+                .iter()
+                .map(|column| cell(Some(&Value::from(column.as_str()))))
+                .collect(),
+        ),
+        line(vec!["---".to_string(); columns.len()]),
+    ];
+    for row in rows {
+        lines.push(line(
+            columns.iter().map(|column| cell(row.get(column))).collect(),
+        ));
+    }
+    Ok(Value::String(lines.join("\n")))
+}
+
+/// Token truncation helper.
+///
+/// Shortens text to about a number of tokens, counted with the same
+/// estimate as [`estimate_cost`](crate::cost::estimate_cost), and marks the
+/// cut with `…`. With `strategy="end"`, the default, the end of the text is
+/// dropped; with `strategy="middle"`, the middle is, keeping the start and
+/// end. Text within the budget is unchanged, and a missing value renders as
+/// nothing.
+///
+/// # Example
+///
+/// ```handlebars
+/// {{truncateTokens document 500}}
+/// {{truncateTokens log 200 strategy="middle"}}
+/// ```
+fn truncate_tokens_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let text = match h.param(0).map(PathAndJson::value) {
+        None | Some(Value::Null) => return Ok(Value::String(String::new())),
+        Some(value) => value.render(),
+    };
+    let budget = h
+        .param(1)
+        .and_then(|param| param.value().as_u64())
+        .and_then(|budget| usize::try_from(budget).ok())
+        .ok_or_else(|| {
+            RenderErrorReason::Other(
+                "truncateTokens requires text and a non-negative token budget".to_string(),
+            )
+        })?;
+    let middle = match h.hash_get("strategy").map(PathAndJson::value) {
+        None => false,
+        Some(Value::String(strategy)) if strategy == "end" => false,
+        Some(Value::String(strategy)) if strategy == "middle" => true,
+        Some(strategy) => {
+            return Err(RenderErrorReason::Other(format!(
+                "truncateTokens strategy must be \"end\" or \"middle\", not {strategy}"
+            ))
+            .into());
+        }
+    };
+    if estimate_tokens(&text) <= budget {
+        return Ok(Value::String(text));
+    }
+
+    let keep = (budget * CHARS_PER_TOKEN).saturating_sub(TRUNCATION_MARKER.chars().count());
+    let chars: Vec<char> = text.chars().collect();
+    let (head, tail) = if middle {
+        (keep.div_ceil(2), keep / 2)
+    } else {
+        (keep, 0)
+    };
+    let head: String = chars[..head].iter().collect();
+    let tail: String = chars[chars.len() - tail..].iter().collect();
+    Ok(Value::String(format!(
+        "{}{TRUNCATION_MARKER}{}",
+        head.trim_end(),
+        tail.trim_start()
+    )))
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
@@ -429,6 +1257,59 @@ mod tests {
         assert_eq!(result, "<<<dotprompt:role:model>>>");
     }
 
+    #[test]
+    fn test_builtin_helpers_are_registered() {
+        let mut hbs = Handlebars::new();
+        register_builtin_helpers(&mut hbs);
+
+        for name in BUILTIN_HELPERS {
+            let result = hbs.render_template(&format!("{{{{{name} 1}}}}"), &json!({}));
+            assert!(
+                !matches!(
+                    result.as_ref().map_err(RenderError::reason),
+                    Err(RenderErrorReason::HelperNotFound(_))
+                ),
+                "{name} is not registered"
+            );
+        }
+        let result = hbs.render_template("{{missing 1}}", &json!({}));
+        assert!(matches!(
+            result.as_ref().map_err(RenderError::reason),
+            Err(RenderErrorReason::HelperNotFound(_))
+        ));
+    }
+
+    // Verbatim helper tests
+
+    #[test]
+    fn test_verbatim_helper_escapes_markers() {
+        let mut hbs = Handlebars::new();
+        register_builtin_helpers(&mut hbs);
+
+        let data = json!({"name": "Ada"});
+        let result = hbs
+            .render_template(
+                "{{#verbatim}}{{name}} {{role \"model\"}}{{/verbatim}}",
+                &data,
+            )
+            .expect("render");
+        assert_eq!(
+            result,
+            format!("Ada {}", escape_markers("<<<dotprompt:role:model>>>"))
+        );
+
+        let result = hbs
+            .render_template(
+                "{{{{raw}}}}{{name}} <<<dotprompt:history>>>{{{{/raw}}}}",
+                &data,
+            )
+            .expect("render");
+        assert_eq!(
+            result,
+            format!("{{{{name}}}} {}", escape_markers("<<<dotprompt:history>>>"))
+        );
+    }
+
     // History helper tests
 
     #[test]
@@ -629,4 +1510,171 @@ mod tests {
             .expect("render should succeed");
         assert_eq!(result, "not equal");
     }
+
+    // Array helper tests
+
+    #[test]
+    fn test_array_helpers() {
+        let mut hbs = Handlebars::new();
+        register_builtin_helpers(&mut hbs);
+
+        let data = json!({"items": ["a", "b", "c", "d"]});
+        let render = |template: &str| hbs.render_template(template, &data);
+        assert_eq!(
+            render("{{first items}} {{last items}}").expect("render"),
+            "a d"
+        );
+        assert_eq!(
+            render("{{join (slice items 1 -1) \"+\"}}").expect("render"),
+            "b+c"
+        );
+        assert_eq!(render("{{length (slice items 2)}}").expect("render"), "2");
+        assert_eq!(render("{{join (slice items 3 1)}}").expect("render"), "");
+        assert!(render("{{slice items \"x\"}}").is_err());
+        assert!(render("{{length 3}}").is_err());
+    }
+
+    // Math helper tests
+
+    #[test]
+    fn test_math_helpers() {
+        let mut hbs = Handlebars::new();
+        register_builtin_helpers(&mut hbs);
+
+        let data = json!({"a": 7, "b": "2", "big": i64::MAX, "x": 2.5});
+        let render = |template: &str| hbs.render_template(template, &data);
+        assert_eq!(render("{{add a b}}").expect("render"), "9");
+        assert_eq!(render("{{subtract a 10}}").expect("render"), "-3");
+        assert_eq!(render("{{multiply x 2}}").expect("render"), "5");
+        assert_eq!(render("{{divide a b}}").expect("render"), "3.5");
+        assert_eq!(
+            render("{{add big 1}}").expect("render"),
+            "9.223372036854776e+18"
+        );
+        assert_eq!(render("{{round (divide 2 3) 2}}").expect("render"), "0.67");
+        assert_eq!(render("{{round -2.5}}").expect("render"), "-3");
+        assert_eq!(render("{{percent 1 3 1}}").expect("render"), "33.3");
+        assert!(render("{{divide a 0}}").is_err());
+        assert!(render("{{add a missing}}").is_err());
+        assert!(render("{{round x 16}}").is_err());
+    }
+
+    // Default and coalesce helper tests
+
+    #[test]
+    fn test_default_and_coalesce_helpers() {
+        let mut hbs = Handlebars::new();
+        register_builtin_helpers(&mut hbs);
+
+        let data = json!({"empty": "", "none": null, "zero": 0, "list": [], "name": "Ada"});
+        let render = |template: &str| hbs.render_template(template, &data);
+        assert_eq!(render("{{default missing \"x\"}}").expect("render"), "x");
+        assert_eq!(render("{{default zero \"x\"}}").expect("render"), "0");
+        assert_eq!(
+            render("{{coalesce empty none list missing name}}").expect("render"),
+            "Ada"
+        );
+        assert_eq!(render("{{coalesce empty none}}").expect("render"), "");
+        assert_eq!(
+            render("{{length (default empty name)}}").expect("render"),
+            "3"
+        );
+        assert!(render("{{default name}}").is_err());
+        // Without arguments, `coalesce` is read as a variable.
+        assert_eq!(render("{{coalesce}}").expect("render"), "");
+    }
+
+    // Pluralize and formatNumber helper tests
+
+    #[test]
+    fn test_pluralize_helper() {
+        let mut hbs = Handlebars::new();
+        register_builtin_helpers(&mut hbs);
+
+        let render = |template: &str, count: serde_json::Value| {
+            hbs.render_template(template, &json!({"n": count}))
+        };
+        assert_eq!(
+            render("{{pluralize n \"item\"}}", json!(1)).expect("render"),
+            "item"
+        );
+        assert_eq!(
+            render("{{pluralize n \"item\"}}", json!(0)).expect("render"),
+            "items"
+        );
+        assert_eq!(
+            render("{{pluralize n \"person\" \"people\"}}", json!("2")).expect("render"),
+            "people"
+        );
+        assert!(render("{{pluralize n}}", json!(1)).is_err());
+    }
+
+    #[test]
+    fn test_format_number_helper() {
+        let mut hbs = Handlebars::new();
+        register_builtin_helpers(&mut hbs);
+
+        let render = |template: &str, value: serde_json::Value| {
+            hbs.render_template(template, &json!({"v": value}))
+        };
+        assert_eq!(
+            render("{{formatNumber v}}", json!(1_234_567)).expect("render"),
+            "1,234,567"
+        );
+        assert_eq!(
+            render("{{formatNumber v}}", json!(-1234.5)).expect("render"),
+            "-1,234.5"
+        );
+        assert_eq!(
+            render("{{formatNumber v}}", json!(0.1 + 0.2)).expect("render"),
+            "0.3"
+        );
+        assert_eq!(
+            render(
+                "{{formatNumber v decimals=2 locale=\"de-DE\"}}",
+                json!(1234.5)
+            )
+            .expect("render"),
+            "1.234,50"
+        );
+        assert_eq!(
+            render("{{formatNumber v locale=\"fr\"}}", json!(1000)).expect("render"),
+            "1\u{a0}000"
+        );
+        assert_eq!(
+            render("{{formatNumber v decimals=0}}", json!(999.5)).expect("render"),
+            "1,000"
+        );
+        assert!(render("{{formatNumber v locale=\"xx\"}}", json!(1)).is_err());
+    }
+
+    // Table helper tests
+
+    #[test]
+    fn test_table_helper() {
+        let mut hbs = Handlebars::new();
+        hbs.register_escape_fn(handlebars::no_escape);
+        register_builtin_helpers(&mut hbs);
+
+        let data = json!({"rows": [
+            {"name": "Ada", "score": 10},
+            {"name": "B|ob", "note": "two\nlines", "tags": ["x"]},
+        ]});
+        let render = |template: &str| hbs.render_template(template, &data);
+        assert_eq!(
+            render("{{table rows columns=\"name, score\"}}").expect("render"),
+            "| name | score |\n| --- | --- |\n| Ada | 10 |\n| B\\|ob |  |"
+        );
+        assert_eq!(
+            render("{{table rows}}").expect("render"),
+            "| name | score | note | tags |\n| --- | --- | --- | --- |\n\
+             | Ada | 10 |  |  |\n| B\\|ob |  | two<br>lines | [\"x\"] |"
+        );
+        assert_eq!(render("{{table missing}}").expect("render"), "");
+        assert!(render("{{table (slice rows 0 1) columns=1}}").is_err());
+        assert!(
+            hbs.render_template("{{table rows}}", &json!({"rows": [1]}))
+                .is_err()
+        );
+    }
 }
@@ -51,18 +51,49 @@
 #![deny(missing_docs)]
 #![deny(missing_debug_implementations)]
 
+pub mod adapters;
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+pub mod cost;
 pub mod dotprompt;
 pub mod error;
+pub mod examples;
+pub mod formats;
 pub mod helpers;
+pub mod limits;
+pub mod manifest;
+pub mod minify;
+pub mod number;
+pub mod observer;
 pub mod parse;
 pub mod picoschema;
+pub mod registry;
+pub mod resolvers;
+pub mod response;
+pub mod search;
 pub mod store;
 pub mod stores;
+pub mod tool_catalog;
+pub mod trace;
 pub mod types;
 pub mod util;
+pub mod yaml;
 
 // Re-export main types for convenience
-pub use dotprompt::{Dotprompt, DotpromptOptions};
+pub use dotprompt::{Dotprompt, DotpromptOptions, KeyOrder};
 pub use error::{DotpromptError, Result};
-pub use store::{PromptStore, PromptStoreWritable};
+pub use examples::StaticExampleSelector;
+pub use formats::Format;
+pub use limits::{Limit, RenderLimits};
+pub use manifest::{HelperManifest, HelperSpec};
+pub use number::NumberFormat;
+pub use observer::RenderObserver;
+pub use registry::{PromptRegistry, PromptRegistryOptions};
+pub use resolvers::{
+    StoreExampleResolver, StorePartialResolver, StoreSchemaResolver, StoreToolResolver,
+};
+pub use response::{ParsedResponse, ResponseError, SchemaViolation};
+pub use store::{AsyncPromptStore, AsyncPromptStoreWritable, PromptStore, PromptStoreWritable};
+pub use tool_catalog::ToolCatalog;
+pub use trace::{PartTrace, SegmentKind, TraceSegment, TracedPrompt};
 pub use types::*;
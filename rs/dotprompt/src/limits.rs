@@ -0,0 +1,374 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Render-time guard limits.
+//!
+//! A template can produce far more output than its source suggests: an
+//! `{{#each}}` over a large input, nested loops, or partials that include
+//! each other. Services rendering untrusted templates or input can bound
+//! the work done per render with [`RenderLimits`]:
+//!
+//! ```
+//! use dotprompt::{Dotprompt, DotpromptOptions, RenderLimits};
+//!
+//! let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+//!     limits: RenderLimits {
+//!         max_output_bytes: Some(64 * 1024),
+//!         max_partial_depth: Some(8),
+//!         max_each_iterations: Some(10_000),
+//!     },
+//!     ..Default::default()
+//! }));
+//! # let _ = dotprompt;
+//! ```
+//!
+//! A render that exceeds a limit fails with
+//! [`DotpromptError::LimitExceeded`].
+
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::sync::LazyLock;
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::error::DotpromptError;
+
+/// Name of the helper that counts `{{#each}}` iterations.
+pub(crate) const EACH_GUARD_HELPER: &str = "__dotprompt_each_guard";
+
+/// Matches partial references, including those with arguments.
+#[allow(clippy::expect_used)]
+static PARTIAL_REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{~?>\s*([A-Za-z_][\w./:-]*)").expect("internal regex pattern should compile")
+});
+
+thread_local! {
+    /// `{{#each}}` iterations started by the render in progress on this thread.
+    static EACH_ITERATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Limits on the work done by a single render.
+///
+/// Every limit defaults to `None`, meaning unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderLimits {
+    /// Maximum size of the rendered template text, in bytes.
+    ///
+    /// Rendering stops as soon as the limit is crossed. Traced renders also
+    /// count their internal markers, so they may reach the limit sooner.
+    pub max_output_bytes: Option<usize>,
+
+    /// Maximum nesting depth of partials; a template including a partial
+    /// has depth 1.
+    ///
+    /// Partials that include each other have no fixed depth and are
+    /// rejected whenever this limit is set.
+    pub max_partial_depth: Option<usize>,
+
+    /// Maximum number of `{{#each}}` iterations across the whole render,
+    /// including nested loops and loops inside partials.
+    pub max_each_iterations: Option<usize>,
+}
+
+/// A limit in [`RenderLimits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// [`RenderLimits::max_output_bytes`].
+    OutputBytes,
+    /// [`RenderLimits::max_partial_depth`].
+    PartialDepth,
+    /// [`RenderLimits::max_each_iterations`].
+    EachIterations,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OutputBytes => "output size in bytes",
+            Self::PartialDepth => "partial depth",
+            Self::EachIterations => "#each iterations",
+        })
+    }
+}
+
+impl RenderLimits {
+    /// Rejects templates whose partials nest deeper than allowed.
+    pub(crate) fn check_partials(
+        &self,
+        template: &str,
+        partials: &HashMap<String, String>,
+    ) -> Result<(), DotpromptError> {
+        let Some(max) = self.max_partial_depth else {
+            return Ok(());
+        };
+        if partial_depth(template, partials, &mut Vec::new()).is_none_or(|depth| depth > max) {
+            return Err(DotpromptError::LimitExceeded {
+                limit: Limit::PartialDepth,
+                max,
+            });
+        }
+        Ok(())
+    }
+
+    /// Prepares the template for a render and resets the iteration count.
+    pub(crate) fn prepare(&self, template: String) -> String {
+        if self.max_each_iterations.is_none() {
+            return template;
+        }
+        EACH_ITERATIONS.set(0);
+        guard_each(&template)
+    }
+
+    /// Prepares a partial's source so its loops count towards the limit.
+    pub(crate) fn prepare_partial(&self, source: String) -> String {
+        if self.max_each_iterations.is_none() {
+            return source;
+        }
+        guard_each(&source)
+    }
+
+    /// Converts a failed render into a limit error, if a limit caused it.
+    pub(crate) fn exceeded(&self, output: &LimitedOutput) -> Option<DotpromptError> {
+        let (limit, max) = if output.exceeded {
+            (Limit::OutputBytes, self.max_output_bytes?)
+        } else {
+            let max = self.max_each_iterations?;
+            if EACH_ITERATIONS.get() <= max {
+                return None;
+            }
+            (Limit::EachIterations, max)
+        };
+        Some(DotpromptError::LimitExceeded { limit, max })
+    }
+}
+
+/// Returns the deepest partial nesting reachable from `template`, or `None`
+/// if partials include each other.
+fn partial_depth(
+    template: &str,
+    partials: &HashMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Option<usize> {
+    let names: HashSet<&str> = PARTIAL_REFERENCE
+        .captures_iter(template)
+        .filter_map(|cap| cap.get(1))
+        .map(|name| name.as_str())
+        .collect();
+
+    let mut deepest = 0;
+    for name in names {
+        if stack.iter().any(|open| open == name) {
+            return None;
+        }
+        let source = partials.get(name).map_or("", String::as_str);
+        stack.push(name.to_string());
+        let depth = partial_depth(source, partials, stack);
+        stack.pop();
+        deepest = deepest.max(depth? + 1);
+    }
+    Some(deepest)
+}
+
+/// Routes the collection of every `{{#each}}` through [`EachGuard`].
+///
+/// `{{#each items as |item|}}` becomes
+/// `{{#each (__dotprompt_each_guard items) as |item|}}`.
+fn guard_each(template: &str) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        let escaped = rest[..open].ends_with('\\');
+        let (before, tag) = rest.split_at(open + 2);
+        result.push_str(before);
+        rest = tag;
+
+        let trimmed = tag.trim_start_matches('~').trim_start();
+        let Some(after_each) = trimmed.strip_prefix("#each") else {
+            continue;
+        };
+        let param = after_each.trim_start();
+        if escaped || param.len() == after_each.len() {
+            continue;
+        }
+
+        let param_start = tag.len() - param.len();
+        let param_end = param_start + param_len(param);
+        result.push_str(&tag[..param_start]);
+        result.push('(');
+        result.push_str(EACH_GUARD_HELPER);
+        result.push(' ');
+        result.push_str(&tag[param_start..param_end]);
+        result.push(')');
+        rest = &tag[param_end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Returns the length of the first Handlebars parameter in `text`.
+fn param_len(text: &str) -> usize {
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            (None, c) if depth == 0 && (c.is_whitespace() || c == '}' || c == '~') => {
+                return i;
+            }
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Counts `{{#each}}` iterations and fails the render past the limit.
+///
+/// Used as a subexpression wrapping the collection, it sees every loop
+/// (including each pass of a nested one) before it starts.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EachGuard {
+    /// Maximum iterations per render.
+    pub max: usize,
+}
+
+impl HelperDef for EachGuard {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = h.param(0).map_or(&Value::Null, |p| p.value());
+        let iterations = match value {
+            Value::Array(items) => items.len(),
+            Value::Object(fields) => fields.len(),
+            _ => 0,
+        };
+        let total = EACH_ITERATIONS.get().saturating_add(iterations);
+        EACH_ITERATIONS.set(total);
+        if total > self.max {
+            return Err(RenderErrorReason::Other(format!(
+                "#each iterations exceed the limit of {}",
+                self.max
+            ))
+            .into());
+        }
+        Ok(ScopedJson::Derived(value.clone()))
+    }
+}
+
+/// A render output buffer that refuses to grow past a size limit.
+#[derive(Debug, Default)]
+pub(crate) struct LimitedOutput {
+    buffer: Vec<u8>,
+    max: Option<usize>,
+    exceeded: bool,
+}
+
+impl LimitedOutput {
+    /// Creates a buffer holding at most `max` bytes.
+    pub(crate) const fn new(max: Option<usize>) -> Self {
+        Self {
+            buffer: Vec::new(),
+            max,
+            exceeded: false,
+        }
+    }
+
+    /// Returns the rendered text.
+    pub(crate) fn into_string(self) -> String {
+        String::from_utf8_lossy(&self.buffer).into_owned()
+    }
+}
+
+impl io::Write for LimitedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self
+            .max
+            .is_some_and(|max| self.buffer.len().saturating_add(buf.len()) > max)
+        {
+            self.exceeded = true;
+            return Err(io::Error::other("rendered output exceeds the size limit"));
+        }
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_each() {
+        assert_eq!(
+            guard_each("{{#each items}}{{this}}{{/each}}"),
+            "{{#each (__dotprompt_each_guard items)}}{{this}}{{/each}}"
+        );
+        assert_eq!(
+            guard_each("{{~#each (list a \"b c\") as |x|~}}{{x}}{{/each}}"),
+            "{{~#each (__dotprompt_each_guard (list a \"b c\")) as |x|~}}{{x}}{{/each}}"
+        );
+        assert_eq!(
+            guard_each("\\{{#each items}} {{#eachKey x}} {{#each}}"),
+            "\\{{#each items}} {{#eachKey x}} {{#each}}"
+        );
+    }
+
+    #[test]
+    fn test_partial_depth() {
+        let partials: HashMap<String, String> = [
+            ("a", "{{> b name=x}}"),
+            ("b", "leaf"),
+            ("loop", "{{> back}}"),
+            ("back", "{{#if x}}{{> loop}}{{/if}}"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        assert_eq!(partial_depth("plain", &partials, &mut Vec::new()), Some(0));
+        assert_eq!(
+            partial_depth("{{> b}} {{> a}}", &partials, &mut Vec::new()),
+            Some(2)
+        );
+        assert_eq!(
+            partial_depth("{{> loop}}", &partials, &mut Vec::new()),
+            None
+        );
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helper manifests.
+//!
+//! Applications supply custom helpers at runtime with
+//! [`Dotprompt::define_helper`](crate::Dotprompt::define_helper), so their
+//! names and usage are invisible to tooling. A `helpers.toml` manifest
+//! describes them instead:
+//!
+//! ```toml
+//! [[helpers]]
+//! name = "formatDate"
+//! signature = "formatDate date [format]"
+//! doc = "Formats a date using the given strftime pattern."
+//!
+//! [[helpers]]
+//! name = "card"
+//! block = true
+//! doc = "Wraps its contents in a card."
+//! ```
+//!
+//! The manifest only carries documentation; behavior still comes from the
+//! registered helper.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DotpromptError, Result};
+
+/// A description of one custom helper.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelperSpec {
+    /// Name the helper is registered under.
+    pub name: String,
+
+    /// How the helper is called, e.g. `formatDate date [format]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+
+    /// Markdown documentation for the helper.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+
+    /// Whether the helper wraps a block (`{{#name}}...{{/name}}`).
+    #[serde(default)]
+    pub block: bool,
+}
+
+/// A set of custom helper descriptions, usually loaded from `helpers.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelperManifest {
+    /// The described helpers.
+    #[serde(default)]
+    pub helpers: Vec<HelperSpec>,
+}
+
+impl HelperManifest {
+    /// Conventional file name for a helper manifest.
+    pub const FILE_NAME: &'static str = "helpers.toml";
+
+    /// Parses a manifest from TOML source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is not a valid manifest or names a
+    /// helper twice.
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let manifest: Self = toml::from_str(source)
+            .map_err(|e| DotpromptError::HelperManifestError(e.to_string()))?;
+        for (i, helper) in manifest.helpers.iter().enumerate() {
+            if manifest.helpers[..i].iter().any(|h| h.name == helper.name) {
+                return Err(DotpromptError::HelperManifestError(format!(
+                    "helper '{}' is declared more than once",
+                    helper.name
+                )));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Loads a manifest from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid manifest.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        Self::from_toml(&source).map_err(|e| match e {
+            DotpromptError::HelperManifestError(msg) => {
+                DotpromptError::HelperManifestError(format!("{}: {msg}", path.display()))
+            }
+            other => other,
+        })
+    }
+
+    /// Looks up a helper by name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&HelperSpec> {
+        self.helpers.iter().find(|h| h.name == name)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let manifest = HelperManifest::from_toml(
+            r#"
+[[helpers]]
+name = "formatDate"
+signature = "formatDate date [format]"
+doc = "Formats a date."
+
+[[helpers]]
+name = "card"
+block = true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.helpers.len(), 2);
+        let format_date = manifest.get("formatDate").unwrap();
+        assert_eq!(
+            format_date.signature.as_deref(),
+            Some("formatDate date [format]")
+        );
+        assert!(!format_date.block);
+        assert!(manifest.get("card").unwrap().block);
+        assert!(manifest.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_from_toml_rejects_duplicates_and_bad_fields() {
+        let duplicate = "[[helpers]]\nname = \"a\"\n[[helpers]]\nname = \"a\"\n";
+        assert!(matches!(
+            HelperManifest::from_toml(duplicate),
+            Err(DotpromptError::HelperManifestError(_))
+        ));
+        assert!(HelperManifest::from_toml("[[helpers]]\nblock = true\n").is_err());
+        assert_eq!(
+            HelperManifest::from_toml("").unwrap(),
+            HelperManifest::default()
+        );
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Template minification for deployment.
+//!
+//! Every whitespace character in a template body ends up in the rendered
+//! prompt and is paid for in tokens. [`minify_template`] produces a compact
+//! copy of a `.prompt` source for deployment, so the checked-in source can
+//! stay readable:
+//!
+//! - Handlebars comments (`{{! }}` and `{{!-- --}}`) are removed.
+//! - Indentation, trailing whitespace, and runs of spaces are collapsed, and
+//!   consecutive blank lines become one.
+//! - Fenced code blocks and the contents of `{{ }}` tags are kept verbatim.
+//! - With [`MinifyOptions::partials`], partial references without arguments
+//!   are replaced by the partial's source, unless the template defines a
+//!   partial of that name inline.
+//!
+//! The frontmatter is kept as-is; only the license header before it, which
+//! the parser ignores, is dropped.
+//!
+//! ```
+//! use dotprompt::minify::{MinifyOptions, minify_template};
+//!
+//! let source = "---\nmodel: gemini\n---\n{{!-- greeting --}}\n    Hello,   {{name}}!\n\n\n\nBye.\n";
+//! assert_eq!(
+//!     minify_template(source, &MinifyOptions::default()).unwrap(),
+//!     "---\nmodel: gemini\n---\nHello, {{name}}!\n\nBye.\n"
+//! );
+//! ```
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::error::Result;
+use crate::parse::{extract_frontmatter_and_body, identify_inline_partials};
+
+/// Matches partial references without arguments or whitespace control.
+#[allow(clippy::expect_used)]
+static PARTIAL_REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{>\s*([A-Za-z_][\w./-]*)\s*\}\}")
+        .expect("internal regex pattern should compile")
+});
+
+/// Matches the end of a `{{!-- --}}` comment.
+#[allow(clippy::expect_used)]
+static LONG_COMMENT_END: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"--~?\}\}").expect("internal regex pattern should compile"));
+
+/// Options for [`minify_template`].
+#[derive(Debug, Clone, Default)]
+pub struct MinifyOptions {
+    /// Partial sources by name. When set, references to these partials
+    /// that take no arguments are inlined.
+    pub partials: Option<HashMap<String, String>>,
+}
+
+/// Returns a minified copy of a `.prompt` source.
+///
+/// # Errors
+///
+/// Returns an error if the frontmatter cannot be separated from the body.
+pub fn minify_template(source: &str, options: &MinifyOptions) -> Result<String> {
+    let (yaml, body) = extract_frontmatter_and_body(source)?;
+    let body = match &options.partials {
+        Some(partials) => {
+            // Partials defined inline take precedence over registered ones.
+            let mut partials = partials.clone();
+            for name in identify_inline_partials(&body) {
+                partials.remove(&name);
+            }
+            inline_partials(&body, &partials, &mut Vec::new())
+        }
+        None => body,
+    };
+
+    let mut result = String::new();
+    if !yaml.is_empty() {
+        result.push_str("---\n");
+        result.push_str(&yaml);
+        result.push_str("\n---\n");
+    }
+    result.push_str(&minify_body(&body));
+    // The parser trims the body of a prompt with frontmatter, so the file
+    // can end with a newline either way.
+    if !yaml.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Replaces references to known partials with their source, recursively.
+///
+/// A reference alone on its line takes the line's newline with it, as
+/// Handlebars does for standalone partials. References to partials that
+/// include themselves are left alone.
+fn inline_partials(
+    template: &str,
+    partials: &HashMap<String, String>,
+    stack: &mut Vec<String>,
+) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut last = 0;
+    for cap in PARTIAL_REFERENCE.captures_iter(template) {
+        let (Some(reference), Some(name)) = (cap.get(0), cap.get(1)) else {
+            continue;
+        };
+        let name = name.as_str();
+        let Some(source) = partials.get(name) else {
+            continue;
+        };
+        if template[..reference.start()].ends_with('\\') || stack.iter().any(|open| open == name) {
+            continue;
+        }
+
+        let line_start = template[..reference.start()]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let line_end = template[reference.end()..]
+            .find('\n')
+            .map_or(template.len(), |i| reference.end() + i + 1);
+        let standalone = line_start >= last
+            && template[line_start..reference.start()].trim().is_empty()
+            && template[reference.end()..line_end].trim().is_empty();
+        let (start, end) = if standalone {
+            (line_start, line_end)
+        } else {
+            (reference.start(), reference.end())
+        };
+
+        result.push_str(&template[last..start]);
+        stack.push(name.to_string());
+        result.push_str(&inline_partials(source, partials, stack));
+        stack.pop();
+        last = end;
+    }
+    result.push_str(&template[last..]);
+    result
+}
+
+/// Minifies a template body.
+fn minify_body(body: &str) -> String {
+    let mut out = Output::default();
+    let mut in_fence = false;
+    let mut text = String::new();
+    for line in body.split_inclusive('\n') {
+        let fence_line = {
+            let trimmed = line.trim_start();
+            trimmed.starts_with("```") || trimmed.starts_with("~~~")
+        };
+        if in_fence || fence_line {
+            if !text.is_empty() {
+                minify_text(&std::mem::take(&mut text), &mut out);
+            }
+            out.verbatim(line.trim_end_matches('\n'));
+            if line.ends_with('\n') {
+                out.text_char('\n');
+            }
+            in_fence = in_fence != fence_line;
+        } else {
+            text.push_str(line);
+        }
+    }
+    minify_text(&text, &mut out);
+    out.finish()
+}
+
+/// Minifies a region outside code fences, copying tags verbatim and
+/// dropping comments.
+fn minify_text(text: &str, out: &mut Output) {
+    let mut rest = text;
+    while let Some(open) = rest.find("{{") {
+        let escaped = rest[..open].ends_with('\\');
+        for c in rest[..open].chars() {
+            out.text_char(c);
+        }
+        let tag = &rest[open..];
+        if escaped {
+            out.verbatim("{{");
+            rest = &tag[2..];
+            continue;
+        }
+
+        let inner = tag[2..].trim_start_matches('~');
+        let (close, len) = if inner.starts_with("!--") {
+            (LONG_COMMENT_END.find(tag).map(|m| m.end() - 2), 2)
+        } else if tag.starts_with("{{{{") {
+            (tag.find("}}}}"), 4)
+        } else if tag.starts_with("{{{") {
+            (tag.find("}}}"), 3)
+        } else {
+            (tag.find("}}"), 2)
+        };
+        let Some(close) = close else {
+            // Unterminated: keep the rest as-is.
+            out.verbatim(tag);
+            return;
+        };
+        let end = close + len;
+
+        if inner.starts_with('!') {
+            let after = &tag[end..];
+            let trim_before = tag[2..].starts_with('~');
+            let trim_after = tag[..close].ends_with('~');
+            let line_rest = after.find('\n').map_or(after, |i| &after[..i]);
+            if !out.line_has_content && line_rest.trim().is_empty() {
+                // A comment alone on its line removes the whole line.
+                rest = after.get(line_rest.len() + 1..).unwrap_or("");
+                out.pending_space = false;
+                continue;
+            }
+            out.comment(trim_before, trim_after);
+        } else {
+            out.verbatim(&tag[..end]);
+        }
+        rest = &tag[end..];
+    }
+    for c in rest.chars() {
+        out.text_char(c);
+    }
+}
+
+/// Minified output, with whitespace held back until the next content.
+#[derive(Debug, Default)]
+struct Output {
+    text: String,
+    /// A space is due before the next content.
+    pending_space: bool,
+    /// Newlines due before the next content.
+    pending_newlines: usize,
+    /// The current line has content.
+    line_has_content: bool,
+    /// Whitespace is dropped until the next content.
+    skip_whitespace: bool,
+}
+
+impl Output {
+    /// Adds a character of template text.
+    fn text_char(&mut self, c: char) {
+        match c {
+            '\n' if !self.skip_whitespace => {
+                self.pending_space = false;
+                self.pending_newlines += 1;
+                self.line_has_content = false;
+            }
+            ' ' | '\t' | '\r' | '\n' => {
+                if self.line_has_content && !self.skip_whitespace {
+                    self.pending_space = true;
+                }
+            }
+            c => {
+                self.flush();
+                self.text.push(c);
+            }
+        }
+    }
+
+    /// Adds content that is copied as-is.
+    fn verbatim(&mut self, s: &str) {
+        self.flush();
+        self.text.push_str(s);
+    }
+
+    /// Drops a comment, applying its whitespace control.
+    const fn comment(&mut self, trim_before: bool, trim_after: bool) {
+        if trim_before {
+            self.pending_space = false;
+            self.pending_newlines = 0;
+        }
+        if trim_after {
+            self.skip_whitespace = true;
+        }
+    }
+
+    /// Writes held-back whitespace ahead of new content.
+    fn flush(&mut self) {
+        if self.pending_newlines > 0 && !self.text.is_empty() {
+            self.text.push_str(if self.pending_newlines > 1 {
+                "\n\n"
+            } else {
+                "\n"
+            });
+        } else if self.pending_space {
+            self.text.push(' ');
+        }
+        self.pending_space = false;
+        self.pending_newlines = 0;
+        self.line_has_content = true;
+        self.skip_whitespace = false;
+    }
+
+    /// Returns the output, ending with a newline if the body did.
+    fn finish(mut self) -> String {
+        if self.pending_newlines > 0 && !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        self.text
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn minify(source: &str) -> String {
+        minify_template(source, &MinifyOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_minify_collapses_whitespace() {
+        assert_eq!(
+            minify("  Hello,\t\tworld!   \n\n\n\n  {{#if x}}\n    {{x}}  {{y}}\n  {{/if}}\n"),
+            "Hello, world!\n\n{{#if x}}\n{{x}} {{y}}\n{{/if}}\n"
+        );
+        assert_eq!(
+            minify("{{json  data  indent=2}} {{role \"a  b\"}} \\{{  x  }}"),
+            "{{json  data  indent=2}} {{role \"a  b\"}} \\{{ x }}"
+        );
+    }
+
+    #[test]
+    fn test_minify_keeps_code_fences() {
+        assert_eq!(
+            minify("Run:\n\n\n```py\n  if x:\n      pass\n\n\n```\n  done"),
+            "Run:\n\n```py\n  if x:\n      pass\n\n\n```\ndone"
+        );
+    }
+
+    #[test]
+    fn test_minify_removes_comments() {
+        assert_eq!(
+            minify("a\n  {{!-- a }} comment --}}  \nb {{! inline }} c\nd   {{~! trim ~}}   e"),
+            "a\nb c\nde"
+        );
+    }
+
+    #[test]
+    fn test_minify_inlines_partials() {
+        let partials: HashMap<String, String> = [
+            ("sig", "--  {{team}}\n"),
+            ("outer", "[{{> sig}}]"),
+            ("self", "{{> self}}"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let options = MinifyOptions {
+            partials: Some(partials),
+        };
+        assert_eq!(
+            minify_template(
+                "Hi\n  {{> sig}}\n{{> outer}} {{> self}} {{> sig x=1}} {{> missing}}",
+                &options
+            )
+            .unwrap(),
+            "Hi\n-- {{team}}\n[-- {{team}}\n] {{> self}} {{> sig x=1}} {{> missing}}"
+        );
+        assert_eq!(
+            minify_template("{{#*inline \"sig\"}}local{{/inline}}{{> sig}}", &options).unwrap(),
+            "{{#*inline \"sig\"}}local{{/inline}}{{> sig}}"
+        );
+    }
+}
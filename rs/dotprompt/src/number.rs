@@ -0,0 +1,220 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Formatting of numbers interpolated into templates.
+//!
+//! Handlebars prints a number the way `serde_json` does, so a price parsed
+//! from `3.0` renders as `3.0` and a rate of `0.000002` as `2e-6`, where the
+//! JavaScript implementation prints `3` and `0.000002`. [`NumberFormat`] chooses how the
+//! numbers in the render data print; the default matches JavaScript's
+//! `String(number)`.
+//!
+//! The numbers are rewritten in the data before rendering. Where the
+//! formatted text cannot be printed by a JSON number, the number is replaced
+//! by a string, which helpers such as `json` and comparisons then see.
+
+use std::sync::Arc;
+
+use serde_json::{Number, Value};
+
+/// Formats a number for a template, or returns `None` to leave it as is.
+pub type NumberFormatter = Arc<dyn Fn(&Number) -> Option<String> + Send + Sync>;
+
+/// How numbers in the render data print.
+#[derive(Clone, Default)]
+pub enum NumberFormat {
+    /// As JavaScript's `String(number)` prints them: `3`, `0.000002`,
+    /// `1e+21`.
+    ///
+    /// Whole floats below 2^53 become integers. Other floats whose
+    /// JavaScript text differs from the `serde_json` text (some below
+    /// `0.00001`, and whole ones from 2^53 up) become strings.
+    #[default]
+    JavaScript,
+    /// As `serde_json` prints them: `3.0`, `2e-6`, `1e+21`.
+    Rust,
+    /// With a function. Numbers it formats become strings.
+    Custom(NumberFormatter),
+}
+
+impl std::fmt::Debug for NumberFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::JavaScript => f.write_str("JavaScript"),
+            Self::Rust => f.write_str("Rust"),
+            Self::Custom(_) => f.write_str("Custom(<formatter>)"),
+        }
+    }
+}
+
+/// Returns a number as JavaScript's `String(number)` does.
+///
+/// Both use the shortest digits that round-trip; JavaScript writes them
+/// without an exponent from `1e-7` (exclusive) up to `1e21` (exclusive).
+#[must_use]
+pub fn to_js_string(x: f64) -> String {
+    if x.is_nan() {
+        return "NaN".to_string();
+    }
+    if x.is_infinite() {
+        return if x > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    // Rust's `{:e}` gives the shortest round-trip digits, e.g. `1.2345e3`.
+    let scientific = format!("{:e}", x.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .unwrap_or((scientific.as_str(), "0"));
+    let digits = mantissa.replace('.', "");
+    let k = i32::try_from(digits.len()).unwrap_or(i32::MAX);
+    // The decimal point goes after `n` digits.
+    let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+
+    let body = if k <= n && n <= 21 {
+        digits + &"0".repeat((n - k).unsigned_abs() as usize)
+    } else if 0 < n && n <= 21 {
+        let (whole, fraction) = digits.split_at(n.unsigned_abs() as usize);
+        format!("{whole}.{fraction}")
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat(n.unsigned_abs() as usize))
+    } else {
+        let exponent = n - 1;
+        let sign = if exponent < 0 { '-' } else { '+' };
+        let (first, rest) = digits.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        format!("{first}{point}{rest}e{sign}{}", exponent.unsigned_abs())
+    };
+    if x < 0.0 { format!("-{body}") } else { body }
+}
+
+/// Rewrites the numbers in `value` for `format`.
+pub(crate) fn apply(format: &NumberFormat, value: &mut Value) {
+    match value {
+        Value::Number(number) => {
+            if let Some(formatted) = reformat(format, number) {
+                *value = formatted;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                apply(format, item);
+            }
+        }
+        Value::Object(fields) => {
+            for field in fields.values_mut() {
+                apply(format, field);
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::String(_) => {}
+    }
+}
+
+/// Returns the value to render in place of a number, if it changes.
+fn reformat(format: &NumberFormat, number: &Number) -> Option<Value> {
+    /// Largest float below which every whole number is exact.
+    const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+
+    match format {
+        NumberFormat::Rust => None,
+        NumberFormat::Custom(formatter) => formatter(number).map(Value::String),
+        NumberFormat::JavaScript => {
+            let x = number.as_f64().filter(|_| number.is_f64())?;
+            if x.fract() == 0.0 && x.abs() < MAX_EXACT {
+                // Whole and within the exact range, so the conversion is lossless.
+                #[allow(clippy::cast_possible_truncation)]
+                return Some(Value::from(x as i64));
+            }
+            let text = to_js_string(x);
+            (text != number.to_string()).then_some(Value::String(text))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::{DataArgument, Dotprompt, DotpromptOptions, Part, RenderedPrompt, TextPart};
+    use serde_json::json;
+
+    #[test]
+    fn test_matches_javascript_string() {
+        // Expected values are `String(x)` in Node.js.
+        let cases = [
+            (3.0, "3"),
+            (-0.0, "0"),
+            (100.0, "100"),
+            (1234.5, "1234.5"),
+            (-42.25, "-42.25"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (0.00001, "0.00001"),
+            (0.000_001, "0.000001"),
+            (1e-7, "1e-7"),
+            (1.5e-7, "1.5e-7"),
+            (5e-324, "5e-324"),
+            (9_007_199_254_740_992.0, "9007199254740992"),
+            (1e20, "100000000000000000000"),
+            (123_456_789_012_345_680_000.0, "123456789012345680000"),
+            (1e21, "1e+21"),
+            (-2.5e25, "-2.5e+25"),
+            (1.797_693_134_862_315_7e308, "1.7976931348623157e+308"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NAN, "NaN"),
+        ];
+        for (x, expected) in cases {
+            assert_eq!(to_js_string(x), expected, "{x:e}");
+        }
+    }
+
+    #[test]
+    fn test_render_number_formats() {
+        let render = |number_format| {
+            let dp = Dotprompt::new(Some(DotpromptOptions {
+                number_format,
+                ..Default::default()
+            }));
+            let data = DataArgument {
+                input: Some(json!({
+                    "price": 3.0,
+                    "count": 7,
+                    "rate": 0.000_002,
+                    "big": 1e21,
+                    "items": [{"weight": 2.0}],
+                })),
+                ..Default::default()
+            };
+            let rendered: RenderedPrompt = dp
+                .render(
+                    "{{price}} {{count}} {{rate}} {{big}} {{#each items}}{{weight}}{{/each}} \
+                     {{#if (gt price 2)}}gt{{/if}}",
+                    &data,
+                    None,
+                )
+                .expect("render");
+            match &rendered.messages[0].content[0] {
+                Part::Text(TextPart { text, .. }) => text.clone(),
+                _ => String::new(),
+            }
+        };
+
+        assert_eq!(render(NumberFormat::JavaScript), "3 7 0.000002 1e+21 2 gt");
+        assert_eq!(render(NumberFormat::Rust), "3.0 7 2e-6 1e+21 2.0 gt");
+        let cents = |number: &Number| number.as_f64().map(|x| format!("{x:.2}"));
+        assert_eq!(
+            render(NumberFormat::Custom(Arc::new(cents))),
+            "3.00 7.00 0.00 1000000000000000000000.00 2.00 gt"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hooks for observing the render pipeline.
+//!
+//! A [`RenderObserver`] registered on
+//! [`DotpromptOptions::observer`](crate::DotpromptOptions::observer) is told
+//! when a prompt is parsed or rendered and when a partial is resolved, with
+//! how long each step took and how much it read or produced. Services use
+//! it to export render latencies and error rates:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! use dotprompt::observer::{RenderEnd, RenderObserver};
+//! use dotprompt::{DataArgument, Dotprompt, DotpromptOptions, RenderedPrompt};
+//!
+//! #[derive(Debug, Default)]
+//! struct Failures(AtomicUsize);
+//!
+//! impl RenderObserver for Failures {
+//!     fn on_render_end(&self, event: &RenderEnd<'_>) {
+//!         if event.error.is_some() {
+//!             self.0.fetch_add(1, Ordering::Relaxed);
+//!         }
+//!     }
+//! }
+//!
+//! let failures = Arc::new(Failures::default());
+//! let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+//!     observer: Some(Box::new(Arc::clone(&failures))),
+//!     ..Default::default()
+//! }));
+//! let rendered: RenderedPrompt = dotprompt
+//!     .render("Hello {{name}}!", &DataArgument::<serde_json::Value>::default(), None)
+//!     .unwrap();
+//! # let _ = rendered;
+//! assert_eq!(failures.0.load(Ordering::Relaxed), 0);
+//! ```
+//!
+//! With the `opentelemetry` feature, [`otel::OtelObserver`] reports the same
+//! events as OpenTelemetry spans and metrics.
+
+#[cfg(feature = "opentelemetry")]
+pub mod otel;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::DotpromptError;
+use crate::types::{Message, Part};
+
+/// Receives events from the render pipeline.
+///
+/// Every method does nothing by default, so implementations only override
+/// the events they need. Methods are called on the rendering thread and
+/// should return quickly.
+pub trait RenderObserver: Send + Sync {
+    /// Called before a prompt's frontmatter and body are parsed.
+    fn on_parse_start(&self, source_bytes: usize) {
+        let _ = source_bytes;
+    }
+
+    /// Called after a prompt is parsed, whether or not parsing succeeded.
+    fn on_parse_end(&self, event: &ParseEnd<'_>) {
+        let _ = event;
+    }
+
+    /// Called before a prompt is rendered.
+    fn on_render_start(&self, source_bytes: usize) {
+        let _ = source_bytes;
+    }
+
+    /// Called after a prompt is rendered, whether or not rendering
+    /// succeeded.
+    fn on_render_end(&self, event: &RenderEnd<'_>) {
+        let _ = event;
+    }
+
+    /// Called when a partial is fetched from a resolver or store.
+    fn on_partial_resolved(&self, event: &PartialResolved<'_>) {
+        let _ = event;
+    }
+}
+
+impl<T: RenderObserver + ?Sized> RenderObserver for Arc<T> {
+    fn on_parse_start(&self, source_bytes: usize) {
+        (**self).on_parse_start(source_bytes);
+    }
+
+    fn on_parse_end(&self, event: &ParseEnd<'_>) {
+        (**self).on_parse_end(event);
+    }
+
+    fn on_render_start(&self, source_bytes: usize) {
+        (**self).on_render_start(source_bytes);
+    }
+
+    fn on_render_end(&self, event: &RenderEnd<'_>) {
+        (**self).on_render_end(event);
+    }
+
+    fn on_partial_resolved(&self, event: &PartialResolved<'_>) {
+        (**self).on_partial_resolved(event);
+    }
+}
+
+/// A finished parse.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseEnd<'a> {
+    /// Size of the prompt source in bytes.
+    pub source_bytes: usize,
+    /// Time spent parsing.
+    pub duration: Duration,
+    /// The error parsing failed with, if it did.
+    pub error: Option<&'a DotpromptError>,
+}
+
+/// A finished render.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderEnd<'a> {
+    /// Size of the prompt source in bytes.
+    pub source_bytes: usize,
+    /// Size in bytes of the text in the rendered messages; zero if
+    /// rendering failed.
+    pub output_bytes: usize,
+    /// Number of rendered messages; zero if rendering failed.
+    pub messages: usize,
+    /// Time spent rendering, including parsing and metadata resolution.
+    pub duration: Duration,
+    /// The error rendering failed with, if it did.
+    pub error: Option<&'a DotpromptError>,
+}
+
+impl<'a> RenderEnd<'a> {
+    /// Describes a render that produced `messages` or failed with `error`.
+    pub(crate) fn new(
+        source_bytes: usize,
+        duration: Duration,
+        result: Result<&[Message], &'a DotpromptError>,
+    ) -> Self {
+        let (messages, error) = match result {
+            Ok(messages) => (messages, None),
+            Err(error) => (&[][..], Some(error)),
+        };
+        let output_bytes = messages
+            .iter()
+            .flat_map(|message| &message.content)
+            .map(|part| match part {
+                Part::Text(text) => text.text.len(),
+                _ => 0,
+            })
+            .sum();
+        Self {
+            source_bytes,
+            output_bytes,
+            messages: messages.len(),
+            duration,
+            error,
+        }
+    }
+}
+
+/// A partial fetched from a resolver or store.
+#[derive(Debug, Clone, Copy)]
+pub struct PartialResolved<'a> {
+    /// The partial's name, including any namespace.
+    pub name: &'a str,
+    /// Size of the partial's source in bytes.
+    pub source_bytes: usize,
+    /// Time spent looking the partial up.
+    pub duration: Duration,
+}
@@ -0,0 +1,204 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! An observer that reports render events to OpenTelemetry.
+//!
+//! [`OtelObserver`] records a span per parse, render, and resolved partial,
+//! ending at the time the event is reported and parented to the caller's
+//! current context, and these metrics:
+//!
+//! | Metric | Kind | Unit |
+//! |--------|------|------|
+//! | `dotprompt.parse.duration` | histogram | `s` |
+//! | `dotprompt.render.duration` | histogram | `s` |
+//! | `dotprompt.render.output_size` | histogram | `By` |
+//! | `dotprompt.render.errors` | counter | |
+//! | `dotprompt.partial.resolve.duration` | histogram | `s` |
+//!
+//! Failed renders are counted in `dotprompt.render.errors` and their spans
+//! get an error status.
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use opentelemetry::global::{self, BoxedTracer};
+use opentelemetry::metrics::{Counter, Histogram, Meter};
+use opentelemetry::trace::{Span, Status, Tracer};
+use opentelemetry::{KeyValue, Value};
+
+use super::{ParseEnd, PartialResolved, RenderEnd, RenderObserver};
+
+/// Name of the instrumentation scope used for spans and metrics.
+const SCOPE: &str = "dotprompt";
+
+/// Reports render events as OpenTelemetry spans and metrics.
+pub struct OtelObserver {
+    tracer: BoxedTracer,
+    parse_duration: Histogram<f64>,
+    render_duration: Histogram<f64>,
+    render_output_size: Histogram<u64>,
+    render_errors: Counter<u64>,
+    partial_duration: Histogram<f64>,
+}
+
+impl fmt::Debug for OtelObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtelObserver").finish_non_exhaustive()
+    }
+}
+
+impl Default for OtelObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OtelObserver {
+    /// Creates an observer that reports to the global tracer and meter
+    /// providers.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_meter(&global::meter(SCOPE))
+    }
+
+    /// Creates an observer that reports spans to the global tracer
+    /// provider and metrics to `meter`.
+    #[must_use]
+    pub fn with_meter(meter: &Meter) -> Self {
+        Self {
+            tracer: global::tracer(SCOPE),
+            parse_duration: meter
+                .f64_histogram("dotprompt.parse.duration")
+                .with_description("Time spent parsing prompts.")
+                .with_unit("s")
+                .build(),
+            render_duration: meter
+                .f64_histogram("dotprompt.render.duration")
+                .with_description("Time spent rendering prompts.")
+                .with_unit("s")
+                .build(),
+            render_output_size: meter
+                .u64_histogram("dotprompt.render.output_size")
+                .with_description("Size of the text in rendered prompts.")
+                .with_unit("By")
+                .build(),
+            render_errors: meter
+                .u64_counter("dotprompt.render.errors")
+                .with_description("Number of failed renders.")
+                .build(),
+            partial_duration: meter
+                .f64_histogram("dotprompt.partial.resolve.duration")
+                .with_description("Time spent looking up partials.")
+                .with_unit("s")
+                .build(),
+        }
+    }
+
+    /// Records a span that started `duration` ago and ends now.
+    fn span(&self, name: &'static str, duration: Duration, attributes: Vec<KeyValue>) -> impl Span {
+        let now = SystemTime::now();
+        self.tracer
+            .span_builder(name)
+            .with_start_time(now.checked_sub(duration).unwrap_or(now))
+            .with_attributes(attributes)
+            .start(&self.tracer)
+    }
+}
+
+impl RenderObserver for OtelObserver {
+    fn on_parse_end(&self, event: &ParseEnd<'_>) {
+        self.parse_duration
+            .record(event.duration.as_secs_f64(), &[]);
+        let mut span = self.span(
+            "dotprompt.parse",
+            event.duration,
+            vec![KeyValue::new(
+                "dotprompt.source_bytes",
+                size(event.source_bytes),
+            )],
+        );
+        if let Some(error) = event.error {
+            span.set_status(Status::error(error.to_string()));
+        }
+        span.end();
+    }
+
+    fn on_render_end(&self, event: &RenderEnd<'_>) {
+        self.render_duration
+            .record(event.duration.as_secs_f64(), &[]);
+        let mut span = self.span(
+            "dotprompt.render",
+            event.duration,
+            vec![
+                KeyValue::new("dotprompt.source_bytes", size(event.source_bytes)),
+                KeyValue::new("dotprompt.output_bytes", size(event.output_bytes)),
+                KeyValue::new("dotprompt.messages", size(event.messages)),
+            ],
+        );
+        match event.error {
+            Some(error) => {
+                self.render_errors.add(1, &[]);
+                span.set_status(Status::error(error.to_string()));
+            }
+            None => self
+                .render_output_size
+                .record(u64::try_from(event.output_bytes).unwrap_or(u64::MAX), &[]),
+        }
+        span.end();
+    }
+
+    fn on_partial_resolved(&self, event: &PartialResolved<'_>) {
+        self.partial_duration
+            .record(event.duration.as_secs_f64(), &[]);
+        let mut span = self.span(
+            "dotprompt.partial.resolve",
+            event.duration,
+            vec![
+                KeyValue::new("dotprompt.partial", event.name.to_string()),
+                KeyValue::new("dotprompt.source_bytes", size(event.source_bytes)),
+            ],
+        );
+        span.end();
+    }
+}
+
+/// Converts a size to an attribute value.
+fn size(bytes: usize) -> Value {
+    Value::I64(i64::try_from(bytes).unwrap_or(i64::MAX))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{DataArgument, Dotprompt, DotpromptOptions, RenderedPrompt};
+
+    #[test]
+    fn test_reports_to_global_providers() {
+        let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+            observer: Some(Box::new(OtelObserver::new())),
+            ..Default::default()
+        }));
+        let data: DataArgument = DataArgument::default();
+        let rendered: RenderedPrompt = dotprompt.render("Hello", &data, None).unwrap();
+        assert_eq!(rendered.messages.len(), 1);
+        assert!(
+            dotprompt
+                .render::<_, serde_json::Value>("{{#if}}", &data, None)
+                .is_err()
+        );
+    }
+}
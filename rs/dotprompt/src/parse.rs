@@ -21,30 +21,26 @@
 
 use crate::error::{DotpromptError, Result};
 use crate::types::{
-    DataArgument, MediaContent, MediaPart, Message, ParsedPrompt, Part, PendingPart,
-    PromptMetadata, Role, TextPart,
+    DataArgument, DataPart, MediaContent, MediaPart, Message, ParsedPrompt, Part, PendingPart,
+    PromptMetadata, Role, TextPart, ToolRequestPart, ToolResponsePart,
 };
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::OnceLock;
 
 /// Regex pattern for extracting YAML frontmatter.
 static FRONTMATTER_RE: OnceLock<Regex> = OnceLock::new();
 
-/// Regex for role and history markers.
-static ROLE_AND_HISTORY_RE: OnceLock<Regex> = OnceLock::new();
+/// Regex for inline partial definitions.
+static INLINE_PARTIAL_RE: OnceLock<Regex> = OnceLock::new();
 
-/// Regex for media and section markers.
-static MEDIA_AND_SECTION_RE: OnceLock<Regex> = OnceLock::new();
-
-/// Role marker prefix in templates.
-const ROLE_MARKER_PREFIX: &str = "<<<dotprompt:role:";
-/// History marker prefix in templates.
-const HISTORY_MARKER_PREFIX: &str = "<<<dotprompt:history";
-/// Section marker prefix in templates.
-const SECTION_MARKER_PREFIX: &str = "<<<dotprompt:section";
-/// Media marker prefix in templates.
-const MEDIA_MARKER_PREFIX: &str = "<<<dotprompt:media:url";
+/// Prefix shared by all markers.
+const MARKER_PREFIX: &str = "<<<dotprompt:";
+/// Suffix that closes a marker.
+const MARKER_SUFFIX: &str = ">>>";
+/// [`MARKER_PREFIX`] as written inside `{{#verbatim}}` and raw blocks, so the
+/// marker scanner does not match it. Text parts get the original back.
+const ESCAPED_MARKER_PREFIX: &str = "\u{E003}<<dotprompt:";
 
 /// Gets or initializes the frontmatter regex pattern.
 /// Allows blank lines and license headers (lines starting with #) before the first ---.
@@ -58,21 +54,12 @@ fn frontmatter_regex() -> &'static Regex {
     })
 }
 
-/// Gets or initializes the role and history marker regex.
-#[allow(clippy::expect_used)]
-fn role_and_history_regex() -> &'static Regex {
-    ROLE_AND_HISTORY_RE.get_or_init(|| {
-        Regex::new(r"(<<<dotprompt:(?:role:[a-z]+|history))>>>")
-            .expect("failed to compile role/history regex")
-    })
-}
-
-/// Gets or initializes the media and section marker regex.
+/// Gets or initializes the inline partial definition regex.
 #[allow(clippy::expect_used)]
-fn media_and_section_regex() -> &'static Regex {
-    MEDIA_AND_SECTION_RE.get_or_init(|| {
-        Regex::new(r"(<<<dotprompt:(?:media:url|section).*?)>>>")
-            .expect("failed to compile media/section regex")
+fn inline_partial_regex() -> &'static Regex {
+    INLINE_PARTIAL_RE.get_or_init(|| {
+        Regex::new(r#"\{\{~?#\*inline\s+(?:"([^"]*)"|'([^']*)')"#)
+            .expect("failed to compile inline partial regex")
     })
 }
 
@@ -112,6 +99,20 @@ pub fn extract_frontmatter_and_body(source: &str) -> Result<(String, String)> {
     }
 }
 
+/// Returns the names of the partials a template defines inline with
+/// `{{#*inline "name"}}...{{/inline}}`.
+///
+/// Inline partials are local to the template and take precedence over
+/// registered partials of the same name, so they never need resolving.
+#[must_use]
+pub fn identify_inline_partials(template: &str) -> HashSet<String> {
+    inline_partial_regex()
+        .captures_iter(template)
+        .filter_map(|cap| cap.get(1).or_else(|| cap.get(2)))
+        .map(|name| name.as_str().to_string())
+        .collect()
+}
+
 /// Parses a dotprompt document into structured metadata and template.
 ///
 /// # Arguments
@@ -131,78 +132,237 @@ where
 {
     let (yaml, template) = extract_frontmatter_and_body(source)?;
 
-    let metadata: PromptMetadata<M> = if yaml.is_empty() {
-        PromptMetadata::default()
-    } else {
-        serde_yaml::from_str(&yaml)?
+    let metadata: PromptMetadata<M> = match parse_frontmatter_value(&yaml)? {
+        serde_yaml_ng::Value::Null => PromptMetadata::default(),
+        value => serde_yaml_ng::from_value(value)?,
     };
 
     Ok(ParsedPrompt { metadata, template })
 }
 
-/// Splits a string by a regex, keeping the matched delimiters.
-#[allow(clippy::unwrap_used)]
-fn split_by_regex(source: &str, regex: &Regex) -> Vec<String> {
-    let mut result = Vec::new();
-    let mut last_end = 0;
-
-    for cap in regex.captures_iter(source) {
-        let full_match = cap.get(0).unwrap();
-        let delimiter = cap.get(1).unwrap();
+/// Parses frontmatter YAML into a value, resolving aliases and merge keys.
+///
+/// Variants commonly share config through an anchor and `<<` merge keys:
+///
+/// ```
+/// use dotprompt::parse::parse_frontmatter_value;
+///
+/// let value = parse_frontmatter_value(
+///     "base: &base {temperature: 0.2}\nconfig:\n  <<: *base\n  topK: 5\n",
+/// )
+/// .unwrap();
+/// assert_eq!(value["config"]["temperature"], 0.2);
+/// assert_eq!(value["config"]["topK"], 5);
+/// ```
+///
+/// Keys set in a mapping win over merged ones. Empty frontmatter parses as
+/// `Null`.
+///
+/// # Errors
+///
+/// Returns error if the YAML is invalid, an alias names an undefined anchor,
+/// or a merge key is not a mapping or list of mappings.
+pub fn parse_frontmatter_value(yaml: &str) -> Result<serde_yaml_ng::Value> {
+    if yaml.trim().is_empty() {
+        return Ok(serde_yaml_ng::Value::Null);
+    }
+    let mut value: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml)?;
+    value.apply_merge()?;
+    Ok(value)
+}
 
-        // Add the text before the match if non-empty
-        let before = &source[last_end..full_match.start()];
-        if !before.trim().is_empty() {
-            result.push(before.to_string());
+/// Parses a document as far as possible, for editors working on a prompt
+/// that is still being typed.
+///
+/// Unlike [`parse_document`], this never fails. When the frontmatter is
+/// not valid YAML, each top-level key is parsed on its own and the keys that
+/// parse are kept; keys whose values do not fit [`PromptMetadata`] are
+/// dropped. The body is returned as written. The error [`parse_document`]
+/// would have returned, if any, comes back alongside the partial result.
+///
+/// ```
+/// use dotprompt::parse::parse_document_lenient;
+///
+/// let source = "---\nmodel: gemini-pro\nconfig: {temperature: \ntags: [a]\n---\nHi";
+/// let (parsed, error) = parse_document_lenient::<serde_json::Value>(source);
+/// assert!(error.is_some());
+/// assert_eq!(parsed.metadata.model.as_deref(), Some("gemini-pro"));
+/// assert_eq!(parsed.metadata.tags, Some(vec!["a".to_string()]));
+/// assert_eq!(parsed.template, "Hi");
+/// ```
+#[must_use]
+pub fn parse_document_lenient<M>(source: &str) -> (ParsedPrompt<M>, Option<DotpromptError>)
+where
+    M: serde::de::DeserializeOwned + Default,
+{
+    let error = match parse_document(source) {
+        Ok(parsed) => return (parsed, None),
+        Err(error) => error,
+    };
+    let (yaml, template) = extract_frontmatter_and_body(source)
+        .unwrap_or_else(|_| (String::new(), source.to_string()));
+
+    let mut fields = serde_yaml_ng::Mapping::new();
+    let mut metadata = PromptMetadata::default();
+    if let serde_yaml_ng::Value::Mapping(entries) = parse_frontmatter_lenient(&yaml) {
+        for (key, value) in entries {
+            fields.insert(key.clone(), value);
+            match serde_yaml_ng::from_value(serde_yaml_ng::Value::Mapping(fields.clone())) {
+                Ok(parsed) => metadata = parsed,
+                Err(_) => {
+                    fields.remove(&key);
+                }
+            }
         }
+    }
+    (ParsedPrompt { metadata, template }, Some(error))
+}
 
-        // Add the captured delimiter (without the trailing >>>)
-        result.push(delimiter.as_str().to_string());
+/// Parses frontmatter YAML into a value, keeping the top-level keys that
+/// parse when the whole does not.
+///
+/// Returns the same value as [`parse_frontmatter_value`] for valid YAML. For
+/// invalid YAML, each top-level key and the indented lines under it are
+/// parsed separately, and the result is a mapping of those that parse.
+#[must_use]
+pub fn parse_frontmatter_lenient(yaml: &str) -> serde_yaml_ng::Value {
+    if let Ok(value) = parse_frontmatter_value(yaml) {
+        return value;
+    }
 
-        last_end = full_match.end();
+    let mut entries: Vec<String> = Vec::new();
+    for line in yaml.lines() {
+        let starts_entry = line
+            .chars()
+            .next()
+            .is_some_and(|c| !c.is_whitespace() && c != '#' && c != '-')
+            && line.contains(':');
+        if starts_entry {
+            entries.push(String::new());
+        }
+        if let Some(entry) = entries.last_mut() {
+            entry.push_str(line);
+            entry.push('\n');
+        }
     }
 
-    // Add any remaining text after the last match
-    let remaining = &source[last_end..];
-    if !remaining.trim().is_empty() {
-        result.push(remaining.to_string());
+    let mut mapping = serde_yaml_ng::Mapping::new();
+    for entry in entries {
+        #[allow(clippy::collapsible_if)]
+        if let Ok(serde_yaml_ng::Value::Mapping(parsed)) = parse_frontmatter_value(&entry) {
+            for (key, value) in parsed {
+                mapping.entry(key).or_insert(value);
+            }
+        }
     }
+    serde_yaml_ng::Value::Mapping(mapping)
+}
 
-    result
+/// Escapes the markers in rendered text so they are kept as text.
+///
+/// Used by `{{#verbatim}}` and raw blocks, whose content must reach the
+/// prompt as written even when it looks like a role or media marker.
+pub(crate) fn escape_markers(text: &str) -> String {
+    text.replace(MARKER_PREFIX, ESCAPED_MARKER_PREFIX)
 }
 
-/// Splits by role and history markers.
-fn split_by_role_and_history_markers(rendered_string: &str) -> Vec<String> {
-    split_by_regex(rendered_string, role_and_history_regex())
+/// A piece of rendered output: text, or a marker.
+#[derive(Debug, PartialEq, Eq)]
+enum Piece<'a> {
+    /// Text between markers.
+    Text(&'a str),
+    /// `<<<dotprompt:role:NAME>>>`, with the role name.
+    Role(&'a str),
+    /// `<<<dotprompt:history>>>`.
+    History,
+    /// `<<<dotprompt:media:url URL [CONTENT_TYPE]>>>`, with what follows
+    /// `media:url`.
+    Media(&'a str),
+    /// `<<<dotprompt:section TYPE>>>`, with what follows `section`.
+    Section(&'a str),
 }
 
-/// Splits by media and section markers.
-fn split_by_media_and_section_markers(source: &str) -> Vec<String> {
-    split_by_regex(source, media_and_section_regex())
+/// Reads the role or history marker at the start of `source`, returning it
+/// and its length.
+fn role_or_history_marker(source: &str) -> Option<(Piece<'_>, usize)> {
+    let rest = source.strip_prefix(MARKER_PREFIX)?;
+    if let Some(name) = rest.strip_prefix("role:") {
+        let len = name.bytes().take_while(u8::is_ascii_lowercase).count();
+        return (len > 0 && name[len..].starts_with(MARKER_SUFFIX)).then(|| {
+            let marker = MARKER_PREFIX.len() + "role:".len() + len + MARKER_SUFFIX.len();
+            (Piece::Role(&name[..len]), marker)
+        });
+    }
+    rest.starts_with("history>>>")
+        .then_some((Piece::History, MARKER_PREFIX.len() + "history>>>".len()))
 }
 
-/// Parses a single piece into a Part.
-fn parse_part(piece: &str) -> Part {
-    if piece.starts_with(MEDIA_MARKER_PREFIX) {
-        parse_media_part(piece)
-    } else if piece.starts_with(SECTION_MARKER_PREFIX) {
-        parse_section_part(piece)
+/// Reads the marker at the start of `source`, returning it and its length.
+///
+/// Media and section markers end at the first `>>>` on their line. One that
+/// contains a role or history marker is not a marker, since messages are
+/// split before parts are.
+fn marker(source: &str) -> Option<(Piece<'_>, usize)> {
+    if let Some(marker) = role_or_history_marker(source) {
+        return Some(marker);
+    }
+    let rest = source.strip_prefix(MARKER_PREFIX)?;
+    let (keyword, after) = if let Some(after) = rest.strip_prefix("media:url") {
+        ("media:url", after)
     } else {
-        Part::Text(TextPart {
-            text: piece.to_string(),
-            metadata: None,
-        })
+        ("section", rest.strip_prefix("section")?)
+    };
+    let line = after.split('\n').next().unwrap_or_default();
+    let body = &line[..line.find(MARKER_SUFFIX)?];
+    if body
+        .match_indices(MARKER_PREFIX)
+        .any(|(i, _)| role_or_history_marker(&after[i..]).is_some())
+    {
+        return None;
     }
+    let len = MARKER_PREFIX.len() + keyword.len() + body.len() + MARKER_SUFFIX.len();
+    let piece = if keyword == "section" {
+        Piece::Section(body)
+    } else {
+        Piece::Media(body)
+    };
+    Some((piece, len))
 }
 
-/// Parses a media marker into a `MediaPart`.
-fn parse_media_part(piece: &str) -> Part {
-    // Format: "<<<dotprompt:media:url URL [CONTENT_TYPE]"
-    let content = piece.strip_prefix(MEDIA_MARKER_PREFIX).unwrap_or(piece);
-    let parts: Vec<&str> = content.split_whitespace().collect();
+/// Splits rendered output into text and markers in a single pass.
+///
+/// Text that is only whitespace is dropped.
+fn scan(source: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut text_start = 0;
+    let mut search_from = 0;
+    while let Some(offset) = source[search_from..].find(MARKER_PREFIX) {
+        let start = search_from + offset;
+        let Some((piece, len)) = marker(&source[start..]) else {
+            search_from = start + 1;
+            continue;
+        };
+        let text = &source[text_start..start];
+        if !text.trim().is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        pieces.push(piece);
+        text_start = start + len;
+        search_from = text_start;
+    }
+    let text = &source[text_start..];
+    if !text.trim().is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    pieces
+}
 
-    let url = parts.first().unwrap_or(&"").to_string();
-    let content_type = parts.get(1).map(std::string::ToString::to_string);
+/// Parses the body of a media marker into a `MediaPart`.
+fn parse_media_part(body: &str) -> Part {
+    // Format: " URL [CONTENT_TYPE]"
+    let mut fields = body.split_whitespace();
+    let url = fields.next().unwrap_or_default().to_string();
+    let content_type = fields.next().map(str::to_string);
 
     Part::Media(MediaPart {
         media: MediaContent { url, content_type },
@@ -210,50 +370,164 @@ fn parse_media_part(piece: &str) -> Part {
     })
 }
 
-/// Parses a section marker into a `PendingPart`.
-fn parse_section_part(piece: &str) -> Part {
-    // Format: "<<<dotprompt:section SECTION_TYPE"
-    let content = piece.strip_prefix(SECTION_MARKER_PREFIX).unwrap_or(piece);
-    let section_type = content.trim().to_string();
-
+/// Parses the body of a section marker into a `PendingPart`.
+fn parse_section_part(body: &str) -> Part {
+    // Format: " SECTION_TYPE"
     let mut metadata = HashMap::new();
     metadata.insert(
         "purpose".to_string(),
-        serde_json::Value::String(section_type),
+        serde_json::Value::String(body.trim().to_string()),
     );
     metadata.insert("pending".to_string(), serde_json::Value::Bool(true));
 
     Part::Pending(PendingPart { metadata })
 }
 
-/// Converts source string into Parts (handling media and section markers).
-fn to_parts(source: &str) -> Vec<Part> {
-    split_by_media_and_section_markers(source)
-        .iter()
-        .map(|s| parse_part(s))
-        .collect()
+/// Moves section markers into the metadata of the parts they introduce.
+///
+/// `{{section "examples"}}` renders as a pending part with `purpose:
+/// examples`, as the spec requires. This tags every part after a marker, up
+/// to the next marker or the end of its message, with that `purpose`
+/// instead, and drops the markers, so consumers can find a section's
+/// content directly. Parts that already have a `purpose` keep it.
+///
+/// ```
+/// use dotprompt::parse::{apply_sections, to_messages};
+/// use dotprompt::types::Part;
+///
+/// let rendered = "Intro<<<dotprompt:section examples>>>Q: 1+1? A: 2";
+/// let mut messages = to_messages::<serde_json::Value>(rendered, None);
+/// apply_sections(&mut messages);
+/// let Part::Text(example) = &messages[0].content[1] else { panic!() };
+/// assert_eq!(example.text, "Q: 1+1? A: 2");
+/// assert_eq!(example.metadata.as_ref().unwrap()["purpose"], "examples");
+/// ```
+pub fn apply_sections(messages: &mut [Message]) {
+    for message in messages {
+        let mut purpose: Option<serde_json::Value> = None;
+        let parts = std::mem::take(&mut message.content);
+        for mut part in parts {
+            let metadata = match &mut part {
+                Part::Pending(pending)
+                    if pending.metadata.get("pending") == Some(&serde_json::Value::Bool(true)) =>
+                {
+                    if let Some(section) = pending.metadata.get("purpose") {
+                        purpose = Some(section.clone());
+                        continue;
+                    }
+                    None
+                }
+                Part::Text(TextPart { metadata, .. })
+                | Part::Data(DataPart { metadata, .. })
+                | Part::Media(MediaPart { metadata, .. })
+                | Part::ToolRequest(ToolRequestPart { metadata, .. })
+                | Part::ToolResponse(ToolResponsePart { metadata, .. }) => Some(metadata),
+                Part::Pending(_) => None,
+            };
+            if let (Some(metadata), Some(purpose)) = (metadata, &purpose) {
+                metadata
+                    .get_or_insert_with(HashMap::new)
+                    .entry("purpose".to_string())
+                    .or_insert_with(|| purpose.clone());
+            }
+            message.content.push(part);
+        }
+    }
+}
+
+/// Cleans up rendered messages for providers.
+///
+/// Templates often leave blank lines around role markers and produce
+/// adjacent messages with the same role, which many providers reject. This:
+///
+/// 1. drops text parts that are only whitespace, and messages left empty;
+/// 2. merges consecutive messages with the same role, appending their parts
+///    in order (the merged message keeps the first message's metadata,
+///    adding keys it lacks from the others);
+/// 3. trims leading and trailing whitespace from every text part.
+///
+/// ```
+/// use dotprompt::parse::{normalize_messages, to_messages};
+///
+/// let rendered = "<<<dotprompt:role:user>>>\nHi\n<<<dotprompt:role:model>>>  \
+///     <<<dotprompt:role:user>>>Again\n";
+/// let messages = normalize_messages(to_messages::<serde_json::Value>(rendered, None));
+/// assert_eq!(messages.len(), 1);
+/// assert_eq!(messages[0].content.len(), 2);
+/// ```
+#[must_use]
+pub fn normalize_messages(messages: Vec<Message>) -> Vec<Message> {
+    normalize_tracked(messages).0
 }
 
-/// A message source during parsing.
-struct MessageSource {
-    role: Role,
-    source: String,
-    content: Option<Vec<Part>>,
-    metadata: Option<HashMap<String, serde_json::Value>>,
+/// Where [`normalize_messages`] moved a part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MovedPart {
+    /// Message and part index before normalizing.
+    pub from: (usize, usize),
+    /// Message and part index after normalizing.
+    pub to: (usize, usize),
+    /// Byte range of the original text kept by trimming, for text parts.
+    pub kept: std::ops::Range<usize>,
 }
 
-impl MessageSource {
-    const fn new(role: Role) -> Self {
-        Self {
-            role,
-            source: String::new(),
-            content: None,
-            metadata: None,
+/// Normalizes messages as [`normalize_messages`] does, also reporting where
+/// each surviving part ended up so traces can follow it.
+pub(crate) fn normalize_tracked(messages: Vec<Message>) -> (Vec<Message>, Vec<MovedPart>) {
+    let mut normalized: Vec<Message> = Vec::new();
+    let mut moves: Vec<MovedPart> = Vec::new();
+    for (m, mut message) in messages.into_iter().enumerate() {
+        let parts: Vec<(usize, Part)> = std::mem::take(&mut message.content)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, part)| !matches!(part, Part::Text(text) if text.text.trim().is_empty()))
+            .collect();
+        if parts.is_empty() {
+            continue;
+        }
+
+        let merge = normalized
+            .last()
+            .is_some_and(|previous| previous.role == message.role);
+        if !merge {
+            normalized.push(Message {
+                content: Vec::new(),
+                ..message
+            });
+        } else if let (Some(previous), Some(metadata)) = (normalized.last_mut(), message.metadata) {
+            let merged = previous.metadata.get_or_insert_with(HashMap::new);
+            for (key, value) in metadata {
+                merged.entry(key).or_insert(value);
+            }
+        }
+        let to = normalized.len() - 1;
+        let Some(target) = normalized.last_mut() else {
+            continue;
+        };
+        for (p, mut part) in parts {
+            let mut kept = 0..0;
+            if let Part::Text(text) = &mut part {
+                kept.start = text.text.len() - text.text.trim_start().len();
+                kept.end = text.text.trim_end().len();
+                text.text = text.text[kept.clone()].to_string();
+            }
+            moves.push(MovedPart {
+                from: (m, p),
+                to: (to, target.content.len()),
+                kept,
+            });
+            target.content.push(part);
         }
     }
+    (normalized, moves)
+}
 
-    fn has_content(&self) -> bool {
-        !self.source.trim().is_empty() || self.content.is_some()
+/// Creates a message with no content yet.
+const fn empty_message(role: Role) -> Message {
+    Message {
+        role,
+        content: Vec::new(),
+        metadata: None,
     }
 }
 
@@ -319,22 +593,6 @@ fn insert_history(messages: Vec<Message>, history: Option<&Vec<Message>>) -> Vec
     result
 }
 
-/// Converts message sources to Messages.
-fn message_sources_to_messages(sources: Vec<MessageSource>) -> Vec<Message> {
-    sources
-        .into_iter()
-        .filter(MessageSource::has_content)
-        .map(|ms| {
-            let content = ms.content.unwrap_or_else(|| to_parts(&ms.source));
-            Message {
-                role: ms.role,
-                content,
-                metadata: ms.metadata,
-            }
-        })
-        .collect()
-}
-
 /// Converts a rendered template string into an array of Messages.
 ///
 /// This function processes role markers and splits content accordingly.
@@ -352,62 +610,53 @@ pub fn to_messages<V>(rendered_string: &str, data: Option<&DataArgument<V>>) ->
 where
     V: serde::Serialize + Default,
 {
-    let mut current_message = MessageSource::new(Role::User);
-    let mut message_sources: Vec<MessageSource> = Vec::new();
-
-    for piece in split_by_role_and_history_markers(rendered_string) {
-        if piece.starts_with(ROLE_MARKER_PREFIX) {
-            // Parse role from marker
-            let role_str = piece.strip_prefix(ROLE_MARKER_PREFIX).unwrap_or("user");
-            let role = match role_str {
-                "model" => Role::Model,
-                "tool" => Role::Tool,
-                "system" => Role::System,
-                // "user" and anything else -> Role::User
-                _ => Role::User,
-            };
-
-            if current_message.source.trim().is_empty() {
-                // Update role of current message
-                current_message.role = role;
-            } else {
-                // Save current and start new
-                message_sources.push(current_message);
-                current_message = MessageSource::new(role);
-            }
-        } else if piece.starts_with(HISTORY_MARKER_PREFIX) {
-            // Save current message if it has content
-            if !current_message.source.trim().is_empty() {
-                message_sources.push(current_message);
+    let mut messages = Vec::new();
+    let mut current_message = empty_message(Role::User);
+
+    for piece in scan(rendered_string) {
+        match piece {
+            Piece::Role(name) => {
+                let role = match name {
+                    "model" => Role::Model,
+                    "tool" => Role::Tool,
+                    "system" => Role::System,
+                    // "user" and anything else -> Role::User
+                    _ => Role::User,
+                };
+                if current_message.content.is_empty() {
+                    // Update role of current message
+                    current_message.role = role;
+                } else {
+                    // Save current and start new
+                    messages.push(std::mem::replace(&mut current_message, empty_message(role)));
+                }
             }
+            Piece::History => {
+                // Save current message if it has content, and start a new
+                // one for content after history
+                let previous = std::mem::replace(&mut current_message, empty_message(Role::Model));
+                if !previous.content.is_empty() {
+                    messages.push(previous);
+                }
 
-            // Add history messages
-            #[allow(clippy::collapsible_if)]
-            if let Some(data_arg) = data {
-                if let Some(history) = &data_arg.messages {
-                    for msg in transform_messages_to_history(history) {
-                        message_sources.push(MessageSource {
-                            role: msg.role,
-                            source: String::new(),
-                            content: Some(msg.content),
-                            metadata: msg.metadata,
-                        });
-                    }
+                // Add history messages
+                if let Some(history) = data.and_then(|d| d.messages.as_ref()) {
+                    messages.extend(transform_messages_to_history(history));
                 }
             }
-
-            // Start new message for content after history
-            current_message = MessageSource::new(Role::Model);
-        } else {
-            // Regular content
-            current_message.source.push_str(&piece);
+            Piece::Media(body) => current_message.content.push(parse_media_part(body)),
+            Piece::Section(body) => current_message.content.push(parse_section_part(body)),
+            Piece::Text(text) => current_message.content.push(Part::Text(TextPart {
+                text: text.replace(ESCAPED_MARKER_PREFIX, MARKER_PREFIX),
+                metadata: None,
+            })),
         }
     }
 
     // Push final message
-    message_sources.push(current_message);
-
-    let messages = message_sources_to_messages(message_sources);
+    if !current_message.content.is_empty() {
+        messages.push(current_message);
+    }
 
     // Insert history if not already present
     let history = data.and_then(|d| d.messages.as_ref());
@@ -443,6 +692,43 @@ mod tests {
         assert_eq!(parsed.template, "Hello!");
     }
 
+    #[test]
+    fn test_parse_document_resolves_anchors_and_merge_keys() {
+        let source = "---\nbase: &base\n  temperature: 0.2\n  topK: 5\nconfig:\n  <<: *base\n  topK: 10\ntags: &tags [a, b]\nmetadata:\n  shared: *tags\n---\nHello!";
+        let parsed: ParsedPrompt = parse_document(source).expect("parse should succeed");
+        assert_eq!(
+            parsed.metadata.config,
+            Some(serde_json::json!({"temperature": 0.2, "topK": 10}))
+        );
+        assert_eq!(
+            parsed.metadata.tags,
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            parsed.metadata.metadata.expect("metadata should parse")["shared"],
+            serde_json::json!(["a", "b"])
+        );
+
+        assert!(parse_document::<serde_json::Value>("---\nconfig: *missing\n---\nHi").is_err());
+        assert!(parse_document::<serde_json::Value>("---\n# only a comment\n---\nHi").is_ok());
+    }
+
+    #[test]
+    fn test_parse_document_lenient() {
+        let source = "---\nmodel: gemini-pro\ntags: 5\ninput:\n  schema:\n    name: string\nconfig:\n  temperature: [\n---\n{{name}}";
+        let (parsed, error) = parse_document_lenient::<serde_json::Value>(source);
+        assert!(error.is_some());
+        assert_eq!(parsed.metadata.model.as_deref(), Some("gemini-pro"));
+        assert!(parsed.metadata.tags.is_none());
+        assert!(parsed.metadata.input.is_some());
+        assert!(parsed.metadata.config.is_none());
+        assert_eq!(parsed.template, "{{name}}");
+
+        let (parsed, error) = parse_document_lenient::<serde_json::Value>("Hello {{name");
+        assert!(error.is_none());
+        assert_eq!(parsed.template, "Hello {{name");
+    }
+
     #[test]
     fn test_to_messages_simple() {
         let rendered = "Hello world!";
@@ -468,6 +754,176 @@ mod tests {
         assert!(matches!(messages[0].content[0], Part::Media(_)));
     }
 
+    #[test]
+    fn test_to_messages_keeps_escaped_markers() {
+        let rendered = format!(
+            "Hi{}<<<dotprompt:media:url a.png>>>",
+            escape_markers("<<<dotprompt:role:model>>>")
+        );
+        let messages: Vec<Message> = to_messages::<serde_json::Value>(&rendered, None);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, Role::User);
+        assert_eq!(messages[0].content.len(), 2);
+        assert!(matches!(
+            &messages[0].content[0],
+            Part::Text(text) if text.text == "Hi<<<dotprompt:role:model>>>"
+        ));
+    }
+
+    #[test]
+    fn test_scan_markers() {
+        assert_eq!(
+            scan("A<<<dotprompt:role:model>>> \n<<<dotprompt:media:url a.png image/png>>>B"),
+            vec![
+                Piece::Text("A"),
+                Piece::Role("model"),
+                Piece::Media(" a.png image/png"),
+                Piece::Text("B"),
+            ]
+        );
+        assert_eq!(
+            scan("<<<dotprompt:history>>><<<dotprompt:section code>>>x"),
+            vec![Piece::History, Piece::Section(" code"), Piece::Text("x")]
+        );
+        // Not markers: an unclosed one, one closed on a later line, and an
+        // uppercase role.
+        for text in [
+            "<<<dotprompt:section code",
+            "<<<dotprompt:media:url a.png\n>>>",
+            "<<<dotprompt:role:Model>>>",
+        ] {
+            assert_eq!(scan(text), vec![Piece::Text(text)]);
+        }
+        // Messages are split before parts, so a role marker inside a media
+        // marker wins.
+        assert_eq!(
+            scan("<<<dotprompt:media:url <<<dotprompt:role:model>>> x>>>"),
+            vec![
+                Piece::Text("<<<dotprompt:media:url "),
+                Piece::Role("model"),
+                Piece::Text(" x>>>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_sections() {
+        let rendered = "Hi<<<dotprompt:section intro>>>Hello<<<dotprompt:media:url a.png>>>\
+            <<<dotprompt:section main>>>Main<<<dotprompt:role:model>>>Reply";
+        let mut messages = to_messages::<serde_json::Value>(rendered, None);
+        apply_sections(&mut messages);
+
+        let purposes: Vec<Option<&serde_json::Value>> = messages[0]
+            .content
+            .iter()
+            .map(|part| match part {
+                Part::Text(TextPart { metadata, .. }) | Part::Media(MediaPart { metadata, .. }) => {
+                    metadata.as_ref().and_then(|m| m.get("purpose"))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            purposes,
+            [
+                None,
+                Some(&serde_json::json!("intro")),
+                Some(&serde_json::json!("intro")),
+                Some(&serde_json::json!("main")),
+            ]
+        );
+        // Sections end with their message.
+        assert_eq!(
+            messages[1].content,
+            [Part::Text(TextPart {
+                text: "Reply".to_string(),
+                metadata: None,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_normalize_messages() {
+        let text = |role: Role, text: &str| Message {
+            role,
+            content: vec![Part::Text(TextPart {
+                text: text.to_string(),
+                metadata: None,
+            })],
+            metadata: None,
+        };
+        let mut history = text(Role::Model, "Earlier");
+        history.metadata = Some(HashMap::from([(
+            "purpose".to_string(),
+            serde_json::json!("history"),
+        )]));
+        let messages = vec![
+            text(Role::System, "\n  Be brief.\n"),
+            text(Role::User, " \n "),
+            history,
+            text(Role::Model, "Later\n"),
+            Message {
+                role: Role::User,
+                content: Vec::new(),
+                metadata: None,
+            },
+            text(Role::User, "Question\n"),
+        ];
+
+        let (normalized, moves) = normalize_tracked(messages);
+        let texts: Vec<(Role, Vec<&str>)> = normalized
+            .iter()
+            .map(|m| {
+                let parts = m
+                    .content
+                    .iter()
+                    .filter_map(|part| match part {
+                        Part::Text(text) => Some(text.text.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                (m.role, parts)
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                (Role::System, vec!["Be brief."]),
+                (Role::Model, vec!["Earlier", "Later"]),
+                (Role::User, vec!["Question"]),
+            ]
+        );
+        assert_eq!(
+            normalized[1].metadata.as_ref().map(|m| &m["purpose"]),
+            Some(&serde_json::json!("history"))
+        );
+        assert_eq!(
+            moves,
+            [
+                MovedPart {
+                    from: (0, 0),
+                    to: (0, 0),
+                    kept: 3..12,
+                },
+                MovedPart {
+                    from: (2, 0),
+                    to: (1, 0),
+                    kept: 0..7,
+                },
+                MovedPart {
+                    from: (3, 0),
+                    to: (1, 1),
+                    kept: 0..5,
+                },
+                MovedPart {
+                    from: (5, 0),
+                    to: (2, 0),
+                    kept: 0..8,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_extract_with_license_header() {
         let source = "# Copyright 2025 Google LLC\n# License: Apache 2.0\n---\nmodel: gemini-pro\n---\nHello!";
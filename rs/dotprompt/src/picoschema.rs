@@ -18,19 +18,48 @@
 //!
 //! This module provides functionality to convert compact "picoschema" syntax
 //! into full JSON Schema definitions.
+//!
+//! Schemas can reuse named schemas from a registry, such as the `schemas/`
+//! directory read by [`StoreSchemaResolver`](crate::StoreSchemaResolver):
+//!
+//! - in picoschema, a schema name can be used as a type: `Person`,
+//!   `Person[]`, or `Person | null`;
+//! - in JSON Schema, `{"$ref": "#/schemas/Person"}` refers to one. See
+//!   [`resolve_schema_refs`].
 
 use crate::error::{DotpromptError, Result};
 use crate::types::JsonSchema;
 use serde_json::json;
 
+/// Maximum nesting of `[]` suffixes, matching `serde_json`'s recursion limit.
+const MAX_ARRAY_DEPTH: usize = 128;
+
+/// Field name that sets the type of fields not otherwise listed.
+const WILDCARD_PROPERTY_NAME: &str = "(*)";
+
+/// Prefix of JSON Schema references to named schemas.
+pub const SCHEMA_REF_PREFIX: &str = "#/schemas/";
+
+/// Looks up a named schema, returning `None` if there is none.
+pub type SchemaLookup<'a> = &'a dyn Fn(&str) -> Result<Option<JsonSchema>>;
+
 /// Converts a picoschema definition to JSON Schema.
 ///
 /// Picoschema supports compact syntax like:
-/// - `string`, `number`, `boolean`, `object`, `array`
+/// - `string`, `number`, `integer`, `boolean`, `null`, `object`, `array`,
+///   and `any`
+/// - `type, description` to describe a value
 /// - `string[]` for arrays of strings
-/// - `{field: type, ...}` for objects
+/// - `{field: type, ...}` for objects, whose fields are required unless
+///   written `field?`, in which case they are also nullable
+/// - `field(array, description): type`, `field(object, description): {...}`,
+///   and `field(enum, description): [values]`
+/// - `(*): type` for the type of any other fields
 /// - `type | null` for nullable types
 ///
+/// Objects that already look like JSON Schema (with a `type`, `properties`,
+/// `$ref`, or combinator keyword) are kept as they are.
+///
 /// # Arguments
 ///
 /// * `schema` - The picoschema as a JSON Value (can be string or object)
@@ -43,69 +72,281 @@ use serde_json::json;
 ///
 /// Returns error if the picoschema syntax is invalid.
 pub fn picoschema_to_json_schema(schema: &serde_json::Value) -> Result<JsonSchema> {
-    // Handle object types
-    if let Some(obj) = schema.as_object() {
-        // If it's already JSON Schema with "type" or "properties", return as-is
-        if obj.contains_key("type") || obj.contains_key("properties") {
-            return Ok(schema.clone());
+    picoschema_to_json_schema_with(schema, &|_| Ok(None))
+}
+
+/// Converts a picoschema definition to JSON Schema, looking up type names
+/// that are not built in with `lookup`.
+///
+/// # Errors
+///
+/// Returns error if the picoschema syntax is invalid, a type name is
+/// unknown, or `lookup` fails.
+pub fn picoschema_to_json_schema_with(
+    schema: &serde_json::Value,
+    lookup: SchemaLookup<'_>,
+) -> Result<JsonSchema> {
+    match schema {
+        serde_json::Value::Object(obj) => {
+            if let Some(schema) = as_json_schema(obj) {
+                return Ok(schema);
+            }
+            parse_picoschema_object(obj, lookup)
+        }
+        serde_json::Value::String(schema_str) => parse_picoschema_string(schema_str, lookup),
+        _ => Ok(schema.clone()),
+    }
+}
+
+/// Returns an object that is already JSON Schema, with `type: object`
+/// filled in when it only has `properties`.
+fn as_json_schema(obj: &serde_json::Map<String, serde_json::Value>) -> Option<JsonSchema> {
+    // Picoschema fields are never arrays, so an array under a combinator
+    // keyword marks JSON Schema such as the `anyOf` produced for unions.
+    if obj.contains_key("type")
+        || obj.contains_key("$ref")
+        || ["anyOf", "oneOf", "allOf"]
+            .iter()
+            .any(|keyword| obj.get(*keyword).is_some_and(serde_json::Value::is_array))
+    {
+        return Some(serde_json::Value::Object(obj.clone()));
+    }
+    if obj
+        .get("properties")
+        .is_some_and(serde_json::Value::is_object)
+    {
+        let mut schema = obj.clone();
+        schema.insert("type".to_string(), json!("object"));
+        return Some(serde_json::Value::Object(schema));
+    }
+    None
+}
+
+/// Parses a picoschema object into an object JSON Schema.
+fn parse_picoschema_object(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    lookup: SchemaLookup<'_>,
+) -> Result<JsonSchema> {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    let mut additional = json!(false);
+
+    for (key, value) in obj {
+        if key == WILDCARD_PROPERTY_NAME {
+            additional = picoschema_to_json_schema_with(value, lookup)?;
+            continue;
         }
 
-        // Otherwise, convert field by field
-        let mut properties = serde_json::Map::new();
-        for (key, value) in obj {
-            properties.insert(key.clone(), picoschema_to_json_schema(value)?);
+        let (name, type_info) = match key.split_once('(') {
+            Some((name, rest)) => (name, Some(rest.strip_suffix(')').unwrap_or(rest))),
+            None => (key.as_str(), None),
+        };
+        let (name, optional) = name
+            .strip_suffix('?')
+            .map_or((name, false), |name| (name, true));
+        if !optional {
+            required.push(json!(name));
         }
-        return Ok(json!({
-            "type": "object",
-            "properties": properties
-        }));
+
+        let Some(type_info) = type_info else {
+            let mut property = picoschema_to_json_schema_with(value, lookup)?;
+            if optional {
+                make_nullable(&mut property);
+            }
+            properties.insert(name.to_string(), property);
+            continue;
+        };
+
+        let (kind, description) = extract_description(type_info);
+        let mut property = match kind {
+            "array" => json!({
+                "type": if optional { json!(["array", "null"]) } else { json!("array") },
+                "items": picoschema_to_json_schema_with(value, lookup)?,
+            }),
+            "object" => {
+                let mut property = picoschema_to_json_schema_with(value, lookup)?;
+                if optional {
+                    make_nullable(&mut property);
+                }
+                property
+            }
+            "enum" => {
+                let mut values = value.as_array().cloned().ok_or_else(|| {
+                    DotpromptError::PicoschemaError(format!(
+                        "enum values for '{name}' must be a list"
+                    ))
+                })?;
+                if optional && !values.contains(&serde_json::Value::Null) {
+                    values.push(serde_json::Value::Null);
+                }
+                json!({"enum": values})
+            }
+            _ => {
+                return Err(DotpromptError::PicoschemaError(format!(
+                    "parenthetical types must be 'object', 'array', or 'enum', got: {kind}"
+                )));
+            }
+        };
+        if let (Some(description), Some(property)) = (description, property.as_object_mut()) {
+            property.insert("description".to_string(), json!(description));
+        }
+        properties.insert(name.to_string(), property);
+    }
+
+    let mut schema = serde_json::Map::new();
+    schema.insert("type".to_string(), json!("object"));
+    schema.insert(
+        "properties".to_string(),
+        serde_json::Value::Object(properties),
+    );
+    if !required.is_empty() {
+        schema.insert("required".to_string(), serde_json::Value::Array(required));
     }
+    schema.insert("additionalProperties".to_string(), additional);
+    Ok(serde_json::Value::Object(schema))
+}
 
-    // If it's a string, parse the picoschema syntax
-    if let Some(schema_str) = schema.as_str() {
-        return parse_picoschema_string(schema_str);
+/// Makes a schema with a single `type` also accept `null`.
+fn make_nullable(schema: &mut JsonSchema) {
+    if let Some(kind) = schema.get("type").and_then(serde_json::Value::as_str) {
+        let kind = kind.to_string();
+        schema["type"] = json!([kind, "null"]);
     }
+}
 
-    Ok(schema.clone())
+/// Splits `type, description` into its type and description.
+fn extract_description(input: &str) -> (&str, Option<&str>) {
+    input.split_once(',').map_or_else(
+        || (input.trim(), None),
+        |(kind, description)| (kind.trim(), Some(description.trim_start())),
+    )
 }
 
 /// Parses a picoschema string into JSON Schema.
-fn parse_picoschema_string(schema_str: &str) -> Result<JsonSchema> {
-    let trimmed = schema_str.trim();
+fn parse_picoschema_string(schema_str: &str, lookup: SchemaLookup<'_>) -> Result<JsonSchema> {
+    let (kind, description) = extract_description(schema_str);
+    let mut schema = parse_picoschema_type(kind, lookup)?;
+    if let (Some(description), Some(fields)) = (description, schema.as_object_mut()) {
+        fields.insert("description".to_string(), json!(description));
+    }
+    Ok(schema)
+}
+
+/// Parses a picoschema type expression, without a description.
+fn parse_picoschema_type(type_str: &str, lookup: SchemaLookup<'_>) -> Result<JsonSchema> {
+    let trimmed = type_str.trim();
 
     // Handle array syntax: "type[]"
-    if let Some(inner_type) = trimmed.strip_suffix("[]") {
-        let items_schema = parse_picoschema_string(inner_type)?;
-        return Ok(json!({
-            "type": "array",
-            "items": items_schema
-        }));
+    let mut item_type = trimmed;
+    let mut depth = 0;
+    while let Some(inner_type) = item_type.strip_suffix("[]") {
+        item_type = inner_type;
+        depth += 1;
+    }
+    if depth > MAX_ARRAY_DEPTH {
+        return Err(DotpromptError::PicoschemaError(format!(
+            "arrays nested more than {MAX_ARRAY_DEPTH} deep"
+        )));
+    }
+    if depth > 0 {
+        let mut schema = parse_picoschema_type(item_type, lookup)?;
+        for _ in 0..depth {
+            schema = json!({
+                "type": "array",
+                "items": schema
+            });
+        }
+        return Ok(schema);
     }
 
     // Handle union syntax: "type1 | type2"
     if trimmed.contains('|') {
         let types: Vec<_> = trimmed
             .split('|')
-            .map(|s| parse_picoschema_string(s.trim()))
+            .map(|s| parse_picoschema_type(s, lookup))
             .collect::<Result<Vec<_>>>()?;
         return Ok(json!({
             "anyOf": types
         }));
     }
 
-    // Handle primitive types
-    // Handle primitive types
+    // Handle primitive types, then named schemas
     match trimmed {
+        "any" => Ok(json!({})),
         "string" | "number" | "integer" | "boolean" | "object" | "array" | "null" => {
             Ok(json!({"type": trimmed}))
         }
-        _ => {
-            // Unknown type, return as-is or error
-            Err(DotpromptError::PicoschemaError(format!(
-                "unknown picoschema type: {trimmed}"
-            )))
+        _ => lookup(trimmed)?.ok_or_else(|| {
+            DotpromptError::PicoschemaError(format!("unknown picoschema type: {trimmed}"))
+        }),
+    }
+}
+
+/// Replaces references to named schemas, such as
+/// `{"$ref": "#/schemas/Person"}`, with references to definitions under
+/// `$defs`, and adds the referenced schemas there.
+///
+/// Referenced schemas may refer to other named schemas, or to themselves.
+///
+/// # Errors
+///
+/// Returns [`DotpromptError::SchemaResolutionError`] if a referenced schema
+/// is unknown, or the error from `lookup` if it fails.
+pub fn resolve_schema_refs(schema: &mut JsonSchema, lookup: SchemaLookup<'_>) -> Result<()> {
+    let mut pending = Vec::new();
+    rewrite_schema_refs(schema, &mut pending);
+    let mut defs = serde_json::Map::new();
+    while let Some(name) = pending.pop() {
+        if defs.contains_key(&name) {
+            continue;
+        }
+        let mut def = lookup(&name)?.ok_or_else(|| {
+            DotpromptError::SchemaResolutionError(format!("unknown schema '{name}'"))
+        })?;
+        rewrite_schema_refs(&mut def, &mut pending);
+        defs.insert(name, def);
+    }
+    if defs.is_empty() {
+        return Ok(());
+    }
+    let Some(root) = schema.as_object_mut() else {
+        return Ok(());
+    };
+    let root_defs = root
+        .entry("$defs")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    if let Some(root_defs) = root_defs.as_object_mut() {
+        for (name, def) in defs {
+            root_defs.entry(name).or_insert(def);
         }
     }
+    Ok(())
+}
+
+/// Points named schema references at `$defs`, collecting the names.
+fn rewrite_schema_refs(value: &mut serde_json::Value, names: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let name = map
+                .get("$ref")
+                .and_then(serde_json::Value::as_str)
+                .and_then(|reference| reference.strip_prefix(SCHEMA_REF_PREFIX))
+                .map(ToString::to_string);
+            if let Some(name) = name {
+                map.insert("$ref".to_string(), json!(format!("#/$defs/{name}")));
+                names.push(name);
+            }
+            for value in map.values_mut() {
+                rewrite_schema_refs(value, names);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                rewrite_schema_refs(item, names);
+            }
+        }
+        _ => {}
+    }
 }
 
 #[cfg(test)]
@@ -126,6 +367,13 @@ mod tests {
             picoschema_to_json_schema(&json!("string[]")).expect("conversion should succeed");
         assert_eq!(schema["type"], "array");
         assert_eq!(schema["items"]["type"], "string");
+
+        let nested =
+            picoschema_to_json_schema(&json!("number[][]")).expect("conversion should succeed");
+        assert_eq!(nested["items"]["items"]["type"], "number");
+
+        let deep = format!("string{}", "[]".repeat(100_000));
+        assert!(picoschema_to_json_schema(&json!(deep)).is_err());
     }
 
     #[test]
@@ -145,4 +393,96 @@ mod tests {
         assert_eq!(schema["type"], "object");
         assert!(schema["properties"].is_object());
     }
+
+    #[test]
+    fn test_descriptions_optional_and_wildcard() {
+        let input = json!({
+            "name": "string, the full name",
+            "nickname?": "string",
+            "tags(array, labels)": "string",
+            "mood(enum)": ["happy", "sad"],
+            "address?(object)": {"city": "string"},
+            "(*)": "number",
+        });
+        let schema = picoschema_to_json_schema(&input).expect("conversion should succeed");
+        assert_eq!(
+            schema["properties"]["name"],
+            json!({"type": "string", "description": "the full name"})
+        );
+        assert_eq!(
+            schema["properties"]["nickname"]["type"],
+            json!(["string", "null"])
+        );
+        assert_eq!(
+            schema["properties"]["tags"],
+            json!({"type": "array", "items": {"type": "string"}, "description": "labels"})
+        );
+        assert_eq!(
+            schema["properties"]["mood"]["enum"],
+            json!(["happy", "sad"])
+        );
+        assert_eq!(
+            schema["properties"]["address"]["type"],
+            json!(["object", "null"])
+        );
+        // Fields are listed in the order the object map keeps them in.
+        let required = if cfg!(feature = "preserve_order") {
+            json!(["name", "tags", "mood"])
+        } else {
+            json!(["mood", "name", "tags"])
+        };
+        assert_eq!(schema["required"], required);
+        assert_eq!(schema["additionalProperties"], json!({"type": "number"}));
+
+        let plain = picoschema_to_json_schema(&json!({"a?": "any"})).expect("conversion");
+        assert_eq!(plain["additionalProperties"], json!(false));
+        assert!(plain.get("required").is_none());
+    }
+
+    #[test]
+    fn test_named_types() {
+        let person = json!({"type": "object", "properties": {"name": {"type": "string"}}});
+        let lookup = |name: &str| Ok((name == "Person").then(|| person.clone()));
+
+        let schema = picoschema_to_json_schema_with(
+            &json!({"author": "Person", "reviewers": "Person[]"}),
+            &lookup,
+        )
+        .expect("conversion should succeed");
+        assert_eq!(schema["properties"]["author"], person);
+        assert_eq!(schema["properties"]["reviewers"]["items"], person);
+        assert!(picoschema_to_json_schema_with(&json!("Robot"), &lookup).is_err());
+    }
+
+    #[test]
+    fn test_resolve_schema_refs() {
+        let lookup = |name: &str| {
+            Ok(match name {
+                "Person" => Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "address": {"$ref": "#/schemas/Address"},
+                        "friends": {"type": "array", "items": {"$ref": "#/schemas/Person"}},
+                    },
+                })),
+                "Address" => Some(json!({"type": "string"})),
+                _ => None,
+            })
+        };
+
+        let mut schema = json!({"type": "array", "items": {"$ref": "#/schemas/Person"}});
+        resolve_schema_refs(&mut schema, &lookup).expect("refs should resolve");
+        assert_eq!(schema["items"]["$ref"], "#/$defs/Person");
+        assert_eq!(
+            schema["$defs"]["Person"]["properties"]["friends"]["items"]["$ref"],
+            "#/$defs/Person"
+        );
+        assert_eq!(schema["$defs"]["Address"], json!({"type": "string"}));
+
+        let mut schema = json!({"$ref": "#/schemas/Robot"});
+        assert!(matches!(
+            resolve_schema_refs(&mut schema, &lookup),
+            Err(DotpromptError::SchemaResolutionError(_))
+        ));
+    }
 }
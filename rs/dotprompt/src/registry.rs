@@ -0,0 +1,423 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A registry of the prompts in a store, rendered by name.
+//!
+//! [`PromptRegistry`] loads a store's prompts once, parses them, and
+//! registers the partials they use, so that services can render a prompt by
+//! name without reading the store on every request:
+//!
+//! ```no_run
+//! use dotprompt::stores::{DirStore, DirStoreOptions};
+//! use dotprompt::{DataArgument, Dotprompt, PromptRegistry, RenderedPrompt};
+//!
+//! # fn example() -> dotprompt::Result<()> {
+//! let store = DirStore::new(DirStoreOptions {
+//!     directory: "prompts".into(),
+//!     ..Default::default()
+//! });
+//! let registry = PromptRegistry::load(Dotprompt::new(None), store, None)?;
+//!
+//! let data = DataArgument::<serde_json::Value> {
+//!     input: Some(serde_json::json!({"order": "A-1"})),
+//!     ..Default::default()
+//! };
+//! let rendered: RenderedPrompt = registry.render("checkout/confirm", &data)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::dotprompt::Dotprompt;
+use crate::error::{DotpromptError, Result};
+use crate::store::PromptStore;
+use crate::types::{
+    DataArgument, ListPromptsOptions, LoadPromptOptions, PromptFunction, PromptRef, RenderedPrompt,
+};
+
+/// Options for a [`PromptRegistry`].
+#[derive(Debug, Clone, Default)]
+pub struct PromptRegistryOptions {
+    /// Load each prompt the first time it is used instead of loading every
+    /// prompt in [`PromptRegistry::load`].
+    pub lazy: bool,
+}
+
+/// Prompts keyed by name and variant.
+type Prompts = HashMap<(String, Option<String>), Arc<Entry>>;
+
+/// A prompt loaded into a registry.
+#[derive(Debug)]
+struct Entry {
+    source: String,
+    function: PromptFunction,
+}
+
+/// Prompts from a store, parsed once and rendered by name.
+///
+/// Partials the prompts use are loaded from the same store and registered
+/// with the registry's [`Dotprompt`]; partials the store does not have are
+/// left to the `Dotprompt`'s own resolvers. The registry can be shared
+/// between threads.
+#[derive(Debug)]
+pub struct PromptRegistry<S> {
+    dotprompt: Dotprompt,
+    store: S,
+    lazy: bool,
+    /// Loaded prompts.
+    prompts: RwLock<Prompts>,
+    /// Partials registered from the store.
+    partials: RwLock<HashSet<String>>,
+}
+
+impl<S: PromptStore> PromptRegistry<S> {
+    /// Creates a registry for the prompts in `store`, rendering them with
+    /// `dotprompt`.
+    ///
+    /// Unless [`PromptRegistryOptions::lazy`] is set, every prompt and
+    /// variant in the store is loaded and parsed now.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be listed or a prompt cannot be
+    /// loaded, and [`DotpromptError::InvalidPrompt`] if a prompt does not
+    /// parse or uses a partial that does not compile.
+    pub fn load(
+        dotprompt: Dotprompt,
+        store: S,
+        options: Option<PromptRegistryOptions>,
+    ) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        let registry = Self {
+            dotprompt,
+            store,
+            lazy: options.lazy,
+            prompts: RwLock::new(HashMap::new()),
+            partials: RwLock::new(HashSet::new()),
+        };
+        if !registry.lazy {
+            for prompt_ref in registry.list_store()? {
+                registry.load_prompt(&prompt_ref.name, prompt_ref.variant.as_deref())?;
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Returns the `Dotprompt` the registry renders with.
+    #[must_use]
+    pub const fn dotprompt(&self) -> &Dotprompt {
+        &self.dotprompt
+    }
+
+    /// Returns the names of the loaded prompts, sorted, with variants as
+    /// `name.variant`.
+    ///
+    /// A lazy registry lists only the prompts used so far.
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        let prompts = self.prompts.read().unwrap_or_else(PoisonError::into_inner);
+        let mut names: Vec<String> = prompts
+            .keys()
+            .map(|(name, variant)| {
+                variant
+                    .as_ref()
+                    .map_or_else(|| name.clone(), |variant| format!("{name}.{variant}"))
+            })
+            .collect();
+        drop(prompts);
+        names.sort();
+        names
+    }
+
+    /// Returns the parsed prompt with the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::UnknownPrompt`] if there is no such prompt,
+    /// and, for a lazy registry, the errors of [`load`](Self::load).
+    pub fn get(&self, name: &str) -> Result<PromptFunction> {
+        self.get_variant(name, None)
+    }
+
+    /// Returns the parsed prompt with the given name and variant.
+    ///
+    /// # Errors
+    ///
+    /// As for [`get`](Self::get).
+    pub fn get_variant(&self, name: &str, variant: Option<&str>) -> Result<PromptFunction> {
+        Ok(self.entry(name, variant)?.function.clone())
+    }
+
+    /// Renders the prompt with the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns the lookup errors of [`get`](Self::get) and the render errors
+    /// of [`Dotprompt::render`].
+    pub fn render<V>(&self, name: &str, data: &DataArgument<V>) -> Result<RenderedPrompt>
+    where
+        V: serde::Serialize + Default + Clone,
+    {
+        self.render_variant(name, None, data)
+    }
+
+    /// Renders the prompt with the given name and variant.
+    ///
+    /// # Errors
+    ///
+    /// As for [`render`](Self::render).
+    pub fn render_variant<V>(
+        &self,
+        name: &str,
+        variant: Option<&str>,
+        data: &DataArgument<V>,
+    ) -> Result<RenderedPrompt>
+    where
+        V: serde::Serialize + Default + Clone,
+    {
+        let entry = self.entry(name, variant)?;
+        self.dotprompt.render(&entry.source, data, None)
+    }
+
+    /// Returns a loaded prompt, loading it first if the registry is lazy.
+    fn entry(&self, name: &str, variant: Option<&str>) -> Result<Arc<Entry>> {
+        let key = (name.to_string(), variant.map(str::to_string));
+        let loaded = self
+            .prompts
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .cloned();
+        match loaded {
+            Some(entry) => Ok(entry),
+            None if self.lazy => self.load_prompt(name, variant),
+            None => Err(unknown_prompt(name, variant)),
+        }
+    }
+
+    /// Lists every prompt and variant in the store.
+    fn list_store(&self) -> Result<Vec<PromptRef>> {
+        let mut prompts = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = self.store.list(Some(ListPromptsOptions {
+                cursor,
+                ..Default::default()
+            }))?;
+            prompts.extend(page.prompts);
+            match page.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        Ok(prompts)
+    }
+
+    /// Loads, parses, and caches a prompt, registering its partials.
+    fn load_prompt(&self, name: &str, variant: Option<&str>) -> Result<Arc<Entry>> {
+        let options = LoadPromptOptions {
+            variant: variant.map(str::to_string),
+            version: None,
+        };
+        let data = match self.store.load(name, Some(options)) {
+            Err(DotpromptError::NotFound(_)) => return Err(unknown_prompt(name, variant)),
+            other => other?,
+        };
+        let invalid = |e: DotpromptError| DotpromptError::InvalidPrompt {
+            name: name.to_string(),
+            source: Box::new(e),
+        };
+        let function = self
+            .dotprompt
+            .compile::<serde_json::Value>(&data.source, None)
+            .map_err(invalid)?;
+        self.register_partials(&function.prompt.template)
+            .map_err(invalid)?;
+
+        let entry = Arc::new(Entry {
+            source: data.source,
+            function,
+        });
+        self.prompts
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(
+                (name.to_string(), variant.map(str::to_string)),
+                Arc::clone(&entry),
+            );
+        Ok(entry)
+    }
+
+    /// Registers the partials a template uses, and the partials they use,
+    /// from the store, then resolves the rest with the `Dotprompt`.
+    fn register_partials(&self, template: &str) -> Result<()> {
+        let mut pending = vec![template.to_string()];
+        while let Some(source) = pending.pop() {
+            for name in self.dotprompt.identify_partials(&source) {
+                let registered = self
+                    .partials
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .contains(&name);
+                if registered {
+                    continue;
+                }
+                let partial = match self.store.load_partial(&name, None) {
+                    Ok(partial) => partial,
+                    Err(DotpromptError::NotFound(_)) => continue,
+                    Err(e) => return Err(e),
+                };
+                self.dotprompt
+                    .define_partial(name.as_str(), &partial.source)?;
+                self.partials
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(name);
+                pending.push(partial.source);
+            }
+        }
+        self.dotprompt.resolve_partials(template)
+    }
+}
+
+/// Returns the error for a prompt that is not in the registry.
+fn unknown_prompt(name: &str, variant: Option<&str>) -> DotpromptError {
+    DotpromptError::UnknownPrompt {
+        name: name.to_string(),
+        variant: variant.map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::stores::dir::{DirStore, DirStoreOptions};
+    use crate::types::{Part, TextPart};
+
+    /// Creates a store with a nested prompt, a variant, and partials.
+    fn store(test: &str) -> (DirStore, PathBuf) {
+        let directory =
+            std::env::temp_dir().join(format!("dotprompt-registry-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(directory.join("checkout")).expect("create store");
+        let files = [
+            (
+                "checkout/confirm.prompt",
+                "{{> header}}Order {{order}} confirmed.",
+            ),
+            ("checkout/confirm.terse.prompt", "{{order}} ok"),
+            ("_header.prompt", "[{{> brand}}] "),
+            ("_brand.prompt", "ACME"),
+        ];
+        for (path, source) in files {
+            std::fs::write(directory.join(path), source).expect("write prompt");
+        }
+        let store = DirStore::new(DirStoreOptions {
+            directory: directory.clone(),
+            ..Default::default()
+        });
+        (store, directory)
+    }
+
+    fn text(rendered: &RenderedPrompt) -> &str {
+        match &rendered.messages[0].content[0] {
+            Part::Text(TextPart { text, .. }) => text,
+            _ => "",
+        }
+    }
+
+    fn order(id: &str) -> DataArgument<serde_json::Value> {
+        DataArgument {
+            input: Some(json!({"order": id})),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_by_name() {
+        let (store, directory) = store("render");
+        let registry = PromptRegistry::load(Dotprompt::new(None), store, None).expect("load");
+        assert_eq!(
+            registry.names(),
+            ["checkout/confirm", "checkout/confirm.terse"]
+        );
+
+        let rendered = registry
+            .render("checkout/confirm", &order("A-1"))
+            .expect("render");
+        assert_eq!(text(&rendered), "[ACME] Order A-1 confirmed.");
+        let rendered = registry
+            .render_variant("checkout/confirm", Some("terse"), &order("A-1"))
+            .expect("render variant");
+        assert_eq!(text(&rendered), "A-1 ok");
+
+        let err = registry.get("checkout/cancel").expect_err("unknown");
+        assert!(
+            matches!(&err, DotpromptError::UnknownPrompt { name, variant: None } if name == "checkout/cancel"),
+            "{err:?}"
+        );
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn test_invalid_prompt_names_the_prompt() {
+        let (store, directory) = store("invalid");
+        std::fs::write(directory.join("broken.prompt"), "---\nmodel: [\n---\nHi")
+            .expect("write prompt");
+        let err = PromptRegistry::load(Dotprompt::new(None), store, None).expect_err("invalid");
+        assert!(
+            matches!(&err, DotpromptError::InvalidPrompt { name, .. } if name == "broken"),
+            "{err:?}"
+        );
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn test_lazy_registry_loads_on_first_use() {
+        let (store, directory) = store("lazy");
+        std::fs::write(directory.join("broken.prompt"), "---\nmodel: [\n---\nHi")
+            .expect("write prompt");
+        let registry = PromptRegistry::load(
+            Dotprompt::new(None),
+            store,
+            Some(PromptRegistryOptions { lazy: true }),
+        )
+        .expect("lazy load skips broken prompts");
+        assert!(registry.names().is_empty());
+
+        let rendered = registry
+            .render("checkout/confirm", &order("B-2"))
+            .expect("render");
+        assert_eq!(text(&rendered), "[ACME] Order B-2 confirmed.");
+        assert_eq!(registry.names(), ["checkout/confirm"]);
+        assert!(matches!(
+            registry.get("missing"),
+            Err(DotpromptError::UnknownPrompt { .. })
+        ));
+        assert!(matches!(
+            registry.get("broken"),
+            Err(DotpromptError::InvalidPrompt { .. })
+        ));
+        let _ = std::fs::remove_dir_all(directory);
+    }
+}
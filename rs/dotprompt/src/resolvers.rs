@@ -0,0 +1,338 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resolvers backed by a prompt store or directory.
+//!
+//! These let a [`Dotprompt`](crate::Dotprompt) load partials, tools, and
+//! schemas from the same place as its prompts:
+//!
+//! - [`StorePartialResolver`] loads partials from any [`PromptStore`].
+//! - [`StoreToolResolver`] loads tools from `<name>.tool.yaml` files.
+//! - [`StoreSchemaResolver`] loads schemas from `<name>.schema.yaml` or
+//!   `<name>.schema.json` files, by convention in a `schemas/` directory
+//!   next to the prompts.
+//! - [`StoreExampleResolver`] loads few-shot examples from the files that
+//!   prompts name in `examples.source`.
+//!
+//! ```no_run
+//! use dotprompt::resolvers::{StorePartialResolver, StoreSchemaResolver, StoreToolResolver};
+//! use dotprompt::stores::{DirStore, DirStoreOptions};
+//! use dotprompt::{Dotprompt, DotpromptOptions};
+//!
+//! let store = DirStore::new(DirStoreOptions {
+//!     directory: "prompts".into(),
+//!     ..Default::default()
+//! });
+//! let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+//!     partial_resolver: Some(Box::new(StorePartialResolver::new(store))),
+//!     tool_resolver: Some(Box::new(StoreToolResolver::new("prompts"))),
+//!     schema_resolver: Some(Box::new(StoreSchemaResolver::for_prompt_dir("prompts"))),
+//!     ..Default::default()
+//! }));
+//! ```
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{DotpromptError, Result};
+use crate::examples::parse_examples;
+use crate::store::PromptStore;
+use crate::types::{
+    ExampleResolver, JsonSchema, PartialResolver, SchemaResolver, ToolDefinition, ToolResolver,
+};
+use crate::util::validate_prompt_name;
+
+/// Directory, relative to a prompt directory, that holds named schemas.
+pub const SCHEMAS_DIR: &str = "schemas";
+
+/// Resolves partials by loading them from a [`PromptStore`].
+#[derive(Debug, Clone)]
+pub struct StorePartialResolver<S> {
+    store: S,
+}
+
+impl<S: PromptStore> StorePartialResolver<S> {
+    /// Creates a resolver that loads partials from `store`.
+    pub const fn new(store: S) -> Self {
+        Self { store }
+    }
+}
+
+impl<S: PromptStore> PartialResolver for StorePartialResolver<S> {
+    fn resolve(&self, name: &str) -> Result<Option<String>> {
+        match self.store.load_partial(name, None) {
+            Ok(partial) => Ok(Some(partial.source)),
+            Err(DotpromptError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Resolves tools from `<name>.tool.yaml` files in a directory.
+///
+/// Each file holds a [`ToolDefinition`]; its `name` defaults to the file
+/// name:
+///
+/// ```yaml
+/// description: Looks up the weather for a city.
+/// inputSchema:
+///   type: object
+///   properties:
+///     city: {type: string}
+/// ```
+#[derive(Debug, Clone)]
+pub struct StoreToolResolver {
+    directory: PathBuf,
+}
+
+impl StoreToolResolver {
+    /// Creates a resolver that loads tools from `directory`.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+}
+
+impl ToolResolver for StoreToolResolver {
+    fn resolve(&self, name: &str) -> Result<Option<ToolDefinition>> {
+        let Some((path, source)) = read_named(&self.directory, name, &["tool.yaml", "tool.yml"])?
+        else {
+            return Ok(None);
+        };
+        let mut value: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str(&source).map_err(|e| invalid_file(&path, &e))?;
+        if let Some(mapping) = value
+            .as_mapping_mut()
+            .filter(|mapping| !mapping.contains_key("name"))
+        {
+            mapping.insert("name".into(), name.into());
+        }
+        serde_yaml_ng::from_value(value)
+            .map(Some)
+            .map_err(|e| invalid_file(&path, &e))
+    }
+}
+
+/// Resolves schemas from `<name>.schema.yaml` or `<name>.schema.json` files
+/// in a directory.
+///
+/// Prompts refer to these schemas by name, either as a picoschema type or
+/// with a JSON Schema reference:
+///
+/// ```yaml
+/// input:
+///   schema:
+///     author: Person
+///     reviewers: Person[]
+/// output:
+///   schema:
+///     $ref: "#/schemas/Review"
+/// ```
+#[derive(Debug, Clone)]
+pub struct StoreSchemaResolver {
+    directory: PathBuf,
+}
+
+impl StoreSchemaResolver {
+    /// Creates a resolver that loads schemas from `directory`.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Creates a resolver that loads schemas from the [`SCHEMAS_DIR`]
+    /// directory inside `prompt_dir`.
+    pub fn for_prompt_dir(prompt_dir: impl AsRef<Path>) -> Self {
+        Self::new(prompt_dir.as_ref().join(SCHEMAS_DIR))
+    }
+}
+
+impl SchemaResolver for StoreSchemaResolver {
+    fn resolve(&self, name: &str) -> Result<Option<JsonSchema>> {
+        let extensions = ["schema.yaml", "schema.yml", "schema.json"];
+        let Some((path, source)) = read_named(&self.directory, name, &extensions)? else {
+            return Ok(None);
+        };
+        let schema = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&source).map_err(|e| invalid_file(&path, &e))?
+        } else {
+            serde_yaml_ng::from_str(&source).map_err(|e| invalid_file(&path, &e))?
+        };
+        Ok(Some(schema))
+    }
+}
+
+/// Resolves few-shot examples from files in a directory.
+///
+/// Sources are paths relative to the directory, such as
+/// `./examples/sentiment.jsonl`; they may not leave it.
+#[derive(Debug, Clone)]
+pub struct StoreExampleResolver {
+    directory: PathBuf,
+}
+
+impl StoreExampleResolver {
+    /// Creates a resolver that loads examples from files in `directory`.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+}
+
+impl ExampleResolver for StoreExampleResolver {
+    fn resolve(&self, source: &str) -> Result<Option<Vec<serde_json::Value>>> {
+        let relative = Path::new(source);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(DotpromptError::ExampleResolutionError(format!(
+                "{source}: must be a path inside the prompt directory"
+            )));
+        }
+        match fs::read_to_string(self.directory.join(relative)) {
+            Ok(text) => parse_examples(source, &text).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Reads the first of `<name>.<extension>` that exists in `directory`.
+fn read_named(
+    directory: &Path,
+    name: &str,
+    extensions: &[&str],
+) -> Result<Option<(PathBuf, String)>> {
+    validate_prompt_name(name)?;
+    for extension in extensions {
+        let path = directory.join(format!("{name}.{extension}"));
+        match fs::read_to_string(&path) {
+            Ok(source) => return Ok(Some((path, source))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// Describes a file that could not be parsed.
+fn invalid_file(path: &Path, error: &impl std::fmt::Display) -> DotpromptError {
+    DotpromptError::StoreError(format!("invalid file '{}': {error}", path.display()))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::stores::{DirStore, DirStoreOptions};
+
+    /// Creates an empty directory for a test.
+    fn directory(test: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("dotprompt-{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).expect("create directory");
+        directory
+    }
+
+    #[test]
+    fn test_store_partial_resolver() {
+        let dir = directory("partial-resolver");
+        fs::write(dir.join("_header.prompt"), "Header").expect("write");
+        let resolver = StorePartialResolver::new(DirStore::new(DirStoreOptions {
+            directory: dir.clone(),
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            resolver.resolve("header").expect("resolve").as_deref(),
+            Some("Header")
+        );
+        assert_eq!(resolver.resolve("footer").expect("resolve"), None);
+        assert!(resolver.resolve("../header").is_err());
+        fs::remove_dir_all(dir).expect("remove directory");
+    }
+
+    #[test]
+    fn test_store_tool_resolver() {
+        let dir = directory("tool-resolver");
+        fs::write(
+            dir.join("weather.tool.yaml"),
+            "description: Looks up the weather.\ninputSchema:\n  type: object\n",
+        )
+        .expect("write");
+        fs::write(dir.join("broken.tool.yaml"), "description: [").expect("write");
+        let resolver = StoreToolResolver::new(&dir);
+
+        let tool = resolver.resolve("weather").expect("resolve").expect("tool");
+        assert_eq!(tool.name, "weather");
+        assert_eq!(tool.description.as_deref(), Some("Looks up the weather."));
+        assert_eq!(resolver.resolve("missing").expect("resolve"), None);
+        assert!(matches!(
+            resolver.resolve("broken"),
+            Err(DotpromptError::StoreError(_))
+        ));
+        fs::remove_dir_all(dir).expect("remove directory");
+    }
+
+    #[test]
+    fn test_store_schema_resolver() {
+        let dir = directory("schema-resolver");
+        fs::write(dir.join("person.schema.yaml"), "type: object\n").expect("write");
+        fs::write(dir.join("city.schema.json"), r#"{"type": "string"}"#).expect("write");
+        let resolver = StoreSchemaResolver::new(&dir);
+
+        assert_eq!(
+            resolver.resolve("person").expect("resolve"),
+            Some(serde_json::json!({"type": "object"}))
+        );
+        assert_eq!(
+            resolver.resolve("city").expect("resolve"),
+            Some(serde_json::json!({"type": "string"}))
+        );
+        assert_eq!(resolver.resolve("missing").expect("resolve"), None);
+        fs::remove_dir_all(dir).expect("remove directory");
+    }
+
+    #[test]
+    fn test_store_example_resolver() {
+        let dir = directory("example-resolver");
+        fs::create_dir_all(dir.join("examples")).expect("create directory");
+        fs::write(dir.join("examples/qa.jsonl"), "{\"q\": 1}\n{\"q\": 2}\n").expect("write");
+        let resolver = StoreExampleResolver::new(&dir);
+
+        assert_eq!(
+            resolver.resolve("./examples/qa.jsonl").expect("resolve"),
+            Some(vec![
+                serde_json::json!({"q": 1}),
+                serde_json::json!({"q": 2})
+            ])
+        );
+        assert_eq!(
+            resolver.resolve("examples/missing.jsonl").expect("resolve"),
+            None
+        );
+        assert!(matches!(
+            resolver.resolve("../qa.jsonl"),
+            Err(DotpromptError::ExampleResolutionError(_))
+        ));
+        fs::remove_dir_all(dir).expect("remove directory");
+    }
+}
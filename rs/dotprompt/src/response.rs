@@ -0,0 +1,563 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parsing model responses against a prompt's output config.
+//!
+//! [`Dotprompt::parse_response`](crate::Dotprompt::parse_response) parses a
+//! response with the prompt's [output format](crate::formats), coerces
+//! strings that differ from one of the schema's `enum` values only in case
+//! or surrounding whitespace, and then checks the value against the output
+//! schema.
+//!
+//! Schemas are checked for the JSON Schema keywords that picoschema
+//! produces and a few common others: `type`, `enum`, `const`, `properties`,
+//! `required`, `additionalProperties`, `items`, `minItems`, `maxItems`,
+//! `minLength`, `maxLength`, `minimum`, `maximum`, `anyOf`, `oneOf`, and
+//! `allOf`. Other keywords are ignored.
+//!
+//! When a response does not parse, [`append_repair`] adds it and a message
+//! asking the model to fix it to the conversation, for another attempt:
+//!
+//! ```no_run
+//! # use dotprompt::{DataArgument, Dotprompt, DotpromptError, RenderedPrompt};
+//! # use dotprompt::response::append_repair;
+//! # fn call_model(_: &[dotprompt::Message]) -> String { String::new() }
+//! # fn example(dp: &Dotprompt, source: &str) -> dotprompt::Result<()> {
+//! let rendered: RenderedPrompt = dp.render(source, &DataArgument::<()>::default(), None)?;
+//! let schema = rendered.metadata.output.as_ref().and_then(|o| o.schema.as_ref());
+//! let mut messages = rendered.messages.clone();
+//! for _ in 0..3 {
+//!     let text = call_model(&messages);
+//!     match dp.parse_response(&rendered.metadata, &text) {
+//!         Err(DotpromptError::InvalidResponse(err)) => {
+//!             append_repair(&mut messages, &text, &err, schema);
+//!         }
+//!         result => return result.map(drop),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::error::Result;
+use crate::formats::{find_enum_value, json_type};
+use crate::types::{JsonSchema, Message, Part, Role, TextPart};
+
+/// A model response parsed with a prompt's output config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedResponse {
+    /// The format the response was parsed with.
+    pub format: String,
+    /// The parsed output.
+    pub output: Value,
+}
+
+impl ParsedResponse {
+    /// Deserializes the output into `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::JsonParseError`](crate::DotpromptError::JsonParseError)
+    /// if the output does not fit `T`.
+    pub fn into_typed<T: DeserializeOwned>(self) -> Result<T> {
+        Ok(serde_json::from_value(self.output)?)
+    }
+}
+
+/// Why a model response could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ResponseError {
+    /// The response is not in the output format.
+    #[error("response is not valid {format}: {reason}")]
+    Unparsable {
+        /// The output format.
+        format: String,
+        /// What is wrong.
+        reason: String,
+    },
+
+    /// The parsed output does not match the output schema.
+    #[error("response does not match the output schema: {}", list(violations))]
+    SchemaMismatch {
+        /// The parsed output.
+        output: Value,
+        /// Each way the output differs from the schema.
+        violations: Vec<SchemaViolation>,
+    },
+}
+
+impl ResponseError {
+    /// Returns a message asking a model to correct a response that failed
+    /// with this error, repeating `schema` if there is one.
+    #[must_use]
+    pub fn repair_instructions(&self, schema: Option<&JsonSchema>) -> String {
+        let mut text = match self {
+            Self::Unparsable { format, reason } => {
+                format!("Your previous response could not be parsed as {format}: {reason}.\n")
+            }
+            Self::SchemaMismatch { violations, .. } => {
+                let mut text =
+                    "Your previous response does not match the required schema:\n\n".to_string();
+                for violation in violations {
+                    let _ = writeln!(text, "- {violation}");
+                }
+                text
+            }
+        };
+        if let Some(schema) = schema {
+            let schema =
+                serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string());
+            let _ = write!(
+                text,
+                "
+The output must conform to this schema:
+
+```
+{schema}
+```
+"
+            );
+        }
+        text.push_str("\nRespond again with only the corrected output.");
+        text
+    }
+}
+
+/// Appends a failed response and a request to repair it to a conversation.
+///
+/// The response is added as a `model` message, followed by a `user` message
+/// with [`ResponseError::repair_instructions`] whose part has `purpose:
+/// repair`. Sending the conversation again gives the model a chance to fix
+/// its output; the caller decides how many attempts to make.
+pub fn append_repair(
+    messages: &mut Vec<Message>,
+    response: &str,
+    error: &ResponseError,
+    schema: Option<&JsonSchema>,
+) {
+    messages.push(Message {
+        role: Role::Model,
+        content: vec![Part::Text(TextPart {
+            text: response.to_string(),
+            metadata: None,
+        })],
+        metadata: None,
+    });
+    messages.push(Message {
+        role: Role::User,
+        content: vec![Part::Text(TextPart {
+            text: error.repair_instructions(schema),
+            metadata: Some(HashMap::from([(
+                "purpose".to_string(),
+                Value::String("repair".to_string()),
+            )])),
+        })],
+        metadata: None,
+    });
+}
+
+/// A way a value differs from a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Where in the value, such as `$.items[2].name`.
+    pub path: String,
+    /// What is wrong there.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Joins violations for an error message.
+fn list(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Checks a value against a JSON Schema, returning every violation.
+#[must_use]
+pub fn validate(schema: &JsonSchema, value: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    check(schema, value, "$", &mut violations);
+    violations
+}
+
+/// Replaces strings that match one of their schema's `enum` values except
+/// for case or surrounding whitespace with that value.
+pub fn coerce_enums(schema: &JsonSchema, value: &mut Value) {
+    if let (Some(values), Value::String(text)) =
+        (schema.get("enum").and_then(Value::as_array), &*value)
+    {
+        if let Some(matched) = find_enum_value(values, text) {
+            *value = matched.clone();
+        }
+        return;
+    }
+    match value {
+        Value::Object(fields) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, field) in fields {
+                if let Some(property) = properties.and_then(|properties| properties.get(name)) {
+                    coerce_enums(property, field);
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for item in items {
+                    coerce_enums(item_schema, item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks `value` at `path` against `schema`.
+fn check(schema: &JsonSchema, value: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let mut violation = |message: String| {
+        out.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        });
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(kind) => vec![kind.as_str()],
+            Value::Array(kinds) => kinds.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|kind| has_type(value, kind)) {
+            violation(format!(
+                "expected {}, got {}",
+                types.join(" or "),
+                json_type(value)
+            ));
+            return;
+        }
+    }
+    if let Some(values) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .filter(|values| !values.contains(value))
+    {
+        violation(format!(
+            "expected one of {}, got {value}",
+            values
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if let Some(expected) = schema.get("const").filter(|expected| *expected != value) {
+        violation(format!("expected {expected}, got {value}"));
+    }
+
+    match value {
+        Value::Object(fields) => check_object(schema, fields, path, out),
+        Value::Array(items) => check_array(schema, items, path, out),
+        Value::String(text) => {
+            let length = text.chars().count();
+            check_bounds(
+                schema,
+                "minLength",
+                "maxLength",
+                length,
+                "characters",
+                path,
+                out,
+            );
+        }
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                check_range(schema, number, path, out);
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+    check_combinators(schema, value, path, out);
+}
+
+/// Checks the fields of an object.
+fn check_object(
+    schema: &Map<String, Value>,
+    fields: &Map<String, Value>,
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for name in schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        if !fields.contains_key(name) {
+            out.push(SchemaViolation {
+                path: format!("{path}.{name}"),
+                message: "is required".to_string(),
+            });
+        }
+    }
+    for (name, field) in fields {
+        let field_path = format!("{path}.{name}");
+        match (
+            properties.and_then(|properties| properties.get(name)),
+            schema.get("additionalProperties"),
+        ) {
+            (Some(property), _) => check(property, field, &field_path, out),
+            (None, Some(Value::Bool(false))) => out.push(SchemaViolation {
+                path: field_path,
+                message: "is not allowed".to_string(),
+            }),
+            (None, Some(additional @ Value::Object(_))) => {
+                check(additional, field, &field_path, out);
+            }
+            (None, _) => {}
+        }
+    }
+}
+
+/// Checks the items of an array.
+fn check_array(
+    schema: &Map<String, Value>,
+    items: &[Value],
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    check_bounds(
+        schema,
+        "minItems",
+        "maxItems",
+        items.len(),
+        "items",
+        path,
+        out,
+    );
+    if let Some(item_schema) = schema.get("items") {
+        for (i, item) in items.iter().enumerate() {
+            check(item_schema, item, &format!("{path}[{i}]"), out);
+        }
+    }
+}
+
+/// Checks a length against a schema's minimum and maximum for it.
+fn check_bounds(
+    schema: &Map<String, Value>,
+    min: &str,
+    max: &str,
+    length: usize,
+    unit: &str,
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let bound = |keyword| {
+        schema
+            .get(keyword)
+            .and_then(Value::as_u64)
+            .and_then(|bound| usize::try_from(bound).ok())
+    };
+    let message = match (bound(min), bound(max)) {
+        (Some(min), _) if length < min => format!("has {length} {unit}, fewer than {min}"),
+        (_, Some(max)) if length > max => format!("has {length} {unit}, more than {max}"),
+        _ => return,
+    };
+    out.push(SchemaViolation {
+        path: path.to_string(),
+        message,
+    });
+}
+
+/// Checks a number against a schema's `minimum` and `maximum`.
+fn check_range(
+    schema: &Map<String, Value>,
+    number: f64,
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let bound = |keyword| schema.get(keyword).and_then(Value::as_f64);
+    let message = match (bound("minimum"), bound("maximum")) {
+        (Some(min), _) if number < min => format!("is {number}, less than {min}"),
+        (_, Some(max)) if number > max => format!("is {number}, more than {max}"),
+        _ => return,
+    };
+    out.push(SchemaViolation {
+        path: path.to_string(),
+        message,
+    });
+}
+
+/// Checks `anyOf`, `oneOf`, and `allOf`.
+///
+/// `oneOf` is checked like `anyOf`: a value matching more than one of its
+/// schemas is accepted.
+fn check_combinators(
+    schema: &Map<String, Value>,
+    value: &Value,
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    for keyword in ["anyOf", "oneOf"] {
+        let Some(options) = schema.get(keyword).and_then(Value::as_array) else {
+            continue;
+        };
+        if !options
+            .iter()
+            .any(|option| validate(option, value).is_empty())
+        {
+            out.push(SchemaViolation {
+                path: path.to_string(),
+                message: "does not match any of the allowed schemas".to_string(),
+            });
+        }
+    }
+    for part in schema
+        .get("allOf")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        check(part, value, path, out);
+    }
+}
+
+/// Returns whether a value has a JSON Schema type.
+fn has_type(value: &Value, kind: &str) -> bool {
+    match kind {
+        "integer" => {
+            value.as_i64().is_some()
+                || value.as_u64().is_some()
+                || value.as_f64().is_some_and(|number| number.fract() == 0.0)
+        }
+        "number" => value.is_number(),
+        other => json_type(value) == other,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "age": {"type": ["integer", "null"], "minimum": 0},
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2},
+                "mood": {"enum": ["happy", "sad"]},
+            },
+            "required": ["name", "mood"],
+            "additionalProperties": false,
+        });
+        assert!(validate(&schema, &json!({"name": "Ada", "age": null, "mood": "sad"})).is_empty());
+
+        let value = json!({"age": 1.5, "tags": ["a", 2, "c"], "mood": "angry", "extra": true});
+        let violations: Vec<String> = validate(&schema, &value)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let mut expected = vec![
+            "$.name: is required",
+            "$.age: expected integer or null, got number",
+            "$.tags: has 3 items, more than 2",
+            "$.tags[1]: expected string, got number",
+            r#"$.mood: expected one of "happy", "sad", got "angry""#,
+            "$.extra: is not allowed",
+        ];
+        let mut violations = violations;
+        violations.sort();
+        expected.sort_unstable();
+        assert_eq!(violations, expected);
+
+        let union = json!({"anyOf": [{"type": "string"}, {"type": "integer"}]});
+        assert!(validate(&union, &json!(3)).is_empty());
+        assert_eq!(validate(&union, &json!(true)).len(), 1);
+    }
+
+    #[test]
+    fn test_append_repair() {
+        let schema = json!({"type": "object", "required": ["stars"]});
+        let error = ResponseError::SchemaMismatch {
+            output: json!({}),
+            violations: validate(&schema, &json!({})),
+        };
+        let mut messages = Vec::new();
+        append_repair(&mut messages, "{}", &error, Some(&schema));
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, Role::Model);
+        assert_eq!(messages[1].role, Role::User);
+        let repair = match &messages[1].content[0] {
+            Part::Text(repair) => repair.clone(),
+            other => TextPart {
+                text: format!("{other:?}"),
+                metadata: None,
+            },
+        };
+        assert!(repair.text.contains("- $.stars: is required\n"));
+        assert!(repair.text.contains("\"required\": ["));
+        assert!(
+            repair
+                .text
+                .ends_with("Respond again with only the corrected output.")
+        );
+        assert_eq!(
+            repair
+                .metadata
+                .as_ref()
+                .map(|metadata| &metadata["purpose"]),
+            Some(&json!("repair"))
+        );
+    }
+
+    #[test]
+    fn test_coerce_enums() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "mood": {"enum": ["happy", "sad"]},
+                "moods": {"type": "array", "items": {"enum": ["happy", "sad"]}},
+            },
+        });
+        let mut value = json!({"mood": " Happy", "moods": ["SAD", "meh"]});
+        coerce_enums(&schema, &mut value);
+        assert_eq!(value, json!({"mood": "happy", "moods": ["sad", "meh"]}));
+    }
+}
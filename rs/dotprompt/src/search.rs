@@ -0,0 +1,344 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Searching prompts by frontmatter metadata.
+//!
+//! Queries are whitespace-separated terms. Every term must match for a
+//! prompt to be returned:
+//!
+//! - `model:<glob>` - the prompt's model matches the glob (`*` and `?`),
+//!   with or without its `provider/` prefix
+//! - `tool:<glob>` / `tools:<glob>[,<glob>...]` - the prompt uses a matching tool
+//! - `name:<glob>` - the prompt name matches the glob
+//! - `variant:<glob>` - the prompt variant matches the glob
+//! - `description:<text>` or a bare word - the description contains the text
+//!
+//! Values may be double-quoted to include spaces, and all matching is
+//! case-insensitive.
+//!
+//! ```
+//! use dotprompt::search::PromptQuery;
+//!
+//! let query = PromptQuery::parse("model:gemini* tools:search weather").unwrap();
+//! assert_eq!(query.models, ["gemini*"]);
+//! assert_eq!(query.tools, ["search"]);
+//! assert_eq!(query.text, ["weather"]);
+//! ```
+
+use crate::error::{DotpromptError, Result};
+use crate::parse::parse_document;
+use crate::store::PromptStore;
+use crate::types::{ListPromptsOptions, LoadPromptOptions, PromptMetadata, PromptRef};
+
+/// A parsed prompt search query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptQuery {
+    /// Globs the prompt's model must match.
+    pub models: Vec<String>,
+    /// Globs that must each match at least one tool used by the prompt.
+    pub tools: Vec<String>,
+    /// Globs the prompt name must match.
+    pub names: Vec<String>,
+    /// Globs the prompt variant must match.
+    pub variants: Vec<String>,
+    /// Text fragments the description must contain.
+    pub text: Vec<String>,
+}
+
+/// A prompt that matched a search query.
+#[derive(Debug, Clone)]
+pub struct PromptSearchResult {
+    /// Reference to the matching prompt.
+    pub prompt_ref: PromptRef,
+    /// The prompt's parsed frontmatter.
+    pub metadata: PromptMetadata,
+}
+
+impl PromptQuery {
+    /// Parses a query string.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query, e.g. `model:gemini* tools:search`
+    ///
+    /// # Errors
+    ///
+    /// Returns `DotpromptError::InvalidFormat` for unknown fields, empty
+    /// values, or unterminated quotes.
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut parsed = Self::default();
+        for term in tokenize(query)? {
+            let Some((key, value)) = term.split_once(':') else {
+                parsed.text.push(term);
+                continue;
+            };
+            if value.is_empty() {
+                return Err(DotpromptError::InvalidFormat(format!(
+                    "empty value for search field '{key}'"
+                )));
+            }
+            match key {
+                "model" => parsed.models.push(value.to_string()),
+                "tool" | "tools" => parsed.tools.extend(
+                    value
+                        .split(',')
+                        .filter(|t| !t.is_empty())
+                        .map(str::to_string),
+                ),
+                "name" => parsed.names.push(value.to_string()),
+                "variant" => parsed.variants.push(value.to_string()),
+                "description" | "desc" => parsed.text.push(value.to_string()),
+                _ => {
+                    return Err(DotpromptError::InvalidFormat(format!(
+                        "unknown search field '{key}'"
+                    )));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Returns true if the query has no terms and therefore matches everything.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.models.is_empty()
+            && self.tools.is_empty()
+            && self.names.is_empty()
+            && self.variants.is_empty()
+            && self.text.is_empty()
+    }
+
+    /// Checks whether a prompt matches every term in the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_ref` - The prompt's name and variant
+    /// * `metadata` - The prompt's parsed frontmatter
+    #[must_use]
+    pub fn matches<M>(&self, prompt_ref: &PromptRef, metadata: &PromptMetadata<M>) -> bool {
+        let model = metadata.model.as_deref();
+        let variant = prompt_ref.variant.as_deref();
+        let description = metadata
+            .description
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase();
+        let tools: Vec<&str> = metadata
+            .tools
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .chain(
+                metadata
+                    .tool_defs
+                    .iter()
+                    .flatten()
+                    .map(|def| def.name.as_str()),
+            )
+            .collect();
+
+        self.models
+            .iter()
+            .all(|p| model.is_some_and(|m| model_match(p, m)))
+            && self
+                .tools
+                .iter()
+                .all(|p| tools.iter().any(|t| glob_match(p, t)))
+            && self.names.iter().all(|p| glob_match(p, &prompt_ref.name))
+            && self
+                .variants
+                .iter()
+                .all(|p| variant.is_some_and(|v| glob_match(p, v)))
+            && self
+                .text
+                .iter()
+                .all(|t| description.contains(&t.to_lowercase()))
+    }
+}
+
+/// Searches every prompt in a store.
+///
+/// Prompts whose frontmatter cannot be parsed are skipped; use the linter to
+/// find them.
+///
+/// # Errors
+///
+/// Returns an error if the store cannot be listed or a prompt cannot be loaded.
+pub(crate) fn search_store<S: PromptStore + ?Sized>(
+    store: &S,
+    query: &PromptQuery,
+) -> Result<Vec<PromptSearchResult>> {
+    let mut results = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = store.list(Some(ListPromptsOptions {
+            cursor,
+            ..Default::default()
+        }))?;
+        for prompt_ref in page.prompts {
+            let data = store.load(
+                &prompt_ref.name,
+                Some(LoadPromptOptions {
+                    variant: prompt_ref.variant.clone(),
+                    version: None,
+                }),
+            )?;
+            let Ok(parsed) = parse_document::<serde_json::Value>(&data.source) else {
+                continue;
+            };
+            if query.matches(&prompt_ref, &parsed.metadata) {
+                results.push(PromptSearchResult {
+                    prompt_ref,
+                    metadata: parsed.metadata,
+                });
+            }
+        }
+        match page.cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    Ok(results)
+}
+
+/// Splits a query into terms, honoring double-quoted values.
+fn tokenize(query: &str) -> Result<Vec<String>> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(DotpromptError::InvalidFormat(
+            "unterminated quote in search query".to_string(),
+        ));
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+    Ok(terms)
+}
+
+/// Matches a model glob against a model name, and against the name without
+/// its `provider/` prefix so `gemini*` finds `googleai/gemini-1.5-pro`.
+fn model_match(pattern: &str, model: &str) -> bool {
+    glob_match(pattern, model)
+        || model
+            .split_once('/')
+            .is_some_and(|(_, name)| glob_match(pattern, name))
+}
+
+/// Case-insensitive glob match supporting `*` and `?`.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn prompt_ref(name: &str) -> PromptRef {
+        PromptRef {
+            name: name.to_string(),
+            variant: None,
+            version: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = PromptQuery::parse(
+            r#"model:gemini* tools:search,lookup description:"weather report" city"#,
+        )
+        .unwrap();
+        assert_eq!(query.models, ["gemini*"]);
+        assert_eq!(query.tools, ["search", "lookup"]);
+        assert_eq!(query.text, ["weather report", "city"]);
+        assert!(PromptQuery::parse("").unwrap().is_empty());
+        assert!(PromptQuery::parse("owner:me").is_err());
+        assert!(PromptQuery::parse("model:").is_err());
+        assert!(PromptQuery::parse(r#"description:"open"#).is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("gemini*", "gemini-1.5-pro"));
+        assert!(glob_match("*flash*", "gemini-2.0-FLASH-exp"));
+        assert!(glob_match("gpt-?o", "gpt-4o"));
+        assert!(!glob_match("gemini*", "googleai/gemini-pro"));
+        assert!(glob_match("*/gemini*", "googleai/gemini-pro"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn test_matches() {
+        let metadata: PromptMetadata = PromptMetadata {
+            model: Some("googleai/gemini-1.5-pro".to_string()),
+            tools: Some(vec!["search".to_string(), "calculator".to_string()]),
+            description: Some("Answers weather questions".to_string()),
+            ..Default::default()
+        };
+        let r = prompt_ref("weather");
+
+        let matching = PromptQuery::parse("model:*gemini* tools:search WEATHER").unwrap();
+        assert!(matching.matches(&r, &metadata));
+
+        let wrong_tool = PromptQuery::parse("tools:search,translate").unwrap();
+        assert!(!wrong_tool.matches(&r, &metadata));
+
+        let wrong_name = PromptQuery::parse("name:greet*").unwrap();
+        assert!(!wrong_name.matches(&r, &metadata));
+
+        let unprefixed = PromptQuery::parse("model:gemini* tools:search weather").unwrap();
+        assert!(unprefixed.matches(&r, &metadata));
+
+        let other_model = PromptQuery::parse("model:gpt*").unwrap();
+        assert!(!other_model.matches(&r, &metadata));
+
+        let no_model = PromptQuery::parse("model:*").unwrap();
+        assert!(!no_model.matches(&r, &PromptMetadata::<serde_json::Value>::default()));
+    }
+}
@@ -18,8 +18,15 @@
 //!
 //! This module defines the `PromptStore` trait for reading and writing
 //! prompts and partials, matching the canonical JavaScript implementation.
+//!
+//! [`AsyncPromptStore`] and [`AsyncPromptStoreWritable`] are the async
+//! counterparts for network-backed stores. Every synchronous store is also
+//! an async one, running each call inline when its future is polled.
+
+use std::future::Future;
 
 use crate::error::Result;
+use crate::search::{PromptQuery, PromptSearchResult, search_store};
 use crate::types::{
     ListPartialsOptions, ListPromptsOptions, LoadPartialOptions, LoadPromptOptions,
     PaginatedPartials, PaginatedPrompts, PartialData, PromptData,
@@ -73,7 +80,8 @@ pub trait PromptStore: Send + Sync {
     ///
     /// # Errors
     ///
-    /// Returns an error if the prompt is not found or cannot be loaded.
+    /// Returns [`DotpromptError::NotFound`](crate::DotpromptError::NotFound) if
+    /// the prompt does not exist, or another error if it cannot be loaded.
     fn load(&self, name: &str, options: Option<LoadPromptOptions>) -> Result<PromptData>;
 
     /// Loads a partial by name.
@@ -89,8 +97,29 @@ pub trait PromptStore: Send + Sync {
     ///
     /// # Errors
     ///
-    /// Returns an error if the partial is not found or cannot be loaded.
+    /// Returns [`DotpromptError::NotFound`](crate::DotpromptError::NotFound) if
+    /// the partial does not exist, or another error if it cannot be loaded.
     fn load_partial(&self, name: &str, options: Option<LoadPartialOptions>) -> Result<PartialData>;
+
+    /// Searches prompts by their frontmatter metadata.
+    ///
+    /// The default implementation lists and parses every prompt in the
+    /// store. Backends that index metadata may override it.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The parsed search query
+    ///
+    /// # Returns
+    ///
+    /// The matching prompts together with their metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be listed or a prompt cannot be loaded.
+    fn search(&self, query: &PromptQuery) -> Result<Vec<PromptSearchResult>> {
+        search_store(self, query)
+    }
 }
 
 /// Options for deleting a prompt or partial.
@@ -152,3 +181,145 @@ pub trait PromptStoreWritable: PromptStore {
         options: Option<DeletePromptOrPartialOptions>,
     ) -> Result<()>;
 }
+
+/// An async store for reading prompts and partials.
+///
+/// Backends that talk to a network service implement this trait so that
+/// callers on an async runtime don't block a thread per request. Every
+/// [`PromptStore`] implements it.
+pub trait AsyncPromptStore: Send + Sync {
+    /// Returns a paginated list of all prompts in the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be accessed.
+    fn list(
+        &self,
+        options: Option<ListPromptsOptions>,
+    ) -> impl Future<Output = Result<PaginatedPrompts>> + Send;
+
+    /// Returns a paginated list of all partials in the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be accessed.
+    fn list_partials(
+        &self,
+        options: Option<ListPartialsOptions>,
+    ) -> impl Future<Output = Result<PaginatedPartials>> + Send;
+
+    /// Loads a prompt by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::NotFound`](crate::DotpromptError::NotFound) if
+    /// the prompt does not exist, or another error if it cannot be loaded.
+    fn load(
+        &self,
+        name: &str,
+        options: Option<LoadPromptOptions>,
+    ) -> impl Future<Output = Result<PromptData>> + Send;
+
+    /// Loads a partial by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DotpromptError::NotFound`](crate::DotpromptError::NotFound) if
+    /// the partial does not exist, or another error if it cannot be loaded.
+    fn load_partial(
+        &self,
+        name: &str,
+        options: Option<LoadPartialOptions>,
+    ) -> impl Future<Output = Result<PartialData>> + Send;
+}
+
+impl<S: PromptStore + ?Sized> AsyncPromptStore for S {
+    async fn list(&self, options: Option<ListPromptsOptions>) -> Result<PaginatedPrompts> {
+        PromptStore::list(self, options)
+    }
+
+    async fn list_partials(
+        &self,
+        options: Option<ListPartialsOptions>,
+    ) -> Result<PaginatedPartials> {
+        PromptStore::list_partials(self, options)
+    }
+
+    async fn load(&self, name: &str, options: Option<LoadPromptOptions>) -> Result<PromptData> {
+        PromptStore::load(self, name, options)
+    }
+
+    async fn load_partial(
+        &self,
+        name: &str,
+        options: Option<LoadPartialOptions>,
+    ) -> Result<PartialData> {
+        PromptStore::load_partial(self, name, options)
+    }
+}
+
+/// An async writable prompt store. Every [`PromptStoreWritable`] implements
+/// it.
+pub trait AsyncPromptStoreWritable: AsyncPromptStore {
+    /// Saves a prompt to the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prompt cannot be saved.
+    fn save(&self, prompt: PromptData) -> impl Future<Output = Result<()>> + Send;
+
+    /// Deletes a prompt from the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prompt cannot be deleted.
+    fn delete(
+        &self,
+        name: &str,
+        options: Option<DeletePromptOrPartialOptions>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Saves a partial to the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the partial cannot be saved.
+    fn save_partial(&self, partial: PartialData) -> impl Future<Output = Result<()>> + Send;
+
+    /// Deletes a partial from the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the partial cannot be deleted.
+    fn delete_partial(
+        &self,
+        name: &str,
+        options: Option<DeletePromptOrPartialOptions>,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl<S: PromptStoreWritable + ?Sized> AsyncPromptStoreWritable for S {
+    async fn save(&self, prompt: PromptData) -> Result<()> {
+        PromptStoreWritable::save(self, prompt)
+    }
+
+    async fn delete(
+        &self,
+        name: &str,
+        options: Option<DeletePromptOrPartialOptions>,
+    ) -> Result<()> {
+        PromptStoreWritable::delete(self, name, options)
+    }
+
+    async fn save_partial(&self, partial: PartialData) -> Result<()> {
+        PromptStoreWritable::save_partial(self, partial)
+    }
+
+    async fn delete_partial(
+        &self,
+        name: &str,
+        options: Option<DeletePromptOrPartialOptions>,
+    ) -> Result<()> {
+        PromptStoreWritable::delete_partial(self, name, options)
+    }
+}
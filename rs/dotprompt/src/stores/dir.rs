@@ -29,22 +29,42 @@
 )]
 
 use crate::error::{DotpromptError, Result};
+use crate::search::glob_match;
 use crate::store::{DeletePromptOrPartialOptions, PromptStore, PromptStoreWritable};
 use crate::types::{
     ListPartialsOptions, ListPromptsOptions, LoadPartialOptions, LoadPromptOptions,
     PaginatedPartials, PaginatedPrompts, PartialData, PartialRef, PromptData, PromptRef,
 };
-use crate::util::validate_prompt_name;
-use sha1::{Digest, Sha1};
+use crate::util::{calculate_version, has_tags, prompt_summary, validate_prompt_name};
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use walkdir::{DirEntry, WalkDir};
+
+/// Directories skipped while walking unless hidden directories are included.
+pub const DEFAULT_IGNORED_DIRS: [&str; 1] = ["node_modules"];
 
 /// Configuration options for DirStore.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DirStoreOptions {
     /// Base directory for prompts.
     pub directory: PathBuf,
+
+    /// Glob patterns (`*` and `?`) for files and directories to skip when
+    /// listing. Patterns containing `/` match the path relative to the base
+    /// directory; others match any file or directory name.
+    pub ignore: Vec<String>,
+
+    /// How deep to walk: `1` lists only the base directory's own files.
+    /// Unlimited when `None`.
+    pub max_depth: Option<usize>,
+
+    /// Follow symbolic links while listing. Links whose targets resolve
+    /// outside the base directory are skipped.
+    pub follow_symlinks: bool,
+
+    /// Also walk hidden directories and [`DEFAULT_IGNORED_DIRS`], which are
+    /// skipped by default.
+    pub include_hidden: bool,
 }
 
 /// A directory-based prompt store.
@@ -60,6 +80,10 @@ pub struct DirStoreOptions {
 #[derive(Debug)]
 pub struct DirStore {
     directory: PathBuf,
+    ignore: Vec<String>,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    include_hidden: bool,
 }
 
 impl DirStore {
@@ -67,14 +91,50 @@ impl DirStore {
     pub fn new(options: DirStoreOptions) -> Self {
         Self {
             directory: options.directory,
+            ignore: options.ignore,
+            max_depth: options.max_depth,
+            follow_symlinks: options.follow_symlinks,
+            include_hidden: options.include_hidden,
         }
     }
 
-    fn calculate_version(content: &str) -> String {
-        let mut hasher = Sha1::new();
-        hasher.update(content.as_bytes());
-        let result = hasher.finalize();
-        hex::encode(result)[..8].to_string()
+    /// Walks the base directory, yielding the entries admitted by the ignore
+    /// rules, depth limit, and symlink policy.
+    fn walk(&self) -> impl Iterator<Item = DirEntry> + '_ {
+        let mut walker = WalkDir::new(&self.directory).follow_links(self.follow_symlinks);
+        if let Some(depth) = self.max_depth {
+            walker = walker.max_depth(depth);
+        }
+        walker
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !self.is_ignored(entry))
+            .filter_map(|e| e.ok())
+    }
+
+    /// Checks whether a walked entry is skipped by the ignore rules, or is a
+    /// symbolic link resolving outside the base directory.
+    fn is_ignored(&self, entry: &DirEntry) -> bool {
+        let name = entry.file_name().to_string_lossy();
+        if entry.file_type().is_dir()
+            && !self.include_hidden
+            && (name.starts_with('.') || DEFAULT_IGNORED_DIRS.contains(&name.as_ref()))
+        {
+            return true;
+        }
+        if entry.path_is_symlink() && self.verify_path_containment(entry.path(), &name).is_err() {
+            return true;
+        }
+        let rel_path = match entry.path().strip_prefix(&self.directory) {
+            Ok(p) => p.to_string_lossy().replace('\\', "/"),
+            Err(_) => return false,
+        };
+        self.ignore.iter().any(|pattern| {
+            if pattern.contains('/') {
+                glob_match(pattern, &rel_path)
+            } else {
+                glob_match(pattern, &name)
+            }
+        })
     }
 
     /// Verifies that a given file path is contained within the store's base directory.
@@ -145,6 +205,12 @@ impl DirStore {
     fn is_partial(filename: &str) -> bool {
         filename.starts_with('_')
     }
+
+    /// Whether a listed name and variant pass the validation `load` applies.
+    fn is_loadable(name: &str, variant: Option<&str>) -> bool {
+        validate_prompt_name(name).is_ok()
+            && variant.is_none_or(|v| validate_prompt_name(v).is_ok())
+    }
 }
 
 impl PromptStore for DirStore {
@@ -153,23 +219,27 @@ impl PromptStore for DirStore {
     /// This method recursively walks the directory structure to find `.prompt` files.
     /// It filters out matching files based on the requested variant (if any).
     /// Files starting with `_` are treated as partials and excluded from this list.
+    /// Files whose names are not valid prompt names, like `weekly report.prompt`,
+    /// are skipped, since they cannot be loaded.
     ///
     /// # Arguments
     ///
-    /// * `options` - Optional filter criteria (limit, cursor, variant).
+    /// * `options` - Optional filter criteria (limit, cursor, variant, tags), and
+    ///   whether to include each prompt's frontmatter summary.
     fn list(&self, options: Option<ListPromptsOptions>) -> Result<PaginatedPrompts> {
         if let Some(opts) = &options {
             if let Some(v) = &opts.variant {
                 validate_prompt_name(v)?;
             }
         }
+        let tags = options
+            .as_ref()
+            .and_then(|o| o.tags.clone())
+            .unwrap_or_default();
+        let include_metadata = options.as_ref().is_some_and(|o| o.include_metadata);
 
         let mut prompts = Vec::new();
-        for entry in WalkDir::new(&self.directory)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
+        for entry in self.walk() {
             if entry.file_type().is_file() {
                 let file_name = entry.file_name().to_string_lossy();
                 if file_name.ends_with(".prompt") && !Self::is_partial(&file_name) {
@@ -182,7 +252,10 @@ impl PromptStore for DirStore {
                         Ok(c) => c,
                         Err(_) => continue,
                     };
-                    let version = Self::calculate_version(&content);
+                    if !has_tags(&content, &tags) {
+                        continue;
+                    }
+                    let version = calculate_version(&content);
 
                     let rel_path = match path.strip_prefix(&self.directory) {
                         Ok(p) => p,
@@ -207,11 +280,15 @@ impl PromptStore for DirStore {
                         } else {
                             parsed_name
                         };
+                        if !Self::is_loadable(&full_name, variant.as_deref()) {
+                            continue;
+                        }
 
                         prompts.push(PromptRef {
                             name: full_name,
                             variant,
                             version: Some(version),
+                            metadata: include_metadata.then(|| prompt_summary(&content)),
                         });
                     }
                 }
@@ -234,11 +311,7 @@ impl PromptStore for DirStore {
         }
 
         let mut partials = Vec::new();
-        for entry in WalkDir::new(&self.directory)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
+        for entry in self.walk() {
             if entry.file_type().is_file() {
                 let file_name = entry.file_name().to_string_lossy();
                 if file_name.ends_with(".prompt") && Self::is_partial(&file_name) {
@@ -251,7 +324,7 @@ impl PromptStore for DirStore {
                         Ok(c) => c,
                         Err(_) => continue,
                     };
-                    let version = Self::calculate_version(&content);
+                    let version = calculate_version(&content);
 
                     let rel_path = match path.strip_prefix(&self.directory) {
                         Ok(p) => p,
@@ -278,6 +351,10 @@ impl PromptStore for DirStore {
                             parsed_name
                         };
 
+                        if !Self::is_loadable(&full_name, variant.as_deref()) {
+                            continue;
+                        }
+
                         partials.push(PartialRef {
                             name: full_name,
                             variant,
@@ -325,13 +402,13 @@ impl PromptStore for DirStore {
 
         let source = fs::read_to_string(&file_path).map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
-                DotpromptError::StoreError(format!("Prompt not found: {name}"))
+                DotpromptError::NotFound(format!("prompt '{name}'"))
             } else {
                 DotpromptError::StoreError(e.to_string())
             }
         })?;
 
-        let version = Self::calculate_version(&source);
+        let version = calculate_version(&source);
 
         if let Some(req) = version_req {
             if req != version {
@@ -346,6 +423,7 @@ impl PromptStore for DirStore {
                 name: name.to_string(),
                 variant,
                 version: Some(version),
+                metadata: None,
             },
             source,
         })
@@ -381,13 +459,13 @@ impl PromptStore for DirStore {
 
         let source = fs::read_to_string(&file_path).map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
-                DotpromptError::StoreError(format!("Partial not found: {name}"))
+                DotpromptError::NotFound(format!("partial '{name}'"))
             } else {
                 DotpromptError::StoreError(e.to_string())
             }
         })?;
 
-        let version = Self::calculate_version(&source);
+        let version = calculate_version(&source);
 
         if let Some(req) = version_req {
             if req != version {
@@ -589,3 +667,128 @@ impl PromptStoreWritable for DirStore {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::search::PromptQuery;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, path: &str) {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "Hello").unwrap();
+    }
+
+    fn names(options: DirStoreOptions) -> Vec<String> {
+        let mut names: Vec<String> = DirStore::new(options)
+            .list(None)
+            .unwrap()
+            .prompts
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_list_skips_hidden_and_ignored() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        write(dir, "greet.prompt");
+        write(dir, "legacy/old.prompt");
+        write(dir, "drafts/wip.prompt");
+        write(dir, ".cache/cached.prompt");
+        write(dir, "node_modules/pkg/dep.prompt");
+
+        let options = DirStoreOptions {
+            directory: dir.to_path_buf(),
+            ..Default::default()
+        };
+        assert_eq!(
+            names(options.clone()),
+            ["drafts/wip", "greet", "legacy/old"]
+        );
+        assert_eq!(
+            names(DirStoreOptions {
+                ignore: vec!["legacy/*".to_string(), "wip.prompt".to_string()],
+                ..options.clone()
+            }),
+            ["greet"]
+        );
+        assert_eq!(
+            names(DirStoreOptions {
+                include_hidden: true,
+                ..options.clone()
+            }),
+            [
+                ".cache/cached",
+                "drafts/wip",
+                "greet",
+                "legacy/old",
+                "node_modules/pkg/dep"
+            ]
+        );
+        assert_eq!(
+            names(DirStoreOptions {
+                max_depth: Some(1),
+                ..options
+            }),
+            ["greet"]
+        );
+    }
+
+    #[test]
+    fn test_list_skips_invalid_names() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        write(dir, "weekly report.prompt");
+        write(dir, "_page header.prompt");
+        write(dir, "greet.prompt");
+        write(dir, "_header.prompt");
+
+        let options = DirStoreOptions {
+            directory: dir.to_path_buf(),
+            ..Default::default()
+        };
+        assert_eq!(names(options.clone()), ["greet"]);
+        let store = DirStore::new(options);
+        let partials: Vec<String> = store
+            .list_partials(None)
+            .unwrap()
+            .partials
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(partials, ["header"]);
+        let found = store.search(&PromptQuery::default()).unwrap();
+        assert_eq!(found.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_follows_contained_symlinks() {
+        let temp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let dir = temp.path();
+        write(dir, "shared/common.prompt");
+        write(outside.path(), "secret.prompt");
+        std::os::unix::fs::symlink(dir.join("shared"), dir.join("linked")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.join("escape")).unwrap();
+
+        let options = DirStoreOptions {
+            directory: dir.to_path_buf(),
+            ..Default::default()
+        };
+        assert_eq!(names(options.clone()), ["shared/common"]);
+        assert_eq!(
+            names(DirStoreOptions {
+                follow_symlinks: true,
+                ..options
+            }),
+            ["linked/common", "shared/common"]
+        );
+    }
+}
@@ -17,3 +17,9 @@
 //! Prompt stores.
 
 pub mod dir;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use dir::{DirStore, DirStoreOptions};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteStore, SqliteStoreOptions};
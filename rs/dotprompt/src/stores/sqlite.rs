@@ -0,0 +1,784 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! SqliteStore implementation.
+//!
+//! Every save is recorded as a new revision, so earlier versions of a prompt
+//! remain loadable through `LoadPromptOptions::version` even after the prompt
+//! has been overwritten or deleted.
+
+#![allow(
+    clippy::collapsible_if,
+    clippy::doc_markdown,
+    clippy::needless_pass_by_value,
+    clippy::significant_drop_tightening
+)]
+
+use crate::error::{DotpromptError, Result};
+use crate::store::{DeletePromptOrPartialOptions, PromptStore, PromptStoreWritable};
+use crate::types::{
+    ListPartialsOptions, ListPromptsOptions, LoadPartialOptions, LoadPromptOptions,
+    PaginatedPartials, PaginatedPrompts, PartialData, PartialRef, PromptData, PromptRef,
+};
+use crate::util::{calculate_version, has_tags, validate_prompt_name};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+/// Schema for the revision table.
+///
+/// Rows are append-only. A row with a `NULL` source is a deletion tombstone.
+/// The empty string stands in for "no variant" so the key columns can be
+/// compared with plain equality.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS revisions (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind       TEXT NOT NULL,
+    name       TEXT NOT NULL,
+    variant    TEXT NOT NULL DEFAULT '',
+    version    TEXT NOT NULL,
+    source     TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+CREATE INDEX IF NOT EXISTS revisions_key ON revisions (kind, name, variant, id);
+";
+
+/// Configuration options for SqliteStore.
+#[derive(Debug, Clone)]
+pub struct SqliteStoreOptions {
+    /// Path to the SQLite database file. Created if it does not exist.
+    pub path: PathBuf,
+}
+
+/// The kind of entry stored in a revision row.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Prompt,
+    Partial,
+}
+
+impl Kind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Prompt => "prompt",
+            Self::Partial => "partial",
+        }
+    }
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Prompt => "Prompt",
+            Self::Partial => "Partial",
+        }
+    }
+}
+
+/// A single listed entry: name, variant, and current version.
+type Entry = (String, Option<String>, String);
+
+/// A SQLite-backed prompt store with full revision history.
+///
+/// `SqliteStore` keeps every saved revision of every prompt and partial.
+/// Loading without a version returns the latest revision; loading with a
+/// version returns that historical revision, which makes the store suitable
+/// for audit trails. Listing supports real keyset pagination through the
+/// `limit` and `cursor` options.
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Opens (or creates) a SqliteStore at the configured path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or initialized.
+    pub fn new(options: SqliteStoreOptions) -> Result<Self> {
+        let conn = Connection::open(&options.path).map_err(|e| {
+            DotpromptError::StoreError(format!(
+                "Failed to open database '{}': {e}",
+                options.path.display()
+            ))
+        })?;
+        Self::from_connection(conn)
+    }
+
+    /// Creates a SqliteStore backed by a private in-memory database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be initialized.
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| DotpromptError::StoreError(format!("Failed to open database: {e}")))?;
+        Self::from_connection(conn)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| DotpromptError::StoreError(format!("Failed to initialize schema: {e}")))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Returns every saved revision of a prompt, newest first.
+    ///
+    /// Deleted prompts keep their history; tombstones are not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or the database cannot be read.
+    pub fn prompt_history(&self, name: &str, variant: Option<&str>) -> Result<Vec<PromptRef>> {
+        Ok(self
+            .history(Kind::Prompt, name, variant)?
+            .into_iter()
+            .map(|(name, variant, version)| PromptRef {
+                name,
+                variant,
+                version: Some(version),
+                metadata: None,
+            })
+            .collect())
+    }
+
+    /// Returns every saved revision of a partial, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or the database cannot be read.
+    pub fn partial_history(&self, name: &str, variant: Option<&str>) -> Result<Vec<PartialRef>> {
+        Ok(self
+            .history(Kind::Partial, name, variant)?
+            .into_iter()
+            .map(|(name, variant, version)| PartialRef {
+                name,
+                variant,
+                version: Some(version),
+            })
+            .collect())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| DotpromptError::StoreError("Database lock poisoned".to_string()))
+    }
+
+    fn history(&self, kind: Kind, name: &str, variant: Option<&str>) -> Result<Vec<Entry>> {
+        validate_prompt_name(name)?;
+        if let Some(v) = variant {
+            validate_prompt_name(v)?;
+        }
+        let conn = self.lock()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT version FROM revisions
+                 WHERE kind = ?1 AND name = ?2 AND variant = ?3 AND source IS NOT NULL
+                 ORDER BY id DESC",
+            )
+            .map_err(query_error)?;
+        let rows = stmt
+            .query_map(
+                params![kind.as_str(), name, variant.unwrap_or_default()],
+                |row| row.get::<_, String>(0),
+            )
+            .map_err(query_error)?;
+        rows.map(|version| {
+            Ok((
+                name.to_string(),
+                variant.map(str::to_string),
+                version.map_err(query_error)?,
+            ))
+        })
+        .collect()
+    }
+
+    /// Lists the latest live revision of each entry, one page at a time.
+    fn list_entries(
+        &self,
+        kind: Kind,
+        variant: Option<&str>,
+        cursor: Option<&str>,
+        limit: Option<usize>,
+        tags: &[String],
+    ) -> Result<(Vec<Entry>, Option<String>)> {
+        if let Some(v) = variant {
+            validate_prompt_name(v)?;
+        }
+        let (after_name, after_variant) = match cursor {
+            Some(c) => decode_cursor(c)?,
+            None => (String::new(), String::new()),
+        };
+        // Fetch one extra row to learn whether another page follows. Tags live
+        // in the frontmatter, so a tag filter has to scan past the page size.
+        let fetch = match limit {
+            Some(l) if tags.is_empty() => i64::try_from(l).map_or(i64::MAX, |l| l + 1),
+            _ => -1,
+        };
+
+        let conn = self.lock()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT r.name, r.variant, r.version, r.source FROM revisions r
+                 WHERE r.kind = ?1
+                   AND r.source IS NOT NULL
+                   AND r.id = (SELECT MAX(id) FROM revisions
+                               WHERE kind = r.kind AND name = r.name AND variant = r.variant)
+                   AND (?2 IS NULL OR r.variant = ?2)
+                   AND (r.name > ?3 OR (r.name = ?3 AND r.variant > ?4))
+                 ORDER BY r.name, r.variant
+                 LIMIT ?5",
+            )
+            .map_err(query_error)?;
+        let rows = stmt
+            .query_map(
+                params![kind.as_str(), variant, after_name, after_variant, fetch],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                },
+            )
+            .map_err(query_error)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (name, variant, version, source) = row.map_err(query_error)?;
+            if !has_tags(&source, tags) {
+                continue;
+            }
+            entries.push((name, (!variant.is_empty()).then_some(variant), version));
+            if limit.is_some_and(|l| entries.len() > l) {
+                break;
+            }
+        }
+
+        let next_cursor = match limit {
+            Some(l) if entries.len() > l => {
+                entries.truncate(l);
+                entries
+                    .last()
+                    .map(|(name, variant, _)| encode_cursor(name, variant.as_deref()))
+            }
+            _ => None,
+        };
+        Ok((entries, next_cursor))
+    }
+
+    /// Loads a revision's source and version.
+    fn load_entry(
+        &self,
+        kind: Kind,
+        name: &str,
+        variant: Option<&str>,
+        version: Option<&str>,
+    ) -> Result<(String, String)> {
+        validate_prompt_name(name)?;
+        if let Some(v) = variant {
+            validate_prompt_name(v)?;
+        }
+        let conn = self.lock()?;
+        let variant_key = variant.unwrap_or_default();
+
+        if let Some(req) = version {
+            let source: Option<String> = conn
+                .query_row(
+                    "SELECT source FROM revisions
+                     WHERE kind = ?1 AND name = ?2 AND variant = ?3 AND version = ?4
+                       AND source IS NOT NULL
+                     ORDER BY id DESC LIMIT 1",
+                    params![kind.as_str(), name, variant_key, req],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(query_error)?;
+            return source.map(|s| (s, req.to_string())).ok_or_else(|| {
+                DotpromptError::StoreError(format!(
+                    "Version {req} not found for {} '{name}'",
+                    kind.as_str()
+                ))
+            });
+        }
+
+        let latest: Option<(Option<String>, String)> = conn
+            .query_row(
+                "SELECT source, version FROM revisions
+                 WHERE kind = ?1 AND name = ?2 AND variant = ?3
+                 ORDER BY id DESC LIMIT 1",
+                params![kind.as_str(), name, variant_key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(query_error)?;
+        match latest {
+            Some((Some(source), version)) => Ok((source, version)),
+            _ => Err(DotpromptError::NotFound(format!(
+                "{} '{name}'",
+                kind.label().to_lowercase()
+            ))),
+        }
+    }
+
+    /// Records a new revision unless the content is unchanged.
+    fn save_entry(
+        &self,
+        kind: Kind,
+        name: &str,
+        variant: Option<&str>,
+        source: &str,
+    ) -> Result<()> {
+        if name.is_empty() {
+            return Err(DotpromptError::StoreError(format!(
+                "{} name is required for saving",
+                kind.label()
+            )));
+        }
+        validate_prompt_name(name)?;
+        if let Some(v) = variant {
+            validate_prompt_name(v)?;
+        }
+        let version = calculate_version(source);
+        let variant_key = variant.unwrap_or_default();
+
+        let conn = self.lock()?;
+        let current: Option<(Option<String>, String)> = conn
+            .query_row(
+                "SELECT source, version FROM revisions
+                 WHERE kind = ?1 AND name = ?2 AND variant = ?3
+                 ORDER BY id DESC LIMIT 1",
+                params![kind.as_str(), name, variant_key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(query_error)?;
+        if let Some((Some(_), current_version)) = current {
+            if current_version == version {
+                return Ok(());
+            }
+        }
+
+        conn.execute(
+            "INSERT INTO revisions (kind, name, variant, version, source)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![kind.as_str(), name, variant_key, version, source],
+        )
+        .map_err(|e| {
+            DotpromptError::StoreError(format!("Failed to write {}: {e}", kind.as_str()))
+        })?;
+        Ok(())
+    }
+
+    /// Writes a tombstone for a live entry. Returns `false` if none exists.
+    fn delete_entry(&self, kind: Kind, name: &str, variant: Option<&str>) -> Result<bool> {
+        let variant_key = variant.unwrap_or_default();
+        let conn = self.lock()?;
+        let live: Option<Option<String>> = conn
+            .query_row(
+                "SELECT source FROM revisions
+                 WHERE kind = ?1 AND name = ?2 AND variant = ?3
+                 ORDER BY id DESC LIMIT 1",
+                params![kind.as_str(), name, variant_key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(query_error)?;
+        if !matches!(live, Some(Some(_))) {
+            return Ok(false);
+        }
+        conn.execute(
+            "INSERT INTO revisions (kind, name, variant, version, source)
+             VALUES (?1, ?2, ?3, '', NULL)",
+            params![kind.as_str(), name, variant_key],
+        )
+        .map_err(|e| {
+            DotpromptError::StoreError(format!("Failed to delete {}: {e}", kind.as_str()))
+        })?;
+        Ok(true)
+    }
+}
+
+/// Wraps a SQLite error as a store error.
+fn query_error(e: rusqlite::Error) -> DotpromptError {
+    DotpromptError::StoreError(format!("Database query failed: {e}"))
+}
+
+/// Encodes the position after `(name, variant)` as an opaque cursor.
+fn encode_cursor(name: &str, variant: Option<&str>) -> String {
+    hex::encode(format!("{name}\0{}", variant.unwrap_or_default()))
+}
+
+/// Decodes a cursor produced by [`encode_cursor`].
+fn decode_cursor(cursor: &str) -> Result<(String, String)> {
+    let invalid = || DotpromptError::StoreError(format!("Invalid cursor: {cursor}"));
+    let bytes = hex::decode(cursor).map_err(|_| invalid())?;
+    let decoded = String::from_utf8(bytes).map_err(|_| invalid())?;
+    let (name, variant) = decoded.split_once('\0').ok_or_else(invalid)?;
+    Ok((name.to_string(), variant.to_string()))
+}
+
+impl PromptStore for SqliteStore {
+    /// Lists the latest revision of every live prompt, ordered by name and variant.
+    fn list(&self, options: Option<ListPromptsOptions>) -> Result<PaginatedPrompts> {
+        let opts = options.unwrap_or_default();
+        let (entries, cursor) = self.list_entries(
+            Kind::Prompt,
+            opts.variant.as_deref(),
+            opts.cursor.as_deref(),
+            opts.limit,
+            opts.tags.as_deref().unwrap_or_default(),
+        )?;
+        Ok(PaginatedPrompts {
+            prompts: entries
+                .into_iter()
+                .map(|(name, variant, version)| PromptRef {
+                    name,
+                    variant,
+                    version: Some(version),
+                    metadata: None,
+                })
+                .collect(),
+            cursor,
+        })
+    }
+
+    /// Lists the latest revision of every live partial, ordered by name and variant.
+    fn list_partials(&self, options: Option<ListPartialsOptions>) -> Result<PaginatedPartials> {
+        let opts = options.unwrap_or_default();
+        let (entries, cursor) = self.list_entries(
+            Kind::Partial,
+            opts.variant.as_deref(),
+            opts.cursor.as_deref(),
+            opts.limit,
+            &[],
+        )?;
+        Ok(PaginatedPartials {
+            partials: entries
+                .into_iter()
+                .map(|(name, variant, version)| PartialRef {
+                    name,
+                    variant,
+                    version: Some(version),
+                })
+                .collect(),
+            cursor,
+        })
+    }
+
+    /// Loads a prompt by name.
+    ///
+    /// When a version is requested, the matching historical revision is
+    /// returned even if newer revisions exist.
+    fn load(&self, name: &str, options: Option<LoadPromptOptions>) -> Result<PromptData> {
+        let opts = options.unwrap_or_default();
+        let (source, version) = self.load_entry(
+            Kind::Prompt,
+            name,
+            opts.variant.as_deref(),
+            opts.version.as_deref(),
+        )?;
+        Ok(PromptData {
+            prompt_ref: PromptRef {
+                name: name.to_string(),
+                variant: opts.variant,
+                version: Some(version),
+                metadata: None,
+            },
+            source,
+        })
+    }
+
+    /// Loads a partial by name, optionally at a historical version.
+    fn load_partial(&self, name: &str, options: Option<LoadPartialOptions>) -> Result<PartialData> {
+        let opts = options.unwrap_or_default();
+        let (source, version) = self.load_entry(
+            Kind::Partial,
+            name,
+            opts.variant.as_deref(),
+            opts.version.as_deref(),
+        )?;
+        Ok(PartialData {
+            partial_ref: PartialRef {
+                name: name.to_string(),
+                variant: opts.variant,
+                version: Some(version),
+            },
+            source,
+        })
+    }
+}
+
+impl PromptStoreWritable for SqliteStore {
+    /// Saves a prompt as a new revision.
+    ///
+    /// Saving content identical to the latest revision is a no-op.
+    fn save(&self, prompt: PromptData) -> Result<()> {
+        self.save_entry(
+            Kind::Prompt,
+            &prompt.prompt_ref.name,
+            prompt.prompt_ref.variant.as_deref(),
+            &prompt.source,
+        )
+    }
+
+    /// Deletes a prompt, falling back to a partial of the same name.
+    ///
+    /// Deletion records a tombstone; earlier revisions stay loadable by version.
+    fn delete(&self, name: &str, options: Option<DeletePromptOrPartialOptions>) -> Result<()> {
+        validate_prompt_name(name)?;
+        let variant = options.and_then(|o| o.variant);
+        if let Some(ref v) = variant {
+            validate_prompt_name(v)?;
+        }
+        if self.delete_entry(Kind::Prompt, name, variant.as_deref())?
+            || self.delete_entry(Kind::Partial, name, variant.as_deref())?
+        {
+            Ok(())
+        } else {
+            Err(DotpromptError::StoreError(format!(
+                "Failed to delete '{name}': not found"
+            )))
+        }
+    }
+
+    /// Saves a partial as a new revision.
+    fn save_partial(&self, partial: PartialData) -> Result<()> {
+        self.save_entry(
+            Kind::Partial,
+            &partial.partial_ref.name,
+            partial.partial_ref.variant.as_deref(),
+            &partial.source,
+        )
+    }
+
+    /// Deletes a partial, recording a tombstone.
+    fn delete_partial(
+        &self,
+        name: &str,
+        options: Option<DeletePromptOrPartialOptions>,
+    ) -> Result<()> {
+        validate_prompt_name(name)?;
+        let variant = options.and_then(|o| o.variant);
+        if let Some(ref v) = variant {
+            validate_prompt_name(v)?;
+        }
+        if self.delete_entry(Kind::Partial, name, variant.as_deref())? {
+            Ok(())
+        } else {
+            Err(DotpromptError::StoreError(format!(
+                "Failed to delete partial '{name}': not found"
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn prompt(name: &str, variant: Option<&str>, source: &str) -> PromptData {
+        PromptData {
+            prompt_ref: PromptRef {
+                name: name.to_string(),
+                variant: variant.map(str::to_string),
+                version: None,
+                metadata: None,
+            },
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_load_latest_and_historical_versions() {
+        let store = SqliteStore::open_in_memory().expect("open should succeed");
+        store
+            .save(prompt("greet", None, "Hello v1"))
+            .expect("save should succeed");
+        store
+            .save(prompt("greet", None, "Hello v2"))
+            .expect("save should succeed");
+
+        let latest = store.load("greet", None).expect("load should succeed");
+        assert_eq!(latest.source, "Hello v2");
+
+        let history = store
+            .prompt_history("greet", None)
+            .expect("history should succeed");
+        assert_eq!(history.len(), 2);
+        let first_version = history[1].version.clone();
+        assert_eq!(first_version, Some(calculate_version("Hello v1")));
+
+        let old = store
+            .load(
+                "greet",
+                Some(LoadPromptOptions {
+                    variant: None,
+                    version: first_version,
+                }),
+            )
+            .expect("historical load should succeed");
+        assert_eq!(old.source, "Hello v1");
+    }
+
+    #[test]
+    fn test_saving_identical_content_does_not_add_revision() {
+        let store = SqliteStore::open_in_memory().expect("open should succeed");
+        store
+            .save(prompt("greet", None, "Hello"))
+            .expect("save should succeed");
+        store
+            .save(prompt("greet", None, "Hello"))
+            .expect("save should succeed");
+        let history = store
+            .prompt_history("greet", None)
+            .expect("history should succeed");
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn test_list_paginates_with_cursor() {
+        let store = SqliteStore::open_in_memory().expect("open should succeed");
+        for name in ["a", "b", "c"] {
+            store
+                .save(prompt(name, None, name))
+                .expect("save should succeed");
+        }
+        store
+            .save(prompt("b", Some("formal"), "b formal"))
+            .expect("save should succeed");
+
+        let page1 = store
+            .list(Some(ListPromptsOptions {
+                limit: Some(2),
+                ..Default::default()
+            }))
+            .expect("list should succeed");
+        let names: Vec<_> = page1.prompts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert!(page1.cursor.is_some());
+
+        let page2 = store
+            .list(Some(ListPromptsOptions {
+                limit: Some(2),
+                cursor: page1.cursor,
+                ..Default::default()
+            }))
+            .expect("list should succeed");
+        let refs: Vec<_> = page2
+            .prompts
+            .iter()
+            .map(|p| (p.name.as_str(), p.variant.as_deref()))
+            .collect();
+        assert_eq!(refs, [("b", Some("formal")), ("c", None)]);
+        assert!(page2.cursor.is_none());
+    }
+
+    #[test]
+    fn test_list_filters_by_tags() {
+        let store = SqliteStore::open_in_memory().expect("open should succeed");
+        store
+            .save(prompt("a", None, "---\ntags: [support, beta]\n---\nA"))
+            .expect("save should succeed");
+        store
+            .save(prompt("b", None, "---\ntags: [support]\n---\nB"))
+            .expect("save should succeed");
+        store
+            .save(prompt("c", None, "C"))
+            .expect("save should succeed");
+
+        let list = |tags: &[&str]| {
+            store
+                .list(Some(ListPromptsOptions {
+                    tags: Some(tags.iter().map(|t| (*t).to_string()).collect()),
+                    limit: Some(1),
+                    ..Default::default()
+                }))
+                .expect("list should succeed")
+        };
+
+        let page = list(&["support"]);
+        assert_eq!(page.prompts[0].name, "a");
+        assert!(page.cursor.is_some());
+        let page = list(&["support", "beta"]);
+        assert_eq!(page.prompts.len(), 1);
+        assert!(page.cursor.is_none());
+        assert!(list(&["missing"]).prompts.is_empty());
+    }
+
+    #[test]
+    fn test_delete_keeps_history() {
+        let store = SqliteStore::open_in_memory().expect("open should succeed");
+        store
+            .save(prompt("greet", None, "Hello"))
+            .expect("save should succeed");
+        store.delete("greet", None).expect("delete should succeed");
+
+        assert!(store.load("greet", None).is_err());
+        assert!(
+            store
+                .list(None)
+                .expect("list should succeed")
+                .prompts
+                .is_empty()
+        );
+
+        let old = store
+            .load(
+                "greet",
+                Some(LoadPromptOptions {
+                    variant: None,
+                    version: Some(calculate_version("Hello")),
+                }),
+            )
+            .expect("historical load should succeed");
+        assert_eq!(old.source, "Hello");
+        assert!(store.delete("greet", None).is_err());
+    }
+
+    #[test]
+    fn test_partials_are_separate_from_prompts() {
+        let store = SqliteStore::open_in_memory().expect("open should succeed");
+        store
+            .save_partial(PartialData {
+                partial_ref: PartialRef {
+                    name: "header".to_string(),
+                    variant: None,
+                    version: None,
+                },
+                source: "Header".to_string(),
+            })
+            .expect("save should succeed");
+
+        assert!(store.load("header", None).is_err());
+        let partial = store
+            .load_partial("header", None)
+            .expect("load should succeed");
+        assert_eq!(partial.source, "Header");
+        assert_eq!(
+            store
+                .list_partials(None)
+                .expect("list should succeed")
+                .partials
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tool catalogs.
+//!
+//! Prompts name the tools they use in their `tools:` frontmatter. A
+//! project-level `tools.yaml` catalog defines those tools once, so every
+//! prompt refers to the same definitions:
+//!
+//! ```yaml
+//! tools:
+//!   - name: getWeather
+//!     description: Looks up the weather for a city.
+//!     inputSchema:
+//!       type: object
+//!       properties:
+//!         city: {type: string}
+//!   - name: search
+//!     inputSchema: {type: object}
+//! ```
+//!
+//! A catalog can pre-register its tools with
+//! [`DotpromptOptions::tools`](crate::DotpromptOptions::tools), or resolve
+//! them on demand as a [`ToolResolver`].
+//!
+//! ```no_run
+//! use dotprompt::{Dotprompt, DotpromptOptions, ToolCatalog};
+//!
+//! # fn example() -> dotprompt::Result<()> {
+//! let catalog = ToolCatalog::load(ToolCatalog::FILE_NAME)?;
+//! let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+//!     tools: Some(catalog.into_map()),
+//!     ..Default::default()
+//! }));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DotpromptError, Result};
+use crate::types::{ToolDefinition, ToolResolver};
+
+/// A set of tool definitions, usually loaded from `tools.yaml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCatalog {
+    /// The defined tools.
+    #[serde(default)]
+    pub tools: Vec<ToolDefinition>,
+}
+
+impl ToolCatalog {
+    /// Conventional file name for a tool catalog.
+    pub const FILE_NAME: &'static str = "tools.yaml";
+
+    /// Parses a catalog from YAML source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is not a valid catalog or defines a
+    /// tool twice.
+    pub fn from_yaml(source: &str) -> Result<Self> {
+        let catalog: Self = serde_yaml_ng::from_str::<Option<Self>>(source)
+            .map_err(|e| DotpromptError::ToolCatalogError(e.to_string()))?
+            .unwrap_or_default();
+        for (i, tool) in catalog.tools.iter().enumerate() {
+            if catalog.tools[..i].iter().any(|t| t.name == tool.name) {
+                return Err(DotpromptError::ToolCatalogError(format!(
+                    "tool '{}' is defined more than once",
+                    tool.name
+                )));
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Loads a catalog from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid catalog.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        Self::from_yaml(&source).map_err(|e| match e {
+            DotpromptError::ToolCatalogError(msg) => {
+                DotpromptError::ToolCatalogError(format!("{}: {msg}", path.display()))
+            }
+            other => other,
+        })
+    }
+
+    /// Looks up a tool by name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&ToolDefinition> {
+        self.tools.iter().find(|t| t.name == name)
+    }
+
+    /// Returns the tools keyed by name, as taken by
+    /// [`DotpromptOptions::tools`](crate::DotpromptOptions::tools).
+    #[must_use]
+    pub fn into_map(self) -> HashMap<String, ToolDefinition> {
+        self.tools
+            .into_iter()
+            .map(|tool| (tool.name.clone(), tool))
+            .collect()
+    }
+}
+
+impl ToolResolver for ToolCatalog {
+    fn resolve(&self, name: &str) -> Result<Option<ToolDefinition>> {
+        Ok(self.get(name).cloned())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_yaml() {
+        let catalog = ToolCatalog::from_yaml(
+            r"
+tools:
+  - name: getWeather
+    description: Looks up the weather.
+    inputSchema:
+      type: object
+  - name: search
+    inputSchema: {type: object}
+",
+        )
+        .unwrap();
+
+        assert_eq!(catalog.tools.len(), 2);
+        let weather = catalog.get("getWeather").unwrap();
+        assert_eq!(
+            weather.description.as_deref(),
+            Some("Looks up the weather.")
+        );
+        assert_eq!(
+            catalog.resolve("search").unwrap().map(|t| t.name),
+            Some("search".to_string())
+        );
+        assert!(catalog.resolve("missing").unwrap().is_none());
+        assert_eq!(catalog.into_map().len(), 2);
+    }
+
+    #[test]
+    fn test_from_yaml_rejects_duplicates_and_bad_fields() {
+        let duplicate = "tools:\n  - {name: a, inputSchema: {}}\n  - {name: a, inputSchema: {}}\n";
+        assert!(matches!(
+            ToolCatalog::from_yaml(duplicate),
+            Err(DotpromptError::ToolCatalogError(_))
+        ));
+        assert!(ToolCatalog::from_yaml("tools:\n  - description: nameless\n").is_err());
+        assert_eq!(ToolCatalog::from_yaml("").unwrap(), ToolCatalog::default());
+    }
+}
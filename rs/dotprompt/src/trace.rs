@@ -0,0 +1,582 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Render tracing.
+//!
+//! [`Dotprompt::render_traced`](crate::Dotprompt::render_traced) renders a
+//! prompt and records, for every text part of every rendered message, which
+//! template regions produced it:
+//!
+//! - literal template text, with its source span;
+//! - expressions and helper calls, e.g. `{{name}}` or `{{json data}}`;
+//! - partials, e.g. `{{> footer}}`, whose output is attributed to the tag.
+//!
+//! Each segment also lists the blocks it was rendered inside, such as
+//! `{{#if admin}}` or `{{#if admin}} {{else}}`, which explains why a
+//! conditional branch did or did not contribute.
+//!
+//! Tracing works by wrapping each run of literal text in invisible marker
+//! characters before rendering and reading them back out of the output, so
+//! the traced render produces exactly the same messages as
+//! [`Dotprompt::render`](crate::Dotprompt::render).
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::parse::MovedPart;
+use crate::types::{Message, Part, RenderedPrompt};
+
+/// Opens a traced text region; followed by the region index and
+/// [`REGION_ID_END`].
+const REGION_START: char = '\u{E000}';
+/// Ends the region index after [`REGION_START`].
+const REGION_ID_END: char = '\u{E001}';
+/// Closes a traced text region.
+const REGION_END: char = '\u{E002}';
+/// Metadata key marking history messages during a traced render.
+pub(crate) const HISTORY_FLAG: &str = "__dotprompt_trace_history";
+
+/// The kind of template region that produced a segment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SegmentKind {
+    /// Literal template text.
+    Text,
+    /// One or more adjacent expressions or helper calls.
+    Expression,
+    /// A partial.
+    Partial {
+        /// Name of the partial.
+        name: String,
+    },
+}
+
+/// A piece of rendered output and the template region that produced it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceSegment {
+    /// The rendered text.
+    pub text: String,
+    /// What produced the text.
+    pub kind: SegmentKind,
+    /// Byte range of the producing region in the prompt source.
+    pub span: Range<usize>,
+    /// Source of the producing region, e.g. `{{name}}`.
+    pub template: String,
+    /// Enclosing block tags, outermost first.
+    pub blocks: Vec<String>,
+}
+
+/// The trace of one text part of a rendered message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartTrace {
+    /// Index of the message in [`RenderedPrompt::messages`].
+    pub message: usize,
+    /// Index of the part in the message's content.
+    pub part: usize,
+    /// The part's text, split by producing region.
+    pub segments: Vec<TraceSegment>,
+}
+
+/// A rendered prompt with the trace of how it was produced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracedPrompt<M = serde_json::Value> {
+    /// The rendered prompt.
+    pub rendered: RenderedPrompt<M>,
+    /// Traces of the text parts of template-produced messages. History
+    /// messages supplied in the render data are not traced.
+    pub parts: Vec<PartTrace>,
+}
+
+/// A traceable region of a template.
+#[derive(Debug)]
+struct Region {
+    kind: SegmentKind,
+    span: Range<usize>,
+    template: String,
+    blocks: Vec<String>,
+    /// Whether the region only opens, closes, or branches blocks, or holds
+    /// comments, and so produces no output of its own.
+    structural: bool,
+}
+
+/// A piece of template source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Piece {
+    /// Raw text between tags.
+    Text(usize, usize),
+    /// A `{{...}}` tag.
+    Tag(usize, usize),
+}
+
+/// A template with its literal text wrapped in region markers.
+#[derive(Debug)]
+pub(crate) struct Instrumented {
+    /// The template to render.
+    pub(crate) template: String,
+    /// Regions in template order.
+    regions: Vec<Region>,
+}
+
+/// An open block while scanning: its opening tag and the `{{else}}` branch
+/// being scanned, if any.
+type OpenBlock<'a> = (&'a str, Option<&'a str>);
+
+impl Instrumented {
+    /// Instruments a template body that starts at byte `offset` of the
+    /// prompt source.
+    pub(crate) fn new(template: &str, offset: usize) -> Self {
+        let pieces = tokenize(template);
+        let mut out = String::with_capacity(template.len());
+        let mut regions: Vec<Region> = Vec::new();
+        let mut blocks: Vec<OpenBlock<'_>> = Vec::new();
+        // Index of the expression region that the next tag extends.
+        let mut run: Option<usize> = None;
+
+        for (i, piece) in pieces.iter().enumerate() {
+            match *piece {
+                Piece::Tag(start, end) => {
+                    let tag = &template[start..end];
+                    out.push_str(tag);
+                    let enclosing = block_names(&blocks);
+                    update_blocks(&mut blocks, tag);
+                    if let Some(r) = run {
+                        let region = &mut regions[r];
+                        region.span.end = offset + end;
+                        region.template = template[region.span.start - offset..end].to_string();
+                        if !is_structural(tag) {
+                            region.kind = if region.structural {
+                                tag_kind(tag)
+                            } else {
+                                SegmentKind::Expression
+                            };
+                            region.structural = false;
+                        }
+                    } else {
+                        run = Some(regions.len());
+                        regions.push(Region {
+                            kind: tag_kind(tag),
+                            span: offset + start..offset + end,
+                            template: tag.to_string(),
+                            blocks: enclosing,
+                            structural: is_structural(tag),
+                        });
+                    }
+                }
+                Piece::Text(start, end) => {
+                    run = None;
+                    let text = &template[start..end];
+                    if text.trim().is_empty() {
+                        out.push_str(text);
+                        continue;
+                    }
+                    let trims_left = i > 0
+                        && matches!(pieces[i - 1], Piece::Tag(s, e) if trims_after(&template[s..e]));
+                    let trims_right = matches!(pieces.get(i + 1), Some(Piece::Tag(s, e)) if trims_before(&template[*s..*e]));
+                    let lead = leading_trim(text, trims_left);
+                    let trail = trailing_trim(text, trims_right);
+                    let body = &text[lead..text.len() - trail];
+
+                    out.push_str(&text[..lead]);
+                    out.push(REGION_START);
+                    out.push_str(&regions.len().to_string());
+                    out.push(REGION_ID_END);
+                    out.push_str(body);
+                    out.push(REGION_END);
+                    out.push_str(&text[text.len() - trail..]);
+                    regions.push(Region {
+                        kind: SegmentKind::Text,
+                        span: offset + start + lead..offset + end - trail,
+                        template: body.to_string(),
+                        blocks: block_names(&blocks),
+                        structural: false,
+                    });
+                }
+            }
+        }
+
+        Self {
+            template: out,
+            regions,
+        }
+    }
+
+    /// Strips region markers from rendered messages and returns the traces
+    /// of their text parts. Messages flagged with [`HISTORY_FLAG`] are
+    /// unflagged and skipped.
+    pub(crate) fn extract(&self, messages: &mut [Message]) -> Vec<PartTrace> {
+        let mut traces = Vec::new();
+        let mut last_text = None;
+        for (m, message) in messages.iter_mut().enumerate() {
+            let history = message
+                .metadata
+                .as_mut()
+                .is_some_and(|metadata| metadata.remove(HISTORY_FLAG).is_some());
+            if history {
+                if message.metadata.as_ref().is_some_and(HashMap::is_empty) {
+                    message.metadata = None;
+                }
+                continue;
+            }
+            for (p, part) in message.content.iter_mut().enumerate() {
+                if let Part::Text(text) = part {
+                    let (clean, segments) = self.split(&text.text, &mut last_text);
+                    text.text = clean;
+                    traces.push(PartTrace {
+                        message: m,
+                        part: p,
+                        segments,
+                    });
+                }
+            }
+        }
+        traces
+    }
+
+    /// Splits marked output into clean text and segments.
+    ///
+    /// Text outside any marker was produced by the expression region that
+    /// follows the most recently seen text region.
+    fn split(&self, marked: &str, last_text: &mut Option<usize>) -> (String, Vec<TraceSegment>) {
+        let mut clean = String::with_capacity(marked.len());
+        let mut segments: Vec<TraceSegment> = Vec::new();
+        let mut inside: Option<usize> = None;
+        let mut rest = marked;
+
+        loop {
+            let next = rest.find([REGION_START, REGION_END]);
+            let chunk = &rest[..next.unwrap_or(rest.len())];
+            if !chunk.is_empty() {
+                clean.push_str(chunk);
+                let region = inside.or_else(|| self.expression_after(*last_text));
+                if let Some(region) = region {
+                    self.push_segment(&mut segments, region, chunk);
+                }
+            }
+            let Some(next) = next else { break };
+            rest = &rest[next..];
+            if rest.starts_with(REGION_END) {
+                inside = None;
+                rest = &rest[REGION_END.len_utf8()..];
+            } else {
+                rest = &rest[REGION_START.len_utf8()..];
+                let id_end = rest.find(REGION_ID_END).unwrap_or(rest.len());
+                inside = rest[..id_end].parse().ok();
+                *last_text = inside;
+                rest = rest
+                    .get(id_end + REGION_ID_END.len_utf8()..)
+                    .unwrap_or_default();
+            }
+        }
+
+        (clean, segments)
+    }
+
+    /// Returns the region to credit with output that follows text region
+    /// `last_text`: the first expression region after it that can produce
+    /// output, or the nearest text region when only whitespace lies between.
+    fn expression_after(&self, last_text: Option<usize>) -> Option<usize> {
+        let next = last_text.map_or(0, |i| i + 1);
+        let mut expressions = self
+            .regions
+            .iter()
+            .skip(next)
+            .take_while(|region| region.kind != SegmentKind::Text);
+        expressions
+            .position(|region| !region.structural)
+            .map(|i| next + i)
+            .or_else(|| {
+                self.regions
+                    .get(next)
+                    .is_some_and(|region| region.kind != SegmentKind::Text)
+                    .then_some(next)
+            })
+            .or(last_text)
+            .or_else(|| (next < self.regions.len()).then_some(next))
+    }
+
+    /// Appends text to the segment list, merging with the previous segment
+    /// when it came from the same region.
+    fn push_segment(&self, segments: &mut Vec<TraceSegment>, region: usize, text: &str) {
+        let Some(region) = self.regions.get(region) else {
+            return;
+        };
+        if let Some(last) = segments.last_mut().filter(|last| last.span == region.span) {
+            last.text.push_str(text);
+            return;
+        }
+        segments.push(TraceSegment {
+            text: text.to_string(),
+            kind: region.kind.clone(),
+            span: region.span.clone(),
+            template: region.template.clone(),
+            blocks: region.blocks.clone(),
+        });
+    }
+}
+
+/// Splits a template into raw text and `{{...}}` tags.
+fn tokenize(template: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut text_start = 0;
+    let mut search = 0;
+
+    while let Some(found) = template[search..].find("{{") {
+        let start = search + found;
+        if template[..start].ends_with('\\') {
+            search = start + 2;
+            continue;
+        }
+        let Some(end) = tag_end(template, start) else {
+            break;
+        };
+        if text_start < start {
+            pieces.push(Piece::Text(text_start, start));
+        }
+        pieces.push(Piece::Tag(start, end));
+        text_start = end;
+        search = end;
+    }
+    if text_start < template.len() {
+        pieces.push(Piece::Text(text_start, template.len()));
+    }
+    pieces
+}
+
+/// Returns the end of the tag starting at `start`, skipping quoted strings.
+fn tag_end(template: &str, start: usize) -> Option<usize> {
+    let rest = &template[start..];
+    if rest.starts_with("{{!--") {
+        return ["--}}", "--~}}"]
+            .iter()
+            .filter_map(|close| rest.find(close).map(|i| start + i + close.len()))
+            .min();
+    }
+    let close = if rest.starts_with("{{{{") {
+        "}}}}"
+    } else if rest.starts_with("{{{") {
+        "}}}"
+    } else {
+        "}}"
+    };
+    let mut quote = None;
+    for (i, c) in rest.char_indices().skip(2) {
+        match quote {
+            Some(q) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if rest[i..].starts_with(close) => return Some(start + i + close.len()),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the contents of a tag without braces or whitespace control.
+fn tag_body(tag: &str) -> &str {
+    tag.trim_start_matches('{')
+        .trim_end_matches('}')
+        .trim_start_matches('~')
+        .trim_end_matches('~')
+        .trim()
+}
+
+/// Whether a tag strips the whitespace before it (`{{~`).
+fn trims_before(tag: &str) -> bool {
+    tag.trim_start_matches('{').starts_with('~')
+}
+
+/// Whether a tag strips the whitespace after it (`~}}`).
+fn trims_after(tag: &str) -> bool {
+    tag.trim_end_matches('}').ends_with('~')
+}
+
+/// The region kind of a lone tag.
+fn tag_kind(tag: &str) -> SegmentKind {
+    let body = tag_body(tag);
+    body.strip_prefix("#>")
+        .or_else(|| body.strip_prefix('>'))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map_or(SegmentKind::Expression, |name| SegmentKind::Partial {
+            name: name.to_string(),
+        })
+}
+
+/// Whether a tag only opens, closes, or branches a block, or is a comment.
+fn is_structural(tag: &str) -> bool {
+    let body = tag_body(tag);
+    body.starts_with(['/', '!', '^'])
+        || (body.starts_with('#') && !body.starts_with("#>"))
+        || body == "else"
+        || body.starts_with("else ")
+}
+
+/// Tracks block nesting across a tag.
+fn update_blocks<'a>(blocks: &mut Vec<OpenBlock<'a>>, tag: &'a str) {
+    let body = tag_body(tag);
+    if body == "else" || body.starts_with("else ") || body == "^" {
+        if let Some(top) = blocks.last_mut() {
+            top.1 = Some(tag);
+        }
+    } else if body.starts_with('#') || body.starts_with('^') {
+        blocks.push((tag, None));
+    } else if body.starts_with('/') {
+        blocks.pop();
+    }
+}
+
+/// Describes open blocks, outermost first.
+fn block_names(blocks: &[OpenBlock<'_>]) -> Vec<String> {
+    blocks
+        .iter()
+        .map(|(open, branch)| {
+            branch.map_or_else(|| (*open).to_string(), |branch| format!("{open} {branch}"))
+        })
+        .collect()
+}
+
+/// Length of leading whitespace to leave outside a text region.
+///
+/// Whitespace up to the first newline may be removed by a standalone tag on
+/// the previous line, and all of it by a preceding `~}}`; keeping it outside
+/// the markers preserves Handlebars' whitespace handling.
+fn leading_trim(text: &str, trimmed: bool) -> usize {
+    let ws = text.len() - text.trim_start().len();
+    if trimmed {
+        return ws;
+    }
+    text[..ws].find('\n').map_or(0, |i| i + 1)
+}
+
+/// Length of trailing whitespace to leave outside a text region: the last
+/// newline and the indentation before a possibly standalone tag, or all of
+/// it before a `{{~`.
+fn trailing_trim(text: &str, trimmed: bool) -> usize {
+    let ws = text.len() - text.trim_end().len();
+    if trimmed {
+        return ws;
+    }
+    let tail = &text[text.len() - ws..];
+    tail.rfind('\n').map_or(0, |i| tail.len() - i)
+}
+
+/// Flags history messages so that [`Instrumented::extract`] can skip them.
+pub(crate) fn flag_history(messages: &mut [Message]) {
+    for message in messages {
+        message
+            .metadata
+            .get_or_insert_with(HashMap::new)
+            .insert(HISTORY_FLAG.to_string(), serde_json::Value::Bool(true));
+    }
+}
+
+/// Updates traces for messages rearranged by
+/// [`normalize_tracked`](crate::parse::normalize_tracked), dropping traces of
+/// removed parts and trimming segments to the text that was kept.
+pub(crate) fn follow_moves(traces: Vec<PartTrace>, moves: &[MovedPart]) -> Vec<PartTrace> {
+    traces
+        .into_iter()
+        .filter_map(|trace| {
+            let part = moves
+                .iter()
+                .find(|moved| moved.from == (trace.message, trace.part))?;
+            let mut start = 0;
+            let segments = trace
+                .segments
+                .into_iter()
+                .filter_map(|mut segment| {
+                    let range = start..start + segment.text.len();
+                    start = range.end;
+                    let kept = range.start.max(part.kept.start)..range.end.min(part.kept.end);
+                    if kept.is_empty() {
+                        return None;
+                    }
+                    segment.text =
+                        segment.text[kept.start - range.start..kept.end - range.start].to_string();
+                    Some(segment)
+                })
+                .collect();
+            Some(PartTrace {
+                message: part.to.0,
+                part: part.to.1,
+                segments,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let template = r#"Hi {{name}}!{{!-- a }} comment --}} \{{x}} {{json "}}"}}"#;
+        let pieces: Vec<&str> = tokenize(template)
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Text(s, e) | Piece::Tag(s, e) => &template[s..e],
+            })
+            .collect();
+        assert_eq!(
+            pieces,
+            [
+                "Hi ",
+                "{{name}}",
+                "!",
+                "{{!-- a }} comment --}}",
+                " \\{{x}} ",
+                r#"{{json "}}"}}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn test_whitespace_outside_markers() {
+        assert_eq!(leading_trim("  \n  text", false), 3);
+        assert_eq!(leading_trim("  text", false), 0);
+        assert_eq!(leading_trim("  text", true), 2);
+        assert_eq!(trailing_trim("text\n  ", false), 3);
+        assert_eq!(trailing_trim("text  ", false), 0);
+        assert_eq!(trailing_trim("text  ", true), 2);
+    }
+
+    #[test]
+    fn test_regions() {
+        let template = "{{#if a}}\nYes {{name}}{{> footer}}\n{{else}}\nNo\n{{/if}}";
+        let instrumented = Instrumented::new(template, 10);
+        let regions: Vec<(&SegmentKind, &str, &[String])> = instrumented
+            .regions
+            .iter()
+            .map(|r| (&r.kind, r.template.as_str(), r.blocks.as_slice()))
+            .collect();
+
+        assert_eq!(regions.len(), 6);
+        assert_eq!(regions[1].1, "Yes ");
+        assert_eq!(regions[1].2, ["{{#if a}}"]);
+        assert_eq!(regions[2].0, &SegmentKind::Expression);
+        assert_eq!(regions[2].1, "{{name}}{{> footer}}");
+        assert_eq!(regions[4].1, "No");
+        assert_eq!(regions[4].2, ["{{#if a}} {{else}}"]);
+        assert_eq!(
+            instrumented.regions[1].span,
+            10 + template.find("Yes").unwrap()..10 + template.find("{{name").unwrap()
+        );
+    }
+}
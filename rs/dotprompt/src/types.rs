@@ -20,8 +20,8 @@
 //! messages, metadata, and related concepts. These types closely mirror the
 //! canonical JavaScript implementation for cross-language compatibility.
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 /// Type alias for generic schemas.
 pub type Schema = HashMap<String, serde_json::Value>;
@@ -52,7 +52,7 @@ pub enum Role {
 }
 
 /// Tool definition specifying inputs and outputs.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolDefinition {
     /// Name of the tool.
@@ -93,6 +93,27 @@ pub struct PromptRef {
     /// Optional version identifier.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+
+    /// Frontmatter summary, filled in by listings when requested with
+    /// [`ListPromptsOptions::include_metadata`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<PromptSummary>,
+}
+
+/// The frontmatter fields of a prompt that listings can include.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptSummary {
+    /// The model the prompt targets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// The prompt's description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The prompt's tags.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
 }
 
 /// Prompt data including source template.
@@ -107,7 +128,7 @@ pub struct PromptData {
 }
 
 /// Configuration for prompt input variables.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PromptInputConfig {
     /// Default values for input variables.
     #[serde(skip_serializing_if = "Option::is_none", rename = "default")]
@@ -119,7 +140,7 @@ pub struct PromptInputConfig {
 }
 
 /// Configuration for prompt output format.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PromptOutputConfig {
     /// Desired output format (e.g., "json", "text").
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -130,8 +151,137 @@ pub struct PromptOutputConfig {
     pub schema: Option<serde_json::Value>,
 }
 
+/// How a template renders a value that is `null` or missing from the data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingPolicy {
+    /// Render nothing.
+    #[default]
+    Empty,
+    /// Render the text `null`.
+    Null,
+    /// Fail the render with
+    /// [`DotpromptError::MissingValue`](crate::DotpromptError::MissingValue).
+    Error,
+}
+
+/// Rendering settings a prompt sets in its frontmatter:
+///
+/// ```yaml
+/// render:
+///   onMissing: error
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderConfig {
+    /// How `null` and missing values render, in place of
+    /// [`DotpromptOptions::on_missing`](crate::DotpromptOptions::on_missing).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_missing: Option<MissingPolicy>,
+}
+
+/// How few-shot examples are chosen from their source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExampleSampling {
+    /// The first `count` examples, in order.
+    #[default]
+    First,
+    /// `count` examples chosen at random, the same ones for the same `seed`.
+    Random,
+}
+
+/// Few-shot examples a prompt loads from a data file and renders at its
+/// `{{examples}}` marker:
+///
+/// ```yaml
+/// examples:
+///   source: ./examples/sentiment.jsonl
+///   count: 5
+///   sample: random
+///   seed: 42
+///   template: "Text: {{text}}\nSentiment: {{label}}"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamplesConfig {
+    /// The file the examples are in, relative to the prompt directory: a
+    /// `.jsonl` file with one example per line, or a `.json` or `.yaml`
+    /// list.
+    pub source: String,
+
+    /// How many examples to use; all of them if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+
+    /// How the examples are chosen.
+    #[serde(default)]
+    pub sample: ExampleSampling,
+
+    /// Seed for `sample: random`. Without one, each render chooses
+    /// differently.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    /// Template each example is rendered with; `{{json this}}` if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+
+    /// Text between rendered examples; a blank line if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+}
+
+/// Reads a list of strings, accepting a single string as a one-item list.
+fn string_or_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+
+    Ok(
+        Option::<StringOrList>::deserialize(deserializer)?.map(|value| match value {
+            StringOrList::String(item) => vec![item],
+            StringOrList::List(items) => items,
+        }),
+    )
+}
+
+/// Reads a `render` field that is a mapping, ignoring other values.
+fn render_config<'de, D>(deserializer: D) -> Result<Option<RenderConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    if !value.is_object() {
+        return Ok(None);
+    }
+    serde_json::from_value(value)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Reads an `examples` field that names a `source`, ignoring other values.
+fn examples_config<'de, D>(deserializer: D) -> Result<Option<ExamplesConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    if value.get("source").is_none() {
+        return Ok(None);
+    }
+    serde_json::from_value(value)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
 /// Metadata associated with a prompt template.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PromptMetadata<M = serde_json::Value> {
     /// Name of the prompt.
@@ -150,6 +300,33 @@ pub struct PromptMetadata<M = serde_json::Value> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 
+    /// Free-form labels used to categorize the prompt. A single label may
+    /// be written as a string.
+    #[serde(
+        default,
+        deserialize_with = "string_or_list",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tags: Option<Vec<String>>,
+
+    /// People or teams responsible for the prompt. A single owner may be
+    /// written as a string.
+    #[serde(
+        default,
+        deserialize_with = "string_or_list",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub owners: Option<Vec<String>>,
+
+    /// People or teams who should review changes to the prompt. A single
+    /// reviewer may be written as a string.
+    #[serde(
+        default,
+        deserialize_with = "string_or_list",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reviewers: Option<Vec<String>>,
+
     /// Model identifier (e.g., "vertexai/gemini-1.0-pro").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
@@ -174,6 +351,28 @@ pub struct PromptMetadata<M = serde_json::Value> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<PromptOutputConfig>,
 
+    /// Rendering settings, from the `render` field.
+    ///
+    /// A `render` value that is not a mapping is left in the raw
+    /// frontmatter.
+    #[serde(
+        default,
+        deserialize_with = "render_config",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub render: Option<RenderConfig>,
+
+    /// Few-shot examples loaded from a data file.
+    ///
+    /// Only the `{source: ...}` form is read; other `examples` values, such
+    /// as an inline list, are left in the raw frontmatter.
+    #[serde(
+        default,
+        deserialize_with = "examples_config",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub examples: Option<ExamplesConfig>,
+
     /// Raw frontmatter as parsed.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub raw: Option<HashMap<String, serde_json::Value>>,
@@ -199,7 +398,7 @@ pub struct ParsedPrompt<M = serde_json::Value> {
 }
 
 /// Media content reference.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MediaContent {
     /// URL of the media.
@@ -211,7 +410,7 @@ pub struct MediaContent {
 }
 
 /// Tool request content.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ToolRequestContent {
     /// Name of the tool being requested.
     pub name: String,
@@ -226,7 +425,7 @@ pub struct ToolRequestContent {
 }
 
 /// Tool response content.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ToolResponseContent {
     /// Name of the tool that was called.
     pub name: String,
@@ -241,7 +440,7 @@ pub struct ToolResponseContent {
 }
 
 /// Content part within a message.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Part {
     /// Text content.
@@ -259,7 +458,7 @@ pub enum Part {
 }
 
 /// Text content part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TextPart {
     /// The text content.
     pub text: String,
@@ -270,7 +469,7 @@ pub struct TextPart {
 }
 
 /// Structured data part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DataPart {
     /// The data content as a JSON object.
     pub data: HashMap<String, serde_json::Value>,
@@ -281,7 +480,7 @@ pub struct DataPart {
 }
 
 /// Media reference part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MediaPart {
     /// The media content reference.
     pub media: MediaContent,
@@ -292,7 +491,7 @@ pub struct MediaPart {
 }
 
 /// Tool request part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolRequestPart {
     /// The tool request details.
@@ -304,7 +503,7 @@ pub struct ToolRequestPart {
 }
 
 /// Tool response part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolResponsePart {
     /// The tool response details.
@@ -316,14 +515,14 @@ pub struct ToolResponsePart {
 }
 
 /// Pending/placeholder part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PendingPart {
     /// Metadata must contain `pending: true`.
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
 /// A message in a conversation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Message {
     /// Role of the message sender.
     pub role: Role,
@@ -365,8 +564,18 @@ pub struct DataArgument<V = serde_json::Value> {
     /// Context variables (exposed as `@` variables in templates).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<HashMap<String, serde_json::Value>>,
+
+    /// Opaque caller metadata, such as trace or request ids, carried into
+    /// the rendered prompt under [`REQUEST_METADATA_KEY`]. It is not
+    /// visible to the template.
+    #[serde(rename = "requestMetadata", skip_serializing_if = "Option::is_none")]
+    pub request_metadata: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
+/// Key of [`DataArgument::request_metadata`] in a rendered prompt's
+/// `metadata` map.
+pub const REQUEST_METADATA_KEY: &str = "requestMetadata";
+
 /// Rendered prompt output with messages.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenderedPrompt<M = serde_json::Value> {
@@ -376,6 +585,98 @@ pub struct RenderedPrompt<M = serde_json::Value> {
 
     /// Rendered messages to send to the model.
     pub messages: Vec<Message>,
+
+    /// Which prompt revision produced the messages, when enabled with
+    /// `DotpromptOptions::render_info`.
+    #[serde(
+        default,
+        rename = "renderInfo",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub render_info: Option<RenderInfo>,
+}
+
+impl<M> RenderedPrompt<M> {
+    /// Returns the text of the system messages, or `None` if there is none.
+    ///
+    /// Text parts are concatenated within a message, and messages are joined
+    /// with a blank line. Other kinds of parts are ignored.
+    #[must_use]
+    pub fn system_text(&self) -> Option<String> {
+        let texts: Vec<String> = self
+            .messages
+            .iter()
+            .filter(|message| message.role == Role::System)
+            .map(|message| {
+                message
+                    .content
+                    .iter()
+                    .filter_map(|part| match part {
+                        Part::Text(text) => Some(text.text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<String>()
+            })
+            .filter(|text| !text.is_empty())
+            .collect();
+        if texts.is_empty() {
+            None
+        } else {
+            Some(texts.join("\n\n"))
+        }
+    }
+
+    /// Returns the request metadata the prompt was rendered with, if any.
+    ///
+    /// See [`DataArgument::request_metadata`].
+    #[must_use]
+    pub fn request_metadata(&self) -> Option<&serde_json::Map<String, serde_json::Value>> {
+        self.metadata
+            .metadata
+            .as_ref()?
+            .get(REQUEST_METADATA_KEY)?
+            .as_object()
+    }
+
+    /// Separates the system prompt from the conversation, for provider APIs
+    /// that take it as its own field.
+    ///
+    /// Returns [`system_text`](Self::system_text) and the non-system
+    /// messages, in order.
+    #[must_use]
+    pub fn split_system(&self) -> (Option<String>, Vec<&Message>) {
+        let rest = self
+            .messages
+            .iter()
+            .filter(|message| message.role != Role::System)
+            .collect();
+        (self.system_text(), rest)
+    }
+}
+
+/// Reproducibility metadata for a rendered prompt.
+///
+/// Logging this alongside a model response attributes the response to the
+/// exact template and partial revisions that produced its prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderInfo {
+    /// Version hash of the prompt source, as reported by prompt stores.
+    pub template_version: String,
+
+    /// Version hashes of the partials the template uses, directly or
+    /// through other partials.
+    pub partial_versions: BTreeMap<String, String>,
+
+    /// Version of the dotprompt library that rendered the prompt.
+    pub library_version: String,
+
+    /// The model the prompt resolved to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// When the prompt was rendered, as an RFC 3339 UTC timestamp.
+    pub rendered_at: String,
 }
 
 /// Reference to a partial template.
@@ -409,7 +710,12 @@ pub struct PartialData {
 /// Used by the picoschema system to look up named schemas from a registry.
 pub trait SchemaResolver: Send + Sync {
     /// Resolves a schema name to its JSON Schema definition.
-    fn resolve(&self, name: &str) -> Option<JsonSchema>;
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lookup itself fails, e.g. because the
+    /// registry is unreachable. An unknown name is `Ok(None)`.
+    fn resolve(&self, name: &str) -> crate::error::Result<Option<JsonSchema>>;
 }
 
 /// Resolves tool names to tool definitions.
@@ -417,7 +723,47 @@ pub trait SchemaResolver: Send + Sync {
 /// Used to look up tool definitions by name from a registry.
 pub trait ToolResolver: Send + Sync {
     /// Resolves a tool name to its definition.
-    fn resolve(&self, name: &str) -> Option<ToolDefinition>;
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lookup itself fails, e.g. because the
+    /// registry is unreachable. An unknown name is `Ok(None)`.
+    fn resolve(&self, name: &str) -> crate::error::Result<Option<ToolDefinition>>;
+}
+
+/// Resolves the `examples.source` of prompts to their examples.
+pub trait ExampleResolver: Send + Sync {
+    /// Loads the examples in a source file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed. An unknown
+    /// source is `Ok(None)`.
+    fn resolve(&self, source: &str) -> crate::error::Result<Option<Vec<serde_json::Value>>>;
+}
+
+/// Chooses the few-shot examples a render uses, given its input.
+///
+/// The default, [`StaticExampleSelector`](crate::examples::StaticExampleSelector),
+/// follows the prompt's `count` and `sample` settings. A selector can
+/// instead pick the examples most similar to the input, e.g. by comparing
+/// embeddings.
+pub trait ExampleSelector: Send + Sync {
+    /// Returns the examples to render, in order.
+    ///
+    /// `input` is the render input with defaults applied, and `examples`
+    /// are all the examples loaded from `config.source`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the examples cannot be chosen, e.g. because an
+    /// embedding service is unreachable.
+    fn select(
+        &self,
+        input: &serde_json::Value,
+        examples: Vec<serde_json::Value>,
+        config: &ExamplesConfig,
+    ) -> crate::error::Result<Vec<serde_json::Value>>;
 }
 
 /// Resolves partial names to their template source.
@@ -425,7 +771,12 @@ pub trait ToolResolver: Send + Sync {
 /// Used to dynamically load partial templates.
 pub trait PartialResolver: Send + Sync {
     /// Resolves a partial name to its template source.
-    fn resolve(&self, name: &str) -> Option<String>;
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lookup itself fails, e.g. on a network or
+    /// I/O error. An unknown name is `Ok(None)`.
+    fn resolve(&self, name: &str) -> crate::error::Result<Option<String>>;
 }
 
 /// Options for listing prompts with pagination.
@@ -442,6 +793,15 @@ pub struct ListPromptsOptions {
     /// Specific variant to filter.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub variant: Option<String>,
+
+    /// Only list prompts carrying all of these tags.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+
+    /// Include each prompt's model, description, and tags in the listing,
+    /// for stores that support it.
+    #[serde(default, rename = "includeMetadata")]
+    pub include_metadata: bool,
 }
 
 /// Options for listing partials with pagination.
@@ -542,6 +902,49 @@ pub struct PromptRefFunction {
     pub prompt_ref: PromptRef,
 }
 
+/// The outcome of [`Dotprompt::precompile_store`](crate::Dotprompt::precompile_store).
+#[derive(Debug, Default)]
+pub struct PrecompileReport {
+    /// Prompts that compiled, as `name` or `name.variant`.
+    pub prompts: Vec<String>,
+    /// Partials that compiled, named the same way.
+    pub partials: Vec<String>,
+    /// Prompts and partials that did not compile.
+    pub failures: Vec<PrecompileFailure>,
+}
+
+impl PrecompileReport {
+    /// Returns whether everything in the store compiled.
+    #[must_use]
+    pub const fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A prompt or partial that failed to compile.
+#[derive(Debug)]
+pub struct PrecompileFailure {
+    /// Name of the prompt or partial.
+    pub name: String,
+    /// Variant, if any.
+    pub variant: Option<String>,
+    /// Whether this is a partial rather than a prompt.
+    pub partial: bool,
+    /// Why it failed.
+    pub error: crate::error::DotpromptError,
+}
+
+impl std::fmt::Display for PrecompileFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = if self.partial { "partial" } else { "prompt" };
+        write!(f, "{kind} '{}'", self.name)?;
+        if let Some(variant) = &self.variant {
+            write!(f, " (variant '{variant}')")?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
 /// A bundle of prompts and partials.
 ///
 /// Used for bulk operations and serialization.
@@ -552,3 +955,49 @@ pub struct PromptBundle {
     /// Prompt templates.
     pub prompts: Vec<PromptData>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, texts: &[&str]) -> Message {
+        Message {
+            role,
+            content: texts
+                .iter()
+                .map(|text| {
+                    Part::Text(TextPart {
+                        text: (*text).to_string(),
+                        metadata: None,
+                    })
+                })
+                .collect(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_split_system() {
+        let rendered: RenderedPrompt = RenderedPrompt {
+            metadata: PromptMetadata::default(),
+            messages: vec![
+                message(Role::System, &["Be ", "brief."]),
+                message(Role::User, &["Hi"]),
+                message(Role::System, &["Be kind."]),
+                message(Role::Model, &["Hello"]),
+            ],
+            render_info: None,
+        };
+        let (system, rest) = rendered.split_system();
+        assert_eq!(system.as_deref(), Some("Be brief.\n\nBe kind."));
+        let roles: Vec<Role> = rest.iter().map(|message| message.role).collect();
+        assert_eq!(roles, [Role::User, Role::Model]);
+
+        let rendered: RenderedPrompt = RenderedPrompt {
+            metadata: PromptMetadata::default(),
+            messages: vec![message(Role::User, &["Hi"])],
+            render_info: None,
+        };
+        assert_eq!(rendered.system_text(), None);
+    }
+}
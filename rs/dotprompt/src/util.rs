@@ -17,13 +17,117 @@
 //! Utility functions for dotprompt.
 
 use crate::error::{DotpromptError, Result};
+use crate::parse::parse_document;
+use crate::types::PromptSummary;
+use sha1::{Digest, Sha1};
+use std::time::{SystemTime, UNIX_EPOCH};
 use unicode_normalization::UnicodeNormalization;
 use urlencoding::decode;
 
-/// Validates that a prompt name doesn't contain path traversal sequences.
+/// Calculates the short content version used by prompt stores.
 ///
-/// This function implements multiple layers of validation to prevent path
-/// traversal attacks (CWE-22):
+/// The version is the first 8 hex characters of the SHA-1 digest of the
+/// source, matching the canonical JavaScript `DirStore`.
+pub(crate) fn calculate_version(content: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(content.as_bytes());
+    let result = hasher.finalize();
+    hex::encode(result)[..8].to_string()
+}
+
+/// Formats a time as an RFC 3339 UTC timestamp with millisecond precision,
+/// e.g. `2025-01-31T09:05:00.250Z`. Times before the Unix epoch format as
+/// the epoch.
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Checks whether a prompt's frontmatter carries every one of `tags`.
+///
+/// Sources whose frontmatter cannot be parsed have no tags.
+pub(crate) fn has_tags(source: &str, tags: &[String]) -> bool {
+    if tags.is_empty() {
+        return true;
+    }
+    let Ok(parsed) = parse_document::<serde_json::Value>(source) else {
+        return false;
+    };
+    let prompt_tags = parsed.metadata.tags.unwrap_or_default();
+    tags.iter().all(|t| prompt_tags.contains(t))
+}
+
+/// Summarizes the model, description, and tags in a prompt's frontmatter.
+///
+/// Sources whose frontmatter cannot be parsed have an empty summary.
+pub(crate) fn prompt_summary(source: &str) -> PromptSummary {
+    let Ok(parsed) = parse_document::<serde_json::Value>(source) else {
+        return PromptSummary::default();
+    };
+    PromptSummary {
+        model: parsed.metadata.model,
+        description: parsed.metadata.description,
+        tags: parsed.metadata.tags,
+    }
+}
+
+/// Punctuation allowed in prompt names, besides letters and digits.
+///
+/// `/` separates nested names and `@` starts scoped package names such as
+/// `@acme/support/greeting`.
+pub const PROMPT_NAME_PUNCTUATION: [char; 5] = ['-', '_', '.', '/', '@'];
+
+/// Options for [`validate_prompt_name_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PromptNameOptions {
+    /// Allow spaces, for display names that are mapped to file names with
+    /// [`prompt_name_slug`].
+    pub allow_spaces: bool,
+}
+
+/// Validates a prompt name with the default [`PromptNameOptions`].
+///
+/// # Errors
+///
+/// Returns an `DotpromptError::InvalidPromptName` if the name contains invalid characters or traversal patterns.
+pub fn validate_prompt_name(name: &str) -> Result<()> {
+    validate_prompt_name_with(name, PromptNameOptions::default())
+}
+
+/// Turns a display name into a prompt name by replacing each run of
+/// whitespace with `-`, e.g. `Weekly Report` becomes `Weekly-Report`.
+#[must_use]
+pub fn prompt_name_slug(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+/// Validates that a prompt name is safe to use as a path in a store.
+///
+/// Names may only contain Unicode letters, digits, and
+/// [`PROMPT_NAME_PUNCTUATION`], plus spaces with
+/// [`PromptNameOptions::allow_spaces`]. On top of that, this function
+/// implements multiple layers of validation to prevent path traversal
+/// attacks (CWE-22):
 /// 1. URL decoding - catches %2e%2e encoded dots
 /// 2. Unicode normalization - catches homograph bypass attempts
 /// 3. Segment-based validation - checks each path component for leading dots
@@ -31,11 +135,13 @@ use urlencoding::decode;
 /// # Arguments
 ///
 /// * `name` - The prompt name to validate
+/// * `options` - Which optional characters to accept
 ///
 /// # Errors
 ///
 /// Returns an `DotpromptError::InvalidPromptName` if the name contains invalid characters or traversal patterns.
-pub fn validate_prompt_name(name: &str) -> Result<()> {
+/// A disallowed character is named in the message.
+pub fn validate_prompt_name_with(name: &str, options: PromptNameOptions) -> Result<()> {
     if name.is_empty() {
         return Err(DotpromptError::InvalidPromptName(
             "Prompt name cannot be empty".to_string(),
@@ -86,6 +192,8 @@ pub fn validate_prompt_name(name: &str) -> Result<()> {
     // SECURITY FIX 2: Normalize Unicode BEFORE validation
     let normalized: String = decoded.nfc().collect();
 
+    check_name_characters(name, &normalized, options)?;
+
     // Check for current directory reference patterns
     if normalized.contains("./") || normalized.contains(".\\") {
         return Err(DotpromptError::InvalidPromptName(format!(
@@ -173,9 +281,37 @@ pub fn validate_prompt_name(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Rejects the first character of a normalized prompt name that is not
+/// allowed in names.
+fn check_name_characters(name: &str, normalized: &str, options: PromptNameOptions) -> Result<()> {
+    let allowed = |c: char| {
+        c.is_alphanumeric()
+            || PROMPT_NAME_PUNCTUATION.contains(&c)
+            || (c == ' ' && options.allow_spaces)
+    };
+    if let Some(c) = normalized.chars().find(|&c| !allowed(c)) {
+        return Err(DotpromptError::InvalidPromptName(format!(
+            "Invalid prompt name: character {c:?} is not allowed in '{name}'; use letters, digits, '-', '_', '.', '@', or '/'"
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_timestamp() {
+        let at = |secs: u64, millis: u64| {
+            format_timestamp(UNIX_EPOCH + Duration::from_millis(secs * 1_000 + millis))
+        };
+        assert_eq!(at(0, 0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(at(951_782_400, 7), "2000-02-29T00:00:00.007Z");
+        assert_eq!(at(1_738_314_300, 250), "2025-01-31T09:05:00.250Z");
+    }
 
     #[test]
     fn test_validate_prompt_name() {
@@ -197,11 +333,11 @@ mod tests {
             ("URL encoded dot", "foo/%2e%2e/bar", true),
             ("Double URL encoded", "%252e%252e/etc/passwd", true),
             ("Double URL nested", "%25252e%25252e", true),
-            // NFC passes this as it doesn't normalize fullwidth dot to ascii dot
+            // NFC keeps the fullwidth dot, which is not an allowed character
             (
                 "Fullwidth dot homograph",
                 "\u{ff0e}\u{ff0e}/etc/passwd",
-                false,
+                true,
             ),
             ("Current dir ./", "./config", true),
             ("Current dir .\\", r".\config", true),
@@ -215,6 +351,13 @@ mod tests {
             ("Multiple dots", "a.b.c", false),
             ("Triple dot start", "...test", false),
             ("Triple dot end", "test...", false),
+            ("Scoped package", "@acme/support/greeting", false),
+            ("Unicode letters", "résumé/日本語", false),
+            ("Unicode digits", "report-٣", false),
+            ("Space", "weekly report", true),
+            ("Colon", "lib:header", true),
+            ("Asterisk", "a*b", true),
+            ("Newline", "a\nb", true),
         ];
 
         for (desc, prompt, should_err) in vectors {
@@ -233,4 +376,27 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_prompt_name_error_names_character() {
+        let err = validate_prompt_name("lib:header").unwrap_err().to_string();
+        assert!(err.contains("character ':'"), "{err}");
+        let err = validate_prompt_name("a\tb").unwrap_err().to_string();
+        assert!(err.contains(r"character '\t'"), "{err}");
+    }
+
+    #[test]
+    fn test_prompt_names_with_spaces() {
+        let options = PromptNameOptions { allow_spaces: true };
+        assert!(validate_prompt_name_with("Weekly Report", options).is_ok());
+        assert!(validate_prompt_name_with("   ", options).is_err());
+        assert!(validate_prompt_name_with("../Weekly Report", options).is_err());
+        assert!(validate_prompt_name_with("a\tb", options).is_err());
+
+        assert_eq!(
+            prompt_name_slug(" Weekly  Report/Q3 draft "),
+            "Weekly-Report/Q3-draft"
+        );
+        assert!(validate_prompt_name(&prompt_name_slug("Weekly Report")).is_ok());
+    }
 }
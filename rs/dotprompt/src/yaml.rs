@@ -0,0 +1,315 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Frontmatter YAML analysis with source spans.
+//!
+//! The YAML deserializer reports a single point for each error, and for
+//! duplicate keys that point is the start of the enclosing mapping. These
+//! functions recover byte ranges within the frontmatter text so tools can
+//! underline the offending source:
+//!
+//! - [`duplicate_keys`] finds every key that appears twice in a mapping.
+//! - [`error_span`] widens a deserializer error to the construct it is about.
+//! - [`scalars`] lists every scalar value with its path and range.
+//!
+//! ```
+//! use dotprompt::yaml::duplicate_keys;
+//!
+//! let yaml = "model: a\nconfig:\n  temperature: 1\n  temperature: 2\n";
+//! let duplicates = duplicate_keys(yaml);
+//! assert_eq!(duplicates[0].path, "config.temperature");
+//! assert_eq!(&yaml[duplicates[0].duplicate.clone()], "temperature");
+//! ```
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use saphyr_parser::{Event, Parser};
+
+/// A mapping key that appears more than once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey {
+    /// Path of the key, such as `config.temperature` or `tools[1].name`.
+    pub path: String,
+    /// Byte range of the first occurrence.
+    pub first: Range<usize>,
+    /// Byte range of the repeated occurrence.
+    pub duplicate: Range<usize>,
+}
+
+/// A scalar value in a YAML document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scalar {
+    /// Path of the value, such as `input.schema.name` or `tools[1]`.
+    pub path: String,
+    /// The value, without quotes or escapes.
+    pub value: String,
+    /// Byte range of the value as written, including any quotes.
+    pub range: Range<usize>,
+}
+
+/// A collection being walked by [`walk`].
+enum Frame {
+    Mapping {
+        path: String,
+        /// The key of the value that comes next, if the next node is a value.
+        key: Option<String>,
+    },
+    Sequence {
+        path: String,
+        index: usize,
+    },
+}
+
+/// A node visited by [`walk`].
+struct Node<'a> {
+    event: &'a Event<'a>,
+    /// Byte range of the node.
+    range: Range<usize>,
+    /// Path of the node or, for a mapping key, of its mapping.
+    path: &'a str,
+    /// Whether the node is a mapping key.
+    is_key: bool,
+}
+
+/// Appends a mapping key to a path.
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Visits the nodes of a document in source order, stopping at the first
+/// syntax error.
+fn walk(yaml: &str, mut visit: impl FnMut(Node<'_>)) {
+    // The parser reports character indices; map them to byte offsets.
+    let offsets: Vec<usize> = yaml
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(yaml.len()))
+        .collect();
+    let byte = |index: usize| offsets.get(index).copied().unwrap_or(yaml.len());
+
+    let mut stack: Vec<Frame> = Vec::new();
+    for event in Parser::new_from_str(yaml) {
+        let Ok((event, span)) = event else {
+            break;
+        };
+        let range = byte(span.start.index())..byte(span.end.index());
+        match event {
+            Event::MappingEnd | Event::SequenceEnd => {
+                stack.pop();
+            }
+            Event::Scalar(..)
+            | Event::Alias(_)
+            | Event::MappingStart(..)
+            | Event::SequenceStart(..) => {
+                let (path, is_key) = match stack.last_mut() {
+                    None => (String::new(), false),
+                    Some(Frame::Sequence { path, index }) => {
+                        *index += 1;
+                        (format!("{path}[{}]", *index - 1), false)
+                    }
+                    Some(Frame::Mapping { path, key }) if key.is_some() => {
+                        (join(path, &key.take().unwrap_or_default()), false)
+                    }
+                    Some(Frame::Mapping { path, key }) => {
+                        // Complex keys are shown as `?` in paths.
+                        *key = Some(match &event {
+                            Event::Scalar(value, ..) => value.to_string(),
+                            _ => "?".to_string(),
+                        });
+                        (path.clone(), true)
+                    }
+                };
+                visit(Node {
+                    event: &event,
+                    range,
+                    path: &path,
+                    is_key,
+                });
+                let path = if is_key { join(&path, "?") } else { path };
+                match event {
+                    Event::MappingStart(..) => stack.push(Frame::Mapping { path, key: None }),
+                    Event::SequenceStart(..) => stack.push(Frame::Sequence { path, index: 0 }),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the keys repeated within a mapping, in source order.
+///
+/// Only scalar keys are compared. Scanning stops at the first syntax error,
+/// so duplicates after it are not reported.
+#[must_use]
+pub fn duplicate_keys(yaml: &str) -> Vec<DuplicateKey> {
+    let mut duplicates = Vec::new();
+    // Keys seen so far in each mapping, with the range of their first
+    // occurrence.
+    let mut seen: HashMap<String, HashMap<String, Range<usize>>> = HashMap::new();
+    walk(yaml, |node| {
+        let (true, Event::Scalar(name, ..)) = (node.is_key, node.event) else {
+            return;
+        };
+        let keys = seen.entry(node.path.to_string()).or_default();
+        if let Some(first) = keys.get(name.as_ref()) {
+            duplicates.push(DuplicateKey {
+                path: join(node.path, name),
+                first: first.clone(),
+                duplicate: node.range,
+            });
+        } else {
+            keys.insert(name.to_string(), node.range);
+        }
+    });
+    duplicates
+}
+
+/// Returns the scalar values of a document, with their paths, in source
+/// order. Mapping keys are not included.
+///
+/// ```
+/// use dotprompt::yaml::scalars;
+///
+/// let yaml = "input:\n  schema:\n    person: {$ref: '#/schemas/Person'}\n";
+/// let values = scalars(yaml);
+/// assert_eq!(values[0].path, "input.schema.person.$ref");
+/// assert_eq!(values[0].value, "#/schemas/Person");
+/// assert_eq!(&yaml[values[0].range.clone()], "'#/schemas/Person'");
+/// ```
+#[must_use]
+pub fn scalars(yaml: &str) -> Vec<Scalar> {
+    let mut scalars = Vec::new();
+    walk(yaml, |node| {
+        if let (false, Event::Scalar(value, ..)) = (node.is_key, node.event) {
+            scalars.push(Scalar {
+                path: node.path.to_string(),
+                value: value.to_string(),
+                range: node.range,
+            });
+        }
+    });
+    scalars
+}
+
+/// Returns the byte range in `yaml` that a deserializer error refers to.
+///
+/// When the error names the construct being parsed ("while parsing a flow
+/// sequence at line 1 column 4"), the range runs from that construct to the
+/// token where parsing failed. Otherwise it covers that token. Returns
+/// `None` if the error has no location.
+#[must_use]
+pub fn error_span(yaml: &str, error: &serde_yaml_ng::Error) -> Option<Range<usize>> {
+    let index = error.location()?.index().min(yaml.len());
+    let rest = yaml.get(index..)?;
+    let token: usize = rest
+        .chars()
+        .take_while(|c| !c.is_whitespace() && !matches!(c, ',' | '[' | ']' | '{' | '}'))
+        .map(char::len_utf8)
+        .sum();
+    let token = if token == 0 {
+        // Point at the character itself, unless it ends the line.
+        rest.chars()
+            .next()
+            .filter(|c| *c != '\n')
+            .map_or(0, char::len_utf8)
+    } else {
+        token
+    };
+    let start = context_offset(yaml, &error.to_string())
+        .filter(|start| *start < index)
+        .unwrap_or(index);
+    Some(start..index + token)
+}
+
+/// Returns the offset of the context mark in a deserializer error message.
+fn context_offset(yaml: &str, message: &str) -> Option<usize> {
+    let (_, context) = message.rsplit_once(", while ")?;
+    let (_, mark) = context.rsplit_once(" at line ")?;
+    let (line, column) = mark.split_once(" column ")?;
+    let line: usize = line.parse().ok()?;
+    let column: usize = column.parse().ok()?;
+    let line_start: usize = yaml
+        .split_inclusive('\n')
+        .take(line.checked_sub(1)?)
+        .map(str::len)
+        .sum();
+    let column: usize = yaml
+        .get(line_start..)?
+        .chars()
+        .take(column.checked_sub(1)?)
+        .map(char::len_utf8)
+        .sum();
+    Some(line_start + column)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn span_text(yaml: &str) -> &str {
+        let error = serde_yaml_ng::from_str::<serde_yaml_ng::Value>(yaml).unwrap_err();
+        &yaml[error_span(yaml, &error).unwrap()]
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let yaml = "a: 1\n\"a\": 2\ntools:\n  - name: x\n    name: y\n  - {b: 1, c: [{d: 1, d: 2}]}\nb: 3\n";
+        let duplicates = duplicate_keys(yaml);
+        let found: Vec<(&str, &str, &str)> = duplicates
+            .iter()
+            .map(|d| {
+                (
+                    d.path.as_str(),
+                    &yaml[d.first.clone()],
+                    &yaml[d.duplicate.clone()],
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("a", "a", "\"a\""),
+                ("tools[0].name", "name", "name"),
+                ("tools[1].c[0].d", "d", "d"),
+            ]
+        );
+        assert!(duplicate_keys("a: 1\nb:\n  a: 2\n").is_empty());
+        assert!(duplicate_keys("é: 1\nb: [\n").is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_keys_uses_byte_offsets() {
+        let yaml = "é: 1\né: 2\n";
+        let duplicates = duplicate_keys(yaml);
+        assert_eq!(duplicates[0].first, 0..2);
+        assert_eq!(duplicates[0].duplicate, 6..8);
+    }
+
+    #[test]
+    fn test_error_span() {
+        assert_eq!(span_text("a: b: c\n"), ":");
+        assert_eq!(span_text("a: @x\n"), "@x");
+        assert_eq!(span_text("a: [1, 2\nb: 3\n"), "[1, 2\nb:");
+        assert_eq!(span_text("a: 'x\n"), "'x\n");
+    }
+}
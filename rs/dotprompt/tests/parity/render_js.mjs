@@ -0,0 +1,76 @@
+/**
+ * Copyright 2026 Google LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+/**
+ * Renders every case of a spec file with the JS reference implementation
+ * and prints the results as JSON, for the Rust spec parity check.
+ *
+ * Usage: node render_js.mjs SPEC_FILE
+ *
+ * The JS package must be built first (`pnpm -C js build`). Set
+ * DOTPROMPT_JS_DIR to use a checkout other than this repository's `js`.
+ *
+ * Cases are set up the same way as in `js/test/spec.test.ts`.
+ */
+
+import { readFileSync } from 'node:fs';
+import { createRequire } from 'node:module';
+import { dirname, join, resolve } from 'node:path';
+import { fileURLToPath } from 'node:url';
+
+const jsDir = resolve(
+  process.env.DOTPROMPT_JS_DIR ??
+    join(dirname(fileURLToPath(import.meta.url)), '..', '..', '..', '..', 'js')
+);
+const require = createRequire(join(jsDir, 'package.json'));
+const { Dotprompt } = require(jsDir);
+const { parse } = require('yaml');
+
+const [specFile] = process.argv.slice(2);
+if (!specFile) {
+  console.error('usage: node render_js.mjs SPEC_FILE');
+  process.exit(2);
+}
+
+const suites = parse(readFileSync(specFile, 'utf-8'));
+const output = [];
+for (const s of suites) {
+  const cases = [];
+  for (const tc of s.tests ?? []) {
+    const env = new Dotprompt({
+      schemas: s.schemas,
+      tools: s.tools,
+      partialResolver: (name) => s.resolverPartials?.[name] || null,
+    });
+    for (const [name, template] of Object.entries(s.partials ?? {})) {
+      env.definePartial(name, template);
+    }
+    try {
+      const { raw, ...result } = await env.render(
+        tc.template ?? s.template,
+        { ...s.data, ...tc.data },
+        tc.options
+      );
+      cases.push({ desc: tc.desc, result });
+    } catch (e) {
+      cases.push({ desc: tc.desc, error: String(e?.message ?? e) });
+    }
+  }
+  output.push({ name: s.name, cases });
+}
+process.stdout.write(`${JSON.stringify(output, null, 2)}\n`);
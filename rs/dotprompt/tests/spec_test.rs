@@ -17,14 +17,23 @@
 //! Spec-based tests for dotprompt.
 //!
 //! This test runner reads YAML spec files and executes test cases defined
-//! within them, comparing rendered output against expected results.
+//! within them, comparing rendered output against expected results. It uses
+//! `libtest-mimic`, so every spec case is its own test, named
+//! `<spec file>::<group>::<case>`, and the usual test flags apply: cases run
+//! in parallel, and a filter runs only the matching ones.
 //!
 //! # Spec File Discovery
 //!
 //! The test runner discovers spec files in the following order:
 //! 1. **Environment variable**: `SPEC_FILE` (used by Bazel)
-//! 2. **Command line argument**: `--spec-file <path>` (for manual runs)
-//! 3. **Directory scan**: Scans `../spec/` for all YAML files (fallback)
+//! 2. **Directory scan**: Scans `../spec/` for all YAML files (fallback)
+//!
+//! A directory scan also runs the specs under `tests/spec/`, which cover
+//! features only this implementation has, such as the array helpers. They
+//! are not part of the parity comparison.
+//!
+//! Under Bazel, the cases are split across shards when the target sets
+//! `shard_count`.
 //!
 //! # Examples
 //!
@@ -32,29 +41,55 @@
 //! # Run all specs (directory scan)
 //! cargo test -p dotprompt --test spec_test
 //!
-//! # Run specific spec via CLI
-//! cargo test -p dotprompt --test spec_test -- --spec-file spec/helpers.yaml
+//! # Run the cases of one spec file, or of one group
+//! cargo test -p dotprompt --test spec_test history::
+//! cargo test -p dotprompt --test spec_test helpers::table::table_columns
 //!
 //! # Run via Bazel (sets SPEC_FILE env var)
 //! bazel test //rs/dotprompt:SpecTest_helpers
 //! ```
+//!
+//! # Parity With the JS Reference
+//!
+//! Setting `DOTPROMPT_PARITY` also renders every spec case the way the JS
+//! spec runner does and compares the whole result, not just the expected
+//! fields, against the JS implementation's output:
+//!
+//! `DOTPROMPT_PARITY=js` runs `tests/parity/render_js.mjs` with Node (build
+//! the JS package first with `pnpm -C js build`).
+//!
+//! Results are compared as canonical JSON (sorted keys, without `raw` and
+//! without the empty `config`, `ext`, and `metadata` objects JS always
+//! includes). The run reports a parity percentage and fails below
+//! `DOTPROMPT_PARITY_MIN` (default 100).
+//!
+//! The comparison is the `parity` test, which is ignored unless
+//! `DOTPROMPT_PARITY` is set:
+//!
+//! ```bash
+//! DOTPROMPT_PARITY=js cargo test -p dotprompt --test spec_test parity
+//! ```
 
 #![allow(clippy::expect_used)]
 #![allow(clippy::unwrap_used)]
 #![allow(clippy::uninlined_format_args)]
-#![allow(clippy::items_after_test_module)]
 #![allow(clippy::collapsible_if)]
 #![allow(clippy::too_many_lines)]
 #![allow(clippy::assigning_clones)]
-#![allow(clippy::panic)]
 #![allow(clippy::expect_fun_call)]
 
-use dotprompt::{DataArgument, Dotprompt, DotpromptOptions, Message, RenderedPrompt};
+use dotprompt::{
+    DataArgument, Dotprompt, DotpromptOptions, Message, Part, PartialResolver, PromptMetadata,
+    RenderedPrompt, ToolDefinition, TracedPrompt,
+};
+use libtest_mimic::{Arguments, Failed, Trial};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::sync::Arc;
 
 /// A group of related tests.
 #[derive(Debug, Deserialize)]
@@ -62,10 +97,6 @@ struct TestGroup {
     /// Name of the test group.
     name: String,
 
-    /// Optional description.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
-
     /// Template source for this group.
     #[serde(skip_serializing_if = "Option::is_none")]
     template: Option<String>,
@@ -78,6 +109,14 @@ struct TestGroup {
     #[serde(default, rename = "resolverPartials")]
     resolver_partials: HashMap<String, String>,
 
+    /// Schemas registered for this group.
+    #[serde(default)]
+    schemas: HashMap<String, serde_json::Value>,
+
+    /// Tools registered for this group.
+    #[serde(default)]
+    tools: HashMap<String, ToolDefinition>,
+
     /// Group-level data (e.g., shared messages for history tests).
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<serde_json::Value>,
@@ -128,19 +167,25 @@ struct ExpectedOutput {
     /// Expected error (if test should fail).
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+
+    /// Other expected fields of the rendered prompt, such as the expanded
+    /// `input` and `output` schemas.
+    #[serde(flatten)]
+    fields: HashMap<String, serde_json::Value>,
 }
 
 /// Default spec directory relative to the test binary location.
 const DEFAULT_SPEC_DIR: &str = "spec";
 
-/// Discovers spec files using three-tier approach:
+/// Directory of specs for Rust-only features, relative to the crate.
+const RUST_SPEC_DIR: &str = "tests/spec";
+
+/// Discovers spec files using two-tier approach:
 /// 1. Environment variable `SPEC_FILE` (for Bazel)
-/// 2. Command line argument `--spec-file <path>` (for manual runs)
-/// 3. Directory scan of `spec/` directory (fallback)
+/// 2. Directory scan of `spec/` directory (fallback)
 fn get_spec_files() -> Vec<PathBuf> {
     // Tier 1: Check SPEC_FILE environment variable (Bazel)
     if let Ok(spec_file) = env::var("SPEC_FILE") {
-        println!("Using SPEC_FILE from environment: {}", spec_file);
         return vec![PathBuf::from(spec_file)];
     }
 
@@ -154,7 +199,6 @@ fn get_spec_files() -> Vec<PathBuf> {
 
     for spec_dir in &possible_spec_dirs {
         if spec_dir.exists() && spec_dir.is_dir() {
-            println!("Scanning spec directory: {}", spec_dir.display());
             let files = scan_spec_directory(spec_dir);
             if !files.is_empty() {
                 return files;
@@ -162,10 +206,18 @@ fn get_spec_files() -> Vec<PathBuf> {
         }
     }
 
-    println!("No spec files found. Set SPEC_FILE env var or run from repo root.");
     vec![]
 }
 
+/// Returns the specs for Rust-only features, unless a single spec file was
+/// requested.
+fn get_rust_spec_files() -> Vec<PathBuf> {
+    if env::var("SPEC_FILE").is_ok() {
+        return vec![];
+    }
+    scan_spec_directory(&Path::new(env!("CARGO_MANIFEST_DIR")).join(RUST_SPEC_DIR))
+}
+
 /// Recursively scans a directory for YAML spec files.
 fn scan_spec_directory(dir: &Path) -> Vec<PathBuf> {
     let mut files = Vec::new();
@@ -186,141 +238,104 @@ fn scan_spec_directory(dir: &Path) -> Vec<PathBuf> {
     files
 }
 
-/// Runs tests for a single spec file.
-fn run_spec_file(spec_file_path: &Path) -> (usize, usize, Vec<(String, String)>) {
-    let spec_content = fs::read_to_string(spec_file_path).unwrap_or_else(|e| {
-        panic!(
-            "Failed to read spec file {}: {}",
-            spec_file_path.display(),
-            e
-        )
-    });
-
-    let groups: Vec<TestGroup> = serde_yaml::from_str(&spec_content).unwrap_or_else(|e| {
-        panic!(
-            "Failed to parse spec file {}: {}",
-            spec_file_path.display(),
-            e
-        )
-    });
-
-    println!("\nRunning spec: {}", spec_file_path.display());
-
-    let mut total_tests = 0;
-    let mut passed_tests = 0;
-    let mut failed_tests = Vec::new();
+/// Returns the test name prefix for a spec file: its path relative to the
+/// spec directory, without the extension, with `::` between directories.
+fn spec_id(spec_file: &Path) -> String {
+    spec_relative(spec_file)
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("::")
+}
 
-    // Run each test group
-    for group in &groups {
-        println!("\n=== Test Group: {} ===", group.name);
-        if let Some(desc) = &group.description {
-            println!("Description: {}", desc);
-        }
+/// Creates a test for every case in a spec file, or one failing test if the
+/// file cannot be loaded.
+fn spec_file_trials(spec_file: &Path) -> Vec<Trial> {
+    let id = spec_id(spec_file);
+    let groups: Vec<TestGroup> = match fs::read_to_string(spec_file)
+        .map_err(|e| format!("Failed to read spec file {}: {}", spec_file.display(), e))
+        .and_then(|content| {
+            serde_yaml_ng::from_str(&content)
+                .map_err(|e| format!("Failed to parse spec file {}: {}", spec_file.display(), e))
+        }) {
+        Ok(groups) => groups,
+        Err(e) => return vec![Trial::test(id, move || Err(e.into()))],
+    };
 
-        for case in &group.cases {
-            total_tests += 1;
+    let mut trials = Vec::new();
+    for group in groups {
+        let group = Arc::new(group);
+        let mut names = HashSet::new();
+        for index in 0..group.cases.len() {
+            let case = &group.cases[index];
             let case_name = case
                 .name
                 .as_deref()
                 .or(case.description.as_deref())
                 .unwrap_or("unnamed");
-            let test_name = format!("{} > {}", group.name, case_name);
-
-            // Get template source (case-specific or group default)
-            let template = case
-                .template
-                .as_ref()
-                .or(group.template.as_ref())
-                .expect(&format!("No template found for test: {}", test_name));
-
-            // Run test with group for partials
-            match run_single_test(&test_name, template, case, group) {
-                Ok(()) => {
-                    println!("  ✓ {}", case_name);
-                    passed_tests += 1;
-                }
-                Err(e) => {
-                    println!("  ✗ {}: {}", case_name, e);
-                    failed_tests.push((test_name.clone(), e));
-                }
+            let mut name = format!("{}::{}::{}", id, group.name, case_name);
+            if !names.insert(name.clone()) {
+                name = format!("{} #{}", name, index + 1);
             }
-        }
-    }
 
-    // Summary
-    println!("\n=== Test Summary ===");
-    println!(
-        "Total: {}, Passed: {}, Failed: {}",
-        total_tests,
-        passed_tests,
-        failed_tests.len()
-    );
-
-    if !failed_tests.is_empty() {
-        println!("\nFailed tests:");
-        for (name, error) in &failed_tests {
-            println!("  - {}: {}", name, error);
+            let group = Arc::clone(&group);
+            trials.push(Trial::test(name, move || {
+                let case = &group.cases[index];
+                let template = case
+                    .template
+                    .as_ref()
+                    .or(group.template.as_ref())
+                    .ok_or("No template found for test")?;
+                run_single_test(template, case, &group).map_err(Failed::from)
+            }));
         }
     }
-
-    (total_tests, passed_tests, failed_tests)
+    trials
 }
 
-#[test]
-fn run_spec_tests() {
-    let spec_files = get_spec_files();
-
-    if spec_files.is_empty() {
-        println!("No spec files found. Skipping tests.");
-        println!("To run specs, either:");
-        println!("  - Set SPEC_FILE environment variable");
-        println!("  - Pass --spec-file <path> argument");
-        println!("  - Run from a directory containing the 'spec/' folder");
-        return;
-    }
-
-    println!("Found {} spec file(s)", spec_files.len());
-
-    let mut grand_total = 0;
-    let mut grand_passed = 0;
-    let mut all_failed: Vec<(String, String)> = Vec::new();
-
-    for spec_file in &spec_files {
-        let (total, passed, failed) = run_spec_file(spec_file);
-        grand_total += total;
-        grand_passed += passed;
-        all_failed.extend(failed);
+/// Keeps the trials of this shard when Bazel splits a test across
+/// `shard_count` shards.
+fn shard(trials: Vec<Trial>) -> Vec<Trial> {
+    let shard = |name: &str| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+    };
+    let (Some(total), Some(index)) = (shard("TEST_TOTAL_SHARDS"), shard("TEST_SHARD_INDEX")) else {
+        return trials;
+    };
+    // Tells Bazel that this runner supports sharding.
+    if let Ok(status_file) = env::var("TEST_SHARD_STATUS_FILE") {
+        let _ = fs::write(status_file, "");
     }
+    trials
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| i % total.max(1) == index)
+        .map(|(_, trial)| trial)
+        .collect()
+}
 
-    // Grand summary
-    println!("\n========================================");
-    println!(
-        "GRAND TOTAL: {} tests, {} passed, {} failed",
-        grand_total,
-        grand_passed,
-        all_failed.len()
+fn main() -> ExitCode {
+    let args = Arguments::from_args();
+
+    let mut spec_files = get_spec_files();
+    spec_files.extend(get_rust_spec_files());
+    let mut trials: Vec<Trial> = spec_files
+        .iter()
+        .flat_map(|spec_file| spec_file_trials(spec_file))
+        .collect();
+    trials.push(
+        Trial::test("parity", run_parity_checks)
+            .with_ignored_flag(env::var("DOTPROMPT_PARITY").is_err()),
     );
-    println!("========================================");
 
-    if !all_failed.is_empty() {
-        println!("\nAll failed tests:");
-        for (name, error) in &all_failed {
-            println!("  - {}: {}", name, error);
-        }
-        panic!(
-            "{} test(s) failed across {} spec file(s)",
-            all_failed.len(),
-            spec_files.len()
-        );
-    }
+    libtest_mimic::run(&args, shard(trials)).exit_code()
 }
 
-fn run_single_test(
-    _test_name: &str,
-    template: &str,
-    case: &TestCase,
-    group: &TestGroup,
-) -> Result<(), String> {
+/// Renders one spec case and compares the result with its expectations.
+fn run_single_test(template: &str, case: &TestCase, group: &TestGroup) -> Result<(), String> {
     // Create Dotprompt instance with partials from group
     let mut all_partials = HashMap::new();
     all_partials.extend(group.partials.clone());
@@ -328,6 +343,8 @@ fn run_single_test(
 
     let options = DotpromptOptions {
         partials: Some(all_partials),
+        schemas: Some(group.schemas.clone()),
+        tools: Some(group.tools.clone()),
         ..Default::default()
     };
     let dotprompt = Dotprompt::new(Some(options));
@@ -335,17 +352,13 @@ fn run_single_test(
     // Prepare data - extract from nested structure
     let mut data = DataArgument::default();
 
-    // First, extract default values from options if present
-    let mut input_defaults: serde_json::Map<String, serde_json::Value> = serde_json::Map::new();
-    if let Some(opts) = &case.options {
-        if let Some(input_opts) = opts.get("input") {
-            if let Some(defaults) = input_opts.get("default") {
-                if let Some(defaults_obj) = defaults.as_object() {
-                    input_defaults = defaults_obj.clone();
-                }
-            }
-        }
-    }
+    // Options are passed to render, which applies their input defaults
+    let options: Option<PromptMetadata> = case
+        .options
+        .clone()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| format!("Invalid options: {}", e))?;
 
     // Merge group data with case data (case data takes precedence)
     let merged_data: Option<serde_json::Value> = match (&group.data, &case.data) {
@@ -370,13 +383,7 @@ fn run_single_test(
         // Test data can be structured as { input: {...}, messages: [...] }
         // or directly as input values
         if let Some(input) = test_data.get("input") {
-            // Merge defaults with input (input overrides defaults)
-            if let Some(input_obj) = input.as_object() {
-                for (k, v) in input_obj {
-                    input_defaults.insert(k.clone(), v.clone());
-                }
-            }
-            data.input = Some(serde_json::Value::Object(input_defaults));
+            data.input = Some(input.clone());
         } else {
             // If no "input" key, treat entire data as input
             data.input = Some(test_data.clone());
@@ -402,7 +409,7 @@ fn run_single_test(
     }
 
     // Render template
-    let result: Result<RenderedPrompt, _> = dotprompt.render(template, &data, None);
+    let result: Result<RenderedPrompt, _> = dotprompt.render(template, &data, options.clone());
 
     // Check if error was expected
     if let Some(expected_error) = &case.expect.error {
@@ -445,6 +452,60 @@ fn run_single_test(
         }
     }
 
+    // A traced render must produce the same messages, and its segments must
+    // cover each traced part exactly
+    let traced: TracedPrompt = dotprompt
+        .render_traced(template, &data, options.clone())
+        .map_err(|e| format!("Traced rendering failed: {}", e))?;
+    let traced_messages = serde_json::to_value(&traced.rendered.messages)
+        .map_err(|e| format!("Failed to serialize traced messages: {}", e))?;
+    let actual_messages = serde_json::to_value(&rendered.messages)
+        .map_err(|e| format!("Failed to serialize messages: {}", e))?;
+    if traced_messages != actual_messages {
+        return Err(format!(
+            "Traced message mismatch:\nExpected: {}\nActual: {}",
+            serde_json::to_string_pretty(&actual_messages).unwrap(),
+            serde_json::to_string_pretty(&traced_messages).unwrap()
+        ));
+    }
+    for trace in &traced.parts {
+        let text: String = trace.segments.iter().map(|s| s.text.as_str()).collect();
+        let part = &traced.rendered.messages[trace.message].content[trace.part];
+        if !matches!(part, Part::Text(t) if t.text == text) {
+            return Err(format!("Trace segments do not cover part: {:?}", trace));
+        }
+    }
+
+    // Verify top-level fields such as expanded schemas, which render() and
+    // render_metadata() must both return. The parser does not populate `raw`
+    // or `ext`, as in the parity comparison.
+    if !case.expect.fields.is_empty() {
+        let metadata_only: PromptMetadata = dotprompt
+            .render_metadata(template, options.clone())
+            .map_err(|e| format!("render_metadata failed: {}", e))?;
+        for (source, metadata) in [
+            ("render", serde_json::to_value(&rendered.metadata)),
+            ("render_metadata", serde_json::to_value(&metadata_only)),
+        ] {
+            let metadata = metadata.map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+            for (key, expected_value) in &case.expect.fields {
+                if matches!(key.as_str(), "raw" | "ext") {
+                    continue;
+                }
+                let actual_value = metadata.get(key).unwrap_or(&serde_json::Value::Null);
+                if actual_value != expected_value {
+                    return Err(format!(
+                        "Mismatch in {} result for field '{}':\nExpected: {}\nActual: {}",
+                        source,
+                        key,
+                        serde_json::to_string_pretty(expected_value).unwrap(),
+                        serde_json::to_string_pretty(actual_value).unwrap()
+                    ));
+                }
+            }
+        }
+    }
+
     // Verify metadata if specified
     if let Some(expected_metadata) = &case.expect.metadata {
         // Check RenderedPrompt metadata (from render())
@@ -468,7 +529,7 @@ fn run_single_test(
 
         // Check explicit render_metadata() call (parity with JS runner)
         let metadata_only: dotprompt::PromptMetadata<serde_json::Value> = dotprompt
-            .render_metadata(template, None)
+            .render_metadata(template, options)
             .map_err(|e| format!("render_metadata failed: {}", e))?;
 
         let actual_metadata_only = serde_json::to_value(&metadata_only)
@@ -492,3 +553,231 @@ fn run_single_test(
 
     Ok(())
 }
+
+/// Reference output for one spec file, as written by `render_js.mjs`.
+#[derive(Debug, Deserialize)]
+struct ReferenceGroup {
+    cases: Vec<ReferenceCase>,
+}
+
+/// Reference output for one spec case.
+#[derive(Debug, Deserialize)]
+struct ReferenceCase {
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Resolves partials from a spec group's `resolverPartials`.
+struct MapResolver(HashMap<String, String>);
+
+impl PartialResolver for MapResolver {
+    fn resolve(&self, name: &str) -> dotprompt::Result<Option<String>> {
+        Ok(self.0.get(name).cloned())
+    }
+}
+
+/// Returns a spec file's path relative to the `spec` directory.
+fn spec_relative(spec_file: &Path) -> PathBuf {
+    let components: Vec<Component> = spec_file.components().collect();
+    let start = components
+        .iter()
+        .rposition(|c| c.as_os_str() == DEFAULT_SPEC_DIR)
+        .map_or(0, |i| i + 1);
+    components[start..].iter().collect()
+}
+
+/// Renders a spec file with the JS implementation.
+fn reference_output(spec_file: &Path) -> Result<Vec<ReferenceGroup>, String> {
+    let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/parity/render_js.mjs");
+    let output = Command::new("node")
+        .arg(&script)
+        .arg(spec_file)
+        .output()
+        .map_err(|e| format!("Failed to run node: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "render_js.mjs failed for {}: {}",
+            spec_file.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Invalid reference output: {}", e))
+}
+
+/// Renders a case with the same inputs the JS spec runner uses.
+fn render_like_js(
+    template: &str,
+    case: &TestCase,
+    group: &TestGroup,
+) -> Result<serde_json::Value, String> {
+    let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+        partials: Some(group.partials.clone()),
+        schemas: Some(group.schemas.clone()),
+        tools: Some(group.tools.clone()),
+        partial_resolver: Some(Box::new(MapResolver(group.resolver_partials.clone()))),
+        ..Default::default()
+    }));
+
+    // { ...s.data, ...tc.data }
+    let mut data = serde_json::Map::new();
+    for layer in [&group.data, &case.data].into_iter().flatten() {
+        if let Some(fields) = layer.as_object() {
+            data.extend(fields.clone());
+        }
+    }
+    let data: DataArgument = serde_json::from_value(serde_json::Value::Object(data))
+        .map_err(|e| format!("Invalid data: {}", e))?;
+    let options: Option<PromptMetadata> = case
+        .options
+        .clone()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| format!("Invalid options: {}", e))?;
+
+    dotprompt
+        .resolve_partials(template)
+        .map_err(|e| e.to_string())?;
+    let rendered: RenderedPrompt = dotprompt
+        .render(template, &data, options)
+        .map_err(|e| e.to_string())?;
+    serde_json::to_value(&rendered).map_err(|e| e.to_string())
+}
+
+/// Serializes a render result canonically for comparison.
+fn canonical(result: &serde_json::Value) -> String {
+    fn sort(value: &serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(fields) => {
+                let sorted: std::collections::BTreeMap<_, _> =
+                    fields.iter().map(|(k, v)| (k.clone(), sort(v))).collect();
+                serde_json::Value::Object(sorted.into_iter().collect())
+            }
+            serde_json::Value::Array(items) => {
+                serde_json::Value::Array(items.iter().map(sort).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    let mut result = sort(result);
+    if let Some(fields) = result.as_object_mut() {
+        fields.remove("raw");
+        for key in ["config", "ext", "metadata"] {
+            if fields
+                .get(key)
+                .is_some_and(|v| v.as_object().is_some_and(serde_json::Map::is_empty))
+            {
+                fields.remove(key);
+            }
+        }
+    }
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Compares every case of a spec file with the reference output, returning
+/// the number of cases, the number matching, and the mismatches.
+fn check_parity(spec_file: &Path) -> (usize, usize, Vec<(String, String)>) {
+    let relative = spec_relative(spec_file);
+    let groups: Vec<TestGroup> =
+        serde_yaml_ng::from_str(&fs::read_to_string(spec_file).unwrap()).unwrap();
+    let total = groups.iter().map(|g| g.cases.len()).sum();
+    let reference = match reference_output(spec_file) {
+        Ok(reference) => reference,
+        Err(e) => return (total, 0, vec![(relative.display().to_string(), e)]),
+    };
+
+    let mut mismatches = Vec::new();
+    for (group_index, group) in groups.iter().enumerate() {
+        for (case_index, case) in group.cases.iter().enumerate() {
+            let name = format!(
+                "{} > {} > {}",
+                relative.display(),
+                group.name,
+                case.description.as_deref().unwrap_or("unnamed")
+            );
+            let Some(expected) = reference
+                .get(group_index)
+                .and_then(|g| g.cases.get(case_index))
+            else {
+                mismatches.push((name, "missing from reference output".to_string()));
+                continue;
+            };
+
+            let template = case
+                .template
+                .as_deref()
+                .or(group.template.as_deref())
+                .unwrap_or_default();
+            let actual = render_like_js(template, case, group);
+            let mismatch = match (&expected.result, &actual) {
+                (Some(js), Ok(rust)) => {
+                    let (js, rust) = (canonical(js), canonical(rust));
+                    (js != rust).then(|| format!("JS:   {}\n    Rust: {}", js, rust))
+                }
+                (None, Err(_)) => None,
+                (None, Ok(rust)) => Some(format!(
+                    "JS failed ({}), Rust rendered {}",
+                    expected.error.as_deref().unwrap_or("no result"),
+                    canonical(rust)
+                )),
+                (Some(_), Err(e)) => Some(format!("JS rendered, Rust failed: {}", e)),
+            };
+            if let Some(mismatch) = mismatch {
+                mismatches.push((name, mismatch));
+            }
+        }
+    }
+    (total, total - mismatches.len(), mismatches)
+}
+
+/// Compares every spec case with the JS reference, failing below the
+/// required parity.
+fn run_parity_checks() -> Result<(), Failed> {
+    match env::var("DOTPROMPT_PARITY").as_deref() {
+        Ok("js") => {}
+        Ok(other) => {
+            return Err(format!("DOTPROMPT_PARITY must be 'js', got '{}'", other).into());
+        }
+        Err(_) => return Ok(()),
+    }
+    let minimum: f64 = match env::var("DOTPROMPT_PARITY_MIN") {
+        Ok(min) => min
+            .parse()
+            .map_err(|_| "DOTPROMPT_PARITY_MIN must be a number")?,
+        Err(_) => 100.0,
+    };
+
+    let mut total = 0;
+    let mut matching = 0;
+    let mut mismatches = Vec::new();
+    for spec_file in get_spec_files() {
+        let (cases, matched, failed) = check_parity(&spec_file);
+        total += cases;
+        matching += matched;
+        mismatches.extend(failed);
+    }
+
+    for (name, mismatch) in &mismatches {
+        println!("  ✗ {}\n    {}", name, mismatch);
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let parity = if total == 0 {
+        100.0
+    } else {
+        matching as f64 * 100.0 / total as f64
+    };
+    println!(
+        "\nParity with JS: {}/{} cases ({:.1}%)",
+        matching, total, parity
+    );
+    if parity < minimum {
+        return Err(format!(
+            "parity {:.1}% is below the required {:.1}%",
+            parity, minimum
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -16,19 +16,30 @@
 
 //! The `check` command for linting `.prompt` files.
 
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use ariadne::{Color, Label, Report, ReportKind, Source};
+use anstream::{eprint, eprintln, println};
+use ariadne::{Color, Config as ReportConfig, IndexType, Label, Report, ReportKind, Source};
 use clap::Args;
+use clap::builder::BoolishValueParser;
+use indicatif::ProgressBar;
 use owo_colors::OwoColorize;
-use walkdir::WalkDir;
+use tracing::{debug, info};
 
-use crate::config::Config;
-use crate::formatter::{Formatter, FormatterConfig};
-use crate::linter::{Diagnostic, DiagnosticSeverity, Linter, OutputFormat};
+use crate::commands::fmt::collect_files;
+use crate::config::{Config, ConfigIssue, IssueKind};
+use crate::formatter::Formatter;
+use crate::git::{staged_content, staged_prompt_files};
+use crate::linter::{Diagnostic, DiagnosticSeverity, Linter, OutputFormat, apply_fixes};
+use crate::output;
+use crate::readability::{self, Readability};
+use crate::span::{SourceMap, Span};
 
 /// Arguments for the check command.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Args, Debug)]
 pub(crate) struct CheckArgs {
     /// Paths to check (files or directories)
@@ -36,17 +47,23 @@ pub(crate) struct CheckArgs {
     pub paths: Vec<PathBuf>,
 
     /// Output format (text or json)
-    #[arg(long, short, default_value = "text")]
+    #[arg(long, short, default_value = "text", env = "PROMPTLY_FORMAT")]
     pub format: OutputFormat,
 
     /// Treat warnings as errors
-    #[arg(long)]
+    #[arg(long, env = "PROMPTLY_LINT_STRICT", value_parser = BoolishValueParser::new())]
     pub strict: bool,
 
-    /// Automatically fix problems where possible
+    /// Automatically fix problems where possible (lint fixes and formatting)
     #[arg(long)]
     pub fix: bool,
 
+    /// Only check .prompt files staged in git (within the given paths),
+    /// reading their staged content; with --fix, the working-tree copies
+    /// are checked and fixed instead
+    #[arg(long)]
+    pub staged: bool,
+
     /// Allow (disable) specific rules (can be repeated)
     #[arg(long, short = 'A', value_name = "RULE")]
     pub allow: Vec<String>,
@@ -54,6 +71,53 @@ pub(crate) struct CheckArgs {
     /// Deny (enable as error) specific rules (can be repeated)
     #[arg(long, short = 'D', value_name = "RULE")]
     pub deny: Vec<String>,
+
+    /// Report readability metrics for each prompt, failing on the floors
+    /// set in [lint.readability]
+    #[arg(long)]
+    pub metrics: bool,
+
+    /// Show the rendered prompt behind diagnostics from rules that check
+    /// rendered output, using the prompt's input defaults
+    #[arg(long)]
+    pub explain_source: bool,
+}
+
+/// The config and linter for the prompts in one directory.
+struct DirSettings {
+    config: Config,
+    linter: Linter,
+}
+
+/// Loads the settings for each directory as its prompts are checked, since
+/// a nested `promptly.toml` overrides the settings above it.
+struct Settings<'a> {
+    args: &'a CheckArgs,
+    by_dir: HashMap<PathBuf, Rc<DirSettings>>,
+}
+
+impl<'a> Settings<'a> {
+    fn new(args: &'a CheckArgs) -> Self {
+        Self {
+            args,
+            by_dir: HashMap::new(),
+        }
+    }
+
+    /// Returns the settings for the prompt at `path`.
+    fn for_file(&mut self, path: &Path) -> Rc<DirSettings> {
+        let dir = fs::canonicalize(path)
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| PathBuf::from("."));
+        let args = self.args;
+        Rc::clone(self.by_dir.entry(dir).or_insert_with_key(|dir| {
+            let mut config = Config::load(dir);
+            config.merge_cli(&args.allow, &args.deny, args.strict);
+            let linter = config.linter();
+            Rc::new(DirSettings { config, linter })
+        }))
+    }
 }
 
 /// Result from processing a single file.
@@ -63,6 +127,15 @@ struct FileResult {
     diagnostics: Vec<Diagnostic>,
 }
 
+impl FileResult {
+    /// Returns readability metrics for the prompt's body.
+    fn readability(&self) -> Option<Readability> {
+        let body = dotprompt::parse::extract_frontmatter_and_body(&self.source)
+            .map_or_else(|_| self.source.clone(), |(_, body)| body);
+        readability::measure(&body)
+    }
+}
+
 /// Runs the check command.
 ///
 /// # Errors
@@ -76,15 +149,28 @@ pub(crate) fn run(args: &CheckArgs) -> Result<(), String> {
     // Merge CLI flags into config (CLI takes precedence)
     config.merge_cli(&args.allow, &args.deny, args.strict);
 
-    let linter = Linter::new();
-    let results = collect_results(&linter, args, &config)?;
+    let mut settings = Settings::new(args);
+    let mut results = collect_results(args, &mut settings)?;
+    results.extend(check_config_files(&config, &settings));
+    results.extend(check_unused_tools(&results, args, &config)?);
 
-    let has_errors = output_results(&results, args, &config);
+    let metrics = args.metrics.then(|| measure_results(&results, &config));
+    let has_errors = output_results(&results, metrics.as_deref(), args);
+    let below_floor = metrics
+        .iter()
+        .flatten()
+        .any(|file| !file.violations.is_empty());
     let (error_count, warning_count) = count_diagnostics(&results);
+    info!(
+        files = results.len(),
+        errors = error_count,
+        warnings = warning_count,
+        "check finished"
+    );
 
     print_summary(error_count, warning_count);
 
-    if has_errors || (config.warnings_as_errors && warning_count > 0) {
+    if has_errors || below_floor || (config.warnings_as_errors && warning_count > 0) {
         Err("Check failed".to_string())
     } else {
         Ok(())
@@ -92,35 +178,125 @@ pub(crate) fn run(args: &CheckArgs) -> Result<(), String> {
 }
 
 /// Collects results from all paths.
-fn collect_results(
-    linter: &Linter,
-    args: &CheckArgs,
-    config: &Config,
-) -> Result<Vec<FileResult>, String> {
-    let mut results = Vec::new();
+fn collect_results(args: &CheckArgs, settings: &mut Settings) -> Result<Vec<FileResult>, String> {
+    let files = if args.staged {
+        staged_prompt_files(&args.paths)?
+    } else {
+        collect_files(&args.paths)?
+    };
 
-    for path in &args.paths {
-        if path.is_file() {
-            if is_prompt_file(path) {
-                results.push(process_file(linter, path, args.fix, config)?);
-            }
-        } else if path.is_dir() {
-            for entry in WalkDir::new(path)
-                .follow_links(true)
-                .into_iter()
-                .filter_map(Result::ok)
-            {
-                let entry_path = entry.path();
-                if entry_path.is_file() && is_prompt_file(entry_path) {
-                    results.push(process_file(linter, entry_path, args.fix, config)?);
-                }
-            }
-        } else {
-            return Err(format!("Path does not exist: {}", path.display()));
+    let progress = output::progress(files.len());
+    let mut results = Vec::with_capacity(files.len());
+    for path in &files {
+        progress.set_message(path.display().to_string());
+        results.push(process_file(
+            &settings.for_file(path),
+            path,
+            args.fix,
+            args.staged && !args.fix,
+            &progress,
+        )?);
+        progress.inc(1);
+    }
+    progress.finish_and_clear();
+    Ok(results)
+}
+
+/// Reports the problems in the `promptly.toml` files that apply to the
+/// checked prompts, with one result per file.
+fn check_config_files(config: &Config, settings: &Settings) -> Vec<FileResult> {
+    let mut issues: Vec<&ConfigIssue> = Vec::new();
+    let loaded = settings.by_dir.values().map(|dir| &dir.config);
+    for issue in std::iter::once(config)
+        .chain(loaded)
+        .flat_map(|c| &c.issues)
+    {
+        if !issues.contains(&issue) {
+            issues.push(issue);
         }
     }
+    issues.sort_by_key(|issue| (&issue.path, issue.span.as_ref().map(|span| span.start)));
+
+    let mut results: Vec<FileResult> = Vec::new();
+    for issue in issues {
+        if results
+            .last()
+            .is_none_or(|result| result.path != issue.path)
+        {
+            results.push(FileResult {
+                path: issue.path.clone(),
+                source: fs::read_to_string(&issue.path).unwrap_or_default(),
+                diagnostics: Vec::new(),
+            });
+        }
+        let Some(result) = results.last_mut() else {
+            continue;
+        };
+        let mut diag = match issue.kind {
+            IssueKind::UnknownKey => Diagnostic::warning(
+                "unknown-config-key",
+                format!("Unknown key '{}', which is ignored", issue.key),
+            ),
+            IssueKind::InvalidValue => Diagnostic::error(
+                "invalid-config",
+                format!("Invalid value for '{}': {}", issue.key, issue.message),
+            ),
+            IssueKind::Unreadable => Diagnostic::error(
+                "invalid-config",
+                format!("Ignoring promptly.toml: {}", issue.message),
+            ),
+        };
+        if let Some(help) = &issue.help {
+            diag = diag.with_help(help.clone());
+        }
+        if let Some(span) = &issue.span {
+            diag = diag.with_span(Span::from_offsets(&result.source, span.start, span.end));
+        }
+        result.diagnostics.push(diag);
+    }
+    for result in &mut results {
+        result.diagnostics = config.apply_rules(std::mem::take(&mut result.diagnostics));
+    }
+    results.retain(|result| !result.diagnostics.is_empty());
+    results
+}
 
-    Ok(results)
+/// Reports the tools in the project's tool catalog that none of the checked
+/// prompts use.
+///
+/// Only meaningful when every prompt is checked, so it runs only when a
+/// checked directory contains the catalog, and never with `--staged`.
+fn check_unused_tools(
+    results: &[FileResult],
+    args: &CheckArgs,
+    config: &Config,
+) -> Result<Option<FileResult>, String> {
+    let Some((path, _)) = &config.tools else {
+        return Ok(None);
+    };
+    let catalog_dir = path.parent().and_then(|dir| dir.canonicalize().ok());
+    let covers_project = catalog_dir.is_some_and(|catalog_dir| {
+        args.paths
+            .iter()
+            .filter(|p| p.is_dir())
+            .filter_map(|p| p.canonicalize().ok())
+            .any(|p| catalog_dir.starts_with(p))
+    });
+    if args.staged || !covers_project {
+        return Ok(None);
+    }
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let used: HashSet<String> = results
+        .iter()
+        .flat_map(|result| Linter::prompt_tools(&result.source))
+        .collect();
+    let diagnostics = config.apply_rules(Linter::unused_tools(&source, &used));
+    Ok((!diagnostics.is_empty()).then(|| FileResult {
+        path: path.clone(),
+        source,
+        diagnostics,
+    }))
 }
 
 /// Checks if a path is a .prompt file.
@@ -129,31 +305,42 @@ fn is_prompt_file(path: &Path) -> bool {
 }
 
 /// Processes a single file and returns the result.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
 fn process_file(
-    linter: &Linter,
+    settings: &DirSettings,
     path: &Path,
     fix: bool,
-    config: &Config,
+    staged: bool,
+    progress: &ProgressBar,
 ) -> Result<FileResult, String> {
-    let source = fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
-
-    let all_diagnostics = linter.lint(&source, Some(path));
+    let DirSettings { config, linter } = settings;
+    let mut source = if staged {
+        staged_content(path)?
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+    };
 
-    // Filter diagnostics based on config (skip allowed rules)
-    let diagnostics: Vec<Diagnostic> = all_diagnostics
-        .into_iter()
-        .filter(|d| !config.is_allowed(&d.code))
-        .collect();
+    // Apply the configured rule levels, dropping rules that are off
+    let lint = |source: &str| config.apply_rules(linter.lint(source, Some(path)));
+    let mut diagnostics = lint(&source);
+    debug!(count = diagnostics.len(), "linted file");
 
-    // If --fix is enabled and there are formatting issues, apply formatting
+    // If --fix is enabled, apply lint fixes and formatting, then lint the result
     if fix {
-        let fmt = Formatter::new(FormatterConfig::default());
-        if fmt.needs_formatting(&source) {
-            let result = fmt.format(&source);
-            fs::write(path, &result)
+        let (mut fixed, applied) = apply_fixes(&source, &diagnostics);
+        let fmt = Formatter::new(config.formatter_config());
+        if fmt.needs_formatting(&fixed) {
+            fixed = fmt.format(&fixed);
+        }
+        if fixed != source {
+            fs::write(path, &fixed)
                 .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
-            eprintln!("{}: {}", "Fixed".green().bold(), path.display());
+            if !output::quiet() {
+                progress.suspend(|| eprintln!("{}: {}", "Fixed".green().bold(), path.display()));
+            }
+            debug!(fixes = applied, "applied fixes");
+            source = fixed;
+            diagnostics = lint(&source);
         }
     }
 
@@ -165,45 +352,65 @@ fn process_file(
 }
 
 /// Outputs results and returns whether there are errors.
-fn output_results(results: &[FileResult], args: &CheckArgs, config: &Config) -> bool {
+///
+/// With metrics, JSON output is an object holding both the diagnostics and
+/// the metrics.
+fn output_results(
+    results: &[FileResult],
+    metrics: Option<&[FileMetrics<'_>]>,
+    args: &CheckArgs,
+) -> bool {
     match args.format {
         OutputFormat::Text => {
+            // Quiet runs only show errors; the summary still counts warnings
+            let quiet = output::quiet();
             for result in results {
                 for diag in &result.diagnostics {
-                    // Check if denied rule should be promoted to error
-                    let effective_diag = if config.is_denied(&diag.code) {
-                        Diagnostic {
-                            severity: DiagnosticSeverity::Error,
-                            ..diag.clone()
-                        }
-                    } else {
-                        diag.clone()
-                    };
-                    print_diagnostic_rich(&result.path, &result.source, &effective_diag);
+                    if quiet && diag.severity != DiagnosticSeverity::Error {
+                        continue;
+                    }
+                    print_diagnostic_rich(&result.path, &result.source, diag);
+                    if let Some(rendered) = diag.rendered.as_ref().filter(|_| args.explain_source) {
+                        print_rendered(rendered);
+                    }
                 }
             }
+            if let Some(metrics) = metrics {
+                print_metrics(metrics);
+            }
         }
         OutputFormat::Json => {
             let output: Vec<_> = results
                 .iter()
                 .flat_map(|r| {
                     r.diagnostics.iter().map(move |d| {
-                        let severity = if config.is_denied(&d.code) {
-                            "error"
-                        } else {
-                            &format!("{:?}", d.severity).to_lowercase()
-                        };
                         serde_json::json!({
                             "file": r.path.display().to_string(),
                             "code": d.code,
-                            "severity": severity,
+                            "severity": format!("{:?}", d.severity).to_lowercase(),
                             "message": d.message,
                             "line": d.span.as_ref().map(|s| s.start.line),
                             "column": d.span.as_ref().map(|s| s.start.column),
+                            "fix": d.fix,
+                            "rendered": d.rendered.as_ref().filter(|_| args.explain_source),
                         })
                     })
                 })
                 .collect();
+            let output = match metrics {
+                Some(metrics) => serde_json::json!({
+                    "diagnostics": output,
+                    "metrics": metrics
+                        .iter()
+                        .map(|file| serde_json::json!({
+                            "file": file.path.display().to_string(),
+                            "metrics": file.metrics,
+                            "violations": file.violations,
+                        }))
+                        .collect::<Vec<_>>(),
+                }),
+                None => serde_json::Value::from(output),
+            };
             println!(
                 "{}",
                 serde_json::to_string_pretty(&output).unwrap_or_default()
@@ -211,11 +418,55 @@ fn output_results(results: &[FileResult], args: &CheckArgs, config: &Config) ->
         }
     }
 
-    // Calculate has_errors - include denied rules as errors
     results
         .iter()
         .flat_map(|r| &r.diagnostics)
-        .any(|d| d.severity == DiagnosticSeverity::Error || config.is_denied(&d.code))
+        .any(|d| d.severity == DiagnosticSeverity::Error)
+}
+
+/// Readability metrics for a prompt and the floors it falls short of.
+struct FileMetrics<'a> {
+    path: &'a Path,
+    metrics: Readability,
+    violations: Vec<String>,
+}
+
+/// Measures the prompts that have prose.
+fn measure_results<'a>(results: &'a [FileResult], config: &Config) -> Vec<FileMetrics<'a>> {
+    results
+        .iter()
+        .filter(|result| is_prompt_file(&result.path))
+        .filter_map(|result| {
+            let metrics = result.readability()?;
+            Some(FileMetrics {
+                path: &result.path,
+                violations: metrics.violations(&config.readability),
+                metrics,
+            })
+        })
+        .collect()
+}
+
+/// Prints readability metrics for each prompt.
+fn print_metrics(metrics: &[FileMetrics<'_>]) {
+    for file in metrics {
+        let m = &file.metrics;
+        println!(
+            "{}: Flesch {:.1}, {} sentence(s), {:.1} words/sentence, {} passive",
+            file.path.display(),
+            m.flesch_reading_ease,
+            m.sentences,
+            m.avg_sentence_length,
+            m.passive_sentences
+        );
+        for violation in &file.violations {
+            eprintln!(
+                "{}: {}: {violation}",
+                "error".red().bold(),
+                file.path.display()
+            );
+        }
+    }
 }
 
 /// Counts errors and warnings in results.
@@ -250,6 +501,19 @@ fn print_summary(error_count: usize, warning_count: usize) {
     }
 }
 
+/// Returns the offset of the character after the one at `offset`.
+fn next_char(source: &str, offset: usize) -> usize {
+    offset + source[offset..].chars().next().map_or(1, char::len_utf8)
+}
+
+/// Prints the rendered excerpt attached to a diagnostic.
+fn print_rendered(rendered: &str) {
+    eprintln!("  {} rendered with input defaults:", "note:".cyan().bold());
+    for line in rendered.lines() {
+        eprintln!("  {} {line}", "│".dimmed());
+    }
+}
+
 /// Prints a diagnostic with rich formatting using ariadne.
 fn print_diagnostic_rich(path: &Path, source: &str, diag: &Diagnostic) {
     let filename = path.display().to_string();
@@ -264,11 +528,11 @@ fn print_diagnostic_rich(path: &Path, source: &str, diag: &Diagnostic) {
     // For diagnostics with a span, show line context
     // For file-level diagnostics (no span), just show the message
     if let Some(span) = &diag.span {
-        let start =
-            line_col_to_offset(source, span.start.line as usize, span.start.column as usize);
-        let end = line_col_to_offset(source, span.end.line as usize, span.end.column as usize);
+        let map = SourceMap::new(source);
+        let start = map.offset(span.start.line, span.start.column);
+        let end = map.offset(span.end.line, span.end.column);
         // Ensure we have at least 1 character span
-        let end = if end <= start { start + 1 } else { end };
+        let end = end.max(next_char(source, start));
         // Clamp to source length
         let (start_offset, end_offset) = (start, end.min(source.len()));
 
@@ -277,6 +541,7 @@ fn print_diagnostic_rich(path: &Path, source: &str, diag: &Diagnostic) {
             kind,
             (filename.clone(), start_offset..end_offset),
         )
+        .with_config(ReportConfig::default().with_index_type(IndexType::Byte))
         .with_code(&diag.code)
         .with_message(&diag.message);
 
@@ -289,8 +554,20 @@ fn print_diagnostic_rich(path: &Path, source: &str, diag: &Diagnostic) {
         };
 
         builder = builder.with_label(label);
+        for related in &diag.related {
+            let start = map.offset(related.span.start.line, related.span.start.column);
+            let end = map.offset(related.span.end.line, related.span.end.column);
+            let end = end.max(next_char(source, start)).min(source.len());
+            builder = builder.with_label(
+                Label::new((filename.clone(), start..end))
+                    .with_color(Color::Blue)
+                    .with_message(&related.message),
+            );
+        }
         let report = builder.finish();
-        report.eprint((filename, Source::from(source))).ok();
+        report
+            .write((filename, Source::from(source)), anstream::stderr())
+            .ok();
     } else {
         // File-level diagnostic: no line context, just message and help
         let prefix = match diag.severity {
@@ -304,15 +581,3 @@ fn print_diagnostic_rich(path: &Path, source: &str, diag: &Diagnostic) {
         }
     }
 }
-
-/// Converts 1-indexed line and column to byte offset.
-fn line_col_to_offset(source: &str, line: usize, col: usize) -> usize {
-    let mut offset = 0;
-    for (i, l) in source.lines().enumerate() {
-        if i + 1 == line {
-            return offset + col.saturating_sub(1).min(l.len());
-        }
-        offset += l.len() + 1; // +1 for newline
-    }
-    offset.min(source.len())
-}
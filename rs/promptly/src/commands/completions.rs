@@ -21,6 +21,7 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
 
+use anstream::println;
 use clap::{Args, CommandFactory, Subcommand};
 use clap_complete::{Generator, Shell};
 use owo_colors::OwoColorize;
@@ -130,9 +131,7 @@ fn is_shell_installed(name: &str) -> bool {
 
 /// Check if a path is writable.
 fn is_writable(path: &std::path::Path) -> bool {
-    path.metadata()
-        .map(|m| !m.permissions().readonly())
-        .unwrap_or(false)
+    path.metadata().is_ok_and(|m| !m.permissions().readonly())
 }
 
 /// Find the first writable completion directory.
@@ -0,0 +1,299 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `config` command for creating and inspecting `promptly.toml` settings.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anstream::{eprintln, print, println};
+use clap::{Args, Subcommand};
+use owo_colors::OwoColorize;
+
+use crate::config::{ConfigFiles, ConfigIssue, IssueKind};
+use crate::linter::{ComplexityLimits, OutputFormat};
+use crate::output;
+
+/// Arguments for the config command.
+#[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct ConfigArgs {
+    /// Config action
+    #[command(subcommand)]
+    pub command: Option<ConfigCommand>,
+
+    /// Show the settings for a file or directory, merged from the
+    /// promptly.toml files that apply to it
+    #[arg(long, value_name = "PATH", default_value = ".")]
+    pub show: PathBuf,
+
+    /// Output format (text or json)
+    #[arg(long, short, default_value = "text", env = "PROMPTLY_FORMAT")]
+    pub format: OutputFormat,
+}
+
+/// Config actions.
+#[derive(Subcommand, Debug)]
+pub(crate) enum ConfigCommand {
+    /// Write a promptly.toml documenting every setting with its default
+    Init(InitArgs),
+    /// Validate the promptly.toml files that apply to a path and print the
+    /// value of every setting
+    Check(CheckArgs),
+}
+
+/// Arguments for `config init`.
+#[derive(Args, Debug)]
+pub(crate) struct InitArgs {
+    /// Directory to write promptly.toml in
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Overwrite an existing promptly.toml
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Arguments for `config check`.
+#[derive(Args, Debug)]
+pub(crate) struct CheckArgs {
+    /// File or directory whose settings to check
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Output format (text or json)
+    #[arg(long, short, default_value = "text", env = "PROMPTLY_FORMAT")]
+    pub format: OutputFormat,
+}
+
+/// Runs the config command.
+///
+/// # Errors
+///
+/// Returns an error if the path does not exist, if `init` cannot write the
+/// file, or if `check` finds problems.
+pub(crate) fn run(args: &ConfigArgs) -> Result<(), String> {
+    match &args.command {
+        Some(ConfigCommand::Init(init)) => run_init(init),
+        Some(ConfigCommand::Check(check)) => {
+            let (path, files) = discover(&check.path)?;
+            let effective = files.effective();
+            print_settings(&path, &files, &effective, check.format);
+            if files.issues.is_empty() {
+                Ok(())
+            } else {
+                Err("Config check failed".to_string())
+            }
+        }
+        None => {
+            let (path, files) = discover(&args.show)?;
+            print_settings(&path, &files, &files.merged, args.format);
+            Ok(())
+        }
+    }
+}
+
+/// Writes a commented `promptly.toml`.
+fn run_init(args: &InitArgs) -> Result<(), String> {
+    let path = args.dir.join("promptly.toml");
+    if path.exists() && !args.force {
+        return Err(format!(
+            "{} already exists; use --force to overwrite it",
+            path.display()
+        ));
+    }
+    fs::write(&path, default_config())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    if !output::quiet() {
+        eprintln!("{}: {}", "Created".green().bold(), path.display());
+    }
+    Ok(())
+}
+
+/// Returns the contents of a new `promptly.toml`: every setting, with its
+/// default value or a commented example.
+fn default_config() -> String {
+    let complexity = ComplexityLimits::default();
+    format!(
+        r#"# Settings for promptly, which apply to the prompts in this directory
+# and its subdirectories. A promptly.toml in a subdirectory overrides the
+# settings here for the prompts below it.
+
+# Stop looking for promptly.toml files in parent directories.
+root = false
+
+# Files describing the project's helpers, tools, and model prices. By
+# default, helpers.toml, tools.yaml, and pricing.toml next to this file are
+# used when present.
+# helper-manifest = "helpers.toml"
+# tool-catalog = "tools.yaml"
+# pricing-table = "pricing.toml"
+
+# Output formats the project registers, beyond json, array, enum, and text.
+# output-formats = ["xml"]
+
+[lint]
+# Rules to turn off.
+allow = []
+# Rules to report as errors.
+deny = []
+# Fail `promptly check` on warnings too.
+warnings-as-errors = false
+# Report prompts that do not name an owner.
+require-owners = false
+# Report prompts whose estimated cost per request is over this, in USD.
+# max-estimated-cost-usd = 0.05
+
+[lint.severity]
+# How to report a rule: "off", "info", "warning", or "error". Entries here
+# win over allow and deny.
+# unverified-partial = "off"
+
+[lint.complexity]
+# Limits above which a template is reported as too complex.
+max-depth = {depth}
+max-conditionals = {conditionals}
+max-partials = {partials}
+
+[lint.spelling]
+# Report common misspellings in prompt text.
+enabled = false
+# Words never reported.
+allow = []
+
+[lint.readability]
+# Floors for `promptly check --metrics`.
+# min-flesch = 40.0
+# max-sentence-length = 25.0
+
+[fmt]
+# Sort schema fields: "alphabetical" or "required-first".
+# sort-schema = "required-first"
+
+# Custom helpers the prompts use, so they are not reported as undefined
+# variables. Mark block helpers with `block = true`.
+# [[helpers]]
+# name = "card"
+# block = true
+# signature = "card title"
+# description = "Renders a card."
+"#,
+        depth = complexity.depth,
+        conditionals = complexity.conditionals,
+        partials = complexity.partials,
+    )
+}
+
+/// Finds the `promptly.toml` files that apply to a file or directory.
+fn discover(path: &Path) -> Result<(PathBuf, ConfigFiles), String> {
+    let path =
+        fs::canonicalize(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let dir = if path.is_dir() {
+        path.as_path()
+    } else {
+        path.parent().unwrap_or(&path)
+    };
+    let files = ConfigFiles::discover(dir);
+    Ok((path, files))
+}
+
+/// Prints settings with the files they came from and their problems.
+fn print_settings(path: &Path, files: &ConfigFiles, settings: &toml::Table, format: OutputFormat) {
+    match format {
+        OutputFormat::Text => {
+            for issue in &files.issues {
+                print_issue(issue);
+            }
+            if files.paths.is_empty() && files.env.is_empty() {
+                println!("# No promptly.toml applies to {}", path.display());
+            } else {
+                println!("# Settings for {}, merged from:", path.display());
+                for file in &files.paths {
+                    println!("#   {}", file.display());
+                }
+                for name in &files.env {
+                    println!("#   ${name}");
+                }
+            }
+            if !settings.is_empty() {
+                println!();
+                print!("{}", toml::to_string_pretty(settings).unwrap_or_default());
+            }
+        }
+        OutputFormat::Json => {
+            let output = serde_json::json!({
+                "path": path.display().to_string(),
+                "files": files
+                    .paths
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect::<Vec<_>>(),
+                "env": files.env,
+                "settings": settings,
+                "issues": files
+                    .issues
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output).unwrap_or_default()
+            );
+        }
+    }
+}
+
+/// Prints a problem with a `promptly.toml` file to stderr.
+fn print_issue(issue: &ConfigIssue) {
+    match issue.kind {
+        IssueKind::UnknownKey => eprintln!("{}: {issue}", "warning".yellow().bold()),
+        IssueKind::Unreadable | IssueKind::InvalidValue => {
+            eprintln!("{}: {issue}", "error".red().bold());
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let dir = TempDir::new().unwrap();
+        run_init(&InitArgs {
+            dir: dir.path().to_path_buf(),
+            force: false,
+        })
+        .unwrap();
+        assert!(
+            run_init(&InitArgs {
+                dir: dir.path().to_path_buf(),
+                force: false,
+            })
+            .is_err(),
+            "an existing file is kept"
+        );
+
+        let files = ConfigFiles::discover(dir.path());
+        assert!(files.issues.is_empty(), "{:?}", files.issues);
+        let mut defaults = ConfigFiles::default().effective();
+        defaults.remove("helpers");
+        assert_eq!(files.merged, defaults);
+    }
+}
@@ -0,0 +1,436 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `doctor` command for verifying that a tree of prompts is ready to
+//! deploy.
+//!
+//! Each check covers one stage of getting a prompt to a model: the
+//! `promptly.toml` files load, every prompt parses, its tools and schemas
+//! resolve, its partials resolve, and it renders with example input built
+//! from its input schema and defaults. With `--providers`, the providers the
+//! prompts' models belong to are checked for reachability too.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anstream::println;
+use clap::Args;
+use dotprompt::{
+    DataArgument, Dotprompt, DotpromptError, DotpromptOptions, ParsedPrompt, PromptMetadata,
+    RenderedPrompt,
+};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::commands::fmt::collect_files;
+use crate::commands::play::example_input;
+use crate::commands::render::{load_partials, load_schemas, load_tools};
+use crate::config::{ConfigFiles, IssueKind};
+use crate::linter::OutputFormat;
+use crate::output;
+
+/// Arguments for the doctor command.
+#[derive(Args, Debug)]
+pub(crate) struct DoctorArgs {
+    /// Directory of prompts to verify
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Also check that the model providers the prompts use are reachable
+    #[arg(long)]
+    pub providers: bool,
+
+    /// Seconds to wait for each provider to accept a connection
+    #[arg(long, value_name = "SECS", default_value_t = 5, requires = "providers")]
+    pub timeout: u64,
+
+    /// Output format (text or json)
+    #[arg(long, short, default_value = "text", env = "PROMPTLY_FORMAT")]
+    pub format: OutputFormat,
+}
+
+/// The outcome of a check, from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    /// The check was not run.
+    Skipped,
+    /// Nothing is wrong.
+    Ok,
+    /// Something is worth a look but does not stop a deployment.
+    Warning,
+    /// Something will fail in production.
+    Error,
+}
+
+/// One stage of the health check.
+#[derive(Debug, Serialize)]
+struct Check {
+    /// Name of the stage.
+    name: &'static str,
+    /// The outcome.
+    status: Status,
+    /// A one-line summary, such as how many prompts passed.
+    summary: String,
+    /// What went wrong.
+    problems: Vec<Problem>,
+}
+
+/// A problem found by a check.
+#[derive(Debug, Serialize)]
+struct Problem {
+    /// The file the problem is in, relative to the checked directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// What is wrong.
+    message: String,
+}
+
+/// The machine-readable health report.
+#[derive(Debug, Serialize)]
+struct Report {
+    /// The checked directory.
+    directory: String,
+    /// Whether no check failed.
+    healthy: bool,
+    /// The checks, in the order they ran.
+    checks: Vec<Check>,
+}
+
+/// Problems found in the prompts, by stage.
+#[derive(Debug, Default)]
+struct PromptStages {
+    /// Prompts whose frontmatter does not parse.
+    parse: Stage,
+    /// Prompts whose tools or schemas do not resolve.
+    schemas: Stage,
+    /// Prompts whose partials do not resolve.
+    partials: Stage,
+    /// Prompts that do not render with example input.
+    render: Stage,
+    /// Models named by the prompts.
+    models: BTreeSet<String>,
+}
+
+/// The prompts one stage checked and the problems it found.
+#[derive(Debug, Default)]
+struct Stage {
+    /// How many prompts reached the stage.
+    checked: usize,
+    /// The problems found.
+    problems: Vec<Problem>,
+}
+
+impl Stage {
+    /// Records that a prompt reached the stage, and its problem if any.
+    fn record<T>(&mut self, path: &str, result: Result<T, DotpromptError>) -> Option<T> {
+        self.checked += 1;
+        result
+            .map_err(|e| {
+                self.problems.push(Problem {
+                    path: Some(path.to_string()),
+                    message: e.to_string(),
+                });
+            })
+            .ok()
+    }
+
+    /// Turns the stage into a check.
+    fn into_check(self, name: &'static str) -> Check {
+        let passed = self.checked - self.problems.len();
+        Check {
+            name,
+            status: if self.problems.is_empty() {
+                Status::Ok
+            } else {
+                Status::Error
+            },
+            summary: format!("{passed}/{} prompt(s)", self.checked),
+            problems: self.problems,
+        }
+    }
+}
+
+/// Runs the doctor command.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read or a check fails.
+pub(crate) fn run(args: &DoctorArgs) -> Result<(), String> {
+    let dir = fs::canonicalize(&args.dir)
+        .map_err(|e| format!("Failed to read {}: {e}", args.dir.display()))?;
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", args.dir.display()));
+    }
+
+    let mut checks = vec![check_config(&dir)];
+    let files = collect_files(std::slice::from_ref(&dir))?;
+    let stages = check_prompts(&dir, &files);
+    let models = stages.models;
+    checks.extend([
+        stages.parse.into_check("parse"),
+        stages.schemas.into_check("schemas"),
+        stages.partials.into_check("partials"),
+        stages.render.into_check("render"),
+    ]);
+    checks.push(if args.providers {
+        check_providers(&models, Duration::from_secs(args.timeout))
+    } else {
+        Check {
+            name: "providers",
+            status: Status::Skipped,
+            summary: "pass --providers to check".to_string(),
+            problems: Vec::new(),
+        }
+    });
+
+    let report = Report {
+        directory: dir.display().to_string(),
+        healthy: checks.iter().all(|check| check.status < Status::Error),
+        checks,
+    };
+    match args.format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Text => print_report(&report),
+    }
+    if report.healthy {
+        Ok(())
+    } else {
+        Err("Health check failed".to_string())
+    }
+}
+
+/// Checks that the `promptly.toml` files that apply to the directory load.
+fn check_config(dir: &Path) -> Check {
+    let files = ConfigFiles::discover(dir);
+    let status = files
+        .issues
+        .iter()
+        .map(|issue| match issue.kind {
+            IssueKind::UnknownKey => Status::Warning,
+            IssueKind::Unreadable | IssueKind::InvalidValue => Status::Error,
+        })
+        .max()
+        .unwrap_or(Status::Ok);
+    let summary = if files.paths.is_empty() {
+        "no promptly.toml".to_string()
+    } else {
+        format!("{} file(s)", files.paths.len())
+    };
+    Check {
+        name: "config",
+        status,
+        summary,
+        problems: files
+            .issues
+            .iter()
+            .map(|issue| Problem {
+                path: None,
+                message: issue.to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// Takes each prompt as far through parsing, resolving, and rendering as it
+/// goes, recording where it stops.
+fn check_prompts(dir: &Path, files: &[PathBuf]) -> PromptStages {
+    let mut stages = PromptStages::default();
+    let progress = output::progress(files.len());
+    for path in files {
+        progress.inc(1);
+        // Partials are checked through the prompts that use them.
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('_'))
+        {
+            continue;
+        }
+        let label = path.strip_prefix(dir).unwrap_or(path).display().to_string();
+        progress.set_message(label.clone());
+
+        let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+            partials: Some(load_partials(path)),
+            tools: load_tools(path),
+            schema_resolver: Some(Box::new(load_schemas(path))),
+            ..Default::default()
+        }));
+        let parsed = fs::read_to_string(path)
+            .map_err(DotpromptError::from)
+            .and_then(|source| {
+                let parsed: ParsedPrompt = dotprompt.parse(&source)?;
+                Ok((source, parsed))
+            });
+        let Some((source, parsed)) = stages.parse.record(&label, parsed) else {
+            continue;
+        };
+        stages.models.extend(parsed.metadata.model);
+
+        let Some(metadata) = stages
+            .schemas
+            .record::<PromptMetadata>(&label, dotprompt.render_metadata(&source, None))
+        else {
+            continue;
+        };
+
+        let data = DataArgument {
+            input: Some(example_input(Some(metadata))),
+            ..Default::default()
+        };
+        let rendered: Result<RenderedPrompt, _> = dotprompt.render(&source, &data, None);
+        if matches!(
+            rendered,
+            Err(DotpromptError::BadPartial { .. } | DotpromptError::PartialResolutionError(_))
+        ) {
+            stages.partials.record(&label, rendered);
+            continue;
+        }
+        stages.partials.record(&label, Ok(()));
+        stages.render.record(&label, rendered);
+    }
+    progress.finish_and_clear();
+    stages
+}
+
+/// Checks that the providers of the given models accept connections.
+fn check_providers(models: &BTreeSet<String>, timeout: Duration) -> Check {
+    let mut problems = Vec::new();
+    let mut hosts = BTreeSet::new();
+    for model in models {
+        let Some((provider, _)) = model.split_once('/') else {
+            problems.push(Problem {
+                path: None,
+                message: format!("model '{model}' does not name a provider"),
+            });
+            continue;
+        };
+        match provider_host(provider) {
+            Some(host) => {
+                hosts.insert(host);
+            }
+            None => problems.push(Problem {
+                path: None,
+                message: format!("no known endpoint for provider '{provider}' (model '{model}')"),
+            }),
+        }
+    }
+    let mut status = if problems.is_empty() {
+        Status::Ok
+    } else {
+        Status::Warning
+    };
+
+    let mut reachable = 0;
+    for host in &hosts {
+        match connect(host, timeout) {
+            Ok(()) => reachable += 1,
+            Err(message) => {
+                status = Status::Error;
+                problems.push(Problem {
+                    path: None,
+                    message,
+                });
+            }
+        }
+    }
+    Check {
+        name: "providers",
+        status,
+        summary: format!("{reachable}/{} provider(s) reachable", hosts.len()),
+        problems,
+    }
+}
+
+/// Returns the API host of a model provider, by the prefix of its model
+/// names.
+fn provider_host(provider: &str) -> Option<&'static str> {
+    match provider {
+        "googleai" | "gemini" => Some("generativelanguage.googleapis.com"),
+        "vertexai" => Some("aiplatform.googleapis.com"),
+        "openai" => Some("api.openai.com"),
+        "anthropic" => Some("api.anthropic.com"),
+        _ => None,
+    }
+}
+
+/// Opens and closes a connection to port 443 of a host.
+fn connect(host: &str, timeout: Duration) -> Result<(), String> {
+    let addresses = (host, 443)
+        .to_socket_addrs()
+        .map_err(|e| format!("cannot resolve {host}: {e}"))?;
+    let mut error = format!("{host} has no addresses");
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(_) => return Ok(()),
+            Err(e) => error = format!("cannot connect to {host}: {e}"),
+        }
+    }
+    Err(error)
+}
+
+/// Prints the report as a table of checks with their problems.
+fn print_report(report: &Report) {
+    let quiet = output::quiet();
+    for check in &report.checks {
+        if quiet && matches!(check.status, Status::Ok | Status::Skipped) {
+            continue;
+        }
+        let status = match check.status {
+            Status::Skipped => format!("{:<8}", "skipped").dimmed().to_string(),
+            Status::Ok => format!("{:<8}", "ok").green().to_string(),
+            Status::Warning => format!("{:<8}", "warning").yellow().to_string(),
+            Status::Error => format!("{:<8}", "error").red().bold().to_string(),
+        };
+        println!("{status} {:<10} {}", check.name, check.summary);
+        for problem in &check.problems {
+            match &problem.path {
+                Some(path) => println!("           {}: {}", path.bold(), problem.message),
+                None => println!("           {}", problem.message),
+            }
+        }
+    }
+    if report.healthy && !quiet {
+        println!();
+        println!("{}", "Healthy.".green());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_providers_are_warnings() {
+        let models = BTreeSet::from(["gemini-2.0-flash".to_string(), "acme/m1".to_string()]);
+        let check = check_providers(&models, Duration::from_secs(1));
+        assert_eq!(check.status, Status::Warning);
+        assert_eq!(check.summary, "0/0 provider(s) reachable");
+        let messages: Vec<&str> = check.problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "no known endpoint for provider 'acme' (model 'acme/m1')",
+                "model 'gemini-2.0-flash' does not name a provider",
+            ]
+        );
+    }
+}
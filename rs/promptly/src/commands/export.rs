@@ -0,0 +1,122 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `export` command for exporting prompts to other tools.
+
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use dotprompt::adapters::to_openai_request;
+use dotprompt::{DataArgument, Dotprompt, DotpromptOptions, RenderedPrompt};
+use serde_json::{Map, Value};
+
+use super::render::{load_partials, load_schemas, load_tools};
+
+/// Arguments for the export command.
+#[derive(Args, Debug)]
+pub(crate) struct ExportArgs {
+    /// Prompt file to export
+    pub file: PathBuf,
+
+    /// Format to export to
+    #[arg(long, value_enum)]
+    pub format: ExportFormat,
+
+    /// Input JSON to render the prompt with, over its `input.default`
+    #[arg(long, short)]
+    pub input: Option<String>,
+
+    /// File to write the export to [default: stdout]
+    #[arg(long, short, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+/// A format that `export` produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ExportFormat {
+    /// An `OpenAI` playground preset: name, model, messages, and parameters.
+    OpenaiPlayground,
+}
+
+/// Runs the export command.
+///
+/// # Errors
+///
+/// Returns an error if the prompt cannot be read or rendered, the input is
+/// not valid JSON, or the output cannot be written.
+pub(crate) fn run(args: &ExportArgs) -> Result<(), String> {
+    let source = fs::read_to_string(&args.file)
+        .map_err(|e| format!("Failed to read {}: {e}", args.file.display()))?;
+    let input = args
+        .input
+        .as_deref()
+        .map(serde_json::from_str::<Value>)
+        .transpose()
+        .map_err(|e| format!("Invalid input JSON: {e}"))?;
+    let data = DataArgument {
+        input,
+        ..Default::default()
+    };
+
+    let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+        partials: Some(load_partials(&args.file)),
+        tools: load_tools(&args.file),
+        schema_resolver: Some(Box::new(load_schemas(&args.file))),
+        ..Default::default()
+    }));
+    let rendered: RenderedPrompt = dotprompt
+        .render(&source, &data, None)
+        .map_err(|e| e.to_string())?;
+
+    let name = args
+        .file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let export = match args.format {
+        ExportFormat::OpenaiPlayground => playground_preset(&rendered, &name),
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    if let Some(path) = &args.out {
+        return fs::write(path, format!("{json}\n"))
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()));
+    }
+    println!("{json}");
+    Ok(())
+}
+
+/// Builds a playground preset from the Chat Completions request for a
+/// rendered prompt: the model and messages at the top level, every other
+/// request field under `parameters`.
+fn playground_preset(rendered: &RenderedPrompt, name: &str) -> Value {
+    let Value::Object(mut request) = to_openai_request(rendered) else {
+        return Value::Null;
+    };
+    let mut preset = Map::new();
+    preset.insert("name".to_string(), name.into());
+    if let Some(description) = &rendered.metadata.description {
+        preset.insert("description".to_string(), description.as_str().into());
+    }
+    for key in ["model", "messages"] {
+        if let Some(value) = request.remove(key) {
+            preset.insert(key.to_string(), value);
+        }
+    }
+    request.remove("metadata");
+    preset.insert("parameters".to_string(), Value::Object(request));
+    Value::Object(preset)
+}
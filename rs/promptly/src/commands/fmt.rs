@@ -16,15 +16,25 @@
 
 //! The `fmt` command for formatting `.prompt` files.
 
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use clap::Args;
+use dotprompt::minify::{MinifyOptions, minify_template};
+use tracing::{debug, info};
 use walkdir::WalkDir;
 
-use crate::formatter::{Formatter, FormatterConfig};
+use crate::commands::render::load_partials;
+use crate::config::Config;
+use crate::diff::print_diff;
+use crate::formatter::{Formatter, SchemaOrder};
+use crate::git::{staged_content, staged_prompt_files};
+use crate::output;
 
 /// Arguments for the fmt command.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Args, Debug)]
 pub(crate) struct FmtArgs {
     /// Paths to format (files or directories)
@@ -38,6 +48,37 @@ pub(crate) struct FmtArgs {
     /// Show diff of changes
     #[arg(long)]
     pub diff: bool,
+
+    /// Only format .prompt files staged in git (within the given paths);
+    /// with --check or --minify, their staged content is read instead of
+    /// the working-tree copies
+    #[arg(long)]
+    pub staged: bool,
+
+    /// Print a minified copy for deployment instead of formatting in place
+    #[arg(long, conflicts_with_all = ["check", "diff"])]
+    pub minify: bool,
+
+    /// Inline partials that take no arguments when minifying
+    #[arg(long, requires = "minify")]
+    pub inline_partials: bool,
+
+    /// Write minified copies under this directory instead of printing them
+    #[arg(long, value_name = "DIR", requires = "minify")]
+    pub out_dir: Option<PathBuf>,
+
+    /// Sort the fields of input and output schemas, overriding
+    /// `sort-schema` in promptly.toml
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ORDER",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "alphabetical",
+        conflicts_with = "minify"
+    )]
+    pub sort_schema: Option<SchemaOrder>,
 }
 
 /// Result of formatting a file.
@@ -53,6 +94,38 @@ struct FormatResult {
     output: String,
 }
 
+/// Formatters built from each prompt directory's `promptly.toml`.
+struct Formatters {
+    /// Schema order from the command line, overriding the config.
+    sort_schema: Option<SchemaOrder>,
+    by_dir: HashMap<PathBuf, Rc<Formatter>>,
+}
+
+impl Formatters {
+    fn new(sort_schema: Option<SchemaOrder>) -> Self {
+        Self {
+            sort_schema,
+            by_dir: HashMap::new(),
+        }
+    }
+
+    /// Returns the formatter for the prompt at `path`.
+    fn for_file(&mut self, path: &Path) -> Rc<Formatter> {
+        let dir = fs::canonicalize(path)
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| PathBuf::from("."));
+        let sort_schema = self.sort_schema;
+        Rc::clone(self.by_dir.entry(dir).or_insert_with_key(|dir| {
+            let mut config = Config::load(dir).formatter_config();
+            if sort_schema.is_some() {
+                config.sort_schema = sort_schema;
+            }
+            Rc::new(Formatter::new(config))
+        }))
+    }
+}
+
 /// Checks if a path is a .prompt file.
 fn is_prompt_file(path: &Path) -> bool {
     path.extension().is_some_and(|ext| ext == "prompt")
@@ -64,60 +137,60 @@ fn is_prompt_file(path: &Path) -> bool {
 ///
 /// Returns an error if file reading/writing fails or if `--check` finds unformatted files.
 pub(crate) fn run(args: &FmtArgs) -> Result<(), String> {
-    let fmt = Formatter::new(FormatterConfig::default());
+    if args.minify {
+        return run_minify(args);
+    }
+
+    let mut formatters = Formatters::new(args.sort_schema);
     let mut results: Vec<FormatResult> = Vec::new();
     let mut error_count = 0;
 
-    for path in &args.paths {
-        if path.is_file() {
-            if is_prompt_file(path) {
-                match format_file(&fmt, path, args.check) {
-                    Ok(result) => results.push(result),
-                    Err(e) => {
-                        eprintln!("error: {e}");
-                        error_count += 1;
-                    }
-                }
-            }
-        } else if path.is_dir() {
-            for entry in WalkDir::new(path)
-                .follow_links(true)
-                .into_iter()
-                .filter_map(Result::ok)
-            {
-                let entry_path = entry.path();
-                if entry_path.is_file() && is_prompt_file(entry_path) {
-                    match format_file(&fmt, entry_path, args.check) {
-                        Ok(result) => results.push(result),
-                        Err(e) => {
-                            eprintln!("error: {e}");
-                            error_count += 1;
-                        }
-                    }
-                }
+    let files = if args.staged {
+        staged_prompt_files(&args.paths)?
+    } else {
+        collect_files(&args.paths)?
+    };
+    let progress = output::progress(files.len());
+    for path in &files {
+        progress.set_message(path.display().to_string());
+        match format_file(&formatters.for_file(path), path, args.check, args.staged) {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                progress.suspend(|| eprintln!("error: {e}"));
+                error_count += 1;
             }
-        } else {
-            return Err(format!("Path does not exist: {}", path.display()));
         }
+        progress.inc(1);
     }
+    progress.finish_and_clear();
 
     // Count changed files
     let changed_count = results.iter().filter(|r| r.changed).count();
     let total_count = results.len();
 
-    // Output results
+    // Output results; quiet runs keep only diffs and the summary
+    let quiet = output::quiet();
     for result in &results {
         if result.changed {
             if args.check {
-                eprintln!("Would reformat: {}", result.path.display());
+                if !quiet {
+                    eprintln!("Would reformat: {}", result.path.display());
+                }
             } else if args.diff {
                 print_diff(&result.path, &result.original, &result.output);
-            } else {
+            } else if !quiet {
                 eprintln!("Formatted: {}", result.path.display());
             }
         }
     }
 
+    info!(
+        files = total_count,
+        changed = changed_count,
+        errors = error_count,
+        "fmt finished"
+    );
+
     // Summary
     if args.check {
         if changed_count > 0 {
@@ -142,13 +215,108 @@ pub(crate) fn run(args: &FmtArgs) -> Result<(), String> {
     }
 }
 
-/// Formats a single file.
-fn format_file(fmt: &Formatter, path: &Path, check_only: bool) -> Result<FormatResult, String> {
-    let original = fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+/// Minifies prompts, printing them or writing them under `--out-dir`.
+fn run_minify(args: &FmtArgs) -> Result<(), String> {
+    let mut files = Vec::new();
+    for root in &args.paths {
+        let base = if root.is_file() {
+            root.parent().unwrap_or_else(|| Path::new(""))
+        } else {
+            root
+        };
+        let found = if args.staged {
+            staged_prompt_files(std::slice::from_ref(root))?
+        } else {
+            collect_files(std::slice::from_ref(root))?
+        };
+        files.extend(found.into_iter().map(|path| {
+            let relative = path.strip_prefix(base).unwrap_or(&path).to_path_buf();
+            (path, relative)
+        }));
+    }
+    if args.out_dir.is_none() && files.len() > 1 {
+        return Err("--minify prints a single prompt; use --out-dir for several".to_string());
+    }
+
+    for (path, relative) in &files {
+        let source = read_source(path, args.staged)?;
+        let options = MinifyOptions {
+            partials: args.inline_partials.then(|| load_partials(path)),
+        };
+        let minified =
+            minify_template(&source, &options).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let Some(out_dir) = &args.out_dir else {
+            print!("{minified}");
+            continue;
+        };
+        let target = out_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::write(&target, &minified)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        if output::quiet() {
+            continue;
+        }
+        eprintln!(
+            "Minified: {} ({} -> {} bytes)",
+            path.display(),
+            source.len(),
+            minified.len()
+        );
+    }
+    Ok(())
+}
+
+/// Collects the .prompt files at or under each path.
+pub(crate) fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            if is_prompt_file(path) {
+                files.push(path.clone());
+            }
+        } else if path.is_dir() {
+            files.extend(
+                WalkDir::new(path)
+                    .follow_links(true)
+                    .into_iter()
+                    .filter_map(Result::ok)
+                    .map(walkdir::DirEntry::into_path)
+                    .filter(|p| p.is_file() && is_prompt_file(p)),
+            );
+        } else {
+            return Err(format!("Path does not exist: {}", path.display()));
+        }
+    }
+    Ok(files)
+}
+
+/// Reads a prompt, from the git index when `staged` is set.
+fn read_source(path: &Path, staged: bool) -> Result<String, String> {
+    if staged {
+        staged_content(path)
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    }
+}
+
+/// Formats a single file. Check runs of staged files read the staged
+/// content; runs that write read the working-tree copy they overwrite.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+fn format_file(
+    fmt: &Formatter,
+    path: &Path,
+    check_only: bool,
+    staged: bool,
+) -> Result<FormatResult, String> {
+    let original = read_source(path, staged && check_only)?;
 
     let output = fmt.format(&original);
     let changed = output != original;
+    debug!(changed, "formatted file");
 
     if changed && !check_only {
         fs::write(path, &output)
@@ -162,34 +330,3 @@ fn format_file(fmt: &Formatter, path: &Path, check_only: bool) -> Result<FormatR
         output,
     })
 }
-
-/// Prints a simple diff between original and formatted content.
-fn print_diff(path: &Path, original: &str, output: &str) {
-    eprintln!("--- {}", path.display());
-    eprintln!("+++ {}", path.display());
-
-    let original_lines: Vec<&str> = original.lines().collect();
-    let output_lines: Vec<&str> = output.lines().collect();
-
-    let max_lines = original_lines.len().max(output_lines.len());
-
-    for i in 0..max_lines {
-        let orig = original_lines.get(i).copied();
-        let out = output_lines.get(i).copied();
-
-        match (orig, out) {
-            (Some(o), Some(f)) if o != f => {
-                eprintln!("-{o}");
-                eprintln!("+{f}");
-            }
-            (Some(o), None) => {
-                eprintln!("-{o}");
-            }
-            (None, Some(f)) => {
-                eprintln!("+{f}");
-            }
-            _ => {}
-        }
-    }
-    eprintln!();
-}
@@ -0,0 +1,176 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `generate-dataset` command for batch-rendering a prompt over a JSONL
+//! file of inputs.
+//!
+//! Each input line is the `input` object for one render. Each rendered
+//! prompt becomes one `{"messages": [...]}` line of the output, ready for
+//! fine-tuning and offline evaluation pipelines. Lines that fail to parse or
+//! render are skipped and recorded in a failure log instead.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anstream::eprintln;
+use clap::Args;
+use dotprompt::{DataArgument, Dotprompt, DotpromptOptions, RenderedPrompt};
+use owo_colors::OwoColorize;
+use serde_json::json;
+
+use super::render::{load_partials, load_schemas, load_tools};
+use crate::output;
+
+/// Arguments for the generate-dataset command.
+#[derive(Args, Debug)]
+pub(crate) struct GenerateDatasetArgs {
+    /// Prompt file to render
+    pub file: PathBuf,
+
+    /// JSONL file with one input object per line
+    #[arg(long, value_name = "FILE")]
+    pub inputs: PathBuf,
+
+    /// JSONL file to write the rendered messages to
+    #[arg(long, value_name = "FILE")]
+    pub out: PathBuf,
+
+    /// JSONL file to record failed lines in [default: <OUT>.failures.jsonl]
+    #[arg(long, value_name = "FILE")]
+    pub failures: Option<PathBuf>,
+}
+
+/// A line of the inputs file that could not be rendered.
+struct Failure {
+    line: usize,
+    input: Option<serde_json::Value>,
+    error: String,
+}
+
+/// Runs the generate-dataset command.
+///
+/// # Errors
+///
+/// Returns an error if the prompt or inputs cannot be read, or an output
+/// file cannot be written.
+pub(crate) fn run(args: &GenerateDatasetArgs) -> Result<(), String> {
+    let source = fs::read_to_string(&args.file)
+        .map_err(|e| format!("Failed to read {}: {e}", args.file.display()))?;
+    let inputs = fs::read_to_string(&args.inputs)
+        .map_err(|e| format!("Failed to read {}: {e}", args.inputs.display()))?;
+    let lines: Vec<(usize, &str)> = inputs
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect();
+
+    let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+        partials: Some(load_partials(&args.file)),
+        tools: load_tools(&args.file),
+        schema_resolver: Some(Box::new(load_schemas(&args.file))),
+        ..Default::default()
+    }));
+
+    let mut out = create(&args.out)?;
+    let progress = output::progress(lines.len());
+    let mut rendered = 0;
+    let mut failures = Vec::new();
+    for (line, text) in lines {
+        let input = match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(input) => input,
+            Err(e) => {
+                failures.push(Failure {
+                    line,
+                    input: None,
+                    error: format!("Invalid input JSON: {e}"),
+                });
+                progress.inc(1);
+                continue;
+            }
+        };
+        let data = DataArgument {
+            input: Some(input.clone()),
+            ..Default::default()
+        };
+        let result: dotprompt::Result<RenderedPrompt> = dotprompt.render(&source, &data, None);
+        match result {
+            Ok(prompt) => {
+                write_line(&mut out, &json!({ "messages": prompt.messages }), &args.out)?;
+                rendered += 1;
+            }
+            Err(e) => failures.push(Failure {
+                line,
+                input: Some(input),
+                error: e.to_string(),
+            }),
+        }
+        progress.inc(1);
+    }
+    progress.finish_and_clear();
+    out.flush()
+        .map_err(|e| format!("Failed to write {}: {e}", args.out.display()))?;
+
+    if !output::quiet() {
+        eprintln!(
+            "{} {rendered} rendered prompt(s) to {}",
+            "Wrote".green().bold(),
+            args.out.display()
+        );
+    }
+    if !failures.is_empty() {
+        let path = args
+            .failures
+            .clone()
+            .unwrap_or_else(|| args.out.with_extension("failures.jsonl"));
+        write_failures(&failures, &path)?;
+        eprintln!(
+            "{} {} line(s) failed; see {}",
+            "warning:".yellow().bold(),
+            failures.len(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Creates an output file.
+fn create(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("Failed to create {}: {e}", path.display()))
+}
+
+/// Writes a value as one JSONL line.
+fn write_line(out: &mut impl Write, value: &serde_json::Value, path: &Path) -> Result<(), String> {
+    writeln!(out, "{value}").map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Writes the failure log, one `{"line", "input", "error"}` object per line.
+fn write_failures(failures: &[Failure], path: &Path) -> Result<(), String> {
+    let mut out = create(path)?;
+    for failure in failures {
+        let entry = json!({
+            "line": failure.line,
+            "input": failure.input,
+            "error": failure.error,
+        });
+        write_line(&mut out, &entry, path)?;
+    }
+    out.flush()
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
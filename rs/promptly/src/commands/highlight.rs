@@ -0,0 +1,319 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `highlight` command, which generates editor syntax definitions.
+//!
+//! The definitions cover YAML frontmatter, Handlebars tags, raw blocks, and
+//! dotprompt markers. Helper names come from
+//! [`BUILTIN_HELPERS`](dotprompt::helpers::BUILTIN_HELPERS), so regenerating
+//! a definition after a release keeps the editor in step with the helpers
+//! templates can actually use.
+
+use clap::{Args, ValueEnum};
+use dotprompt::helpers::BUILTIN_HELPERS;
+use serde_json::json;
+
+/// Arguments for the highlight command.
+#[derive(Args, Debug)]
+pub(crate) struct HighlightArgs {
+    /// Editor syntax format to generate (prints to stdout)
+    #[arg(long, value_enum)]
+    pub emit: SyntaxFormat,
+}
+
+/// An editor syntax definition format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum SyntaxFormat {
+    /// A `TextMate` grammar, used by VS Code and most other editors.
+    Tmgrammar,
+    /// A Vim syntax file.
+    Vim,
+    /// An Emacs Lisp file defining `dotprompt-font-lock-keywords`.
+    Emacs,
+}
+
+/// Handlebars keywords and built-in helpers.
+const HANDLEBARS_KEYWORDS: [&str; 7] = ["if", "unless", "each", "with", "lookup", "log", "else"];
+
+/// Vim syntax file, with `@KEYWORDS@` and `@HELPERS@` placeholders.
+const VIM_TEMPLATE: &str = r#"" Vim syntax file
+" Language: Dotprompt
+" Generated by `promptly highlight --emit vim`; do not edit.
+
+if exists("b:current_syntax")
+  finish
+endif
+
+" Markers and partials
+syntax match dotpromptMarker "<<<dotprompt:[^>]\+>>>"
+syntax match dotpromptPartial "{{>.\\+}}"
+
+" License header comments (lines starting with #)
+syntax match dotpromptHeaderComment "^#.*$"
+
+" Handlebars tags, comments, and raw blocks
+syntax region dotpromptTag start="{{" end="}}" contains=dotpromptKeyword,dotpromptHelper,dotpromptString,dotpromptNumber,dotpromptBoolean
+syntax region dotpromptComment start="{{!" end="}}"
+syntax region dotpromptComment start="{{!--" end="--}}"
+syntax region dotpromptRaw start="{{{{" end="{{{{/[^}]*}}}}" keepend
+
+" Keywords and helpers inside tags
+syntax keyword dotpromptKeyword contained @KEYWORDS@
+syntax keyword dotpromptHelper contained @HELPERS@
+
+" Data types inside tags
+syntax region dotpromptString start=/"/ skip=/\\"/ end=/"/ contained
+syntax region dotpromptString start=/'/ skip=/\\'/ end=/'/ contained
+syntax match dotpromptNumber "\d\+" contained
+syntax keyword dotpromptBoolean contained true false null undefined
+
+" Highlight links
+highlight default link dotpromptHeaderComment Comment
+highlight default link dotpromptMarker PreProc
+highlight default link dotpromptPartial Structure
+highlight default link dotpromptTag Delimiter
+highlight default link dotpromptComment Comment
+highlight default link dotpromptRaw Special
+highlight default link dotpromptKeyword Keyword
+highlight default link dotpromptHelper Function
+highlight default link dotpromptString String
+highlight default link dotpromptNumber Number
+highlight default link dotpromptBoolean Boolean
+
+" YAML frontmatter, which may follow a license header
+syntax include @Yaml syntax/yaml.vim
+syntax region dotpromptFrontmatter start="^---$" end="^---$" contains=@Yaml keepend
+
+let b:current_syntax = "dotprompt"
+"#;
+
+/// Emacs Lisp file, with `@KEYWORDS@` and `@HELPERS@` placeholders holding
+/// regexp alternations.
+const EMACS_TEMPLATE: &str = r#";;; dotprompt-syntax.el --- Dotprompt syntax highlighting -*- lexical-binding: t; -*-
+
+;; Generated by `promptly highlight --emit emacs'; do not edit.
+
+;;; Code:
+
+(defvar dotprompt-font-lock-keywords
+  (list
+   ;; YAML frontmatter delimiters
+   '("^---$" . font-lock-preprocessor-face)
+
+   ;; Dotprompt markers <<<dotprompt:role:system>>> etc.
+   '("<<<dotprompt:[^>]+>>>" . font-lock-preprocessor-face)
+
+   ;; Raw blocks {{{{raw}}}} ... {{{{/raw}}}}
+   '("{{{{[^}]*}}}}\\(.\\|\n\\)*?{{{{/[^}]*}}}}" 0 font-lock-doc-face t)
+
+   ;; Handlebars comments {{!-- ... --}} and {{! ... }}
+   '("{{!--\\(.\\|\n\\)*?--}}" . font-lock-comment-face)
+   '("{{![^}]*}}" . font-lock-comment-face)
+
+   ;; Partials {{> partialName}}
+   '("{{\\(>\\)\\s-*\\([a-zA-Z0-9_.-]+\\)"
+     (1 font-lock-keyword-face)
+     (2 font-lock-builtin-face))
+
+   ;; Block tags {{#each items}} and {{/each}}
+   '("{{~?\\([#/]\\)\\([a-zA-Z][a-zA-Z0-9_-]*\\)"
+     (1 font-lock-keyword-face)
+     (2 font-lock-keyword-face))
+
+   ;; Handlebars keywords {{else}}, {{lookup obj key}}
+   '("{{~?\\s-*@KEYWORDS@\\_>" 1 font-lock-keyword-face)
+
+   ;; Dotprompt helpers {{json this indent=2}}
+   '("{{~?[#/]?\\s-*@HELPERS@\\_>" 1 font-lock-function-name-face t)
+
+   ;; Helper named parameters (param=value)
+   '("\\_<\\([a-zA-Z_][a-zA-Z0-9_]*\\)=" 1 font-lock-type-face)
+
+   ;; Quoted strings, constants, and numbers inside handlebars
+   '("{{[^}]*\\([\"'][^\"']*[\"']\\)" 1 font-lock-string-face)
+   '("{{[^}]*\\_<\\(true\\|false\\|null\\|undefined\\)\\_>" 1 font-lock-constant-face)
+   '("{{[^}]*\\_<\\([0-9]+\\)\\_>" 1 font-lock-constant-face)
+
+   ;; The handlebars delimiters themselves
+   '("\\({{\\)" 1 font-lock-constant-face)
+   '("\\(}}\\)" 1 font-lock-constant-face)
+
+   ;; License header comments (lines starting with #)
+   '("^#.*$" 0 font-lock-comment-face t))
+  "Syntax highlighting for Dotprompt templates.")
+
+(provide 'dotprompt-syntax)
+
+;;; dotprompt-syntax.el ends here
+"#;
+
+/// Runs the highlight command.
+///
+/// # Errors
+///
+/// Returns an error if the syntax definition cannot be serialized.
+pub(crate) fn run(args: &HighlightArgs) -> Result<(), String> {
+    let syntax = match args.emit {
+        SyntaxFormat::Tmgrammar => tmgrammar()?,
+        SyntaxFormat::Vim => vim(),
+        SyntaxFormat::Emacs => emacs(),
+    };
+    print!("{syntax}");
+    Ok(())
+}
+
+/// Generates a `TextMate` grammar.
+fn tmgrammar() -> Result<String, String> {
+    let alternation = |names: &[&str]| format!(r"\b({})\b", names.join("|"));
+    let grammar = json!({
+        "$schema": "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json",
+        "name": "Dotprompt",
+        "scopeName": "source.dotprompt",
+        "patterns": [
+            { "include": "#headerComments" },
+            { "include": "#frontmatter" },
+            { "include": "#markers" },
+            { "include": "#handlebars" },
+            { "include": "text.html.basic" },
+        ],
+        "repository": {
+            "headerComments": {
+                "comment": "Comments or license headers before the YAML frontmatter",
+                "patterns": [
+                    { "match": "^#!.*$", "name": "comment.line.shebang.dotprompt" },
+                    { "match": "^#.*$", "name": "comment.line.number-sign.dotprompt" },
+                ],
+            },
+            "frontmatter": {
+                "begin": r"^---\s*$",
+                "end": r"^---\s*$",
+                "beginCaptures": {
+                    "0": { "name": "punctuation.definition.frontmatter.begin.dotprompt" },
+                },
+                "endCaptures": {
+                    "0": { "name": "punctuation.definition.frontmatter.end.dotprompt" },
+                },
+                "patterns": [{ "include": "source.yaml" }],
+            },
+            "markers": {
+                "patterns": [
+                    {
+                        "match": "(<<<dotprompt:role:[a-z]+>>>)",
+                        "name": "keyword.control.directive.role.dotprompt",
+                    },
+                    {
+                        "match": "(<<<dotprompt:history>>>)",
+                        "name": "keyword.control.directive.history.dotprompt",
+                    },
+                    {
+                        "match": "(<<<dotprompt:media:.*?>>>)",
+                        "name": "keyword.control.directive.media.dotprompt",
+                    },
+                    {
+                        "match": "(<<<dotprompt:section.*?>>>)",
+                        "name": "keyword.control.directive.section.dotprompt",
+                    },
+                ],
+            },
+            "handlebars": {
+                "patterns": [
+                    {
+                        "begin": r"{{{{\s*([\w-]+)\s*}}}}",
+                        "end": r"{{{{/\s*\1\s*}}}}",
+                        "name": "markup.raw.block.dotprompt",
+                    },
+                    { "begin": "{{!--", "end": "--}}", "name": "comment.block.handlebars" },
+                    { "begin": "{{!", "end": "}}", "name": "comment.block.handlebars" },
+                    {
+                        "begin": "{{>",
+                        "end": "}}",
+                        "beginCaptures": { "0": { "name": "keyword.control.partials.handlebars" } },
+                        "endCaptures": { "0": { "name": "keyword.control.partials.handlebars" } },
+                        "contentName": "entity.name.function.partials.handlebars",
+                        "patterns": [
+                            { "match": "[\"'].*?[\"']", "name": "string.quoted.handlebars" },
+                        ],
+                    },
+                    {
+                        "begin": "{{",
+                        "end": "}}",
+                        "beginCaptures": { "0": { "name": "entity.name.tag.handlebars" } },
+                        "endCaptures": { "0": { "name": "entity.name.tag.handlebars" } },
+                        "contentName": "variable.parameter.handlebars",
+                        "patterns": [
+                            {
+                                "match": alternation(&HANDLEBARS_KEYWORDS),
+                                "name": "keyword.control.handlebars",
+                            },
+                            {
+                                "match": alternation(&BUILTIN_HELPERS),
+                                "name": "support.function.dotprompt",
+                            },
+                            { "match": "[\"'].*?[\"']", "name": "string.quoted.handlebars" },
+                            { "match": "[0-9]+", "name": "constant.numeric.handlebars" },
+                            {
+                                "match": "(true|false|null|undefined)",
+                                "name": "constant.language.handlebars",
+                            },
+                        ],
+                    },
+                ],
+            },
+        },
+    });
+    serde_json::to_string_pretty(&grammar)
+        .map(|json| json + "\n")
+        .map_err(|e| e.to_string())
+}
+
+/// Generates a Vim syntax file.
+fn vim() -> String {
+    VIM_TEMPLATE
+        .replace("@KEYWORDS@", &HANDLEBARS_KEYWORDS.join(" "))
+        .replace("@HELPERS@", &BUILTIN_HELPERS.join(" "))
+}
+
+/// Generates an Emacs Lisp file.
+fn emacs() -> String {
+    let alternation = |names: &[&str]| format!(r"\\({}\\)", names.join(r"\\|"));
+    EMACS_TEMPLATE
+        .replace("@KEYWORDS@", &alternation(&HANDLEBARS_KEYWORDS))
+        .replace("@HELPERS@", &alternation(&BUILTIN_HELPERS))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntaxes_list_every_helper() {
+        for syntax in [tmgrammar().expect("tmgrammar"), vim(), emacs()] {
+            assert!(!syntax.contains("@HELPERS@") && !syntax.contains("@KEYWORDS@"));
+            for name in BUILTIN_HELPERS.iter().chain(&HANDLEBARS_KEYWORDS) {
+                assert!(syntax.contains(name), "{name} is missing from:\n{syntax}");
+            }
+        }
+        assert!(emacs().contains(r"\\(if\\|unless\\|"));
+    }
+
+    #[test]
+    fn test_tmgrammar_is_valid_json() {
+        let grammar: serde_json::Value =
+            serde_json::from_str(&tmgrammar().expect("tmgrammar")).expect("valid JSON");
+        assert_eq!(grammar["scopeName"], "source.dotprompt");
+    }
+}
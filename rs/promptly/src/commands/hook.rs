@@ -0,0 +1,182 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `hook` command for installing git pre-commit hooks.
+
+use std::fs;
+use std::path::Path;
+
+use anstream::{eprintln, println};
+use clap::{Args, Subcommand};
+use owo_colors::OwoColorize;
+
+use crate::{git, output};
+
+/// Marker identifying hooks written by promptly.
+const MARKER: &str = "# Installed by `promptly hook install`.";
+
+/// The git pre-commit hook script.
+const HOOK_SCRIPT: &str = "#!/bin/sh
+# Installed by `promptly hook install`.
+# Checks staged .prompt files before each commit.
+set -e
+promptly fmt --check --staged
+promptly check --strict --staged
+";
+
+/// The hooks to add to a pre-commit framework config. The framework passes
+/// staged files as arguments, so `--staged` is not needed.
+const PRE_COMMIT_HOOKS: &str = r"  - repo: local
+    hooks:
+      - id: promptly-fmt
+        name: promptly fmt
+        entry: promptly fmt --check
+        language: system
+        files: \.prompt$
+      - id: promptly-check
+        name: promptly check
+        entry: promptly check --strict
+        language: system
+        files: \.prompt$
+";
+
+/// The pre-commit framework config file name.
+const PRE_COMMIT_CONFIG: &str = ".pre-commit-config.yaml";
+
+/// Arguments for the hook command.
+#[derive(Args, Debug)]
+pub(crate) struct HookArgs {
+    /// Hook action
+    #[command(subcommand)]
+    pub command: HookCommand,
+}
+
+/// Hook actions.
+#[derive(Subcommand, Debug)]
+pub(crate) enum HookCommand {
+    /// Install a pre-commit hook that formats and checks staged prompts
+    Install(InstallArgs),
+}
+
+/// Arguments for `hook install`.
+#[derive(Args, Debug)]
+pub(crate) struct InstallArgs {
+    /// Add the hooks to .pre-commit-config.yaml instead of writing a git hook
+    #[arg(long)]
+    pub pre_commit: bool,
+
+    /// Overwrite an existing pre-commit hook
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Runs the hook command.
+///
+/// # Errors
+///
+/// Returns an error if the current directory is not in a git repository or
+/// the hook cannot be written.
+pub(crate) fn run(args: &HookArgs) -> Result<(), String> {
+    match &args.command {
+        HookCommand::Install(install) if install.pre_commit => install_pre_commit_config(),
+        HookCommand::Install(install) => install_git_hook(install.force),
+    }
+}
+
+/// Writes `.git/hooks/pre-commit`.
+fn install_git_hook(force: bool) -> Result<(), String> {
+    let path = git::hook_path("pre-commit")?;
+    let foreign = fs::read_to_string(&path).is_ok_and(|existing| !existing.contains(MARKER));
+    if foreign && !force {
+        return Err(format!(
+            "{} already exists; pass --force to replace it, or --pre-commit to use the pre-commit framework",
+            path.display()
+        ));
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    fs::write(&path, HOOK_SCRIPT)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    make_executable(&path)?;
+
+    if !output::quiet() {
+        eprintln!("{}: {}", "Installed".green().bold(), path.display());
+    }
+    Ok(())
+}
+
+/// Adds the promptly hooks to `.pre-commit-config.yaml`.
+fn install_pre_commit_config() -> Result<(), String> {
+    let path = git::toplevel()?.join(PRE_COMMIT_CONFIG);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+
+    if existing.contains("id: promptly-check") {
+        if !output::quiet() {
+            eprintln!("{} already runs promptly", path.display());
+        }
+        return Ok(());
+    }
+
+    // Only a config whose last top-level key is `repos:` can be extended by
+    // appending; anything else needs a human.
+    let last_key = existing
+        .lines()
+        .rfind(|line| !line.is_empty() && !line.starts_with([' ', '\t', '#', '-']));
+    // Match the indentation of an existing `repos` list.
+    let hooks = if existing.lines().any(|line| line.starts_with("- ")) {
+        PRE_COMMIT_HOOKS.replace("\n  ", "\n").replacen("  ", "", 1)
+    } else {
+        PRE_COMMIT_HOOKS.to_string()
+    };
+    let updated = match last_key {
+        None => format!("{existing}repos:\n{hooks}"),
+        Some(key) if key.trim_end() == "repos:" => {
+            let separator = if existing.ends_with('\n') { "" } else { "\n" };
+            format!("{existing}{separator}{hooks}")
+        }
+        Some(_) => {
+            println!("{hooks}");
+            return Err(format!(
+                "Could not update {}; add the entries above to its 'repos' list",
+                path.display()
+            ));
+        }
+    };
+
+    fs::write(&path, updated).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    if !output::quiet() {
+        eprintln!("{}: {}", "Updated".green().bold(), path.display());
+    }
+    Ok(())
+}
+
+/// Marks a file as executable.
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Failed to make {} executable: {e}", path.display()))
+}
+
+/// Marks a file as executable (a no-op outside Unix).
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
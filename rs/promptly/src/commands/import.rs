@@ -0,0 +1,127 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `import` command for translating templates from other formats into
+//! `.prompt` files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anstream::eprintln;
+use clap::Args;
+use owo_colors::OwoColorize;
+
+use crate::importers::{ImportFormat, translate};
+use crate::output;
+
+/// Arguments for the import command.
+#[derive(Args, Debug)]
+pub(crate) struct ImportArgs {
+    /// Template file (or export) to import
+    pub file: PathBuf,
+
+    /// Format of the template
+    #[arg(long, value_enum)]
+    pub format: ImportFormat,
+
+    /// YAML file to use as frontmatter [default: the template's `.yaml` or
+    /// `.yml` sidecar, if present]
+    #[arg(long, value_name = "FILE")]
+    pub sidecar: Option<PathBuf>,
+
+    /// Where to write the prompt [default: the template path with a
+    /// `.prompt` extension]
+    #[arg(long, short, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Overwrite the output file if it exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Runs the import command.
+///
+/// # Errors
+///
+/// Returns an error if the template or sidecar cannot be read, or the
+/// output exists (without `--force`) or cannot be written.
+pub(crate) fn run(args: &ImportArgs) -> Result<(), String> {
+    let template = fs::read_to_string(&args.file)
+        .map_err(|e| format!("Failed to read {}: {e}", args.file.display()))?;
+    let frontmatter = match sidecar(args) {
+        Some(path) => Some(
+            fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {e}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let out = args
+        .out
+        .clone()
+        .unwrap_or_else(|| args.file.with_extension("prompt"));
+    if out.exists() && !args.force {
+        return Err(format!(
+            "{} already exists; pass --force to overwrite it",
+            out.display()
+        ));
+    }
+
+    let imported = translate(args.format, &template)?;
+    fs::write(&out, imported.to_prompt(frontmatter.as_deref()))
+        .map_err(|e| format!("Failed to write {}: {e}", out.display()))?;
+
+    for issue in &imported.issues {
+        let location = if issue.line == 0 {
+            args.file.display().to_string()
+        } else {
+            format!("{}:{}", args.file.display(), issue.line)
+        };
+        eprintln!(
+            "{}: {location}: {} {}",
+            "warning".yellow().bold(),
+            issue.message,
+            issue.construct.dimmed()
+        );
+    }
+    if !output::quiet() {
+        eprintln!(
+            "{} {} -> {}",
+            "Imported".green().bold(),
+            args.file.display(),
+            out.display()
+        );
+    }
+    if !imported.issues.is_empty() {
+        eprintln!(
+            "{} construct(s) left as comments for manual translation.",
+            imported.issues.len()
+        );
+    }
+    Ok(())
+}
+
+/// Returns the frontmatter sidecar: the explicit path, or a `.yaml` or
+/// `.yml` file next to the template.
+fn sidecar(args: &ImportArgs) -> Option<PathBuf> {
+    if let Some(path) = &args.sidecar {
+        return Some(path.clone());
+    }
+    ["yaml", "yml"]
+        .into_iter()
+        .map(|ext| args.file.with_extension(ext))
+        .find(|path| Path::is_file(path))
+}
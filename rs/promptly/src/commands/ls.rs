@@ -0,0 +1,111 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `ls` command for listing prompts in a directory.
+
+use std::path::PathBuf;
+
+use anstream::println;
+use clap::Args;
+use dotprompt::stores::{DirStore, DirStoreOptions};
+use dotprompt::{ListPromptsOptions, PromptStore};
+use owo_colors::OwoColorize;
+
+use crate::linter::OutputFormat;
+
+/// Arguments for the ls command.
+#[derive(Args, Debug)]
+pub(crate) struct LsArgs {
+    /// Directory containing the prompts
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Only list prompts carrying this tag (can be repeated; all must match)
+    #[arg(long, short, value_name = "TAG")]
+    pub tag: Vec<String>,
+
+    /// Output format (text or json)
+    #[arg(long, short, default_value = "text", env = "PROMPTLY_FORMAT")]
+    pub format: OutputFormat,
+}
+
+/// Runs the ls command.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read.
+pub(crate) fn run(args: &LsArgs) -> Result<(), String> {
+    if !args.dir.is_dir() {
+        return Err(format!("Directory does not exist: {}", args.dir.display()));
+    }
+
+    let store = DirStore::new(DirStoreOptions {
+        directory: args.dir.clone(),
+        ..Default::default()
+    });
+    let page = store
+        .list(Some(ListPromptsOptions {
+            tags: (!args.tag.is_empty()).then(|| args.tag.clone()),
+            include_metadata: true,
+            ..Default::default()
+        }))
+        .map_err(|e| e.to_string())?;
+
+    let mut prompts = page.prompts;
+    prompts.sort_by(|a, b| (&a.name, &a.variant).cmp(&(&b.name, &b.variant)));
+
+    match args.format {
+        OutputFormat::Text => {
+            for prompt in &prompts {
+                let name = prompt
+                    .variant
+                    .as_ref()
+                    .map_or_else(|| prompt.name.clone(), |v| format!("{}.{v}", prompt.name));
+                let summary = prompt.metadata.clone().unwrap_or_default();
+                let mut details = Vec::new();
+                details.extend(summary.model);
+                details.extend(summary.tags.map(|tags| tags.join(", ")));
+                if details.is_empty() {
+                    println!("{}", name.bold());
+                } else {
+                    println!("{}  {}", name.bold(), details.join("  ").dimmed());
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let output: Vec<_> = prompts
+                .iter()
+                .map(|p| {
+                    let summary = p.metadata.clone().unwrap_or_default();
+                    serde_json::json!({
+                        "name": p.name,
+                        "variant": p.variant,
+                        "version": p.version,
+                        "model": summary.model,
+                        "description": summary.description,
+                        "tags": summary.tags.unwrap_or_default(),
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output).unwrap_or_default()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -16,6 +16,8 @@
 
 //! The `lsp` command for starting the Language Server.
 
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
 use clap::Args;
 
 /// Arguments for the lsp command.
@@ -24,6 +26,23 @@ pub(crate) struct LspArgs {
     /// Use stdio for communication (default)
     #[arg(long, default_value = "true")]
     pub stdio: bool,
+
+    /// Listen for clients over TCP on this port
+    #[arg(long, value_name = "PORT", conflicts_with = "ws")]
+    pub tcp: Option<u16>,
+
+    /// Listen for clients over WebSocket on this port
+    #[arg(long, value_name = "PORT")]
+    pub ws: Option<u16>,
+
+    /// Address to bind when using --tcp or --ws
+    #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    pub host: IpAddr,
+
+    /// Print the client configuration (language registration, server
+    /// command, and semantic token legend) as JSON and exit
+    #[arg(long, conflicts_with_all = ["tcp", "ws"])]
+    pub print_client_config: bool,
 }
 
 /// Runs the LSP server.
@@ -31,14 +50,31 @@ pub(crate) struct LspArgs {
 /// # Errors
 ///
 /// Returns an error if the server fails to start.
-pub(crate) fn run(_args: &LspArgs) -> Result<(), String> {
+pub(crate) fn run(args: &LspArgs) -> Result<(), String> {
+    if args.print_client_config {
+        let config = serde_json::to_string_pretty(&crate::lsp::client_config())
+            .map_err(|e| e.to_string())?;
+        println!("{config}");
+        return Ok(());
+    }
+
     // Create a tokio runtime and run the LSP server
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| format!("Failed to create tokio runtime: {e}"))?;
 
     rt.block_on(async {
-        crate::lsp::run_server()
-            .await
-            .map_err(|e| format!("LSP server error: {e}"))
+        if let Some(port) = args.tcp {
+            crate::lsp::run_tcp_server(SocketAddr::new(args.host, port))
+                .await
+                .map_err(|e| format!("LSP server error: {e}"))
+        } else if let Some(port) = args.ws {
+            crate::lsp::run_ws_server(SocketAddr::new(args.host, port))
+                .await
+                .map_err(|e| format!("LSP server error: {e}"))
+        } else {
+            crate::lsp::run_server()
+                .await
+                .map_err(|e| format!("LSP server error: {e}"))
+        }
     })
 }
@@ -0,0 +1,211 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `migrate` command for bulk frontmatter edits across a tree, and for
+//! converting prompts written in other dialects.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anstream::eprintln;
+use clap::Args;
+use globset::{Glob, GlobMatcher};
+use owo_colors::OwoColorize;
+use tracing::{debug, info};
+use walkdir::WalkDir;
+
+use crate::dialect::Dialect;
+use crate::diff::print_diff;
+use crate::frontmatter_edit::FrontmatterEditor;
+
+/// Arguments for the migrate command.
+#[derive(Args, Debug)]
+pub(crate) struct MigrateArgs {
+    /// Directory (or file) to migrate
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Replace models matching a glob, as `FROM:TO` (e.g. `gemini-1.5*:gemini-2.0-flash`)
+    ///
+    /// A pattern without a provider also matches prefixed models, which keep
+    /// their prefix: `googleai/gemini-1.5-pro` becomes `googleai/gemini-2.0-flash`.
+    #[arg(long, value_name = "FROM:TO")]
+    pub rename_model: Vec<String>,
+
+    /// Rename a frontmatter field, as `FROM:TO` (e.g. `config.maxTokens:config.maxOutputTokens`)
+    #[arg(long, value_name = "FROM:TO")]
+    pub rename_field: Vec<String>,
+
+    /// Rewrite role syntax from another dialect into dotprompt role helpers
+    #[arg(long, value_enum, value_name = "DIALECT")]
+    pub from: Option<Dialect>,
+
+    /// Show a diff of the changes without writing them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// A model replacement rule.
+#[derive(Debug)]
+struct ModelRename {
+    from: GlobMatcher,
+    to: String,
+}
+
+impl ModelRename {
+    /// Returns the replacement for `model`, if the rule matches it.
+    fn apply(&self, model: &str) -> Option<String> {
+        if self.from.is_match(model) {
+            return Some(self.to.clone());
+        }
+        let (provider, name) = model.split_once('/')?;
+        if !self.from.is_match(name) {
+            return None;
+        }
+        if self.to.contains('/') {
+            Some(self.to.clone())
+        } else {
+            Some(format!("{provider}/{}", self.to))
+        }
+    }
+}
+
+/// Runs the migrate command.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, the path does not exist,
+/// or any file fails to migrate.
+pub(crate) fn run(args: &MigrateArgs) -> Result<(), String> {
+    if args.rename_model.is_empty() && args.rename_field.is_empty() && args.from.is_none() {
+        return Err("Nothing to do: pass --rename-model, --rename-field, or --from".to_string());
+    }
+    if !args.path.exists() {
+        return Err(format!("Path does not exist: {}", args.path.display()));
+    }
+
+    let models = args
+        .rename_model
+        .iter()
+        .map(|rule| {
+            let (from, to) = split_rule(rule)?;
+            let from = Glob::new(from)
+                .map_err(|e| format!("Invalid model pattern '{from}': {e}"))?
+                .compile_matcher();
+            Ok(ModelRename {
+                from,
+                to: to.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let fields = args
+        .rename_field
+        .iter()
+        .map(|rule| split_rule(rule))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut changed = 0;
+    let mut errors = 0;
+    let mut total = 0;
+    for entry in WalkDir::new(&args.path)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "prompt") {
+            continue;
+        }
+        total += 1;
+        match migrate_file(path, &models, &fields, args.from, args.dry_run) {
+            Ok(true) => changed += 1,
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("{}: {}: {e}", "error".red().bold(), path.display());
+                errors += 1;
+            }
+        }
+    }
+
+    info!(files = total, changed, errors, "migrate finished");
+    let verb = if args.dry_run {
+        "would be migrated"
+    } else {
+        "migrated"
+    };
+    eprintln!("{changed} file(s) {verb}, {total} file(s) checked.");
+
+    if errors > 0 {
+        Err(format!("{errors} error(s) occurred"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Applies the migration rules to one file.
+///
+/// # Returns
+///
+/// Whether the file changed.
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+fn migrate_file(
+    path: &Path,
+    models: &[ModelRename],
+    fields: &[(&str, &str)],
+    dialect: Option<Dialect>,
+    dry_run: bool,
+) -> Result<bool, String> {
+    let original =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let mut editor = FrontmatterEditor::new(&original)?;
+
+    let model = editor.get("model")?;
+    let rename = model.and_then(|m| models.iter().find_map(|r| r.apply(&m)).map(|to| (m, to)));
+    if let Some((model, to)) = rename {
+        debug!(from = %model, to = %to, "renaming model");
+        editor.set("model", &to)?;
+    }
+    for (from, to) in fields {
+        if editor.rename(from, to)? {
+            debug!(from, to, "renamed field");
+        }
+    }
+
+    let mut output = editor.into_source();
+    if let Some(dialect) = dialect {
+        debug!(?dialect, "converting role syntax");
+        output = dialect.convert(&output);
+    }
+    if output == original {
+        return Ok(false);
+    }
+    if dry_run {
+        print_diff(path, &original, &output);
+    } else {
+        fs::write(path, &output).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        if !crate::output::quiet() {
+            eprintln!("{}: {}", "Migrated".green().bold(), path.display());
+        }
+    }
+    Ok(true)
+}
+
+/// Splits a `FROM:TO` rule.
+fn split_rule(rule: &str) -> Result<(&str, &str), String> {
+    rule.split_once(':')
+        .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+        .ok_or_else(|| format!("Expected FROM:TO, got '{rule}'"))
+}
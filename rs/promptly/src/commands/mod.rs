@@ -18,5 +18,19 @@
 
 pub(crate) mod check;
 pub(crate) mod completions;
+pub(crate) mod config;
+pub(crate) mod doctor;
+pub(crate) mod export;
 pub(crate) mod fmt;
+pub(crate) mod generate_dataset;
+pub(crate) mod highlight;
+pub(crate) mod hook;
+pub(crate) mod import;
+pub(crate) mod ls;
 pub(crate) mod lsp;
+pub(crate) mod migrate;
+pub(crate) mod play;
+pub(crate) mod render;
+pub(crate) mod search;
+pub(crate) mod serve;
+pub(crate) mod set;
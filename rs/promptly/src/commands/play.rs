@@ -0,0 +1,423 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `play` command: an interactive prompt playground.
+//!
+//! Shows a prompt's template, an editable input JSON document, and the
+//! messages it renders to. The output refreshes as the input is edited and
+//! whenever the prompt file is saved, so a prompt can be iterated on from
+//! any editor in a second terminal.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use clap::Args;
+use dotprompt::{
+    DataArgument, Dotprompt, DotpromptOptions, Message, PromptMetadata, ToolDefinition,
+};
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use serde_json::{Map, Value};
+use tui_textarea::TextArea;
+
+use super::render::{load_partials, load_schemas, load_tools};
+use crate::transcript::{TranscriptLine, transcript};
+
+/// How often to check the prompt file for changes.
+const TICK: Duration = Duration::from_millis(250);
+
+/// Arguments for the play command.
+#[derive(Args, Debug)]
+pub(crate) struct PlayArgs {
+    /// Prompt file to play with
+    pub file: PathBuf,
+
+    /// Initial input JSON (defaults to an example derived from input.schema)
+    #[arg(long, short)]
+    pub input: Option<String>,
+}
+
+/// Runs the play command.
+///
+/// # Errors
+///
+/// Returns an error if the prompt cannot be read or the terminal cannot be
+/// set up.
+pub(crate) fn run(args: &PlayArgs) -> Result<(), String> {
+    let mut playground = Playground::open(&args.file, args.input.as_deref())?;
+
+    let mut terminal =
+        ratatui::try_init().map_err(|e| format!("Failed to initialize terminal: {e}"))?;
+    let result = playground.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// Playground state.
+struct Playground {
+    /// The prompt file.
+    path: PathBuf,
+    /// The prompt source as last read.
+    source: String,
+    /// Modification time of the prompt file as last read.
+    modified: Option<SystemTime>,
+    /// Partials from the prompt's directory.
+    partials: HashMap<String, String>,
+    /// Tools from the project's tool catalog.
+    tools: Option<HashMap<String, ToolDefinition>>,
+    /// The input JSON editor.
+    input: TextArea<'static>,
+    /// Whether the input has been edited since it was derived from the schema.
+    input_edited: bool,
+    /// The rendered messages, or why rendering failed.
+    output: Result<Vec<Message>, String>,
+}
+
+impl Playground {
+    /// Loads a prompt and renders it once.
+    fn open(path: &Path, input: Option<&str>) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let mut playground = Self {
+            path: path.to_path_buf(),
+            modified: modified(path),
+            partials: load_partials(path),
+            tools: load_tools(path),
+            input: TextArea::default(),
+            input_edited: input.is_some(),
+            output: Ok(Vec::new()),
+            source,
+        };
+        let input = input.map_or_else(|| playground.example_input(), str::to_string);
+        playground.set_input(&input);
+        playground.render();
+        Ok(playground)
+    }
+
+    /// Builds a renderer with the prompt directory's partials and the
+    /// project's tools.
+    fn dotprompt(&self) -> Dotprompt {
+        Dotprompt::new(Some(DotpromptOptions {
+            partials: Some(self.partials.clone()),
+            tools: self.tools.clone(),
+            schema_resolver: Some(Box::new(load_schemas(&self.path))),
+            ..Default::default()
+        }))
+    }
+
+    /// Returns example input JSON for the prompt.
+    fn example_input(&self) -> String {
+        let metadata: Option<PromptMetadata> =
+            self.dotprompt().render_metadata(&self.source, None).ok();
+        serde_json::to_string_pretty(&example_input(metadata)).unwrap_or_default()
+    }
+
+    /// Replaces the contents of the input editor.
+    fn set_input(&mut self, input: &str) {
+        let mut editor = TextArea::new(input.lines().map(str::to_string).collect());
+        editor.set_block(Block::bordered().title(" Input (JSON) "));
+        editor.set_cursor_line_style(Style::default());
+        self.input = editor;
+    }
+
+    /// Renders the prompt with the current input.
+    fn render(&mut self) {
+        self.output = self.try_render();
+    }
+
+    fn try_render(&self) -> Result<Vec<Message>, String> {
+        let text = self.input.lines().join("\n");
+        let input: Value = if text.trim().is_empty() {
+            Value::Object(Map::new())
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("Invalid input JSON: {e}"))?
+        };
+        let data = DataArgument {
+            input: Some(input),
+            ..Default::default()
+        };
+        self.dotprompt()
+            .render::<Value, Value>(&self.source, &data, None)
+            .map(|rendered| rendered.messages)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Reloads the prompt if its file has changed, returning whether it did.
+    fn reload_if_changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        let Ok(source) = fs::read_to_string(&self.path) else {
+            return false;
+        };
+        self.source = source;
+        self.partials = load_partials(&self.path);
+        self.tools = load_tools(&self.path);
+        if !self.input_edited {
+            let input = self.example_input();
+            self.set_input(&input);
+        }
+        self.render();
+        true
+    }
+
+    /// Handles a key press, returning `false` when the playground should exit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Char('c' | 'q') if ctrl => return false,
+            KeyCode::Char('r') if ctrl => {
+                let input = self.example_input();
+                self.set_input(&input);
+                self.input_edited = false;
+                self.render();
+            }
+            _ => {
+                if self.input.input(key) {
+                    self.input_edited = true;
+                    self.render();
+                }
+            }
+        }
+        true
+    }
+
+    /// Runs until the user quits.
+    fn event_loop(&mut self, terminal: &mut ratatui::DefaultTerminal) -> Result<(), String> {
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| format!("Failed to draw: {e}"))?;
+
+            if event::poll(TICK).map_err(|e| format!("Failed to read input: {e}"))? {
+                let quit = match event::read().map_err(|e| format!("Failed to read input: {e}"))? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => !self.handle_key(key),
+                    _ => false,
+                };
+                if quit {
+                    return Ok(());
+                }
+            }
+            self.reload_if_changed();
+        }
+    }
+
+    /// Draws the template, input, and output panes.
+    fn draw(&self, frame: &mut Frame<'_>) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main);
+        let [template, input] =
+            Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(left);
+
+        let title = format!(" {} ", self.path.display());
+        frame.render_widget(
+            Paragraph::new(self.source.as_str()).block(Block::bordered().title(title)),
+            template,
+        );
+        frame.render_widget(&self.input, input);
+
+        let (lines, border) = match &self.output {
+            Ok(messages) => (message_lines(messages), Style::default()),
+            Err(error) => (
+                vec![Line::styled(error.clone(), Style::default().fg(Color::Red))],
+                Style::default().fg(Color::Red),
+            ),
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(" Rendered ").border_style(border)),
+            right,
+        );
+        frame.render_widget(
+            Line::styled(
+                " Esc quit · Ctrl-R reset input · output refreshes on save",
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+            help,
+        );
+    }
+}
+
+/// Returns a file's modification time.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Builds example input for a prompt from its input schema and defaults.
+pub(crate) fn example_input(metadata: Option<PromptMetadata>) -> Value {
+    let input = metadata.and_then(|m| m.input);
+    let mut example = input
+        .as_ref()
+        .and_then(|i| i.schema.as_ref())
+        .map_or_else(|| Value::Object(Map::new()), example_value);
+    if let (Some(defaults), Value::Object(fields)) = (input.and_then(|i| i.default), &mut example) {
+        fields.extend(defaults);
+    }
+    example
+}
+
+/// Builds an example value satisfying a JSON Schema.
+fn example_value(schema: &Value) -> Value {
+    if let Some(default) = schema.get("default") {
+        return default.clone();
+    }
+    if let Some(first) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|values| values.first())
+    {
+        return first.clone();
+    }
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => Some(kind.as_str()),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null"),
+        _ => None,
+    };
+    match kind {
+        Some("object") | None if schema.get("properties").is_some() => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            Value::Object(
+                properties
+                    .into_iter()
+                    .flatten()
+                    .map(|(name, property)| (name.clone(), example_value(property)))
+                    .collect(),
+            )
+        }
+        Some("object") => Value::Object(Map::new()),
+        Some("array") => Value::Array(Vec::new()),
+        Some("string") => Value::String(String::new()),
+        Some("number" | "integer") => Value::from(0),
+        Some("boolean") => Value::Bool(false),
+        _ => Value::Null,
+    }
+}
+
+/// Formats rendered messages for display.
+fn message_lines(messages: &[Message]) -> Vec<Line<'static>> {
+    transcript(messages)
+        .into_iter()
+        .map(|line| match line {
+            TranscriptLine::Role(_) => Line::from(Span::styled(
+                line.to_string(),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            TranscriptLine::Text(text) => Line::raw(text),
+            TranscriptLine::Part(json) => Line::styled(json, Style::default().fg(Color::Yellow)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    const PROMPT: &str = "---\ninput:\n  schema:\n    name: string\n    count: integer\n  default:\n    count: 2\n---\n{{role \"system\"}}Be brief.\n{{role \"user\"}}Hi {{name}} x{{count}}";
+
+    #[test]
+    fn test_example_value() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "tags": {"type": ["array", "null"]},
+                "tone": {"enum": ["formal", "casual"]},
+                "n": {"type": "integer", "default": 3}
+            }
+        });
+        assert_eq!(
+            example_value(&schema),
+            json!({"name": "", "tags": [], "tone": "formal", "n": 3})
+        );
+    }
+
+    #[test]
+    fn test_playground_renders_and_reloads() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("greet.prompt");
+        fs::write(&path, PROMPT).unwrap();
+
+        let mut playground = Playground::open(&path, None).unwrap();
+        let input: Value = serde_json::from_str(&playground.input.lines().join("\n")).unwrap();
+        assert_eq!(input, json!({"name": "", "count": 2}));
+        let messages = playground.output.as_ref().unwrap();
+        assert_eq!(messages.len(), 2);
+
+        playground.set_input("{\"name\": \"Ada\"");
+        playground.render();
+        assert!(
+            playground
+                .output
+                .as_ref()
+                .unwrap_err()
+                .contains("Invalid input JSON")
+        );
+
+        fs::write(&path, "Bye {{name}}").unwrap();
+        playground.modified = None;
+        assert!(playground.reload_if_changed());
+        assert!(!playground.reload_if_changed());
+        let input: Value = serde_json::from_str(&playground.input.lines().join("\n")).unwrap();
+        assert_eq!(input, json!({}));
+        assert_eq!(playground.output.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_draw_shows_all_panes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("greet.prompt");
+        fs::write(&path, PROMPT).unwrap();
+        let playground =
+            Playground::open(&path, Some("{\"name\": \"Ada\", \"count\": 2}")).unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| playground.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect();
+
+        assert!(screen.contains("Input (JSON)"));
+        assert!(screen.contains("── system ──"));
+        assert!(screen.contains("Hi Ada x2"));
+    }
+}
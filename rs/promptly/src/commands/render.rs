@@ -0,0 +1,268 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `render` command for rendering a prompt from the command line.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anstream::println;
+use clap::{Args, ValueEnum};
+use dotprompt::adapters::{
+    to_anthropic_request, to_gemini_request, to_genkit_request, to_openai_request,
+};
+use dotprompt::resolvers::StoreSchemaResolver;
+use dotprompt::stores::{DirStore, DirStoreOptions};
+use dotprompt::{
+    DataArgument, Dotprompt, DotpromptOptions, Message, Part, PartTrace, PromptStore,
+    RenderedPrompt, SegmentKind, ToolDefinition, TracedPrompt,
+};
+use owo_colors::OwoColorize;
+
+use crate::config::Config;
+use crate::linter::OutputFormat;
+use crate::span::position_at_offset;
+
+/// Arguments for the render command.
+#[derive(Args, Debug)]
+pub(crate) struct RenderArgs {
+    /// Prompt file to render
+    pub file: PathBuf,
+
+    /// Input JSON
+    #[arg(long, short)]
+    pub input: Option<String>,
+
+    /// Show which template regions, partials, and blocks produced each part
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Output format (text or json)
+    #[arg(long, short, default_value = "text", env = "PROMPTLY_FORMAT")]
+    pub format: OutputFormat,
+
+    /// Print the request body for a model provider instead of the messages
+    #[arg(long, value_enum, conflicts_with = "explain")]
+    pub target: Option<Target>,
+
+    /// Print the estimated cost of sending the prompt to its model instead
+    /// of the messages
+    #[arg(long, conflicts_with_all = ["explain", "target"])]
+    pub cost: bool,
+}
+
+/// A model provider API whose request format `render` can emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Target {
+    /// `OpenAI` Chat Completions.
+    Openai,
+    /// Anthropic Messages.
+    Anthropic,
+    /// Gemini `generateContent`.
+    Gemini,
+    /// Genkit `GenerateRequest`.
+    Genkit,
+}
+
+/// Runs the render command.
+///
+/// # Errors
+///
+/// Returns an error if the prompt cannot be read or rendered, or the input is
+/// not valid JSON.
+pub(crate) fn run(args: &RenderArgs) -> Result<(), String> {
+    let source = fs::read_to_string(&args.file)
+        .map_err(|e| format!("Failed to read {}: {e}", args.file.display()))?;
+    let input = args
+        .input
+        .as_deref()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .transpose()
+        .map_err(|e| format!("Invalid input JSON: {e}"))?;
+    let data = DataArgument {
+        input,
+        ..Default::default()
+    };
+
+    let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+        partials: Some(load_partials(&args.file)),
+        tools: load_tools(&args.file),
+        schema_resolver: Some(Box::new(load_schemas(&args.file))),
+        ..Default::default()
+    }));
+    let traced: TracedPrompt = if args.explain {
+        dotprompt.render_traced(&source, &data, None)
+    } else {
+        dotprompt
+            .render(&source, &data, None)
+            .map(|rendered| TracedPrompt {
+                rendered,
+                parts: Vec::new(),
+            })
+    }
+    .map_err(|e| e.to_string())?;
+
+    if args.cost {
+        return print_cost(&traced.rendered, &args.file, args.format);
+    }
+
+    if let Some(target) = args.target {
+        let request = match target {
+            Target::Openai => to_openai_request(&traced.rendered),
+            Target::Anthropic => to_anthropic_request(&traced.rendered),
+            Target::Gemini => to_gemini_request(&traced.rendered),
+            Target::Genkit => to_genkit_request(&traced.rendered),
+        };
+        return print_json(&request);
+    }
+
+    match args.format {
+        OutputFormat::Json if args.explain => print_json(&traced),
+        OutputFormat::Json => print_json(&traced.rendered),
+        OutputFormat::Text => {
+            print_messages(&traced.rendered.messages, &traced.parts, &source);
+            Ok(())
+        }
+    }
+}
+
+/// Prints the estimated cost of a rendered prompt, priced with the project's
+/// pricing table.
+fn print_cost(rendered: &RenderedPrompt, path: &Path, format: OutputFormat) -> Result<(), String> {
+    let model = rendered
+        .metadata
+        .model
+        .as_deref()
+        .ok_or("The prompt does not specify a model")?;
+    let estimate = Config::load(prompt_dir(path))
+        .pricing_table()
+        .estimate_cost(rendered, model)
+        .ok_or_else(|| format!("No pricing for model '{model}'; add it to pricing.toml"))?;
+    match format {
+        OutputFormat::Json => print_json(&estimate),
+        OutputFormat::Text => {
+            println!("{}", estimate.model.bold());
+            println!(
+                "  input   ~{} tokens  ${:.6}",
+                estimate.input_tokens, estimate.input_cost
+            );
+            println!(
+                "  output  {} tokens  ${:.6}",
+                estimate.output_tokens, estimate.output_cost
+            );
+            println!("  total   ${:.6}", estimate.total_cost);
+            Ok(())
+        }
+    }
+}
+
+/// Loads the partials in a prompt's directory.
+pub(crate) fn load_partials(path: &Path) -> HashMap<String, String> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let store = DirStore::new(DirStoreOptions {
+        directory: dir.to_path_buf(),
+        ..Default::default()
+    });
+    let Ok(page) = store.list_partials(None) else {
+        return HashMap::new();
+    };
+    page.partials
+        .into_iter()
+        .filter_map(|partial| {
+            let data = store.load_partial(&partial.name, None).ok()?;
+            Some((partial.name, data.source))
+        })
+        .collect()
+}
+
+/// Loads the tools in the tool catalog of a prompt's project.
+pub(crate) fn load_tools(path: &Path) -> Option<HashMap<String, ToolDefinition>> {
+    Config::load(prompt_dir(path)).tool_map()
+}
+
+/// Returns a resolver for the named schemas next to a prompt.
+pub(crate) fn load_schemas(path: &Path) -> StoreSchemaResolver {
+    StoreSchemaResolver::for_prompt_dir(prompt_dir(path))
+}
+
+/// Returns the directory containing a prompt.
+fn prompt_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// Prints a value as pretty JSON.
+fn print_json(value: &impl serde::Serialize) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    println!("{json}");
+    Ok(())
+}
+
+/// Prints messages, followed by the trace of each traced part.
+fn print_messages(messages: &[Message], traces: &[PartTrace], source: &str) {
+    for (m, message) in messages.iter().enumerate() {
+        let role = serde_json::to_value(message.role)
+            .ok()
+            .and_then(|role| role.as_str().map(str::to_string))
+            .unwrap_or_default();
+        if m > 0 {
+            println!();
+        }
+        println!("{}", format!("── {role} ──").cyan().bold());
+        for (p, part) in message.content.iter().enumerate() {
+            match part {
+                Part::Text(text) => println!("{}", text.text),
+                other => println!("{}", serde_json::to_string(other).unwrap_or_default()),
+            }
+            if let Some(trace) = traces.iter().find(|t| t.message == m && t.part == p) {
+                print_trace(trace, source);
+            }
+        }
+    }
+}
+
+/// Prints one line per traced segment: source location, producing region,
+/// rendered text, and enclosing blocks.
+fn print_trace(trace: &PartTrace, source: &str) {
+    println!();
+    for segment in &trace.segments {
+        let position = position_at_offset(source, segment.span.start);
+        let origin = match &segment.kind {
+            SegmentKind::Text => "text".to_string(),
+            SegmentKind::Partial { name } => format!("partial {name}"),
+            SegmentKind::Expression => segment.template.clone(),
+        };
+        let blocks = if segment.blocks.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "  {}",
+                format!("in {}", segment.blocks.join(" > ")).dimmed()
+            )
+        };
+        println!(
+            "  {} {} {:?}{blocks}",
+            format!("{}:{}", position.line, position.column).dimmed(),
+            origin.yellow(),
+            segment.text,
+        );
+    }
+}
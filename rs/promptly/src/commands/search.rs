@@ -0,0 +1,111 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `search` command for finding prompts by frontmatter metadata.
+
+use std::path::PathBuf;
+
+use anstream::{eprintln, println};
+use clap::Args;
+use dotprompt::PromptStore;
+use dotprompt::search::{PromptQuery, PromptSearchResult};
+use dotprompt::stores::{DirStore, DirStoreOptions};
+use owo_colors::OwoColorize;
+
+use crate::linter::OutputFormat;
+
+/// Arguments for the search command.
+#[derive(Args, Debug)]
+pub(crate) struct SearchArgs {
+    /// Search query, e.g. `model:gemini* tools:search weather`
+    ///
+    /// Fields: `model:`, `tool:`/`tools:`, `name:`, `variant:`, `description:`.
+    /// Bare words match the description. All terms must match.
+    #[arg(default_value = "")]
+    pub query: String,
+
+    /// Directory containing the prompts
+    #[arg(long, short, default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Output format (text or json)
+    #[arg(long, short, default_value = "text", env = "PROMPTLY_FORMAT")]
+    pub format: OutputFormat,
+}
+
+/// Runs the search command.
+///
+/// # Errors
+///
+/// Returns an error if the query is invalid or the directory cannot be read.
+pub(crate) fn run(args: &SearchArgs) -> Result<(), String> {
+    if !args.dir.is_dir() {
+        return Err(format!("Directory does not exist: {}", args.dir.display()));
+    }
+
+    let query = PromptQuery::parse(&args.query).map_err(|e| e.to_string())?;
+    let store = DirStore::new(DirStoreOptions {
+        directory: args.dir.clone(),
+        ..Default::default()
+    });
+    let results = store.search(&query).map_err(|e| e.to_string())?;
+
+    match args.format {
+        OutputFormat::Text => print_text(&results),
+        OutputFormat::Json => {
+            let output: Vec<_> = results
+                .iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "name": r.prompt_ref.name,
+                        "variant": r.prompt_ref.variant,
+                        "model": r.metadata.model,
+                        "tools": r.metadata.tools,
+                        "description": r.metadata.description,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output).unwrap_or_default()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints one line per matching prompt.
+fn print_text(results: &[PromptSearchResult]) {
+    if results.is_empty() {
+        eprintln!("No prompts matched");
+        return;
+    }
+
+    for result in results {
+        let name = result.prompt_ref.variant.as_ref().map_or_else(
+            || result.prompt_ref.name.clone(),
+            |v| format!("{}.{v}", result.prompt_ref.name),
+        );
+        let model = result.metadata.model.as_deref().unwrap_or("-");
+        match &result.metadata.description {
+            Some(description) => {
+                println!("{}  {}  {description}", name.bold(), model.dimmed());
+            }
+            None => println!("{}  {}", name.bold(), model.dimmed()),
+        }
+    }
+}
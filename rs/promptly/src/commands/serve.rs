@@ -0,0 +1,68 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `serve` command for running the prompt rendering service.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+use clap::Args;
+
+/// Arguments for the serve command.
+#[derive(Args, Debug)]
+pub(crate) struct ServeArgs {
+    /// Directory containing the prompts
+    #[arg(long, default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// Address to bind
+    #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    pub host: IpAddr,
+
+    /// Also serve the gRPC API (promptly.v1.PromptService) on this port
+    #[arg(long, value_name = "PORT")]
+    pub grpc_port: Option<u16>,
+
+    /// Reload prompts from disk on every request
+    #[arg(long)]
+    pub watch: bool,
+}
+
+/// Runs the rendering service.
+///
+/// # Errors
+///
+/// Returns an error if the directory does not exist or the server fails.
+pub(crate) fn run(args: &ServeArgs) -> Result<(), String> {
+    if !args.dir.is_dir() {
+        return Err(format!("Directory does not exist: {}", args.dir.display()));
+    }
+
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| format!("Failed to create tokio runtime: {e}"))?;
+
+    rt.block_on(crate::server::run(
+        args.dir.clone(),
+        SocketAddr::new(args.host, args.port),
+        args.grpc_port.map(|port| SocketAddr::new(args.host, port)),
+        args.watch,
+    ))
+    .map_err(|e| format!("Server error: {e}"))
+}
@@ -0,0 +1,90 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `set` command for editing frontmatter fields.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anstream::{eprintln, print};
+use clap::Args;
+use owo_colors::OwoColorize;
+
+use crate::frontmatter_edit::FrontmatterEditor;
+use crate::output;
+
+/// Arguments for the set command.
+#[derive(Args, Debug)]
+pub(crate) struct SetArgs {
+    /// The .prompt file to edit
+    pub file: PathBuf,
+
+    /// Fields to set, as `KEY=VALUE` (nested keys use dots, e.g. `config.temperature=0.2`)
+    #[arg(value_name = "KEY=VALUE")]
+    pub assignments: Vec<String>,
+
+    /// Fields to remove (can be repeated)
+    #[arg(long, value_name = "KEY")]
+    pub unset: Vec<String>,
+
+    /// Print the updated file instead of writing it
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Runs the set command.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or written, an assignment is
+/// malformed, or an edit fails.
+pub(crate) fn run(args: &SetArgs) -> Result<(), String> {
+    if args.assignments.is_empty() && args.unset.is_empty() {
+        return Err("Nothing to do: pass KEY=VALUE assignments or --unset KEY".to_string());
+    }
+
+    let source = fs::read_to_string(&args.file)
+        .map_err(|e| format!("Failed to read {}: {e}", args.file.display()))?;
+    let mut editor =
+        FrontmatterEditor::new(&source).map_err(|e| format!("{}: {e}", args.file.display()))?;
+
+    for assignment in &args.assignments {
+        let (key, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("Expected KEY=VALUE, got '{assignment}'"))?;
+        editor
+            .set(key.trim(), value)
+            .map_err(|e| format!("Failed to set '{key}': {e}"))?;
+    }
+    for key in &args.unset {
+        editor
+            .remove(key)
+            .map_err(|e| format!("Failed to unset '{key}': {e}"))?;
+    }
+
+    let updated = editor.into_source();
+    if args.dry_run {
+        print!("{updated}");
+    } else if updated != source {
+        fs::write(&args.file, &updated)
+            .map_err(|e| format!("Failed to write {}: {e}", args.file.display()))?;
+        if !output::quiet() {
+            eprintln!("{}: {}", "Updated".green().bold(), args.file.display());
+        }
+    }
+
+    Ok(())
+}
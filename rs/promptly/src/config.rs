@@ -17,28 +17,124 @@
 //! Configuration management for promptly.
 //!
 //! This module handles loading and merging configuration from:
-//! 1. `promptly.toml` files (searched in current and parent directories)
-//! 2. CLI flags (which override config file settings)
+//! 1. `promptly.toml` files (searched in current and parent directories,
+//!    with nearer files overriding the settings of those above them)
+//! 2. An optional `helpers.toml` helper manifest next to `promptly.toml`
+//! 3. An optional `tools.yaml` tool catalog next to `promptly.toml`
+//! 4. An optional `pricing.toml` pricing table next to `promptly.toml`
+//! 5. `PROMPTLY_*` environment variables (which override config file
+//!    settings, see [`ConfigFiles::discover`])
+//! 6. CLI flags (which override config file settings and the environment)
+//!
+//! A `promptly.toml` that sets `root = true` stops the search, so the files
+//! above it do not apply.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use dotprompt::cost::PricingTable;
+use dotprompt::{HelperManifest, ToolCatalog, ToolDefinition};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
 
-use serde::Deserialize;
+use crate::formatter::{FormatterConfig, SchemaOrder};
+use crate::helpers::HelperDecl;
+use crate::linter::{ComplexityLimits, CostBudget, Diagnostic, DiagnosticSeverity, Linter};
+use crate::readability::ReadabilityConfig;
+use crate::spelling::SpellingConfig;
 
 /// The name of the configuration file.
 const CONFIG_FILE_NAME: &str = "promptly.toml";
 
+/// Keys holding paths relative to the file that sets them.
+const PATH_KEYS: [&str; 3] = ["helper-manifest", "tool-catalog", "pricing-table"];
+
+/// The tables in `promptly.toml` and the keys each accepts. The keys of
+/// tables not listed, such as `[lint.severity]`, are not checked.
+const SCHEMA: &[(&str, &[&str])] = &[
+    (
+        "",
+        &[
+            "root",
+            "lint",
+            "fmt",
+            "helpers",
+            "helper-manifest",
+            "tool-catalog",
+            "pricing-table",
+            "output-formats",
+        ],
+    ),
+    (
+        "lint",
+        &[
+            "allow",
+            "deny",
+            "warnings-as-errors",
+            "severity",
+            "ignore",
+            "require-owners",
+            "complexity",
+            "spelling",
+            "readability",
+            "max-estimated-cost-usd",
+        ],
+    ),
+    (
+        "lint.complexity",
+        &["max-depth", "max-conditionals", "max-partials"],
+    ),
+    ("lint.spelling", &["enabled", "allow"]),
+    ("lint.readability", &["min-flesch", "max-sentence-length"]),
+    ("fmt", &["sort-schema"]),
+    ("helpers", &["name", "block", "signature", "description"]),
+];
+
 /// Root configuration structure matching the TOML file format.
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default)]
 struct TomlConfig {
+    /// Whether to stop looking for `promptly.toml` in parent directories.
+    #[serde(default)]
+    root: bool,
+
     /// Lint configuration section.
     #[serde(default)]
     lint: LintTomlConfig,
+
+    /// Formatting configuration section.
+    #[serde(default)]
+    fmt: FmtTomlConfig,
+
+    /// Custom helpers used by the project's prompts.
+    #[serde(default)]
+    helpers: Vec<HelperDecl>,
+
+    /// Path to a helper manifest, relative to the config file.
+    #[serde(default, rename = "helper-manifest")]
+    helper_manifest: Option<PathBuf>,
+
+    /// Path to a tool catalog, relative to the config file.
+    #[serde(default, rename = "tool-catalog")]
+    tool_catalog: Option<PathBuf>,
+
+    /// Path to a pricing table, relative to the config file.
+    #[serde(default, rename = "pricing-table")]
+    pricing_table: Option<PathBuf>,
+
+    /// Custom output formats the project registers.
+    #[serde(
+        default,
+        rename = "output-formats",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    output_formats: Vec<String>,
 }
 
 /// Lint section of the TOML configuration.
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default)]
 struct LintTomlConfig {
     /// Rules to allow (disable).
     #[serde(default)]
@@ -52,9 +148,564 @@ struct LintTomlConfig {
     #[serde(default, rename = "warnings-as-errors")]
     warnings_as_errors: bool,
 
-    /// File patterns to ignore.
+    /// Severity overrides by rule.
     #[serde(default)]
+    severity: BTreeMap<String, RuleLevel>,
+
+    /// File patterns to ignore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     ignore: Vec<String>,
+
+    /// Require every prompt to have an owner.
+    #[serde(default, rename = "require-owners")]
+    require_owners: bool,
+
+    /// Thresholds for `template-too-complex`.
+    #[serde(default)]
+    complexity: ComplexityLimits,
+
+    /// Settings for the opt-in `spelling` pass.
+    #[serde(default)]
+    spelling: SpellingConfig,
+
+    /// Floors for `check --metrics`.
+    #[serde(default)]
+    readability: ReadabilityConfig,
+
+    /// Ceiling for `cost-over-budget`, in USD per request.
+    #[serde(default, rename = "max-estimated-cost-usd")]
+    max_estimated_cost_usd: Option<f64>,
+}
+
+/// How a rule's diagnostics are reported, as set in `[lint.severity]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RuleLevel {
+    /// The rule is disabled.
+    Off,
+    /// Diagnostics are reported as information.
+    Info,
+    /// Diagnostics are reported as warnings.
+    #[serde(alias = "warn")]
+    Warning,
+    /// Diagnostics are reported as errors.
+    Error,
+}
+
+/// Fmt section of the TOML configuration.
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct FmtTomlConfig {
+    /// How to sort schema fields, if at all.
+    #[serde(default, rename = "sort-schema")]
+    sort_schema: Option<SchemaOrder>,
+}
+
+/// What is wrong with a setting in `promptly.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IssueKind {
+    /// The file could not be read or is not valid TOML, so none of it applies.
+    Unreadable,
+    /// The key is not a setting, so it is ignored.
+    UnknownKey,
+    /// The value has the wrong type or is out of range, so it is ignored.
+    InvalidValue,
+}
+
+/// A problem with a `promptly.toml` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConfigIssue {
+    /// The file.
+    pub path: PathBuf,
+    /// What is wrong.
+    pub kind: IssueKind,
+    /// The setting, such as `lint.warnings-as-errors`, or empty for the
+    /// whole file.
+    pub key: String,
+    /// A description of the problem.
+    pub message: String,
+    /// A suggested fix.
+    pub help: Option<String>,
+    /// Byte range of the problem in the file, when it can be found.
+    pub span: Option<Range<usize>>,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path.display())?;
+        if !self.key.is_empty() {
+            write!(f, "{}: ", self.key)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(help) = &self.help {
+            write!(f, ". {help}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The `promptly.toml` files that apply to a directory, merged.
+#[derive(Debug, Default)]
+pub(crate) struct ConfigFiles {
+    /// The files, from the outermost to the nearest.
+    pub paths: Vec<PathBuf>,
+    /// The environment variables that override settings in the files.
+    pub env: Vec<String>,
+    /// Problems found in the files and environment variables. The settings they affect are left out
+    /// of `merged`.
+    pub issues: Vec<ConfigIssue>,
+    /// Their settings, with nearer files winning.
+    ///
+    /// Tables merge key by key. Lists are combined, with the nearer file's
+    /// entries first, except that a rule the nearer file allows, denies, or
+    /// gives a severity is dropped from the outer files' rule settings.
+    /// Relative paths are made absolute.
+    pub merged: toml::Table,
+}
+
+impl ConfigFiles {
+    /// Finds the `promptly.toml` files in `start_dir` and its parents and
+    /// merges them.
+    ///
+    /// The search stops after a file that sets `root = true`. Files that
+    /// cannot be read or parsed are skipped, and unknown keys and invalid
+    /// values are left out; each is reported in `issues`.
+    ///
+    /// Environment variables override the files' settings. Each setting has
+    /// one named after its key, such as `PROMPTLY_LINT_WARNINGS_AS_ERRORS`
+    /// or `PROMPTLY_LINT_COMPLEXITY_MAX_DEPTH`, and
+    /// `PROMPTLY_LINT_SEVERITY_<RULE>` sets a rule's level. Values are read
+    /// as TOML, falling back to a string. Lists may be written as
+    /// comma-separated names, and switches as `1` or `0`.
+    #[must_use]
+    pub(crate) fn discover(start_dir: &Path) -> Self {
+        Self::discover_with_env(start_dir, std::env::vars())
+    }
+
+    /// Finds and merges the `promptly.toml` files, reading overrides from
+    /// `vars` rather than the environment.
+    fn discover_with_env(
+        start_dir: &Path,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        let mut issues = Vec::new();
+        let mut files = Vec::new();
+        for dir in start_dir.ancestors() {
+            let path = dir.join(CONFIG_FILE_NAME);
+            if !path.is_file() {
+                continue;
+            }
+            let parsed = fs::read_to_string(&path)
+                .map_err(|e| (e.to_string(), None))
+                .and_then(|content| {
+                    let table = content
+                        .parse::<toml::Table>()
+                        .map_err(|e| (e.message().to_string(), e.span()))?;
+                    Ok((content, table))
+                });
+            let table = match parsed {
+                Ok((content, mut table)) => {
+                    // Report the outermost file's problems first.
+                    issues.splice(0..0, validate(&path, &content, &mut table));
+                    table
+                }
+                Err((message, span)) => {
+                    issues.insert(
+                        0,
+                        ConfigIssue {
+                            path,
+                            kind: IssueKind::Unreadable,
+                            key: String::new(),
+                            message,
+                            help: None,
+                            span,
+                        },
+                    );
+                    continue;
+                }
+            };
+            let root = table
+                .get("root")
+                .and_then(toml::Value::as_bool)
+                .unwrap_or(false);
+            files.push((path, table));
+            if root {
+                break;
+            }
+        }
+
+        let mut config = Self {
+            issues,
+            ..Self::default()
+        };
+        for (path, mut table) in files.into_iter().rev() {
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            for key in PATH_KEYS {
+                if let Some(toml::Value::String(relative)) = table.get_mut(key) {
+                    *relative = dir.join(&*relative).to_string_lossy().into_owned();
+                }
+            }
+            override_rules(&mut config.merged, &table);
+            merge_table(&mut config.merged, table);
+            config.paths.push(path);
+        }
+
+        let (table, env, issues) = env_settings(vars);
+        override_rules(&mut config.merged, &table);
+        merge_table(&mut config.merged, table);
+        config.env = env;
+        config.issues.extend(issues);
+        config
+    }
+
+    /// Returns the value of every setting: the merged settings with the
+    /// defaults filled in.
+    #[must_use]
+    pub(crate) fn effective(&self) -> toml::Table {
+        toml::Table::try_from(self.settings()).unwrap_or_default()
+    }
+
+    /// Returns the merged settings, deserialized.
+    fn settings(&self) -> TomlConfig {
+        toml::Value::Table(self.merged.clone())
+            .try_into::<TomlConfig>()
+            .unwrap_or_else(|e| {
+                warn!(error = %e, "ignoring invalid config");
+                TomlConfig::default()
+            })
+    }
+
+    /// Returns the directories of the files, nearest first.
+    fn dirs(&self) -> impl Iterator<Item = &Path> {
+        self.paths.iter().rev().filter_map(|path| path.parent())
+    }
+}
+
+/// Checks a file's settings against the schema, removing unknown keys and
+/// invalid values so that the rest of the file still applies.
+fn validate(path: &Path, source: &str, table: &mut toml::Table) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    let issue = |kind, key: String, message, help| ConfigIssue {
+        path: path.to_path_buf(),
+        kind,
+        span: key_span(source, &key),
+        key,
+        message,
+        help,
+    };
+
+    for (key, known) in unknown_keys("", table) {
+        let name = key.rsplit('.').next().unwrap_or(&key);
+        let help = closest(name, known).map(|name| format!("Did you mean '{name}'?"));
+        issues.push(issue(
+            IssueKind::UnknownKey,
+            key,
+            "unknown key".to_string(),
+            help,
+        ));
+    }
+
+    // Deserialize each value on its own, so an error names the key it is
+    // about and only that key is dropped.
+    for key in leaf_keys(table, &[]) {
+        let Some(value) = get(table, &key) else {
+            continue;
+        };
+        let mut single = toml::Table::new();
+        insert(&mut single, &key, value.clone());
+        if let Err(e) = toml::Value::Table(single).try_into::<TomlConfig>() {
+            remove(table, &key);
+            issues.push(issue(
+                IssueKind::InvalidValue,
+                key.join("."),
+                e.message().to_string(),
+                None,
+            ));
+        }
+    }
+    issues.sort_by_key(|issue| issue.span.as_ref().map(|span| span.start));
+    issues
+}
+
+/// Reads settings from `PROMPTLY_*` environment variables, returning them
+/// with the variables they came from and their problems.
+fn env_settings(
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> (toml::Table, Vec<String>, Vec<ConfigIssue>) {
+    let defaults = ConfigFiles::default().effective();
+    let keys: Vec<(String, Vec<String>)> = SCHEMA
+        .iter()
+        .filter(|(section, _)| *section != "helpers")
+        .flat_map(|(section, keys)| keys.iter().map(move |key| join(section, key)))
+        .filter(|path| {
+            path != "root"
+                && path != "lint.severity"
+                && !SCHEMA.iter().any(|(section, _)| section == path)
+        })
+        .map(|path| {
+            let name = format!("PROMPTLY_{}", path.to_uppercase().replace(['.', '-'], "_"));
+            (name, path.split('.').map(str::to_string).collect())
+        })
+        .collect();
+
+    let mut vars: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(name, _)| name.starts_with("PROMPTLY_"))
+        .collect();
+    vars.sort();
+
+    let mut table = toml::Table::new();
+    let mut names = Vec::new();
+    let mut issues = Vec::new();
+    for (name, raw) in vars {
+        let key = if let Some(rule) = name.strip_prefix("PROMPTLY_LINT_SEVERITY_") {
+            vec![
+                "lint".to_string(),
+                "severity".to_string(),
+                rule.to_lowercase().replace('_', "-"),
+            ]
+        } else if let Some((_, key)) = keys.iter().find(|(key_name, _)| *key_name == name) {
+            key.clone()
+        } else {
+            continue;
+        };
+        let value = env_value(&raw, get(&defaults, &key));
+        let mut single = toml::Table::new();
+        insert(&mut single, &key, value.clone());
+        if let Err(e) = toml::Value::Table(single).try_into::<TomlConfig>() {
+            issues.push(ConfigIssue {
+                path: PathBuf::from(format!("${name}")),
+                kind: IssueKind::InvalidValue,
+                key: key.join("."),
+                message: e.message().to_string(),
+                help: None,
+                span: None,
+            });
+            continue;
+        }
+        insert(&mut table, &key, value);
+        names.push(name);
+    }
+    (table, names, issues)
+}
+
+/// Reads an environment variable's value for a setting whose default is
+/// `default`.
+fn env_value(raw: &str, default: Option<&toml::Value>) -> toml::Value {
+    let value = format!("value = {raw}")
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()));
+    match (default, value) {
+        (Some(toml::Value::Boolean(_)), toml::Value::Integer(n)) => toml::Value::Boolean(n != 0),
+        (Some(toml::Value::Array(_)), toml::Value::String(list)) => toml::Value::Array(
+            list.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| toml::Value::String(item.to_string()))
+                .collect(),
+        ),
+        (_, value) => value,
+    }
+}
+
+/// Removes the keys of `table` and the tables in it that the schema does not
+/// list, returning each one's path with the keys it could have been.
+fn unknown_keys(prefix: &str, table: &mut toml::Table) -> Vec<(String, &'static [&'static str])> {
+    let Some((_, known)) = SCHEMA.iter().find(|(path, _)| *path == prefix) else {
+        return Vec::new();
+    };
+    let mut unknown = Vec::new();
+    table.retain(|key, _| {
+        let keep = known.contains(&key);
+        if !keep {
+            unknown.push((join(prefix, key), *known));
+        }
+        keep
+    });
+    for (key, value) in table.iter_mut() {
+        let path = join(prefix, key);
+        match value {
+            toml::Value::Table(table) => unknown.extend(unknown_keys(&path, table)),
+            toml::Value::Array(items) => {
+                for item in items {
+                    if let toml::Value::Table(table) = item {
+                        unknown.extend(unknown_keys(&path, table));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    unknown
+}
+
+/// Returns the known key closest to a misspelled one, if any is close.
+fn closest(name: &str, known: &[&'static str]) -> Option<&'static str> {
+    known
+        .iter()
+        .map(|candidate| (strsim::levenshtein(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 3 && *distance * 2 < name.len())
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns the paths of the values in a table that are not tables.
+fn leaf_keys(table: &toml::Table, prefix: &[String]) -> Vec<Vec<String>> {
+    let mut keys = Vec::new();
+    for (key, value) in table {
+        let mut path = prefix.to_vec();
+        path.push(key.clone());
+        match value {
+            toml::Value::Table(table) => keys.extend(leaf_keys(table, &path)),
+            _ => keys.push(path),
+        }
+    }
+    keys
+}
+
+/// Returns the value at a path.
+fn get<'a>(table: &'a toml::Table, path: &[String]) -> Option<&'a toml::Value> {
+    let (last, parents) = path.split_last()?;
+    let mut table = table;
+    for key in parents {
+        table = table.get(key)?.as_table()?;
+    }
+    table.get(last)
+}
+
+/// Sets the value at a path, creating the tables above it.
+fn insert(table: &mut toml::Table, path: &[String], value: toml::Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for key in parents {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        let toml::Value::Table(inner) = entry else {
+            return;
+        };
+        table = inner;
+    }
+    table.insert(last.clone(), value);
+}
+
+/// Removes the value at a path.
+fn remove(table: &mut toml::Table, path: &[String]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for key in parents {
+        let Some(toml::Value::Table(inner)) = table.get_mut(key) else {
+            return;
+        };
+        table = inner;
+    }
+    table.remove(last);
+}
+
+/// Appends a key to a dotted path.
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// Returns the byte range of a key, or of a table's header, in a
+/// `promptly.toml` file.
+///
+/// Keys are found by following the table headers, so keys written as part of
+/// a dotted key or an inline table are not found.
+fn key_span(source: &str, key: &str) -> Option<Range<usize>> {
+    if key.is_empty() {
+        return None;
+    }
+    let mut section = String::new();
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let start = offset + line.len() - trimmed.len();
+        offset += line.len();
+        if trimmed.starts_with('[') {
+            section = trimmed
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            if section == key {
+                return Some(start..start + trimmed.trim_end().len());
+            }
+        } else if let Some((name, _)) = trimmed.split_once('=') {
+            let name = name.trim_end();
+            if join(&section, name.trim_matches('"')) == key {
+                return Some(start..start + name.len());
+            }
+        }
+    }
+    None
+}
+
+/// Merges `overlay` into `base`, with `overlay` winning.
+fn merge_table(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_table(base, overlay);
+            }
+            (Some(toml::Value::Array(base)), toml::Value::Array(mut overlay)) => {
+                overlay.append(base);
+                *base = overlay;
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Drops the rules a nearer file configures from the outer files' `allow`,
+/// `deny`, and `severity` settings, so the nearer setting wins.
+fn override_rules(base: &mut toml::Table, overlay: &toml::Table) {
+    let Some(overlay) = overlay.get("lint").and_then(toml::Value::as_table) else {
+        return;
+    };
+    let Some(toml::Value::Table(base)) = base.get_mut("lint") else {
+        return;
+    };
+    let names = |key: &str| -> Vec<String> {
+        overlay
+            .get(key)
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+            .map(str::to_string)
+            .collect()
+    };
+    let mut rules = names("allow");
+    rules.extend(names("deny"));
+    if let Some(severity) = overlay.get("severity").and_then(toml::Value::as_table) {
+        rules.extend(severity.keys().cloned());
+    }
+    for key in ["allow", "deny"] {
+        if let Some(toml::Value::Array(list)) = base.get_mut(key) {
+            list.retain(|rule| {
+                !rule
+                    .as_str()
+                    .is_some_and(|rule| rules.iter().any(|r| r == rule))
+            });
+        }
+    }
+    if let Some(toml::Value::Table(severity)) = base.get_mut("severity") {
+        severity.retain(|rule, _| !rules.iter().any(|r| r == rule));
+    }
 }
 
 /// Runtime configuration for promptly.
@@ -69,9 +720,47 @@ pub(crate) struct Config {
     /// Treat warnings as errors.
     pub warnings_as_errors: bool,
 
+    /// Severity overrides by rule. These win over `allow` and `deny` in the
+    /// same file.
+    pub severity: HashMap<String, RuleLevel>,
+
     /// File patterns to ignore (future use).
     #[allow(dead_code)]
     pub(crate) ignore: Vec<String>,
+
+    /// Require every prompt to have an owner.
+    pub require_owners: bool,
+
+    /// Custom helpers used by the project's prompts.
+    pub helpers: Vec<HelperDecl>,
+
+    /// Custom output formats the project registers.
+    pub output_formats: Vec<String>,
+
+    /// Thresholds for `template-too-complex`.
+    pub complexity: ComplexityLimits,
+
+    /// Settings for the opt-in `spelling` pass.
+    pub spelling: SpellingConfig,
+
+    /// Floors for `check --metrics`.
+    pub readability: ReadabilityConfig,
+
+    /// Ceiling for `cost-over-budget`, in USD per request.
+    pub max_estimated_cost_usd: Option<f64>,
+
+    /// How `fmt` sorts schema fields, if at all.
+    pub sort_schema: Option<SchemaOrder>,
+
+    /// The project's tool catalog and the file it was loaded from.
+    pub tools: Option<(PathBuf, ToolCatalog)>,
+
+    /// Model prices from the project's pricing table, overriding the
+    /// built-in ones.
+    pub pricing: Option<PricingTable>,
+
+    /// Problems found in the `promptly.toml` files.
+    pub issues: Vec<ConfigIssue>,
 }
 
 impl Config {
@@ -81,8 +770,8 @@ impl Config {
         Self::default()
     }
 
-    /// Loads configuration from `promptly.toml` by searching the current directory
-    /// and all parent directories.
+    /// Loads configuration from the `promptly.toml` files in the current
+    /// directory and its parents.
     ///
     /// # Arguments
     ///
@@ -90,29 +779,131 @@ impl Config {
     ///
     /// # Returns
     ///
-    /// A `Config` loaded from the file, or default configuration if no file is found.
+    /// A `Config` merged from the files, or default configuration if no file
+    /// is found.
     #[must_use]
-    #[allow(clippy::collapsible_if)] // Using nested ifs for stable Rust compatibility (no let-chains)
     pub(crate) fn load(start_dir: &Path) -> Self {
-        let mut current = start_dir;
-
-        loop {
-            let config_path = current.join(CONFIG_FILE_NAME);
-            if config_path.exists() {
-                if let Ok(content) = fs::read_to_string(&config_path) {
-                    if let Ok(toml_config) = toml::from_str::<TomlConfig>(&content) {
-                        return Self::from_toml(toml_config);
+        let files = ConfigFiles::discover(start_dir);
+        for issue in &files.issues {
+            warn!(%issue, "config problem");
+        }
+        let toml_config = files.settings();
+
+        // Files named by the config are found next to the nearest
+        // `promptly.toml` that has them.
+        let mut dirs: Vec<&Path> = files.dirs().collect();
+        if dirs.is_empty() {
+            dirs.push(start_dir);
+        }
+        let locate = |explicit: Option<PathBuf>, name: &str| {
+            explicit.or_else(|| dirs.iter().map(|dir| dir.join(name)).find(|p| p.is_file()))
+        };
+        let manifest = locate(
+            toml_config.helper_manifest.clone(),
+            HelperManifest::FILE_NAME,
+        );
+        let catalog = locate(toml_config.tool_catalog.clone(), ToolCatalog::FILE_NAME);
+        let pricing = locate(toml_config.pricing_table.clone(), PricingTable::FILE_NAME);
+
+        let mut config = Self::from_toml(toml_config);
+        config.issues = files.issues;
+        config.add_manifest_helpers(manifest.as_deref());
+        config.load_tool_catalog(catalog.as_deref());
+        config.load_pricing_table(pricing.as_deref());
+        config
+    }
+
+    /// Returns a linter configured with these settings.
+    #[must_use]
+    pub(crate) fn linter(&self) -> Linter {
+        Linter::new()
+            .with_require_owners(self.require_owners)
+            .with_helpers(self.helpers.clone())
+            .with_output_formats(self.output_formats.clone())
+            .with_complexity(self.complexity)
+            .with_spelling(self.spelling.clone())
+            .with_tools(self.tools.as_ref().map(|(_, catalog)| catalog.clone()))
+            .with_cost_budget(self.cost_budget())
+    }
+
+    /// Loads the tool catalog at `path`, if there is one.
+    fn load_tool_catalog(&mut self, path: Option<&Path>) {
+        let Some(path) = path else {
+            return;
+        };
+        match ToolCatalog::load(path) {
+            Ok(catalog) => self.tools = Some((path.to_path_buf(), catalog)),
+            Err(e) => warn!(error = %e, "ignoring tool catalog"),
+        }
+    }
+
+    /// Loads the pricing table at `path`, if there is one.
+    fn load_pricing_table(&mut self, path: Option<&Path>) {
+        let Some(path) = path else {
+            return;
+        };
+        match PricingTable::load(path) {
+            Ok(table) => self.pricing = Some(table),
+            Err(e) => warn!(error = %e, "ignoring pricing table"),
+        }
+    }
+
+    /// Returns the built-in model prices with the project's overrides.
+    #[must_use]
+    pub(crate) fn pricing_table(&self) -> PricingTable {
+        let builtin = PricingTable::builtin();
+        match &self.pricing {
+            Some(overrides) => builtin.with_overrides(overrides.clone()),
+            None => builtin,
+        }
+    }
+
+    /// Returns the cost ceiling for `cost-over-budget`, if one is set.
+    #[must_use]
+    pub(crate) fn cost_budget(&self) -> Option<CostBudget> {
+        self.max_estimated_cost_usd.map(|max_usd| CostBudget {
+            max_usd,
+            pricing: self.pricing_table(),
+        })
+    }
+
+    /// Returns the formatter settings, applying the `[fmt]` section.
+    #[must_use]
+    pub(crate) fn formatter_config(&self) -> FormatterConfig {
+        FormatterConfig {
+            sort_schema: self.sort_schema,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the catalog's tools keyed by name, for registering with a
+    /// [`Dotprompt`](dotprompt::Dotprompt).
+    #[must_use]
+    pub(crate) fn tool_map(&self) -> Option<HashMap<String, ToolDefinition>> {
+        self.tools
+            .as_ref()
+            .map(|(_, catalog)| catalog.clone().into_map())
+    }
+
+    /// Adds the helpers described by the helper manifest at `path`, if there
+    /// is one.
+    ///
+    /// Helpers declared in `promptly.toml` win over manifest entries with the
+    /// same name.
+    fn add_manifest_helpers(&mut self, path: Option<&Path>) {
+        let Some(path) = path else {
+            return;
+        };
+        match HelperManifest::load(path) {
+            Ok(manifest) => {
+                for spec in manifest.helpers {
+                    if !self.helpers.iter().any(|h| h.name == spec.name) {
+                        self.helpers.push(spec.into());
                     }
                 }
             }
-
-            match current.parent() {
-                Some(parent) => current = parent,
-                None => break,
-            }
+            Err(e) => warn!(error = %e, "ignoring helper manifest"),
         }
-
-        Self::default()
     }
 
     /// Converts a parsed TOML config into runtime config.
@@ -121,7 +912,19 @@ impl Config {
             allow: toml.lint.allow.into_iter().collect(),
             deny: toml.lint.deny.into_iter().collect(),
             warnings_as_errors: toml.lint.warnings_as_errors,
+            severity: toml.lint.severity.into_iter().collect(),
             ignore: toml.lint.ignore,
+            require_owners: toml.lint.require_owners,
+            helpers: toml.helpers,
+            output_formats: toml.output_formats,
+            complexity: toml.lint.complexity,
+            spelling: toml.lint.spelling,
+            readability: toml.lint.readability,
+            max_estimated_cost_usd: toml.lint.max_estimated_cost_usd,
+            sort_schema: toml.fmt.sort_schema,
+            tools: None,
+            pricing: None,
+            issues: Vec::new(),
         }
     }
 
@@ -131,14 +934,16 @@ impl Config {
     pub(crate) fn merge_cli(&mut self, allow: &[String], deny: &[String], strict: bool) {
         for rule in allow {
             self.allow.insert(rule.clone());
-            // Remove from deny if present (CLI allow overrides)
+            // Remove from deny and severity if present (CLI allow overrides)
             self.deny.remove(rule);
+            self.severity.remove(rule);
         }
 
         for rule in deny {
             self.deny.insert(rule.clone());
-            // Remove from allow if present (CLI deny overrides)
+            // Remove from allow and severity if present (CLI deny overrides)
             self.allow.remove(rule);
+            self.severity.remove(rule);
         }
 
         if strict {
@@ -146,16 +951,40 @@ impl Config {
         }
     }
 
-    /// Checks if a rule is allowed (disabled).
+    /// Returns the level configured for a rule, if any.
+    ///
+    /// A `[lint.severity]` entry wins; otherwise allowed rules are off and
+    /// denied rules are errors.
     #[must_use]
-    pub(crate) fn is_allowed(&self, rule: &str) -> bool {
-        self.allow.contains(rule)
+    pub(crate) fn rule_level(&self, rule: &str) -> Option<RuleLevel> {
+        self.severity.get(rule).copied().or_else(|| {
+            if self.allow.contains(rule) {
+                Some(RuleLevel::Off)
+            } else if self.deny.contains(rule) {
+                Some(RuleLevel::Error)
+            } else {
+                None
+            }
+        })
     }
 
-    /// Checks if a rule is explicitly denied.
+    /// Drops diagnostics from rules that are off and sets the severity of
+    /// the rest to their configured level.
     #[must_use]
-    pub(crate) fn is_denied(&self, rule: &str) -> bool {
-        self.deny.contains(rule)
+    pub(crate) fn apply_rules(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diagnostic| {
+                diagnostic.severity = match self.rule_level(&diagnostic.code) {
+                    Some(RuleLevel::Off) => return None,
+                    Some(RuleLevel::Info) => DiagnosticSeverity::Info,
+                    Some(RuleLevel::Warning) => DiagnosticSeverity::Warning,
+                    Some(RuleLevel::Error) => DiagnosticSeverity::Error,
+                    None => diagnostic.severity,
+                };
+                Some(diagnostic)
+            })
+            .collect()
     }
 }
 
@@ -190,17 +1019,120 @@ allow = ["unused-variable", "unverified-partial"]
 deny = ["undefined-variable"]
 warnings-as-errors = true
 ignore = ["examples/*"]
+require-owners = true
+max-estimated-cost-usd = 0.05
+
+[fmt]
+sort-schema = "required-first"
+
+[lint.complexity]
+max-depth = 6
+
+[lint.spelling]
+enabled = true
+allow = ["teh"]
+
+[lint.readability]
+min-flesch = 40.0
+
+[[helpers]]
+name = "card"
+block = true
 "#
         )
         .unwrap();
 
         let config = Config::load(temp_dir.path());
 
-        assert!(config.is_allowed("unused-variable"));
-        assert!(config.is_allowed("unverified-partial"));
-        assert!(config.is_denied("undefined-variable"));
+        assert_eq!(config.rule_level("unused-variable"), Some(RuleLevel::Off));
+        assert_eq!(
+            config.rule_level("unverified-partial"),
+            Some(RuleLevel::Off)
+        );
+        assert_eq!(
+            config.rule_level("undefined-variable"),
+            Some(RuleLevel::Error)
+        );
         assert!(config.warnings_as_errors);
         assert_eq!(config.ignore, vec!["examples/*"]);
+        assert!(config.require_owners);
+        assert_eq!(config.complexity.depth, 6);
+        assert_eq!(
+            config.complexity.partials,
+            ComplexityLimits::default().partials
+        );
+        assert!(config.spelling.enabled);
+        assert_eq!(config.spelling.allow, vec!["teh"]);
+        assert_eq!(config.readability.min_flesch, Some(40.0));
+        assert_eq!(config.readability.max_sentence_length, None);
+        assert_eq!(config.max_estimated_cost_usd, Some(0.05));
+        assert!(config.cost_budget().is_some());
+        assert_eq!(config.sort_schema, Some(SchemaOrder::RequiredFirst));
+        assert_eq!(config.helpers.len(), 1);
+        assert!(config.helpers[0].block);
+    }
+
+    #[test]
+    fn test_load_helper_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("promptly.toml"),
+            "helper-manifest = \"tools/helpers.toml\"\n\n[[helpers]]\nname = \"card\"\n",
+        )
+        .unwrap();
+        fs::create_dir(temp_dir.path().join("tools")).unwrap();
+        fs::write(
+            temp_dir.path().join("tools/helpers.toml"),
+            "[[helpers]]\nname = \"card\"\nblock = true\n\n[[helpers]]\nname = \"formatDate\"\nsignature = \"formatDate date\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path());
+        assert_eq!(config.helpers.len(), 2);
+        assert!(
+            !config.helpers[0].block,
+            "promptly.toml wins over the manifest"
+        );
+        assert_eq!(config.helpers[1].name, "formatDate");
+        assert_eq!(
+            config.helpers[1].signature.as_deref(),
+            Some("formatDate date")
+        );
+
+        let bare = TempDir::new().unwrap();
+        fs::write(
+            bare.path().join("helpers.toml"),
+            "[[helpers]]\nname = \"today\"\n",
+        )
+        .unwrap();
+        assert_eq!(Config::load(bare.path()).helpers[0].name, "today");
+    }
+
+    #[test]
+    fn test_load_tool_catalog() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("promptly.toml"),
+            "tool-catalog = \"catalog/tools.yaml\"\n",
+        )
+        .unwrap();
+        fs::create_dir(temp_dir.path().join("catalog")).unwrap();
+        fs::write(
+            temp_dir.path().join("catalog/tools.yaml"),
+            "tools:\n  - name: search\n    inputSchema: {type: object}\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path());
+        let (path, catalog) = config.tools.as_ref().unwrap();
+        assert!(path.ends_with("catalog/tools.yaml"));
+        assert!(catalog.get("search").is_some());
+        assert!(config.tool_map().unwrap().contains_key("search"));
+
+        let bare = TempDir::new().unwrap();
+        assert!(Config::load(bare.path()).tools.is_none());
+        fs::write(bare.path().join("tools.yaml"), "tools: [").unwrap();
+        assert!(Config::load(bare.path()).tools.is_none());
     }
 
     #[test]
@@ -209,15 +1141,56 @@ ignore = ["examples/*"]
         config.allow.insert("rule-a".to_string());
         config.deny.insert("rule-b".to_string());
 
+        config
+            .severity
+            .insert("rule-c".to_string(), RuleLevel::Warning);
+
         // CLI deny overrides config allow
         config.merge_cli(&[], &["rule-a".to_string()], false);
-        assert!(!config.is_allowed("rule-a"));
-        assert!(config.is_denied("rule-a"));
+        assert_eq!(config.rule_level("rule-a"), Some(RuleLevel::Error));
 
         // CLI allow overrides config deny
         config.merge_cli(&["rule-b".to_string()], &[], false);
-        assert!(config.is_allowed("rule-b"));
-        assert!(!config.is_denied("rule-b"));
+        assert_eq!(config.rule_level("rule-b"), Some(RuleLevel::Off));
+
+        // CLI allow overrides config severity
+        config.merge_cli(&["rule-c".to_string()], &[], false);
+        assert_eq!(config.rule_level("rule-c"), Some(RuleLevel::Off));
+    }
+
+    #[test]
+    fn test_severity_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("promptly.toml"),
+            "[lint]\ndeny = [\"unused-variable\"]\n\n[lint.severity]\nunverified-partial = \"off\"\nunused-variable = \"warning\"\nmissing-owner = \"error\"\n",
+        )
+        .unwrap();
+        let config = Config::load(temp_dir.path());
+        assert_eq!(
+            config.rule_level("unused-variable"),
+            Some(RuleLevel::Warning),
+            "[lint.severity] wins over deny"
+        );
+
+        let diagnostics = config.apply_rules(vec![
+            Diagnostic::warning("unverified-partial", "partial"),
+            Diagnostic::warning("unused-variable", "unused"),
+            Diagnostic::warning("missing-owner", "model"),
+            Diagnostic::error("invalid-yaml", "yaml"),
+        ]);
+        let levels: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.code.as_str(), d.severity))
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                ("unused-variable", DiagnosticSeverity::Warning),
+                ("missing-owner", DiagnosticSeverity::Error),
+                ("invalid-yaml", DiagnosticSeverity::Error),
+            ]
+        );
     }
 
     #[test]
@@ -229,6 +1202,146 @@ ignore = ["examples/*"]
         assert!(config.warnings_as_errors);
     }
 
+    #[test]
+    fn test_nested_configs_override_parents() {
+        let temp_dir = TempDir::new().unwrap();
+        let team = temp_dir.path().join("team");
+        fs::create_dir(&team).unwrap();
+        fs::write(
+            temp_dir.path().join("promptly.toml"),
+            "[lint]\nallow = [\"rule-a\"]\ndeny = [\"rule-b\"]\nrequire-owners = true\n\n[lint.severity]\nrule-c = \"error\"\n\n[lint.complexity]\nmax-depth = 6\n",
+        )
+        .unwrap();
+        fs::write(
+            team.join("promptly.toml"),
+            "tool-catalog = \"tools/catalog.yaml\"\n\n[lint]\nallow = [\"rule-b\"]\ndeny = [\"rule-c\"]\nrequire-owners = false\n",
+        )
+        .unwrap();
+
+        let files = ConfigFiles::discover(&team);
+        assert_eq!(files.paths.len(), 2);
+        assert!(files.paths[1].starts_with(&team));
+        assert_eq!(
+            files.merged["tool-catalog"].as_str(),
+            Some(team.join("tools/catalog.yaml").to_string_lossy().as_ref()),
+            "relative paths resolve against the file that sets them"
+        );
+
+        let config = Config::load(&team);
+        assert_eq!(config.rule_level("rule-a"), Some(RuleLevel::Off));
+        assert_eq!(config.rule_level("rule-b"), Some(RuleLevel::Off));
+        assert_eq!(config.rule_level("rule-c"), Some(RuleLevel::Error));
+        assert!(config.severity.is_empty());
+        assert!(!config.require_owners);
+        assert_eq!(config.complexity.depth, 6);
+
+        fs::write(
+            team.join("promptly.toml"),
+            "root = true\n\n[lint]\nallow = [\"rule-b\"]\n",
+        )
+        .unwrap();
+        let config = Config::load(&team);
+        assert_eq!(config.rule_level("rule-a"), None);
+        assert!(!config.require_owners);
+    }
+
+    #[test]
+    fn test_invalid_settings_are_reported_and_skipped() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("promptly.toml"),
+            "[lint]\nwarnings-as-error = true\nrequire-owners = \"yes\"\nallow = [\"rule-a\"]\n\n[lint.severity]\nrule-b = \"warnn\"\n\n[lint.compexity]\nmax-depth = 2\n\n[[helpers]]\nname = \"card\"\nblok = true\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path());
+        assert_eq!(config.rule_level("rule-a"), Some(RuleLevel::Off));
+        assert!(config.severity.is_empty());
+        let issues: Vec<_> = config
+            .issues
+            .iter()
+            .map(|i| (i.kind, i.key.as_str(), i.help.as_deref()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (
+                    IssueKind::UnknownKey,
+                    "lint.warnings-as-error",
+                    Some("Did you mean 'warnings-as-errors'?")
+                ),
+                (IssueKind::InvalidValue, "lint.require-owners", None),
+                (IssueKind::InvalidValue, "lint.severity.rule-b", None),
+                (
+                    IssueKind::UnknownKey,
+                    "lint.compexity",
+                    Some("Did you mean 'complexity'?")
+                ),
+                (
+                    IssueKind::UnknownKey,
+                    "helpers.blok",
+                    Some("Did you mean 'block'?")
+                ),
+            ]
+        );
+        let source = fs::read_to_string(temp_dir.path().join("promptly.toml")).unwrap();
+        let span = config.issues[1].span.clone().unwrap();
+        assert_eq!(&source[span], "require-owners");
+
+        fs::write(temp_dir.path().join("promptly.toml"), "[lint\n").unwrap();
+        let config = Config::load(temp_dir.path());
+        assert_eq!(config.issues.len(), 1);
+        assert_eq!(config.issues[0].kind, IssueKind::Unreadable);
+        assert!(config.issues[0].span.is_some());
+    }
+
+    #[test]
+    fn test_environment_overrides_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("promptly.toml"),
+            "[lint]\ndeny = [\"rule-a\"]\n\n[lint.complexity]\nmax-depth = 6\n",
+        )
+        .unwrap();
+        let vars = [
+            ("PROMPTLY_LINT_ALLOW", "rule-a, rule-b"),
+            ("PROMPTLY_LINT_WARNINGS_AS_ERRORS", "1"),
+            ("PROMPTLY_LINT_COMPLEXITY_MAX_DEPTH", "2"),
+            ("PROMPTLY_LINT_SEVERITY_RULE_C", "warn"),
+            ("PROMPTLY_FMT_SORT_SCHEMA", "alphabetical"),
+            ("PROMPTLY_LINT_REQUIRE_OWNERS", "maybe"),
+            ("PROMPTLY_FORMAT", "json"),
+            ("HOME", "/root"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let files = ConfigFiles::discover_with_env(temp_dir.path(), vars);
+        assert_eq!(
+            files.env,
+            [
+                "PROMPTLY_FMT_SORT_SCHEMA",
+                "PROMPTLY_LINT_ALLOW",
+                "PROMPTLY_LINT_COMPLEXITY_MAX_DEPTH",
+                "PROMPTLY_LINT_SEVERITY_RULE_C",
+                "PROMPTLY_LINT_WARNINGS_AS_ERRORS",
+            ]
+        );
+        assert_eq!(files.issues.len(), 1);
+        assert_eq!(files.issues[0].key, "lint.require-owners");
+
+        let effective = files.effective();
+        let lint = effective["lint"].as_table().unwrap();
+        assert_eq!(lint["allow"].as_array().unwrap().len(), 2);
+        assert!(lint["deny"].as_array().unwrap().is_empty());
+        assert_eq!(lint["warnings-as-errors"].as_bool(), Some(true));
+        assert_eq!(lint["complexity"]["max-depth"].as_integer(), Some(2));
+        assert_eq!(lint["severity"]["rule-c"].as_str(), Some("warning"));
+        assert_eq!(
+            effective["fmt"]["sort-schema"].as_str(),
+            Some("alphabetical")
+        );
+    }
+
     #[test]
     fn test_load_from_parent_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -248,6 +1361,6 @@ allow = ["parent-rule"]
 
         // Load from subdirectory should find parent config
         let config = Config::load(&sub_dir);
-        assert!(config.is_allowed("parent-rule"));
+        assert_eq!(config.rule_level("parent-rule"), Some(RuleLevel::Off));
     }
 }
@@ -0,0 +1,203 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversion of other prompt dialects into canonical dotprompt syntax.
+//!
+//! Teams moving prompts from other tools bring along their role syntax:
+//! block-form `{{#role "user"}}...{{/role}}` helpers, or chat-template
+//! markers such as `<|system|>` and `<|im_start|>user`. Each [`Dialect`]
+//! rewrites its markers into `{{role "..."}}` helpers, leaving verbatim
+//! regions and the frontmatter alone.
+
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use regex::{Captures, Regex};
+
+use crate::verbatim::verbatim_regions;
+
+/// A prompt dialect that `promptly migrate --from` converts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Dialect {
+    /// Block-form role helpers: `{{#role "user"}}...{{/role}}`.
+    RoleBlocks,
+    /// Chat-template markers: `<|user|>`, `<|im_start|>user ... <|im_end|>`.
+    Chatml,
+}
+
+/// Regex for block-form role openers, capturing the quoted role.
+static ROLE_BLOCK_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for block-form role closers, with the rest of the line when the
+/// closer stands alone.
+static ROLE_BLOCK_END_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for chat-template role markers, capturing the role.
+static CHAT_MARKER_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for chat-template end-of-turn markers, with the rest of the line
+/// when the marker stands alone.
+static CHAT_END_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+impl Dialect {
+    /// Rewrites the template body of `source` into canonical dotprompt role
+    /// helpers.
+    #[must_use]
+    pub(crate) fn convert(self, source: &str) -> String {
+        let body_start = body_start(source);
+        let body = &source[body_start..];
+        let edits = match self {
+            Self::RoleBlocks => {
+                let mut edits = replacements(
+                    &ROLE_BLOCK_RE,
+                    r#"\{\{~?#role\s+(?:"(\w+)"|'(\w+)')\s*~?\}\}"#,
+                    body,
+                    role_helper,
+                );
+                edits.extend(replacements(
+                    &ROLE_BLOCK_END_RE,
+                    r"(?m)^[ \t]*\{\{~?/role\s*~?\}\}[ \t]*\r?\n|\{\{~?/role\s*~?\}\}",
+                    body,
+                    |_| String::new(),
+                ));
+                edits
+            }
+            Self::Chatml => {
+                let mut edits = replacements(
+                    &CHAT_MARKER_RE,
+                    r"<\|im_start\|>[ \t]*(\w+)|<\|(system|user|assistant|model)\|>",
+                    body,
+                    role_helper,
+                );
+                edits.extend(replacements(&CHAT_END_RE, r"(?m)^[ \t]*(?:<\|im_end\|>|<\|end\|>|</s>)[ \t]*\r?\n|<\|im_end\|>|<\|end\|>|</s>", body, |_| String::new()));
+                edits
+            }
+        };
+
+        let verbatim = verbatim_regions(body);
+        let mut edits: Vec<(Range<usize>, String)> = edits
+            .into_iter()
+            .filter(|(range, _)| {
+                !verbatim
+                    .iter()
+                    .any(|region| range.start < region.end && region.start < range.end)
+            })
+            .collect();
+        edits.sort_by_key(|(range, _)| range.start);
+
+        let mut output = String::with_capacity(source.len());
+        output.push_str(&source[..body_start]);
+        let mut last = 0;
+        for (range, replacement) in edits {
+            if range.start < last {
+                continue;
+            }
+            output.push_str(&body[last..range.start]);
+            output.push_str(&replacement);
+            last = range.end;
+        }
+        output.push_str(&body[last..]);
+        output
+    }
+}
+
+/// Finds the matches of a lazily compiled regex in `body`, paired with
+/// their replacements.
+fn replacements(
+    cell: &OnceLock<Option<Regex>>,
+    pattern: &str,
+    body: &str,
+    replace: impl Fn(&Captures<'_>) -> String,
+) -> Vec<(Range<usize>, String)> {
+    let Some(re) = cell.get_or_init(|| Regex::new(pattern).ok()) else {
+        return Vec::new();
+    };
+    re.captures_iter(body)
+        .filter_map(|caps| {
+            let range = caps.get(0)?.range();
+            Some((range, replace(&caps)))
+        })
+        .collect()
+}
+
+/// Returns the canonical role helper for the role captured by a marker
+/// regex, mapping the `assistant` role of other tools to dotprompt's `model`.
+fn role_helper(caps: &Captures<'_>) -> String {
+    let role = caps
+        .get(1)
+        .or_else(|| caps.get(2))
+        .map_or("", |m| m.as_str())
+        .to_lowercase();
+    let role = if role == "assistant" {
+        "model"
+    } else {
+        role.as_str()
+    };
+    format!("{{{{role \"{role}\"}}}}")
+}
+
+/// Returns the byte offset where the template body starts, after any
+/// frontmatter.
+fn body_start(source: &str) -> usize {
+    let mut lines = source.split_inclusive('\n');
+    let Some(first) = lines.next() else {
+        return 0;
+    };
+    if first.trim() != "---" {
+        return 0;
+    }
+    let mut offset = first.len();
+    for line in lines {
+        offset += line.len();
+        if line.trim() == "---" {
+            return offset;
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_role_blocks() {
+        let source = "---\nmodel: m\n---\n{{#role \"system\"}}\nBe brief.\n{{/role}}\n{{#role 'user'}}Hi {{name}}{{/role}}\n";
+        assert_eq!(
+            Dialect::RoleBlocks.convert(source),
+            "---\nmodel: m\n---\n{{role \"system\"}}\nBe brief.\n{{role \"user\"}}Hi {{name}}\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_chatml() {
+        let source = "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHi {{name}}\n<|im_end|>\n<|assistant|>\nHello!</s>\n";
+        assert_eq!(
+            Dialect::Chatml.convert(source),
+            "{{role \"system\"}}\nBe brief.\n{{role \"user\"}}\nHi {{name}}\n{{role \"model\"}}\nHello!\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_leaves_verbatim_regions() {
+        let source = "<|user|>\n{{{{raw}}}}<|system|>{{{{/raw}}}}\n";
+        assert_eq!(
+            Dialect::Chatml.convert(source),
+            "{{role \"user\"}}\n{{{{raw}}}}<|system|>{{{{/raw}}}}\n"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Line diffs for showing what a command changed.
+//!
+//! Lines are matched with Myers' algorithm, so an inserted or deleted line
+//! shifts the lines around it instead of pairing every later line with the
+//! wrong one. Changes are printed as unified diff hunks with a few lines of
+//! context.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anstream::eprint;
+use owo_colors::OwoColorize;
+
+/// Unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// How a line changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    /// The line is in both texts.
+    Equal,
+    /// The line is only in the old text.
+    Delete,
+    /// The line is only in the new text.
+    Insert,
+}
+
+/// A line of the diff, with the line numbers (0-based) it starts at in the
+/// old and new texts.
+#[derive(Debug, Clone, Copy)]
+struct DiffLine<'a> {
+    change: Change,
+    old: usize,
+    new: usize,
+    text: &'a str,
+}
+
+/// Returns a unified diff from `old` to `new`, or an empty string if they
+/// are the same.
+pub(crate) fn unified_diff(path: &Path, old: &str, new: &str) -> String {
+    let lines = diff_lines(old, new);
+    let mut out = String::new();
+    for (i, hunk) in hunks(&lines).into_iter().enumerate() {
+        if i == 0 {
+            let _ = writeln!(out, "--- {}", path.display());
+            let _ = writeln!(out, "+++ {}", path.display());
+        }
+        let first = hunk[0];
+        let old_len = hunk.iter().filter(|l| l.change != Change::Insert).count();
+        let new_len = hunk.iter().filter(|l| l.change != Change::Delete).count();
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            range(first.old, old_len),
+            range(first.new, new_len)
+        );
+        for line in hunk {
+            let prefix = match line.change {
+                Change::Equal => ' ',
+                Change::Delete => '-',
+                Change::Insert => '+',
+            };
+            if let Some(text) = line.text.strip_suffix('\n') {
+                let _ = writeln!(out, "{prefix}{}", text.strip_suffix('\r').unwrap_or(text));
+            } else {
+                let _ = writeln!(out, "{prefix}{}", line.text);
+                out.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// Prints a colored unified diff from `old` to `new` on stderr.
+pub(crate) fn print_diff(path: &Path, old: &str, new: &str) {
+    let diff = unified_diff(path, old, new);
+    for line in diff.lines() {
+        if line.starts_with("---") || line.starts_with("+++") {
+            eprint!("{}", line.bold());
+        } else if line.starts_with("@@") {
+            eprint!("{}", line.cyan());
+        } else if line.starts_with('-') {
+            eprint!("{}", line.red());
+        } else if line.starts_with('+') {
+            eprint!("{}", line.green());
+        } else {
+            eprint!("{line}");
+        }
+        eprint!("\n");
+    }
+}
+
+/// Formats one side of a hunk header.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Groups the changed lines into hunks with [`CONTEXT`] lines around them,
+/// merging changes whose context would overlap.
+fn hunks<'a, 'b>(lines: &'b [DiffLine<'a>]) -> Vec<&'b [DiffLine<'a>]> {
+    let is_change = |i: &usize| lines[*i].change != Change::Equal;
+    let mut hunks = Vec::new();
+    let mut next = 0;
+    while let Some(first) = (next..lines.len()).find(is_change) {
+        let mut end = first + 1;
+        while let Some(change) = (end..lines.len()).find(is_change) {
+            if change - end > 2 * CONTEXT {
+                break;
+            }
+            end = change + 1;
+        }
+        let stop = (end + CONTEXT).min(lines.len());
+        hunks.push(&lines[first.saturating_sub(CONTEXT)..stop]);
+        next = stop;
+    }
+    hunks
+}
+
+/// Diffs two texts line by line, keeping line endings.
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+
+    // Only the middle, between the common prefix and suffix, needs searching
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let changes = myers(&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let changes = std::iter::repeat_n(Change::Equal, prefix)
+        .chain(changes)
+        .chain(std::iter::repeat_n(Change::Equal, suffix));
+    let (mut i, mut j) = (0, 0);
+    changes
+        .map(|change| {
+            let line = DiffLine {
+                change,
+                old: i,
+                new: j,
+                text: if change == Change::Insert { b[j] } else { a[i] },
+            };
+            if change != Change::Insert {
+                i += 1;
+            }
+            if change != Change::Delete {
+                j += 1;
+            }
+            line
+        })
+        .collect()
+}
+
+/// Finds a shortest edit script from `a` to `b` with Myers' algorithm.
+///
+/// Diagonals `k = x - y` are stored at index `k + max` so they stay
+/// unsigned.
+fn myers(old: &[&str], new: &[&str]) -> Vec<Change> {
+    let max = old.len() + new.len();
+    if max == 0 {
+        return Vec::new();
+    }
+    let mut frontier = vec![0; 2 * max + 2];
+    let mut trace = Vec::new();
+    'search: for cost in 0..=max {
+        trace.push(frontier.clone());
+        for k in (max - cost..=max + cost).step_by(2) {
+            let mut x = if from_above(&frontier, k, max, cost) {
+                frontier[k + 1]
+            } else {
+                frontier[k - 1] + 1
+            };
+            let mut y = x + max - k;
+            while x < old.len() && y < new.len() && old[x] == new[y] {
+                x += 1;
+                y += 1;
+            }
+            frontier[k] = x;
+            if x >= old.len() && y >= new.len() {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk back from the end through the saved frontiers
+    let mut changes = Vec::new();
+    let (mut x, mut y) = (old.len(), new.len());
+    for (cost, frontier) in trace.iter().enumerate().skip(1).rev() {
+        let k = x + max - y;
+        let prev_k = if from_above(frontier, k, max, cost) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = frontier[prev_k];
+        let prev_y = prev_x + max - prev_k;
+        while x > prev_x && y > prev_y {
+            changes.push(Change::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        changes.push(if x == prev_x {
+            Change::Insert
+        } else {
+            Change::Delete
+        });
+        (x, y) = (prev_x, prev_y);
+    }
+    changes.extend(std::iter::repeat_n(Change::Equal, x));
+    changes.reverse();
+    changes
+}
+
+/// Returns whether the furthest path onto diagonal `k` after `cost` edits
+/// comes from diagonal `k + 1`, by an insertion, rather than from `k - 1`,
+/// by a deletion.
+fn from_above(frontier: &[usize], k: usize, max: usize, cost: usize) -> bool {
+    k == max - cost || (k != max + cost && frontier[k - 1] < frontier[k + 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn diff(old: &str, new: &str) -> String {
+        unified_diff(Path::new("a.prompt"), old, new)
+    }
+
+    /// Strategy for short texts over a few distinct lines, so both sides
+    /// share many lines.
+    fn text() -> impl Strategy<Value = String> {
+        let line = prop_oneof![Just("a\n"), Just("b\n"), Just("c\n"), Just("d")];
+        proptest::collection::vec(line, 0..16).prop_map(|lines| lines.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn test_diff_rebuilds_both_texts(old in text(), new in text()) {
+            let lines = diff_lines(&old, &new);
+            let kept = |skip: Change| {
+                lines
+                    .iter()
+                    .filter(|line| line.change != skip)
+                    .map(|line| line.text)
+                    .collect::<String>()
+            };
+            prop_assert_eq!(kept(Change::Insert), old.as_str());
+            prop_assert_eq!(kept(Change::Delete), new.as_str());
+        }
+    }
+
+    #[test]
+    fn test_same_text_has_no_diff() {
+        assert_eq!(diff("a\nb\n", "a\nb\n"), "");
+    }
+
+    #[test]
+    fn test_insertion_keeps_later_lines_aligned() {
+        let old = "one\ntwo\nthree\nfour\n";
+        let new = "one\nnew\ntwo\nthree\nfour\n";
+        assert_eq!(
+            diff(old, new),
+            "--- a.prompt\n+++ a.prompt\n@@ -1,4 +1,5 @@\n one\n+new\n two\n three\n four\n"
+        );
+    }
+
+    #[test]
+    fn test_deletion_and_replacement() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nc\nD\n";
+        assert_eq!(
+            diff(old, new),
+            "--- a.prompt\n+++ a.prompt\n@@ -1,4 +1,3 @@\n a\n-b\n c\n-d\n+D\n"
+        );
+    }
+
+    #[test]
+    fn test_distant_changes_get_separate_hunks() {
+        let old: String = (1..=20).map(|i| i.to_string() + "\n").collect();
+        let new: String = (1..=20)
+            .map(|i| match i {
+                2 => "two\n".to_string(),
+                19 => "nineteen\n".to_string(),
+                _ => i.to_string() + "\n",
+            })
+            .collect();
+        assert_eq!(
+            diff(&old, &new),
+            "--- a.prompt\n+++ a.prompt\n\
+             @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+             @@ -16,5 +16,5 @@\n 16\n 17\n 18\n-19\n+nineteen\n 20\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_final_newline() {
+        assert_eq!(
+            diff("a\nb", "a\nb\n"),
+            "--- a.prompt\n+++ a.prompt\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_sides() {
+        assert_eq!(
+            diff("", "x\n"),
+            "--- a.prompt\n+++ a.prompt\n@@ -0,0 +1 @@\n+x\n"
+        );
+        assert_eq!(
+            diff("x\n", ""),
+            "--- a.prompt\n+++ a.prompt\n@@ -1 +0,0 @@\n-x\n"
+        );
+    }
+}
@@ -26,8 +26,29 @@
 //! - Trimmed trailing whitespace
 //! - Ensured final newline
 //! - Blank line between frontmatter and template
-
-use regex::Regex;
+//!
+//! Raw blocks and the content of `{{#verbatim}}` blocks are left as written.
+//!
+//! With [`FormatterConfig::sort_schema`], the fields of `input.schema` and
+//! `output.schema` are also sorted, so concurrent schema edits conflict less.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::frontmatter_edit::FrontmatterEditor;
+use crate::verbatim::verbatim_regions;
+
+/// How `fmt` orders schema fields.
+///
+/// Set with `sort-schema` in the `[fmt]` section of `promptly.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum SchemaOrder {
+    /// By field name.
+    Alphabetical,
+    /// Required fields first, then optional ones, each by name.
+    RequiredFirst,
+}
 
 /// Formatter configuration options.
 #[derive(Debug, Clone)]
@@ -41,6 +62,8 @@ pub(crate) struct FormatterConfig {
     pub trim_trailing_whitespace: bool,
     /// Whether to ensure a final newline.
     pub ensure_final_newline: bool,
+    /// How to sort schema fields, if at all.
+    pub sort_schema: Option<SchemaOrder>,
 }
 
 impl Default for FormatterConfig {
@@ -50,6 +73,7 @@ impl Default for FormatterConfig {
             handlebars_spacing: true,
             trim_trailing_whitespace: true,
             ensure_final_newline: true,
+            sort_schema: None,
         }
     }
 }
@@ -58,8 +82,6 @@ impl Default for FormatterConfig {
 #[derive(Debug)]
 pub(crate) struct Formatter {
     config: FormatterConfig,
-    /// Regex for matching Handlebars expressions without spacing.
-    expr_regex: Option<Regex>,
 }
 
 impl Default for Formatter {
@@ -71,11 +93,8 @@ impl Default for Formatter {
 impl Formatter {
     /// Creates a new formatter with the given configuration.
     #[must_use]
-    pub(crate) fn new(config: FormatterConfig) -> Self {
-        // Match {{ or {{# or {{/ or {{> followed by non-space content
-        let expr_regex = Regex::new(r"\{\{([#/>!]?)(\S)").ok();
-
-        Self { config, expr_regex }
+    pub(crate) const fn new(config: FormatterConfig) -> Self {
+        Self { config }
     }
 
     /// Formats a `.prompt` file source.
@@ -92,6 +111,7 @@ impl Formatter {
         let mut result = source.to_string();
 
         // Apply formatting rules
+        result = self.sort_schemas(&result);
         result = self.format_handlebars_spacing(&result);
         result = self.trim_trailing_whitespace(&result);
         result = self.normalize_frontmatter_spacing(&result);
@@ -100,47 +120,43 @@ impl Formatter {
         result
     }
 
+    /// Sorts the fields of the input and output schemas, if enabled.
+    ///
+    /// Sources whose frontmatter cannot be parsed are left unchanged.
+    fn sort_schemas(&self, source: &str) -> String {
+        let Some(order) = self.config.sort_schema else {
+            return source.to_string();
+        };
+        let Ok(mut editor) = FrontmatterEditor::new(source) else {
+            return source.to_string();
+        };
+        for path in ["input.schema", "output.schema"] {
+            // A schema that cannot be sorted is left as written.
+            let _ = editor.sort_schema(path, order == SchemaOrder::RequiredFirst);
+        }
+        editor.into_source()
+    }
+
     /// Adds spacing inside Handlebars expressions.
     ///
     /// This adds consistent spacing: `{{ variable }}` not `{{variable}}`.
     /// Block helpers preserve their prefix: `{{#if}}` stays as `{{#if }}`, not `{{# if }}`.
+    /// Whitespace control (`{{~x~}}`) stays attached to the braces, and
+    /// raw output (`{{{x}}}`), `{{!-- --}}` comments, escaped `\{{`, and
+    /// verbatim regions are left untouched.
     fn format_handlebars_spacing(&self, source: &str) -> String {
         if !self.config.handlebars_spacing {
             return source.to_string();
         }
 
-        let mut result = source.to_string();
-
-        // Add space after opening braces for simple expressions: {{x -> {{ x
-        // For block/partial/comment prefixes (#, /, >, !), keep them attached: {{#if -> {{#if
-        if let Some(re) = &self.expr_regex {
-            result = re
-                .replace_all(&result, |caps: &regex::Captures| {
-                    let prefix = caps.get(1).map_or("", |m| m.as_str());
-                    let first_char = caps.get(2).map_or("", |m| m.as_str());
-                    if prefix.is_empty() {
-                        // Simple expression like {{variable}} -> {{ variable
-                        format!("{{{{ {first_char}")
-                    } else {
-                        // Block/partial/comment like {{#if or {{/if -> no extra space after prefix
-                        format!("{{{{{prefix}{first_char}")
-                    }
-                })
-                .to_string();
-        }
-
-        // Add space before closing braces: x}} -> x }}
-        // But be careful not to add space after space
-        let closing_re = Regex::new(r"(\S)\}\}").ok();
-        if let Some(re) = closing_re {
-            result = re
-                .replace_all(&result, |caps: &regex::Captures| {
-                    let last_char = caps.get(1).map_or("", |m| m.as_str());
-                    format!("{last_char} }}}}")
-                })
-                .to_string();
+        let mut result = String::with_capacity(source.len());
+        let mut last = 0;
+        for region in verbatim_regions(source) {
+            result.push_str(&space_tags(&source[last..region.start]));
+            result.push_str(&source[region.clone()]);
+            last = region.end;
         }
-
+        result.push_str(&space_tags(&source[last..]));
         result
     }
 
@@ -151,7 +167,7 @@ impl Formatter {
         }
 
         source
-            .lines()
+            .split('\n')
             .map(str::trim_end)
             .collect::<Vec<_>>()
             .join("\n")
@@ -161,7 +177,7 @@ impl Formatter {
     #[allow(clippy::unused_self)] // May use config in future
     fn normalize_frontmatter_spacing(&self, source: &str) -> String {
         // Simple approach: find second --- and ensure blank line after
-        let lines: Vec<&str> = source.lines().collect();
+        let lines: Vec<&str> = source.split('\n').collect();
         let mut in_frontmatter = false;
         let mut output_lines: Vec<String> = Vec::new();
 
@@ -209,9 +225,123 @@ impl Formatter {
     }
 }
 
+/// Adds spacing inside the Handlebars tags of `source`.
+fn space_tags(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(open) = rest.find("{{") {
+        let (text, tag) = rest.split_at(open);
+        result.push_str(text);
+
+        let verbatim_end = if text.ends_with('\\') {
+            Some(2)
+        } else if tag.starts_with("{{{") {
+            tag.find("}}}").map(|end| end + 3)
+        } else if tag
+            .trim_start_matches("{{")
+            .trim_start_matches('~')
+            .starts_with("!--")
+        {
+            tag.find("--}}").map(|end| end + 4)
+        } else {
+            None
+        };
+        if let Some(end) = verbatim_end {
+            result.push_str(&tag[..end]);
+            rest = &tag[end..];
+            continue;
+        }
+
+        let Some(close) = tag.find("}}") else {
+            rest = tag;
+            break;
+        };
+        // A nested opener means this one is unterminated; keep it as text.
+        if let Some(inner) = tag[2..close].find("{{") {
+            result.push_str(&tag[..inner + 2]);
+            rest = &tag[inner + 2..];
+            continue;
+        }
+
+        result.push_str(&format_tag(&tag[2..close]));
+        rest = &tag[close + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Formats the inside of a `{{...}}` tag, returning the whole tag.
+fn format_tag(inner: &str) -> String {
+    let (open_trim, inner) = inner
+        .strip_prefix('~')
+        .map_or(("", inner), |stripped| ("~", stripped));
+    let (close_trim, inner) = inner
+        .strip_suffix('~')
+        .map_or(("", inner), |stripped| ("~", stripped));
+    let content = inner.trim();
+    if content.is_empty() {
+        return format!("{{{{{open_trim}{inner}{close_trim}}}}}");
+    }
+
+    // Decorators close up too: {{#* inline "x"}} becomes {{#*inline "x" }}
+    let content = content.strip_prefix("#*").map_or_else(
+        || content.to_string(),
+        |decorator| format!("#*{}", decorator.trim_start()),
+    );
+
+    // Block, partial, and comment prefixes stay attached: {{#if x }}
+    let attached = content.starts_with(['#', '/', '>', '!', '^', '&', '*']);
+    let space = if attached { "" } else { " " };
+    format!("{{{{{open_trim}{space}{content} {close_trim}}}}}")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    /// Strategy for prompt sources built from frontmatter, template tags,
+    /// text, and irregular whitespace.
+    fn prompt_source() -> impl Strategy<Value = String> {
+        let fragment = prop_oneof![
+            Just("---"),
+            Just("model: m"),
+            Just("{{name}}"),
+            Just("{{ name }}"),
+            Just("{{#if x}}"),
+            Just("{{/if}}"),
+            Just("{{> footer}}"),
+            Just("{{!note}}"),
+            Just("{{{raw}}}"),
+            Just("{{~trim~}}"),
+            Just("{{{{raw}}}}"),
+            Just("{{{{/raw}}}}"),
+            Just("{{#verbatim}}"),
+            Just("{{/verbatim}}"),
+            Just("text"),
+            Just("日本語"),
+            Just("🙂"),
+            Just("مرحبا"),
+            Just("{{名前}}"),
+            Just("\u{3000}"),
+            Just(" "),
+            Just("\t"),
+            Just("\n"),
+            Just("\r\n"),
+            Just("}}"),
+            Just("{{"),
+        ];
+        proptest::collection::vec(fragment, 0..24).prop_map(|fragments| fragments.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn test_format_is_idempotent(source in prompt_source()) {
+            let formatter = Formatter::default();
+            let once = formatter.format(&source);
+            prop_assert_eq!(formatter.format(&once), once);
+        }
+    }
 
     #[test]
     fn test_format_handlebars_spacing() {
@@ -225,6 +355,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_localized_prompt() {
+        let formatter = Formatter::default();
+        let source = "---\nmodel: m\n---\n{{role \"user\"}}\n日本語で{{名前}}さん🙂\u{3000}\nمرحبا {{#if عربي}}{{اسم}}{{/if}}";
+        assert_eq!(
+            formatter.format(source),
+            "---\nmodel: m\n---\n\n{{ role \"user\" }}\n日本語で{{ 名前 }}さん🙂\nمرحبا {{#if عربي }}{{ اسم }}{{/if }}\n"
+        );
+    }
+
     #[test]
     fn test_trim_trailing_whitespace() {
         let formatter = Formatter::default();
@@ -364,6 +504,66 @@ Hello {{name}}!
         );
     }
 
+    #[test]
+    fn test_format_leaves_raw_and_trim_markers_valid() {
+        let formatter = Formatter::default();
+
+        assert_eq!(formatter.format("{{~name~}}"), "{{~ name ~}}\n");
+        assert_eq!(formatter.format("{{~#if x}}"), "{{~#if x }}\n");
+        assert_eq!(formatter.format("{{{raw}}}"), "{{{raw}}}\n");
+        assert_eq!(formatter.format("{{!-- a --}}"), "{{!-- a --}}\n");
+        assert_eq!(formatter.format("\\{{name}}"), "\\{{name}}\n");
+        assert_eq!(formatter.format("a }} b {{"), "a }} b {{\n");
+    }
+
+    #[test]
+    fn test_format_leaves_verbatim_regions() {
+        let formatter = Formatter::default();
+
+        assert_eq!(
+            formatter.format("{{{{raw}}}}{{x}}{{{{/raw}}}} {{y}}"),
+            "{{{{raw}}}}{{x}}{{{{/raw}}}} {{ y }}\n"
+        );
+        assert_eq!(
+            formatter.format("{{#verbatim}}```\n{{x}}\n```{{/verbatim}}{{y}}"),
+            "{{#verbatim }}```\n{{x}}\n```{{/verbatim }}{{ y }}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_yaml_anchors() {
+        let formatter = Formatter::default();
+
+        let input =
+            "---\nbase: &base\n  temperature: 0.2\nconfig:\n  <<: *base\n  topK: 5\n---\n\nHello\n";
+        assert_eq!(formatter.format(input), input);
+    }
+
+    #[test]
+    fn test_format_sort_schema_is_opt_in() {
+        let input = "---\ninput:\n  schema:\n    b?: string\n    a: string\noutput:\n  schema:\n    y: string\n    x: string\n---\n\nHi\n";
+        assert_eq!(Formatter::default().format(input), input);
+
+        let formatter = Formatter::new(FormatterConfig {
+            sort_schema: Some(SchemaOrder::Alphabetical),
+            ..Default::default()
+        });
+        assert_eq!(
+            formatter.format(input),
+            "---\ninput:\n  schema:\n    a: string\n    b?: string\noutput:\n  schema:\n    x: string\n    y: string\n---\n\nHi\n"
+        );
+    }
+
+    #[test]
+    fn test_format_inline_partials() {
+        let formatter = Formatter::default();
+
+        assert_eq!(
+            formatter.format("{{#*  inline \"sig\"}}-- {{team}}{{/inline}}\n{{>sig}}"),
+            "{{#*inline \"sig\" }}-- {{ team }}{{/inline }}\n{{>sig }}\n"
+        );
+    }
+
     #[test]
     fn test_format_role_helper() {
         let formatter = Formatter::default();
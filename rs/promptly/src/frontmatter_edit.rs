@@ -0,0 +1,867 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Comment-preserving edits to `.prompt` frontmatter.
+//!
+//! Round-tripping through a YAML value would drop comments and reorder or
+//! restyle keys. Instead, the frontmatter is parsed into events that carry
+//! source spans, and each edit splices text at those spans. Everything
+//! outside the edited entry — comments, key order, quoting, indentation, and
+//! the template body — is left byte-for-byte intact.
+//!
+//! Fields are addressed by dot-separated mapping keys, e.g. `config.temperature`.
+
+use std::ops::Range;
+
+use saphyr_parser::{Event, Parser, ScalarStyle};
+
+/// A YAML node with byte ranges into the frontmatter text.
+#[derive(Debug)]
+enum Node {
+    /// A scalar. `implicit` is set for a missing value (`key:`).
+    Scalar {
+        range: Range<usize>,
+        value: String,
+        implicit: bool,
+    },
+    /// An alias to an anchored node (`*name`).
+    Alias { range: Range<usize> },
+    /// A mapping in block or flow style.
+    Mapping {
+        range: Range<usize>,
+        flow: bool,
+        entries: Vec<Entry>,
+    },
+    /// A sequence in block or flow style.
+    Sequence { range: Range<usize>, flow: bool },
+}
+
+impl Node {
+    /// Returns the node's byte range.
+    const fn range(&self) -> &Range<usize> {
+        match self {
+            Self::Scalar { range, .. }
+            | Self::Alias { range }
+            | Self::Mapping { range, .. }
+            | Self::Sequence { range, .. } => range,
+        }
+    }
+}
+
+/// A key/value pair in a mapping.
+#[derive(Debug)]
+struct Entry {
+    /// The key's text.
+    key: String,
+    /// Byte range of the key.
+    key_range: Range<usize>,
+    /// The value.
+    value: Node,
+}
+
+/// The byte range of the frontmatter YAML and its root node, if any.
+type Frontmatter = (Range<usize>, Option<Node>);
+
+/// Edits a prompt's frontmatter in place.
+///
+/// ```text
+/// let mut editor = FrontmatterEditor::new(source)?;
+/// editor.set("model", "gemini-2.0-pro")?;
+/// editor.set("config.temperature", "0.2")?;
+/// let updated = editor.into_source();
+/// ```
+#[derive(Debug)]
+pub(crate) struct FrontmatterEditor {
+    /// The full prompt source.
+    source: String,
+}
+
+impl FrontmatterEditor {
+    /// Creates an editor for a prompt source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the existing frontmatter is not valid YAML.
+    pub(crate) fn new(source: &str) -> Result<Self, String> {
+        let editor = Self {
+            source: source.to_string(),
+        };
+        editor.parse()?;
+        Ok(editor)
+    }
+
+    /// Returns the edited source.
+    #[must_use]
+    pub(crate) fn into_source(self) -> String {
+        self.source
+    }
+
+    /// Sets a field, creating it and any missing parent mappings.
+    ///
+    /// `value` is written as-is when it is a single-line YAML scalar or flow
+    /// collection (so `0.2` stays a number and `[a, b]` a list), and as a
+    /// double-quoted string otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty, a parent is not a mapping, or
+    /// the edit would produce invalid YAML.
+    pub(crate) fn set(&mut self, path: &str, value: &str) -> Result<(), String> {
+        let keys = split_path(path)?;
+        let value = yaml_value(value);
+
+        let Some((yaml, root)) = self.parse()? else {
+            // No frontmatter yet: add one.
+            let block = nested_block(&keys, &value, 0);
+            self.source = format!("---\n{block}---\n{}", self.source);
+            return Ok(());
+        };
+
+        let Some(root) = root else {
+            let block = nested_block(&keys, &value, 0);
+            return self.splice(yaml.end..yaml.end, &block);
+        };
+
+        let text = &self.source[yaml.clone()];
+        let mut node = &root;
+        for (depth, key) in keys.iter().enumerate() {
+            let Node::Mapping {
+                flow,
+                entries,
+                range,
+            } = node
+            else {
+                return Err(format!("'{}' is not a mapping", keys[..depth].join(".")));
+            };
+
+            let Some(entry) = entries.iter().find(|e| e.key == *key) else {
+                let rest = &keys[depth..];
+                let (at, insert) = if *flow {
+                    let close = range.end.saturating_sub(1);
+                    let separator = if entries.is_empty() { "" } else { ", " };
+                    (close, format!("{separator}{}", flow_entry(rest, &value)))
+                } else {
+                    let indent = entries.first().map_or(0, |e| {
+                        e.key_range.start - line_start(text, e.key_range.start)
+                    });
+                    let at = line_end(text, range.end);
+                    let newline = if at > 0 && !text[..at].ends_with('\n') {
+                        "\n"
+                    } else {
+                        ""
+                    };
+                    (
+                        at,
+                        format!("{newline}{}", nested_block(rest, &value, indent)),
+                    )
+                };
+                return self.splice(yaml.start + at..yaml.start + at, &insert);
+            };
+
+            if depth + 1 < keys.len() {
+                node = &entry.value;
+                continue;
+            }
+
+            let (range, replacement) = match &entry.value {
+                Node::Scalar {
+                    range,
+                    implicit: false,
+                    ..
+                }
+                | Node::Alias { range } => (range.clone(), value),
+                other => {
+                    // Replace everything after the key, including any block
+                    // collection or empty value, with an inline value.
+                    let colon = text[entry.key_range.end..]
+                        .find(':')
+                        .map_or(entry.key_range.end, |i| entry.key_range.end + i + 1);
+                    let end = other.range().end.max(colon);
+                    (entry.key_range.end..end, format!(": {value}"))
+                }
+            };
+            return self.splice(
+                yaml.start + range.start..yaml.start + range.end,
+                &replacement,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of a scalar field.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty.
+    pub(crate) fn get(&self, path: &str) -> Result<Option<String>, String> {
+        let keys = split_path(path)?;
+        let Some((_, Some(root))) = self.parse()? else {
+            return Ok(None);
+        };
+        Ok(match find_entry(&root, &keys) {
+            Some((
+                Entry {
+                    value:
+                        Node::Scalar {
+                            value,
+                            implicit: false,
+                            ..
+                        },
+                    ..
+                },
+                _,
+            )) => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    /// Returns the byte range of a field's key in the source.
+    pub(crate) fn key_range(&self, path: &str) -> Option<Range<usize>> {
+        let keys = split_path(path).ok()?;
+        let (yaml, root) = self.parse().ok()??;
+        let (entry, _) = find_entry(root.as_ref()?, &keys)?;
+        Some(yaml.start + entry.key_range.start..yaml.start + entry.key_range.end)
+    }
+
+    /// Removes a field.
+    ///
+    /// # Returns
+    ///
+    /// Whether the field existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty or the edit would produce
+    /// invalid YAML.
+    pub(crate) fn remove(&mut self, path: &str) -> Result<bool, String> {
+        let keys = split_path(path)?;
+        let Some((yaml, Some(root))) = self.parse()? else {
+            return Ok(false);
+        };
+        let Some((entry, flow)) = find_entry(&root, &keys) else {
+            return Ok(false);
+        };
+
+        let text = &self.source[yaml.clone()];
+        let value_end = entry.value.range().end.max(entry.key_range.end);
+        let range = if flow {
+            flow_entry_range(text, entry.key_range.start, value_end)
+        } else {
+            line_start(text, entry.key_range.start)..line_end(text, value_end)
+        };
+        self.splice(yaml.start + range.start..yaml.start + range.end, "")?;
+        Ok(true)
+    }
+
+    /// Renames a field, moving it if the new path has a different parent.
+    ///
+    /// A rename within the same mapping only rewrites the key, so the value
+    /// keeps its formatting. Moving to another mapping copies the value's
+    /// text, which is only supported for inline values.
+    ///
+    /// # Returns
+    ///
+    /// Whether the field existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either path is empty, the new path already exists,
+    /// or a block-style value would have to be moved.
+    pub(crate) fn rename(&mut self, from: &str, to: &str) -> Result<bool, String> {
+        let from_keys = split_path(from)?;
+        let to_keys = split_path(to)?;
+        let Some((yaml, Some(root))) = self.parse()? else {
+            return Ok(false);
+        };
+        let Some((entry, _)) = find_entry(&root, &from_keys) else {
+            return Ok(false);
+        };
+        if find_entry(&root, &to_keys).is_some() {
+            return Err(format!("'{to}' already exists"));
+        }
+
+        if from_keys[..from_keys.len() - 1] == to_keys[..to_keys.len() - 1] {
+            let key = to_keys[to_keys.len() - 1];
+            let range = entry.key_range.clone();
+            self.splice(yaml.start + range.start..yaml.start + range.end, key)?;
+            return Ok(true);
+        }
+
+        let inline = match &entry.value {
+            Node::Scalar { implicit, .. } => !implicit,
+            Node::Alias { .. } => true,
+            Node::Mapping { flow, .. } | Node::Sequence { flow, .. } => *flow,
+        };
+        if !inline {
+            return Err(format!(
+                "Cannot move block-style value of '{from}' to '{to}'; rename it within its mapping instead"
+            ));
+        }
+        let range = entry.value.range();
+        let value = self.source[yaml.start + range.start..yaml.start + range.end].to_string();
+
+        let mut moved = Self {
+            source: self.source.clone(),
+        };
+        moved.set(to, &value)?;
+        moved.remove(from)?;
+        *self = moved;
+        Ok(true)
+    }
+
+    /// Sorts the fields of the schema at `path`, recursing into nested
+    /// objects.
+    ///
+    /// Picoschema fields are ordered by name, ignoring their `?` and
+    /// `(type)` suffixes, with the `(*)` wildcard last; `required_first`
+    /// moves optional fields after required ones. In JSON Schema objects
+    /// only `properties` is sorted. Comments move with the field below them,
+    /// and flow-style mappings are left as written.
+    ///
+    /// # Returns
+    ///
+    /// Whether the source changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty.
+    pub(crate) fn sort_schema(&mut self, path: &str, required_first: bool) -> Result<bool, String> {
+        let keys = split_path(path)?;
+        let Some((yaml, Some(root))) = self.parse()? else {
+            return Ok(false);
+        };
+        let Some((entry, _)) = find_entry(&root, &keys) else {
+            return Ok(false);
+        };
+        let text = &self.source[yaml.clone()];
+        let Some((range, sorted)) =
+            sorted_schema(text, &entry.value, SchemaKind::Auto, required_first)
+        else {
+            return Ok(false);
+        };
+        if text[range.clone()] == sorted {
+            return Ok(false);
+        }
+        self.splice(yaml.start + range.start..yaml.start + range.end, &sorted)?;
+        Ok(true)
+    }
+
+    /// Replaces `range` of the source, rejecting edits that break the YAML.
+    fn splice(&mut self, range: Range<usize>, replacement: &str) -> Result<(), String> {
+        let mut edited = self.source.clone();
+        edited.replace_range(range, replacement);
+        let previous = std::mem::replace(&mut self.source, edited);
+        if let Err(e) = self.parse() {
+            self.source = previous;
+            return Err(format!("Edit would produce invalid frontmatter: {e}"));
+        }
+        Ok(())
+    }
+
+    /// Parses the frontmatter.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the source has no frontmatter, otherwise the byte range of
+    /// the YAML text and its root node (`None` for empty frontmatter).
+    fn parse(&self) -> Result<Option<Frontmatter>, String> {
+        let Some(range) = frontmatter_range(&self.source) else {
+            return Ok(None);
+        };
+        let root = parse_yaml(&self.source[range.clone()])?;
+        Ok(Some((range, root)))
+    }
+}
+
+/// Finds the YAML text between the `---` delimiters.
+fn frontmatter_range(source: &str) -> Option<Range<usize>> {
+    let mut lines = source.split_inclusive('\n');
+    let first = lines.next()?;
+    if first.trim() != "---" {
+        return None;
+    }
+    let start = first.len();
+    let mut offset = start;
+    for line in lines {
+        if line.trim() == "---" {
+            return Some(start..offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Parses YAML text into nodes.
+fn parse_yaml(yaml: &str) -> Result<Option<Node>, String> {
+    // The parser reports character indices; map them to byte offsets.
+    let offsets: Vec<usize> = yaml
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(yaml.len()))
+        .collect();
+    let byte = |index: usize| offsets.get(index).copied().unwrap_or(yaml.len());
+
+    let mut events = Vec::new();
+    for event in Parser::new_from_str(yaml) {
+        let (event, span) = event.map_err(|e| format!("Invalid YAML frontmatter: {e}"))?;
+        if !matches!(
+            event,
+            Event::Nothing
+                | Event::StreamStart
+                | Event::StreamEnd
+                | Event::DocumentStart(_)
+                | Event::DocumentEnd
+        ) {
+            events.push((event, byte(span.start.index())..byte(span.end.index())));
+        }
+    }
+
+    let mut events = events.into_iter();
+    events
+        .next()
+        .map(|first| build_node(first, &mut events))
+        .transpose()
+}
+
+/// Builds a node from its first event, consuming the rest of its events.
+fn build_node<'a>(
+    (event, range): (Event<'a>, Range<usize>),
+    events: &mut impl Iterator<Item = (Event<'a>, Range<usize>)>,
+) -> Result<Node, String> {
+    let truncated = || "Unexpected end of YAML frontmatter".to_string();
+    match event {
+        Event::Scalar(value, style, ..) => Ok(Node::Scalar {
+            implicit: style == ScalarStyle::Plain && range.is_empty() && value == "~",
+            value: value.into_owned(),
+            range,
+        }),
+        Event::Alias(_) => Ok(Node::Alias { range }),
+        Event::MappingStart(..) => {
+            // Block collections have empty start spans; flow ones span `{`.
+            let flow = !range.is_empty();
+            let mut entries: Vec<Entry> = Vec::new();
+            let mut end = range.end;
+            loop {
+                let (event, key_range) = events.next().ok_or_else(truncated)?;
+                let key = match event {
+                    Event::MappingEnd => {
+                        if flow {
+                            end = key_range.end;
+                        }
+                        break;
+                    }
+                    Event::Scalar(key, ..) => key.into_owned(),
+                    _ => return Err("Only scalar mapping keys can be edited".to_string()),
+                };
+                let value = build_node(events.next().ok_or_else(truncated)?, events)?;
+                if !flow {
+                    end = value.range().end.max(key_range.end);
+                }
+                entries.push(Entry {
+                    key,
+                    key_range,
+                    value,
+                });
+            }
+            let start = if flow {
+                range.start
+            } else {
+                entries.first().map_or(range.start, |e| e.key_range.start)
+            };
+            Ok(Node::Mapping {
+                range: start..end,
+                flow,
+                entries,
+            })
+        }
+        Event::SequenceStart(..) => {
+            let flow = !range.is_empty();
+            let mut end = range.end;
+            loop {
+                let item = events.next().ok_or_else(truncated)?;
+                if matches!(item.0, Event::SequenceEnd) {
+                    if flow {
+                        end = item.1.end;
+                    }
+                    break;
+                }
+                let item = build_node(item, events)?;
+                if !flow {
+                    end = item.range().end;
+                }
+            }
+            Ok(Node::Sequence {
+                range: range.start..end,
+                flow,
+            })
+        }
+        _ => Err("Unexpected YAML structure in frontmatter".to_string()),
+    }
+}
+
+/// What a mapping holds, for [`sorted_schema`].
+#[derive(Debug, Clone, Copy)]
+enum SchemaKind {
+    /// Picoschema fields, or a JSON Schema object if it has JSON Schema
+    /// keywords.
+    Auto,
+    /// A JSON Schema object, whose keywords keep their order.
+    Json,
+    /// The `properties` of a JSON Schema object.
+    Properties,
+}
+
+/// Returns the text range of a block mapping and its text with the schema
+/// fields sorted, or `None` if the node cannot be sorted.
+fn sorted_schema(
+    text: &str,
+    node: &Node,
+    kind: SchemaKind,
+    required_first: bool,
+) -> Option<(Range<usize>, String)> {
+    let Node::Mapping {
+        entries,
+        flow: false,
+        ..
+    } = node
+    else {
+        return None;
+    };
+    let first = entries.first()?;
+    let start = line_start(text, first.key_range.start);
+    // A mapping that starts mid-line, such as a sequence item, cannot be
+    // split into lines.
+    if !text[start..first.key_range.start].trim().is_empty() {
+        return None;
+    }
+
+    let kind = match kind {
+        SchemaKind::Auto
+            if entries.iter().any(|e| {
+                matches!(
+                    e.key.as_str(),
+                    "type" | "$ref" | "properties" | "anyOf" | "oneOf" | "allOf"
+                )
+            }) =>
+        {
+            SchemaKind::Json
+        }
+        kind => kind,
+    };
+
+    // Each entry takes the comments and blank lines above it along.
+    let mut chunks = Vec::new();
+    let mut chunk_start = start;
+    for entry in entries {
+        let end = line_end(text, entry.value.range().end.max(entry.key_range.end));
+        let mut chunk = text[chunk_start..end].to_string();
+        if !chunk.ends_with('\n') {
+            return None;
+        }
+        let nested = match (kind, entry.key.as_str()) {
+            (SchemaKind::Auto, _) => {
+                sorted_schema(text, &entry.value, SchemaKind::Auto, required_first)
+            }
+            (SchemaKind::Json, "properties") => {
+                sorted_schema(text, &entry.value, SchemaKind::Properties, required_first)
+            }
+            (SchemaKind::Json, "items") | (SchemaKind::Properties, _) => {
+                sorted_schema(text, &entry.value, SchemaKind::Json, required_first)
+            }
+            (SchemaKind::Json, _) => None,
+        };
+        if let Some((range, sorted)) = nested {
+            chunk.replace_range(range.start - chunk_start..range.end - chunk_start, &sorted);
+        }
+        chunks.push((field_order(&entry.key, required_first), chunk));
+        chunk_start = end;
+    }
+    if !matches!(kind, SchemaKind::Json) {
+        chunks.sort_by_key(|(order, _)| *order);
+    }
+    let sorted = chunks.into_iter().map(|(_, chunk)| chunk).collect();
+    Some((start..chunk_start, sorted))
+}
+
+/// Returns the sort key of a schema field: its group (required, optional,
+/// wildcard) and its name.
+fn field_order(key: &str, required_first: bool) -> (u8, &str) {
+    let name = key.split('(').next().unwrap_or(key).trim_end();
+    if name.is_empty() {
+        return (2, key);
+    }
+    let optional = name.ends_with('?');
+    let name = name.trim_end_matches('?');
+    (u8::from(optional && required_first), name)
+}
+
+/// Finds the entry at `keys`, and whether its mapping is flow-style.
+fn find_entry<'a>(root: &'a Node, keys: &[&str]) -> Option<(&'a Entry, bool)> {
+    let (last, parents) = keys.split_last()?;
+    let mut node = root;
+    for key in parents {
+        let Node::Mapping { entries, .. } = node else {
+            return None;
+        };
+        node = &entries.iter().find(|e| e.key == *key)?.value;
+    }
+    let Node::Mapping { entries, flow, .. } = node else {
+        return None;
+    };
+    entries.iter().find(|e| e.key == *last).map(|e| (e, *flow))
+}
+
+/// Splits a dotted path into keys.
+fn split_path(path: &str) -> Result<Vec<&str>, String> {
+    let keys: Vec<&str> = path.split('.').collect();
+    if keys.iter().any(|k| k.trim().is_empty()) {
+        return Err(format!("Invalid field path '{path}'"));
+    }
+    Ok(keys)
+}
+
+/// Formats a user-supplied value as YAML.
+fn yaml_value(value: &str) -> String {
+    let value = value.trim();
+    let is_inline = !value.is_empty()
+        && !value.contains('\n')
+        && match serde_yaml_ng::from_str::<serde_yaml_ng::Value>(value) {
+            // `a: b` would become a nested mapping; only allow explicit `{...}`.
+            Ok(serde_yaml_ng::Value::Mapping(_)) => value.starts_with('{'),
+            Ok(_) => true,
+            Err(_) => false,
+        };
+    if is_inline {
+        value.to_string()
+    } else {
+        serde_json::to_string(value).unwrap_or_default()
+    }
+}
+
+/// Renders block-style lines that set `keys` to `value`.
+fn nested_block(keys: &[&str], value: &str, indent: usize) -> String {
+    keys.iter()
+        .enumerate()
+        .map(|(depth, key)| {
+            let pad = " ".repeat(indent + depth * 2);
+            if depth + 1 == keys.len() {
+                format!("{pad}{key}: {value}\n")
+            } else {
+                format!("{pad}{key}:\n")
+            }
+        })
+        .collect()
+}
+
+/// Renders a flow-style entry that sets `keys` to `value`.
+fn flow_entry(keys: &[&str], value: &str) -> String {
+    match keys {
+        [] => value.to_string(),
+        [key] => format!("{key}: {value}"),
+        [key, rest @ ..] => format!("{key}: {{{}}}", flow_entry(rest, value)),
+    }
+}
+
+/// Returns the range of a flow mapping entry including one adjacent comma.
+fn flow_entry_range(text: &str, start: usize, end: usize) -> Range<usize> {
+    let after = &text[end..];
+    let trailing = after.len() - after.trim_start().len();
+    if after.trim_start().starts_with(',') {
+        let rest = &after[trailing + 1..];
+        return start..end + trailing + 1 + (rest.len() - rest.trim_start().len());
+    }
+    let before = text[..start].trim_end();
+    before
+        .strip_suffix(',')
+        .map_or(start..end, |kept| kept.len()..end)
+}
+
+/// Returns the offset of the start of the line containing `offset`.
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Returns the offset just past the line containing `offset`.
+///
+/// An offset already at the start of a line is returned unchanged.
+fn line_end(text: &str, offset: usize) -> usize {
+    if offset == 0 || text[..offset].ends_with('\n') {
+        return offset;
+    }
+    text[offset..]
+        .find('\n')
+        .map_or(text.len(), |i| offset + i + 1)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn set(source: &str, path: &str, value: &str) -> String {
+        let mut editor = FrontmatterEditor::new(source).unwrap();
+        editor.set(path, value).unwrap();
+        editor.into_source()
+    }
+
+    #[test]
+    fn test_set_preserves_comments_and_order() {
+        let source = "---\n# Model choice\nmodel: gemini-1.5-pro # fast enough\nconfig:\n  temperature: 0.7\n---\nHello {{name}}\n";
+
+        assert_eq!(
+            set(source, "model", "gemini-2.0-pro"),
+            "---\n# Model choice\nmodel: gemini-2.0-pro # fast enough\nconfig:\n  temperature: 0.7\n---\nHello {{name}}\n"
+        );
+        assert_eq!(
+            set(source, "config.temperature", "0.2"),
+            "---\n# Model choice\nmodel: gemini-1.5-pro # fast enough\nconfig:\n  temperature: 0.2\n---\nHello {{name}}\n"
+        );
+    }
+
+    #[test]
+    fn test_set_adds_missing_fields() {
+        let source = "---\nmodel: gemini\nconfig:\n  temperature: 0.7\n---\nHi\n";
+
+        assert_eq!(
+            set(source, "config.topK", "40"),
+            "---\nmodel: gemini\nconfig:\n  temperature: 0.7\n  topK: 40\n---\nHi\n"
+        );
+        assert_eq!(
+            set(source, "output.format", "json"),
+            "---\nmodel: gemini\nconfig:\n  temperature: 0.7\noutput:\n  format: json\n---\nHi\n"
+        );
+        assert_eq!(
+            set(
+                "---\nconfig: {temperature: 0.7}\n---\n",
+                "config.topK",
+                "40"
+            ),
+            "---\nconfig: {temperature: 0.7, topK: 40}\n---\n"
+        );
+        assert_eq!(
+            set("Hi\n", "model", "gemini"),
+            "---\nmodel: gemini\n---\nHi\n"
+        );
+        assert_eq!(
+            set("---\n---\nHi\n", "model", "gemini"),
+            "---\nmodel: gemini\n---\nHi\n"
+        );
+    }
+
+    #[test]
+    fn test_set_replaces_collections_and_quotes_values() {
+        let source = "---\ntools:\n  - a\n  - b\ndescription:\n---\n";
+
+        assert_eq!(
+            set(source, "tools", "[search]"),
+            "---\ntools: [search]\ndescription:\n---\n"
+        );
+        assert_eq!(
+            set(source, "description", "Note: be brief"),
+            "---\ntools:\n  - a\n  - b\ndescription: \"Note: be brief\"\n---\n"
+        );
+
+        let mut editor = FrontmatterEditor::new("---\nmodel: gemini\n---\n").unwrap();
+        assert!(editor.set("model.name", "x").is_err());
+    }
+
+    #[test]
+    fn test_remove() {
+        let source = "---\nmodel: gemini\nconfig:\n  temperature: 0.7\n  topK: 40\nmeta: {a: 1, b: 2}\n---\n";
+        let mut editor = FrontmatterEditor::new(source).unwrap();
+
+        assert!(editor.remove("config.temperature").unwrap());
+        assert!(editor.remove("meta.a").unwrap());
+        assert!(!editor.remove("missing").unwrap());
+        assert_eq!(
+            editor.into_source(),
+            "---\nmodel: gemini\nconfig:\n  topK: 40\nmeta: {b: 2}\n---\n"
+        );
+    }
+
+    #[test]
+    fn test_key_range() {
+        let source = "---\nmodel: a\noutput: {format: json}\n---\nHi";
+        let editor = FrontmatterEditor::new(source).unwrap();
+        let range = editor.key_range("output.format").unwrap();
+        assert_eq!(&source[range], "format");
+        assert!(editor.key_range("output.schema").is_none());
+    }
+
+    #[test]
+    fn test_get_and_rename() {
+        let source =
+            "---\nmodel: gemini-1.5-pro\nconfig:\n  maxTokens: 100 # budget\n  stop: [x]\n---\n";
+        let mut editor = FrontmatterEditor::new(source).unwrap();
+
+        assert_eq!(
+            editor.get("model").unwrap().as_deref(),
+            Some("gemini-1.5-pro")
+        );
+        assert_eq!(editor.get("config").unwrap(), None);
+        assert!(
+            editor
+                .rename("config.maxTokens", "config.maxOutputTokens")
+                .unwrap()
+        );
+        assert!(editor.rename("config.stop", "stop").unwrap());
+        assert!(!editor.rename("config.missing", "config.other").unwrap());
+        assert!(editor.rename("model", "stop").is_err());
+        assert_eq!(
+            editor.into_source(),
+            "---\nmodel: gemini-1.5-pro\nconfig:\n  maxOutputTokens: 100 # budget\nstop: [x]\n---\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_schema() {
+        let source = "---\ninput:\n  schema:\n    zip?: string\n    # The full name\n    name: string, the name\n    (*): any\n    address(object):\n      street: string\n      city: string\n    age?: integer\n---\nHi\n";
+        let sort = |required_first| {
+            let mut editor = FrontmatterEditor::new(source).unwrap();
+            assert!(editor.sort_schema("input.schema", required_first).unwrap());
+            editor.into_source()
+        };
+
+        assert_eq!(
+            sort(false),
+            "---\ninput:\n  schema:\n    address(object):\n      city: string\n      street: string\n    age?: integer\n    # The full name\n    name: string, the name\n    zip?: string\n    (*): any\n---\nHi\n"
+        );
+        assert_eq!(
+            sort(true),
+            "---\ninput:\n  schema:\n    address(object):\n      city: string\n      street: string\n    # The full name\n    name: string, the name\n    age?: integer\n    zip?: string\n    (*): any\n---\nHi\n"
+        );
+
+        let mut editor = FrontmatterEditor::new(&sort(false)).unwrap();
+        assert!(!editor.sort_schema("input.schema", false).unwrap());
+        assert!(!editor.sort_schema("output.schema", false).unwrap());
+    }
+
+    #[test]
+    fn test_sort_json_schema_properties() {
+        let source = "---\noutput:\n  schema:\n    type: object\n    properties:\n      b:\n        type: string\n        description: B\n      a: {type: number}\n    required: [a]\n  format: json\n---\n";
+        let mut editor = FrontmatterEditor::new(source).unwrap();
+        assert!(editor.sort_schema("output.schema", true).unwrap());
+        assert_eq!(
+            editor.into_source(),
+            "---\noutput:\n  schema:\n    type: object\n    properties:\n      a: {type: number}\n      b:\n        type: string\n        description: B\n    required: [a]\n  format: json\n---\n"
+        );
+
+        let mut editor =
+            FrontmatterEditor::new("---\ninput:\n  schema: {b: string, a: string}\n---\n").unwrap();
+        assert!(!editor.sort_schema("input.schema", false).unwrap());
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Git integration: staged files and hook locations.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tracing::debug;
+
+/// Runs `git` with the given arguments and returns its standard output.
+fn git(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| format!("git produced invalid UTF-8: {e}"))
+}
+
+/// Returns the root of the current repository's working tree.
+pub(crate) fn toplevel() -> Result<PathBuf, String> {
+    let root = git(&["rev-parse", "--show-toplevel"])?;
+    fs::canonicalize(root.trim()).map_err(|e| format!("Failed to resolve repository root: {e}"))
+}
+
+/// Returns the path of a hook in the current repository, honoring
+/// `core.hooksPath`.
+pub(crate) fn hook_path(name: &str) -> Result<PathBuf, String> {
+    let path = git(&["rev-parse", "--git-path", &format!("hooks/{name}")])?;
+    Ok(PathBuf::from(path.trim()))
+}
+
+/// Returns the staged `.prompt` files that lie under any of `paths`.
+///
+/// Deleted files are skipped; added, copied, modified, and renamed files are
+/// returned as absolute paths to their working-tree copies. Read their
+/// staged content with [`staged_content`].
+pub(crate) fn staged_prompt_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let scopes = paths
+        .iter()
+        .map(|p| fs::canonicalize(p).map_err(|_| format!("Path does not exist: {}", p.display())))
+        .collect::<Result<Vec<_>, String>>()?;
+    let root = toplevel()?;
+    let staged = git(&[
+        "diff",
+        "--cached",
+        "--name-only",
+        "--diff-filter=ACMR",
+        "-z",
+    ])?;
+
+    let files: Vec<PathBuf> = staged
+        .split('\0')
+        .filter(|name| {
+            Path::new(name)
+                .extension()
+                .is_some_and(|ext| ext == "prompt")
+        })
+        .map(|name| root.join(name))
+        .filter(|path| scopes.iter().any(|scope| path.starts_with(scope)))
+        .collect();
+    debug!(count = files.len(), "staged prompt files");
+    Ok(files)
+}
+
+/// Returns the staged content of a file returned by [`staged_prompt_files`],
+/// which may differ from its working-tree copy.
+pub(crate) fn staged_content(path: &Path) -> Result<String, String> {
+    let root = toplevel()?;
+    let name = path.strip_prefix(&root).unwrap_or(path);
+    git(&["show", &format!(":{}", name.display())])
+}
@@ -0,0 +1,187 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Custom helper declarations.
+//!
+//! Projects register their own Handlebars helpers at runtime, which tooling
+//! cannot see. Declaring them in `promptly.toml` or in a prompt's `helpers:`
+//! frontmatter list tells the linter and language server they exist:
+//!
+//! ```yaml
+//! helpers:
+//!   - today
+//!   - name: card
+//!     block: true
+//!     description: Renders its contents as a card.
+//! ```
+//!
+//! Helpers described by a [`HelperManifest`](dotprompt::HelperManifest)
+//! (`helpers.toml`) are declared the same way.
+
+use dotprompt::HelperSpec;
+use dotprompt::parse::{extract_frontmatter_and_body, parse_frontmatter_lenient};
+use serde::{Deserialize, Serialize};
+
+/// A custom helper declared by the project or prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "RawHelperDecl")]
+pub(crate) struct HelperDecl {
+    /// The helper name.
+    pub name: String,
+    /// Whether the helper is used as a block (`{{#name}}...{{/name}}`).
+    pub block: bool,
+    /// How the helper is called, e.g. `formatDate date [format]`.
+    pub signature: Option<String>,
+    /// What the helper does, shown on hover.
+    pub description: Option<String>,
+}
+
+/// A helper declaration as written: a bare name or a table.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawHelperDecl {
+    Name(String),
+    Full {
+        name: String,
+        #[serde(default)]
+        block: bool,
+        signature: Option<String>,
+        description: Option<String>,
+    },
+}
+
+impl From<RawHelperDecl> for HelperDecl {
+    fn from(raw: RawHelperDecl) -> Self {
+        match raw {
+            RawHelperDecl::Name(name) => Self {
+                name,
+                block: false,
+                signature: None,
+                description: None,
+            },
+            RawHelperDecl::Full {
+                name,
+                block,
+                signature,
+                description,
+            } => Self {
+                name,
+                block,
+                signature,
+                description,
+            },
+        }
+    }
+}
+
+impl From<HelperSpec> for HelperDecl {
+    fn from(spec: HelperSpec) -> Self {
+        Self {
+            name: spec.name,
+            block: spec.block,
+            signature: spec.signature,
+            description: spec.doc,
+        }
+    }
+}
+
+impl HelperDecl {
+    /// Renders hover documentation for the helper.
+    #[must_use]
+    pub(crate) fn docs(&self) -> String {
+        let call = self.signature.as_deref().unwrap_or(&self.name);
+        let usage = if self.block {
+            format!("{{{{#{call}}}}}...{{{{/{}}}}}", self.name)
+        } else {
+            format!("{{{{{call}}}}}")
+        };
+        let description = self
+            .description
+            .as_deref()
+            .unwrap_or("Custom helper declared by this project.");
+        format!("## `{usage}`\n\n{description}")
+    }
+}
+
+/// Returns the helpers declared in a prompt's `helpers:` frontmatter field.
+///
+/// Malformed declarations are ignored; the prompt's own diagnostics report
+/// invalid frontmatter. A YAML error elsewhere in the frontmatter does not
+/// hide a well-formed `helpers:` field.
+#[must_use]
+pub(crate) fn frontmatter_helpers(source: &str) -> Vec<HelperDecl> {
+    extract_frontmatter_and_body(source)
+        .ok()
+        .map(|(yaml, _)| parse_frontmatter_lenient(&yaml))
+        .and_then(|mut value| value.get_mut("helpers").map(std::mem::take))
+        .and_then(|helpers| serde_yaml_ng::from_value(helpers).ok())
+        .unwrap_or_default()
+}
+
+/// Combines project helpers with those declared by a prompt.
+///
+/// A prompt's declaration wins over a project one with the same name.
+#[must_use]
+pub(crate) fn declared_helpers(project: &[HelperDecl], source: &str) -> Vec<HelperDecl> {
+    let mut helpers = frontmatter_helpers(source);
+    for helper in project {
+        if !helpers.iter().any(|h| h.name == helper.name) {
+            helpers.push(helper.clone());
+        }
+    }
+    helpers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frontmatter_helpers() {
+        let source = "---\nhelpers:\n  - today\n  - name: card\n    block: true\n    description: A card.\n---\n{{today}}";
+        let helpers = frontmatter_helpers(source);
+
+        assert_eq!(helpers.len(), 2);
+        assert_eq!(helpers[0].name, "today");
+        assert!(!helpers[0].block);
+        assert_eq!(helpers[1].name, "card");
+        assert!(helpers[1].block);
+        assert_eq!(helpers[1].docs(), "## `{{#card}}...{{/card}}`\n\nA card.");
+    }
+
+    #[test]
+    fn test_prompt_declarations_override_project() {
+        let project = vec![
+            HelperDecl::from(RawHelperDecl::Name("card".to_string())),
+            HelperDecl::from(RawHelperDecl::Name("today".to_string())),
+        ];
+        let source = "---\nhelpers:\n  - name: card\n    block: true\n---\n";
+        let helpers = declared_helpers(&project, source);
+
+        assert_eq!(helpers.len(), 2);
+        assert!(helpers.iter().any(|h| h.name == "card" && h.block));
+        assert!(helpers.iter().any(|h| h.name == "today" && !h.block));
+        assert!(frontmatter_helpers("No frontmatter").is_empty());
+    }
+
+    #[test]
+    fn test_helpers_survive_yaml_errors_elsewhere() {
+        let source = "---\nhelpers:\n  - today\nconfig: {temperature: \n---\n{{today}}";
+        let helpers = frontmatter_helpers(source);
+        assert_eq!(helpers.len(), 1);
+        assert_eq!(helpers[0].name, "today");
+    }
+}
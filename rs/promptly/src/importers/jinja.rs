@@ -0,0 +1,431 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Translation of a practical subset of Jinja2 into dotprompt Handlebars.
+//!
+//! | Jinja2 | dotprompt |
+//! |--------|-----------|
+//! | `{{ user.name }}`, `{{ items[0] }}` | `{{user.name}}`, `{{items.[0]}}` |
+//! | `{{ data \| tojson }}` | `{{json data}}` |
+//! | `{% if x %}`, `{% elif y %}`, `{% else %}` | `{{#if x}}`, `{{else if y}}`, `{{else}}` |
+//! | `{% if not x %}` | `{{#unless x}}` |
+//! | `{% if x == "a" %}`, `{% if x != "a" %}` | `{{#ifEquals x "a"}}`, `{{#unlessEquals x "a"}}` |
+//! | `{% for item in items %}` | `{{#each items as \|item\|}}` |
+//! | `{% for key, value in map.items() %}` | `{{#each map as \|value key\|}}` |
+//! | `loop.index0`, `loop.first`, `loop.last` | `@index`, `@first`, `@last` |
+//! | `{# note #}` | `{{!-- note --}}` |
+//! | `{%-`, `-%}` | `{{~`, `~}}` |
+//!
+//! Everything else (filters, macros, `set`, `include`, arithmetic) is
+//! reported and left behind as a comment.
+
+use super::{ImportIssue, Imported};
+
+/// The block helpers that `{% if %}` translates into.
+const IF_HELPERS: [&str; 4] = ["if", "unless", "ifEquals", "unlessEquals"];
+
+/// Tag delimiters: opener, closer.
+const TAGS: [(&str, &str); 3] = [("{{", "}}"), ("{%", "%}"), ("{#", "#}")];
+
+/// Translates a Jinja2 template.
+#[must_use]
+pub(crate) fn translate(template: &str) -> Imported {
+    let mut translator = Translator {
+        template,
+        output: String::with_capacity(template.len()),
+        issues: Vec::new(),
+        blocks: Vec::new(),
+    };
+    translator.run();
+    translator.finish()
+}
+
+/// An open block: the Handlebars helper that closes it, or `None` when the
+/// opening tag was not translated.
+type Block = Option<&'static str>;
+
+/// Translation state.
+struct Translator<'a> {
+    template: &'a str,
+    output: String,
+    issues: Vec<ImportIssue>,
+    blocks: Vec<Block>,
+}
+
+impl Translator<'_> {
+    /// Translates every tag, copying the text between tags as written.
+    fn run(&mut self) {
+        let mut offset = 0;
+        while let Some((start, (open, close))) = TAGS
+            .iter()
+            .filter_map(|&(open, close)| {
+                self.template[offset..]
+                    .find(open)
+                    .map(|i| (offset + i, (open, close)))
+            })
+            .min_by_key(|(start, _)| *start)
+        {
+            self.output.push_str(&self.template[offset..start]);
+            let inner_start = start + open.len();
+            let Some(end) = self.template[inner_start..].find(close) else {
+                let rest = &self.template[start..];
+                self.untranslated(start, rest, "the tag is never closed");
+                return;
+            };
+            let end = inner_start + end + close.len();
+            let tag = &self.template[start..end];
+            let inner = &tag[open.len()..tag.len() - close.len()];
+            match open {
+                "{#" => {
+                    let comment = inner.trim_matches('-').trim();
+                    self.comment(comment);
+                }
+                "{{" => self.expression(start, tag, inner),
+                _ => self.statement(start, tag, inner),
+            }
+            offset = end;
+        }
+        self.output.push_str(&self.template[offset..]);
+    }
+
+    /// Reports blocks that were never closed and returns the result.
+    fn finish(mut self) -> Imported {
+        if self.blocks.iter().any(Option::is_some) {
+            self.issues.push(ImportIssue {
+                line: self.template.lines().count().max(1),
+                construct: String::new(),
+                message: "the template ends inside an unclosed block".to_string(),
+            });
+        }
+        Imported {
+            template: self.output,
+            issues: self.issues,
+            frontmatter: None,
+        }
+    }
+
+    /// Translates a `{{ ... }}` expression.
+    fn expression(&mut self, start: usize, tag: &str, inner: &str) {
+        let (body, trim) = whitespace_control(inner);
+        let mut parts = body.split('|').map(str::trim);
+        let value = parts.next().and_then(path);
+        let translated = match (value, parts.next(), parts.next()) {
+            (Some(value), None, _) => Some(value),
+            (Some(value), Some("tojson"), None) => Some(format!("json {value}")),
+            _ => None,
+        };
+        match translated {
+            Some(expression) => self.emit(&expression, trim),
+            None => self.untranslated(start, tag, "only variables and `tojson` are supported"),
+        }
+    }
+
+    /// Translates a `{% ... %}` statement.
+    fn statement(&mut self, start: usize, tag: &str, inner: &str) {
+        let (body, trim) = whitespace_control(inner);
+        let (keyword, rest) = body
+            .split_once(char::is_whitespace)
+            .map_or((body, ""), |(keyword, rest)| (keyword, rest.trim()));
+        match keyword {
+            "if" => {
+                if let Some((helper, args)) = condition(rest) {
+                    self.emit(&format!("#{helper} {args}"), trim);
+                    self.blocks.push(Some(helper));
+                } else {
+                    self.untranslated(start, tag, "unsupported condition");
+                    self.blocks.push(None);
+                }
+            }
+            "elif" => match (self.blocks.last().copied(), condition(rest)) {
+                (Some(Some(block)), Some((helper, args))) if IF_HELPERS.contains(&block) => {
+                    self.emit(&format!("else {helper} {args}"), trim);
+                }
+                (Some(None), _) => self.untranslated_quietly(tag),
+                (Some(Some(block)), None) if IF_HELPERS.contains(&block) => {
+                    self.untranslated(start, tag, "unsupported condition");
+                }
+                _ => self.untranslated(start, tag, "`elif` outside an `if`"),
+            },
+            "else" if rest.is_empty() => match self.blocks.last() {
+                Some(Some(_)) => self.emit("else", trim),
+                Some(None) => self.untranslated_quietly(tag),
+                None => self.untranslated(start, tag, "`else` outside a block"),
+            },
+            "for" => {
+                if let Some(args) = each(rest) {
+                    self.emit(&format!("#each {args}"), trim);
+                    self.blocks.push(Some("each"));
+                } else {
+                    self.untranslated(start, tag, "unsupported loop");
+                    self.blocks.push(None);
+                }
+            }
+            "endif" | "endfor" if rest.is_empty() => {
+                let expected: &[&str] = if keyword == "endfor" {
+                    &["each"]
+                } else {
+                    &IF_HELPERS
+                };
+                match self.blocks.last().copied() {
+                    Some(Some(helper)) if expected.contains(&helper) => {
+                        self.blocks.pop();
+                        self.emit(&format!("/{helper}"), trim);
+                    }
+                    Some(None) => {
+                        self.blocks.pop();
+                        self.untranslated_quietly(tag);
+                    }
+                    _ => self.untranslated(start, tag, "closes a block that is not open"),
+                }
+            }
+            _ => self.untranslated(start, tag, "unsupported statement"),
+        }
+    }
+
+    /// Writes a Handlebars tag, keeping Jinja's whitespace control.
+    fn emit(&mut self, expression: &str, (left, right): (bool, bool)) {
+        let left = if left { "~" } else { "" };
+        let right = if right { "~" } else { "" };
+        for piece in ["{{", left, expression, right, "}}"] {
+            self.output.push_str(piece);
+        }
+    }
+
+    /// Leaves a construct behind as a comment and reports it.
+    fn untranslated(&mut self, start: usize, tag: &str, message: &str) {
+        self.issues.push(ImportIssue {
+            line: self.template[..start].matches('\n').count() + 1,
+            construct: tag.to_string(),
+            message: message.to_string(),
+        });
+        self.untranslated_quietly(tag);
+    }
+
+    /// Leaves a construct belonging to an already reported block behind as
+    /// a comment.
+    fn untranslated_quietly(&mut self, tag: &str) {
+        self.comment(&format!("jinja: {tag}"));
+    }
+
+    /// Writes a Handlebars comment.
+    fn comment(&mut self, text: &str) {
+        self.output.push_str("{{!-- ");
+        self.output.push_str(&text.replace("--}}", "- -}}"));
+        self.output.push_str(" --}}");
+    }
+}
+
+/// Strips Jinja's `-` whitespace control markers, returning the trimmed
+/// body and whether each side trims.
+fn whitespace_control(inner: &str) -> (&str, (bool, bool)) {
+    let left = inner.starts_with('-');
+    let right = inner.ends_with('-');
+    let body = inner.strip_prefix('-').unwrap_or(inner);
+    let body = body.strip_suffix('-').unwrap_or(body);
+    (body.trim(), (left, right))
+}
+
+/// Translates a condition into a block helper and its arguments.
+fn condition(condition: &str) -> Option<(&'static str, String)> {
+    if let Some(negated) = condition.strip_prefix("not ") {
+        return path(negated).map(|value| ("unless", value));
+    }
+    for (operator, helper) in [("==", "ifEquals"), ("!=", "unlessEquals")] {
+        if let Some((left, right)) = condition.split_once(operator) {
+            let left = path(left)?;
+            let right = literal(right).or_else(|| path(right))?;
+            return Some((helper, format!("{left} {right}")));
+        }
+    }
+    path(condition).map(|value| ("if", value))
+}
+
+/// Translates a `for` loop header into `#each` arguments with block
+/// parameters.
+fn each(header: &str) -> Option<String> {
+    let (names, iterable) = header.split_once(" in ")?;
+    let names: Vec<&str> = names.split(',').map(str::trim).collect();
+    match names.as_slice() {
+        [item] if is_identifier(item) => Some(format!("{} as |{item}|", path(iterable)?)),
+        [key, value] if is_identifier(key) && is_identifier(value) => {
+            let map = iterable.trim().strip_suffix(".items()")?;
+            Some(format!("{} as |{value} {key}|", path(map)?))
+        }
+        _ => None,
+    }
+}
+
+/// Translates a variable reference such as `user.name`, `items[0]`, or
+/// `row["id"]` into a Handlebars path.
+fn path(expression: &str) -> Option<String> {
+    let expression = expression.trim();
+    match expression {
+        "loop.index0" => return Some("@index".to_string()),
+        "loop.first" => return Some("@first".to_string()),
+        "loop.last" => return Some("@last".to_string()),
+        _ => {}
+    }
+    let mut segments = Vec::new();
+    let mut rest = expression;
+    while !rest.is_empty() {
+        if let Some(index) = rest.strip_prefix('[') {
+            let (key, after) = index.split_once(']')?;
+            let key = key.trim();
+            if !key.is_empty() && key.chars().all(|c| c.is_ascii_digit()) {
+                segments.push(format!("[{key}]"));
+            } else {
+                let key = unquote(key)?;
+                segments.push(if is_identifier(key) {
+                    key.to_string()
+                } else {
+                    format!("[{key}]")
+                });
+            }
+            rest = after;
+        } else {
+            let rest_start = rest.strip_prefix('.').unwrap_or(rest);
+            if !segments.is_empty() && rest_start.len() == rest.len() {
+                return None;
+            }
+            let end = rest_start.find(['.', '[']).unwrap_or(rest_start.len());
+            let name = &rest_start[..end];
+            if !is_identifier(name) {
+                return None;
+            }
+            segments.push(name.to_string());
+            rest = &rest_start[end..];
+        }
+    }
+    if segments.first().is_none_or(|first| first == "loop") {
+        return None;
+    }
+    Some(segments.join("."))
+}
+
+/// Translates a string or number literal.
+fn literal(text: &str) -> Option<String> {
+    let text = text.trim();
+    if let Some(value) = unquote(text) {
+        return Some(format!("{value:?}"));
+    }
+    text.parse::<f64>().ok().map(|_| text.to_string())
+}
+
+/// Strips matching single or double quotes.
+fn unquote(text: &str) -> Option<&str> {
+    ['"', '\''].into_iter().find_map(|quote| {
+        text.strip_prefix(quote)
+            .and_then(|inner| inner.strip_suffix(quote))
+            .filter(|inner| !inner.contains(quote))
+    })
+}
+
+/// Checks whether text is a plain identifier.
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use dotprompt::{DataArgument, Dotprompt, RenderedPrompt};
+    use serde_json::json;
+
+    fn render(template: &str, input: serde_json::Value) -> String {
+        let data = DataArgument {
+            input: Some(input),
+            ..Default::default()
+        };
+        let rendered: RenderedPrompt = Dotprompt::new(None).render(template, &data, None).unwrap();
+        serde_json::to_value(&rendered.messages).unwrap()[0]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_translate_variables_and_blocks() {
+        let template = "Hi {{ user.name }}! {# greet #}\n\
+            {% if tier == \"gold\" %}VIP{% elif trial %}Trial{% else %}Free{% endif %}\n\
+            {% for item in items -%}\n{{ loop.index0 }}={{ item['id'] }} {% endfor %}\n\
+            {% if not done %}todo{% endif %}";
+        let imported = translate(template);
+        assert_eq!(imported.issues, Vec::new());
+        assert_eq!(
+            imported.template,
+            "Hi {{user.name}}! {{!-- greet --}}\n\
+            {{#ifEquals tier \"gold\"}}VIP{{else if trial}}Trial{{else}}Free{{/ifEquals}}\n\
+            {{#each items as |item|~}}\n{{@index}}={{item.id}} {{/each}}\n\
+            {{#unless done}}todo{{/unless}}"
+        );
+        assert_eq!(
+            render(
+                &imported.template,
+                json!({"user": {"name": "Ada"}, "tier": "gold", "items": [{"id": "a"}, {"id": "b"}]})
+            ),
+            "Hi Ada! \nVIP\n0=a 1=b \ntodo"
+        );
+        assert_eq!(
+            render(
+                &imported.template,
+                json!({"user": {"name": "Bo"}, "trial": true, "items": [], "done": true})
+            ),
+            "Hi Bo! \nTrial\n\n"
+        );
+    }
+
+    #[test]
+    fn test_translate_key_value_loop_and_tojson() {
+        let imported = translate(
+            "{% for k, v in env.items() %}{{ k }}={{ v }};{% endfor %}{{ env | tojson }}",
+        );
+        assert!(imported.issues.is_empty());
+        assert_eq!(
+            imported.template,
+            "{{#each env as |v k|}}{{k}}={{v}};{{/each}}{{json env}}"
+        );
+    }
+
+    #[test]
+    fn test_translate_reports_untranslatable_constructs() {
+        let imported =
+            translate("{{ name | upper }}\n{% if a > b %}x{% else %}y{% endif %}\n{% set z = 1 %}");
+        let lines: Vec<(usize, &str)> = imported
+            .issues
+            .iter()
+            .map(|issue| (issue.line, issue.construct.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (1, "{{ name | upper }}"),
+                (2, "{% if a > b %}"),
+                (3, "{% set z = 1 %}")
+            ]
+        );
+        assert_eq!(
+            imported.template,
+            "{{!-- jinja: {{ name | upper }} --}}\n\
+            {{!-- jinja: {% if a > b %} --}}x{{!-- jinja: {% else %} --}}y{{!-- jinja: {% endif %} --}}\n\
+            {{!-- jinja: {% set z = 1 %} --}}"
+        );
+    }
+}
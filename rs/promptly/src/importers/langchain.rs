@@ -0,0 +1,354 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Translation of `LangChain` prompt template exports.
+//!
+//! Reads the JSON that `LangChain` writes for a `PromptTemplate` or
+//! `ChatPromptTemplate`, either the serialized form (`dumpd`, with `lc`,
+//! `id`, and `kwargs`) or the legacy `prompt.save()` form (with `_type`).
+//! Chat messages become `{{role}}` sections, `MessagesPlaceholder` becomes
+//! `{{history}}`, and `input_variables` become the input schema, with
+//! `partial_variables` as input defaults.
+//!
+//! Templates in `f-string` format are translated (`{name}` becomes
+//! `{{name}}`), `jinja2` templates go through the [Jinja2
+//! importer](super::jinja), and `mustache` templates are kept as written.
+
+use serde_json::{Map, Value};
+
+use super::{ImportIssue, Imported, jinja};
+
+/// Translates a `LangChain` prompt template export.
+///
+/// # Errors
+///
+/// Returns an error if the export is not JSON or not a prompt template.
+pub(crate) fn translate(export: &str) -> Result<Imported, String> {
+    let export: Value =
+        serde_json::from_str(export).map_err(|e| format!("Invalid LangChain export: {e}"))?;
+    let (class, fields) = class_and_fields(&export)
+        .ok_or("Not a LangChain prompt template export: expected `lc` or `_type`")?;
+
+    let mut imported = Imported::default();
+    let mut placeholders = Vec::new();
+    match class {
+        "PromptTemplate" | "prompt" => {
+            let text = template(fields, &mut imported.issues);
+            imported.template.push_str(&text);
+        }
+        "ChatPromptTemplate" | "chat" => {
+            let messages = fields
+                .get("messages")
+                .and_then(Value::as_array)
+                .ok_or("ChatPromptTemplate export has no `messages`")?;
+            for message in messages {
+                message_section(message, &mut imported, &mut placeholders);
+            }
+        }
+        other => return Err(format!("Unsupported LangChain template class '{other}'")),
+    }
+    imported.frontmatter = input_frontmatter(fields, &placeholders);
+    Ok(imported)
+}
+
+/// Returns an object's class name and fields, for both export forms.
+fn class_and_fields(value: &Value) -> Option<(&str, &Map<String, Value>)> {
+    let object = value.as_object()?;
+    if let Some(kind) = object.get("_type").and_then(Value::as_str) {
+        return Some((kind, object));
+    }
+    let class = object.get("id")?.as_array()?.last()?.as_str()?;
+    Some((class, object.get("kwargs")?.as_object()?))
+}
+
+/// Appends one chat message as a role section.
+fn message_section(message: &Value, imported: &mut Imported, placeholders: &mut Vec<String>) {
+    let Some((class, fields)) = class_and_fields(message) else {
+        imported
+            .issues
+            .push(issue(message.to_string(), "unrecognized message"));
+        return;
+    };
+    let role = match class {
+        "SystemMessagePromptTemplate" | "SystemMessage" => "system",
+        "HumanMessagePromptTemplate" | "HumanMessage" => "user",
+        "AIMessagePromptTemplate" | "AIMessage" => "model",
+        "ChatMessagePromptTemplate" => match fields.get("role").and_then(Value::as_str) {
+            Some("assistant" | "ai") => "model",
+            Some("human") => "user",
+            Some(role @ ("system" | "user" | "model")) => role,
+            _ => {
+                imported
+                    .issues
+                    .push(issue(class.to_string(), "unsupported role"));
+                return;
+            }
+        },
+        "MessagesPlaceholder" => {
+            if let Some(name) = fields.get("variable_name").and_then(Value::as_str) {
+                placeholders.push(name.to_string());
+            }
+            imported.template.push_str("{{history}}\n");
+            return;
+        }
+        other => {
+            imported
+                .issues
+                .push(issue(other.to_string(), "unsupported message type"));
+            return;
+        }
+    };
+
+    let text = match fields.get("prompt").and_then(class_and_fields) {
+        Some((_, prompt)) => template(prompt, &mut imported.issues),
+        None => fields
+            .get("content")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    };
+    for piece in ["{{role \"", role, "\"}}\n", text.trim_end(), "\n"] {
+        imported.template.push_str(piece);
+    }
+}
+
+/// Translates a `PromptTemplate`'s text according to its format.
+fn template(fields: &Map<String, Value>, issues: &mut Vec<ImportIssue>) -> String {
+    let text = fields
+        .get("template")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    match fields.get("template_format").and_then(Value::as_str) {
+        Some("jinja2") => {
+            let translated = jinja::translate(text);
+            issues.extend(translated.issues);
+            translated.template
+        }
+        Some("mustache") => text.to_string(),
+        _ => f_string(text, issues),
+    }
+}
+
+/// Translates a Python format string: `{name}` becomes `{{name}}` and the
+/// `{{`/`}}` escapes become literal braces.
+fn f_string(text: &str, issues: &mut Vec<ImportIssue>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '{' if chars.next_if(|&(_, c)| c == '{').is_some() => {
+                // A literal `{` must not start a Handlebars tag.
+                if output.ends_with('{') {
+                    output.pop();
+                    output.push_str("\\{{");
+                } else {
+                    output.push('{');
+                }
+            }
+            '}' if chars.next_if(|&(_, c)| c == '}').is_some() => output.push('}'),
+            '{' => {
+                let Some(end) = text[start..].find('}') else {
+                    issues.push(issue(
+                        text[start..].to_string(),
+                        "unclosed replacement field",
+                    ));
+                    output.push_str(&text[start..]);
+                    break;
+                };
+                let field = &text[start + 1..start + end];
+                if is_field_path(field) {
+                    output.push_str("{{");
+                    output.push_str(field);
+                    output.push_str("}}");
+                } else {
+                    let construct = &text[start..=start + end];
+                    issues.push(issue(
+                        construct.to_string(),
+                        "format specs and conversions are not supported",
+                    ));
+                    output.push_str("{{!-- f-string: ");
+                    output.push_str(construct);
+                    output.push_str(" --}}");
+                }
+                while chars.next_if(|&(i, _)| i <= start + end).is_some() {}
+            }
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// Checks whether a replacement field is a plain dotted name.
+fn is_field_path(field: &str) -> bool {
+    !field.is_empty()
+        && field.split('.').all(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Builds the `input` frontmatter from the template's variables, leaving
+/// out chat history placeholders.
+fn input_frontmatter(fields: &Map<String, Value>, placeholders: &[String]) -> Option<String> {
+    let names = |key: &str| -> Vec<&str> {
+        fields
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|name| !placeholders.iter().any(|p| p == name))
+            .collect()
+    };
+    let required = names("input_variables");
+    let optional = names("optional_variables");
+    let defaults: Vec<(&String, &Value)> = fields
+        .get("partial_variables")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter(|(_, value)| value.is_string())
+        .collect();
+    if required.is_empty() && optional.is_empty() && defaults.is_empty() {
+        return None;
+    }
+
+    let mut lines = vec!["input:".to_string(), "  schema:".to_string()];
+    lines.extend(required.iter().map(|name| format!("    {name}: string")));
+    let mut optional_names: Vec<&str> = Vec::new();
+    for name in optional
+        .iter()
+        .copied()
+        .chain(defaults.iter().map(|(name, _)| name.as_str()))
+    {
+        if !required.contains(&name) && !optional_names.contains(&name) {
+            optional_names.push(name);
+        }
+    }
+    lines.extend(
+        optional_names
+            .iter()
+            .map(|name| format!("    {name}?: string")),
+    );
+    if !defaults.is_empty() {
+        lines.push("  default:".to_string());
+        lines.extend(
+            defaults
+                .iter()
+                .map(|(name, value)| format!("    {name}: {value}")),
+        );
+    }
+    lines.push(String::new());
+    Some(lines.join("\n"))
+}
+
+/// Builds an issue for a construct without a source line.
+fn issue(construct: String, message: &str) -> ImportIssue {
+    ImportIssue {
+        line: 0,
+        construct,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prompt_template(template: &str) -> Value {
+        json!({
+            "lc": 1,
+            "type": "constructor",
+            "id": ["langchain", "prompts", "prompt", "PromptTemplate"],
+            "kwargs": {"input_variables": [], "template": template, "template_format": "f-string"}
+        })
+    }
+
+    fn message(class: &str, template: &str) -> Value {
+        json!({
+            "lc": 1,
+            "type": "constructor",
+            "id": ["langchain", "prompts", "chat", class],
+            "kwargs": {"prompt": prompt_template(template)}
+        })
+    }
+
+    #[test]
+    fn test_translate_chat_prompt_template() {
+        let export = json!({
+            "lc": 1,
+            "type": "constructor",
+            "id": ["langchain", "prompts", "chat", "ChatPromptTemplate"],
+            "kwargs": {
+                "input_variables": ["history", "question", "tone"],
+                "partial_variables": {"tone": "friendly"},
+                "messages": [
+                    message("SystemMessagePromptTemplate", "Answer in a {tone} tone. Use {{json}}."),
+                    {
+                        "lc": 1,
+                        "type": "constructor",
+                        "id": ["langchain_core", "prompts", "chat", "MessagesPlaceholder"],
+                        "kwargs": {"variable_name": "history"}
+                    },
+                    message("HumanMessagePromptTemplate", "{question}"),
+                    message("AIMessagePromptTemplate", "Sure: {answer!r}")
+                ]
+            }
+        });
+        let imported = translate(&export.to_string()).unwrap();
+        assert_eq!(
+            imported.template,
+            "{{role \"system\"}}\nAnswer in a {{tone}} tone. Use {json}.\n\
+             {{history}}\n\
+             {{role \"user\"}}\n{{question}}\n\
+             {{role \"model\"}}\nSure: {{!-- f-string: {answer!r} --}}\n"
+        );
+        assert_eq!(
+            imported.frontmatter.as_deref(),
+            Some(
+                "input:\n  schema:\n    question: string\n    tone: string\n  default:\n    tone: \"friendly\"\n"
+            )
+        );
+        assert_eq!(imported.issues.len(), 1);
+        assert_eq!(imported.issues[0].construct, "{answer!r}");
+    }
+
+    #[test]
+    fn test_translate_legacy_prompt() {
+        let export = json!({
+            "_type": "prompt",
+            "input_variables": ["topic"],
+            "template": "Tell me about {topic}. Literal {{{{braces}}}}.",
+            "template_format": "f-string"
+        });
+        let imported = translate(&export.to_string()).unwrap();
+        assert!(imported.issues.is_empty());
+        assert_eq!(
+            imported.template,
+            "Tell me about {{topic}}. Literal \\{{braces}}."
+        );
+        assert_eq!(
+            imported.frontmatter.as_deref(),
+            Some("input:\n  schema:\n    topic: string\n")
+        );
+        assert!(translate("{\"unrelated\": true}").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Importers that translate prompt templates from other formats.
+//!
+//! An importer turns a foreign template into a `.prompt` source, leaving a
+//! `{{!-- ... --}}` comment in place of each construct it cannot translate
+//! and reporting it as an [`ImportIssue`] so the author can finish by hand.
+
+pub(crate) mod jinja;
+pub(crate) mod langchain;
+
+use clap::ValueEnum;
+
+/// A template format that `promptly import` translates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ImportFormat {
+    /// Jinja2 templates: `{{ var }}`, `{% if %}`, and `{% for %}`.
+    Jinja,
+    /// `LangChain` `PromptTemplate` and `ChatPromptTemplate` JSON exports.
+    Langchain,
+}
+
+/// A construct that could not be translated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ImportIssue {
+    /// The 1-based line of the construct in the original template, or 0
+    /// when the format has no meaningful lines.
+    pub line: usize,
+    /// The construct as written.
+    pub construct: String,
+    /// Why it was not translated.
+    pub message: String,
+}
+
+/// The result of translating a template.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Imported {
+    /// The translated template body.
+    pub template: String,
+    /// Frontmatter YAML inferred from the source, such as an input schema.
+    pub frontmatter: Option<String>,
+    /// Constructs left for the author to translate.
+    pub issues: Vec<ImportIssue>,
+}
+
+impl Imported {
+    /// Assembles the `.prompt` source: the frontmatter, if any, between
+    /// `---` delimiters, followed by the template.
+    ///
+    /// The `sidecar` YAML comes first. Inferred frontmatter follows, unless
+    /// the sidecar already sets its keys.
+    #[must_use]
+    pub(crate) fn to_prompt(&self, sidecar: Option<&str>) -> String {
+        let sidecar = sidecar.map(str::trim_end).filter(|yaml| !yaml.is_empty());
+        let inferred = self
+            .frontmatter
+            .as_deref()
+            .map(str::trim_end)
+            .filter(|yaml| sidecar.is_none_or(|sidecar| !sets_any_key(sidecar, yaml)));
+        let yaml: Vec<&str> = sidecar.into_iter().chain(inferred).collect();
+        if yaml.is_empty() {
+            return self.template.clone();
+        }
+        format!("---\n{}\n---\n{}", yaml.join("\n"), self.template)
+    }
+}
+
+/// Checks whether `yaml` sets any of the top-level keys of `other`.
+fn sets_any_key(yaml: &str, other: &str) -> bool {
+    let keys = |text: &str| -> Vec<String> {
+        serde_yaml_ng::from_str::<serde_yaml_ng::Mapping>(text)
+            .map(|mapping| {
+                mapping
+                    .keys()
+                    .filter_map(|key| key.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let keys_in_yaml = keys(yaml);
+    keys(other).iter().any(|key| keys_in_yaml.contains(key))
+}
+
+/// Translates a template from the given format.
+///
+/// # Errors
+///
+/// Returns an error if the source is not valid for the format.
+pub(crate) fn translate(format: ImportFormat, source: &str) -> Result<Imported, String> {
+    match format {
+        ImportFormat::Jinja => Ok(jinja::translate(source)),
+        ImportFormat::Langchain => langchain::translate(source),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_prompt_merges_sidecar_and_inferred_frontmatter() {
+        let imported = Imported {
+            template: "Hi {{name}}\n".to_string(),
+            frontmatter: Some("input:\n  schema:\n    name: string\n".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            imported.to_prompt(Some("model: gemini-2.0-flash\n")),
+            "---\nmodel: gemini-2.0-flash\ninput:\n  schema:\n    name: string\n---\nHi {{name}}\n"
+        );
+        assert_eq!(
+            imported.to_prompt(Some("input:\n  schema:\n    name?: string\n")),
+            "---\ninput:\n  schema:\n    name?: string\n---\nHi {{name}}\n"
+        );
+        assert_eq!(
+            imported.to_prompt(None),
+            "---\ninput:\n  schema:\n    name: string\n---\nHi {{name}}\n"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Link detection for `.prompt` files.
+//!
+//! Finds `{{>partial}}` references and literal `{{media url="..."}}` URLs,
+//! and resolves partial names to files on disk.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Regex for partial references; group 1 is the partial name.
+static PARTIAL_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for literal media URLs; group 1 is the URL.
+static MEDIA_URL_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// What a link points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LinkTarget {
+    /// A partial template, by name.
+    Partial(String),
+    /// An external URL.
+    Url(String),
+}
+
+/// A link found in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Link {
+    /// Byte range of the partial name or URL.
+    pub range: Range<usize>,
+    /// The link target.
+    pub target: LinkTarget,
+}
+
+/// Finds all partial references and media URLs in a document.
+#[must_use]
+pub(crate) fn find_links(source: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+
+    let partial_re = PARTIAL_RE.get_or_init(|| Regex::new(r"\{\{~?#?>\s*([\w./-]+)").ok());
+    if let Some(re) = partial_re {
+        for cap in re.captures_iter(source) {
+            if let Some(name) = cap.get(1) {
+                links.push(Link {
+                    range: name.range(),
+                    target: LinkTarget::Partial(name.as_str().to_string()),
+                });
+            }
+        }
+    }
+
+    let media_re = MEDIA_URL_RE.get_or_init(|| {
+        Regex::new(r#"\{\{~?\s*media\b[^}]*?\burl\s*=\s*"(https?://[^"\s]+)""#).ok()
+    });
+    if let Some(re) = media_re {
+        for cap in re.captures_iter(source) {
+            if let Some(url) = cap.get(1) {
+                links.push(Link {
+                    range: url.range(),
+                    target: LinkTarget::Url(url.as_str().to_string()),
+                });
+            }
+        }
+    }
+
+    links.sort_by_key(|l| l.range.start);
+    links
+}
+
+/// Resolves a partial name to a file.
+///
+/// Partials are looked up next to the referencing document first, then in
+/// each workspace root. For a name like `common/header`, the candidates in
+/// each directory are `common/_header.prompt` and `common/header.prompt`.
+#[must_use]
+pub(crate) fn resolve_partial(
+    document: Option<&Path>,
+    roots: &[PathBuf],
+    name: &str,
+) -> Option<PathBuf> {
+    let (folder, base) = name.rsplit_once('/').unwrap_or(("", name));
+    let candidates = [format!("_{base}.prompt"), format!("{base}.prompt")];
+
+    document
+        .and_then(Path::parent)
+        .into_iter()
+        .chain(roots.iter().map(PathBuf::as_path))
+        .flat_map(|dir| {
+            let dir = dir.join(folder);
+            candidates.iter().map(move |file| dir.join(file))
+        })
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_find_links() {
+        let source = "{{> header}}\n{{media url=\"https://example.com/cat.png\"}}\n{{media url=photoUrl}}\n{{>common/footer }}";
+        let links = find_links(source);
+
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].target, LinkTarget::Partial("header".to_string()));
+        assert_eq!(&source[links[0].range.clone()], "header");
+        assert_eq!(
+            links[1].target,
+            LinkTarget::Url("https://example.com/cat.png".to_string())
+        );
+        assert_eq!(
+            links[2].target,
+            LinkTarget::Partial("common/footer".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_partial_prefers_document_directory() {
+        let root = tempfile::TempDir::new().unwrap();
+        let prompts = root.path().join("prompts");
+        fs::create_dir_all(prompts.join("common")).unwrap();
+        fs::write(prompts.join("_header.prompt"), "Header").unwrap();
+        fs::write(root.path().join("_header.prompt"), "Root header").unwrap();
+        fs::write(root.path().join("footer.prompt"), "Footer").unwrap();
+        fs::write(prompts.join("common/_nav.prompt"), "Nav").unwrap();
+
+        let document = prompts.join("greet.prompt");
+        let roots = vec![root.path().to_path_buf()];
+
+        assert_eq!(
+            resolve_partial(Some(&document), &roots, "header"),
+            Some(prompts.join("_header.prompt"))
+        );
+        assert_eq!(
+            resolve_partial(Some(&document), &roots, "footer"),
+            Some(root.path().join("footer.prompt"))
+        );
+        assert_eq!(
+            resolve_partial(Some(&document), &roots, "common/nav"),
+            Some(prompts.join("common/_nav.prompt"))
+        );
+        assert_eq!(resolve_partial(Some(&document), &roots, "missing"), None);
+    }
+}
@@ -26,16 +26,22 @@
 //! | Code | Description |
 //! |------|-------------|
 //! | invalid-yaml | Invalid YAML frontmatter |
+//! | unknown-yaml-alias | Frontmatter alias refers to an undefined anchor |
+//! | duplicate-frontmatter-key | Frontmatter sets the same key twice |
 //! | unclosed-block | Handlebars block not closed |
 //! | unmatched-closing-block | Closing block without matching open |
 //! | missing-partial | Referenced partial not found |
 //! | circular-partial | Circular partial dependency |
+//! | unknown-schema-ref | Schema name or `$ref: "#/schemas/..."` not found in `schemas/` |
+//! | cost-over-budget | Estimated per-request cost over `max-estimated-cost-usd` (opt-in) |
+//! | invalid-config | `promptly.toml` is not valid TOML or has a value of the wrong type |
 //!
 //! ## Hints
 //!
 //! | Code | Description |
 //! |------|-------------|
 //! | unverified-partial | Partial template used (verify it exists) |
+//! | template-too-complex | Nesting, conditionals, or partial fan-out over the configured limits |
 //!
 //! ## Warnings
 //!
@@ -43,16 +49,105 @@
 //! |------|-------------|
 //! | unused-variable | Variable in schema but not used |
 //! | undefined-variable | Variable used but not in schema |
+//! | missing-owner | Prompt has no owner (only with `require-owners`) |
+//! | owner-mismatch | Declared owners disagree with `PROMPTOWNERS` |
+//! | helper-misuse | Declared block helper used inline, or inline helper used as a block |
+//! | json-output-without-schema | `output.format: json` without an `output.schema` |
+//! | schema-without-format | `output.schema` with `output.format: text` |
+//! | unknown-output-format | `output.format` is neither built in nor in `output-formats` |
+//! | spelling | Common misspelling in prose (opt-in, see [`crate::spelling`]) |
+//! | unknown-tool | Tool in `tools:` is not defined in the project's `tools.yaml` |
+//! | unused-tool | Tool in `tools.yaml` is not used by any checked prompt |
+//! | unknown-config-key | `promptly.toml` has a key that is not a setting |
+//! | system-after-user | System role marker after user content |
+//! | empty-role-block | Role marker with no content |
+//! | multiple-history | More than one `{{history}}` |
 
 use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 
 use clap::ValueEnum;
+use dotprompt::cost::PricingTable;
+use dotprompt::formats::BUILTIN_FORMATS;
+use dotprompt::parse::{identify_inline_partials, parse_frontmatter_value};
+use dotprompt::picoschema::SCHEMA_REF_PREFIX;
+use dotprompt::resolvers::{SCHEMAS_DIR, StoreSchemaResolver};
+use dotprompt::yaml::{DuplicateKey, Scalar, duplicate_keys, error_span, scalars};
+use dotprompt::{
+    DataArgument, Dotprompt, DotpromptOptions, RenderedPrompt, SchemaResolver, ToolCatalog,
+};
+use std::sync::OnceLock;
+
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::span::{Span, position_at_offset};
+use crate::commands::render::{load_partials, load_tools};
+use crate::frontmatter_edit::FrontmatterEditor;
+use crate::helpers::{HelperDecl, declared_helpers};
+use crate::owners::PromptOwners;
+use crate::span::{SourceMap, Span};
+use crate::spelling::{SpellingConfig, check_prose};
+use crate::transcript::transcript;
+use crate::verbatim::mask_verbatim;
+
+/// Regex for partial references.
+static PARTIAL_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for plain variable references, such as `{{ name }}`.
+static VARIABLE_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for block openers, capturing the block name.
+static BLOCK_START_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for block closers, capturing the block name.
+static BLOCK_END_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for the tags that make a template more complex: block openers and
+/// closers, partials, and `else if` branches.
+static COMPLEXITY_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for `{{role}}` and `{{history}}` markers.
+static ROLE_MARKER_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for Handlebars comments.
+static COMMENT_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Regex for helper calls, capturing whether they open a block.
+static HELPER_CALL_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Returns a regex compiled on first use, or `None` if it does not compile.
+fn cached_regex(cell: &'static OnceLock<Option<Regex>>, pattern: &str) -> Option<&'static Regex> {
+    cell.get_or_init(|| Regex::new(pattern).ok()).as_ref()
+}
+
+/// Returns the regex for partial references.
+fn partial_regex() -> Option<&'static Regex> {
+    cached_regex(&PARTIAL_RE, r"\{\{>\s*([\w-]+)\s*\}\}")
+}
+
+/// Types built into picoschema.
+const BUILTIN_TYPES: &[&str] = &[
+    "string", "number", "integer", "boolean", "object", "array", "null", "any",
+];
+
+/// JSON Schema keywords whose values are not picoschema types.
+const NON_TYPE_KEYWORDS: &[&str] = &[
+    "description",
+    "title",
+    "format",
+    "pattern",
+    "enum",
+    "const",
+    "default",
+    "examples",
+    "required",
+    "$comment",
+    "$id",
+    "$schema",
+    "contentMediaType",
+    "contentEncoding",
+];
 
 /// Diagnostic severity levels.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -88,6 +183,36 @@ pub(crate) struct Diagnostic {
     pub help: Option<String>,
     /// Optional source span where the issue occurred.
     pub span: Option<Span>,
+    /// Other locations relevant to this diagnostic.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<RelatedInformation>,
+    /// Optional automatic fix for the issue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<Fix>,
+    /// Excerpt of the rendered prompt, for rules that check what the model
+    /// would receive rather than the template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rendered: Option<String>,
+}
+
+/// A machine-applicable fix: replace the text in `span` with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Fix {
+    /// Short description of the edit, e.g. "Remove '{{/if}}'".
+    pub message: String,
+    /// The source span to replace. Offsets are byte offsets into the source.
+    pub span: Span,
+    /// The replacement text.
+    pub replacement: String,
+}
+
+/// A secondary location attached to a diagnostic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RelatedInformation {
+    /// What this location has to do with the diagnostic.
+    pub message: String,
+    /// The related source span.
+    pub span: Span,
 }
 
 impl Diagnostic {
@@ -100,6 +225,9 @@ impl Diagnostic {
             message: message.into(),
             help: None,
             span: None,
+            related: Vec::new(),
+            fix: None,
+            rendered: None,
         }
     }
 
@@ -112,6 +240,9 @@ impl Diagnostic {
             message: message.into(),
             help: None,
             span: None,
+            related: Vec::new(),
+            fix: None,
+            rendered: None,
         }
     }
 
@@ -124,6 +255,9 @@ impl Diagnostic {
             message: message.into(),
             help: None,
             span: None,
+            related: Vec::new(),
+            fix: None,
+            rendered: None,
         }
     }
 
@@ -140,13 +274,116 @@ impl Diagnostic {
         self.span = Some(span);
         self
     }
+
+    /// Adds an automatic fix to the diagnostic.
+    #[must_use]
+    pub(crate) fn with_fix(
+        mut self,
+        message: impl Into<String>,
+        span: Span,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.fix = Some(Fix {
+            message: message.into(),
+            span,
+            replacement: replacement.into(),
+        });
+        self
+    }
+
+    /// Attaches an excerpt of the rendered prompt to the diagnostic.
+    #[must_use]
+    pub(crate) fn with_rendered(mut self, rendered: impl Into<String>) -> Self {
+        self.rendered = Some(rendered.into());
+        self
+    }
+
+    /// Adds a related location to the diagnostic.
+    #[must_use]
+    pub(crate) fn with_related(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.related.push(RelatedInformation {
+            message: message.into(),
+            span,
+        });
+        self
+    }
+}
+
+/// Rendered lines kept in a diagnostic's excerpt.
+const RENDERED_EXCERPT_LINES: usize = 20;
+
+/// Returns the start of a rendered prompt as text, with a header before
+/// each message, for diagnostics about rendered output.
+fn rendered_excerpt(rendered: &RenderedPrompt) -> String {
+    let mut lines: Vec<String> = transcript(&rendered.messages)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let hidden = lines.len().saturating_sub(RENDERED_EXCERPT_LINES);
+    lines.truncate(RENDERED_EXCERPT_LINES);
+    if hidden > 0 {
+        lines.push(format!("... {hidden} more line(s)"));
+    }
+    lines.join("\n")
+}
+
+/// Limits above which a template is reported as too complex.
+///
+/// Configured in the `[lint.complexity]` section of `promptly.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct ComplexityLimits {
+    /// Deepest allowed nesting of block helpers.
+    #[serde(rename = "max-depth")]
+    pub depth: usize,
+    /// Most `if`, `unless`, and `else if` branches allowed.
+    #[serde(rename = "max-conditionals")]
+    pub conditionals: usize,
+    /// Most distinct partials a template may reference.
+    #[serde(rename = "max-partials")]
+    pub partials: usize,
+}
+
+impl Default for ComplexityLimits {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            conditionals: 10,
+            partials: 8,
+        }
+    }
+}
+
+/// A ceiling on the estimated cost of one request, checked by
+/// `cost-over-budget`.
+///
+/// Set with `max-estimated-cost-usd` in the `[lint]` section of
+/// `promptly.toml`.
+#[derive(Debug, Clone)]
+pub(crate) struct CostBudget {
+    /// Most a single request may cost, in USD.
+    pub max_usd: f64,
+    /// Prices to estimate with.
+    pub pricing: PricingTable,
 }
 
 /// The linter for `.prompt` files.
 #[derive(Debug, Default)]
 pub(crate) struct Linter {
-    /// Regex for detecting partial references.
-    partial_regex: Option<Regex>,
+    /// Whether every prompt must have at least one owner.
+    require_owners: bool,
+    /// Custom helpers declared by the project.
+    helpers: Vec<HelperDecl>,
+    /// Custom output formats declared by the project.
+    output_formats: Vec<String>,
+    /// Thresholds for `template-too-complex`.
+    complexity: ComplexityLimits,
+    /// Settings for the opt-in `spelling` pass.
+    spelling: SpellingConfig,
+    /// The project's tool catalog, if it has one.
+    tools: Option<ToolCatalog>,
+    /// Cost ceiling for `cost-over-budget`, if configured.
+    cost_budget: Option<CostBudget>,
 }
 
 impl Linter {
@@ -154,10 +391,74 @@ impl Linter {
     #[must_use]
     pub(crate) fn new() -> Self {
         Self {
-            partial_regex: Regex::new(r"\{\{>\s*([\w-]+)\s*\}\}").ok(),
+            require_owners: false,
+            helpers: Vec::new(),
+            output_formats: Vec::new(),
+            complexity: ComplexityLimits::default(),
+            spelling: SpellingConfig::default(),
+            tools: None,
+            cost_budget: None,
         }
     }
 
+    /// Sets whether prompts without an owner should be reported.
+    #[must_use]
+    pub(crate) const fn with_require_owners(mut self, require_owners: bool) -> Self {
+        self.require_owners = require_owners;
+        self
+    }
+
+    /// Sets the custom helpers declared by the project.
+    ///
+    /// Prompts can declare more in their `helpers:` frontmatter field.
+    #[must_use]
+    pub(crate) fn with_helpers(mut self, helpers: Vec<HelperDecl>) -> Self {
+        self.helpers = helpers;
+        self
+    }
+
+    /// Sets the custom output formats declared by the project, which
+    /// `unknown-output-format` accepts.
+    #[must_use]
+    pub(crate) fn with_output_formats(mut self, output_formats: Vec<String>) -> Self {
+        self.output_formats = output_formats;
+        self
+    }
+
+    /// Sets the thresholds for `template-too-complex`.
+    #[must_use]
+    pub(crate) const fn with_complexity(mut self, complexity: ComplexityLimits) -> Self {
+        self.complexity = complexity;
+        self
+    }
+
+    /// Sets the spell checking settings.
+    #[must_use]
+    pub(crate) fn with_spelling(mut self, spelling: SpellingConfig) -> Self {
+        self.spelling = spelling;
+        self
+    }
+
+    /// Sets the project's tool catalog, enabling `unknown-tool`.
+    #[must_use]
+    pub(crate) fn with_tools(mut self, tools: Option<ToolCatalog>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Sets the cost ceiling, enabling `cost-over-budget`.
+    #[must_use]
+    pub(crate) fn with_cost_budget(mut self, cost_budget: Option<CostBudget>) -> Self {
+        self.cost_budget = cost_budget;
+        self
+    }
+
+    /// Returns the custom helpers declared by the project.
+    #[must_use]
+    pub(crate) fn helpers(&self) -> &[HelperDecl] {
+        &self.helpers
+    }
+
     /// Lints a `.prompt` file source and returns diagnostics.
     ///
     /// # Arguments
@@ -172,6 +473,11 @@ impl Linter {
     pub(crate) fn lint(&self, source: &str, path: Option<&Path>) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
 
+        // Raw and verbatim blocks are not checked; masking keeps offsets
+        let original = source;
+        let masked = mask_verbatim(source);
+        let source = masked.as_ref();
+
         // Check YAML frontmatter syntax
         self.check_yaml_frontmatter(source, &mut diagnostics);
 
@@ -179,28 +485,57 @@ impl Linter {
         self.check_handlebars_syntax(source, &mut diagnostics);
 
         // Check partial references and resolution
-        self.check_partial_references(source, path, &mut diagnostics);
+        Self::check_partial_references(source, path, &mut diagnostics);
 
         // Check for circular partial dependencies
-        self.check_circular_partials(source, path, &mut diagnostics);
+        Self::check_circular_partials(source, path, &mut diagnostics);
 
-        // Check for unused/undefined variables
-        Self::check_variables(source, &mut diagnostics);
+        // Check that referenced schemas exist
+        Self::check_schema_refs(source, path, &mut diagnostics);
+
+        // Check that referenced tools are in the catalog
+        self.check_tools(source, &mut diagnostics);
+
+        // Check for unused/undefined variables, skipping custom helpers
+        let helpers = declared_helpers(&self.helpers, source);
+        Self::check_variables(source, &helpers, &mut diagnostics);
+
+        // Check that custom helpers are used as declared
+        Self::check_helper_usage(source, &helpers, &mut diagnostics);
+
+        // Check nesting, branching, and partial fan-out
+        self.check_complexity(source, &mut diagnostics);
+
+        // Check the order and content of role and history markers
+        Self::check_role_structure(source, &mut diagnostics);
+
+        // Check prose for misspellings, if enabled
+        self.check_spelling(source, &mut diagnostics);
+
+        // Check that structured output declares both a format and a schema
+        self.check_output_format(source, &mut diagnostics);
+
+        // Check prompt ownership
+        self.check_owners(source, path, &mut diagnostics);
+
+        // Check the estimated cost, rendering the unmasked source
+        self.check_cost_budget(original, path, &mut diagnostics);
 
         diagnostics
     }
 
     /// Extracts partial names from a template source.
-    fn extract_partial_names(&self, source: &str) -> Vec<String> {
+    fn extract_partial_names(source: &str) -> Vec<String> {
         let template = match Self::extract_frontmatter_and_body(source) {
             Ok((_, body)) => body,
             Err(_) => source.to_string(),
         };
 
+        let inline = identify_inline_partials(&template);
         let mut partials = Vec::new();
-        if let Some(re) = &self.partial_regex {
+        if let Some(re) = partial_regex() {
             for cap in re.captures_iter(&template) {
-                if let Some(name) = cap.get(1) {
+                if let Some(name) = cap.get(1).filter(|name| !inline.contains(name.as_str())) {
                     partials.push(name.as_str().to_string());
                 }
             }
@@ -213,27 +548,26 @@ impl Linter {
     fn extract_template_variables_with_positions(
         source: &str,
     ) -> std::collections::HashMap<String, (u32, u32)> {
-        let body_start_line = Self::calculate_body_start_line(source);
         let template = match Self::extract_frontmatter_and_body(source) {
             Ok((_, body)) => body,
             Err(_) => source.to_string(),
         };
+        let body_offset = source.len() - template.len();
+        let map = SourceMap::new(source);
 
         let mut variables = std::collections::HashMap::new();
         // Match {{ variable }} but not {{#block}}, {{/block}}, {{>partial}}, {{!comment}}
-        let var_regex = Regex::new(r"\{\{\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\}\}").ok();
-        if let Some(re) = var_regex {
+        if let Some(re) = cached_regex(&VARIABLE_RE, r"\{\{\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\}\}") {
             for cap in re.captures_iter(&template) {
                 if let Some(name) = cap.get(1) {
                     let var_name = name.as_str();
                     // Skip built-in helpers and keywords
                     if !["this", "else", "true", "false", "null"].contains(&var_name) {
                         let offset = cap.get(0).map_or(0, |m| m.start());
-                        let pos = position_at_offset(&template, offset);
-                        let abs_line = pos.line + body_start_line - 1;
+                        let pos = map.position(body_offset + offset);
                         variables
                             .entry(var_name.to_string())
-                            .or_insert((abs_line, pos.column));
+                            .or_insert((pos.line, pos.column));
                     }
                 }
             }
@@ -247,7 +581,7 @@ impl Linter {
         let mut variables = HashSet::new();
 
         if let Ok((yaml, _)) = Self::extract_frontmatter_and_body(source) {
-            if let Ok(value) = serde_yaml::from_str::<serde_yaml::Value>(&yaml) {
+            if let Ok(value) = parse_frontmatter_value(&yaml) {
                 // Look for input.schema.properties or input.schema directly
                 if let Some(input) = value.get("input") {
                     if let Some(schema) = input.get("schema") {
@@ -260,7 +594,7 @@ impl Linter {
     }
 
     /// Recursively extracts keys from a schema object.
-    fn extract_schema_keys(schema: &serde_yaml::Value, variables: &mut HashSet<String>) {
+    fn extract_schema_keys(schema: &serde_yaml_ng::Value, variables: &mut HashSet<String>) {
         // Handle shorthand: { name: string, age: number }
         if let Some(obj) = schema.as_mapping() {
             for (key, _value) in obj {
@@ -288,6 +622,51 @@ impl Linter {
         }
     }
 
+    /// Finds a plain YAML scalar in the frontmatter that parses once quoted.
+    ///
+    /// Values like `description: Note: read this` are invalid YAML because of
+    /// the second `: `. Each unquoted `key: value` line is tried in turn, and the
+    /// first one whose quoting makes the whole frontmatter valid is returned as
+    /// the span of the value and its quoted replacement.
+    fn find_scalar_to_quote(source: &str) -> Option<(Span, String)> {
+        let (yaml, _) = Self::extract_frontmatter_and_body(source).ok()?;
+        let yaml_start = source.find(&yaml)?;
+
+        let mut line_start = yaml_start;
+        for line in yaml.split_inclusive('\n') {
+            let offset = line_start;
+            line_start += line.len();
+
+            let line = line.trim_end_matches(['\n', '\r']);
+            let Some((key, value)) = line.split_once(": ") else {
+                continue;
+            };
+            let value = value.trim();
+            if value.is_empty()
+                || key.trim_start().starts_with(['#', '-'])
+                || value.contains(" #")
+                || value.starts_with(['"', '\'', '[', '{', '|', '>', '&', '*', '!'])
+            {
+                continue;
+            }
+
+            let quoted = format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+            let after_key = &line[key.len() + 2..];
+            let value_start =
+                offset + key.len() + 2 + (after_key.len() - after_key.trim_start().len());
+            let value_end = value_start + value.len();
+            let candidate = format!(
+                "{}{quoted}{}",
+                &source[yaml_start..value_start],
+                &source[value_end..yaml_start + yaml.len()]
+            );
+            if serde_yaml_ng::from_str::<serde_yaml_ng::Value>(&candidate).is_ok() {
+                return Some((Span::from_offsets(source, value_start, value_end), quoted));
+            }
+        }
+        None
+    }
+
     /// Extracts frontmatter and body from a prompt source.
     fn extract_frontmatter_and_body(source: &str) -> Result<(String, String), String> {
         // Find the first --- (start of frontmatter)
@@ -307,34 +686,51 @@ impl Linter {
         )
     }
 
-    /// Calculates the line number where the body starts (1-indexed).
-    /// This counts all lines in the source up to and including the closing --- delimiter.
-    /// Returns 0 if no frontmatter is found.
-    /// Body positions should use: `pos.line + body_start_line - 1` for absolute line numbers.
-    fn calculate_body_start_line(source: &str) -> u32 {
-        // Find the first --- (start of frontmatter)
-        let Some(first_delimiter) = source.find("---") else {
-            return 0;
-        };
-
-        // Count lines before the first ---
-        #[allow(clippy::cast_possible_truncation)]
-        let lines_before_start = source[..first_delimiter].matches('\n').count() as u32;
-
-        // Find the closing --- after the first one
-        let after_first = &source[first_delimiter + 3..];
-        let Some(closing_pos) = after_first.find("\n---") else {
-            return 0;
-        };
-
-        // Count lines within the frontmatter content
-        let frontmatter_content = &after_first[..closing_pos];
-        #[allow(clippy::cast_possible_truncation)]
-        let frontmatter_lines = frontmatter_content.matches('\n').count() as u32;
+    /// Reports an alias to an undefined anchor, if that is what `error` is.
+    fn unknown_alias(source: &str, yaml: &str, error: &serde_yaml_ng::Error) -> Option<Diagnostic> {
+        if !error.to_string().starts_with("unknown anchor") {
+            return None;
+        }
+        let index = error.location()?.index();
+        let name: String = yaml
+            .get(index..)?
+            .strip_prefix('*')?
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !matches!(c, ',' | '[' | ']' | '{' | '}'))
+            .collect();
+        let mut diag = Diagnostic::error(
+            "unknown-yaml-alias",
+            format!("The alias '*{name}' does not refer to an anchor defined before it"),
+        )
+        .with_help(format!(
+            "Define the anchor with '&{name}' on an earlier value, or fix the alias name"
+        ));
+        if let Some(start) = source.find(yaml).map(|offset| offset + index) {
+            diag = diag.with_span(Span::from_offsets(source, start, start + 1 + name.len()));
+        }
+        Some(diag)
+    }
 
-        // Total: lines_before + 1 (opening ---) + frontmatter_lines + 1 (closing --- line)
-        // The body starts on the line AFTER the closing ---
-        lines_before_start + 1 + frontmatter_lines + 1
+    /// Builds the diagnostic for a repeated frontmatter key, pointing at the
+    /// repeat and relating it to the first occurrence.
+    fn duplicate_key(source: &str, yaml: &str, duplicate: &DuplicateKey) -> Diagnostic {
+        let mut diag = Diagnostic::error(
+            "duplicate-frontmatter-key",
+            format!(
+                "The key '{}' is set more than once, so one of its values would be silently ignored",
+                duplicate.path
+            ),
+        )
+        .with_help("Remove one of the entries, or merge their values into one");
+        if let Some(offset) = source.find(yaml) {
+            let span = |range: &std::ops::Range<usize>| {
+                Span::from_offsets(source, offset + range.start, offset + range.end)
+            };
+            diag = diag
+                .with_span(span(&duplicate.duplicate))
+                .with_related(span(&duplicate.first), "first set here");
+        }
+        diag
     }
 
     /// Checks YAML frontmatter for syntax errors (E001).
@@ -344,7 +740,25 @@ impl Linter {
             Ok((yaml, _)) => {
                 if !yaml.is_empty() {
                     // Try to parse the YAML to check for errors
-                    if let Err(e) = serde_yaml::from_str::<serde_yaml::Value>(&yaml) {
+                    let parsed = serde_yaml_ng::from_str::<serde_yaml_ng::Value>(&yaml);
+                    if let Some(diag) = parsed
+                        .as_ref()
+                        .err()
+                        .and_then(|e| Self::unknown_alias(source, &yaml, e))
+                    {
+                        diagnostics.push(diag);
+                        return;
+                    }
+                    let duplicates = duplicate_keys(&yaml);
+                    if !duplicates.is_empty() {
+                        diagnostics.extend(
+                            duplicates
+                                .iter()
+                                .map(|duplicate| Self::duplicate_key(source, &yaml, duplicate)),
+                        );
+                        return;
+                    }
+                    if let Err(e) = parsed.and_then(|mut value| value.apply_merge()) {
                         let msg = format!(
                             "The YAML configuration at the top of this file has a syntax error: {e}"
                         );
@@ -352,13 +766,19 @@ impl Linter {
                             "Check for proper indentation, colons after keys, and matching quotes",
                         );
 
-                        // Try to extract line number from YAML error
-                        if let Some(location) = e.location() {
-                            #[allow(clippy::cast_possible_truncation)]
-                            let line = location.line() as u32;
-                            #[allow(clippy::cast_possible_truncation)]
-                            let column = location.column() as u32;
-                            diag = diag.with_span(Span::from_line_col(line, column, line, column));
+                        // Error locations are relative to the frontmatter text
+                        if let (Some(offset), Some(range)) =
+                            (source.find(&yaml), error_span(&yaml, &e))
+                        {
+                            diag = diag.with_span(Span::from_offsets(
+                                source,
+                                offset + range.start,
+                                offset + range.end,
+                            ));
+                        }
+
+                        if let Some((span, quoted)) = Self::find_scalar_to_quote(source) {
+                            diag = diag.with_fix("Quote the value", span, quoted);
                         }
 
                         diagnostics.push(diag);
@@ -382,23 +802,20 @@ impl Linter {
     /// Checks Handlebars syntax for errors (E002).
     #[allow(clippy::unused_self)] // May use config in future
     fn check_handlebars_syntax(&self, source: &str, diagnostics: &mut Vec<Diagnostic>) {
-        // Calculate the line offset where body starts
-        let body_start_line = Self::calculate_body_start_line(source);
-
         // Extract the template body
         let template = match Self::extract_frontmatter_and_body(source) {
             Ok((_, body)) => body,
             Err(_) => source.to_string(),
         };
+        let body_offset = source.len() - template.len();
+        let map = SourceMap::new(source);
 
         // Check for unbalanced Handlebars blocks
         let mut block_stack: Vec<(String, usize)> = Vec::new();
 
         // Find all block starts and ends
-        let block_start_re = Regex::new(r"\{\{#(\w+)").ok();
-        let block_end_re = Regex::new(r"\{\{/(\w+)").ok();
-
-        if let Some(re) = &block_start_re {
+        // `{{#*inline "name"}}` is closed by `{{/inline}}`
+        if let Some(re) = cached_regex(&BLOCK_START_RE, r"\{\{#\*?(\w+)") {
             for cap in re.captures_iter(&template) {
                 if let Some(name) = cap.get(1) {
                     let offset = cap.get(0).map_or(0, |m| m.start());
@@ -407,7 +824,7 @@ impl Linter {
             }
         }
 
-        if let Some(re) = &block_end_re {
+        if let Some(re) = cached_regex(&BLOCK_END_RE, r"\{\{/(\w+)") {
             for cap in re.captures_iter(&template) {
                 if let Some(name) = cap.get(1) {
                     let block_name = name.as_str();
@@ -417,20 +834,22 @@ impl Linter {
                     if let Some(pos) = block_stack.iter().rposition(|(n, _)| n == block_name) {
                         block_stack.remove(pos);
                     } else {
-                        let pos = position_at_offset(&template, offset);
-                        diagnostics.push(
-                            Diagnostic::error(
-                                "unmatched-closing-block",
-                                format!("Found '{{{{/{block_name}}}}}' but no matching '{{{{#{block_name}}}}}' was opened"),
-                            )
-                            .with_span(Span::from_line_col(
-                                pos.line + body_start_line - 1,
-                                pos.column,
-                                pos.line + body_start_line - 1,
-                                pos.column,
-                            ))
-                            .with_help(format!("Either add '{{{{#{block_name}}}}}' before this, or remove this closing tag")),
-                        );
+                        let mut diag = Diagnostic::error(
+                            "unmatched-closing-block",
+                            format!("Found '{{{{/{block_name}}}}}' but no matching '{{{{#{block_name}}}}}' was opened"),
+                        )
+                        .with_span(map.span_in(body_offset, offset..offset))
+                        .with_help(format!("Either add '{{{{#{block_name}}}}}' before this, or remove this closing tag"));
+
+                        if let Some(tag_len) = template[offset..].find("}}").map(|i| i + 2) {
+                            let start = body_offset + offset;
+                            diag = diag.with_fix(
+                                format!("Remove '{{{{/{block_name}}}}}'"),
+                                Span::from_offsets(source, start, start + tag_len),
+                                "",
+                            );
+                        }
+                        diagnostics.push(diag);
                     }
                 }
             }
@@ -438,29 +857,32 @@ impl Linter {
 
         // Report unclosed blocks
         for (name, offset) in block_stack {
-            let pos = position_at_offset(&template, offset);
             diagnostics.push(
                 Diagnostic::error(
                     "unclosed-block",
                     format!("Block '{{{{#{name}}}}}' was never closed"),
                 )
-                .with_span(Span::from_line_col(
-                    pos.line + body_start_line - 1,
-                    pos.column,
-                    pos.line + body_start_line - 1,
-                    pos.column,
-                ))
+                .with_span(map.span_in(body_offset, offset..offset))
                 .with_help(format!(
                     "Add '{{{{/{name}}}}}' somewhere after this to close the block"
                 )),
             );
         }
 
-        // Check for unbalanced braces
+        Self::check_unbalanced_braces(&map, &template, body_offset, diagnostics);
+    }
+
+    /// Checks for closing braces without matching opening braces.
+    fn check_unbalanced_braces(
+        map: &SourceMap,
+        template: &str,
+        body_offset: usize,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
         let mut brace_count = 0i32;
         let mut in_handlebars = false;
 
-        for (i, ch) in template.chars().enumerate() {
+        for (offset, ch) in template.char_indices() {
             if ch == '{' {
                 brace_count += 1;
                 if brace_count >= 2 {
@@ -469,18 +891,12 @@ impl Linter {
             } else if ch == '}' {
                 brace_count -= 1;
                 if brace_count < 0 {
-                    let pos = position_at_offset(&template, i);
                     diagnostics.push(
                         Diagnostic::error(
                             "unbalanced-brace",
                             "Found a closing '}}' without a matching opening '{{'",
                         )
-                        .with_span(Span::from_line_col(
-                            pos.line + body_start_line - 1,
-                            pos.column,
-                            pos.line + body_start_line - 1,
-                            pos.column,
-                        ))
+                        .with_span(map.span_in(body_offset, offset..offset))
                         .with_help(
                             "Add the missing opening braces or remove the extra closing braces",
                         ),
@@ -498,41 +914,34 @@ impl Linter {
 
     /// Checks for partial references (E003).
     fn check_partial_references(
-        &self,
         source: &str,
         _path: Option<&Path>,
         diagnostics: &mut Vec<Diagnostic>,
     ) {
-        // Calculate the line offset where body starts
-        let body_start_line = Self::calculate_body_start_line(source);
-
         // Extract the template body
         let template = match Self::extract_frontmatter_and_body(source) {
             Ok((_, body)) => body,
             Err(_) => source.to_string(),
         };
+        let body_offset = source.len() - template.len();
+        let map = SourceMap::new(source);
 
-        // Find all partial references
-        if let Some(re) = &self.partial_regex {
+        // Find all partial references, except to partials defined inline
+        let inline = identify_inline_partials(&template);
+        if let Some(re) = partial_regex() {
             for cap in re.captures_iter(&template) {
-                if let Some(name) = cap.get(1) {
+                if let Some(name) = cap.get(1).filter(|name| !inline.contains(name.as_str())) {
                     let partial_name = name.as_str();
                     let offset = cap.get(0).map_or(0, |m| m.start());
 
                     // For now, just emit an info diagnostic about partials found
                     // Full resolution requires access to the file system
-                    let pos = position_at_offset(&template, offset);
                     diagnostics.push(
                         Diagnostic::info(
                             "unverified-partial",
                             format!("Uses partial template '{partial_name}' — ensure this partial exists"),
                         )
-                            .with_span(Span::from_line_col(
-                                pos.line + body_start_line - 1,
-                                pos.column,
-                                pos.line + body_start_line - 1,
-                                pos.column,
-                            )),
+                            .with_span(map.span_in(body_offset, offset..offset)),
                     );
                 }
             }
@@ -541,7 +950,6 @@ impl Linter {
 
     /// Checks for circular partial dependencies.
     fn check_circular_partials(
-        &self,
         source: &str,
         path: Option<&Path>,
         diagnostics: &mut Vec<Diagnostic>,
@@ -551,7 +959,7 @@ impl Linter {
             return;
         };
 
-        let partials = self.extract_partial_names(source);
+        let partials = Self::extract_partial_names(source);
         if partials.is_empty() {
             return;
         }
@@ -564,7 +972,8 @@ impl Linter {
         let mut path_stack = vec![current_name.to_string()];
 
         for partial in &partials {
-            if let Some(cycle) = self.find_cycle(parent_dir, partial, &mut visited, &mut path_stack)
+            if let Some(cycle) =
+                Self::find_cycle(parent_dir, partial, &mut visited, &mut path_stack)
             {
                 diagnostics.push(
                     Diagnostic::error(
@@ -579,7 +988,6 @@ impl Linter {
 
     /// DFS helper to find cycles in partial dependencies.
     fn find_cycle(
-        &self,
         base_dir: &Path,
         partial_name: &str,
         visited: &mut HashSet<String>,
@@ -607,9 +1015,9 @@ impl Linter {
         path_stack.push(partial_name.to_string());
 
         // Check nested partials
-        let nested_partials = self.extract_partial_names(&partial_source);
+        let nested_partials = Self::extract_partial_names(&partial_source);
         for nested in &nested_partials {
-            if let Some(cycle) = self.find_cycle(base_dir, nested, visited, path_stack) {
+            if let Some(cycle) = Self::find_cycle(base_dir, nested, visited, path_stack) {
                 return Some(cycle);
             }
         }
@@ -618,77 +1026,861 @@ impl Linter {
         None
     }
 
-    /// Checks for unused and undefined variables.
-    fn check_variables(source: &str, diagnostics: &mut Vec<Diagnostic>) {
-        let schema_vars = Self::parse_schema_variables(source);
-        let template_vars = Self::extract_template_variables_with_positions(source);
-        let template_var_names: HashSet<_> = template_vars.keys().cloned().collect();
-
-        // Skip if no schema defined
-        if schema_vars.is_empty() {
-            return;
+    /// Parses the `owners` list from YAML frontmatter.
+    fn parse_owners(source: &str) -> Vec<String> {
+        let Ok((yaml, _)) = Self::extract_frontmatter_and_body(source) else {
+            return Vec::new();
+        };
+        let Ok(value) = parse_frontmatter_value(&yaml) else {
+            return Vec::new();
+        };
+        match value.get("owners") {
+            Some(serde_yaml_ng::Value::String(owner)) => vec![owner.clone()],
+            Some(serde_yaml_ng::Value::Sequence(owners)) => owners
+                .iter()
+                .filter_map(|o| o.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
         }
+    }
 
-        // Check for unused variables (in schema but not template)
-        // For unused vars, point to input.schema section (roughly line 5-6 in most files)
-        for var in &schema_vars {
-            if !template_var_names.contains(var) {
+    /// Checks that the prompt has an owner and agrees with `PROMPTOWNERS`.
+    fn check_owners(&self, source: &str, path: Option<&Path>, diagnostics: &mut Vec<Diagnostic>) {
+        let declared = Self::parse_owners(source);
+        let file_path = path.and_then(|p| fs::canonicalize(p).ok());
+        let codeowners = file_path.as_deref().and_then(PromptOwners::discover);
+        let assigned = codeowners
+            .as_ref()
+            .zip(file_path.as_deref())
+            .and_then(|(rules, p)| rules.owners_for(p));
+
+        match assigned {
+            None if declared.is_empty() && self.require_owners => {
+                diagnostics.push(
+                    Diagnostic::warning("missing-owner", "This prompt has no owner")
+                        .with_help("Add 'owners: [...]' to the frontmatter, or a matching line to PROMPTOWNERS"),
+                );
+            }
+            Some(assigned)
+                if !declared.is_empty() && !declared.iter().any(|o| assigned.contains(o)) =>
+            {
                 diagnostics.push(
                     Diagnostic::warning(
-                        "unused-variable",
-                        format!("Variable '{var}' is defined in schema but never used in template"),
+                        "owner-mismatch",
+                        format!(
+                            "Owners {} do not include any owner assigned by PROMPTOWNERS ({})",
+                            declared.join(", "),
+                            assigned.join(", ")
+                        ),
                     )
-                    .with_help("Remove from schema if not needed, or use it in the template"),
+                    .with_help(
+                        "Update the frontmatter 'owners' or the PROMPTOWNERS file so they agree",
+                    ),
                 );
             }
+            _ => {}
         }
+    }
 
-        // Check for undefined variables (in template but not schema)
-        // For undefined vars, point to where the variable is used
-        for (var, (line, col)) in &template_vars {
-            if !schema_vars.contains(var) {
+    /// Checks the template against the configured complexity limits.
+    ///
+    /// Each exceeded limit is reported once, at the construct that first
+    /// goes over it.
+    fn check_complexity(&self, source: &str, diagnostics: &mut Vec<Diagnostic>) {
+        let template = match Self::extract_frontmatter_and_body(source) {
+            Ok((_, body)) => body,
+            Err(_) => source.to_string(),
+        };
+        let body_offset = source.len() - template.len();
+        let Some(re) = cached_regex(
+            &COMPLEXITY_RE,
+            r"\{\{~?\s*(?:#\*?\s*(?<open>\w+)|/\s*(?<close>\w+)|>\s*(?<partial>[\w./-]+)|else\s+(?:if|unless)\b)",
+        ) else {
+            return;
+        };
+        let limits = self.complexity;
+
+        let mut depth = 0;
+        let mut conditionals = 0;
+        let mut partials = HashSet::new();
+        let mut too_deep = None;
+        let mut too_branchy = None;
+        let mut too_many_partials = None;
+        for cap in re.captures_iter(&template) {
+            let Some(whole) = cap.get(0) else {
+                continue;
+            };
+            if template[..whole.start()].ends_with('\\') {
+                continue;
+            }
+            let at = (whole.start(), whole.end());
+            if let Some(open) = cap.name("open") {
+                depth += 1;
+                if depth > limits.depth && too_deep.is_none() {
+                    too_deep = Some(at);
+                }
+                if matches!(open.as_str(), "if" | "unless") {
+                    conditionals += 1;
+                }
+            } else if cap.name("close").is_some() {
+                depth = depth.saturating_sub(1);
+            } else if let Some(partial) = cap.name("partial") {
+                partials.insert(partial.as_str());
+                if partials.len() > limits.partials && too_many_partials.is_none() {
+                    too_many_partials = Some(at);
+                }
+            } else {
+                conditionals += 1;
+            }
+            if conditionals > limits.conditionals && too_branchy.is_none() {
+                too_branchy = Some(at);
+            }
+        }
+
+        let findings = [
+            (
+                too_deep,
+                format!("Blocks are nested more than {} levels deep", limits.depth),
+                "Move the inner blocks into a partial, or flatten the logic into the input data",
+            ),
+            (
+                too_branchy,
+                format!(
+                    "The template has more than {} conditional branches",
+                    limits.conditionals
+                ),
+                "Split the variants into partials or separate prompts",
+            ),
+            (
+                too_many_partials,
+                format!(
+                    "The template references more than {} different partials",
+                    limits.partials
+                ),
+                "Group related partials into a larger partial",
+            ),
+        ];
+        for (at, message, help) in findings {
+            if let Some((start, end)) = at {
                 diagnostics.push(
-                    Diagnostic::warning(
-                        "undefined-variable",
-                        format!("Variable '{var}' is used in template but not defined in schema"),
-                    )
-                    .with_span(Span::from_line_col(*line, *col, *line, *col))
-                    .with_help("Add to input.schema in frontmatter, or remove from template"),
+                    Diagnostic::info("template-too-complex", message)
+                        .with_span(Span::from_offsets(
+                            source,
+                            body_offset + start,
+                            body_offset + end,
+                        ))
+                        .with_help(help),
                 );
             }
         }
     }
-}
 
-#[cfg(test)]
-#[allow(clippy::unwrap_used, clippy::expect_used, clippy::needless_collect)]
-mod tests {
-    use super::*;
+    /// Checks the prose in the template body for common misspellings.
+    fn check_spelling(&self, source: &str, diagnostics: &mut Vec<Diagnostic>) {
+        if !self.spelling.enabled {
+            return;
+        }
+        let template = match Self::extract_frontmatter_and_body(source) {
+            Ok((_, body)) => body,
+            Err(_) => source.to_string(),
+        };
+        let body_offset = source.len() - template.len();
+        for misspelling in check_prose(&template, &self.spelling) {
+            let span = Span::from_offsets(
+                source,
+                body_offset + misspelling.range.start,
+                body_offset + misspelling.range.end,
+            );
+            let suggestions: Vec<String> = misspelling
+                .suggestions
+                .iter()
+                .map(|s| format!("'{s}'"))
+                .collect();
+            let mut diag = Diagnostic::warning(
+                "spelling",
+                format!("'{}' looks misspelled", misspelling.word),
+            )
+            .with_span(span.clone())
+            .with_help(format!(
+                "Did you mean {}? Add the word to [lint.spelling] allow in promptly.toml if it is intended",
+                suggestions.join(" or ")
+            ));
+            if let [suggestion] = misspelling.suggestions.as_slice() {
+                diag = diag.with_fix(format!("Replace with '{suggestion}'"), span, suggestion);
+            }
+            diagnostics.push(diag);
+        }
+    }
 
-    #[test]
-    fn test_lint_valid_prompt() {
-        let source = r"---
-model: gemini-2.0-flash
-config:
-  temperature: 0.7
----
-Hello {{name}}!
-";
+    /// Checks the structure of `{{role}}` and `{{history}}` markers.
+    fn check_role_structure(source: &str, diagnostics: &mut Vec<Diagnostic>) {
+        let template = match Self::extract_frontmatter_and_body(source) {
+            Ok((_, body)) => body,
+            Err(_) => source.to_string(),
+        };
+        let body_offset = source.len() - template.len();
+        let (Some(marker_re), Some(comment_re)) = (
+            cached_regex(
+                &ROLE_MARKER_RE,
+                r#"\{\{~?\s*(?:role\s+(?:"(\w+)"|'(\w+)')|(history))\s*~?\}\}"#,
+            ),
+            cached_regex(&COMMENT_RE, r"\{\{~?!--[\s\S]*?--~?\}\}|\{\{~?![^}]*\}\}"),
+        ) else {
+            return;
+        };
+        let span = |start: usize, end: usize| {
+            Span::from_offsets(source, body_offset + start, body_offset + end)
+        };
+        let has_content = |text: &str| !comment_re.replace_all(text, "").trim().is_empty();
+
+        let markers: Vec<_> = marker_re.captures_iter(&template).collect();
+        // Text before the first marker belongs to an implicit user message.
+        let first_start = markers
+            .first()
+            .and_then(|c| c.get(0))
+            .map_or(template.len(), |m| m.start());
+        let mut user_content = has_content(&template[..first_start]);
+        let mut first_history: Option<Span> = None;
+        for (index, cap) in markers.iter().enumerate() {
+            let Some(marker) = cap.get(0) else {
+                continue;
+            };
+            let next = markers
+                .get(index + 1)
+                .and_then(|c| c.get(0))
+                .map_or(template.len(), |m| m.start());
+            let content = has_content(&template[marker.end()..next]);
+            let marker_span = span(marker.start(), marker.end());
+
+            if cap.get(3).is_some() {
+                if let Some(first) = &first_history {
+                    diagnostics.push(
+                        Diagnostic::warning(
+                            "multiple-history",
+                            "The conversation history is inserted more than once",
+                        )
+                        .with_span(marker_span)
+                        .with_related(first.clone(), "history is first inserted here")
+                        .with_help("Keep a single {{history}} where past turns belong"),
+                    );
+                } else {
+                    first_history = Some(marker_span);
+                }
+                continue;
+            }
 
-        let linter = Linter::new();
-        let diagnostics = linter.lint(source, None);
+            let role = cap.get(1).or_else(|| cap.get(2)).map_or("", |m| m.as_str());
+            if role == "system" && user_content {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "system-after-user",
+                        "A system message appears after user content",
+                    )
+                    .with_span(marker_span.clone())
+                    .with_help("Move the system instructions to the start of the template; most models expect them first"),
+                );
+            }
+            if content {
+                user_content |= role == "user";
+            } else {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "empty-role-block",
+                        format!("The '{role}' role marker has no content"),
+                    )
+                    .with_span(marker_span.clone())
+                    .with_help("Add the message text after the marker, or remove it")
+                    .with_fix("Remove the empty role marker", marker_span, ""),
+                );
+            }
+        }
+    }
 
-        // Should have no errors or warnings
-        let errors: Vec<_> = diagnostics
+    /// Checks that the named schemas used in `input.schema` and
+    /// `output.schema` exist in the `schemas/` directory next to the prompt.
+    fn check_schema_refs(source: &str, path: Option<&Path>, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(dir) = path.and_then(Path::parent) else {
+            return;
+        };
+        let Ok((yaml, _)) = Self::extract_frontmatter_and_body(source) else {
+            return;
+        };
+        let Some(offset) = source.find(&yaml) else {
+            return;
+        };
+        let resolver = StoreSchemaResolver::for_prompt_dir(dir);
+        for scalar in scalars(&yaml) {
+            for name in Self::schema_names(&scalar) {
+                if !matches!(resolver.resolve(name), Ok(None)) {
+                    continue;
+                }
+                diagnostics.push(
+                    Diagnostic::error(
+                        "unknown-schema-ref",
+                        format!("The schema '{name}' is not defined"),
+                    )
+                    .with_help(format!(
+                        "Add {SCHEMAS_DIR}/{name}.schema.yaml next to this prompt, or fix the name"
+                    ))
+                    .with_span(Span::from_offsets(
+                        source,
+                        offset + scalar.range.start,
+                        offset + scalar.range.end,
+                    )),
+                );
+            }
+        }
+    }
+
+    /// Returns the schema names a frontmatter value refers to: the target of
+    /// a `$ref: "#/schemas/Name"`, or the non-builtin types in a picoschema
+    /// type such as `Person[] | null`.
+    fn schema_names(scalar: &Scalar) -> Vec<&str> {
+        let Some(schema_path) = ["input.schema", "output.schema"]
             .iter()
-            .filter(|d| d.severity == DiagnosticSeverity::Error)
+            .find_map(|prefix| scalar.path.strip_prefix(prefix))
+            .filter(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+        else {
+            return Vec::new();
+        };
+        let segments: Vec<&str> = schema_path
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.split('[').next().unwrap_or(s))
             .collect();
-        assert!(errors.is_empty(), "Expected no errors, got: {errors:?}");
+        if segments.last() == Some(&"$ref") {
+            return scalar
+                .value
+                .strip_prefix(SCHEMA_REF_PREFIX)
+                .into_iter()
+                .collect();
+        }
+        // Values of JSON Schema keywords, and list items, are not types.
+        if schema_path.ends_with(']') || segments.iter().any(|s| NON_TYPE_KEYWORDS.contains(s)) {
+            return Vec::new();
+        }
+        let type_expr = scalar.value.split(',').next().unwrap_or_default();
+        type_expr
+            .split('|')
+            .map(|t| t.trim().trim_end_matches("[]").trim())
+            .filter(|t| {
+                t.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && t.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+                    && !BUILTIN_TYPES.contains(t)
+            })
+            .collect()
     }
 
-    #[test]
-    fn test_lint_invalid_yaml() {
-        let source = r#"---
+    /// Checks that the tools a prompt names are defined in the catalog.
+    fn check_tools(&self, source: &str, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(catalog) = &self.tools else {
+            return;
+        };
+        let Ok((yaml, _)) = Self::extract_frontmatter_and_body(source) else {
+            return;
+        };
+        let offset = source.find(&yaml).unwrap_or_default();
+        for scalar in scalars(&yaml) {
+            let is_tool = scalar
+                .path
+                .strip_prefix("tools[")
+                .is_some_and(|rest| rest.ends_with(']') && !rest.contains('.'));
+            if !is_tool || catalog.get(&scalar.value).is_some() {
+                continue;
+            }
+            diagnostics.push(
+                Diagnostic::warning(
+                    "unknown-tool",
+                    format!(
+                        "The tool '{}' is not defined in the tool catalog",
+                        scalar.value
+                    ),
+                )
+                .with_help(format!(
+                    "Add '{}' to {}, or fix the name",
+                    scalar.value,
+                    ToolCatalog::FILE_NAME
+                ))
+                .with_span(Span::from_offsets(
+                    source,
+                    offset + scalar.range.start,
+                    offset + scalar.range.end,
+                )),
+            );
+        }
+    }
+
+    /// Reports a prompt whose estimated per-request cost is over the
+    /// configured ceiling.
+    ///
+    /// The prompt is rendered with its `input.default` values and the
+    /// partials and tools of its directory. Prompts that do not render, have
+    /// no model, or use a model without a price are not checked.
+    fn check_cost_budget(
+        &self,
+        source: &str,
+        path: Option<&Path>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(budget) = &self.cost_budget else {
+            return;
+        };
+        let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+            partials: path.map(load_partials),
+            tools: path.and_then(load_tools),
+            ..Default::default()
+        }));
+        let Ok(rendered) = dotprompt.render::<serde_json::Value, serde_json::Value>(
+            source,
+            &DataArgument::default(),
+            None,
+        ) else {
+            return;
+        };
+        let Some(model) = rendered.metadata.model.as_deref() else {
+            return;
+        };
+        let Some(estimate) = budget.pricing.estimate_cost(&rendered, model) else {
+            return;
+        };
+        if estimate.total_cost <= budget.max_usd {
+            return;
+        }
+
+        let mut diagnostic = Diagnostic::error(
+            "cost-over-budget",
+            format!(
+                "Estimated cost per request is ${:.4} on '{model}', over the ${:.4} budget",
+                estimate.total_cost, budget.max_usd
+            ),
+        )
+        .with_help(format!(
+            "~{} input and {} output tokens; shorten the prompt, lower maxOutputTokens, or use a cheaper model",
+            estimate.input_tokens, estimate.output_tokens
+        ))
+        .with_rendered(rendered_excerpt(&rendered));
+        if let Ok((yaml, _)) = Self::extract_frontmatter_and_body(source) {
+            let offset = source.find(&yaml).unwrap_or_default();
+            if let Some(scalar) = scalars(&yaml).into_iter().find(|s| s.path == "model") {
+                diagnostic = diagnostic.with_span(Span::from_offsets(
+                    source,
+                    offset + scalar.range.start,
+                    offset + scalar.range.end,
+                ));
+            }
+        }
+        diagnostics.push(diagnostic);
+    }
+
+    /// Returns the tool names in a prompt's `tools:` frontmatter.
+    #[must_use]
+    pub(crate) fn prompt_tools(source: &str) -> Vec<String> {
+        Self::extract_frontmatter_and_body(source)
+            .ok()
+            .and_then(|(yaml, _)| parse_frontmatter_value(&yaml).ok())
+            .and_then(|value| value.get("tools").and_then(|t| t.as_sequence()).cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|tool| tool.as_str().map(ToString::to_string))
+            .collect()
+    }
+
+    /// Reports the tools in a catalog that none of `used` refer to.
+    ///
+    /// `catalog_source` is the text of the catalog file, used to point at
+    /// each unused tool's name.
+    #[must_use]
+    pub(crate) fn unused_tools(catalog_source: &str, used: &HashSet<String>) -> Vec<Diagnostic> {
+        scalars(catalog_source)
+            .into_iter()
+            .filter(|scalar| {
+                scalar.path.starts_with("tools[")
+                    && scalar.path.ends_with("].name")
+                    && !used.contains(&scalar.value)
+            })
+            .map(|scalar| {
+                Diagnostic::warning(
+                    "unused-tool",
+                    format!("The tool '{}' is not used by any prompt", scalar.value),
+                )
+                .with_help("Remove it from the catalog, or add it to a prompt's tools")
+                .with_span(Span::from_offsets(
+                    catalog_source,
+                    scalar.range.start,
+                    scalar.range.end,
+                ))
+            })
+            .collect()
+    }
+
+    /// Checks that `output.format` is known and agrees with `output.schema`.
+    fn check_output_format(&self, source: &str, diagnostics: &mut Vec<Diagnostic>) {
+        let Ok((yaml, _)) = Self::extract_frontmatter_and_body(source) else {
+            return;
+        };
+        let Ok(value) = parse_frontmatter_value(&yaml) else {
+            return;
+        };
+        let Some(output) = value.get("output") else {
+            return;
+        };
+        let format = output.get("format").and_then(serde_yaml_ng::Value::as_str);
+        let has_schema = output.get("schema").is_some_and(|s| !s.is_null());
+
+        let editor = FrontmatterEditor::new(source).ok();
+        let key_span = |path: &str| {
+            editor
+                .as_ref()
+                .and_then(|e| e.key_range(path))
+                .map(|range| Span::from_offsets(source, range.start, range.end))
+        };
+        let diag = match (format, has_schema) {
+            (Some("json"), false) => {
+                let mut diag = Diagnostic::warning(
+                    "json-output-without-schema",
+                    "Output format is 'json' but no output.schema is declared",
+                )
+                .with_help("Add an output.schema describing the expected JSON so responses can be validated");
+                if let Some(span) = key_span("output.format") {
+                    diag = diag.with_span(span);
+                }
+                diag
+            }
+            (Some("text"), true) => {
+                let mut diag = Diagnostic::warning(
+                    "schema-without-format",
+                    "An output.schema is declared but the output format is 'text'",
+                )
+                .with_help("Set output.format to 'json', or remove output.schema");
+                if let Some(span) = key_span("output.schema") {
+                    diag = diag.with_span(span);
+                }
+                if let Some(span) = key_span("output.format") {
+                    diag = diag.with_related(span, "format is set here");
+                }
+                diag
+            }
+            (Some(name), _)
+                if !BUILTIN_FORMATS.contains(&name)
+                    && !self.output_formats.iter().any(|f| f == name) =>
+            {
+                let mut diag = Diagnostic::warning(
+                    "unknown-output-format",
+                    format!("Unknown output format '{name}'"),
+                )
+                .with_help(format!(
+                    "Use one of {}, or add '{name}' to output-formats in promptly.toml",
+                    BUILTIN_FORMATS.join(", ")
+                ));
+                if let Some(span) = key_span("output.format") {
+                    diag = diag.with_span(span);
+                }
+                diag
+            }
+            _ => return,
+        };
+        diagnostics.push(diag);
+    }
+
+    /// Checks that declared block helpers are opened with `#` and inline
+    /// helpers are not.
+    fn check_helper_usage(source: &str, helpers: &[HelperDecl], diagnostics: &mut Vec<Diagnostic>) {
+        if helpers.is_empty() {
+            return;
+        }
+        let template = match Self::extract_frontmatter_and_body(source) {
+            Ok((_, body)) => body,
+            Err(_) => source.to_string(),
+        };
+        let body_offset = source.len() - template.len();
+        let map = SourceMap::new(source);
+
+        let Some(re) = cached_regex(&HELPER_CALL_RE, r"\{\{~?\s*(#?)\s*([a-zA-Z_][\w-]*)") else {
+            return;
+        };
+        for cap in re.captures_iter(&template) {
+            let (Some(whole), Some(name)) = (cap.get(0), cap.get(2)) else {
+                continue;
+            };
+            let Some(helper) = helpers.iter().find(|h| h.name == name.as_str()) else {
+                continue;
+            };
+            let opened = cap.get(1).is_some_and(|m| !m.as_str().is_empty());
+            let name = &helper.name;
+            let (message, help) = match (helper.block, opened) {
+                (true, false) => (
+                    format!("'{name}' is a block helper but is used inline"),
+                    format!("Use '{{{{#{name}}}}}...{{{{/{name}}}}}'"),
+                ),
+                (false, true) => (
+                    format!("'{name}' is an inline helper but is opened as a block"),
+                    format!("Use '{{{{{name}}}}}', or declare it with 'block: true'"),
+                ),
+                _ => continue,
+            };
+            let offset = whole.start();
+            diagnostics.push(
+                Diagnostic::warning("helper-misuse", message)
+                    .with_span(map.span_in(body_offset, offset..offset))
+                    .with_help(help),
+            );
+        }
+    }
+
+    /// Checks for unused and undefined variables.
+    fn check_variables(source: &str, helpers: &[HelperDecl], diagnostics: &mut Vec<Diagnostic>) {
+        let schema_vars = Self::parse_schema_variables(source);
+        let mut template_vars = Self::extract_template_variables_with_positions(source);
+        template_vars.retain(|name, _| !helpers.iter().any(|h| h.name == *name));
+        let template_var_names: HashSet<_> = template_vars.keys().cloned().collect();
+
+        // Skip if no schema defined
+        if schema_vars.is_empty() {
+            return;
+        }
+
+        let layout = SchemaLayout::scan(source);
+
+        // Check for unused variables (in schema but not template)
+        // For unused vars, point to the variable's key in input.schema when it can be found
+        let mut unused: Vec<_> = schema_vars
+            .iter()
+            .filter(|var| !template_var_names.contains(*var))
+            .collect();
+        unused.sort();
+        for var in unused {
+            let mut diag = Diagnostic::warning(
+                "unused-variable",
+                format!("Variable '{var}' is defined in schema but never used in template"),
+            )
+            .with_help("Remove from schema if not needed, or use it in the template");
+            if let Some(span) = layout.as_ref().and_then(|l| l.key_span(var)) {
+                diag = diag.with_span(span);
+            }
+            diagnostics.push(diag);
+        }
+
+        // Check for undefined variables (in template but not schema)
+        // For undefined vars, point to where the variable is used
+        let mut undefined: Vec<_> = template_vars
+            .iter()
+            .filter(|(var, _)| !schema_vars.contains(*var))
+            .collect();
+        undefined.sort_by_key(|(_, position)| **position);
+        for (var, (line, col)) in undefined {
+            let mut diag = Diagnostic::warning(
+                "undefined-variable",
+                format!("Variable '{var}' is used in template but not defined in schema"),
+            )
+            .with_span(Span::from_line_col(*line, *col, *line, *col))
+            .with_help("Add to input.schema in frontmatter, or remove from template");
+            if let Some(layout) = &layout {
+                diag = diag.with_related(layout.schema.clone(), "input schema is defined here");
+                if let Some((span, line)) = layout.insertion(source, var) {
+                    diag = diag.with_fix(format!("Add '{var}' to input.schema"), span, line);
+                }
+            }
+            diagnostics.push(diag);
+        }
+    }
+}
+
+/// A key inside the `input.schema` block.
+struct SchemaKey {
+    /// The key with any Picoschema suffix removed.
+    name: String,
+    /// Indentation of the key's line.
+    indent: usize,
+    /// Span of the key.
+    span: Span,
+}
+
+/// Where `input.schema` and its keys sit in the frontmatter.
+///
+/// This is a line-based scan, so it only sees keys written in block style,
+/// but it lets fixes edit the schema without disturbing comments or layout.
+struct SchemaLayout {
+    /// Span of the `schema` key.
+    schema: Span,
+    /// Indentation of the `schema` key's line.
+    indent: usize,
+    /// Keys in the schema block, at any depth.
+    keys: Vec<SchemaKey>,
+    /// Byte offset just past the last line of the schema block.
+    end: usize,
+}
+
+impl SchemaLayout {
+    /// Scans the frontmatter for a block-style `input.schema`.
+    fn scan(source: &str) -> Option<Self> {
+        let mut lines = source.split_inclusive('\n');
+        let first = lines.next()?;
+        if first.trim() != "---" {
+            return None;
+        }
+
+        let mut offset = first.len();
+        let mut input_indent = None;
+        let mut layout: Option<Self> = None;
+        for (index, line) in lines.enumerate().map(|(i, l)| (i + 1, l)) {
+            let line_end = offset + line.len();
+            offset = line_end;
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.trim() == "---" {
+                break;
+            }
+            let content = line.trim_start();
+            if content.is_empty() || content.starts_with('#') {
+                continue;
+            }
+            let indent = line.len() - content.len();
+            let key = schema_key_name(content.split(':').next().unwrap_or_default());
+
+            if let Some(layout) = &mut layout {
+                if indent <= layout.indent {
+                    break;
+                }
+                layout.keys.push(SchemaKey {
+                    name: key.to_string(),
+                    indent,
+                    span: key_span(index, indent, key),
+                });
+                layout.end = line_end;
+                continue;
+            }
+            match input_indent {
+                Some(input) if indent <= input => input_indent = None,
+                Some(_) if key == "schema" => {
+                    layout = Some(Self {
+                        schema: key_span(index, indent, key),
+                        indent,
+                        keys: Vec::new(),
+                        end: line_end,
+                    });
+                    continue;
+                }
+                _ => {}
+            }
+            if indent == 0 && key == "input" {
+                input_indent = Some(indent);
+            }
+        }
+        layout
+    }
+
+    /// Returns the span of a variable's key in the schema.
+    fn key_span(&self, variable: &str) -> Option<Span> {
+        let name = schema_key_name(variable);
+        self.keys
+            .iter()
+            .find(|k| k.name == name)
+            .map(|k| k.span.clone())
+    }
+
+    /// Returns where and what to insert to declare `variable` in the schema.
+    ///
+    /// The new line goes after the schema's last line, at the indentation of
+    /// its top-level keys, as a Picoschema `string` with a TODO description.
+    /// Schemas written as JSON Schema (`type`/`properties`) are left alone.
+    fn insertion(&self, source: &str, variable: &str) -> Option<(Span, String)> {
+        let indent = self.keys.iter().map(|k| k.indent).min()?;
+        if self
+            .keys
+            .iter()
+            .any(|k| k.indent == indent && (k.name == "type" || k.name == "properties"))
+        {
+            return None;
+        }
+        let line = format!(
+            "{}{variable}: string, TODO describe {variable}\n",
+            " ".repeat(indent)
+        );
+        Some((Span::from_offsets(source, self.end, self.end), line))
+    }
+}
+
+/// Applies the fixes attached to `diagnostics` to `source`.
+///
+/// Fixes are applied from the end of the file backwards so earlier offsets
+/// stay valid. A fix that overlaps one already applied is skipped; running
+/// the linter again will offer it if it is still needed.
+///
+/// # Returns
+///
+/// The fixed source and the number of fixes applied.
+#[must_use]
+pub(crate) fn apply_fixes(source: &str, diagnostics: &[Diagnostic]) -> (String, usize) {
+    let mut fixes: Vec<(usize, &Fix)> = diagnostics
+        .iter()
+        .filter_map(|d| d.fix.as_ref())
+        .enumerate()
+        .collect();
+    // Insertions at the same offset end up in diagnostic order.
+    fixes.sort_by_key(|(index, f)| std::cmp::Reverse((f.span.start.offset, *index)));
+
+    let mut output = source.to_string();
+    let mut applied = 0;
+    let mut limit = source.len();
+    for (_, fix) in fixes {
+        let (start, end) = (fix.span.start.offset, fix.span.end.offset);
+        if start > end
+            || end > limit
+            || !output.is_char_boundary(start)
+            || !output.is_char_boundary(end)
+        {
+            continue;
+        }
+        output.replace_range(start..end, &fix.replacement);
+        limit = start;
+        applied += 1;
+    }
+    (output, applied)
+}
+
+/// Strips Picoschema suffixes from a key, e.g. `name?` or `items(array)`.
+fn schema_key_name(key: &str) -> &str {
+    key.split(['?', '(']).next().unwrap_or_default().trim()
+}
+
+/// Builds a span covering a frontmatter key on a 0-indexed line, indented by
+/// `indent` characters.
+#[allow(clippy::cast_possible_truncation)]
+fn key_span(line_index: usize, indent: usize, key: &str) -> Span {
+    let line = line_index as u32 + 1;
+    let column = indent as u32 + 1;
+    Span::from_line_col(line, column, line, column + key.chars().count() as u32)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::needless_collect)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_valid_prompt() {
+        let source = r"---
+model: gemini-2.0-flash
+config:
+  temperature: 0.7
+---
+Hello {{name}}!
+";
+
+        let linter = Linter::new();
+        let diagnostics = linter.lint(source, None);
+
+        // Should have no errors or warnings
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.severity == DiagnosticSeverity::Error)
+            .collect();
+        assert!(errors.is_empty(), "Expected no errors, got: {errors:?}");
+    }
+
+    #[test]
+    fn test_lint_invalid_yaml() {
+        let source = r#"---
 model: gemini-2.0-flash
 config:
   temperature: "not a number
@@ -731,26 +1923,6 @@ Hello world!
         );
     }
 
-    #[test]
-    fn test_calculate_body_start_line_no_frontmatter() {
-        let source = "Hello world!";
-        assert_eq!(Linter::calculate_body_start_line(source), 0);
-    }
-
-    #[test]
-    fn test_calculate_body_start_line_simple_frontmatter() {
-        // 3 lines: ---, model: ..., ---
-        let source = "---\nmodel: gemini\n---\nHello";
-        assert_eq!(Linter::calculate_body_start_line(source), 3);
-    }
-
-    #[test]
-    fn test_calculate_body_start_line_multiline_frontmatter() {
-        // 5 lines: ---, model, config, temp, ---
-        let source = "---\nmodel: gemini\nconfig:\n  temp: 0.7\n---\nHello";
-        assert_eq!(Linter::calculate_body_start_line(source), 5);
-    }
-
     #[test]
     fn test_unclosed_block_reports_correct_line_number() {
         // Lines:
@@ -787,6 +1959,36 @@ Hello world!
         );
     }
 
+    #[test]
+    fn test_positions_count_characters_in_localized_text() {
+        let source = "---\nmodel: gemini\n---\nこんにちは {{#if a}} 世界 }}";
+        let diagnostics = Linter::new().lint(source, None);
+        let column = |code: &str| {
+            diagnostics
+                .iter()
+                .find(|d| d.code == code)
+                .and_then(|d| d.span.as_ref())
+                .map(|span| (span.start.line, span.start.column))
+        };
+
+        assert_eq!(column("unclosed-block"), Some((4, 7)));
+        assert_eq!(column("unbalanced-brace"), Some((4, 20)));
+    }
+
+    #[test]
+    fn test_spans_with_emoji_and_rtl_text() {
+        let source =
+            "---\ninput:\n  schema:\n    name: string\n---\n🙂🙂 مرحبا {{name}} {{city}}\n";
+        let diagnostics = Linter::new().lint(source, None);
+        let undefined = diagnostics
+            .iter()
+            .find(|d| d.code == "undefined-variable")
+            .and_then(|d| d.span.as_ref())
+            .expect("undefined-variable span");
+
+        assert_eq!((undefined.start.line, undefined.start.column), (6, 19));
+    }
+
     #[test]
     fn test_partial_reference_reports_correct_line_number() {
         // Lines:
@@ -819,6 +2021,55 @@ Hello world!
         );
     }
 
+    #[test]
+    fn test_inline_partials_are_not_reported() {
+        let source = "---\nmodel: gemini\n---\n{{#*inline \"sig\"}}-- {{team}}{{/inline}}\n{{> sig}}\n{{> footer}}\n";
+
+        let linter = Linter::new();
+        let diagnostics = linter.lint(source, None);
+
+        let codes: Vec<_> = diagnostics.iter().map(|d| d.code.as_str()).collect();
+        assert!(!codes.contains(&"unmatched-closing-block"), "{codes:?}");
+        assert!(!codes.contains(&"unclosed-block"), "{codes:?}");
+        let partials: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.code == "unverified-partial")
+            .collect();
+        assert_eq!(partials.len(), 1, "Only footer is external: {partials:?}");
+        assert!(partials[0].message.contains("footer"));
+    }
+
+    #[test]
+    fn test_yaml_anchors_and_merge_keys() {
+        let linter = Linter::new();
+
+        let source =
+            "---\nbase: &base\n  temperature: 0.2\nconfig:\n  <<: *base\n  topK: 5\n---\nHello";
+        let diagnostics = linter.lint(source, None);
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.severity != DiagnosticSeverity::Error),
+            "{diagnostics:?}"
+        );
+
+        let source = "---\nmodel: gemini\nconfig:\n  <<: *missing\n---\nHello";
+        let diagnostics = linter.lint(source, None);
+        let alias = diagnostics
+            .iter()
+            .find(|d| d.code == "unknown-yaml-alias")
+            .expect("Expected unknown-yaml-alias");
+        assert!(alias.message.contains("*missing"));
+        let span = alias.span.as_ref().expect("Expected span on diagnostic");
+        assert_eq!((span.start.line, span.end.line), (4, 4));
+        assert_eq!((span.start.column, span.end.column), (7, 15));
+        assert!(!diagnostics.iter().any(|d| d.code == "invalid-yaml"));
+
+        let source = "---\nconfig:\n  <<: 3\n---\nHello";
+        let diagnostics = linter.lint(source, None);
+        assert!(diagnostics.iter().any(|d| d.code == "invalid-yaml"));
+    }
+
     #[test]
     fn test_yaml_error_reports_correct_line_number() {
         // Lines:
@@ -855,4 +2106,443 @@ Hello world!
             span.start.line
         );
     }
+
+    #[test]
+    fn test_yaml_error_span_covers_construct() {
+        let source = "---\nmodel: gemini\nconfig: {temperature: 0.2\ntopK: 5\n---\nHello";
+        let diagnostics = Linter::new().lint(source, None);
+        let error = diagnostics
+            .iter()
+            .find(|d| d.code == "invalid-yaml")
+            .expect("Expected invalid-yaml");
+        let span = error.span.as_ref().expect("Expected span on YAML error");
+        // From the unclosed `{` to where parsing failed
+        assert_eq!((span.start.line, span.start.column), (3, 9));
+        assert_eq!(span.end.line, 4);
+    }
+
+    #[test]
+    fn test_duplicate_frontmatter_key() {
+        let source =
+            "---\nmodel: gemini\nconfig:\n  temperature: 0.2\n  temperature: 0.9\n---\nHello";
+        let diagnostics = Linter::new().lint(source, None);
+        let duplicates: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.code == "duplicate-frontmatter-key")
+            .collect();
+        assert_eq!(duplicates.len(), 1, "{diagnostics:?}");
+        assert!(duplicates[0].message.contains("config.temperature"));
+        let span = duplicates[0].span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (5, 3));
+        assert_eq!((span.end.line, span.end.column), (5, 14));
+        assert_eq!(duplicates[0].related.len(), 1);
+        let first = &duplicates[0].related[0].span;
+        assert_eq!((first.start.line, first.start.column), (4, 3));
+        assert_eq!((first.end.line, first.end.column), (4, 14));
+        assert!(!diagnostics.iter().any(|d| d.code == "invalid-yaml"));
+
+        let source = "---\nmodel: a\ntools: [{name: x, name: y}]\nmodel: b\n---\nHello";
+        let diagnostics = Linter::new().lint(source, None);
+        let paths: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.code == "duplicate-frontmatter-key")
+            .map(|d| d.message.split('\'').nth(1).unwrap_or_default())
+            .collect();
+        assert_eq!(paths, ["tools[0].name", "model"]);
+    }
+
+    #[test]
+    fn test_output_format_and_schema_agree() {
+        let codes = |source: &str| -> Vec<String> {
+            Linter::new()
+                .lint(source, None)
+                .into_iter()
+                .map(|d| d.code)
+                .filter(|c| c.contains("format") || c.contains("schema"))
+                .collect()
+        };
+
+        let source = "---\nmodel: gemini\noutput:\n  format: json\n---\nHello";
+        let diagnostics = Linter::new().lint(source, None);
+        let json = diagnostics
+            .iter()
+            .find(|d| d.code == "json-output-without-schema")
+            .expect("Expected json-output-without-schema");
+        let span = json.span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (4, 3));
+
+        let source = "---\noutput:\n  format: text\n  schema:\n    answer: string\n---\nHello";
+        let diagnostics = Linter::new().lint(source, None);
+        let schema = diagnostics
+            .iter()
+            .find(|d| d.code == "schema-without-format")
+            .expect("Expected schema-without-format");
+        let span = schema.span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (4, 3));
+        assert_eq!(schema.related.len(), 1);
+
+        assert!(
+            codes("---\noutput:\n  format: json\n  schema: {answer: string}\n---\nHi").is_empty()
+        );
+        assert!(codes("---\noutput:\n  schema: {answer: string}\n---\nHi").is_empty());
+        assert!(codes("---\noutput:\n  format: text\n---\nHi").is_empty());
+    }
+
+    #[test]
+    fn test_unknown_output_format() {
+        let source = "---\noutput:\n  format: my-xml\n---\nHi";
+        let diagnostics = Linter::new().lint(source, None);
+        let unknown = diagnostics
+            .iter()
+            .find(|d| d.code == "unknown-output-format")
+            .expect("Expected unknown-output-format");
+        assert!(unknown.message.contains("'my-xml'"));
+        let span = unknown.span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (3, 3));
+
+        let declared = Linter::new().with_output_formats(vec!["my-xml".to_string()]);
+        assert!(
+            !declared
+                .lint(source, None)
+                .iter()
+                .any(|d| d.code == "unknown-output-format")
+        );
+    }
+
+    #[test]
+    fn test_role_structure() {
+        let source = "---\nmodel: gemini\n---\nHi there.\n{{role \"system\"}}\nBe brief.\n{{role \"model\"}}  {{! nothing }}\n{{role 'user'}}\n{{history}}\nQ?\n{{history}}";
+        let diagnostics = Linter::new().lint(source, None);
+        let find = |code: &str| {
+            diagnostics
+                .iter()
+                .filter(|d| d.code == code)
+                .collect::<Vec<_>>()
+        };
+
+        let system = find("system-after-user");
+        assert_eq!(system.len(), 1);
+        let span = system[0].span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (5, 1));
+
+        // The model block only holds a comment; the user block is followed
+        // directly by history.
+        let empty = find("empty-role-block");
+        assert_eq!(empty.len(), 2, "{empty:?}");
+        assert!(empty[0].message.contains("'model'"));
+        assert_eq!(
+            empty[0].fix.as_ref().map(|f| f.replacement.as_str()),
+            Some("")
+        );
+
+        let history = find("multiple-history");
+        assert_eq!(history.len(), 1);
+        let span = history[0].span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (11, 1));
+        assert_eq!(history[0].related.len(), 1);
+
+        let source = "---\nmodel: gemini\n---\n{{role \"system\"}}\nBe brief.\n{{history}}\n{{role \"user\"}}\nHi";
+        let diagnostics = Linter::new().lint(source, None);
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.severity == DiagnosticSeverity::Info),
+            "{diagnostics:?}"
+        );
+    }
+
+    #[test]
+    fn test_template_too_complex() {
+        let limits = ComplexityLimits {
+            depth: 2,
+            conditionals: 2,
+            partials: 1,
+        };
+        let linter = Linter::new().with_complexity(limits);
+        let complex = |source: &str| -> Vec<Diagnostic> {
+            linter
+                .lint(source, None)
+                .into_iter()
+                .filter(|d| d.code == "template-too-complex")
+                .collect()
+        };
+
+        let source = "---\nmodel: gemini\n---\n{{#if a}}\n{{#each b}}\n{{#if c}}x{{/if}}\n{{/each}}\n{{else if d}}\n{{/if}}";
+        let diagnostics = complex(source);
+        assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Info);
+        let span = diagnostics[0].span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (6, 1));
+        assert!(diagnostics[1].message.contains("conditional"));
+        let span = diagnostics[1].span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (8, 1));
+
+        let diagnostics = complex("{{> a}} {{> a}} {{> b}}");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("partials"));
+
+        assert!(complex("{{#if a}}{{#if b}}x{{/if}}{{/if}}").is_empty());
+        assert!(
+            Linter::new()
+                .lint("{{#if a}}{{#if b}}{{/if}}{{/if}}", None)
+                .iter()
+                .all(|d| d.code != "template-too-complex")
+        );
+    }
+
+    #[test]
+    fn test_cost_over_budget() {
+        let budget = |max_usd| {
+            Linter::new().with_cost_budget(Some(CostBudget {
+                max_usd,
+                pricing: PricingTable::builtin(),
+            }))
+        };
+        let over = |linter: &Linter, source: &str| -> Vec<Diagnostic> {
+            linter
+                .lint(source, None)
+                .into_iter()
+                .filter(|d| d.code == "cost-over-budget")
+                .collect()
+        };
+
+        // 4000 output tokens at $10 per million is $0.04.
+        let source = "---\nmodel: openai/gpt-4o\nconfig:\n  maxOutputTokens: 4000\ninput:\n  default:\n    name: Ada\n---\nHello {{name}}!";
+        let diagnostics = over(&budget(0.01), source);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert!(diagnostics[0].message.contains("$0.0400"));
+        let span = diagnostics[0].span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (2, 8));
+        assert_eq!(
+            diagnostics[0].rendered.as_deref(),
+            Some("── user ──\nHello Ada!")
+        );
+
+        assert!(over(&budget(0.05), source).is_empty());
+        assert!(over(&budget(0.0), "---\nmodel: unknown/model\n---\nHi").is_empty());
+        assert!(over(&budget(0.0), "Hi").is_empty());
+        assert!(over(&Linter::new(), source).is_empty());
+    }
+
+    #[test]
+    fn test_spelling_is_opt_in() {
+        let source =
+            "---\nmodel: gemini\ndescription: teh\n---\nPlease recieve {{teh}} the answer.";
+        assert!(
+            !Linter::new()
+                .lint(source, None)
+                .iter()
+                .any(|d| d.code == "spelling")
+        );
+
+        let linter = Linter::new().with_spelling(SpellingConfig {
+            enabled: true,
+            allow: Vec::new(),
+        });
+        let diagnostics = linter.lint(source, None);
+        let spelling: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.code == "spelling")
+            .collect();
+        assert_eq!(spelling.len(), 1, "{spelling:?}");
+        let span = spelling[0].span.as_ref().expect("Expected span");
+        assert_eq!((span.start.line, span.start.column), (5, 8));
+        let (fixed, count) = apply_fixes(source, &diagnostics);
+        assert_eq!(count, 1);
+        assert!(fixed.ends_with("Please receive {{teh}} the answer."));
+    }
+
+    #[test]
+    fn test_missing_owner_only_when_required() {
+        let source = "---\nmodel: gemini\n---\nHello";
+
+        let lenient = Linter::new().lint(source, None);
+        assert!(!lenient.iter().any(|d| d.code == "missing-owner"));
+
+        let strict = Linter::new().with_require_owners(true).lint(source, None);
+        assert!(strict.iter().any(|d| d.code == "missing-owner"));
+
+        let owned = "---\nowners: [alice]\n---\nHello";
+        let diagnostics = Linter::new().with_require_owners(true).lint(owned, None);
+        assert!(!diagnostics.iter().any(|d| d.code == "missing-owner"));
+    }
+
+    #[test]
+    fn test_promptowners_cross_check() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("PROMPTOWNERS"), "*.prompt @prompt-team\n").unwrap();
+        let path = dir.path().join("greet.prompt");
+        let linter = Linter::new().with_require_owners(true);
+
+        // PROMPTOWNERS alone satisfies the ownership requirement.
+        let source = "Hello";
+        fs::write(&path, source).unwrap();
+        let diagnostics = linter.lint(source, Some(&path));
+        assert!(!diagnostics.iter().any(|d| d.code == "missing-owner"));
+
+        let source = "---\nowners: [\"@someone-else\"]\n---\nHello";
+        fs::write(&path, source).unwrap();
+        let diagnostics = linter.lint(source, Some(&path));
+        assert!(diagnostics.iter().any(|d| d.code == "owner-mismatch"));
+
+        let source = "---\nowners: [\"@prompt-team\"]\n---\nHello";
+        fs::write(&path, source).unwrap();
+        let diagnostics = linter.lint(source, Some(&path));
+        assert!(!diagnostics.iter().any(|d| d.code == "owner-mismatch"));
+    }
+
+    #[test]
+    fn test_unknown_schema_ref() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("schemas")).unwrap();
+        fs::write(
+            dir.path().join("schemas/Person.schema.yaml"),
+            "type: object\n",
+        )
+        .unwrap();
+        let path = dir.path().join("review.prompt");
+        let source = "---\ninput:\n  schema:\n    author: Person\n    reviewers?: Reviewer[] | null\n    note: string, a Note for Robot\n    tone(enum): [Formal, casual]\noutput:\n  schema:\n    $ref: \"#/schemas/Review\"\n---\nReview {{author}}";
+        let unknown: Vec<_> = Linter::new()
+            .lint(source, Some(&path))
+            .into_iter()
+            .filter(|d| d.code == "unknown-schema-ref")
+            .collect();
+        assert_eq!(unknown.len(), 2, "{unknown:?}");
+        assert!(unknown[0].message.contains("'Reviewer'"));
+        assert!(unknown[1].message.contains("'Review'"));
+        let span = unknown[1].span.as_ref().unwrap();
+        assert_eq!((span.start.line, span.start.column), (10, 11));
+
+        // Without a path there is no schemas directory to check.
+        let diagnostics = Linter::new().lint(source, None);
+        assert!(!diagnostics.iter().any(|d| d.code == "unknown-schema-ref"));
+    }
+
+    #[test]
+    fn test_unknown_and_unused_tools() {
+        let catalog = "tools:\n  - name: search\n    inputSchema: {}\n  - name: weather\n    inputSchema: {}\n";
+        let linter = Linter::new().with_tools(Some(ToolCatalog::from_yaml(catalog).unwrap()));
+        let source = "---\ntools: [search, serach]\n---\nFind it";
+        let unknown: Vec<_> = linter
+            .lint(source, None)
+            .into_iter()
+            .filter(|d| d.code == "unknown-tool")
+            .collect();
+        assert_eq!(unknown.len(), 1, "{unknown:?}");
+        assert!(unknown[0].message.contains("'serach'"));
+        let span = unknown[0].span.as_ref().unwrap();
+        assert_eq!((span.start.line, span.start.column), (2, 17));
+        assert!(
+            !Linter::new()
+                .lint(source, None)
+                .iter()
+                .any(|d| d.code == "unknown-tool")
+        );
+
+        let used: HashSet<String> = Linter::prompt_tools(source).into_iter().collect();
+        let unused = Linter::unused_tools(catalog, &used);
+        assert_eq!(unused.len(), 1);
+        assert!(unused[0].message.contains("'weather'"));
+        assert_eq!(unused[0].span.as_ref().unwrap().start.line, 4);
+    }
+
+    #[test]
+    fn test_variable_diagnostics_point_into_schema() {
+        let source = "---\ninput:\n  schema:\n    name: string\n    age?: number\n---\nHello {{name}} {{city}}";
+        let diagnostics = Linter::new().lint(source, None);
+
+        let unused = diagnostics
+            .iter()
+            .find(|d| d.code == "unused-variable")
+            .unwrap();
+        assert_eq!(
+            unused.span,
+            Some(Span::from_line_col(5, 5, 5, 8)),
+            "should point at the 'age' key"
+        );
+
+        let undefined = diagnostics
+            .iter()
+            .find(|d| d.code == "undefined-variable")
+            .unwrap();
+        assert_eq!(undefined.related.len(), 1);
+        assert_eq!(undefined.related[0].span, Span::from_line_col(3, 3, 3, 9));
+    }
+
+    #[test]
+    fn test_apply_fixes() {
+        let source = "---\ndescription: Note: be brief\n---\n{{#if a}}x{{/if}}{{/each}}\n";
+        let diagnostics = Linter::new().lint(source, None);
+        let (fixed, applied) = apply_fixes(source, &diagnostics);
+
+        assert_eq!(applied, 2);
+        assert_eq!(
+            fixed,
+            "---\ndescription: \"Note: be brief\"\n---\n{{#if a}}x{{/if}}\n"
+        );
+        assert!(Linter::new().lint(&fixed, None).is_empty());
+    }
+
+    #[test]
+    fn test_fix_inserts_undefined_variables_into_schema() {
+        let source = "---\ninput:\n  schema:\n    # who to greet\n    name: string\n\n    style?:\n      tone: string\noutput:\n  format: text\n---\nHi {{name}} from {{city}} on {{day}}, {{style.tone}}\n";
+        let diagnostics = Linter::new().lint(source, None);
+        let (fixed, applied) = apply_fixes(source, &diagnostics);
+
+        assert_eq!(applied, 2);
+        assert_eq!(
+            fixed,
+            "---\ninput:\n  schema:\n    # who to greet\n    name: string\n\n    style?:\n      tone: string\n    city: string, TODO describe city\n    day: string, TODO describe day\noutput:\n  format: text\n---\nHi {{name}} from {{city}} on {{day}}, {{style.tone}}\n"
+        );
+        assert!(
+            !Linter::new()
+                .lint(&fixed, None)
+                .iter()
+                .any(|d| d.code == "undefined-variable")
+        );
+
+        let json_schema = "---\ninput:\n  schema:\n    type: object\n    properties:\n      name: {type: string}\n---\n{{name}} {{city}}";
+        let diagnostics = Linter::new().lint(json_schema, None);
+        assert!(diagnostics.iter().all(|d| d.fix.is_none()));
+    }
+
+    #[test]
+    fn test_verbatim_regions_are_not_linted() {
+        let linter = Linter::new();
+        let source = "---\ninput:\n  schema:\n    name: string\n---\n\
+            {{name}}\n{{{{raw}}}}{{#if x}}{{code}}{{{{/raw}}}}\n\
+            {{#verbatim}}\n{{example}} }}\n{{/verbatim}}\n";
+
+        let diagnostics = linter.lint(source, None);
+        assert!(diagnostics.is_empty(), "unexpected: {diagnostics:?}");
+    }
+
+    #[test]
+    fn test_declared_helpers_are_not_variables() {
+        let source = "---\ninput:\n  schema:\n    name: string\nhelpers:\n  - name: card\n    block: true\n---\n{{today}} {{name}}\n{{card}}\n{{#today}}{{/today}}\n";
+        let project = vec![HelperDecl {
+            name: "today".to_string(),
+            block: false,
+            signature: None,
+            description: None,
+        }];
+        let diagnostics = Linter::new().with_helpers(project).lint(source, None);
+        let codes: Vec<_> = diagnostics.iter().map(|d| d.code.as_str()).collect();
+
+        assert_eq!(codes, vec!["helper-misuse", "helper-misuse"]);
+        assert!(diagnostics[0].message.contains("'card' is a block helper"));
+        assert!(
+            diagnostics[1]
+                .message
+                .contains("'today' is an inline helper")
+        );
+
+        let undeclared = Linter::new().lint(source, None);
+        assert!(
+            undeclared
+                .iter()
+                .any(|d| d.code == "undefined-variable" && d.message.contains("'today'"))
+        );
+    }
 }
@@ -0,0 +1,117 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured logging for the CLI.
+//!
+//! Logging is off unless `--log-level` or `RUST_LOG` is set. Logs go to
+//! stderr by default so they never mix with command output (or with the LSP
+//! protocol on stdout); `--log-file` redirects them to a file instead.
+
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use clap::{Args, ValueEnum};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// Log verbosity levels.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum LogLevel {
+    /// Only errors.
+    Error,
+    /// Errors and warnings.
+    Warn,
+    /// High-level progress.
+    Info,
+    /// Per-file spans with timing.
+    Debug,
+    /// Everything.
+    Trace,
+}
+
+impl LogLevel {
+    /// Returns the filter directive for this level.
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+}
+
+/// Global logging options.
+#[derive(Args, Debug)]
+#[allow(clippy::struct_field_names)] // Field names become the `--log-*` flags.
+pub(crate) struct LogArgs {
+    /// Log verbosity (defaults to `RUST_LOG`, or off)
+    #[arg(long, global = true, value_name = "LEVEL")]
+    pub log_level: Option<LogLevel>,
+
+    /// Emit logs as JSON lines
+    #[arg(long, global = true)]
+    pub log_json: bool,
+
+    /// Write logs to this file instead of stderr
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+}
+
+/// Initializes the global tracing subscriber.
+///
+/// Does nothing if neither `--log-level` nor `RUST_LOG` is set.
+///
+/// # Errors
+///
+/// Returns an error if the filter is invalid or the log file cannot be opened.
+pub(crate) fn init(args: &LogArgs) -> Result<(), String> {
+    let filter = match args.log_level {
+        Some(level) => EnvFilter::new(level.as_str()),
+        None => match std::env::var(EnvFilter::DEFAULT_ENV) {
+            Ok(directives) => EnvFilter::try_new(directives)
+                .map_err(|e| format!("Invalid {}: {e}", EnvFilter::DEFAULT_ENV))?,
+            Err(_) => return Ok(()),
+        },
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_target(false);
+
+    let result = match (&args.log_file, args.log_json) {
+        (Some(path), json) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("Failed to open log file {}: {e}", path.display()))?;
+            let builder = builder.with_ansi(false).with_writer(Mutex::new(file));
+            if json {
+                builder.json().try_init()
+            } else {
+                builder.try_init()
+            }
+        }
+        (None, true) => builder.with_writer(std::io::stderr).json().try_init(),
+        (None, false) => builder.with_writer(std::io::stderr).try_init(),
+    };
+
+    result.map_err(|e| format!("Failed to initialize logging: {e}"))
+}
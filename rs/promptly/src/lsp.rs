@@ -17,29 +17,170 @@
 //! Language Server Protocol (LSP) backend for `.prompt` files.
 //!
 //! This module implements an LSP server that provides:
-//! - Diagnostics (errors and warnings)
+//! - Diagnostics (errors and warnings), pushed or pulled (LSP 3.17)
 //! - Document formatting
-//! - Hover documentation
+//! - Quick fixes for diagnostics that carry a fix
+//! - Hover documentation and helper completion, including declared custom helpers
+//! - Folding ranges and selection ranges
+//! - Document links for partials and media URLs
+//!
+//! The server runs over stdio by default, or over TCP or WebSocket for
+//! containerized and remote-development setups.
 
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 
+use dotprompt::helpers::BUILTIN_HELPERS;
+use dotprompt::parse::parse_document_lenient;
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::Diagnostic as LspDiagnostic;
 use tower_lsp::lsp_types::DiagnosticSeverity as LspDiagSeverity;
 use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, CodeActionResponse, CompletionItem, CompletionItemKind,
+    CompletionOptions, CompletionParams, CompletionResponse, DiagnosticOptions,
+    DiagnosticRelatedInformation, DiagnosticServerCapabilities, DiagnosticTag,
     DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
-    DidSaveTextDocumentParams, DocumentFormattingParams, Hover, HoverContents, HoverParams,
-    HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams, MarkupContent,
-    MarkupKind, MessageType, NumberOrString, OneOf, Position, Range, ServerCapabilities,
-    ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+    DidSaveTextDocumentParams, DocumentDiagnosticParams, DocumentDiagnosticReport,
+    DocumentDiagnosticReportResult, DocumentFormattingParams, DocumentLink, DocumentLinkOptions,
+    DocumentLinkParams, Documentation, FoldingRange, FoldingRangeKind, FoldingRangeParams,
+    FoldingRangeProviderCapability, FullDocumentDiagnosticReport, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams,
+    Location, MarkupContent, MarkupKind, MessageType, NumberOrString, OneOf, Position, Range,
+    RelatedFullDocumentDiagnosticReport, RelatedUnchangedDocumentDiagnosticReport, SelectionRange,
+    SelectionRangeParams, SelectionRangeProviderCapability, SemanticToken, SemanticTokenModifier,
+    SemanticTokenType, SemanticTokens, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
+    SemanticTokensServerCapabilities, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, UnchangedDocumentDiagnosticReport, Url,
+    WorkDoneProgressOptions, WorkspaceEdit,
 };
 use tower_lsp::{Client, LanguageServer, LspService, Server};
+use tracing::{debug, info};
 
+use crate::config::Config;
 use crate::formatter::{Formatter, FormatterConfig};
-use crate::linter::{DiagnosticSeverity as LintSeverity, Linter};
+use crate::helpers::{HelperDecl, declared_helpers};
+use crate::links::{LinkTarget, find_links, resolve_partial};
+use crate::linter::{Diagnostic, DiagnosticSeverity as LintSeverity, Linter};
+use crate::span::{SourceMap, Span};
+use crate::structure::{DocumentStructure, RegionKind};
+use crate::verbatim::verbatim_regions;
+
+/// Helpers built into Handlebars itself.
+const HANDLEBARS_HELPERS: [&str; 4] = ["if", "unless", "each", "with"];
+
+/// Returns the helpers that every dotprompt template can use.
+fn builtin_helpers() -> impl Iterator<Item = &'static str> {
+    HANDLEBARS_HELPERS.into_iter().chain(BUILTIN_HELPERS)
+}
+
+/// Handlebars keywords, highlighted as keywords rather than helpers.
+const HANDLEBARS_KEYWORDS: [&str; 13] = [
+    "if",
+    "unless",
+    "each",
+    "with",
+    "else",
+    "lookup",
+    "log",
+    "as",
+    "this",
+    "true",
+    "false",
+    "null",
+    "undefined",
+];
+
+/// Semantic token types, in legend order.
+const TOKEN_TYPES: [SemanticTokenType; 8] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::NAMESPACE,
+];
+
+/// Semantic token modifiers, in legend order. Built-in helpers are marked
+/// `defaultLibrary`.
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 1] = [SemanticTokenModifier::DEFAULT_LIBRARY];
+
+/// Language identifier clients register `.prompt` files under.
+const LANGUAGE_ID: &str = "dotprompt";
+
+/// Returns the legend for the semantic tokens the server reports.
+pub(crate) fn semantic_tokens_legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// Returns the configuration a client needs to run the server.
+///
+/// The `packageJson` entries merge into a VS Code extension manifest, and
+/// `languageConfiguration` is the file its `configuration` path names.
+/// `serverOptions` and `clientOptions` follow `vscode-languageclient`.
+pub(crate) fn client_config() -> serde_json::Value {
+    serde_json::json!({
+        "serverOptions": {
+            "command": "promptly",
+            "args": ["lsp"],
+            "transport": "stdio",
+        },
+        "clientOptions": {
+            "documentSelector": [{ "scheme": "file", "language": LANGUAGE_ID }],
+            "synchronize": { "fileEvents": "**/*.prompt" },
+        },
+        "packageJson": {
+            "activationEvents": [format!("onLanguage:{LANGUAGE_ID}")],
+            "contributes": {
+                "languages": [{
+                    "id": LANGUAGE_ID,
+                    "aliases": ["Dotprompt", LANGUAGE_ID],
+                    "extensions": [".prompt"],
+                    "configuration": "./language-configuration.json",
+                }],
+                "grammars": [{
+                    "language": LANGUAGE_ID,
+                    "scopeName": "source.dotprompt",
+                    "path": "./syntaxes/dotprompt.tmLanguage.json",
+                }],
+            },
+        },
+        "languageConfiguration": {
+            "comments": { "blockComment": ["{{!--", "--}}"] },
+            "brackets": [["{", "}"], ["[", "]"], ["(", ")"], ["<<<", ">>>"]],
+            "autoClosingPairs": [
+                { "open": "{", "close": "}" },
+                { "open": "[", "close": "]" },
+                { "open": "(", "close": ")" },
+                { "open": "\"", "close": "\"", "notIn": ["string"] },
+                { "open": "'", "close": "'", "notIn": ["string"] },
+                { "open": "<<<", "close": ">>>", "notIn": ["string", "comment"] },
+            ],
+            "surroundingPairs": [
+                ["{", "}"], ["[", "]"], ["(", ")"], ["\"", "\""], ["'", "'"], ["<<<", ">>>"],
+            ],
+        },
+        "semanticTokensLegend": semantic_tokens_legend(),
+    })
+}
 
 /// Documentation for built-in Handlebars helpers.
+#[allow(clippy::too_many_lines)]
 fn get_helper_docs(name: &str) -> Option<&'static str> {
     match name {
         "if" => Some(
@@ -131,6 +272,122 @@ fn get_helper_docs(name: &str) -> Option<&'static str> {
             {{/section}}\n\
             ```",
         ),
+        "first" | "last" => Some(
+            "## `{{first items}}` / `{{last items}}`\n\n\
+            Returns the first or last element of an array.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            Top result: {{first results}}\n\
+            ```",
+        ),
+        "slice" => Some(
+            "## `{{slice items start end}}`\n\n\
+            Returns the elements from `start` up to `end`. Negative indices count \
+            from the end, and `end` defaults to the length.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{#each (slice items 0 3)}}- {{this}}\n{{/each}}\n\
+            ```",
+        ),
+        "join" => Some(
+            "## `{{join items separator}}`\n\n\
+            Joins the elements of an array. The separator defaults to `\", \"`.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            Tags: {{join tags \" | \"}}\n\
+            ```",
+        ),
+        "add" | "subtract" | "multiply" | "divide" => Some(
+            "## `{{add a b}}`, `{{subtract a b}}`, `{{multiply a b}}`, `{{divide a b}}`\n\n\
+            Computes with numbers or numeric strings. Whole results render \
+            without a fraction.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{subtract budget spent}} left of {{budget}}\n\
+            ```",
+        ),
+        "round" | "percent" => Some(
+            "## `{{round value places}}` / `{{percent value total places}}`\n\n\
+            Rounds a number, or a percentage of a total, to whole numbers or \
+            `places` decimal places.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{percent done total 1}}% complete\n\
+            ```",
+        ),
+        "default" | "coalesce" => Some(
+            "## `{{default value fallback}}` / `{{coalesce a b c}}`\n\n\
+            Returns the first argument that is not missing, null, an empty \
+            string, or an empty array.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            Hello, {{default nickname \"friend\"}}!\n\
+            ```",
+        ),
+        "pluralize" => Some(
+            "## `{{pluralize count singular plural}}`\n\n\
+            Returns the singular form for a count of 1, and the plural form \
+            otherwise. The plural defaults to the singular plus `s`.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{count}} {{pluralize count \"person\" \"people\"}}\n\
+            ```",
+        ),
+        "formatNumber" => Some(
+            "## `{{formatNumber value decimals=2 locale=\"en\"}}`\n\n\
+            Groups digits by thousands with a locale's separators, optionally \
+            rounding to a fixed number of decimals.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            Total: {{formatNumber total decimals=2 locale=\"de\"}} EUR\n\
+            ```",
+        ),
+        "verbatim" => Some(
+            "## `{{#verbatim}}...{{/verbatim}}`\n\n\
+            Keeps role, media, history, and section markers inside the block as \
+            text. The formatter and linter leave the block's content alone.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{#verbatim}}Write {{role \"system\"}} to switch roles.{{/verbatim}}\n\
+            ```",
+        ),
+        "raw" => Some(
+            "## `{{{{raw}}}}...{{{{/raw}}}}`\n\n\
+            Renders the block's content exactly as written, without evaluating \
+            Handlebars expressions or reading markers.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{{{raw}}}}Use {{name}} to insert a name.{{{{/raw}}}}\n\
+            ```",
+        ),
+        "table" => Some(
+            "## `{{table rows columns=\"name,score\"}}`\n\n\
+            Renders an array of objects as a Markdown table. `columns` picks \
+            the keys to show; by default every key is shown.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{table results columns=\"name,score\"}}\n\
+            ```",
+        ),
+        "truncateTokens" => Some(
+            "## `{{truncateTokens text 500 strategy=\"end\"}}`\n\n\
+            Shortens text to about a number of tokens and marks the cut with \
+            `…`. `strategy=\"middle\"` keeps the start and end instead of \
+            the start.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{truncateTokens document 500 strategy=\"middle\"}}\n\
+            ```",
+        ),
+        "length" => Some(
+            "## `{{length value}}`\n\n\
+            Returns the number of elements in an array, characters in a string, \
+            or keys in an object.\n\n\
+            **Example:**\n\
+            ```handlebars\n\
+            {{#if (length items)}}{{length items}} items{{/if}}\n\
+            ```",
+        ),
         _ => None,
     }
 }
@@ -189,6 +446,27 @@ fn get_frontmatter_field_docs(field: &str) -> Option<&'static str> {
               - calculator\n\
             ```",
         ),
+        "tags" => Some(
+            "## `tags`\n\n\
+            Labels used to categorize the prompt. Filter by tag with\n\
+            `promptly ls --tag <TAG>`.\n\n\
+            **Example:**\n\
+            ```yaml\n\
+            tags: [support, beta]\n\
+            ```",
+        ),
+        "helpers" => Some(
+            "## `helpers`\n\n\
+            Custom helpers this prompt uses, so tooling does not report them\n\
+            as undefined variables. Mark block helpers with `block: true`.\n\n\
+            **Example:**\n\
+            ```yaml\n\
+            helpers:\n  \
+              - today\n  \
+              - name: card\n    \
+                block: true\n\
+            ```",
+        ),
         _ => None,
     }
 }
@@ -196,14 +474,27 @@ fn get_frontmatter_field_docs(field: &str) -> Option<&'static str> {
 /// Thread-safe document storage.
 type DocumentStore = Arc<RwLock<HashMap<Url, String>>>;
 
+/// The config and linter for the documents in one directory.
+#[derive(Debug)]
+struct DirSettings {
+    config: Config,
+    linter: Linter,
+}
+
 /// The LSP backend for promptly.
 #[derive(Debug)]
 pub(crate) struct Backend {
     client: Client,
-    linter: Arc<Linter>,
+    /// Settings by directory, loaded from the `promptly.toml` files above
+    /// each directory the first time one of its documents needs them.
+    settings: Arc<RwLock<HashMap<PathBuf, Arc<DirSettings>>>>,
     formatter: Arc<Formatter>,
     /// Document content storage for formatting support.
     documents: DocumentStore,
+    /// Workspace folders reported by the client, used to resolve partials.
+    workspace_roots: Arc<RwLock<Vec<PathBuf>>>,
+    /// Whether the client pulls diagnostics instead of receiving them.
+    pull_diagnostics: Arc<AtomicBool>,
 }
 
 impl Backend {
@@ -211,17 +502,60 @@ impl Backend {
     pub(crate) fn new(client: Client) -> Self {
         Self {
             client,
-            linter: Arc::new(Linter::new()),
+            settings: Arc::new(RwLock::new(HashMap::new())),
             formatter: Arc::new(Formatter::new(FormatterConfig::default())),
             documents: Arc::new(RwLock::new(HashMap::new())),
+            workspace_roots: Arc::new(RwLock::new(Vec::new())),
+            pull_diagnostics: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    /// Publishes diagnostics for a document.
-    async fn publish_diagnostics(&self, uri: Url, text: &str) {
-        let diagnostics = self.linter.lint(text, None);
+    /// Returns the settings for a document.
+    ///
+    /// Documents that are not files use the settings of the first workspace
+    /// folder.
+    fn settings(&self, uri: &Url) -> Arc<DirSettings> {
+        let dir = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .or_else(|| self.workspace_roots.read().ok()?.first().cloned())
+            .unwrap_or_default();
+        if let Some(settings) = self
+            .settings
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(&dir).cloned())
+        {
+            return settings;
+        }
+        let config = Config::load(&dir);
+        let settings = Arc::new(DirSettings {
+            linter: config.linter(),
+            config,
+        });
+        if let Ok(mut cache) = self.settings.write() {
+            cache.insert(dir, Arc::clone(&settings));
+        }
+        settings
+    }
 
-        let lsp_diagnostics: Vec<LspDiagnostic> = diagnostics
+    /// Lints a document, applying the configured rule levels.
+    fn lint(&self, uri: &Url, text: &str) -> Vec<Diagnostic> {
+        let settings = self.settings(uri);
+        settings
+            .config
+            .apply_rules(settings.linter.lint(text, None))
+    }
+
+    /// Lints a document and converts the results to LSP diagnostics.
+    #[tracing::instrument(skip_all, fields(uri = %uri))]
+    fn diagnostics(&self, uri: &Url, text: &str) -> Vec<LspDiagnostic> {
+        let diagnostics = self.lint(uri, text);
+        debug!(count = diagnostics.len(), "linted document");
+        let map = SourceMap::new(text);
+
+        diagnostics
             .into_iter()
             .map(|d| {
                 let severity = match d.severity {
@@ -230,22 +564,24 @@ impl Backend {
                     LintSeverity::Info => Some(LspDiagSeverity::INFORMATION),
                 };
 
-                let range = d.span.map_or_else(
+                let range = d.span.as_ref().map_or_else(
                     || Range::new(Position::new(0, 0), Position::new(0, 0)),
-                    |span| {
-                        Range::new(
-                            Position::new(
-                                span.start.line.saturating_sub(1),
-                                span.start.column.saturating_sub(1),
-                            ),
-                            Position::new(
-                                span.end.line.saturating_sub(1),
-                                span.end.column.saturating_sub(1),
-                            ),
-                        )
-                    },
+                    |span| lsp_range(&map, span),
                 );
 
+                let related_information = (!d.related.is_empty()).then(|| {
+                    d.related
+                        .iter()
+                        .map(|related| DiagnosticRelatedInformation {
+                            location: Location::new(uri.clone(), lsp_range(&map, &related.span)),
+                            message: related.message.clone(),
+                        })
+                        .collect()
+                });
+
+                // Unused code is rendered faded out by most clients.
+                let tags = (d.code == "unused-variable").then(|| vec![DiagnosticTag::UNNECESSARY]);
+
                 LspDiagnostic {
                     range,
                     severity,
@@ -253,15 +589,22 @@ impl Backend {
                     code_description: None,
                     source: Some("promptly".to_string()),
                     message: d.message,
-                    related_information: None,
-                    tags: None,
+                    related_information,
+                    tags,
                     data: None,
                 }
             })
-            .collect();
+            .collect()
+    }
 
+    /// Publishes diagnostics for a document, unless the client pulls them.
+    async fn publish_diagnostics(&self, uri: Url, text: &str) {
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            return;
+        }
+        let diagnostics = self.diagnostics(&uri, text);
         self.client
-            .publish_diagnostics(uri, lsp_diagnostics, None)
+            .publish_diagnostics(uri, diagnostics, None)
             .await;
     }
 
@@ -274,16 +617,10 @@ impl Backend {
         }
 
         // Replace the entire document
-        let lines: Vec<&str> = text.lines().collect();
-        let last_line = lines.len().saturating_sub(1);
-        #[allow(clippy::cast_possible_truncation)]
-        let last_char = lines.last().map_or(0, |s| s.len()) as u32;
-
         vec![TextEdit {
             range: Range::new(
                 Position::new(0, 0),
-                #[allow(clippy::cast_possible_truncation)]
-                Position::new(last_line as u32, last_char),
+                lsp_position(&SourceMap::new(text), text.len()),
             ),
             new_text: formatted,
         }]
@@ -292,14 +629,60 @@ impl Backend {
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        #[allow(deprecated)] // `root_uri` is the fallback for clients without workspace folders.
+        let uris: Vec<Url> = params.workspace_folders.map_or_else(
+            || params.root_uri.into_iter().collect(),
+            |folders| folders.into_iter().map(|f| f.uri).collect(),
+        );
+        let pull = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .is_some_and(|t| t.diagnostic.is_some());
+        self.pull_diagnostics.store(pull, Ordering::Relaxed);
+
+        let roots: Vec<PathBuf> = uris.iter().filter_map(|u| u.to_file_path().ok()).collect();
+        debug!(?roots, "workspace roots");
+        if let Ok(mut workspace_roots) = self.workspace_roots.write() {
+            *workspace_roots = roots;
+        }
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::FULL,
                 )),
                 document_formatting_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec!["{".to_string(), "#".to_string()]),
+                    ..Default::default()
+                }),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                }),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: semantic_tokens_legend(),
+                            full: Some(SemanticTokensFullOptions::Bool(true)),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
+                    DiagnosticOptions {
+                        identifier: Some("promptly".to_string()),
+                        inter_file_dependencies: false,
+                        workspace_diagnostics: false,
+                        work_done_progress_options: WorkDoneProgressOptions::default(),
+                    },
+                )),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -322,6 +705,7 @@ impl LanguageServer for Backend {
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri.clone();
         let text = params.text_document.text.clone();
+        debug!(%uri, "did_open");
 
         // Store the document content
         if let Ok(mut docs) = self.documents.write() {
@@ -335,6 +719,7 @@ impl LanguageServer for Backend {
         if let Some(change) = params.content_changes.into_iter().last() {
             let uri = params.text_document.uri.clone();
             let text = change.text.clone();
+            debug!(%uri, version = params.text_document.version, "did_change");
 
             // Update stored document content
             if let Ok(mut docs) = self.documents.write() {
@@ -346,6 +731,7 @@ impl LanguageServer for Backend {
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        debug!(uri = %params.text_document.uri, "did_save");
         if let Some(text) = params.text {
             self.publish_diagnostics(params.text_document.uri, &text)
                 .await;
@@ -353,6 +739,7 @@ impl LanguageServer for Backend {
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        debug!(uri = %params.text_document.uri, "did_close");
         // Remove stored document content
         if let Ok(mut docs) = self.documents.write() {
             docs.remove(&params.text_document.uri);
@@ -365,6 +752,7 @@ impl LanguageServer for Backend {
     }
 
     async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        debug!(uri = %params.text_document.uri, "formatting");
         // Get the document content from our store
         let text = self
             .documents
@@ -375,9 +763,64 @@ impl LanguageServer for Backend {
         Ok(text.map(|content| self.format_document(&content)))
     }
 
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        debug!(%uri, "code_action");
+        let text = self
+            .documents
+            .read()
+            .ok()
+            .and_then(|docs| docs.get(&uri).cloned());
+        let Some(content) = text else {
+            return Ok(None);
+        };
+
+        let requested = params.range;
+        let map = SourceMap::new(&content);
+        let actions: CodeActionResponse = self
+            .lint(&uri, &content)
+            .into_iter()
+            .filter_map(|d| {
+                let fix = d.fix?;
+                let range = lsp_range(&map, d.span.as_ref().unwrap_or(&fix.span));
+                if range.start > requested.end || requested.start > range.end {
+                    return None;
+                }
+
+                let code = Some(NumberOrString::String(d.code));
+                let diagnostics: Vec<LspDiagnostic> = params
+                    .context
+                    .diagnostics
+                    .iter()
+                    .filter(|c| c.range == range && c.code == code)
+                    .cloned()
+                    .collect();
+                let edit = TextEdit {
+                    range: lsp_range(&map, &fix.span),
+                    new_text: fix.replacement,
+                };
+
+                Some(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: fix.message,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: (!diagnostics.is_empty()).then_some(diagnostics),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                        ..Default::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                }))
+            })
+            .collect();
+
+        Ok(Some(actions))
+    }
+
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
         let uri = &params.text_document_position_params.text_document.uri;
         let position = params.text_document_position_params.position;
+        debug!(%uri, line = position.line, character = position.character, "hover");
 
         // Get document content
         let text = self
@@ -400,19 +843,26 @@ impl LanguageServer for Backend {
         }
 
         let line = lines[line_idx];
-        #[allow(clippy::cast_possible_truncation)]
-        let col = position.character as usize;
+        let col = char_column(line, position.character);
 
         // Check if we're in a Handlebars expression
         // Note: Using nested if-let instead of let-chains for Bazel compatibility
         // (rules_rust stable toolchain doesn't support let-chains yet)
         #[allow(clippy::collapsible_if)]
         if let Some(helper_name) = find_helper_at_position(line, col) {
-            if let Some(docs) = get_helper_docs(&helper_name) {
+            let docs = get_helper_docs(&helper_name)
+                .map(str::to_string)
+                .or_else(|| {
+                    declared_helpers(self.settings(uri).linter.helpers(), &content)
+                        .into_iter()
+                        .find(|h| h.name == helper_name)
+                        .map(|h| h.docs())
+                });
+            if let Some(docs) = docs {
                 return Ok(Some(Hover {
                     contents: HoverContents::Markup(MarkupContent {
                         kind: MarkupKind::Markdown,
-                        value: docs.to_string(),
+                        value: docs,
                     }),
                     range: None,
                 }));
@@ -424,10 +874,17 @@ impl LanguageServer for Backend {
         if is_in_frontmatter(&content, line_idx) {
             if let Some(field_name) = find_yaml_field_at_position(line, col) {
                 if let Some(docs) = get_frontmatter_field_docs(&field_name) {
+                    let mut value = docs.to_string();
+                    if field_name == "tags" {
+                        if let Some(tags) = document_tags(&content) {
+                            value.push_str("\n\n**This prompt:** ");
+                            value.push_str(&tags);
+                        }
+                    }
                     return Ok(Some(Hover {
                         contents: HoverContents::Markup(MarkupContent {
                             kind: MarkupKind::Markdown,
-                            value: docs.to_string(),
+                            value,
                         }),
                         range: None,
                     }));
@@ -437,6 +894,427 @@ impl LanguageServer for Backend {
 
         Ok(None)
     }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        debug!(%uri, "completion");
+        let text = self
+            .documents
+            .read()
+            .ok()
+            .and_then(|docs| docs.get(uri).cloned());
+        let Some(content) = text else {
+            return Ok(None);
+        };
+
+        #[allow(clippy::cast_possible_truncation)]
+        let line = content
+            .lines()
+            .nth(position.line as usize)
+            .unwrap_or_default();
+        if !is_in_expression(line, char_column(line, position.character)) {
+            return Ok(None);
+        }
+
+        let markdown = |value: String| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            })
+        };
+        let declared = declared_helpers(self.settings(uri).linter.helpers(), &content);
+        let builtins = builtin_helpers().map(|name| CompletionItem {
+            label: name.to_string(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            documentation: get_helper_docs(name).map(|docs| markdown(docs.to_string())),
+            ..Default::default()
+        });
+        let custom = declared
+            .into_iter()
+            .filter(|h| !builtin_helpers().any(|name| name == h.name))
+            .map(|h| CompletionItem {
+                label: h.name.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: h.signature.clone(),
+                documentation: Some(markdown(h.docs())),
+                ..Default::default()
+            });
+
+        Ok(Some(CompletionResponse::Array(
+            builtins.chain(custom).collect(),
+        )))
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        debug!(uri = %params.text_document.uri, "folding_range");
+        let text = self
+            .documents
+            .read()
+            .ok()
+            .and_then(|docs| docs.get(&params.text_document.uri).cloned());
+
+        Ok(text.map(|content| folding_ranges(&content)))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        debug!(uri = %params.text_document.uri, "semantic_tokens_full");
+        let Some(content) = self
+            .documents
+            .read()
+            .ok()
+            .and_then(|docs| docs.get(&params.text_document.uri).cloned())
+        else {
+            return Ok(None);
+        };
+        let helpers = declared_helpers(
+            self.settings(&params.text_document.uri).linter.helpers(),
+            &content,
+        );
+
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data: semantic_tokens(&content, &helpers),
+        })))
+    }
+
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> Result<Option<Vec<SelectionRange>>> {
+        debug!(uri = %params.text_document.uri, "selection_range");
+        let text = self
+            .documents
+            .read()
+            .ok()
+            .and_then(|docs| docs.get(&params.text_document.uri).cloned());
+
+        Ok(text.map(|content| {
+            let structure = DocumentStructure::parse(&content);
+            params
+                .positions
+                .iter()
+                .map(|position| selection_range_at(&content, &structure, *position))
+                .collect()
+        }))
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let uri = params.text_document.uri;
+        debug!(%uri, "document_link");
+        let text = self
+            .documents
+            .read()
+            .ok()
+            .and_then(|docs| docs.get(&uri).cloned());
+        let Some(content) = text else {
+            return Ok(None);
+        };
+
+        let document_path = uri.to_file_path().ok();
+        let roots = self
+            .workspace_roots
+            .read()
+            .map(|r| r.clone())
+            .unwrap_or_default();
+
+        let map = SourceMap::new(&content);
+        let links = find_links(&content)
+            .into_iter()
+            .filter_map(|link| {
+                let (target, tooltip) = match &link.target {
+                    LinkTarget::Partial(name) => {
+                        let path = resolve_partial(document_path.as_deref(), &roots, name)?;
+                        (
+                            Url::from_file_path(&path).ok()?,
+                            format!("Open partial '{name}'"),
+                        )
+                    }
+                    LinkTarget::Url(url) => (Url::parse(url).ok()?, "Open media URL".to_string()),
+                };
+                Some(DocumentLink {
+                    range: Range::new(
+                        lsp_position(&map, link.range.start),
+                        lsp_position(&map, link.range.end),
+                    ),
+                    target: Some(target),
+                    tooltip: Some(tooltip),
+                    data: None,
+                })
+            })
+            .collect();
+
+        Ok(Some(links))
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        let uri = params.text_document.uri;
+        debug!(%uri, "diagnostic");
+        let text = self
+            .documents
+            .read()
+            .ok()
+            .and_then(|docs| docs.get(&uri).cloned())
+            .unwrap_or_default();
+
+        // Diagnostics depend only on the text, so its hash identifies the result.
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let result_id = format!("{:016x}", hasher.finish());
+
+        let report = if params.previous_result_id.as_deref() == Some(result_id.as_str()) {
+            DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                    result_id,
+                },
+            })
+        } else {
+            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items: self.diagnostics(&uri, &text),
+                },
+            })
+        };
+
+        Ok(DocumentDiagnosticReportResult::Report(report))
+    }
+}
+
+/// Converts a 1-indexed linter span into an LSP range.
+fn lsp_range(map: &SourceMap, span: &Span) -> Range {
+    Range::new(
+        lsp_position(map, map.offset(span.start.line, span.start.column)),
+        lsp_position(map, map.offset(span.end.line, span.end.column)),
+    )
+}
+
+/// Converts a byte offset into an LSP position.
+fn lsp_position(map: &SourceMap, offset: usize) -> Position {
+    let (line, character) = map.lsp_position(offset);
+    Position::new(line, character)
+}
+
+/// Computes folding ranges for the frontmatter, blocks, markers, and comments.
+fn folding_ranges(source: &str) -> Vec<FoldingRange> {
+    let map = SourceMap::new(source);
+    DocumentStructure::parse(source)
+        .regions
+        .into_iter()
+        .filter_map(|region| {
+            let start_line = lsp_position(&map, region.range.start).line;
+            let text = &source[region.range.clone()];
+            let last = region.range.start + text.trim_end().len().saturating_sub(1);
+            let last_line = lsp_position(&map, last).line;
+
+            // Keep closing delimiters visible when folded.
+            let (end_line, kind) = match region.kind {
+                RegionKind::Frontmatter | RegionKind::Block(_) => {
+                    (last_line.saturating_sub(1), FoldingRangeKind::Region)
+                }
+                RegionKind::Role | RegionKind::Section => (last_line, FoldingRangeKind::Region),
+                RegionKind::Comment => (last_line, FoldingRangeKind::Comment),
+            };
+
+            (end_line > start_line).then_some(FoldingRange {
+                start_line,
+                start_character: None,
+                end_line,
+                end_character: None,
+                kind: Some(kind),
+                collapsed_text: None,
+            })
+        })
+        .collect()
+}
+
+/// Computes semantic tokens for every Handlebars expression outside raw and
+/// verbatim blocks.
+fn semantic_tokens(source: &str, helpers: &[HelperDecl]) -> Vec<SemanticToken> {
+    let verbatim = verbatim_regions(source);
+    let mut tokens = Vec::new();
+    for expression in DocumentStructure::parse(source).expressions {
+        if !verbatim
+            .iter()
+            .any(|region| region.contains(&expression.start))
+        {
+            expression_tokens(source, expression, helpers, &mut tokens);
+        }
+    }
+
+    let map = SourceMap::new(source);
+    let mut data = Vec::with_capacity(tokens.len());
+    let mut previous = Position::new(0, 0);
+    for (range, token_type, modifiers) in tokens {
+        let start = lsp_position(&map, range.start);
+        let Ok(length) = u32::try_from(source[range].encode_utf16().count()) else {
+            continue;
+        };
+        let delta_start = if start.line == previous.line {
+            start.character - previous.character
+        } else {
+            start.character
+        };
+        data.push(SemanticToken {
+            delta_line: start.line - previous.line,
+            delta_start,
+            length,
+            token_type,
+            token_modifiers_bitset: modifiers,
+        });
+        previous = start;
+    }
+    data
+}
+
+/// Appends the tokens of the expression at `range`, in order, as byte
+/// range, token type index, and modifier bits. No token spans a line.
+fn expression_tokens(
+    source: &str,
+    range: std::ops::Range<usize>,
+    helpers: &[HelperDecl],
+    tokens: &mut Vec<(std::ops::Range<usize>, u32, u32)>,
+) {
+    let token_type = |kind: &SemanticTokenType| {
+        TOKEN_TYPES
+            .iter()
+            .position(|t| t == kind)
+            .and_then(|index| u32::try_from(index).ok())
+            .unwrap_or_default()
+    };
+    let tag = &source[range.clone()];
+    let inner_start = range.start + tag.len() - tag.trim_start_matches(['{', '~']).len();
+    let inner_end = range.start + tag.trim_end_matches(['}', '~']).len();
+    let inner = &source[inner_start..inner_end.max(inner_start)];
+
+    if inner.starts_with('!') {
+        let mut line_start = range.start;
+        for line in tag.split('\n') {
+            let text = line.trim_end_matches('\r');
+            if !text.trim().is_empty() {
+                let start = line_start + text.len() - text.trim_start().len();
+                let end = line_start + text.trim_end().len();
+                tokens.push((start..end, token_type(&SemanticTokenType::COMMENT), 0));
+            }
+            line_start += line.len() + 1;
+        }
+        return;
+    }
+
+    let prefix = inner
+        .trim_start()
+        .chars()
+        .next()
+        .filter(|c| "#/^>".contains(*c));
+    let mut helper_position = true;
+    let mut chars = inner.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let start = inner_start + i;
+        let (end, kind, modifiers) = if c == '"' || c == '\'' {
+            let end = chars
+                .by_ref()
+                .find(|(_, next)| *next == c)
+                .map_or(inner.len(), |(j, _)| j + 1);
+            (end, SemanticTokenType::STRING, 0)
+        } else if c.is_ascii_digit()
+            || (c == '-' && chars.peek().is_some_and(|(_, d)| d.is_ascii_digit()))
+        {
+            let mut end = i + 1;
+            while let Some((j, _)) = chars.next_if(|(_, d)| d.is_ascii_digit() || *d == '.') {
+                end = j + 1;
+            }
+            (end, SemanticTokenType::NUMBER, 0)
+        } else if c.is_alphanumeric() || "_@.[]-".contains(c) {
+            let mut end = i + c.len_utf8();
+            while let Some((j, d)) =
+                chars.next_if(|(_, d)| d.is_alphanumeric() || "_@.[]-/".contains(*d))
+            {
+                end = j + d.len_utf8();
+            }
+            let word = &inner[i..end];
+            let (kind, modifiers) = if chars.peek().is_some_and(|(_, d)| *d == '=') {
+                (SemanticTokenType::PARAMETER, 0)
+            } else if HANDLEBARS_KEYWORDS.contains(&word) {
+                (SemanticTokenType::KEYWORD, 0)
+            } else if helper_position && prefix == Some('>') {
+                (SemanticTokenType::NAMESPACE, 0)
+            } else if helper_position && BUILTIN_HELPERS.contains(&word) {
+                (SemanticTokenType::FUNCTION, 1)
+            } else if helper_position
+                && (prefix.is_some() || helpers.iter().any(|h| h.name == word))
+            {
+                (SemanticTokenType::FUNCTION, 0)
+            } else {
+                (SemanticTokenType::VARIABLE, 0)
+            };
+            helper_position = false;
+            (end, kind, modifiers)
+        } else {
+            if c == '(' {
+                helper_position = true;
+            } else if !c.is_whitespace() && !"#/^>*".contains(c) {
+                helper_position = false;
+            }
+            continue;
+        };
+        if !source[start..inner_start + end].contains('\n') {
+            tokens.push((start..inner_start + end, token_type(&kind), modifiers));
+        }
+    }
+}
+
+/// Builds the expand-selection chain for a position: the expression under the
+/// cursor, then each enclosing region, then the whole document.
+fn selection_range_at(
+    source: &str,
+    structure: &DocumentStructure,
+    position: Position,
+) -> SelectionRange {
+    let map = SourceMap::new(source);
+    let offset = map.lsp_offset(position.line, position.character);
+    let to_range = |range: std::ops::Range<usize>| {
+        Range::new(
+            lsp_position(&map, range.start),
+            lsp_position(&map, range.end),
+        )
+    };
+
+    let mut ranges: Vec<Range> = structure
+        .regions_at(offset)
+        .into_iter()
+        .map(|r| to_range(r.range.clone()))
+        .collect();
+    ranges.extend(structure.expression_at(offset).map(to_range));
+
+    let mut selection = SelectionRange {
+        range: to_range(0..source.len()),
+        parent: None,
+    };
+    for range in ranges {
+        if range != selection.range {
+            selection = SelectionRange {
+                range,
+                parent: Some(Box::new(selection)),
+            };
+        }
+    }
+    selection
+}
+
+/// Converts an LSP character offset, in UTF-16 code units, into a 0-indexed
+/// character column within a line.
+fn char_column(line: &str, character: u32) -> usize {
+    let map = SourceMap::new(line);
+    let offset = map.lsp_offset(0, character);
+    line[..offset].chars().count()
 }
 
 /// Finds a Handlebars helper name at the given column position.
@@ -478,6 +1356,28 @@ fn find_helper_at_position(line: &str, col: usize) -> Option<String> {
     None
 }
 
+/// Returns the document's tags formatted for display, if it has any.
+fn document_tags(content: &str) -> Option<String> {
+    let (parsed, _) = parse_document_lenient::<serde_json::Value>(content);
+    let tags = parsed.metadata.tags.filter(|t| !t.is_empty())?;
+    Some(
+        tags.iter()
+            .map(|t| format!("`{t}`"))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+/// Checks whether a column is inside an unclosed `{{` expression that can
+/// name a helper (not a partial or comment).
+fn is_in_expression(line: &str, col: usize) -> bool {
+    let before: String = line.chars().take(col).collect();
+    before.rfind("{{").is_some_and(|start| {
+        let inner = before[start + 2..].trim_start_matches('~');
+        !inner.contains("}}") && !inner.starts_with(['>', '!'])
+    })
+}
+
 /// Checks if a line index is within the YAML frontmatter section.
 fn is_in_frontmatter(content: &str, line_idx: usize) -> bool {
     let lines: Vec<&str> = content.lines().collect();
@@ -501,7 +1401,7 @@ fn is_in_frontmatter(content: &str, line_idx: usize) -> bool {
 fn find_yaml_field_at_position(line: &str, col: usize) -> Option<String> {
     // Look for pattern like "field:" at the start of the line
     let trimmed = line.trim_start();
-    let indent = line.len() - trimmed.len();
+    let indent = line.chars().count() - trimmed.chars().count();
 
     // Check if cursor is in the field name area
     if col < indent {
@@ -514,7 +1414,7 @@ fn find_yaml_field_at_position(line: &str, col: usize) -> Option<String> {
         if !field_name.is_empty() && !field_name.starts_with('-') {
             // Only return if cursor is on or near the field name
             let col_in_trimmed = col.saturating_sub(indent);
-            if col_in_trimmed <= colon_idx + 1 {
+            if col_in_trimmed <= trimmed[..colon_idx].chars().count() + 1 {
                 return Some(field_name.to_string());
             }
         }
@@ -523,18 +1423,269 @@ fn find_yaml_field_at_position(line: &str, col: usize) -> Option<String> {
     None
 }
 
-/// Runs the LSP server.
+/// Serves a single LSP session over an arbitrary transport.
+pub(crate) async fn serve<I, O>(input: I, output: O)
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite,
+{
+    let (service, socket) = LspService::new(Backend::new);
+    Server::new(input, output, socket).serve(service).await;
+}
+
+/// Runs the LSP server over stdio.
 ///
 /// # Errors
 ///
 /// Returns an error if the server fails to start.
 pub(crate) async fn run_server() -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>>
 {
-    let stdin = tokio::io::stdin();
-    let stdout = tokio::io::stdout();
-
-    let (service, socket) = LspService::new(Backend::new);
-    Server::new(stdin, stdout, socket).serve(service).await;
+    info!("starting language server on stdio");
+    serve(tokio::io::stdin(), tokio::io::stdout()).await;
+    info!("language server stopped");
 
     Ok(())
 }
+
+/// Runs the LSP server over TCP, serving each connection as its own session.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound or accepting fails.
+pub(crate) async fn run_tcp_server(addr: SocketAddr) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let local = listener.local_addr()?;
+    eprintln!("promptly lsp listening on tcp://{local}");
+    info!(%local, "listening for LSP clients over TCP");
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        info!(%peer, "client connected");
+        tokio::spawn(async move {
+            let (read, write) = stream.into_split();
+            serve(read, write).await;
+            info!(%peer, "client disconnected");
+        });
+    }
+}
+
+/// Runs the LSP server over WebSocket.
+///
+/// Each WebSocket message carries one JSON-RPC message, without the
+/// `Content-Length` framing used on stdio and TCP.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound or accepting fails.
+pub(crate) async fn run_ws_server(addr: SocketAddr) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let local = listener.local_addr()?;
+    eprintln!("promptly lsp listening on ws://{local}");
+    info!(%local, "listening for LSP clients over WebSocket");
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        info!(%peer, "client connected");
+        tokio::spawn(async move {
+            if let Err(e) = serve_websocket(stream).await {
+                debug!(%peer, error = %e, "websocket session failed");
+            }
+            info!(%peer, "client disconnected");
+        });
+    }
+}
+
+/// Bridges a WebSocket connection to a `Content-Length` framed LSP session.
+async fn serve_websocket(stream: TcpStream) -> io::Result<()> {
+    let ws = tokio_tungstenite::accept_async(stream)
+        .await
+        .map_err(io::Error::other)?;
+    let (mut ws_tx, mut ws_rx) = ws.split();
+
+    let (server_io, bridge_io) = tokio::io::duplex(64 * 1024);
+    let (server_read, server_write) = tokio::io::split(server_io);
+    let (bridge_read, mut bridge_write) = tokio::io::split(bridge_io);
+
+    // Client to server: frame each message for the LSP codec.
+    let inbound = async move {
+        while let Some(message) = ws_rx.next().await {
+            let payload = match message.map_err(io::Error::other)? {
+                WsMessage::Text(text) => text.as_bytes().to_vec(),
+                WsMessage::Binary(bytes) => bytes.to_vec(),
+                WsMessage::Close(_) => break,
+                _ => continue,
+            };
+            let header = format!("Content-Length: {}\r\n\r\n", payload.len());
+            bridge_write.write_all(header.as_bytes()).await?;
+            bridge_write.write_all(&payload).await?;
+        }
+        bridge_write.shutdown().await
+    };
+
+    // Server to client: strip the framing and send one message per frame.
+    let outbound = async move {
+        let mut reader = BufReader::new(bridge_read);
+        while let Some(body) = read_framed_message(&mut reader).await? {
+            ws_tx
+                .send(WsMessage::text(body))
+                .await
+                .map_err(io::Error::other)?;
+        }
+        ws_tx.close().await.map_err(io::Error::other)
+    };
+
+    let ((), inbound, outbound) = tokio::join!(serve(server_read, server_write), inbound, outbound);
+    inbound.and(outbound)
+}
+
+/// Reads one `Content-Length` framed message, or `None` at end of stream.
+#[allow(clippy::collapsible_if)] // Using nested ifs for stable Rust compatibility (no let-chains)
+async fn read_framed_message<R>(reader: &mut R) -> io::Result<Option<String>>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = content_length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes semantic tokens into `(text, type, modifiers)` triples.
+    fn decoded(source: &str) -> Vec<(String, SemanticTokenType, u32)> {
+        let lines: Vec<&str> = source.lines().collect();
+        let (mut line, mut character) = (0, 0);
+        let today = HelperDecl {
+            name: "today".to_string(),
+            block: false,
+            signature: None,
+            description: None,
+        };
+        semantic_tokens(source, &[today])
+            .into_iter()
+            .map(|token| {
+                if token.delta_line > 0 {
+                    character = 0;
+                }
+                line += token.delta_line as usize;
+                character += token.delta_start as usize;
+                let units: Vec<u16> = lines[line]
+                    .encode_utf16()
+                    .skip(character)
+                    .take(token.length as usize)
+                    .collect();
+                let text = String::from_utf16_lossy(&units);
+                let kind = TOKEN_TYPES[token.token_type as usize].clone();
+                (text, kind, token.token_modifiers_bitset)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_semantic_tokens() {
+        use SemanticTokenType as T;
+
+        let source = "---\nmodel: m\n---\n{{#each items as |item|}}{{ json item indent=2 }}\
+            {{/each}}\n{{> footer}} {{today}} {{!-- a\n  b --}} {{{{raw}}}}{{x}}{{{{/raw}}}}\n\
+            {{ x.y \"é\" (length z) }}\n";
+        let t = |text: &str, kind: T, modifiers: u32| (text.to_string(), kind, modifiers);
+        assert_eq!(
+            decoded(source),
+            [
+                t("each", T::KEYWORD, 0),
+                t("items", T::VARIABLE, 0),
+                t("as", T::KEYWORD, 0),
+                t("item", T::VARIABLE, 0),
+                t("json", T::FUNCTION, 1),
+                t("item", T::VARIABLE, 0),
+                t("indent", T::PARAMETER, 0),
+                t("2", T::NUMBER, 0),
+                t("each", T::KEYWORD, 0),
+                t("footer", T::NAMESPACE, 0),
+                t("today", T::FUNCTION, 0),
+                t("{{!-- a", T::COMMENT, 0),
+                t("b --}}", T::COMMENT, 0),
+                t("x.y", T::VARIABLE, 0),
+                t("\"é\"", T::STRING, 0),
+                t("length", T::FUNCTION, 1),
+                t("z", T::VARIABLE, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_semantic_tokens_count_utf16_units() {
+        use SemanticTokenType as T;
+
+        let source = "🙂 日本 {{名前}} 🙂🙂 {{upper \"🙂\" مرحبا}}\nمرحبا {{اسم}}\n";
+        let t = |text: &str, kind: T| (text.to_string(), kind, 0);
+        assert_eq!(
+            decoded(source),
+            [
+                t("名前", T::VARIABLE),
+                t("upper", T::VARIABLE),
+                t("\"🙂\"", T::STRING),
+                t("مرحبا", T::VARIABLE),
+                t("اسم", T::VARIABLE),
+            ]
+        );
+        let tokens = semantic_tokens(source, &[]);
+        assert_eq!((tokens[0].delta_start, tokens[0].length), (8, 2));
+        assert_eq!((tokens[1].delta_start, tokens[1].length), (12, 5));
+    }
+
+    #[test]
+    fn test_hover_columns_count_utf16_units() {
+        let line = "🙂 {{#each 項目}}";
+        assert_eq!(char_column(line, 3), 2);
+        assert_eq!(
+            find_helper_at_position(line, char_column(line, 7)).as_deref(),
+            Some("each")
+        );
+        assert_eq!(
+            find_yaml_field_at_position("\u{3000}名前: 値", 2).as_deref(),
+            Some("名前")
+        );
+    }
+
+    #[test]
+    fn test_client_config_registers_language_and_legend() {
+        let config = client_config();
+        assert_eq!(
+            config["packageJson"]["contributes"]["languages"][0]["extensions"][0],
+            ".prompt"
+        );
+        assert_eq!(config["serverOptions"]["args"][0], "lsp");
+        assert_eq!(
+            config["semanticTokensLegend"]["tokenTypes"][0],
+            serde_json::json!("keyword")
+        );
+        assert_eq!(
+            config["semanticTokensLegend"]["tokenModifiers"],
+            serde_json::json!(["defaultLibrary"])
+        );
+    }
+}
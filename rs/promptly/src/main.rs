@@ -29,14 +29,36 @@
 
 mod commands;
 pub(crate) mod config;
+mod dialect;
+mod diff;
 mod formatter;
+mod frontmatter_edit;
+mod git;
+mod helpers;
+mod importers;
+mod links;
 mod linter;
+mod logging;
 mod lsp;
+mod output;
+mod owners;
+mod prose;
+mod readability;
+mod server;
 mod span;
+mod spelling;
+mod structure;
+mod transcript;
+mod verbatim;
 
+use anstream::eprintln;
 use clap::{Parser, Subcommand};
+use commands::config as config_cmd;
 use commands::lsp as lsp_cmd;
-use commands::{check, completions, fmt};
+use commands::{
+    check, completions, doctor, export, fmt, generate_dataset, highlight, hook, import, ls,
+    migrate, play, render, search, serve, set,
+};
 use owo_colors::OwoColorize;
 
 /// Promptly: Cargo for prompts - lint, format, test, and publish .prompt files
@@ -48,6 +70,14 @@ pub struct Cli {
     /// Subcommand to execute
     #[command(subcommand)]
     command: Commands,
+
+    /// Logging options
+    #[command(flatten)]
+    log: logging::LogArgs,
+
+    /// Output options
+    #[command(flatten)]
+    output: output::OutputArgs,
 }
 
 /// Returns custom styles for clap.
@@ -80,20 +110,68 @@ enum Commands {
     Check(check::CheckArgs),
     /// Generate shell completions
     Completions(completions::CompletionsArgs),
+    /// Show the promptly.toml settings that apply to a path
+    Config(config_cmd::ConfigArgs),
+    /// Verify that prompts load, resolve, and render, for deployment health checks
+    Doctor(doctor::DoctorArgs),
+    /// Export a rendered prompt for use in other tools
+    Export(export::ExportArgs),
     /// Format .prompt files
     Fmt(fmt::FmtArgs),
+    /// Render a prompt over a JSONL file of inputs into a dataset
+    GenerateDataset(generate_dataset::GenerateDatasetArgs),
+    /// Generate editor syntax highlighting for .prompt files
+    Highlight(highlight::HighlightArgs),
+    /// Manage git hooks that check prompts before commit
+    Hook(hook::HookArgs),
+    /// Translate a template from another format into a .prompt file
+    Import(import::ImportArgs),
+    /// List prompts in a directory
+    Ls(ls::LsArgs),
     /// Start the Language Server Protocol (LSP) server
     Lsp(lsp_cmd::LspArgs),
+    /// Rename models and frontmatter fields across a tree of prompts
+    Migrate(migrate::MigrateArgs),
+    /// Edit input and watch a prompt render live in a terminal UI
+    Play(play::PlayArgs),
+    /// Render a prompt, optionally explaining where each part came from
+    Render(render::RenderArgs),
+    /// Search prompts by frontmatter metadata
+    Search(search::SearchArgs),
+    /// Serve prompts over HTTP for rendering from other languages
+    Serve(serve::ServeArgs),
+    /// Set frontmatter fields, preserving comments and formatting
+    Set(set::SetArgs),
 }
 
 fn main() {
     let cli = Cli::parse();
+    output::init(&cli.output);
+
+    if let Err(e) = logging::init(&cli.log) {
+        eprintln!("{}: {e}", "error".red().bold());
+        std::process::exit(1);
+    }
 
     let result = match cli.command {
         Commands::Check(args) => check::run(&args),
         Commands::Completions(args) => completions::run(&args),
+        Commands::Config(args) => config_cmd::run(&args),
+        Commands::Doctor(args) => doctor::run(&args),
+        Commands::Export(args) => export::run(&args),
         Commands::Fmt(args) => fmt::run(&args),
+        Commands::GenerateDataset(args) => generate_dataset::run(&args),
+        Commands::Highlight(args) => highlight::run(&args),
+        Commands::Hook(args) => hook::run(&args),
+        Commands::Import(args) => import::run(&args),
+        Commands::Ls(args) => ls::run(&args),
         Commands::Lsp(args) => lsp_cmd::run(&args),
+        Commands::Migrate(args) => migrate::run(&args),
+        Commands::Play(args) => play::run(&args),
+        Commands::Render(args) => render::run(&args),
+        Commands::Search(args) => search::run(&args),
+        Commands::Serve(args) => serve::run(&args),
+        Commands::Set(args) => set::run(&args),
     };
 
     if let Err(e) = result {
@@ -0,0 +1,79 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Terminal output shared by the commands.
+//!
+//! Colors are written through `anstream`, which strips them when `--no-color`
+//! or `NO_COLOR` is set or the stream is not a terminal. `--quiet` limits
+//! commands to errors and their closing summary, and hides progress bars.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anstream::ColorChoice;
+use clap::Args;
+use indicatif::{ProgressBar, ProgressStyle};
+
+/// Whether `--quiet` was given.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// The fewest items worth showing a progress bar for.
+const PROGRESS_MIN_ITEMS: usize = 50;
+
+/// Global output options.
+#[derive(Args, Debug)]
+pub(crate) struct OutputArgs {
+    /// Only print errors and summaries
+    #[arg(long, short, global = true)]
+    pub quiet: bool,
+
+    /// Disable colored output
+    #[arg(long, global = true)]
+    pub no_color: bool,
+}
+
+/// Applies the output options for the rest of the process.
+pub(crate) fn init(args: &OutputArgs) {
+    if args.no_color {
+        ColorChoice::Never.write_global();
+    }
+    QUIET.store(args.quiet, Ordering::Relaxed);
+}
+
+/// Returns whether `--quiet` was given.
+pub(crate) fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Returns a progress bar over `len` items, drawn on stderr.
+///
+/// Short runs get a hidden bar, as do quiet runs; indicatif also hides it
+/// when stderr is not a terminal.
+#[allow(clippy::literal_string_with_formatting_args)] // indicatif templates
+pub(crate) fn progress(len: usize) -> ProgressBar {
+    if quiet() || len < PROGRESS_MIN_ITEMS {
+        return ProgressBar::hidden();
+    }
+    let template = if anstream::AutoStream::choice(&std::io::stderr()) == ColorChoice::Never {
+        "{bar:40} {pos}/{len} {wide_msg}"
+    } else {
+        "{bar:40.cyan/blue} {pos}/{len} {wide_msg}"
+    };
+    let bar = ProgressBar::new(len as u64);
+    match ProgressStyle::with_template(template) {
+        Ok(style) => bar.with_style(style),
+        Err(_) => bar,
+    }
+}
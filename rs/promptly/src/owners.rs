@@ -0,0 +1,198 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! `PROMPTOWNERS` file support.
+//!
+//! A `PROMPTOWNERS` file follows the `CODEOWNERS` format: each non-empty,
+//! non-comment line is a path pattern followed by one or more owners.
+//! Patterns are relative to the directory containing the file, and the last
+//! matching line wins.
+//!
+//! ```text
+//! # Default owners for everything
+//! *                 @prompt-team
+//! /support/         @support-team alice@example.com
+//! *.beta.prompt     @beta-reviewers
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobBuilder, GlobMatcher};
+
+/// The name of the owners file.
+pub(crate) const PROMPTOWNERS_FILE_NAME: &str = "PROMPTOWNERS";
+
+/// A single pattern line from a `PROMPTOWNERS` file.
+#[derive(Debug)]
+struct OwnerRule {
+    /// Matchers for the pattern (the pattern itself and its directory form).
+    matchers: Vec<GlobMatcher>,
+    /// Owners assigned by this line.
+    owners: Vec<String>,
+}
+
+/// Parsed `PROMPTOWNERS` rules.
+#[derive(Debug)]
+pub(crate) struct PromptOwners {
+    /// Directory containing the `PROMPTOWNERS` file.
+    root: PathBuf,
+    /// Rules in file order.
+    rules: Vec<OwnerRule>,
+}
+
+impl PromptOwners {
+    /// Finds the nearest `PROMPTOWNERS` file in `path`'s directory or its ancestors.
+    ///
+    /// # Returns
+    ///
+    /// The parsed rules, or `None` if no readable file is found.
+    #[must_use]
+    pub(crate) fn discover(path: &Path) -> Option<Self> {
+        let mut current = path.parent();
+        while let Some(dir) = current {
+            let candidate = dir.join(PROMPTOWNERS_FILE_NAME);
+            if candidate.is_file() {
+                let content = fs::read_to_string(&candidate).ok()?;
+                return Some(Self::parse(dir, &content));
+            }
+            current = dir.parent();
+        }
+        None
+    }
+
+    /// Parses `PROMPTOWNERS` content rooted at `root`.
+    ///
+    /// Lines with invalid patterns or without owners are skipped.
+    #[must_use]
+    pub(crate) fn parse(root: &Path, content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?;
+                let owners: Vec<String> = parts
+                    .take_while(|p| !p.starts_with('#'))
+                    .map(str::to_string)
+                    .collect();
+                if owners.is_empty() {
+                    return None;
+                }
+                let matchers = compile_pattern(pattern)?;
+                Some(OwnerRule { matchers, owners })
+            })
+            .collect();
+
+        Self {
+            root: root.to_path_buf(),
+            rules,
+        }
+    }
+
+    /// Returns the owners assigned to `path`, if any rule matches.
+    #[must_use]
+    pub(crate) fn owners_for(&self, path: &Path) -> Option<&[String]> {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matchers.iter().any(|m| m.is_match(&relative)))
+            .map(|rule| rule.owners.as_slice())
+    }
+}
+
+/// Converts a `CODEOWNERS`-style pattern into glob matchers.
+fn compile_pattern(pattern: &str) -> Option<Vec<GlobMatcher>> {
+    let anchored = pattern.starts_with('/');
+    let trimmed = pattern.trim_start_matches('/');
+    let is_dir = trimmed.ends_with('/');
+    let trimmed = trimmed.trim_end_matches('/');
+
+    // Patterns without an inner slash match at any depth.
+    let base = if anchored || trimmed.contains('/') {
+        trimmed.to_string()
+    } else {
+        format!("**/{trimmed}")
+    };
+
+    let globs = if is_dir {
+        vec![format!("{base}/**")]
+    } else {
+        vec![base.clone(), format!("{base}/**")]
+    };
+
+    globs
+        .iter()
+        .map(|g| build_glob(g).map(|glob| glob.compile_matcher()))
+        .collect()
+}
+
+/// Builds a glob where `*` does not cross directory separators.
+fn build_glob(pattern: &str) -> Option<Glob> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owners(rules: &PromptOwners, path: &str) -> Option<Vec<String>> {
+        rules
+            .owners_for(&Path::new("/repo").join(path))
+            .map(<[String]>::to_vec)
+    }
+
+    #[test]
+    fn test_last_matching_rule_wins() {
+        let rules = PromptOwners::parse(
+            Path::new("/repo"),
+            "# comment\n*  @all\n/support/ @support alice\n*.beta.prompt @beta # trailing\n",
+        );
+
+        assert_eq!(owners(&rules, "greet.prompt"), Some(vec!["@all".into()]));
+        assert_eq!(
+            owners(&rules, "support/refund.prompt"),
+            Some(vec!["@support".into(), "alice".into()])
+        );
+        assert_eq!(
+            owners(&rules, "support/refund.beta.prompt"),
+            Some(vec!["@beta".into()])
+        );
+    }
+
+    #[test]
+    fn test_anchored_patterns() {
+        let rules = PromptOwners::parse(Path::new("/repo"), "/greet.prompt @greeters\n");
+
+        assert_eq!(
+            owners(&rules, "greet.prompt"),
+            Some(vec!["@greeters".into()])
+        );
+        assert_eq!(owners(&rules, "nested/greet.prompt"), None);
+    }
+
+    #[test]
+    fn test_lines_without_owners_are_ignored() {
+        let rules = PromptOwners::parse(Path::new("/repo"), "*.prompt\n");
+        assert_eq!(owners(&rules, "greet.prompt"), None);
+    }
+}
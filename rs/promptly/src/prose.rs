@@ -0,0 +1,98 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prose in template bodies.
+//!
+//! Lints and metrics that look at the natural-language text of a prompt
+//! need to skip the parts that are not prose: Handlebars expressions and
+//! comments, code fences, inline code, and URLs.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Regex for text that is not prose within a line: inline code and URLs.
+static NON_PROSE_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Replaces everything that is not prose with spaces.
+///
+/// Byte offsets and newlines are kept, so positions in the result map
+/// directly back to `body`.
+#[must_use]
+pub(crate) fn mask_non_prose(body: &str) -> String {
+    let mut masked = String::with_capacity(body.len());
+    let mut in_fence = false;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let fence_line = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        if in_fence || fence_line {
+            in_fence = in_fence != fence_line;
+            push_blank(&mut masked, line);
+            continue;
+        }
+        masked.push_str(line);
+    }
+
+    // Handlebars tags and comments can span lines, so mask them over the
+    // whole body.
+    let mut result = String::with_capacity(masked.len());
+    let mut rest = masked.as_str();
+    let mut in_tag = false;
+    while !rest.is_empty() {
+        if in_tag {
+            let close = if rest.starts_with("{{!--") {
+                rest.find("--}}").map(|i| i + 4)
+            } else {
+                rest.find("}}").map(|i| i + 2)
+            };
+            let end = close.unwrap_or(rest.len());
+            push_blank(&mut result, &rest[..end]);
+            rest = &rest[end..];
+            in_tag = false;
+        } else if let Some(open) = rest.find("{{") {
+            result.push_str(&rest[..open]);
+            rest = &rest[open..];
+            in_tag = true;
+        } else {
+            result.push_str(rest);
+            rest = "";
+        }
+    }
+
+    let Some(re) = NON_PROSE_RE.get_or_init(|| Regex::new(r"`[^`\n]*`|\w+://\S+").ok()) else {
+        return result;
+    };
+    let mut prose = String::with_capacity(result.len());
+    let mut last = 0;
+    for m in re.find_iter(&result) {
+        prose.push_str(&result[last..m.start()]);
+        push_blank(&mut prose, m.as_str());
+        last = m.end();
+    }
+    prose.push_str(&result[last..]);
+    prose
+}
+
+/// Appends spaces in place of `text`, keeping its newlines and byte length.
+fn push_blank(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c == '\n' {
+            out.push('\n');
+        } else {
+            out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Readability metrics for the prose in a template body.
+//!
+//! Used by `promptly check --metrics`. The scores are heuristics for
+//! English text:
+//!
+//! - **Sentences** end at `.`, `!`, or `?`, at blank lines, and before list
+//!   items and headings, so bullet lists without punctuation still split.
+//! - **Syllables** are counted as vowel groups, ignoring a silent final `e`.
+//! - **Passive voice** is a form of "to be" followed, optionally after an
+//!   `-ly` adverb, by a past participle ("is written", "was quickly fixed").
+//! - The **Flesch reading ease** score is
+//!   `206.835 - 1.015 * words/sentences - 84.6 * syllables/words`; higher is
+//!   easier, and 60-70 reads as plain English.
+//!
+//! Floors can be set in `promptly.toml`; `check --metrics` fails when a
+//! prompt falls below them:
+//!
+//! ```toml
+//! [lint.readability]
+//! min-flesch = 50.0
+//! max-sentence-length = 25.0
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::prose::mask_non_prose;
+
+/// Forms of "to be" that introduce a passive construction.
+const TO_BE: &[&str] = &["am", "is", "are", "was", "were", "be", "been", "being"];
+
+/// Common irregular past participles.
+const IRREGULAR_PARTICIPLES: &[&str] = &[
+    "begun",
+    "bought",
+    "brought",
+    "built",
+    "caught",
+    "chosen",
+    "done",
+    "driven",
+    "eaten",
+    "found",
+    "forgotten",
+    "given",
+    "held",
+    "hidden",
+    "kept",
+    "known",
+    "left",
+    "made",
+    "paid",
+    "said",
+    "seen",
+    "sent",
+    "shown",
+    "sold",
+    "spoken",
+    "taken",
+    "taught",
+    "told",
+    "thought",
+    "thrown",
+    "understood",
+    "worn",
+    "written",
+];
+
+/// The `[lint.readability]` section of `promptly.toml`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct ReadabilityConfig {
+    /// Lowest acceptable Flesch reading ease score.
+    pub min_flesch: Option<f64>,
+    /// Highest acceptable average sentence length, in words.
+    pub max_sentence_length: Option<f64>,
+}
+
+/// Readability metrics for one prompt.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Readability {
+    /// Number of sentences.
+    pub sentences: usize,
+    /// Number of words.
+    pub words: usize,
+    /// Average words per sentence.
+    pub avg_sentence_length: f64,
+    /// Number of sentences that use the passive voice.
+    pub passive_sentences: usize,
+    /// Flesch reading ease score.
+    pub flesch_reading_ease: f64,
+}
+
+impl Readability {
+    /// Returns why these metrics fall short of `config`, if they do.
+    #[must_use]
+    pub(crate) fn violations(&self, config: &ReadabilityConfig) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(floor) = config.min_flesch.filter(|f| self.flesch_reading_ease < *f) {
+            violations.push(format!(
+                "Flesch reading ease {:.1} is below the floor of {floor:.1}",
+                self.flesch_reading_ease
+            ));
+        }
+        if let Some(ceiling) = config
+            .max_sentence_length
+            .filter(|m| self.avg_sentence_length > *m)
+        {
+            violations.push(format!(
+                "average sentence length {:.1} words is above the limit of {ceiling:.1}",
+                self.avg_sentence_length
+            ));
+        }
+        violations
+    }
+}
+
+/// Measures the prose in a template body.
+///
+/// # Returns
+///
+/// `None` if the body has no prose.
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // Counts are far below 2^52
+pub(crate) fn measure(body: &str) -> Option<Readability> {
+    let prose = mask_non_prose(body);
+    let sentences = split_sentences(&prose);
+    let sentences: Vec<Vec<&str>> = sentences
+        .iter()
+        .map(|sentence| {
+            sentence
+                .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '-')
+                .filter(|w| w.chars().any(char::is_alphabetic))
+                .collect::<Vec<_>>()
+        })
+        .filter(|words| !words.is_empty())
+        .collect();
+    let words: usize = sentences.iter().map(Vec::len).sum();
+    if words == 0 {
+        return None;
+    }
+    let syllables: usize = sentences.iter().flatten().map(|w| syllables(w)).sum();
+    let passive_sentences = sentences.iter().filter(|s| is_passive(s)).count();
+
+    let avg_sentence_length = words as f64 / sentences.len() as f64;
+    let flesch_reading_ease = 84.6f64.mul_add(
+        -(syllables as f64 / words as f64),
+        1.015f64.mul_add(-avg_sentence_length, 206.835),
+    );
+    Some(Readability {
+        sentences: sentences.len(),
+        words,
+        avg_sentence_length,
+        passive_sentences,
+        flesch_reading_ease,
+    })
+}
+
+/// Splits prose into sentences.
+fn split_sentences(prose: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for line in prose.lines() {
+        let trimmed = line.trim();
+        let starts_block = trimmed.is_empty()
+            || trimmed.starts_with(['-', '*', '#', '>'])
+            || trimmed
+                .split_once(['.', ')'])
+                .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        if starts_block && !current.trim().is_empty() {
+            sentences.push(std::mem::take(&mut current));
+        }
+        let mut chars = trimmed.char_indices().peekable();
+        while let Some((_, c)) = chars.next() {
+            current.push(c);
+            let ends = matches!(c, '.' | '!' | '?')
+                && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+            if ends {
+                sentences.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(' ');
+    }
+    if !current.trim().is_empty() {
+        sentences.push(current);
+    }
+    sentences
+}
+
+/// Estimates the syllables in a word.
+fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let word = word.trim_matches(|c: char| !c.is_alphabetic());
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    // A final "e" is usually silent ("make"), but not in "-le" ("table").
+    if count > 1 && word.ends_with('e') && !word.ends_with("le") {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+/// Returns whether a sentence's words contain a passive construction.
+fn is_passive(words: &[&str]) -> bool {
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+    lower.iter().enumerate().any(|(i, word)| {
+        if !TO_BE.contains(&word.as_str()) {
+            return false;
+        }
+        let mut next = lower.get(i + 1);
+        if next.is_some_and(|w| w.ends_with("ly")) {
+            next = lower.get(i + 2);
+        }
+        next.is_some_and(|w| {
+            (w.len() > 3 && w.ends_with("ed")) || IRREGULAR_PARTICIPLES.contains(&w.as_str())
+        })
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syllables() {
+        for (word, count) in [
+            ("cat", 1),
+            ("make", 1),
+            ("table", 2),
+            ("readability", 5),
+            ("the", 1),
+        ] {
+            assert_eq!(syllables(word), count, "{word}");
+        }
+    }
+
+    #[test]
+    fn test_measure() {
+        let metrics = measure(
+            "You are a helpful assistant. The answer is written by {{name}}.\n\n- Be brief\n- Be kind\n```\nIgnored code. More code.\n```",
+        )
+        .unwrap();
+        assert_eq!(metrics.sentences, 4);
+        assert_eq!(metrics.words, 14);
+        assert_eq!(metrics.passive_sentences, 1);
+        assert!(metrics.flesch_reading_ease > 60.0, "{metrics:?}");
+        assert!(measure("{{name}}\n```\ncode\n```").is_none());
+    }
+
+    #[test]
+    fn test_violations() {
+        let metrics = measure(
+            "Comprehensive documentation necessitates considerable organizational investment.",
+        )
+        .unwrap();
+        let config = ReadabilityConfig {
+            min_flesch: Some(50.0),
+            max_sentence_length: Some(5.0),
+        };
+        let violations = metrics.violations(&config);
+        assert_eq!(violations.len(), 2, "{violations:?}");
+        assert!(violations[0].contains("Flesch"));
+        assert!(metrics.violations(&ReadabilityConfig::default()).is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! gRPC front end of the rendering service.
+//!
+//! Implements `promptly.v1.PromptService` for clients that prefer protobuf
+//! over JSON. Structured values travel as `google.protobuf.Struct` with the
+//! same field names as the JSON API.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use dotprompt::{DataArgument, PromptRef};
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Value};
+use serde_json::Map;
+use tokio::net::TcpListener;
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use super::proto::prompt_service_server::{PromptService, PromptServiceServer};
+use super::proto::{self};
+use super::{RenderService, ServiceError};
+
+impl From<ServiceError> for Status {
+    fn from(error: ServiceError) -> Self {
+        match error {
+            ServiceError::NotFound(message) => Self::not_found(message),
+            ServiceError::InvalidRequest(message) => Self::invalid_argument(message),
+            ServiceError::Internal(message) => Self::internal(message),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl PromptService for RenderService {
+    async fn list_prompts(
+        &self,
+        _request: Request<proto::ListPromptsRequest>,
+    ) -> Result<Response<proto::ListPromptsResponse>, Status> {
+        let prompts = self
+            .list()
+            .await?
+            .into_iter()
+            .map(|prompt| proto::PromptRef {
+                name: prompt.name,
+                variant: prompt.variant,
+            })
+            .collect();
+        Ok(Response::new(proto::ListPromptsResponse { prompts }))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: Request<proto::GetPromptRequest>,
+    ) -> Result<Response<proto::GetPromptResponse>, Status> {
+        let prompt = prompt_ref(request.into_inner().prompt)?;
+        let metadata = self.metadata(&prompt.name, prompt.variant).await?;
+        Ok(Response::new(proto::GetPromptResponse {
+            metadata: to_struct(to_json(&metadata)?),
+        }))
+    }
+
+    async fn render(
+        &self,
+        request: Request<proto::RenderRequest>,
+    ) -> Result<Response<proto::RenderedPrompt>, Status> {
+        let request = request.into_inner();
+        let prompt = prompt_ref(request.prompt)?;
+
+        let mut data = Map::new();
+        if let Some(input) = request.input {
+            data.insert("input".to_string(), from_struct(input));
+        }
+        if let Some(context) = request.context {
+            data.insert("context".to_string(), from_struct(context));
+        }
+        if let Some(request_metadata) = request.request_metadata {
+            data.insert("requestMetadata".to_string(), from_struct(request_metadata));
+        }
+        if !request.messages.is_empty() {
+            let messages = request.messages.into_iter().map(from_message).collect();
+            data.insert("messages".to_string(), serde_json::Value::Array(messages));
+        }
+        let data: DataArgument = serde_json::from_value(serde_json::Value::Object(data))
+            .map_err(|e| Status::invalid_argument(format!("Invalid render request: {e}")))?;
+
+        let rendered = self.render(&prompt.name, prompt.variant, &data).await?;
+        let mut json = to_json(&rendered)?;
+        let messages = json
+            .as_object_mut()
+            .and_then(|object| object.remove("messages"))
+            .and_then(|messages| match messages {
+                serde_json::Value::Array(messages) => Some(messages),
+                _ => None,
+            })
+            .unwrap_or_default();
+        Ok(Response::new(proto::RenderedPrompt {
+            metadata: to_struct(json),
+            messages: messages.into_iter().map(to_message).collect(),
+        }))
+    }
+}
+
+/// Extracts the required prompt reference from a request.
+fn prompt_ref(prompt: Option<proto::PromptRef>) -> Result<PromptRef, Status> {
+    let prompt = prompt.ok_or_else(|| Status::invalid_argument("Missing prompt"))?;
+    Ok(PromptRef {
+        name: prompt.name,
+        variant: prompt.variant,
+        version: None,
+        metadata: None,
+    })
+}
+
+/// Serializes a response value to JSON.
+fn to_json(value: &impl serde::Serialize) -> Result<serde_json::Value, Status> {
+    serde_json::to_value(value).map_err(|e| Status::internal(e.to_string()))
+}
+
+/// Converts a dotprompt message from JSON.
+fn to_message(message: serde_json::Value) -> proto::Message {
+    let serde_json::Value::Object(mut message) = message else {
+        return proto::Message::default();
+    };
+    let role = match message.remove("role") {
+        Some(serde_json::Value::String(role)) => role,
+        _ => String::new(),
+    };
+    let content = match message.remove("content") {
+        Some(serde_json::Value::Array(parts)) => parts.into_iter().filter_map(to_struct).collect(),
+        _ => Vec::new(),
+    };
+    proto::Message {
+        role,
+        content,
+        metadata: message.remove("metadata").and_then(to_struct),
+    }
+}
+
+/// Converts a message into dotprompt message JSON.
+fn from_message(message: proto::Message) -> serde_json::Value {
+    let mut json = Map::new();
+    json.insert("role".to_string(), serde_json::Value::String(message.role));
+    json.insert(
+        "content".to_string(),
+        message.content.into_iter().map(from_struct).collect(),
+    );
+    if let Some(metadata) = message.metadata {
+        json.insert("metadata".to_string(), from_struct(metadata));
+    }
+    serde_json::Value::Object(json)
+}
+
+/// Converts a JSON object into a `Struct`; other values yield `None`.
+fn to_struct(value: serde_json::Value) -> Option<Struct> {
+    match value {
+        serde_json::Value::Object(object) => Some(Struct {
+            fields: object.into_iter().map(|(k, v)| (k, to_value(v))).collect(),
+        }),
+        _ => None,
+    }
+}
+
+/// Converts a JSON value into a protobuf `Value`.
+fn to_value(value: serde_json::Value) -> Value {
+    let kind = match value {
+        serde_json::Value::Null => Kind::NullValue(0),
+        serde_json::Value::Bool(b) => Kind::BoolValue(b),
+        serde_json::Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => Kind::StringValue(s),
+        serde_json::Value::Array(items) => Kind::ListValue(ListValue {
+            values: items.into_iter().map(to_value).collect(),
+        }),
+        object @ serde_json::Value::Object(_) => {
+            Kind::StructValue(to_struct(object).unwrap_or_default())
+        }
+    };
+    Value { kind: Some(kind) }
+}
+
+/// Converts a `Struct` into a JSON object.
+fn from_struct(value: Struct) -> serde_json::Value {
+    serde_json::Value::Object(
+        value
+            .fields
+            .into_iter()
+            .map(|(k, v)| (k, from_value(v)))
+            .collect(),
+    )
+}
+
+/// Converts a protobuf `Value` into JSON.
+///
+/// Protobuf carries every number as a double; whole numbers become JSON
+/// integers so that templates render `3` rather than `3.0`.
+fn from_value(value: Value) -> serde_json::Value {
+    /// Largest integer a double represents exactly.
+    const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(b)) => serde_json::Value::Bool(b),
+        #[allow(clippy::cast_possible_truncation)]
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER => {
+            serde_json::Value::from(n as i64)
+        }
+        Some(Kind::NumberValue(n)) => serde_json::Number::from_f64(n)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        Some(Kind::StringValue(s)) => serde_json::Value::String(s),
+        Some(Kind::ListValue(list)) => list.values.into_iter().map(from_value).collect(),
+        Some(Kind::StructValue(s)) => from_struct(s),
+    }
+}
+
+/// Serves the rendering service over gRPC until the process is stopped.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound or the server fails.
+pub(super) async fn serve(service: Arc<RenderService>, addr: SocketAddr) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let local = listener.local_addr()?;
+    eprintln!("promptly serve listening on grpc://{local}");
+    info!(%local, "serving prompts over gRPC");
+    Server::builder()
+        .add_service(PromptServiceServer::from_arc(service))
+        .serve_with_incoming(TcpIncoming::from(listener))
+        .await
+        .map_err(io::Error::other)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    async fn service() -> (TempDir, RenderService) {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("greet.prompt"),
+            "---\nmodel: test/model\n---\nHello {{name}}, you are {{age}}!",
+        )
+        .unwrap();
+        let service = RenderService::new(dir.path().to_path_buf(), false)
+            .await
+            .unwrap();
+        (dir, service)
+    }
+
+    #[test]
+    fn test_struct_round_trip() {
+        let value = json!({"a": 1, "b": [true, null, "x"], "c": {"d": 1.5}});
+        assert_eq!(from_struct(to_struct(value.clone()).unwrap()), value);
+        assert!(to_struct(json!("scalar")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_render() {
+        let (_dir, service) = service().await;
+        let request = proto::RenderRequest {
+            prompt: Some(proto::PromptRef {
+                name: "greet".to_string(),
+                variant: None,
+            }),
+            input: to_struct(json!({"name": "Ada", "age": 36})),
+            request_metadata: to_struct(json!({"traceId": "abc"})),
+            ..Default::default()
+        };
+        let rendered = PromptService::render(&service, Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(rendered.messages.len(), 1);
+        assert_eq!(rendered.messages[0].role, "user");
+        assert_eq!(
+            from_struct(rendered.messages[0].content[0].clone()),
+            json!({"text": "Hello Ada, you are 36!"})
+        );
+        let metadata = from_struct(rendered.metadata.unwrap());
+        assert_eq!(metadata["model"], "test/model");
+        assert_eq!(metadata["metadata"]["requestMetadata"]["traceId"], "abc");
+    }
+
+    #[tokio::test]
+    async fn test_errors_map_to_status_codes() {
+        let (_dir, service) = service().await;
+        let missing = proto::GetPromptRequest {
+            prompt: Some(proto::PromptRef {
+                name: "missing".to_string(),
+                variant: None,
+            }),
+        };
+        let status = service.get_prompt(Request::new(missing)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let status = PromptService::render(&service, Request::new(proto::RenderRequest::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! HTTP front end of the rendering service.
+//!
+//! | Method | Path | Description |
+//! |--------|------|-------------|
+//! | `GET` | `/healthz` | Liveness check |
+//! | `GET` | `/prompts` | List prompts |
+//! | `GET` | `/prompts/{name}` | Prompt metadata |
+//! | `POST` | `/render/{name}` | Render with a `DataArgument` body |
+//!
+//! Each endpoint accepts a `?variant=` query parameter where it applies.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use dotprompt::{DataArgument, PromptMetadata, RenderedPrompt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tracing::info;
+
+use super::{RenderService, ServiceError};
+
+/// An error response: a status code and a JSON `{"error": ...}` body.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl From<ServiceError> for ApiError {
+    fn from(error: ServiceError) -> Self {
+        let (status, message) = match error {
+            ServiceError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ServiceError::InvalidRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ServiceError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        };
+        Self { status, message }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+/// The `?variant=` query parameter.
+#[derive(Debug, Deserialize)]
+struct VariantQuery {
+    variant: Option<String>,
+}
+
+/// Builds the HTTP router for a rendering service.
+fn router(service: Arc<RenderService>) -> Router {
+    Router::new()
+        .route("/healthz", get(|| async { "ok" }))
+        .route("/prompts", get(list_prompts))
+        .route("/prompts/{*name}", get(prompt_metadata))
+        .route("/render/{*name}", post(render_prompt))
+        .with_state(service)
+}
+
+/// Lists the available prompts.
+async fn list_prompts(
+    State(service): State<Arc<RenderService>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let prompts = service.list().await?;
+    Ok(Json(json!({ "prompts": prompts })))
+}
+
+/// Returns a prompt's parsed frontmatter.
+async fn prompt_metadata(
+    State(service): State<Arc<RenderService>>,
+    Path(name): Path<String>,
+    Query(query): Query<VariantQuery>,
+) -> Result<Json<PromptMetadata>, ApiError> {
+    Ok(Json(service.metadata(&name, query.variant).await?))
+}
+
+/// Renders a prompt with the posted data.
+async fn render_prompt(
+    State(service): State<Arc<RenderService>>,
+    Path(name): Path<String>,
+    Query(query): Query<VariantQuery>,
+    Json(data): Json<DataArgument>,
+) -> Result<Json<RenderedPrompt>, ApiError> {
+    Ok(Json(service.render(&name, query.variant, &data).await?))
+}
+
+/// Serves the rendering service over HTTP until the process is stopped.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound.
+pub(super) async fn serve(service: Arc<RenderService>, addr: SocketAddr) -> io::Result<()> {
+    let hot_reload = service.hot_reload;
+    let listener = TcpListener::bind(addr).await?;
+    let local = listener.local_addr()?;
+    eprintln!("promptly serve listening on http://{local}");
+    info!(%local, hot_reload, "serving prompts over HTTP");
+    axum::serve(listener, router(service)).await
+}
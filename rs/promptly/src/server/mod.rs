@@ -0,0 +1,246 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prompt rendering service.
+//!
+//! Serves a directory of prompts so that other languages can use this
+//! implementation as a rendering sidecar. [`RenderService`] holds the
+//! transport-independent handlers; [`http`] exposes them as a JSON API and
+//! [`grpc`] as the `promptly.v1.PromptService` defined in
+//! `proto/promptly/v1/render.proto`.
+
+mod grpc;
+mod http;
+mod proto;
+
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use dotprompt::parse::parse_document;
+use dotprompt::resolvers::StoreSchemaResolver;
+use dotprompt::stores::{DirStore, DirStoreOptions};
+use dotprompt::{
+    AsyncPromptStore, DataArgument, Dotprompt, DotpromptOptions, ListPromptsOptions,
+    LoadPromptOptions, PromptMetadata, PromptRef, RenderedPrompt, ToolDefinition,
+};
+use tracing::debug;
+
+use crate::config::Config;
+
+/// Prompts, partials, and tools loaded from a directory.
+#[derive(Debug, Default)]
+struct Catalog {
+    /// Prompt sources keyed by name and variant.
+    prompts: HashMap<(String, Option<String>), String>,
+    /// Listed prompts with their frontmatter summaries, sorted by name and
+    /// variant.
+    listing: Vec<PromptRef>,
+    /// Partial sources keyed by name.
+    partials: HashMap<String, String>,
+    /// Tools from the project's tool catalog, keyed by name.
+    tools: HashMap<String, ToolDefinition>,
+}
+
+impl Catalog {
+    /// Loads every prompt and partial in a store, and the tool catalog of
+    /// the project in `dir`.
+    async fn load(store: &impl AsyncPromptStore, dir: &Path) -> Result<Self, String> {
+        let mut catalog = Self::default();
+
+        let options = ListPromptsOptions {
+            include_metadata: true,
+            ..Default::default()
+        };
+        let mut listing = store
+            .list(Some(options))
+            .await
+            .map_err(|e| e.to_string())?
+            .prompts;
+        listing.sort_by(|a, b| (&a.name, &a.variant).cmp(&(&b.name, &b.variant)));
+        for prompt_ref in &listing {
+            let data = store
+                .load(
+                    &prompt_ref.name,
+                    Some(LoadPromptOptions {
+                        variant: prompt_ref.variant.clone(),
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            catalog.prompts.insert(
+                (prompt_ref.name.clone(), prompt_ref.variant.clone()),
+                data.source,
+            );
+        }
+        catalog.listing = listing;
+        for partial_ref in store
+            .list_partials(None)
+            .await
+            .map_err(|e| e.to_string())?
+            .partials
+        {
+            let data = store
+                .load_partial(&partial_ref.name, None)
+                .await
+                .map_err(|e| e.to_string())?;
+            catalog.partials.insert(partial_ref.name, data.source);
+        }
+        catalog.tools = Config::load(dir).tool_map().unwrap_or_default();
+
+        debug!(
+            prompts = catalog.prompts.len(),
+            partials = catalog.partials.len(),
+            tools = catalog.tools.len(),
+            "loaded catalog"
+        );
+        Ok(catalog)
+    }
+
+    /// Looks up a prompt's source.
+    fn source(&self, name: &str, variant: Option<String>) -> Result<&str, ServiceError> {
+        self.prompts
+            .get(&(name.to_string(), variant))
+            .map(String::as_str)
+            .ok_or_else(|| ServiceError::NotFound(format!("No prompt named '{name}'")))
+    }
+}
+
+/// A failed request, independent of the transport that carried it.
+#[derive(Debug)]
+enum ServiceError {
+    /// The requested prompt does not exist.
+    NotFound(String),
+    /// The request could not be served as given, e.g. a render error.
+    InvalidRequest(String),
+    /// The server could not load or parse its prompts.
+    Internal(String),
+}
+
+/// The rendering service shared by the HTTP and gRPC front ends.
+#[derive(Debug)]
+struct RenderService {
+    /// The prompt directory.
+    dir: PathBuf,
+    /// The store the catalog is loaded from.
+    store: DirStore,
+    /// Whether to reload prompts from disk on every request.
+    hot_reload: bool,
+    /// The catalog loaded at startup.
+    catalog: Arc<Catalog>,
+}
+
+impl RenderService {
+    /// Loads the prompts in a directory.
+    async fn new(dir: PathBuf, hot_reload: bool) -> Result<Self, String> {
+        let store = DirStore::new(DirStoreOptions {
+            directory: dir.clone(),
+            ..Default::default()
+        });
+        let catalog = Catalog::load(&store, &dir).await?;
+        Ok(Self {
+            dir,
+            store,
+            hot_reload,
+            catalog: Arc::new(catalog),
+        })
+    }
+
+    /// Returns the catalog, reloading it from the store in hot-reload mode.
+    async fn catalog(&self) -> Result<Arc<Catalog>, ServiceError> {
+        if self.hot_reload {
+            return Catalog::load(&self.store, &self.dir)
+                .await
+                .map(Arc::new)
+                .map_err(ServiceError::Internal);
+        }
+        Ok(Arc::clone(&self.catalog))
+    }
+
+    /// Lists the available prompts with their frontmatter summaries, sorted
+    /// by name and variant.
+    async fn list(&self) -> Result<Vec<PromptRef>, ServiceError> {
+        Ok(self.catalog().await?.listing.clone())
+    }
+
+    /// Returns a prompt's parsed frontmatter.
+    async fn metadata(
+        &self,
+        name: &str,
+        variant: Option<String>,
+    ) -> Result<PromptMetadata, ServiceError> {
+        let catalog = self.catalog().await?;
+        let source = catalog.source(name, variant)?;
+        let parsed = parse_document::<serde_json::Value>(source)
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+        Ok(parsed.metadata)
+    }
+
+    /// Renders a prompt with the given data.
+    async fn render(
+        &self,
+        name: &str,
+        variant: Option<String>,
+        data: &DataArgument,
+    ) -> Result<RenderedPrompt, ServiceError> {
+        let catalog = self.catalog().await?;
+        let source = catalog.source(name, variant)?;
+        let dotprompt = Dotprompt::new(Some(DotpromptOptions {
+            partials: Some(catalog.partials.clone()),
+            tools: Some(catalog.tools.clone()),
+            schema_resolver: Some(Box::new(StoreSchemaResolver::for_prompt_dir(&self.dir))),
+            ..Default::default()
+        }));
+        let rendered = dotprompt
+            .render(source, data, None)
+            .map_err(|e| ServiceError::InvalidRequest(e.to_string()))?;
+        debug!(%name, messages = rendered.messages.len(), "rendered prompt");
+        Ok(rendered)
+    }
+}
+
+/// Serves a prompt directory over HTTP, and over gRPC when `grpc_addr` is
+/// given, until the process is stopped.
+///
+/// # Errors
+///
+/// Returns an error if the prompts cannot be loaded or an address cannot be
+/// bound.
+pub(crate) async fn run(
+    dir: PathBuf,
+    http_addr: SocketAddr,
+    grpc_addr: Option<SocketAddr>,
+    hot_reload: bool,
+) -> io::Result<()> {
+    let service = Arc::new(
+        RenderService::new(dir, hot_reload)
+            .await
+            .map_err(io::Error::other)?,
+    );
+    match grpc_addr {
+        Some(grpc_addr) => {
+            tokio::try_join!(
+                http::serve(Arc::clone(&service), http_addr),
+                grpc::serve(service, grpc_addr),
+            )?;
+            Ok(())
+        }
+        None => http::serve(service, http_addr).await,
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// This file is @generated by prost-build.
+/// Identifies a prompt.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PromptRef {
+    /// Prompt name, e.g. "greeting" or "support/triage".
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// Optional variant, e.g. "formal" for greeting.formal.prompt.
+    #[prost(string, optional, tag = "2")]
+    pub variant: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Request for ListPrompts.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ListPromptsRequest {}
+/// Response for ListPrompts.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPromptsResponse {
+    /// The prompts, sorted by name and variant.
+    #[prost(message, repeated, tag = "1")]
+    pub prompts: ::prost::alloc::vec::Vec<PromptRef>,
+}
+/// Request for GetPrompt.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct GetPromptRequest {
+    /// The prompt to describe.
+    #[prost(message, optional, tag = "1")]
+    pub prompt: ::core::option::Option<PromptRef>,
+}
+/// Response for GetPrompt.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPromptResponse {
+    /// The prompt's frontmatter, as dotprompt PromptMetadata JSON.
+    #[prost(message, optional, tag = "1")]
+    pub metadata: ::core::option::Option<::prost_types::Struct>,
+}
+/// Request for Render.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RenderRequest {
+    /// The prompt to render.
+    #[prost(message, optional, tag = "1")]
+    pub prompt: ::core::option::Option<PromptRef>,
+    /// Input variables.
+    #[prost(message, optional, tag = "2")]
+    pub input: ::core::option::Option<::prost_types::Struct>,
+    /// Context variables, exposed as @ variables in templates.
+    #[prost(message, optional, tag = "3")]
+    pub context: ::core::option::Option<::prost_types::Struct>,
+    /// Conversation history, available to the history helper.
+    #[prost(message, repeated, tag = "4")]
+    pub messages: ::prost::alloc::vec::Vec<Message>,
+    /// Opaque caller metadata, such as trace ids, returned in the rendered
+    /// prompt's metadata.
+    #[prost(message, optional, tag = "5")]
+    pub request_metadata: ::core::option::Option<::prost_types::Struct>,
+}
+/// A rendered message.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Message {
+    /// "system", "user", "model", or "tool".
+    #[prost(string, tag = "1")]
+    pub role: ::prost::alloc::string::String,
+    /// Message parts, as dotprompt Part JSON (e.g. {"text": "..."}).
+    #[prost(message, repeated, tag = "2")]
+    pub content: ::prost::alloc::vec::Vec<::prost_types::Struct>,
+    /// Optional message metadata.
+    #[prost(message, optional, tag = "3")]
+    pub metadata: ::core::option::Option<::prost_types::Struct>,
+}
+/// Response for Render.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RenderedPrompt {
+    /// The prompt's resolved metadata, as dotprompt PromptMetadata JSON.
+    #[prost(message, optional, tag = "1")]
+    pub metadata: ::core::option::Option<::prost_types::Struct>,
+    /// The rendered messages.
+    #[prost(message, repeated, tag = "2")]
+    pub messages: ::prost::alloc::vec::Vec<Message>,
+}
+/// Generated server implementations.
+pub mod prompt_service_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with PromptServiceServer.
+    #[async_trait]
+    pub trait PromptService: std::marker::Send + std::marker::Sync + 'static {
+        /// Lists the available prompts.
+        async fn list_prompts(
+            &self,
+            request: tonic::Request<super::ListPromptsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListPromptsResponse>,
+            tonic::Status,
+        >;
+        /// Returns a prompt's parsed frontmatter.
+        async fn get_prompt(
+            &self,
+            request: tonic::Request<super::GetPromptRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetPromptResponse>,
+            tonic::Status,
+        >;
+        /// Renders a prompt into messages.
+        async fn render(
+            &self,
+            request: tonic::Request<super::RenderRequest>,
+        ) -> std::result::Result<tonic::Response<super::RenderedPrompt>, tonic::Status>;
+    }
+    /// Lists, describes, and renders the prompts in a directory.
+    #[derive(Debug)]
+    pub struct PromptServiceServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> PromptServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for PromptServiceServer<T>
+    where
+        T: PromptService,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/promptly.v1.PromptService/ListPrompts" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListPromptsSvc<T: PromptService>(pub Arc<T>);
+                    impl<
+                        T: PromptService,
+                    > tonic::server::UnaryService<super::ListPromptsRequest>
+                    for ListPromptsSvc<T> {
+                        type Response = super::ListPromptsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListPromptsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PromptService>::list_prompts(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListPromptsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/promptly.v1.PromptService/GetPrompt" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPromptSvc<T: PromptService>(pub Arc<T>);
+                    impl<
+                        T: PromptService,
+                    > tonic::server::UnaryService<super::GetPromptRequest>
+                    for GetPromptSvc<T> {
+                        type Response = super::GetPromptResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetPromptRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PromptService>::get_prompt(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetPromptSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/promptly.v1.PromptService/Render" => {
+                    #[allow(non_camel_case_types)]
+                    struct RenderSvc<T: PromptService>(pub Arc<T>);
+                    impl<
+                        T: PromptService,
+                    > tonic::server::UnaryService<super::RenderRequest>
+                    for RenderSvc<T> {
+                        type Response = super::RenderedPrompt;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RenderRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PromptService>::render(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RenderSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        let mut response = http::Response::new(
+                            tonic::body::Body::default(),
+                        );
+                        let headers = response.headers_mut();
+                        headers
+                            .insert(
+                                tonic::Status::GRPC_STATUS,
+                                (tonic::Code::Unimplemented as i32).into(),
+                            );
+                        headers
+                            .insert(
+                                http::header::CONTENT_TYPE,
+                                tonic::metadata::GRPC_CONTENT_TYPE,
+                            );
+                        Ok(response)
+                    })
+                }
+            }
+        }
+    }
+    impl<T> Clone for PromptServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "promptly.v1.PromptService";
+    impl<T> tonic::server::NamedService for PromptServiceServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
@@ -0,0 +1,31 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Protobuf messages and service for `promptly.v1`.
+//!
+//! `promptly.v1.rs` is generated from `proto/promptly/v1/render.proto` by
+//! `tonic-prost-build` (server only) and checked in so that building does not
+//! need `protoc`. Regenerate it after changing the `.proto` file.
+
+#![allow(
+    unreachable_pub,
+    missing_docs,
+    clippy::pedantic,
+    clippy::nursery,
+    clippy::derive_partial_eq_without_eq
+)]
+
+include!("promptly.v1.rs");
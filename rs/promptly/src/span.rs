@@ -17,7 +17,9 @@
 //! Source span tracking for error reporting.
 //!
 //! This module provides types for tracking source locations in `.prompt` files,
-//! enabling Rust-style error messages with precise line and column information.
+//! enabling Rust-style error messages with precise line and column information,
+//! and a [`SourceMap`] for converting between byte offsets, character columns,
+//! and the UTF-16 positions the LSP uses.
 
 use serde::{Deserialize, Serialize};
 
@@ -28,7 +30,7 @@ pub(crate) struct Position {
     pub offset: usize,
     /// 1-indexed line number.
     pub line: u32,
-    /// 1-indexed column number (UTF-8 aware).
+    /// 1-indexed column number, in characters.
     pub column: u32,
 }
 
@@ -83,31 +85,142 @@ impl Span {
             end: Position::new(0, end_line, end_col),
         }
     }
+
+    /// Creates a span from byte offsets into `source`.
+    #[must_use]
+    pub(crate) fn from_offsets(source: &str, start: usize, end: usize) -> Self {
+        SourceMap::new(source).span_in(0, start..end)
+    }
 }
 
-/// Calculates the position at a given byte offset in the source.
-#[must_use]
-pub(crate) fn position_at_offset(source: &str, offset: usize) -> Position {
-    let mut line = 1u32;
-    let mut column = 1u32;
-    let mut current_offset = 0usize;
+/// Maps between byte offsets and line and column positions in a source.
+///
+/// Columns come in two units: 1-indexed characters in a [`Position`], for
+/// diagnostics shown to people, and 0-indexed UTF-16 code units, which is
+/// what the Language Server Protocol counts. The two agree only on ASCII
+/// text, so localized prompts need the right one for each audience.
+///
+/// Offsets inside a character are treated as the start of that character,
+/// and offsets and positions past the end clamp to the end.
+#[derive(Debug, Clone)]
+pub(crate) struct SourceMap<'a> {
+    source: &'a str,
+    /// Byte offset at which each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    /// Indexes the lines of a source.
+    #[must_use]
+    pub(crate) fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// Returns the position of a byte offset.
+    #[must_use]
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let (line, text) = self.line_before(offset);
+        Position::new(offset, line_number(line) + 1, count(text.chars()) + 1)
+    }
+
+    /// Returns the byte offset of a 1-indexed line and character column.
+    ///
+    /// This is the inverse of [`position`](Self::position). Columns past the
+    /// end of a line clamp to the end of that line.
+    #[must_use]
+    pub(crate) fn offset(&self, line: u32, column: u32) -> usize {
+        let Some(start) = self.line_start(line.saturating_sub(1)) else {
+            return self.source.len();
+        };
+        let text = self.line_text(start);
+        let skip = column.saturating_sub(1) as usize;
+        start + text.char_indices().nth(skip).map_or(text.len(), |(i, _)| i)
+    }
+
+    /// Returns the 0-indexed line and UTF-16 character of a byte offset, as
+    /// an LSP position.
+    #[must_use]
+    pub(crate) fn lsp_position(&self, offset: usize) -> (u32, u32) {
+        let (line, text) = self.line_before(offset);
+        (line_number(line), count(text.encode_utf16()))
+    }
 
-    for ch in source.chars() {
-        if current_offset >= offset {
-            break;
+    /// Returns the byte offset of a 0-indexed line and UTF-16 character, as
+    /// an LSP position.
+    ///
+    /// This is the inverse of [`lsp_position`](Self::lsp_position). A
+    /// character inside a surrogate pair maps to the start of its character.
+    #[must_use]
+    pub(crate) fn lsp_offset(&self, line: u32, character: u32) -> usize {
+        let Some(start) = self.line_start(line) else {
+            return self.source.len();
+        };
+        let text = self.line_text(start);
+        let mut units = 0;
+        for (i, c) in text.char_indices() {
+            units += c.len_utf16();
+            if units > character as usize {
+                return start + i;
+            }
         }
+        start + text.len()
+    }
 
-        if ch == '\n' {
-            line += 1;
-            column = 1;
-        } else {
-            column += 1;
+    /// Returns the span of a byte range of a region starting at `base`, such
+    /// as the frontmatter YAML or the template body.
+    #[must_use]
+    pub(crate) fn span_in(&self, base: usize, range: std::ops::Range<usize>) -> Span {
+        Span {
+            start: self.position(base + range.start),
+            end: self.position(base + range.end),
         }
+    }
+
+    /// Returns the 0-indexed line containing `offset` and the text of that
+    /// line before it.
+    fn line_before(&self, offset: usize) -> (usize, &'a str) {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        (line, &self.source[self.line_starts[line]..offset])
+    }
 
-        current_offset += ch.len_utf8();
+    /// Returns the byte offset at which a 0-indexed line starts.
+    fn line_start(&self, line: u32) -> Option<usize> {
+        self.line_starts.get(line as usize).copied()
     }
 
-    Position::new(offset, line, column)
+    /// Returns the text of the line starting at `start`, without its line
+    /// terminator.
+    fn line_text(&self, start: usize) -> &'a str {
+        let rest = &self.source[start..];
+        let line = rest.find('\n').map_or(rest, |end| &rest[..end]);
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+}
+
+/// Converts a 0-indexed line to a line number.
+fn line_number(line: usize) -> u32 {
+    u32::try_from(line).unwrap_or(u32::MAX)
+}
+
+/// Counts the items of an iterator as a column.
+fn count<T>(items: impl Iterator<Item = T>) -> u32 {
+    u32::try_from(items.count()).unwrap_or(u32::MAX)
+}
+
+/// Calculates the position at a given byte offset in the source.
+#[must_use]
+pub(crate) fn position_at_offset(source: &str, offset: usize) -> Position {
+    SourceMap::new(source).position(offset)
 }
 
 #[cfg(test)]
@@ -138,6 +251,18 @@ mod tests {
         assert_eq!(pos.column, 1);
     }
 
+    #[test]
+    fn test_offset_round_trips() {
+        let source = "héllo\nworld";
+        let map = SourceMap::new(source);
+        for offset in [0, 1, 3, 7, source.len()] {
+            let pos = position_at_offset(source, offset);
+            assert_eq!(map.offset(pos.line, pos.column), offset);
+        }
+        assert_eq!(map.offset(1, 99), 6);
+        assert_eq!(map.offset(9, 1), source.len());
+    }
+
     #[test]
     fn test_position_at_offset_end() {
         let source = "hello\nworld";
@@ -145,4 +270,50 @@ mod tests {
         assert_eq!(pos.line, 2);
         assert_eq!(pos.column, 6);
     }
+
+    #[test]
+    fn test_columns_count_characters_and_utf16_units() {
+        let source = "---\nmodel: é\n---\n日本 {{name}} 🙂 {{x}}";
+        let map = SourceMap::new(source);
+        let name = source.find("{{name").unwrap_or_default();
+        let x = source.find("{{x").unwrap_or_default();
+
+        assert_eq!(map.position(name), Position::new(name, 4, 4));
+        assert_eq!(map.position(x), Position::new(x, 4, 15));
+        assert_eq!(map.lsp_position(name), (3, 3));
+        assert_eq!(map.lsp_position(x), (3, 15));
+
+        for offset in [0, 11, name, x, source.len()] {
+            let (line, character) = map.lsp_position(offset);
+            assert_eq!(map.lsp_offset(line, character), offset);
+        }
+        // Inside the surrogate pair of the emoji.
+        let emoji = source.find('🙂').unwrap_or_default();
+        assert_eq!(map.lsp_offset(3, 13), emoji);
+        assert_eq!(map.lsp_offset(3, 99), source.len());
+        assert_eq!(map.lsp_offset(9, 0), source.len());
+        assert_eq!(map.position(emoji + 1).column, 13);
+    }
+
+    #[test]
+    fn test_positions_past_a_crlf_line_end_clamp_before_it() {
+        let source = "مرحبا\r\nworld";
+        let map = SourceMap::new(source);
+
+        assert_eq!(map.lsp_offset(0, 99), "مرحبا".len());
+        assert_eq!(map.offset(1, 99), "مرحبا".len());
+        assert_eq!(map.lsp_position(source.len()), (1, 5));
+        assert_eq!(map.position("مرحبا".len()).column, 6);
+    }
+
+    #[test]
+    fn test_span_in_maps_region_offsets() {
+        let source = "---\nmodel: é\n---\nHi {{name}}";
+        let body = source.find("Hi").unwrap_or_default();
+        let span = SourceMap::new(source).span_in(body, 3..11);
+
+        assert_eq!((span.start.line, span.start.column), (4, 4));
+        assert_eq!((span.end.line, span.end.column), (4, 12));
+        assert_eq!(&source[span.start.offset..span.end.offset], "{{name}}");
+    }
 }
@@ -0,0 +1,194 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Spell checking for the prose in a template body.
+//!
+//! Prompts are full of names, jargon, and identifiers that no dictionary
+//! knows, so rather than flag every unknown word, the checker looks words up
+//! in a bundled list of common misspellings (`misspellings.txt`). Anything
+//! not on the list is assumed correct.
+//!
+//! Only prose is checked: Handlebars expressions and comments, code fences,
+//! inline code, and URLs are skipped. The pass is opt-in:
+//!
+//! ```toml
+//! [lint.spelling]
+//! enabled = true
+//! # Words to accept even though they are on the misspellings list.
+//! allow = ["teh"]
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::prose::mask_non_prose;
+
+/// The bundled misspellings list.
+const MISSPELLINGS: &str = include_str!("misspellings.txt");
+
+/// Misspellings and their corrections, parsed from [`MISSPELLINGS`].
+static DICTIONARY: OnceLock<HashMap<&'static str, Vec<&'static str>>> = OnceLock::new();
+
+/// Regex for candidate words.
+static WORD_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// The `[lint.spelling]` section of `promptly.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct SpellingConfig {
+    /// Whether prose is spell checked.
+    pub enabled: bool,
+    /// Words never reported, compared case-insensitively.
+    pub allow: Vec<String>,
+}
+
+/// A misspelled word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Misspelling {
+    /// Byte range of the word in the checked text.
+    pub range: Range<usize>,
+    /// The word as written.
+    pub word: String,
+    /// Likely corrections, matching the word's capitalization.
+    pub suggestions: Vec<String>,
+}
+
+/// Returns the misspelled words in the prose of a template body.
+#[must_use]
+pub(crate) fn check_prose(body: &str, config: &SpellingConfig) -> Vec<Misspelling> {
+    let dictionary = DICTIONARY.get_or_init(|| {
+        MISSPELLINGS
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once("->"))
+            .map(|(word, corrections)| {
+                (word.trim(), corrections.split(',').map(str::trim).collect())
+            })
+            .collect()
+    });
+    let Some(word_re) = WORD_RE.get_or_init(|| Regex::new(r"[A-Za-z]+(?:'[A-Za-z]+)?").ok()) else {
+        return Vec::new();
+    };
+    let allow: HashSet<String> = config.allow.iter().map(|w| w.to_lowercase()).collect();
+
+    let prose = mask_non_prose(body);
+    let mut misspellings = Vec::new();
+    for word in word_re.find_iter(&prose) {
+        let before = prose[..word.start()].chars().next_back();
+        let after = prose[word.end()..].chars().next();
+        // Skip parts of identifiers, paths, and addresses.
+        if before.is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '/' | '@' | '-'))
+            || after.is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '/' | '@'))
+        {
+            continue;
+        }
+        let lower = word.as_str().to_lowercase();
+        if allow.contains(&lower) {
+            continue;
+        }
+        if let Some(corrections) = dictionary.get(lower.as_str()) {
+            misspellings.push(Misspelling {
+                range: word.range(),
+                word: word.as_str().to_string(),
+                suggestions: corrections
+                    .iter()
+                    .map(|c| match_case(word.as_str(), c))
+                    .collect(),
+            });
+        }
+    }
+    misspellings
+}
+
+/// Returns `correction` capitalized like `word`.
+fn match_case(word: &str, correction: &str) -> String {
+    if word.len() > 1 && word.chars().all(|c| !c.is_lowercase()) {
+        correction.to_uppercase()
+    } else if word.starts_with(char::is_uppercase) {
+        let mut chars = correction.chars();
+        chars.next().map_or_else(String::new, |first| {
+            first.to_uppercase().chain(chars).collect()
+        })
+    } else {
+        correction.to_string()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn words(body: &str, config: &SpellingConfig) -> Vec<(String, Vec<String>)> {
+        check_prose(body, config)
+            .into_iter()
+            .map(|m| {
+                assert_eq!(&body[m.range.clone()], m.word);
+                (m.word, m.suggestions)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dictionary_is_well_formed() {
+        let entries: Vec<&str> = MISSPELLINGS
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        let mut sorted = entries.clone();
+        sorted.sort_unstable();
+        assert_eq!(entries, sorted, "misspellings.txt should be sorted");
+        for entry in entries {
+            let (word, corrections) = entry.split_once("->").unwrap();
+            assert!(!corrections.split(',').any(|c| c.trim() == word), "{entry}");
+        }
+    }
+
+    #[test]
+    fn test_check_prose() {
+        let config = SpellingConfig {
+            enabled: true,
+            allow: Vec::new(),
+        };
+        assert_eq!(
+            words("Teh answer is RECIEVE, or throught.", &config),
+            [
+                ("Teh".to_string(), vec!["The".to_string()]),
+                ("RECIEVE".to_string(), vec!["RECEIVE".to_string()]),
+                (
+                    "throught".to_string(),
+                    vec!["through".to_string(), "thought".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_prose_skips_code_and_expressions() {
+        let config = SpellingConfig {
+            enabled: true,
+            allow: vec!["Wich".to_string()],
+        };
+        let body = "{{teh}} {{!-- teh\nteh --}} `teh` https://x.dev/teh\n```\nteh\n```\nteh_var a.teh wich teh";
+        let found = words(body, &config);
+        assert_eq!(found.len(), 1);
+        assert_eq!(check_prose(body, &config)[0].range.start, body.len() - 3);
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Block structure of `.prompt` files.
+//!
+//! This module scans a document into the regions editors care about: the
+//! frontmatter, Handlebars blocks (`{{#each}}...{{/each}}`), role and section
+//! markers (which extend to the next marker), and multi-line comments. All
+//! offsets are byte offsets into the source.
+
+use std::ops::Range;
+
+/// The kind of a structural region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RegionKind {
+    /// The YAML frontmatter, including its `---` delimiters.
+    Frontmatter,
+    /// A Handlebars block such as `{{#if}}...{{/if}}`, by helper name.
+    Block(String),
+    /// Content following a `{{role}}` or `{{history}}` marker.
+    Role,
+    /// Content following a `{{section}}` marker.
+    Section,
+    /// A Handlebars comment (`{{!-- ... --}}`).
+    Comment,
+}
+
+/// A structural region of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Region {
+    /// What this region is.
+    pub kind: RegionKind,
+    /// Byte range of the region, from its opening tag to its closing tag.
+    pub range: Range<usize>,
+}
+
+/// The scanned structure of a document.
+#[derive(Debug, Default)]
+pub(crate) struct DocumentStructure {
+    /// Regions in order of their start offset.
+    pub regions: Vec<Region>,
+    /// Byte ranges of every `{{...}}` expression.
+    pub expressions: Vec<Range<usize>>,
+}
+
+/// A marker region that stays open until the next marker.
+struct OpenMarker {
+    kind: RegionKind,
+    start: usize,
+}
+
+impl DocumentStructure {
+    /// Scans a document.
+    #[must_use]
+    pub(crate) fn parse(source: &str) -> Self {
+        let mut structure = Self::default();
+        let body_start = structure.scan_frontmatter(source);
+        structure.scan_body(source, body_start);
+        structure.regions.sort_by_key(|r| r.range.start);
+        structure
+    }
+
+    /// Returns the regions containing `offset`, outermost first.
+    #[must_use]
+    pub(crate) fn regions_at(&self, offset: usize) -> Vec<&Region> {
+        let mut regions: Vec<&Region> = self
+            .regions
+            .iter()
+            .filter(|r| r.range.start <= offset && offset < r.range.end)
+            .collect();
+        regions.sort_by_key(|r| std::cmp::Reverse(r.range.len()));
+        regions
+    }
+
+    /// Returns the expression containing `offset`, if any.
+    #[must_use]
+    pub(crate) fn expression_at(&self, offset: usize) -> Option<Range<usize>> {
+        self.expressions
+            .iter()
+            .find(|e| e.start <= offset && offset < e.end)
+            .cloned()
+    }
+
+    /// Records the frontmatter region and returns the offset where the body starts.
+    fn scan_frontmatter(&mut self, source: &str) -> usize {
+        let mut lines = line_ranges(source);
+        let Some(first) = lines.next() else {
+            return 0;
+        };
+        if source[first.clone()].trim() != "---" {
+            return 0;
+        }
+        for line in lines {
+            if source[line.clone()].trim() == "---" {
+                self.regions.push(Region {
+                    kind: RegionKind::Frontmatter,
+                    range: first.start..line.end,
+                });
+                return line.end;
+            }
+        }
+        0
+    }
+
+    /// Records blocks, markers, comments, and expressions in the template body.
+    fn scan_body(&mut self, source: &str, body_start: usize) {
+        let mut blocks: Vec<(String, usize)> = Vec::new();
+        let mut role: Option<OpenMarker> = None;
+        let mut section: Option<OpenMarker> = None;
+        let mut pos = body_start;
+
+        while let Some(found) = source[pos..].find("{{") {
+            let start = pos + found;
+            let inner_start = start + 2;
+            let is_long_comment = source[inner_start..].starts_with("!--");
+            let terminator = if is_long_comment { "--}}" } else { "}}" };
+            let Some(end) = expression_end(source, inner_start, terminator) else {
+                // An expression still being typed ends at the next `{{` or
+                // the end of its line, so the rest of the document scans.
+                let rest = &source[inner_start..];
+                let body = inner_start + rest.len() - rest.trim_start_matches('{').len();
+                let end = source[body..]
+                    .find("{{")
+                    .into_iter()
+                    .chain(source[body..].find('\n'))
+                    .min()
+                    .map_or(source.len(), |i| body + i);
+                pos = end;
+                self.expressions.push(start..end);
+                continue;
+            };
+            let inner = &source[inner_start..end - terminator.len()];
+            pos = end;
+            self.expressions.push(start..end);
+
+            let content = inner
+                .trim_start_matches('{')
+                .trim_start_matches('~')
+                .trim_end_matches('~')
+                .trim();
+
+            if content.starts_with('!') {
+                if source[start..end].contains('\n') {
+                    self.regions.push(Region {
+                        kind: RegionKind::Comment,
+                        range: start..end,
+                    });
+                }
+            } else if let Some(open) = content.strip_prefix('#') {
+                let name = helper_name(open.trim_start_matches(['>', '*']));
+                blocks.push((name, start));
+            } else if let Some(close) = content.strip_prefix('/') {
+                let name = helper_name(close);
+                if let Some(index) = blocks.iter().rposition(|(n, _)| *n == name) {
+                    // Blocks opened after the match were never closed; drop them.
+                    let open_start = blocks[index].1;
+                    blocks.truncate(index);
+                    self.regions.push(Region {
+                        kind: RegionKind::Block(name),
+                        range: open_start..end,
+                    });
+                }
+            } else if blocks.is_empty() {
+                match helper_name(content).as_str() {
+                    "role" | "history" => {
+                        self.close_marker(section.take(), start);
+                        self.close_marker(role.take(), start);
+                        role = Some(OpenMarker {
+                            kind: RegionKind::Role,
+                            start,
+                        });
+                    }
+                    "section" => {
+                        self.close_marker(section.take(), start);
+                        section = Some(OpenMarker {
+                            kind: RegionKind::Section,
+                            start,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.close_marker(section, source.len());
+        self.close_marker(role, source.len());
+    }
+
+    /// Records a marker region ending at `end`.
+    fn close_marker(&mut self, marker: Option<OpenMarker>, end: usize) {
+        let Some(marker) = marker else { return };
+        self.regions.push(Region {
+            kind: marker.kind,
+            range: marker.start..end,
+        });
+    }
+}
+
+/// Returns the end offset of the expression whose content starts at
+/// `inner_start`, or `None` if it is not closed before the next `{{`.
+fn expression_end(source: &str, inner_start: usize, terminator: &str) -> Option<usize> {
+    let rest = &source[inner_start..];
+    let len = rest.find(terminator)?;
+    // Comments may contain `{{`; other expressions never do past their
+    // opening braces.
+    let content = rest[..len].trim_start_matches('{');
+    if !content.starts_with('!') && content.contains("{{") {
+        return None;
+    }
+    Some(inner_start + len + terminator.len())
+}
+
+/// Extracts the helper name at the start of an expression.
+fn helper_name(content: &str) -> String {
+    content
+        .trim_start()
+        .split(|c: char| c.is_whitespace() || c == '}' || c == '~')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Iterates over the byte ranges of each line, excluding line terminators.
+fn line_ranges(source: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = 0;
+    source.split_inclusive('\n').map(move |line| {
+        let range = start..start + line.trim_end_matches(['\n', '\r']).len();
+        start += line.len();
+        range
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(RegionKind, &str)> {
+        DocumentStructure::parse(source)
+            .regions
+            .into_iter()
+            .map(|r| (r.kind, &source[r.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_frontmatter_and_blocks() {
+        let source =
+            "---\nmodel: gemini\n---\n{{#each items}}\n{{#if this}}{{this}}{{/if}}\n{{/each}}\n";
+        let regions = kinds(source);
+
+        assert_eq!(
+            regions[0],
+            (RegionKind::Frontmatter, "---\nmodel: gemini\n---")
+        );
+        assert_eq!(
+            regions[1],
+            (
+                RegionKind::Block("each".to_string()),
+                "{{#each items}}\n{{#if this}}{{this}}{{/if}}\n{{/each}}"
+            )
+        );
+        assert_eq!(
+            regions[2],
+            (
+                RegionKind::Block("if".to_string()),
+                "{{#if this}}{{this}}{{/if}}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_role_markers_extend_to_next_marker() {
+        let source = "{{role \"system\"}}\nBe nice.\n{{role \"user\"}}\nHi {{name}}\n";
+        let regions = kinds(source);
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            regions[0],
+            (RegionKind::Role, "{{role \"system\"}}\nBe nice.\n")
+        );
+        assert_eq!(
+            regions[1],
+            (RegionKind::Role, "{{role \"user\"}}\nHi {{name}}\n")
+        );
+    }
+
+    #[test]
+    fn test_unclosed_blocks_and_comments() {
+        let source = "{{#if a}}\n{{!-- a\n}} comment --}}\n{{#each b}}\n{{/if}}";
+        let regions = kinds(source);
+
+        assert_eq!(regions[0].0, RegionKind::Block("if".to_string()));
+        assert_eq!(
+            regions[1],
+            (RegionKind::Comment, "{{!-- a\n}} comment --}}")
+        );
+        assert_eq!(regions.len(), 2);
+    }
+
+    #[test]
+    fn test_unterminated_expressions_do_not_stop_the_scan() {
+        let source = "Hi {{name\n{{#each items}}{{this}} {{upper x{{/each}}";
+        let structure = DocumentStructure::parse(source);
+        let expressions: Vec<&str> = structure
+            .expressions
+            .iter()
+            .map(|e| &source[e.clone()])
+            .collect();
+
+        assert_eq!(
+            expressions,
+            [
+                "{{name",
+                "{{#each items}}",
+                "{{this}}",
+                "{{upper x",
+                "{{/each}}"
+            ]
+        );
+        assert_eq!(
+            kinds(source),
+            [(
+                RegionKind::Block("each".to_string()),
+                "{{#each items}}{{this}} {{upper x{{/each}}"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_lookup_by_offset() {
+        let source = "{{#if a}}\nHello {{name}}\n{{/if}}";
+        let structure = DocumentStructure::parse(source);
+        let offset = source.find("name").unwrap_or_default();
+
+        let expression = structure.expression_at(offset).unwrap_or_default();
+        assert_eq!(&source[expression], "{{name}}");
+        assert_eq!(structure.regions_at(offset).len(), 1);
+        assert!(structure.regions_at(source.len()).is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Line layout of rendered messages.
+//!
+//! The playground and lint diagnostics both show rendered prompts as a
+//! header per message followed by its content. They style the lines
+//! differently, so this module only decides what the lines are.
+
+use std::fmt;
+
+use dotprompt::{Message, Part};
+
+/// One line of rendered messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TranscriptLine {
+    /// Header before a message, holding its role.
+    Role(String),
+    /// A line of text content, or the blank line between messages.
+    Text(String),
+    /// A part other than text, as JSON.
+    Part(String),
+}
+
+impl fmt::Display for TranscriptLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Role(role) => write!(f, "── {role} ──"),
+            Self::Text(line) | Self::Part(line) => f.write_str(line),
+        }
+    }
+}
+
+/// Lays out messages as lines, with a blank line between messages.
+pub(crate) fn transcript(messages: &[Message]) -> Vec<TranscriptLine> {
+    let mut lines = Vec::new();
+    for message in messages {
+        let role = serde_json::to_value(message.role)
+            .ok()
+            .and_then(|role| role.as_str().map(str::to_string))
+            .unwrap_or_default();
+        if !lines.is_empty() {
+            lines.push(TranscriptLine::Text(String::new()));
+        }
+        lines.push(TranscriptLine::Role(role));
+        for part in &message.content {
+            match part {
+                Part::Text(text) => {
+                    lines.extend(
+                        text.text
+                            .lines()
+                            .map(|line| TranscriptLine::Text(line.to_string())),
+                    );
+                }
+                other => lines.push(TranscriptLine::Part(
+                    serde_json::to_string(other).unwrap_or_default(),
+                )),
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,146 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verbatim regions in template bodies.
+//!
+//! Raw blocks (`{{{{raw}}}}...{{{{/raw}}}}`) and the content of
+//! `{{#verbatim}}...{{/verbatim}}` reach the rendered prompt as written, so
+//! they often hold code examples. Tools that rewrite or check template
+//! syntax skip these regions: the formatter leaves them alone and the linter
+//! does not look inside.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Returns the byte ranges of `source` that must be kept as written.
+///
+/// A raw block's range covers the whole block, tags included, since its tags
+/// are not ordinary Handlebars either. A verbatim block's range covers only
+/// its content, between the opening and closing tags. A region that is never
+/// closed runs to the end of `source`.
+#[must_use]
+pub(crate) fn verbatim_regions(source: &str) -> Vec<Range<usize>> {
+    let mut regions = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = source[offset..].find("{{") {
+        let start = offset + open;
+        let tag = &source[start..];
+        let inner = tag[2..].trim_start_matches('~');
+        offset = if source[..start].ends_with('\\') {
+            start + 2
+        } else if tag.starts_with("{{{{") {
+            let end = raw_block_len(tag).map_or(source.len(), |len| start + len);
+            regions.push(start..end);
+            end
+        } else if inner.starts_with("!--") {
+            tag.find("--}}").map_or(source.len(), |end| start + end + 4)
+        } else if is_verbatim_open(inner) {
+            let Some(body) = tag.find("}}").map(|end| start + end + 2) else {
+                break;
+            };
+            let end = ["{{/verbatim", "{{~/verbatim"]
+                .iter()
+                .filter_map(|close| source[body..].find(close))
+                .min()
+                .map_or(source.len(), |close| body + close);
+            regions.push(body..end);
+            end
+        } else {
+            start + 2
+        };
+    }
+    regions
+}
+
+/// Replaces the verbatim regions of `source` with spaces.
+///
+/// Byte offsets and newlines are kept, so positions in the result map
+/// directly back to `source`.
+#[must_use]
+pub(crate) fn mask_verbatim(source: &str) -> Cow<'_, str> {
+    let regions = verbatim_regions(source);
+    if regions.is_empty() {
+        return Cow::Borrowed(source);
+    }
+    let mut masked = String::with_capacity(source.len());
+    let mut last = 0;
+    for region in regions {
+        masked.push_str(&source[last..region.start]);
+        for c in source[region.clone()].chars() {
+            if c == '\n' {
+                masked.push('\n');
+            } else {
+                masked.extend(std::iter::repeat_n(' ', c.len_utf8()));
+            }
+        }
+        last = region.end;
+    }
+    masked.push_str(&source[last..]);
+    Cow::Owned(masked)
+}
+
+/// Returns the length of the raw block at the start of `tag`, if it is
+/// closed. Raw content ends at the next `{{{{`, which must be the end tag.
+fn raw_block_len(tag: &str) -> Option<usize> {
+    let content = tag.find("}}}}")? + 4;
+    let close = content + tag[content..].find("{{{{")?;
+    Some(close + tag[close..].find("}}}}")? + 4)
+}
+
+/// Returns whether a tag's content, after `{{` and any `~`, opens a
+/// `{{#verbatim}}` block.
+fn is_verbatim_open(inner: &str) -> bool {
+    inner
+        .strip_prefix("#verbatim")
+        .is_some_and(|rest| rest.starts_with(['}', '~']) || rest.starts_with(char::is_whitespace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbatim_regions() {
+        let source = "a {{{{raw}}}}{{x}}{{{{/raw}}}} b {{#verbatim}} {{y}} {{/verbatim}} \
+                      {{#verbatimish}}{{/verbatimish}} {{!-- {{{{raw}}}} --}} \\{{{{raw}}}}";
+        let regions: Vec<&str> = verbatim_regions(source)
+            .into_iter()
+            .map(|region| &source[region])
+            .collect();
+        assert_eq!(regions, ["{{{{raw}}}}{{x}}{{{{/raw}}}}", " {{y}} "]);
+    }
+
+    #[test]
+    fn test_unclosed_regions_run_to_end() {
+        let ends = |source: &str| {
+            verbatim_regions(source)
+                .into_iter()
+                .map(|region| (region.start, region.end))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ends("{{{{raw}}}} {{x}}"), [(0, 17)]);
+        assert_eq!(ends("{{~#verbatim~}} {{x}}"), [(15, 21)]);
+    }
+
+    #[test]
+    fn test_mask_verbatim() {
+        assert_eq!(mask_verbatim("a {{ b }}"), "a {{ b }}");
+        assert_eq!(
+            mask_verbatim("{{#verbatim}}é\n{{x}}{{/verbatim}}"),
+            "{{#verbatim}}  \n     {{/verbatim}}"
+        );
+    }
+}
@@ -17,7 +17,9 @@
 //! Integration tests for the promptly CLI.
 
 use std::fs;
-use std::process::Command;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
 
 use tempfile::TempDir;
 
@@ -190,6 +192,130 @@ fn test_check_json_output() {
     assert!(json.is_array(), "Expected JSON array");
 }
 
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_check_metrics() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("plain.prompt"),
+        "---\nmodel: gemini\n---\nYou are kind. Be brief.\n",
+    )
+    .unwrap();
+
+    let output = Command::new(promptly_bin())
+        .current_dir(dir.path())
+        .args(["check", "--metrics", "--format=json", "."])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(json["diagnostics"].as_array().unwrap().is_empty());
+    let metrics = &json["metrics"][0]["metrics"];
+    assert_eq!(metrics["sentences"], 2);
+    assert_eq!(metrics["words"], 5);
+
+    fs::write(
+        dir.path().join("promptly.toml"),
+        "[lint.readability]\nmin-flesch = 150.0\n",
+    )
+    .unwrap();
+    let output = Command::new(promptly_bin())
+        .current_dir(dir.path())
+        .args(["check", "--metrics", "."])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Flesch"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("below the floor"));
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_check_explain_source() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("costly.prompt"),
+        "---\nmodel: openai/gpt-4o\nconfig:\n  maxOutputTokens: 4000\ninput:\n  default:\n    name: Ada\n---\nHello {{name}}!\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("promptly.toml"),
+        "[lint]\nmax-estimated-cost-usd = 0.01\n",
+    )
+    .unwrap();
+    let check = |args: &[&str]| {
+        Command::new(promptly_bin())
+            .current_dir(dir.path())
+            .arg("check")
+            .args(args)
+            .arg(".")
+            .output()
+            .unwrap()
+    };
+
+    let output = check(&["--format=json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[0]["code"], "cost-over-budget");
+    assert!(json[0]["rendered"].is_null());
+
+    let output = check(&["--explain-source", "--format=json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[0]["rendered"], "── user ──\nHello Ada!");
+
+    let output = check(&["--explain-source"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("rendered with input defaults"), "{stderr}");
+    assert!(stderr.contains("│ Hello Ada!"), "{stderr}");
+}
+
+#[test]
+#[allow(clippy::unwrap_used)]
+fn test_doctor_reports_each_stage() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("greet.prompt"),
+        "---\ninput:\n  schema:\n    name: string\n---\n{{> header}}Hello {{name}}!\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("_header.prompt"), "Hi. ").unwrap();
+    let doctor = || {
+        Command::new(promptly_bin())
+            .args(["doctor", "--format=json"])
+            .arg(dir.path())
+            .output()
+            .unwrap()
+    };
+    let status = |json: &serde_json::Value, name: &str| {
+        json["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|check| check["name"] == name)
+            .map(|check| check["status"].clone())
+            .unwrap()
+    };
+
+    let output = doctor();
+    assert!(output.status.success(), "{output:?}");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["healthy"], true);
+    assert_eq!(status(&json, "render"), "ok");
+    assert_eq!(status(&json, "providers"), "skipped");
+
+    fs::write(dir.path().join("broken.prompt"), "{{> missing}}").unwrap();
+    fs::write(dir.path().join("bad.prompt"), "---\nmodel: [\n---\nx").unwrap();
+    let output = doctor();
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["healthy"], false);
+    assert_eq!(status(&json, "parse"), "error");
+    assert_eq!(status(&json, "partials"), "error");
+    assert_eq!(status(&json, "render"), "ok");
+    let partials = &json["checks"][3];
+    assert_eq!(partials["summary"], "1/2 prompt(s)");
+    assert_eq!(partials["problems"][0]["path"], "broken.prompt");
+}
+
 #[test]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 fn test_check_directory() {
@@ -227,6 +353,55 @@ fn test_check_nonexistent_path() {
     );
 }
 
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_check_quiet_and_no_color() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("warn.prompt"),
+        "---\nmodel: gemini\ninput:\n  schema:\n    name: string\n---\nHello {{nam}}\n",
+    )
+    .unwrap();
+    let check = |args: &[&str]| {
+        let output = Command::new(promptly_bin())
+            .arg("check")
+            .args(args)
+            .arg(".")
+            .current_dir(dir.path())
+            .env("CLICOLOR_FORCE", "1")
+            .output()
+            .expect("Failed to run promptly check");
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    let loud = check(&[]);
+    assert!(loud.contains("undefined-variable"));
+    assert!(loud.contains('\x1b'), "Expected colors: {loud}");
+
+    let quiet = check(&["--quiet", "--no-color"]);
+    assert!(!quiet.contains("undefined-variable"), "{quiet}");
+    assert!(quiet.contains("2 warning(s) generated"), "{quiet}");
+    assert!(!quiet.contains('\x1b'), "Expected no colors: {quiet}");
+
+    // Write commands drop their status lines too.
+    let output = Command::new(promptly_bin())
+        .args(["--quiet", "set", "warn.prompt", "model=gemini-pro"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly set");
+    assert!(output.status.success());
+    assert!(
+        output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        fs::read_to_string(dir.path().join("warn.prompt"))
+            .unwrap()
+            .contains("model: gemini-pro")
+    );
+}
+
 #[test]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 fn test_version() {
@@ -374,6 +549,86 @@ fn test_fmt_formats_file() {
     );
 }
 
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_fmt_sort_schema() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ask.prompt");
+    let source = "---\ninput:\n  schema:\n    about?: string\n    question: string\n    context: string\n---\n\nAnswer {{ question }}\n";
+    fs::write(&path, source).unwrap();
+    let fmt = |args: &[&str]| {
+        let output = Command::new(promptly_bin())
+            .arg("fmt")
+            .args(args)
+            .arg(&path)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run promptly fmt");
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        fs::read_to_string(&path).unwrap()
+    };
+
+    // Off unless configured.
+    assert_eq!(fmt(&[]), source);
+
+    assert_eq!(
+        fmt(&["--sort-schema"]),
+        "---\ninput:\n  schema:\n    about?: string\n    context: string\n    question: string\n---\n\nAnswer {{ question }}\n"
+    );
+
+    fs::write(&path, source).unwrap();
+    fs::write(
+        dir.path().join("promptly.toml"),
+        "[fmt]\nsort-schema = \"required-first\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        fmt(&[]),
+        "---\ninput:\n  schema:\n    context: string\n    question: string\n    about?: string\n---\n\nAnswer {{ question }}\n"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_fmt_nested_config() {
+    let dir = TempDir::new().unwrap();
+    let team = dir.path().join("team");
+    fs::create_dir(&team).unwrap();
+    fs::write(
+        team.join("promptly.toml"),
+        "[fmt]\nsort-schema = \"alphabetical\"\n",
+    )
+    .unwrap();
+    let source = "---\ninput:\n  schema:\n    question: string\n    context: string\n---\n\nAnswer {{ question }}\n";
+    fs::write(dir.path().join("top.prompt"), source).unwrap();
+    fs::write(team.join("a.prompt"), source).unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["fmt", "."])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly fmt");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Each prompt follows the promptly.toml nearest to it.
+    assert_eq!(
+        fs::read_to_string(team.join("a.prompt")).unwrap(),
+        "---\ninput:\n  schema:\n    context: string\n    question: string\n---\n\nAnswer {{ question }}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("top.prompt")).unwrap(),
+        source
+    );
+}
+
 #[test]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 fn test_fmt_diff_shows_changes() {
@@ -416,6 +671,52 @@ fn test_fmt_directory() {
     );
 }
 
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_fmt_minify() {
+    let dir = TempDir::new().unwrap();
+    let src = dir.path().join("src");
+    fs::create_dir(&src).unwrap();
+    fs::write(src.join("_sig.prompt"), "--   {{team}}\n").unwrap();
+    let source =
+        "---\nmodel: gemini\n---\n{{!-- note --}}\n  Hello,   {{name}}!\n\n\n  {{> sig}}\n";
+    fs::write(src.join("greet.prompt"), source).unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["fmt", "--minify", "--inline-partials"])
+        .arg(src.join("greet.prompt"))
+        .output()
+        .expect("Failed to run promptly fmt --minify");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "---\nmodel: gemini\n---\nHello, {{name}}!\n\n-- {{team}}\n"
+    );
+    // The source is left alone.
+    assert_eq!(
+        fs::read_to_string(src.join("greet.prompt")).unwrap(),
+        source
+    );
+
+    let out = dir.path().join("dist");
+    let output = Command::new(promptly_bin())
+        .args(["fmt", "--minify", "--out-dir"])
+        .arg(&out)
+        .arg(&src)
+        .output()
+        .expect("Failed to run promptly fmt --minify --out-dir");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(out.join("greet.prompt")).unwrap(),
+        "---\nmodel: gemini\n---\nHello, {{name}}!\n\n{{> sig}}\n"
+    );
+    assert!(out.join("_sig.prompt").exists());
+}
+
 #[test]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 fn test_fmt_nonexistent_path() {
@@ -498,3 +799,1160 @@ fn test_check_fix_with_strict() {
         String::from_utf8_lossy(&output.stderr)
     );
 }
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_check_fix_applies_lint_fixes() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("broken.prompt");
+    fs::write(
+        &path,
+        "---\ndescription: Note: be brief\n---\nHello {{ name }}{{/if}}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["check", "--format", "json", path.to_str().unwrap()])
+        .output()
+        .expect("Failed to run promptly check");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let replacements: Vec<&str> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["fix"]["replacement"].as_str())
+        .collect();
+    assert_eq!(
+        replacements,
+        ["\"Note: be brief\"", ""],
+        "unexpected fixes: {json}"
+    );
+
+    let output = Command::new(promptly_bin())
+        .args(["check", "--fix", path.to_str().unwrap()])
+        .output()
+        .expect("Failed to run promptly check --fix");
+    assert!(
+        output.status.success(),
+        "Expected fixed file to pass, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let fixed = fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        fixed,
+        "---\ndescription: \"Note: be brief\"\n---\n\nHello {{ name }}\n"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_set_edits_frontmatter_in_place() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("greet.prompt");
+    fs::write(
+        &path,
+        "---\n# Pinned for eval parity\nmodel: gemini-1.5-pro\nconfig:\n  temperature: 0.7\n---\nHello {{name}}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args([
+            "set",
+            path.to_str().unwrap(),
+            "model=gemini-2.0-pro",
+            "config.topK=40",
+            "--unset",
+            "config.temperature",
+        ])
+        .output()
+        .expect("Failed to run promptly set");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "---\n# Pinned for eval parity\nmodel: gemini-2.0-pro\nconfig:\n  topK: 40\n---\nHello {{name}}\n"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_migrate_renames_models_and_fields() {
+    let dir = TempDir::new().unwrap();
+    let old = dir.path().join("old.prompt");
+    let current = dir.path().join("current.prompt");
+    let original = "---\nmodel: gemini-1.5-pro # legacy\nconfig:\n  maxTokens: 256\n---\nHi\n";
+    fs::write(&old, original).unwrap();
+    fs::write(&current, "---\nmodel: gemini-2.0-flash\n---\nHi\n").unwrap();
+
+    let migrate = |extra: &[&str]| {
+        Command::new(promptly_bin())
+            .args([
+                "migrate",
+                "--rename-model",
+                "gemini-1.5*:gemini-2.0-flash",
+                "--rename-field",
+                "config.maxTokens:config.maxOutputTokens",
+                dir.path().to_str().unwrap(),
+            ])
+            .args(extra)
+            .output()
+            .expect("Failed to run promptly migrate")
+    };
+
+    let output = migrate(&["--dry-run"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("+model: gemini-2.0-flash # legacy"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("1 file(s) would be migrated"), "{stderr}");
+    assert_eq!(fs::read_to_string(&old).unwrap(), original);
+
+    let output = migrate(&[]);
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&old).unwrap(),
+        "---\nmodel: gemini-2.0-flash # legacy\nconfig:\n  maxOutputTokens: 256\n---\nHi\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&current).unwrap(),
+        "---\nmodel: gemini-2.0-flash\n---\nHi\n"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_migrate_keeps_model_provider_prefix() {
+    let dir = TempDir::new().unwrap();
+    let google = dir.path().join("google.prompt");
+    let vertex = dir.path().join("vertex.prompt");
+    fs::write(&google, "---\nmodel: googleai/gemini-1.5-pro\n---\nHi\n").unwrap();
+    fs::write(&vertex, "---\nmodel: vertexai/gemini-pro\n---\nHi\n").unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args([
+            "migrate",
+            "--rename-model",
+            "gemini-1.5*:gemini-2.0-flash",
+            "--rename-model",
+            "vertexai/*:googleai/gemini-2.0-pro",
+            dir.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run promptly migrate");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(&google).unwrap(),
+        "---\nmodel: googleai/gemini-2.0-flash\n---\nHi\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&vertex).unwrap(),
+        "---\nmodel: googleai/gemini-2.0-pro\n---\nHi\n"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_migrate_from_chatml() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("chat.prompt");
+    fs::write(&path, "<|system|>\nBe brief.\n<|user|>\nHi {{name}}\n").unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["migrate", "--from", "chatml"])
+        .arg(&path)
+        .output()
+        .expect("Failed to run promptly migrate");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "{{role \"system\"}}\nBe brief.\n{{role \"user\"}}\nHi {{name}}\n"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_search_filters_by_metadata() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(
+        dir.path().join("weather.prompt"),
+        r"---
+model: googleai/gemini-2.0-flash
+description: Answers weather questions
+tools: [search]
+---
+What is the weather in {{city}}?
+",
+    )
+    .expect("Failed to write weather.prompt");
+    fs::write(
+        dir.path().join("greet.prompt"),
+        r"---
+model: openai/gpt-4o
+description: Greets the user
+---
+Hello {{name}}!
+",
+    )
+    .expect("Failed to write greet.prompt");
+
+    let output = Command::new(promptly_bin())
+        .args([
+            "search",
+            "model:gemini* tools:search weather",
+            "--format",
+            "json",
+            "--dir",
+            dir.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run promptly search");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Expected JSON output");
+    let names: Vec<_> = results
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["weather"]);
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_search_rejects_unknown_field() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+
+    let output = Command::new(promptly_bin())
+        .args([
+            "search",
+            "owner:someone",
+            "--dir",
+            dir.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run promptly search");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unknown search field"),
+        "Expected unknown field error: {stderr}"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_ls_filters_by_tag() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(
+        dir.path().join("refund.prompt"),
+        "---\ntags: [support, beta]\n---\nHandle the refund.\n",
+    )
+    .expect("Failed to write refund.prompt");
+    fs::write(
+        dir.path().join("faq.prompt"),
+        "---\ntags: [support]\n---\nAnswer the question.\n",
+    )
+    .expect("Failed to write faq.prompt");
+    fs::write(dir.path().join("other.prompt"), "Hello!\n").expect("Failed to write other.prompt");
+
+    let output = Command::new(promptly_bin())
+        .args(["ls", dir.path().to_str().unwrap(), "--tag", "support"])
+        .output()
+        .expect("Failed to run promptly ls");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("faq"), "stdout: {stdout}");
+    assert!(stdout.contains("refund"), "stdout: {stdout}");
+    assert!(!stdout.contains("other"), "stdout: {stdout}");
+
+    let output = Command::new(promptly_bin())
+        .args([
+            "ls",
+            dir.path().to_str().unwrap(),
+            "--tag",
+            "support",
+            "--tag",
+            "beta",
+            "--format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run promptly ls");
+    let results: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Expected JSON output");
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    assert_eq!(results[0]["name"], "refund");
+    assert_eq!(results[0]["tags"], serde_json::json!(["support", "beta"]));
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_export_openai_playground_preset() {
+    let dir = TempDir::new().unwrap();
+    let prompt = dir.path().join("greet.prompt");
+    fs::write(
+        &prompt,
+        "---\nmodel: openai/gpt-4o\ndescription: Greets people.\nconfig:\n  temperature: 0.3\n  maxOutputTokens: 64\n---\n{{role \"system\"}}Be kind.\n{{role \"user\"}}Hi {{name}}",
+    )
+    .unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args([
+            "export",
+            "--format",
+            "openai-playground",
+            "-i",
+            r#"{"name": "Ada"}"#,
+        ])
+        .arg(&prompt)
+        .output()
+        .expect("Failed to run promptly export");
+    assert!(output.status.success());
+    let preset: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(preset["name"], "greet");
+    assert_eq!(preset["description"], "Greets people.");
+    assert_eq!(preset["model"], "gpt-4o");
+    assert_eq!(preset["messages"][0]["role"], "system");
+    assert_eq!(preset["messages"][1]["content"], "Hi Ada");
+    assert_eq!(preset["parameters"]["temperature"], 0.3);
+    assert_eq!(preset["parameters"]["max_tokens"], 64);
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_generate_dataset_renders_each_input() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let prompt = dir.path().join("greet.prompt");
+    fs::write(&prompt, "Hello {{name}}!").unwrap();
+    let inputs = dir.path().join("inputs.jsonl");
+    fs::write(
+        &inputs,
+        "{\"name\": \"Ada\"}\n\nnot json\n{\"name\": \"Grace\"}\n",
+    )
+    .unwrap();
+    let out = dir.path().join("rendered.jsonl");
+
+    let output = Command::new(promptly_bin())
+        .arg("generate-dataset")
+        .arg(&prompt)
+        .arg("--inputs")
+        .arg(&inputs)
+        .arg("--out")
+        .arg(&out)
+        .output()
+        .expect("Failed to run promptly generate-dataset");
+    assert!(output.status.success());
+
+    let rendered: Vec<serde_json::Value> = fs::read_to_string(&out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rendered.len(), 2);
+    assert_eq!(
+        rendered[0]["messages"][0]["content"][0]["text"],
+        "Hello Ada!"
+    );
+    assert_eq!(
+        rendered[1]["messages"][0]["content"][0]["text"],
+        "Hello Grace!"
+    );
+
+    let failures = fs::read_to_string(dir.path().join("rendered.failures.jsonl")).unwrap();
+    let failure: serde_json::Value = serde_json::from_str(failures.trim()).unwrap();
+    assert_eq!(failure["line"], 3);
+    assert!(
+        failure["error"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid input JSON")
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_import_jinja_with_sidecar() {
+    let dir = TempDir::new().unwrap();
+    let template = dir.path().join("greet.j2");
+    fs::write(
+        &template,
+        "Hello {{ name }}!\n{% for t in topics %}- {{ t }}\n{% endfor %}{{ name | upper }}\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("greet.yaml"), "model: gemini-2.0-flash\n").unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["import", "--format", "jinja"])
+        .arg(&template)
+        .output()
+        .expect("Failed to run promptly import");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("greet.j2:3"), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(dir.path().join("greet.prompt")).unwrap(),
+        "---\nmodel: gemini-2.0-flash\n---\nHello {{name}}!\n\
+        {{#each topics as |t|}}- {{t}}\n{{/each}}{{!-- jinja: {{ name | upper }} --}}\n"
+    );
+
+    let output = Command::new(promptly_bin())
+        .args(["import", "--format", "jinja"])
+        .arg(&template)
+        .output()
+        .expect("Failed to run promptly import");
+    assert!(!output.status.success());
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_ls_includes_frontmatter_summary() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(
+        dir.path().join("refund.prompt"),
+        "---\nmodel: googleai/gemini-2.0-flash\ndescription: Handles refunds.\ntags: [support]\n---\nHandle the refund.\n",
+    )
+    .expect("Failed to write refund.prompt");
+
+    let output = Command::new(promptly_bin())
+        .args(["ls", dir.path().to_str().unwrap(), "--format", "json"])
+        .output()
+        .expect("Failed to run promptly ls");
+    assert!(output.status.success());
+    let results: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Expected JSON output");
+    assert_eq!(results[0]["model"], "googleai/gemini-2.0-flash");
+    assert_eq!(results[0]["description"], "Handles refunds.");
+    assert_eq!(results[0]["tags"], serde_json::json!(["support"]));
+
+    let output = Command::new(promptly_bin())
+        .args(["ls", dir.path().to_str().unwrap()])
+        .output()
+        .expect("Failed to run promptly ls");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("googleai/gemini-2.0-flash"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_log_json_emits_per_file_spans() {
+    let dir = setup_test_dir();
+    let valid_path = dir.path().join("valid.prompt");
+    let log_path = dir.path().join("promptly.log");
+
+    let output = Command::new(promptly_bin())
+        .args([
+            "--log-level",
+            "debug",
+            "--log-json",
+            "--log-file",
+            log_path.to_str().unwrap(),
+            "check",
+            valid_path.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run promptly check with logging");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let log = fs::read_to_string(&log_path).expect("Expected log file");
+    let lines: Vec<serde_json::Value> = log
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("Expected JSON log line"))
+        .collect();
+    assert!(
+        lines
+            .iter()
+            .any(|l| l["span"]["name"] == "process_file" && l["fields"]["time.busy"].is_string()),
+        "Expected a closed process_file span with timing: {log}"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_lsp_over_tcp_answers_initialize() {
+    let mut child = Command::new(promptly_bin())
+        .args(["lsp", "--tcp", "0"])
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start promptly lsp --tcp");
+
+    // The server announces its bound address on stderr.
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut banner = String::new();
+    stderr.read_line(&mut banner).unwrap();
+    let addr = banner
+        .trim()
+        .rsplit("tcp://")
+        .next()
+        .expect("Expected listening address")
+        .to_string();
+
+    let mut stream = TcpStream::connect(&addr).expect("Failed to connect to LSP server");
+    let request = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#;
+    write!(stream, "Content-Length: {}\r\n\r\n{request}", request.len()).unwrap();
+
+    let mut reader = BufReader::new(stream);
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse().unwrap();
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    child.kill().ok();
+    child.wait().ok();
+
+    let response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(response["id"], 1);
+    assert!(
+        response["result"]["capabilities"].is_object(),
+        "Unexpected response: {response}"
+    );
+    assert!(
+        response["result"]["capabilities"]["semanticTokensProvider"]["legend"]["tokenTypes"]
+            .is_array(),
+        "Expected a semantic token legend: {response}"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_lsp_print_client_config() {
+    let output = Command::new(promptly_bin())
+        .args(["lsp", "--print-client-config"])
+        .output()
+        .expect("Failed to run promptly lsp --print-client-config");
+    assert!(output.status.success());
+
+    let config: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Expected JSON config");
+    let language = &config["packageJson"]["contributes"]["languages"][0];
+    assert_eq!(language["id"], "dotprompt");
+    assert_eq!(language["extensions"], serde_json::json!([".prompt"]));
+    assert_eq!(
+        config["clientOptions"]["documentSelector"][0]["language"],
+        "dotprompt"
+    );
+    assert!(config["semanticTokensLegend"]["tokenTypes"].is_array());
+}
+
+/// Runs git in a directory, panicking on failure.
+#[allow(clippy::expect_used)]
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success(), "git {args:?} failed");
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_staged_only_checks_staged_prompts() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    git(dir.path(), &["init", "-q"]);
+    fs::write(dir.path().join("good.prompt"), "Hello {{ name }}\n").unwrap();
+    fs::write(dir.path().join("broken.prompt"), "{{#if x}}\nunclosed\n").unwrap();
+    git(dir.path(), &["add", "good.prompt"]);
+
+    let output = Command::new(promptly_bin())
+        .args(["check", "--staged", "--strict"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly check --staged");
+    assert!(
+        output.status.success(),
+        "Unstaged files should be skipped: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    fs::write(dir.path().join("messy.prompt"), "Hello {{name}}").unwrap();
+    git(dir.path(), &["add", "messy.prompt"]);
+    let output = Command::new(promptly_bin())
+        .args(["fmt", "--check", "--staged"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly fmt --staged");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("messy.prompt"),
+        "Unexpected output: {stderr}"
+    );
+    assert!(stderr.contains("1 file(s) would be reformatted, 2 file(s) checked"));
+
+    // The staged content is checked, not the working-tree copy.
+    fs::write(dir.path().join("messy.prompt"), "Hello {{ name }}\n").unwrap();
+    fs::write(dir.path().join("good.prompt"), "{{#if x}}\nunclosed\n").unwrap();
+    let output = Command::new(promptly_bin())
+        .args(["fmt", "--check", "--staged"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly fmt --staged");
+    assert!(!output.status.success());
+    let output = Command::new(promptly_bin())
+        .args(["check", "--staged", "--strict"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly check --staged");
+    let printed = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        !printed.contains("good.prompt"),
+        "Unexpected output: {printed}"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_hook_install() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    git(dir.path(), &["init", "-q"]);
+    let hook = dir.path().join(".git/hooks/pre-commit");
+    let install = |args: &[&str]| {
+        Command::new(promptly_bin())
+            .args(["hook", "install"])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run promptly hook install")
+    };
+
+    assert!(install(&[]).status.success());
+    let script = fs::read_to_string(&hook).unwrap();
+    assert!(script.contains("promptly check --strict --staged"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_ne!(fs::metadata(&hook).unwrap().permissions().mode() & 0o111, 0);
+    }
+    assert!(install(&[]).status.success(), "Reinstalling should succeed");
+
+    fs::write(&hook, "#!/bin/sh\nmake lint\n").unwrap();
+    assert!(!install(&[]).status.success(), "Foreign hooks need --force");
+    assert!(install(&["--force"]).status.success());
+
+    fs::write(
+        dir.path().join(".pre-commit-config.yaml"),
+        "repos:\n- repo: https://github.com/pre-commit/pre-commit-hooks\n  rev: v4.6.0\n  hooks:\n  - id: trailing-whitespace\n",
+    )
+    .unwrap();
+    assert!(install(&["--pre-commit"]).status.success());
+    let config = fs::read_to_string(dir.path().join(".pre-commit-config.yaml")).unwrap();
+    assert!(config.contains("\n- repo: local\n  hooks:\n    - id: promptly-fmt\n"));
+    assert!(config.starts_with("repos:\n- repo: https://"));
+}
+
+/// Sends a raw HTTP/1.1 request and returns the status line and body.
+#[allow(clippy::unwrap_used)]
+fn http_request(addr: &str, method: &str, path: &str, body: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.lines().next().unwrap().to_string();
+    (status, body.to_string())
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_serve_lists_and_renders_prompts() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(
+        dir.path().join("greeting.prompt"),
+        "---\nmodel: gemini-2.0-flash\n---\n{{role \"system\"}}{{> signature}}\n{{role \"user\"}}Hello {{name}}!",
+    )
+    .unwrap();
+    fs::write(dir.path().join("_signature.prompt"), "Be brief.").unwrap();
+
+    let mut child = Command::new(promptly_bin())
+        .args(["serve", "--port", "0", "--dir"])
+        .arg(dir.path())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start promptly serve");
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut banner = String::new();
+    stderr.read_line(&mut banner).unwrap();
+    let addr = banner
+        .trim()
+        .rsplit("http://")
+        .next()
+        .expect("Expected listening address")
+        .to_string();
+
+    let (status, body) = http_request(&addr, "GET", "/prompts", "");
+    let listing: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let (meta_status, meta) = http_request(&addr, "GET", "/prompts/greeting", "");
+    let (render_status, rendered) = http_request(
+        &addr,
+        "POST",
+        "/render/greeting",
+        r#"{"input": {"name": "Ada"}}"#,
+    );
+    let (missing, _) = http_request(&addr, "POST", "/render/missing", "{}");
+    child.kill().ok();
+    child.wait().ok();
+
+    assert!(status.contains("200"), "Unexpected status: {status}");
+    assert_eq!(listing["prompts"][0]["name"], "greeting");
+    assert_eq!(
+        listing["prompts"][0]["metadata"]["model"],
+        "gemini-2.0-flash"
+    );
+    assert!(meta_status.contains("200"));
+    let meta: serde_json::Value = serde_json::from_str(&meta).unwrap();
+    assert_eq!(meta["model"], "gemini-2.0-flash");
+    assert!(render_status.contains("200"), "Render failed: {rendered}");
+    let rendered: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+    assert_eq!(rendered["messages"][0]["role"], "system");
+    assert_eq!(rendered["messages"][0]["content"][0]["text"], "Be brief.\n");
+    assert_eq!(rendered["messages"][1]["content"][0]["text"], "Hello Ada!");
+    assert!(missing.contains("404"));
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_render_explain_traces_parts() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("_sig.prompt"), "-- {{team}}").unwrap();
+    let path = dir.path().join("greet.prompt");
+    fs::write(
+        &path,
+        "---\nmodel: test/model\n---\n{{#if formal}}\nDear {{name}},\n{{/if}}\n{{> sig}}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args([
+            "render",
+            path.to_str().unwrap(),
+            "-i",
+            r#"{"name": "Ada", "formal": true, "team": "Support"}"#,
+            "--explain",
+            "--format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run promptly render");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let traced: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        traced["rendered"]["messages"][0]["content"][0]["text"],
+        "Dear Ada,\n-- Support"
+    );
+    let segments = traced["parts"][0]["segments"].as_array().unwrap();
+    let name = segments.iter().find(|s| s["text"] == "Ada").unwrap();
+    assert_eq!(name["template"], "{{name}}");
+    assert_eq!(name["blocks"][0], "{{#if formal}}");
+    let sig = segments
+        .iter()
+        .find(|s| s["kind"]["type"] == "partial")
+        .unwrap();
+    assert_eq!(sig["kind"]["name"], "sig");
+    assert_eq!(sig["text"], "\n-- Support");
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_render_target_emits_provider_request() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("greet.prompt");
+    fs::write(
+        &path,
+        "---\nmodel: anthropic/claude\nconfig:\n  maxOutputTokens: 50\n---\n{{role \"system\"}}Be brief.\n{{role \"user\"}}Hello {{name}}!",
+    )
+    .unwrap();
+
+    let render = |target: &str| {
+        let output = Command::new(promptly_bin())
+            .args([
+                "render",
+                path.to_str().unwrap(),
+                "-i",
+                r#"{"name": "Ada"}"#,
+                "--target",
+                target,
+            ])
+            .output()
+            .expect("Failed to run promptly render");
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let anthropic = render("anthropic");
+    assert_eq!(anthropic["model"], "claude");
+    assert_eq!(anthropic["system"], "Be brief.\n");
+    assert_eq!(anthropic["max_tokens"], 50);
+    assert_eq!(anthropic["messages"][0]["content"][0]["text"], "Hello Ada!");
+
+    let gemini = render("gemini");
+    assert_eq!(gemini["contents"][0]["parts"][0]["text"], "Hello Ada!");
+    assert_eq!(gemini["generationConfig"]["maxOutputTokens"], 50);
+
+    assert_eq!(render("openai")["messages"][1]["content"], "Hello Ada!");
+    assert_eq!(render("genkit")["model"], "anthropic/claude");
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_render_cost_uses_pricing_table() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("greet.prompt");
+    fs::write(
+        &path,
+        "---\nmodel: acme/house-model\nconfig:\n  maxOutputTokens: 1000\n---\nHello {{name}}!",
+    )
+    .unwrap();
+    let render = || {
+        Command::new(promptly_bin())
+            .args([
+                "render",
+                path.to_str().unwrap(),
+                "-i",
+                r#"{"name": "Ada"}"#,
+                "--cost",
+                "--format",
+                "json",
+            ])
+            .output()
+            .expect("Failed to run promptly render")
+    };
+
+    let output = render();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No pricing for model"));
+
+    fs::write(
+        dir.path().join("pricing.toml"),
+        "[models.\"house-model\"]\ninput = 1.0\noutput = 2.0\n",
+    )
+    .unwrap();
+    let output = render();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let estimate: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(estimate["model"], "acme/house-model");
+    assert_eq!(estimate["inputTokens"], 3);
+    assert_eq!(estimate["outputTokens"], 1000);
+    assert_eq!(estimate["outputCost"], 0.002);
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_check_tool_catalog() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("tools.yaml"),
+        "tools:\n  - name: search\n    description: Searches the web.\n    inputSchema: {type: object}\n  - name: weather\n    inputSchema: {type: object}\n",
+    )
+    .unwrap();
+    let path = dir.path().join("ask.prompt");
+    fs::write(
+        &path,
+        "---\ntools: [search, serach]\n---\nAnswer {{question}}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["check", "--format", "json", "."])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly check");
+    let diagnostics: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let codes: Vec<(&str, &str)> = diagnostics
+        .iter()
+        .map(|d| (d["code"].as_str().unwrap(), d["message"].as_str().unwrap()))
+        .filter(|(code, _)| code.ends_with("-tool"))
+        .collect();
+    assert_eq!(codes.len(), 2, "{diagnostics:?}");
+    assert_eq!(codes[0].0, "unknown-tool");
+    assert!(codes[0].1.contains("'serach'"));
+    assert_eq!(codes[1].0, "unused-tool");
+    assert!(codes[1].1.contains("'weather'"));
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_nested_config_overrides_parent() {
+    let dir = TempDir::new().unwrap();
+    let team = dir.path().join("team");
+    fs::create_dir(&team).unwrap();
+    fs::write(
+        dir.path().join("promptly.toml"),
+        "[lint.severity]\nundefined-variable = \"error\"\n",
+    )
+    .unwrap();
+    fs::write(
+        team.join("promptly.toml"),
+        "[lint]\nallow = [\"undefined-variable\"]\n",
+    )
+    .unwrap();
+    let prompt =
+        "---\nmodel: m\ninput:\n  schema:\n    name: string\n---\nHello {{name}} {{city}}\n";
+    fs::write(dir.path().join("a.prompt"), prompt).unwrap();
+    fs::write(team.join("b.prompt"), prompt).unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["check", "--format", "json", "."])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly check");
+    assert!(!output.status.success());
+    let diagnostics: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let undefined: Vec<_> = diagnostics
+        .iter()
+        .filter(|d| d["code"] == "undefined-variable")
+        .collect();
+    assert_eq!(undefined.len(), 1, "{diagnostics:?}");
+    assert!(undefined[0]["file"].as_str().unwrap().ends_with("a.prompt"));
+    assert_eq!(undefined[0]["severity"], "error");
+
+    let output = Command::new(promptly_bin())
+        .args(["config", "--show", "team/b.prompt", "--format", "json"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly config");
+    assert!(output.status.success());
+    let shown: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(shown["files"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        shown["settings"]["lint"],
+        serde_json::json!({"allow": ["undefined-variable"], "severity": {}})
+    );
+
+    let output = Command::new(promptly_bin())
+        .args(["config", "--show", "team"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly config");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("allow = [\"undefined-variable\"]"),
+        "{stdout}"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_check_reports_config_problems() {
+    let dir = setup_test_dir();
+    fs::write(
+        dir.path().join("promptly.toml"),
+        "[lint]\nwarnings-as-error = true\nrequire-owners = \"yes\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["check", "--format", "json", "valid.prompt"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly check");
+    assert!(!output.status.success());
+    let diagnostics: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let found: Vec<(&str, &str, u64)> = diagnostics
+        .iter()
+        .filter(|d| d["file"].as_str().unwrap().ends_with("promptly.toml"))
+        .map(|d| {
+            (
+                d["code"].as_str().unwrap(),
+                d["severity"].as_str().unwrap(),
+                d["line"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("unknown-config-key", "warning", 2),
+            ("invalid-config", "error", 3),
+        ]
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_config_init_and_check() {
+    let dir = TempDir::new().unwrap();
+    let run = |args: &[&str]| {
+        Command::new(promptly_bin())
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run promptly config")
+    };
+
+    assert!(run(&["config", "init"]).status.success());
+    assert!(!run(&["config", "init"]).status.success());
+    let output = run(&["config", "check", "--format", "json"]);
+    assert!(output.status.success());
+    let checked: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(checked["settings"]["lint"]["complexity"]["max-depth"], 4);
+
+    fs::write(
+        dir.path().join("promptly.toml"),
+        "[lint]\nwarnings-as-error = true\n",
+    )
+    .unwrap();
+    let output = run(&["config", "check"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Did you mean 'warnings-as-errors'?"),
+        "{stderr}"
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("warnings-as-errors = false"), "{stdout}");
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_environment_overrides_config() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("warn.prompt"),
+        "---\nmodel: m\ninput:\n  schema:\n    x: string\n---\nHello\n",
+    )
+    .unwrap();
+    let check = |vars: &[(&str, &str)]| {
+        Command::new(promptly_bin())
+            .arg("check")
+            .current_dir(dir.path())
+            .envs(vars.iter().copied())
+            .output()
+            .expect("Failed to run promptly check")
+    };
+
+    let output = check(&[("PROMPTLY_FORMAT", "json")]);
+    assert!(output.status.success());
+    let diagnostics: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(diagnostics[0]["code"], "unused-variable");
+
+    assert!(!check(&[("PROMPTLY_LINT_STRICT", "1")]).status.success());
+    assert!(check(&[("PROMPTLY_LINT_STRICT", "0")]).status.success());
+    assert!(
+        !check(&[("PROMPTLY_LINT_SEVERITY_UNUSED_VARIABLE", "error")])
+            .status
+            .success()
+    );
+    assert!(
+        check(&[
+            ("PROMPTLY_LINT_STRICT", "1"),
+            ("PROMPTLY_LINT_ALLOW", "unused-variable")
+        ])
+        .status
+        .success()
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_render_resolves_tools_and_schemas() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("tools.yaml"),
+        "tools:\n  - name: search\n    inputSchema: {type: object}\n",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("schemas")).unwrap();
+    fs::write(
+        dir.path().join("schemas/Answer.schema.yaml"),
+        "type: object\nproperties:\n  text: {type: string, description: the answer}\n",
+    )
+    .unwrap();
+    let path = dir.path().join("ask.prompt");
+    fs::write(
+        &path,
+        "---\ntools: [search]\noutput:\n  schema: Answer\n---\nAnswer {{question}}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(promptly_bin())
+        .args(["render", "--format", "json", "ask.prompt"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run promptly render");
+    assert!(output.status.success(), "{output:?}");
+    let rendered: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(rendered["toolDefs"][0]["name"], "search");
+    assert_eq!(
+        rendered["output"]["schema"]["properties"]["text"]["description"],
+        "the answer"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+fn test_highlight_emits_syntax_definitions() {
+    let output = Command::new(promptly_bin())
+        .args(["highlight", "--emit", "tmgrammar"])
+        .output()
+        .expect("Failed to run promptly highlight");
+    assert!(output.status.success());
+    let grammar: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("grammar is JSON");
+    assert_eq!(grammar["scopeName"], "source.dotprompt");
+    assert!(grammar.to_string().contains("formatNumber"));
+
+    for editor in ["vim", "emacs"] {
+        let output = Command::new(promptly_bin())
+            .args(["highlight", "--emit", editor])
+            .output()
+            .expect("Failed to run promptly highlight");
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("formatNumber"));
+    }
+}